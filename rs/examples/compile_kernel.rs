@@ -0,0 +1,62 @@
+//! Compiles a kernel written in the s-expression language of [lime_rs::prada::dsl], verifies the
+//! program on pseudo-random inputs derived from the seed (see [lime_rs::prada::random]) and prints
+//! the annotated program:
+//!
+//! ```sh
+//! echo '(out y (maj a b (not c)))' > kernel.sexp
+//! cargo run --example compile_kernel kernel.sexp --seed 42
+//! ```
+use lime_rs::prada::architecture::ARCHITECTURE;
+use lime_rs::prada::dsl::parse_kernel;
+use lime_rs::prada::reference::differential_test_seeded;
+use lime_rs::prada::{CompileError, CompilerSettings};
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (path, seed) = match &args[..] {
+        [path] => (path, 0),
+        [path, option, seed] if option == "--seed" => match seed.parse() {
+            Ok(seed) => (path, seed),
+            Err(_) => {
+                eprintln!("invalid seed {seed}");
+                std::process::exit(1);
+            }
+        },
+        _ => {
+            eprintln!("usage: compile_kernel <kernel> [--seed <seed>]");
+            std::process::exit(1);
+        }
+    };
+    let text = std::fs::read_to_string(&path).expect("kernel should be readable");
+    let kernel = match parse_kernel(&text) {
+        Ok(kernel) => kernel,
+        Err(err) => {
+            eprintln!("invalid kernel {path}: {err}");
+            std::process::exit(1);
+        }
+    };
+    let settings = CompilerSettings::builder().seed(seed).build();
+    let program = match kernel.compile(&ARCHITECTURE, settings) {
+        Ok(program) => program,
+        Err(err) => {
+            eprintln!("could not compile {path}: {err}");
+            if let CompileError::Escalated { diagnostics, .. } = &err {
+                for diagnostic in diagnostics {
+                    eprintln!("{diagnostic}");
+                }
+            }
+            std::process::exit(1);
+        }
+    };
+    match differential_test_seeded(kernel.network(), &program, 4, &settings) {
+        Ok(None) => println!("{program:#}"),
+        Ok(Some(mismatch)) => {
+            eprintln!("miscompiled {path}: output {} differs on bitline {}", mismatch.output, mismatch.bitline());
+            std::process::exit(1);
+        }
+        Err(err) => {
+            eprintln!("could not simulate the program: {err}");
+            std::process::exit(1);
+        }
+    }
+}
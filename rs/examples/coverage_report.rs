@@ -0,0 +1,30 @@
+//! Merges the coverage written by test runs and reports the code paths and rewrite rules they
+//! never exercised:
+//!
+//! ```sh
+//! PRADA_COVERAGE_DIR=target/coverage cargo test
+//! cargo run --example coverage_report target/coverage
+//! ```
+use lime_rs::prada::coverage::CoverageReport;
+
+fn main() {
+    let Some(dir) = std::env::args().nth(1) else {
+        eprintln!("usage: coverage_report <coverage dir>");
+        std::process::exit(1);
+    };
+    let mut merged = CoverageReport::default();
+    let entries = std::fs::read_dir(&dir).expect("coverage dir should be readable");
+    for entry in entries {
+        let path = entry.expect("coverage dir should be readable").path();
+        let text = std::fs::read_to_string(&path).expect("coverage file should be readable");
+        match CoverageReport::parse(&text) {
+            Ok(report) => merged.merge(&report),
+            Err(err) => eprintln!("skipping {}: {err}", path.display()),
+        }
+    }
+    let uncovered = merged.uncovered();
+    println!("{} of {} entries uncovered", uncovered.len(), merged.paths.len() + merged.rules.len());
+    for entry in uncovered {
+        println!("  {entry}");
+    }
+}
@@ -0,0 +1,52 @@
+//! Cross-validates the cost model against a cycle-accurate simulation:
+//!
+//! 1. `cross_validate <trace>` compiles the benchmark and writes its DRAM command trace to `<trace>`
+//! 2. the trace is simulated (e.g. using Ramulator), yielding the latency of every instruction as
+//!    CSV lines `<instruction index>,<latency in ns>`
+//! 3. `cross_validate <trace> <latencies>` compiles the benchmark again and reports the
+//!    discrepancy between estimated and simulated runtime per instruction class
+use lime_rs::prada::architecture::ARCHITECTURE;
+use lime_rs::prada::cost::CostModel;
+use lime_rs::prada::network::MigNetwork;
+use lime_rs::prada::trace::{parse_instruction_latencies, ramulator_trace, CrossValidation};
+use lime_rs::prada::{compile, CompilerSettings};
+
+fn settings() -> CompilerSettings {
+    CompilerSettings::default()
+}
+
+/// 8-bit ripple-carry adder
+fn benchmark() -> MigNetwork {
+    let mut network = MigNetwork::new();
+    let a: Vec<_> = (0..8).map(|_| network.add_input()).collect();
+    let b: Vec<_> = (0..8).map(|_| network.add_input()).collect();
+    let mut carry = network.constant(false);
+    for (a, b) in a.into_iter().zip(b) {
+        let next_carry = network.maj(a, b, carry);
+        let inner = network.maj(a, b, carry.invert());
+        let sum = network.maj(next_carry.invert(), carry, inner);
+        network.add_output(sum);
+        carry = next_carry;
+    }
+    network.add_output(carry);
+    network
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args: Vec<String> = std::env::args().collect();
+    let Some(trace_path) = args.get(1) else {
+        eprintln!("usage: {} <trace> [<latencies>]", args[0]);
+        std::process::exit(1);
+    };
+    let program = compile(&ARCHITECTURE, &benchmark(), settings()).expect("network should be compilable");
+    std::fs::write(trace_path, ramulator_trace(&program))?;
+    println!("wrote trace of {} instructions to {trace_path}", program.unrolled_instructions().len());
+
+    if let Some(latencies_path) = args.get(2) {
+        let text = std::fs::read_to_string(latencies_path)?;
+        let latencies = parse_instruction_latencies(&text, program.unrolled_instructions().len())?;
+        let validation = CrossValidation::new(&CostModel::default(), &program, &latencies)?;
+        print!("{}", validation.to_markdown());
+    }
+    Ok(())
+}
@@ -0,0 +1,25 @@
+//! Compares sequential and parallel extraction of the Hamming distance networks of
+//! [stdlib](lime_rs::prada::stdlib), checking that both choose the same terms:
+//!
+//! ```sh
+//! cargo run --release --features parallel-extraction --example extraction_benchmark
+//! ```
+use lime_rs::prada::architecture::ARCHITECTURE;
+use lime_rs::prada::stdlib::hamming_distance_network;
+use lime_rs::prada::{benchmark_extraction, CompilerSettings};
+
+fn main() {
+    let settings = CompilerSettings::builder().rewrite(true).build();
+    println!("{:>6} {:>8} {:>12} {:>12} {:>8}", "width", "classes", "sequential", "parallel", "speedup");
+    for width in [64, 128, 256, 512] {
+        let result = benchmark_extraction(&ARCHITECTURE, &hamming_distance_network(width), settings);
+        println!(
+            "{:>6} {:>8} {:>10.1}ms {:>10.1}ms {:>7.2}x",
+            width,
+            result.classes,
+            result.sequential.as_secs_f64() * 1e3,
+            result.parallel.as_secs_f64() * 1e3,
+            result.sequential.as_secs_f64() / result.parallel.as_secs_f64()
+        );
+    }
+}
@@ -0,0 +1,36 @@
+//! Re-runs the compilation recorded in a [manifest](lime_rs::prada::manifest), failing if the
+//! configuration or the compiled program differs from the recorded one:
+//!
+//! ```sh
+//! cargo run --example repro experiment.manifest
+//! ```
+use lime_rs::prada::architecture::ARCHITECTURE;
+use lime_rs::prada::manifest::Manifest;
+
+fn main() {
+    let Some(path) = std::env::args().nth(1) else {
+        eprintln!("usage: repro <manifest>");
+        std::process::exit(1);
+    };
+    let text = std::fs::read_to_string(&path).expect("manifest should be readable");
+    let manifest = match Manifest::parse(&text) {
+        Ok(manifest) => manifest,
+        Err(err) => {
+            eprintln!("invalid manifest {path}: {err}");
+            std::process::exit(1);
+        }
+    };
+    match manifest.reproduce(&ARCHITECTURE) {
+        Ok(program) => println!(
+            "reproduced {path} (seed {}): {} instructions, runtime {}, energy {}",
+            manifest.seed,
+            program.instructions.len(),
+            program.runtime_estimate,
+            program.energy_consumption_estimate
+        ),
+        Err(err) => {
+            eprintln!("could not reproduce {path}: {err}");
+            std::process::exit(1);
+        }
+    }
+}
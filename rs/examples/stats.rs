@@ -0,0 +1,56 @@
+//! Queries the experiment database of [lime_rs::prada::experiments], printing e.g. the ten
+//! compilations with the lowest energy consumption estimate:
+//!
+//! ```sh
+//! cargo run --example stats --features sqlite -- experiments.db top --by energy
+//! ```
+use lime_rs::prada::experiments::{ExperimentDatabase, Metric};
+
+const USAGE: &str = "usage: stats <database> top [--by <energy|runtime|instructions|compile_time>] [--limit <n>] \
+                     [--benchmark <name>]";
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let [path, command, options @ ..] = &args[..] else {
+        exit(USAGE);
+    };
+    if command != "top" {
+        exit(USAGE);
+    }
+    let (mut metric, mut limit, mut benchmark) = (Metric::Energy, 10, None);
+    for option in options.chunks(2) {
+        match option {
+            [name, value] if name == "--by" => {
+                metric = Metric::from_name(value).unwrap_or_else(|| exit("unknown metric"));
+            }
+            [name, value] if name == "--limit" => limit = value.parse().unwrap_or_else(|_| exit("invalid limit")),
+            [name, value] if name == "--benchmark" => benchmark = Some(value.as_str()),
+            _ => exit(USAGE),
+        }
+    }
+
+    let database = ExperimentDatabase::open(path).unwrap_or_else(|err| exit(&format!("cannot open {path}: {err}")));
+    let experiments =
+        database.top(metric, benchmark, limit).unwrap_or_else(|err| exit(&format!("query failed: {err}")));
+    println!(
+        "{:>6} {:<24} {:>12} {:>10} {:>10} {:>12}  settings",
+        "id", "benchmark", "instructions", "runtime", "energy", "compile time"
+    );
+    for experiment in experiments {
+        println!(
+            "{:>6} {:<24} {:>12} {:>10} {:>10} {:>10}ms  {}",
+            experiment.id,
+            experiment.benchmark,
+            experiment.instructions,
+            experiment.runtime_estimate,
+            experiment.energy_consumption_estimate,
+            experiment.compile_time.as_millis(),
+            experiment.settings,
+        );
+    }
+}
+
+fn exit(message: &str) -> ! {
+    eprintln!("{message}");
+    std::process::exit(1);
+}
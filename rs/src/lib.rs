@@ -1,5 +1,26 @@
+//! Compiler for processing-using-DRAM (PUD) architectures, rewriting majority-inverter graphs
+//! (MIGs) in an e-graph and compiling them into row-level DRAM programs.
+//!
+//! Besides the FFI entry points used by the C++ host, the [prada] module is usable as a Rust
+//! library. The most common items are re-exported at its top level and in the [prelude]:
+//!
+//! - [prada::compile] runs rewriting, extraction and compilation of a [prada::MigNetwork]
+//! - [prada::Architecture] describes the targeted DRAM module
+//! - [prada::Program] is the compiled program, which can be run by a [prada::Simulator]
 #![allow(clippy::upper_case_acronyms)]
 
 mod ambit;
 mod opt_extractor;
 pub mod prada;
+
+/// Re-exports of the items needed for compiling and simulating networks, to be glob-imported via
+/// `use lime_rs::prelude::*`
+pub mod prelude {
+    pub use crate::prada::architecture::{Capabilities, RowAddress, SubarrayId, ARCHITECTURE};
+    pub use crate::prada::simulation::simulate;
+    pub use crate::prada::{
+        compile, Architecture, CompileError, CompilerSettings, Instruction, Linker, MigNetwork, ModuleLibrary,
+        Program, RewriteBackend, RowInit, RunnerScheduler, SchedulingPolicy, Simulator, Strictness,
+    };
+    pub use eggmock::{Network, Signal};
+}
@@ -22,6 +22,13 @@ pub struct OptExtractor<'g, CF: OptCostFunction<L, A>, L: Language, A: Analysis<
     costs: FxHashMap<Id, (CF::Cost, L)>,
 }
 
+/// Nr of classes from which on a wavefront is evaluated in parallel, smaller ones aren't worth
+/// the synchronization
+#[cfg(feature = "parallel-extraction")]
+const PARALLEL_WAVEFRONT: usize = 256;
+
+type Costs<CF, L, A> = FxHashMap<Id, (<CF as OptCostFunction<L, A>>::Cost, L)>;
+
 impl<'g, CF: OptCostFunction<L, A>, L: Language, A: Analysis<L>> OptExtractor<'g, CF, L, A> {
     pub fn new(graph: &'g EGraph<L, A>, cost_fn: CF) -> Self {
         let mut extractor = Self {
@@ -29,7 +36,12 @@ impl<'g, CF: OptCostFunction<L, A>, L: Language, A: Analysis<L>> OptExtractor<'g
             cost_fn,
             costs: FxHashMap::default(),
         };
-        extractor.find_costs();
+        extractor.find_costs(|graph, costs, cost_fn, wavefront| {
+            wavefront
+                .iter()
+                .filter_map(|&id| best_node(graph, costs, cost_fn, &graph[id]).map(|best| (id, best)))
+                .collect()
+        });
         extractor
     }
 
@@ -39,46 +51,110 @@ impl<'g, CF: OptCostFunction<L, A>, L: Language, A: Analysis<L>> OptExtractor<'g
             .map(|(_, node)| node)
     }
 
-    fn find_costs(&mut self) {
-        let mut changed = true;
-        while changed {
-            changed = false;
-            for class in self.graph.classes() {
-                let new_cost = self.determine_class_costs(class);
-                match (self.costs.get(&class.id), new_cost) {
-                    (None, Some(new)) => {
-                        self.costs.insert(class.id, new);
-                        changed = true;
-                    }
-                    (Some(old), Some(new)) if new.0 < old.0 => {
-                        self.costs.insert(class.id, new);
-                        changed = true;
-                    }
-                    _ => (),
+    /// Returns the cost of the best node of the given class
+    pub fn find_best_cost(&self, class: Id) -> Option<&CF::Cost> {
+        self.costs
+            .get(&self.graph.find(class))
+            .map(|(cost, _)| cost)
+    }
+
+    /// Determines the best node of every class in waves: the first wave evaluates all classes,
+    /// every following one the parents of the classes whose cost improved in the previous wave.
+    /// Each wave is evaluated against the costs known before it (by `evaluate`), so the result
+    /// doesn't depend on the order in which the classes of a wave are evaluated.
+    fn find_costs(
+        &mut self,
+        mut evaluate: impl FnMut(&EGraph<L, A>, &Costs<CF, L, A>, &mut CF, &[Id]) -> Vec<(Id, (CF::Cost, L))>,
+    ) {
+        let mut parents: FxHashMap<Id, Vec<Id>> = FxHashMap::default();
+        for class in self.graph.classes() {
+            for node in class.iter() {
+                for child in node.children() {
+                    parents.entry(self.graph.find(*child)).or_default().push(class.id);
                 }
             }
         }
+        let mut wavefront: Vec<Id> = self.graph.classes().map(|class| class.id).collect();
+        wavefront.sort_unstable();
+        while !wavefront.is_empty() {
+            let mut next = vec!();
+            for (id, new) in evaluate(self.graph, &self.costs, &mut self.cost_fn, &wavefront) {
+                let improved = match self.costs.get(&id) {
+                    None => true,
+                    Some(old) => new.0 < old.0,
+                };
+                if improved {
+                    self.costs.insert(id, new);
+                    next.extend(parents.get(&id).into_iter().flatten().copied());
+                }
+            }
+            next.sort_unstable();
+            next.dedup();
+            wavefront = next;
+        }
     }
+}
 
-    fn determine_class_costs(&mut self, class: &EClass<L, A::Data>) -> Option<(CF::Cost, L)> {
-        class
-            .iter()
-            .map(|node| (self.opt_node_cost(node, class), node))
-            .filter_map(|(cost, node)| cost.map(|cost| (cost, node)))
-            .min_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap())
-            .map(|(cost, node)| (cost, node.clone()))
-    }
+#[cfg(feature = "parallel-extraction")]
+impl<'g, CF, L, A> OptExtractor<'g, CF, L, A>
+where
+    CF: OptCostFunction<L, A> + Clone + Send + Sync,
+    CF::Cost: Send + Sync,
+    L: Language + Send + Sync,
+    A: Analysis<L>,
+    EGraph<L, A>: Sync,
+{
+    /// Same as [Self::new], but evaluates large wavefronts in parallel, each thread using its own
+    /// copy of the cost function. Chooses the same nodes as [Self::new].
+    pub fn new_parallel(graph: &'g EGraph<L, A>, cost_fn: CF) -> Self {
+        use rayon::prelude::*;
 
-    fn opt_node_cost(&mut self, node: &L, class: &EClass<L, A::Data>) -> Option<CF::Cost> {
-        if node.all(|id| self.costs.contains_key(&id)) {
-            self.cost_fn
-                .cost(class, node, |id| self.costs[&self.graph.find(id)].0.clone())
-        } else {
-            None
-        }
+        let mut extractor = Self {
+            graph,
+            cost_fn,
+            costs: FxHashMap::default(),
+        };
+        extractor.find_costs(|graph, costs, cost_fn, wavefront| {
+            let best: Vec<Option<(Id, (CF::Cost, L))>> = if wavefront.len() < PARALLEL_WAVEFRONT {
+                wavefront
+                    .iter()
+                    .map(|&id| best_node(graph, costs, cost_fn, &graph[id]).map(|best| (id, best)))
+                    .collect()
+            } else {
+                let cost_fn = &*cost_fn;
+                wavefront
+                    .par_iter()
+                    .map_init(
+                        || cost_fn.clone(),
+                        |cost_fn, &id| best_node(graph, costs, cost_fn, &graph[id]).map(|best| (id, best)),
+                    )
+                    .collect()
+            };
+            best.into_iter().flatten().collect()
+        });
+        extractor
     }
 }
 
+/// Cheapest node of `class` whose children all have a cost, ties going to the first node
+fn best_node<CF: OptCostFunction<L, A>, L: Language, A: Analysis<L>>(
+    graph: &EGraph<L, A>,
+    costs: &Costs<CF, L, A>,
+    cost_fn: &mut CF,
+    class: &EClass<L, A::Data>,
+) -> Option<(CF::Cost, L)> {
+    class
+        .iter()
+        .filter(|node| node.all(|id| costs.contains_key(&graph.find(id))))
+        .filter_map(|node| {
+            cost_fn
+                .cost(class, node, |id| costs[&graph.find(id)].0.clone())
+                .map(|cost| (cost, node))
+        })
+        .min_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap())
+        .map(|(cost, node)| (cost, node.clone()))
+}
+
 pub struct OptExtractionNetwork<E>(pub E, pub Vec<Id>);
 
 impl<CF, L, A> Network for OptExtractionNetwork<OptExtractor<'_, CF, L, A>>
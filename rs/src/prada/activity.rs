@@ -0,0 +1,179 @@
+//! Search for input values causing the most switching activity (bit flips, see
+//! [Simulator::bit_flips]) while running a program, which yields worst-case energy bounds in
+//! addition to the static, data-independent estimate of the cost model. [power_report] turns the
+//! activity of a simulated run into energy using the [ChargeModel] of the architecture.
+use std::fmt::{Display, Formatter};
+
+use super::cost::ChargeModel;
+use super::program::{Program, RowInit};
+use super::random::{Stream, Xorshift};
+use super::simulation::Simulator;
+use super::CompilerSettings;
+
+/// Parameters of [search_activity]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ActivitySearch {
+    /// Nr of random input assignments simulated for the average case, the best one of which is
+    /// the starting point of the greedy search
+    pub samples: usize,
+    /// Nr of greedy improvement rounds, each of which tries to flip every input bit once
+    pub rounds: usize,
+    pub seed: u64,
+}
+
+impl Default for ActivitySearch {
+    fn default() -> Self {
+        Self { samples: 64, rounds: 2, seed: 0x853c_49e6_748f_ea9b }
+    }
+}
+
+impl ActivitySearch {
+    /// Default search drawing its inputs from the [seed](CompilerSettings::seed) of `settings`
+    pub fn seeded(settings: &CompilerSettings) -> Self {
+        Self { seed: settings.stream_seed(Stream::Activity), ..Self::default() }
+    }
+}
+
+/// Switching activity of a program over the searched inputs
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActivityReport {
+    /// Mean nr of bit flips over the random samples
+    pub average_bit_flips: f64,
+    pub worst_bit_flips: u64,
+    /// Input values (one `u64` per input) causing [Self::worst_bit_flips]
+    pub worst_inputs: Vec<u64>,
+}
+
+impl ActivityReport {
+    /// Scales the static energy estimate of `program` by the worst-case activity, assuming that the
+    /// static estimate reflects the average activity
+    pub fn worst_case_energy(&self, program: &Program) -> f64 {
+        program.energy_consumption_estimate as f64 * self.worst_bit_flips as f64 / self.average_bit_flips.max(1.0)
+    }
+}
+
+impl Display for ActivityReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "bit flips: {:.1} on average, {} worst case ({:.2}x)",
+            self.average_bit_flips,
+            self.worst_bit_flips,
+            self.worst_bit_flips as f64 / self.average_bit_flips.max(1.0)
+        )
+    }
+}
+
+/// Nr of bit flips caused by running `program` once on the given inputs
+pub fn bit_flips(program: &Program, inputs: &[u64]) -> Result<u64, &'static str> {
+    let mut simulator = Simulator::new();
+    simulator.load_persistent_rows(program, inputs)?;
+    simulator.load_inputs(program, inputs)?;
+    simulator.run(&program.instructions)?;
+    Ok(simulator.bit_flips)
+}
+
+/// Energy of running a program once on some inputs according to both the static per-instruction
+/// estimate of the [CostModel](super::cost::CostModel) and the [ChargeModel]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PowerReport {
+    /// [Program::energy_consumption_estimate] (in mJ/KOps)
+    pub static_energy: u64,
+    /// Nr of row activations (see [ChargeModel::activations])
+    pub activations: u64,
+    pub bit_flips: u64,
+    /// in pJ
+    pub activation_energy: f64,
+    /// in pJ
+    pub toggle_energy: f64,
+}
+
+impl PowerReport {
+    /// Energy according to the charge model (in pJ)
+    pub fn charge_energy(&self) -> f64 {
+        self.activation_energy + self.toggle_energy
+    }
+}
+
+impl Display for PowerReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "energy: {} mJ/KOps static, {:.1} pJ by charge ({} activations: {:.1} pJ, {} bit flips: {:.1} pJ)",
+            self.static_energy,
+            self.charge_energy(),
+            self.activations,
+            self.activation_energy,
+            self.bit_flips,
+            self.toggle_energy
+        )
+    }
+}
+
+/// Simulates `program` once on the given inputs and estimates its energy using the
+/// [charge model](super::architecture::PRADAArchitecture::charge_model) of its architecture
+pub fn power_report(program: &Program, inputs: &[u64]) -> Result<PowerReport, &'static str> {
+    let model = &program.architecture.charge_model;
+    let activations = program.unrolled_instructions().iter().map(ChargeModel::activations).sum();
+    let bit_flips = bit_flips(program, inputs)?;
+    Ok(PowerReport {
+        static_energy: program.energy_consumption_estimate,
+        activations,
+        bit_flips,
+        activation_energy: activations as f64 * model.activation_energy(),
+        toggle_energy: model.toggle_energy(bit_flips),
+    })
+}
+
+/// Simulates random inputs for the average case and greedily flips single input bits (keeping
+/// flips which increase the activity) for the worst case
+pub fn search_activity(program: &Program, search: ActivitySearch) -> Result<ActivityReport, &'static str> {
+    let nr_inputs = program
+        .input_map
+        .iter()
+        .filter_map(|(_, init)| match init {
+            RowInit::Input { index, .. } => Some(*index as usize + 1),
+            RowInit::Constant(_) => None,
+        })
+        .max()
+        .unwrap_or(0);
+    let mut random = Xorshift::new(search.seed);
+
+    let mut total = 0;
+    let mut worst_inputs = vec!(0; nr_inputs);
+    let mut worst = bit_flips(program, &worst_inputs)?;
+    for _ in 0..search.samples {
+        let inputs: Vec<u64> = (0..nr_inputs).map(|_| random.next_u64()).collect();
+        let flips = bit_flips(program, &inputs)?;
+        total += flips;
+        if flips > worst {
+            worst = flips;
+            worst_inputs = inputs;
+        }
+    }
+
+    for _ in 0..search.rounds {
+        let mut improved = false;
+        for input in 0..nr_inputs {
+            for bit in 0..64 {
+                worst_inputs[input] ^= 1 << bit;
+                let flips = bit_flips(program, &worst_inputs)?;
+                if flips > worst {
+                    worst = flips;
+                    improved = true;
+                } else {
+                    worst_inputs[input] ^= 1 << bit;
+                }
+            }
+        }
+        if !improved {
+            break;
+        }
+    }
+
+    Ok(ActivityReport {
+        average_bit_flips: total as f64 / search.samples.max(1) as f64,
+        worst_bit_flips: worst,
+        worst_inputs,
+    })
+}
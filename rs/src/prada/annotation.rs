@@ -0,0 +1,82 @@
+//! Per-node metadata of the extracted network which is forwarded to the C++ side alongside the
+//! network itself, so that it can report on the quality of the mapping.
+use super::compilation::reachable_nodes;
+use super::extraction::CompilingCost;
+use crate::opt_extractor::{OptCostFunction, OptExtractionNetwork, OptExtractor};
+use eggmock::{Id, Mig, MigLanguage, Network};
+use rustc_hash::FxHashMap;
+use std::ffi::c_void;
+
+#[derive(Debug, Copy, Clone)]
+#[repr(C)]
+pub struct NodeAnnotation {
+    /// Id of the node (=e-class) in the extracted network
+    pub id: u64,
+    /// Nr of MAJ nodes on the longest path from a leaf to (and including) this node
+    pub depth: u64,
+    /// Extraction cost of the node chosen for the e-class (including its children)
+    pub runtime_estimate: u64,
+    pub energy_consumption_estimate: u64,
+}
+
+/// Callbacks receiving the annotations, `data` is passed through to them unchanged
+#[repr(C)]
+pub struct AnnotationReceiverFFI {
+    pub data: *mut c_void,
+    pub annotate_node: extern "C" fn(*mut c_void, NodeAnnotation),
+    /// called with the index of each output and the id of the node it points to
+    pub annotate_output: extern "C" fn(*mut c_void, u64, u64),
+}
+
+/// Computes the annotations of all nodes reachable from the outputs of the extracted network
+pub fn annotate<CF: OptCostFunction<MigLanguage, (), Cost = CompilingCost>>(
+    ntk: &OptExtractionNetwork<OptExtractor<CF, MigLanguage, ()>>,
+) -> Vec<NodeAnnotation> {
+    let mut depths: FxHashMap<Id, u64> = FxHashMap::default();
+    let mut nodes = reachable_nodes(ntk);
+    // `reachable_nodes` returns parents before their children
+    nodes.reverse();
+    let mut annotations = Vec::with_capacity(nodes.len());
+    for id in nodes {
+        let depth = match ntk.node(id) {
+            Mig::Maj(inputs) => {
+                1 + inputs
+                    .iter()
+                    .map(|input| depths.get(&input.node_id()).copied().unwrap_or(0))
+                    .max()
+                    .unwrap_or(0)
+            }
+            _ => 0,
+        };
+        depths.insert(id, depth);
+        let cost = ntk
+            .0
+            .find_best_cost(id.into())
+            .expect("class should be extractable");
+        annotations.push(NodeAnnotation {
+            id: id_to_u64(id),
+            depth,
+            runtime_estimate: cost.runtime,
+            energy_consumption_estimate: cost.energy_consumption,
+        });
+    }
+    annotations
+}
+
+impl AnnotationReceiverFFI {
+    pub fn send<CF: OptCostFunction<MigLanguage, (), Cost = CompilingCost>>(
+        &self,
+        ntk: &OptExtractionNetwork<OptExtractor<CF, MigLanguage, ()>>,
+    ) {
+        for annotation in annotate(ntk) {
+            (self.annotate_node)(self.data, annotation);
+        }
+        for (idx, output) in ntk.outputs().enumerate() {
+            (self.annotate_output)(self.data, idx as u64, id_to_u64(output.node_id()));
+        }
+    }
+}
+
+fn id_to_u64(id: Id) -> u64 {
+    usize::from(eggmock::egg::Id::from(id)) as u64
+}
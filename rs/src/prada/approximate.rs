@@ -0,0 +1,215 @@
+//! Approximate computing for error-tolerant workloads (e.g. [BNN](super::bnn) inference): MAJs are
+//! greedily replaced by one of their operands or by a constant as long as the error rate of the
+//! network, measured by evaluating it on pseudo-random input vectors, stays within the
+//! [ErrorBudget]. MAJs only feeding outputs of low significance (e.g. the carry logic of the low
+//! bits of a sum) are dropped first. The compiled program is verified by simulating it on input
+//! vectors independent of those the approximation has been chosen on.
+use eggmock::{Id, Mig, Network, Signal};
+use rustc_hash::FxHashMap;
+
+use super::architecture::PRADAArchitecture;
+use super::compilation::reachable_nodes;
+use super::error::CompileError;
+use super::network::MigNetwork;
+use super::program::Program;
+use super::random::{Stream, Xorshift};
+use super::simulation::{evaluate_network, simulate};
+use super::{compile, count_majs, CompilerSettings};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ErrorBudget {
+    /// Maximal fraction of input vectors for which any output of the approximated network differs
+    /// from the exact one
+    pub error_rate: f64,
+    /// Significance of every output (by index), outputs without significance count as 0. MAJs are
+    /// tried in the order of the highest significance of the outputs depending on them.
+    pub significance: Vec<u64>,
+    /// Nr of rounds of 64 input vectors the error rate is measured on
+    pub rounds: usize,
+    pub seed: u64,
+}
+
+impl Default for ErrorBudget {
+    fn default() -> Self {
+        Self { error_rate: 0.01, significance: vec!(), rounds: 16, seed: 0x2545_f491_4f6c_dd1d }
+    }
+}
+
+impl ErrorBudget {
+    /// Default budget drawing its input vectors from the [seed](CompilerSettings::seed) of
+    /// `settings`
+    pub fn seeded(settings: &CompilerSettings) -> Self {
+        Self { seed: settings.stream_seed(Stream::Approximation), ..Self::default() }
+    }
+}
+
+/// Outcome of approximating a network
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ApproximationReport {
+    /// Nr of replaced MAJs, not counting MAJs which only became unused
+    pub replaced: usize,
+    pub exact_majs: u64,
+    pub approximate_majs: u64,
+    /// Error rate on the input vectors the approximation has been chosen on
+    pub error_rate: f64,
+    /// Error rate of the compiled program on independent input vectors, `None` if the network
+    /// hasn't been compiled
+    pub verified_error_rate: Option<f64>,
+}
+
+/// Replacement of a MAJ
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Replacement {
+    Operand(usize),
+    Constant(bool),
+}
+
+impl Replacement {
+    const ALL: [Self; 5] =
+        [Self::Constant(false), Self::Constant(true), Self::Operand(0), Self::Operand(1), Self::Operand(2)];
+}
+
+/// Approximates `network` within `budget` (see the [module docs](self)). Evaluates the network
+/// about five times per MAJ, so it's meant for kernels of moderate size.
+pub fn approximate(
+    network: &MigNetwork,
+    budget: &ErrorBudget,
+) -> Result<(MigNetwork, ApproximationReport), &'static str> {
+    let samples = random_inputs(network.nr_inputs(), budget.rounds, budget.seed);
+    let exact: Vec<Vec<u64>> =
+        samples.iter().map(|inputs| evaluate_network(network, inputs)).collect::<Result<_, _>>()?;
+    let error_rate = |approximated: &MigNetwork| -> Result<f64, &'static str> {
+        let mut wrong = 0;
+        for (inputs, exact) in samples.iter().zip(&exact) {
+            wrong += wrong_vectors(&evaluate_network(approximated, inputs)?, exact);
+        }
+        Ok(wrong as f64 / (64 * samples.len().max(1)) as f64)
+    };
+
+    let exact_majs = count_majs(network);
+    let mut replacements: FxHashMap<Id, Replacement> = FxHashMap::default();
+    let mut approximated = network.clone();
+    let mut majs = exact_majs;
+    let mut current_error_rate = 0.0;
+    for id in candidates(network, &budget.significance) {
+        let mut best: Option<(f64, u64, MigNetwork, Replacement)> = None;
+        for replacement in Replacement::ALL {
+            replacements.insert(id, replacement);
+            let candidate = rebuild(network, &replacements);
+            let candidate_majs = count_majs(&candidate);
+            let candidate_error_rate = error_rate(&candidate)?;
+            let better = !matches!(&best, Some((best_error_rate, ..)) if candidate_error_rate >= *best_error_rate);
+            if candidate_majs < majs && candidate_error_rate <= budget.error_rate && better {
+                best = Some((candidate_error_rate, candidate_majs, candidate, replacement));
+            }
+        }
+        match best {
+            Some((candidate_error_rate, candidate_majs, candidate, replacement)) => {
+                replacements.insert(id, replacement);
+                (current_error_rate, majs, approximated) = (candidate_error_rate, candidate_majs, candidate);
+            }
+            None => {
+                replacements.remove(&id);
+            }
+        }
+    }
+    let report = ApproximationReport {
+        replaced: replacements.len(),
+        exact_majs,
+        approximate_majs: majs,
+        error_rate: current_error_rate,
+        verified_error_rate: None,
+    };
+    // drops the nodes which only became unused
+    Ok((rebuild(&approximated, &FxHashMap::default()), report))
+}
+
+/// Approximates `network` within `budget` and compiles the approximated network, verifying the
+/// error rate of the program by simulation (see [ApproximationReport::verified_error_rate])
+pub fn compile_approximate<'a>(
+    architecture: &'a PRADAArchitecture,
+    network: &MigNetwork,
+    settings: CompilerSettings,
+    budget: &ErrorBudget,
+) -> Result<(Program<'a>, ApproximationReport), CompileError> {
+    let (approximated, mut report) = approximate(network, budget)?;
+    let program = compile(architecture, &approximated, settings)?;
+    // independent of the input vectors the approximation has been chosen on
+    let samples = random_inputs(network.nr_inputs(), budget.rounds, !budget.seed);
+    let mut wrong = 0;
+    for inputs in &samples {
+        wrong += wrong_vectors(&simulate(&program, inputs)?, &evaluate_network(network, inputs)?);
+    }
+    report.verified_error_rate = Some(wrong as f64 / (64 * samples.len().max(1)) as f64);
+    Ok((program, report))
+}
+
+/// MAJs of `network` in the order in which they are tried to be replaced: by the highest
+/// significance of the outputs depending on them, users before their operands
+fn candidates(network: &MigNetwork, significance: &[u64]) -> Vec<Id> {
+    let nodes = reachable_nodes(network);
+    let mut node_significance: FxHashMap<Id, u64> = FxHashMap::default();
+    for (idx, output) in network.outputs().enumerate() {
+        let significance = significance.get(idx).copied().unwrap_or(0);
+        let entry = node_significance.entry(output.node_id()).or_insert(0);
+        *entry = significance.max(*entry);
+    }
+    // `reachable_nodes` lists users before their operands, which hence inherit the final
+    // significance of their users
+    for id in &nodes {
+        if let Mig::Maj(operands) = network.node(*id) {
+            let significance = node_significance.get(id).copied().unwrap_or(0);
+            for operand in operands {
+                let entry = node_significance.entry(operand.node_id()).or_insert(0);
+                *entry = significance.max(*entry);
+            }
+        }
+    }
+    let mut majs: Vec<Id> = nodes.into_iter().filter(|id| matches!(network.node(*id), Mig::Maj(_))).collect();
+    // stable, keeping users before their operands
+    majs.sort_by_key(|id| node_significance.get(id).copied().unwrap_or(0));
+    majs
+}
+
+/// Copy of the nodes of `network` reachable from its outputs, with the MAJs in `replacements`
+/// replaced
+fn rebuild(network: &MigNetwork, replacements: &FxHashMap<Id, Replacement>) -> MigNetwork {
+    let mut rebuilt = MigNetwork::new();
+    // inputs keep their indices, even if they are unused
+    let inputs: Vec<Signal> = (0..network.nr_inputs()).map(|_| rebuilt.add_input()).collect();
+    let mut signals: FxHashMap<Id, Signal> = FxHashMap::default();
+    let map = |signals: &FxHashMap<Id, Signal>, signal: Signal| {
+        let mapped = signals[&signal.node_id()];
+        if signal.is_inverted() { mapped.invert() } else { mapped }
+    };
+    for id in reachable_nodes(network).into_iter().rev() {
+        let signal = match network.node(id) {
+            Mig::False => rebuilt.constant(false),
+            Mig::Input(index) => inputs[index as usize],
+            Mig::Maj(operands) => {
+                let [a, b, c] = operands.map(|operand| map(&signals, operand));
+                match replacements.get(&id) {
+                    Some(Replacement::Operand(operand)) => [a, b, c][*operand],
+                    Some(Replacement::Constant(value)) => rebuilt.constant(*value),
+                    None => rebuilt.maj(a, b, c),
+                }
+            }
+        };
+        signals.insert(id, signal);
+    }
+    for output in network.outputs() {
+        rebuilt.add_output(map(&signals, output));
+    }
+    rebuilt
+}
+
+/// Nr of input vectors (bitlines) for which any of the `outputs` differs from the `exact` ones
+fn wrong_vectors(outputs: &[u64], exact: &[u64]) -> u64 {
+    outputs.iter().zip(exact).fold(0, |differing, (output, exact)| differing | (output ^ exact)).count_ones() as u64
+}
+
+/// `rounds` assignments of 64 pseudo-random input vectors each
+fn random_inputs(nr_inputs: u64, rounds: usize, seed: u64) -> Vec<Vec<u64>> {
+    let mut random = Xorshift::new(seed);
+    (0..rounds).map(|_| (0..nr_inputs).map(|_| random.next_u64()).collect()).collect()
+}
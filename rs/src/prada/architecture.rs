@@ -1,8 +1,12 @@
-use std::{fmt::{Debug, Display, Formatter, Result}, sync::LazyLock};
+use std::{fmt::{Debug, Display, Formatter, Result}, ops, sync::LazyLock};
+
+use super::cost::{ChargeModel, CompilingCost, CostModel};
+use super::error::CompileError;
+use super::program::Instruction;
 
 pub const NR_SUBARRAYS: u64 = 2u64.pow(7);
 pub const ROWS_PER_SUBARRAY: u64 = 2u64.pow(9);
-pub const SUBARRAY_ID_BITMASK: u64 = 0b1_111_111_000_000_000; // 7 highest bits=subarray id
+pub const SUBARRAY_ID_BITMASK: u64 = !ROW_ID_BITMASK; // all bits above the row id=subarray id
 pub const ROW_ID_BITMASK: u64 = 0b0_000_000_111_111_111; // 7 highest bits=subarray id
 
 // some utility functions
@@ -16,6 +20,31 @@ pub struct PRADAArchitecture {
     pub nr_subarrays: u64,
     /// Nr of rows in a single subarray
     pub rows_per_subarray: u64,
+    /// Operations supported by the DRAM module
+    pub capabilities: Capabilities,
+    /// Nr of dual-contact cells (DCC) rows per subarray (if [Capabilities::DCC] is supported),
+    /// whose negated wordline provides the inverse of the stored value without an explicit N.
+    /// Extraction treats inversions as free as long as there are enough DCC rows.
+    pub nr_dcc_rows: u64,
+    /// Costs of the instructions, used for extraction and for the estimates of compiled programs.
+    /// May be calibrated from measurements, see [CostModel::calibrate].
+    pub cost_model: CostModel,
+    /// Capacitances of the module for the data-dependent energy estimates of simulated programs,
+    /// see [power_report](super::activity::power_report)
+    pub charge_model: ChargeModel,
+    /// Organisation of the subarrays into banks, ranks and channels
+    pub hierarchy: Hierarchy,
+    /// Relative noise margin of TRAs on each row of a subarray (by local row address, higher is
+    /// more reliable), e.g. derived from the distance to the sense amplifiers. The row allocator
+    /// prefers reliable rows for values on deep dependency chains, on which errors would propagate
+    /// the furthest. Empty if all rows are equally reliable, missing rows count as 0.
+    pub row_reliability: Vec<u32>,
+    /// Triples of local rows (in ascending order) which have been verified to be activated
+    /// simultaneously by a TRA, e.g. by characterizing the module, since the row decoders of
+    /// commodity modules only support some combinations. Empty if the module hasn't been
+    /// characterized, in which case TRAs aren't checked. TRAs of other rows are reported as
+    /// [DiagnosticCode::UnverifiedTraGrouping](super::diagnostics::DiagnosticCode::UnverifiedTraGrouping).
+    pub verified_tra_groups: Vec<[u64; 3]>,
 }
 
 impl PRADAArchitecture {
@@ -23,6 +52,33 @@ impl PRADAArchitecture {
         Self {
             nr_subarrays,
             rows_per_subarray,
+            capabilities: Capabilities::DEFAULT,
+            nr_dcc_rows: 0,
+            cost_model: CostModel::default(),
+            charge_model: ChargeModel::default(),
+            hierarchy: Hierarchy::single_bank(nr_subarrays),
+            row_reliability: vec!(),
+            verified_tra_groups: vec!(),
+        }
+    }
+
+    /// Spans the architecture over the given hierarchy, i.e. the nr of subarrays becomes the nr of
+    /// subarrays of all channels
+    pub fn with_hierarchy(self, hierarchy: Hierarchy) -> Self {
+        Self { nr_subarrays: hierarchy.nr_subarrays(), hierarchy, ..self }
+    }
+
+    pub fn supports(&self, capabilities: Capabilities) -> bool {
+        self.capabilities.contains(capabilities)
+    }
+
+    /// Fails with [CompileError::UnsupportedOperation] if any of the given capabilities is missing
+    pub fn require(&self, capabilities: Capabilities, operation: &'static str) -> std::result::Result<(), CompileError> {
+        let missing = capabilities.difference(self.capabilities);
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            Err(CompileError::UnsupportedOperation { operation, missing })
         }
     }
 
@@ -36,9 +92,276 @@ pub static ARCHITECTURE: LazyLock<PRADAArchitecture> = LazyLock::new(|| {
     PRADAArchitecture {
         nr_subarrays: NR_SUBARRAYS,
         rows_per_subarray: ROWS_PER_SUBARRAY,
+        capabilities: Capabilities::DEFAULT,
+        nr_dcc_rows: 0,
+        cost_model: CostModel::default(),
+        charge_model: ChargeModel::default(),
+        hierarchy: Hierarchy::single_bank(NR_SUBARRAYS),
+        row_reliability: vec!(),
+        verified_tra_groups: vec!(),
     }
 });
 
+/// Organisation of the subarrays into banks, ranks and channels. Subarrays are numbered
+/// consecutively across the hierarchy, i.e. subarray `s` is the `s % subarrays_per_bank`-th
+/// subarray of bank `s / subarrays_per_bank` (counted over all ranks and channels) and so on.
+/// Since compute/reference subarray pairs can't span banks, `subarrays_per_bank` should be even.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Hierarchy {
+    pub nr_channels: u64,
+    pub ranks_per_channel: u64,
+    pub banks_per_rank: u64,
+    pub subarrays_per_bank: u64,
+    /// Cost of moving a row out of its bank, on top of the cost of the row copy itself
+    pub transfer_costs: TransferCosts,
+}
+
+/// Additional cost of copying a row into a subarray of another bank in the same rank, another rank
+/// on the same channel and another channel respectively
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct TransferCosts {
+    pub bank: CompilingCost,
+    pub rank: CompilingCost,
+    pub channel: CompilingCost,
+}
+
+impl Default for TransferCosts {
+    fn default() -> Self {
+        let cost = |runtime, energy_consumption| CompilingCost { runtime, energy_consumption };
+        // via the global row buffer resp. the bus of the channel resp. the host
+        Self { bank: cost(200, 150), rank: cost(1000, 800), channel: cost(4000, 3000) }
+    }
+}
+
+/// Smallest unit of the [Hierarchy] two subarrays share
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum HierarchyLevel {
+    Subarray,
+    Bank,
+    Rank,
+    Channel,
+    /// The subarrays lie on different channels
+    System,
+}
+
+/// Position of a subarray in the [Hierarchy], every index being local to its parent
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SubarrayLocation {
+    pub channel: u64,
+    pub rank: u64,
+    pub bank: u64,
+    pub subarray: u64,
+}
+
+impl Hierarchy {
+    /// Hierarchy of a single bank holding all subarrays, which is what the architecture of the
+    /// PRADA paper models
+    pub fn single_bank(nr_subarrays: u64) -> Self {
+        Self {
+            nr_channels: 1,
+            ranks_per_channel: 1,
+            banks_per_rank: 1,
+            subarrays_per_bank: nr_subarrays,
+            transfer_costs: TransferCosts::default(),
+        }
+    }
+
+    pub fn nr_subarrays(&self) -> u64 {
+        self.nr_channels * self.ranks_per_channel * self.banks_per_rank * self.subarrays_per_bank
+    }
+
+    /// Location of `subarray`, which has to be smaller than [Self::nr_subarrays] (this is NOT
+    /// checked!)
+    pub fn locate(&self, subarray: SubarrayId) -> SubarrayLocation {
+        let bank = subarray.0 / self.subarrays_per_bank;
+        let rank = bank / self.banks_per_rank;
+        SubarrayLocation {
+            channel: rank / self.ranks_per_channel,
+            rank: rank % self.ranks_per_channel,
+            bank: bank % self.banks_per_rank,
+            subarray: subarray.0 % self.subarrays_per_bank,
+        }
+    }
+
+    /// Inverse of [Self::locate], `None` if the location lies outside of the hierarchy
+    pub fn subarray_id(&self, location: SubarrayLocation) -> Option<SubarrayId> {
+        if location.channel >= self.nr_channels
+            || location.rank >= self.ranks_per_channel
+            || location.bank >= self.banks_per_rank
+            || location.subarray >= self.subarrays_per_bank
+        {
+            return None;
+        }
+        let rank = location.channel * self.ranks_per_channel + location.rank;
+        let bank = rank * self.banks_per_rank + location.bank;
+        Some(SubarrayId(bank * self.subarrays_per_bank + location.subarray))
+    }
+
+    /// Smallest unit containing both subarrays
+    pub fn common_level(&self, a: SubarrayId, b: SubarrayId) -> HierarchyLevel {
+        let (a, b) = (self.locate(a), self.locate(b));
+        if a.channel != b.channel {
+            HierarchyLevel::System
+        } else if a.rank != b.rank {
+            HierarchyLevel::Channel
+        } else if a.bank != b.bank {
+            HierarchyLevel::Rank
+        } else if a.subarray != b.subarray {
+            HierarchyLevel::Bank
+        } else {
+            HierarchyLevel::Subarray
+        }
+    }
+
+    /// Additional cost of an instruction whose operands span the given level
+    pub fn level_cost(&self, level: HierarchyLevel) -> CompilingCost {
+        match level {
+            HierarchyLevel::Subarray | HierarchyLevel::Bank => CompilingCost { runtime: 0, energy_consumption: 0 },
+            HierarchyLevel::Rank => self.transfer_costs.bank,
+            HierarchyLevel::Channel => self.transfer_costs.rank,
+            HierarchyLevel::System => self.transfer_costs.channel,
+        }
+    }
+
+    /// Additional cost of the transfers between banks, ranks and channels when executing the
+    /// given instructions, taking into account that loop bodies are executed multiple times
+    pub fn transfer_cost(&self, instructions: &[Instruction]) -> CompilingCost {
+        let mut total = CompilingCost { runtime: 0, energy_consumption: 0 };
+        // nr of executions of the instructions of every currently open loop body
+        let mut executions = vec!(1u64);
+        for instruction in instructions {
+            let current = *executions.last().unwrap();
+            match instruction {
+                Instruction::LoopBegin(count) => executions.push(current * count),
+                Instruction::LoopEnd if executions.len() > 1 => {
+                    executions.pop();
+                }
+                _ => {
+                    let mut subarrays = instruction.used_addresses().map(|row| row.get_subarray_id());
+                    let Some(first) = subarrays.next() else {
+                        continue;
+                    };
+                    let level = subarrays.map(|other| self.common_level(first, other)).max();
+                    let cost = self.level_cost(level.unwrap_or(HierarchyLevel::Subarray));
+                    total.runtime += current * cost.runtime;
+                    total.energy_consumption += current * cost.energy_consumption;
+                }
+            }
+        }
+        total
+    }
+}
+
+impl Display for Hierarchy {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(
+            f,
+            "{} channel(s) x {} rank(s) x {} bank(s) x {} subarrays",
+            self.nr_channels, self.ranks_per_channel, self.banks_per_rank, self.subarrays_per_bank
+        )
+    }
+}
+
+/// Set of operations a DRAM module supports
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub struct Capabilities(u32);
+
+impl Capabilities {
+    /// Rows can be negated in place (see [Instruction::N](super::program::Instruction::N))
+    pub const NOT: Self = Self(1 << 0);
+    /// Dual-contact cell rows, see [PRADAArchitecture::nr_dcc_rows]
+    pub const DCC: Self = Self(1 << 1);
+    /// Rows can be copied between different subarrays, which is required for linking fragments
+    /// placed into different subarrays
+    pub const INTERSUBARRAY_ROWCLONE: Self = Self(1 << 2);
+    /// A mask row can gate row copies (see
+    /// [Instruction::MaskedRowCopy](super::program::Instruction::MaskedRowCopy)), which is used to
+    /// lower MUX structures
+    pub const MASKED_COPY: Self = Self(1 << 3);
+    /// Rows can be shifted between neighboring bitlines (see
+    /// [Instruction::ColumnShift](super::program::Instruction::ColumnShift)), which is required
+    /// for carry propagation in bit-serial arithmetic
+    pub const COLUMN_SHIFT: Self = Self(1 << 4);
+    /// Two rows can be XORed in-DRAM (see [Instruction::Xor](super::program::Instruction::Xor)),
+    /// which is used to lower the three-MAJ XOR/XNOR constructions of MIGs
+    pub const XOR: Self = Self(1 << 5);
+    /// A TRA leaves its result in all three activated rows instead of only in the first one
+    pub const TRA_RESULT_IN_ALL_ROWS: Self = Self(1 << 6);
+    /// Two rows can be ANDed resp. ORed in-DRAM (see
+    /// [Instruction::And](super::program::Instruction::And)), which is used to lower MAJs with a
+    /// constant operand instead of issuing a TRA with a constant row
+    pub const AND_OR: Self = Self(1 << 7);
+    /// Every subarray provides preset control rows holding 0s resp. 1s (Ambit's C0 and C1, see
+    /// [Instruction::ControlTra](super::program::Instruction::ControlTra)), which are used as third
+    /// operand of MAJs with a constant operand instead of a constant row
+    pub const CONTROL_ROWS: Self = Self(1 << 8);
+
+    const NAMES: [(Self, &'static str); 9] = [
+        (Self::NOT, "not"),
+        (Self::DCC, "dcc"),
+        (Self::INTERSUBARRAY_ROWCLONE, "intersubarray_rowclone"),
+        (Self::MASKED_COPY, "masked_copy"),
+        (Self::COLUMN_SHIFT, "column_shift"),
+        (Self::XOR, "xor"),
+        (Self::TRA_RESULT_IN_ALL_ROWS, "tra_result_in_all_rows"),
+        (Self::AND_OR, "and_or"),
+        (Self::CONTROL_ROWS, "control_rows"),
+    ];
+
+    /// Capabilities of the architecture described in the PRADA paper
+    pub const DEFAULT: Self = Self(Self::NOT.0 | Self::INTERSUBARRAY_ROWCLONE.0 | Self::TRA_RESULT_IN_ALL_ROWS.0);
+
+    pub const fn empty() -> Self {
+        Self(0)
+    }
+
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Capabilities contained in `self` but not in `other`
+    pub const fn difference(self, other: Self) -> Self {
+        Self(self.0 & !other.0)
+    }
+
+    pub fn insert(&mut self, other: Self) {
+        self.0 |= other.0;
+    }
+
+    pub fn remove(&mut self, other: Self) {
+        self.0 &= !other.0;
+    }
+}
+
+impl ops::BitOr for Capabilities {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl Display for Capabilities {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let names: Vec<&str> = Self::NAMES
+            .iter()
+            .filter(|(capability, _)| self.contains(*capability))
+            .map(|(_, name)| *name)
+            .collect();
+        write!(f, "{{{}}}", names.join(", "))
+    }
+}
+
+impl Debug for Capabilities {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "Capabilities{self}")
+    }
+}
+
 /// - ! must be smaller than `rows_per_subarray * nr_subarrays` (this is NOT checked!)
 #[derive(Copy, Clone, PartialEq, Eq, Hash)]
 pub struct RowAddress(pub u64);
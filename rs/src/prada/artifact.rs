@@ -0,0 +1,192 @@
+//! Serialized programs: the instructions and row maps of a [Program] in its textual form, preceded
+//! by a header identifying the compiler, the architecture (by its [architecture_fingerprint]), the
+//! settings and the rewrite rules the program has been compiled with, plus a hash of the content.
+//! Loading a program verifies the header, so that neither corrupted programs nor programs compiled
+//! for a different architecture or by a different compiler version are run.
+use std::fmt::Write;
+
+use super::architecture::{PRADAArchitecture, RowAddress, SubarrayId};
+use super::cost::InstructionClass;
+use super::program::{describe_init, ControlRow, Instruction, Program, RowInit};
+use super::CompilerSettings;
+
+/// Compiler serialized programs are tagged with
+pub const COMPILER_VERSION: &str = concat!(env!("CARGO_PKG_NAME"), " ", env!("CARGO_PKG_VERSION"));
+
+const MAGIC: &str = "# prada program";
+
+/// Header of a serialized program
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProgramMetadata {
+    pub compiler_version: String,
+    /// [architecture_fingerprint] of the architecture the program has been compiled for
+    pub architecture: u64,
+    /// [CompilerSettings::snapshot] of the settings the program has been compiled with
+    pub settings: String,
+    /// [CompilerSettings::rules_hash] of the settings the program has been compiled with
+    pub rules: u64,
+    /// Hash of the header (without the hash itself) and of the content
+    pub content_hash: u64,
+}
+
+/// FNV-1a, which (unlike the hashers of the std and of rustc-hash) is stable across releases
+pub(super) fn fnv1a(text: &str) -> u64 {
+    text.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3))
+}
+
+/// Hash of everything about `architecture` which affects compiled programs or their estimates
+pub fn architecture_fingerprint(architecture: &PRADAArchitecture) -> u64 {
+    let mut description = format!(
+        "{} {} {:?} {} {:?} {:?} {:?}",
+        architecture.nr_subarrays,
+        architecture.rows_per_subarray,
+        architecture.capabilities,
+        architecture.nr_dcc_rows,
+        architecture.hierarchy,
+        architecture.row_reliability,
+        architecture.verified_tra_groups,
+    );
+    for class in InstructionClass::ALL {
+        let cost = architecture.cost_model.cost(class);
+        write!(description, " {}={}/{}", class.name(), cost.runtime, cost.energy_consumption).unwrap();
+    }
+    fnv1a(&description)
+}
+
+/// Serializes `program`, compiled using `settings`, see [deserialize]
+pub fn serialize(program: &Program, settings: &CompilerSettings) -> String {
+    let header = format!(
+        "{MAGIC}\n# compiler: {COMPILER_VERSION}\n# architecture: {:016x}\n# settings: {}\n# rules: {:016x}\n",
+        architecture_fingerprint(program.architecture),
+        settings.snapshot(),
+        settings.rules_hash(),
+    );
+    let mut body = String::new();
+    for (row, init) in &program.input_map {
+        writeln!(body, "input {row} {}", describe_init(init)).unwrap();
+    }
+    for (row, init) in &program.persistent_rows.rows {
+        writeln!(body, "persistent {row} {}", describe_init(init)).unwrap();
+    }
+    for row in &program.output_map {
+        writeln!(body, "output {row}").unwrap();
+    }
+    write!(body, "{program}").unwrap();
+    format!("{header}# hash: {:016x}\n{body}", fnv1a(&(header.clone() + &body)))
+}
+
+/// Loads a program serialized by [serialize] for `architecture`, failing if it has been modified
+/// since, if it has been serialized by a different compiler version or if it has been compiled for
+/// a different architecture. The settings aren't checked, compare [ProgramMetadata::settings] to
+/// [CompilerSettings::snapshot] if they matter.
+pub fn deserialize<'a>(
+    text: &str,
+    architecture: &'a PRADAArchitecture,
+) -> Result<(Program<'a>, ProgramMetadata), &'static str> {
+    let mut lines = text.split_inclusive('\n');
+    let mut header = String::new();
+    let mut field = |name: &str| -> Result<String, &'static str> {
+        let line = lines.next().ok_or("incomplete header")?;
+        let value = line.trim_end().strip_prefix(name).ok_or("malformed header")?.to_string();
+        if name != "# hash: " {
+            header.push_str(line);
+        }
+        Ok(value)
+    };
+    if !field(MAGIC)?.is_empty() {
+        return Err("not a serialized program");
+    }
+    let compiler_version = field("# compiler: ")?;
+    let fingerprint = field("# architecture: ")?;
+    let settings = field("# settings: ")?;
+    let rules = field("# rules: ")?;
+    let hash = field("# hash: ")?;
+    let body: String = lines.collect();
+
+    let parse_hash = |hash: &str| u64::from_str_radix(hash, 16).map_err(|_| "malformed header");
+    let metadata = ProgramMetadata {
+        compiler_version,
+        architecture: parse_hash(&fingerprint)?,
+        settings,
+        rules: parse_hash(&rules)?,
+        content_hash: parse_hash(&hash)?,
+    };
+    if fnv1a(&(header + &body)) != metadata.content_hash {
+        return Err("content doesn't match its hash");
+    }
+    if metadata.compiler_version != COMPILER_VERSION {
+        return Err("program has been serialized by a different compiler version");
+    }
+    if metadata.architecture != architecture_fingerprint(architecture) {
+        return Err("program has been compiled for a different architecture");
+    }
+
+    let mut program = Program::new(architecture, vec!());
+    for line in body.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        let (keyword, operands) = line.split_once(' ').unwrap_or((line, ""));
+        let operands: Vec<&str> = operands.split_whitespace().collect();
+        let row = |index: usize| parse_row(operands.get(index).ok_or("missing operand")?, architecture);
+        let instruction = match (keyword, operands.len()) {
+            ("input", _) => {
+                program.input_map.push((row(0)?, parse_init(&operands[1..])?));
+                continue;
+            }
+            ("persistent", _) => {
+                program.persistent_rows.rows.push((row(0)?, parse_init(&operands[1..])?));
+                continue;
+            }
+            ("output", 1) => {
+                program.output_map.push(row(0)?);
+                continue;
+            }
+            ("AAPRowCopy", 2) => Instruction::AAPRowCopy(row(0)?, row(1)?),
+            ("AAPTRA", 3) => Instruction::AAPTRA(row(0)?, row(1)?, row(2)?),
+            ("N", 1) => Instruction::N(row(0)?),
+            ("MaskedRowCopy", 3) => Instruction::MaskedRowCopy(row(0)?, row(1)?, row(2)?),
+            ("ColumnShift", 2) => {
+                Instruction::ColumnShift(row(0)?, operands[1].parse().map_err(|_| "invalid shift offset")?)
+            }
+            ("Xor", 3) => Instruction::Xor(row(0)?, row(1)?, row(2)?),
+            ("And", 3) => Instruction::And(row(0)?, row(1)?, row(2)?),
+            ("Or", 3) => Instruction::Or(row(0)?, row(1)?, row(2)?),
+            ("ControlTra", 3) => {
+                let control = match operands[2] {
+                    "C0" => ControlRow::C0,
+                    "C1" => ControlRow::C1,
+                    _ => return Err("invalid control row"),
+                };
+                Instruction::ControlTra(row(0)?, row(1)?, control)
+            }
+            ("DccNot", 2) => Instruction::DccNot(row(0)?, row(1)?),
+            ("LoopBegin", 1) => Instruction::LoopBegin(operands[0].parse().map_err(|_| "invalid loop count")?),
+            ("LoopEnd", 0) => Instruction::LoopEnd,
+            _ => return Err("invalid instruction"),
+        };
+        program.instructions.push(instruction);
+    }
+    program.update_cost_estimates(&architecture.cost_model);
+    Ok((program, metadata))
+}
+
+/// Inverse of [describe_init], given its whitespace-separated words
+fn parse_init(words: &[&str]) -> Result<RowInit, &'static str> {
+    let index = |index: &str| index.parse().map_err(|_| "invalid input index");
+    match words {
+        ["input", input] => Ok(RowInit::Input { index: index(input)?, inverted: false }),
+        ["inverted", "input", input] => Ok(RowInit::Input { index: index(input)?, inverted: true }),
+        ["constant", "0"] => Ok(RowInit::Constant(false)),
+        ["constant", "1"] => Ok(RowInit::Constant(true)),
+        _ => Err("invalid row initialization"),
+    }
+}
+
+/// Parses a row in the `<subarray>.<row>` form of [RowAddress]'s `Display`
+fn parse_row(text: &str, architecture: &PRADAArchitecture) -> Result<RowAddress, &'static str> {
+    let (subarray, row) = text.split_once('.').ok_or("invalid row")?;
+    let subarray: u64 = subarray.parse().map_err(|_| "invalid row")?;
+    let row: u64 = row.parse().map_err(|_| "invalid row")?;
+    if subarray >= architecture.nr_subarrays || row >= architecture.rows_per_subarray {
+        return Err("row outside of the architecture");
+    }
+    Ok(RowAddress(row).local_rowaddress_to_subarray_id(SubarrayId(subarray)))
+}
@@ -0,0 +1,136 @@
+//! Binarized neural network (BNN) layers, whose activations and weights are `+1` (encoded as 1) or
+//! `-1` (encoded as 0): every neuron computes `popcount(XNOR(inputs, weights)) >= threshold`, which
+//! maps directly onto majority-based PIM.
+use std::fmt::{Display, Formatter};
+
+use super::architecture::{PRADAArchitecture, RowAddress};
+use super::error::CompileError;
+use super::network::MigNetwork;
+use super::program::{Program, RowInit};
+use super::{compile, CompilerSettings};
+use eggmock::Signal;
+
+/// Fully connected binarized layer with fixed weights
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BinaryLayer {
+    /// `weights[j][i]` is the weight of input `i` for neuron `j`
+    pub weights: Vec<Vec<bool>>,
+    /// Neuron `j` fires if at least `thresholds[j]` of its inputs agree with its weights
+    pub thresholds: Vec<u64>,
+}
+
+impl BinaryLayer {
+    pub fn new(weights: Vec<Vec<bool>>, thresholds: Vec<u64>) -> Result<Self, &'static str> {
+        if weights.len() != thresholds.len() {
+            return Err("expected one threshold per neuron");
+        }
+        if weights.windows(2).any(|neurons| neurons[0].len() != neurons[1].len()) {
+            return Err("all neurons need the same nr of weights");
+        }
+        Ok(Self { weights, thresholds })
+    }
+
+    pub fn input_width(&self) -> usize {
+        self.weights.first().map_or(0, Vec::len)
+    }
+
+    pub fn output_width(&self) -> usize {
+        self.weights.len()
+    }
+
+    /// Network with input `i` being activation `i` and output `j` being neuron `j`. As the weights
+    /// are fixed, the XNORs reduce to (possibly inverted) inputs.
+    pub fn network(&self) -> MigNetwork {
+        let mut network = MigNetwork::new();
+        let inputs: Vec<Signal> = (0..self.input_width()).map(|_| network.add_input()).collect();
+        for (weights, threshold) in self.weights.iter().zip(&self.thresholds) {
+            let agreements: Vec<Signal> = inputs
+                .iter()
+                .zip(weights)
+                .map(|(input, weight)| input.maybe_invert(!weight))
+                .collect();
+            let count = network.popcount(&agreements);
+            let fires = at_least(&mut network, &count, *threshold);
+            network.add_output(fires);
+        }
+        network
+    }
+
+    /// Compiles the layer through rewriting, extraction and compilation, evaluating as many samples
+    /// as there are bitlines at once (one sample per bitline, one activation per row)
+    pub fn compile<'a>(
+        &self,
+        architecture: &'a PRADAArchitecture,
+        settings: CompilerSettings,
+    ) -> Result<CompiledLayer<'a>, CompileError> {
+        let program = compile(architecture, &self.network(), settings)?;
+        let layout = LayerLayout::of(&program, self.input_width());
+        Ok(CompiledLayer { program, layout })
+    }
+}
+
+/// `number >= threshold` for a binary number (least significant bit first), evaluated from the
+/// least significant bit: `number[..=k] >= threshold[..=k]` iff bit `k` exceeds the threshold bit
+/// or both are equal and `number[..k] >= threshold[..k]`
+fn at_least(network: &mut MigNetwork, number: &[Signal], threshold: u64) -> Signal {
+    if number.len() < 64 && threshold >> number.len() != 0 {
+        return network.constant(false);
+    }
+    let mut greater_equal = network.constant(true);
+    for (k, bit) in number.iter().enumerate() {
+        greater_equal = if threshold & (1 << k) != 0 {
+            network.and(*bit, greater_equal)
+        } else {
+            network.or(*bit, greater_equal)
+        };
+    }
+    greater_equal
+}
+
+/// Where the host has to place the activations of a [CompiledLayer] and where it finds the results
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LayerLayout {
+    /// Rows of activation `i`, together with whether they store it inverted
+    pub inputs: Vec<Vec<(RowAddress, bool)>>,
+    /// Row of neuron `j`
+    pub outputs: Vec<RowAddress>,
+    /// Rows to be filled with 0s (`false`) or 1s (`true`)
+    pub constants: Vec<(RowAddress, bool)>,
+}
+
+impl LayerLayout {
+    fn of(program: &Program, input_width: usize) -> Self {
+        let mut layout = Self { inputs: vec!(vec!(); input_width), outputs: program.output_map.clone(), constants: vec!() };
+        for (row, init) in &program.input_map {
+            match *init {
+                RowInit::Input { index, inverted } => layout.inputs[index as usize].push((*row, inverted)),
+                RowInit::Constant(value) => layout.constants.push((*row, value)),
+            }
+        }
+        layout
+    }
+}
+
+impl Display for LayerLayout {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (idx, rows) in self.inputs.iter().enumerate() {
+            for (row, inverted) in rows {
+                writeln!(f, "activation {idx}{}: {row}", if *inverted { " (inverted)" } else { "" })?;
+            }
+        }
+        for (row, value) in &self.constants {
+            writeln!(f, "constant {}: {row}", *value as u8)?;
+        }
+        for (idx, row) in self.outputs.iter().enumerate() {
+            writeln!(f, "neuron {idx}: {row}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Program of a [BinaryLayer] together with its [LayerLayout]
+#[derive(Debug, Clone)]
+pub struct CompiledLayer<'a> {
+    pub program: Program<'a>,
+    pub layout: LayerLayout,
+}
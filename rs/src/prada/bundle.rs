@@ -0,0 +1,200 @@
+//! Bundles of several named programs (kernels) compiled for the same architecture, together with
+//! the contracts describing how values are handed over between them, which are managed and shipped
+//! as a single artifact (a directory or a tar archive).
+use std::fmt::Write as _;
+use std::io::{self, Write};
+use std::path::Path;
+
+use super::architecture::{PRADAArchitecture, RowAddress};
+use super::program::{Program, RowInit};
+
+/// A program inside a [Bundle]
+#[derive(Debug, Clone)]
+pub struct Kernel<'a> {
+    pub name: String,
+    pub program: Program<'a>,
+}
+
+/// Output `output` of kernel `from` is consumed as input `input` of kernel `to`, which runs later
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Handoff {
+    pub from: String,
+    pub output: usize,
+    pub to: String,
+    pub input: u64,
+}
+
+/// Row copy (negated if `inverted` is set) the host has to perform to fulfill a [Handoff]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct HandoffCopy {
+    pub from: RowAddress,
+    pub to: RowAddress,
+    pub inverted: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct Bundle<'a> {
+    pub architecture: &'a PRADAArchitecture,
+    /// In execution order
+    pub kernels: Vec<Kernel<'a>>,
+    pub handoffs: Vec<Handoff>,
+}
+
+impl<'a> Bundle<'a> {
+    pub fn new(architecture: &'a PRADAArchitecture) -> Self {
+        Self { architecture, kernels: vec!(), handoffs: vec!() }
+    }
+
+    /// Appends a kernel, which has to be compiled for the architecture of the bundle
+    pub fn add(&mut self, name: impl Into<String>, program: Program<'a>) -> Result<(), &'static str> {
+        let name = name.into();
+        if !std::ptr::eq(program.architecture, self.architecture) {
+            return Err("kernel has been compiled for a different architecture");
+        }
+        if self.kernel(&name).is_some() {
+            return Err("kernel names have to be unique");
+        }
+        self.kernels.push(Kernel { name, program });
+        Ok(())
+    }
+
+    pub fn kernel(&self, name: &str) -> Option<&Kernel<'a>> {
+        self.kernels.iter().find(|kernel| kernel.name == name)
+    }
+
+    /// Declares a [Handoff] from output `output` of kernel `from` to input `input` of kernel `to`
+    pub fn connect(&mut self, from: &str, output: usize, to: &str, input: u64) -> Result<(), &'static str> {
+        let position = |name: &str| self.kernels.iter().position(|kernel| kernel.name == name);
+        let from_idx = position(from).ok_or("unknown source kernel")?;
+        let to_idx = position(to).ok_or("unknown target kernel")?;
+        if from_idx >= to_idx {
+            return Err("handoffs have to go to a later kernel");
+        }
+        if output >= self.kernels[from_idx].program.output_map.len() {
+            return Err("source kernel has no such output");
+        }
+        self.handoffs.push(Handoff { from: from.to_string(), output, to: to.to_string(), input });
+        Ok(())
+    }
+
+    /// Copies fulfilling the given handoff, omitting copies of a row onto itself. An input without
+    /// rows (e.g. because it has been optimized away) requires no copies.
+    pub fn handoff_copies(&self, handoff: &Handoff) -> Result<Vec<HandoffCopy>, &'static str> {
+        let from = self.kernel(&handoff.from).ok_or("unknown source kernel")?;
+        let to = self.kernel(&handoff.to).ok_or("unknown target kernel")?;
+        let source = *from.program.output_map.get(handoff.output).ok_or("source kernel has no such output")?;
+        Ok(to
+            .program
+            .input_map
+            .iter()
+            .filter_map(|(row, init)| match *init {
+                RowInit::Input { index, inverted } if index == handoff.input => {
+                    Some(HandoffCopy { from: source, to: *row, inverted })
+                }
+                _ => None,
+            })
+            .filter(|copy| copy.inverted || copy.from != copy.to)
+            .collect())
+    }
+
+    /// File name of the given kernel's program inside the bundle
+    pub fn program_file(kernel: &Kernel) -> String {
+        format!("{}.prada", kernel.name)
+    }
+
+    /// Describes the architecture, the kernels in execution order with their row layout and the
+    /// handoffs to perform between them
+    pub fn manifest(&self) -> Result<String, &'static str> {
+        let mut out = String::new();
+        let architecture = self.architecture;
+        writeln!(out, "[architecture]").unwrap();
+        writeln!(out, "subarrays: {}", architecture.nr_subarrays).unwrap();
+        writeln!(out, "hierarchy: {}", architecture.hierarchy).unwrap();
+        writeln!(out, "rows per subarray: {}", architecture.rows_per_subarray).unwrap();
+        writeln!(out, "capabilities: {}", architecture.capabilities).unwrap();
+        if let Some(provenance) = &architecture.cost_model.provenance {
+            writeln!(out, "cost model: {}", provenance.source).unwrap();
+        }
+        for kernel in &self.kernels {
+            writeln!(out, "\n[kernel {}]", kernel.name).unwrap();
+            writeln!(out, "program: {}", Self::program_file(kernel)).unwrap();
+            writeln!(
+                out,
+                "estimates: {} ns, {} mJ/KOps",
+                kernel.program.runtime_estimate, kernel.program.energy_consumption_estimate
+            )
+            .unwrap();
+            write!(out, "{}", kernel.program.layout()).unwrap();
+        }
+        for handoff in &self.handoffs {
+            writeln!(
+                out,
+                "\n[handoff {}.output {} -> {}.input {}]",
+                handoff.from, handoff.output, handoff.to, handoff.input
+            )
+            .unwrap();
+            for copy in self.handoff_copies(handoff)? {
+                writeln!(out, "copy {} -> {}{}", copy.from, copy.to, if copy.inverted { " (negated)" } else { "" }).unwrap();
+            }
+        }
+        Ok(out)
+    }
+
+    /// Files of the bundle: the manifest (`manifest.txt`) and the program of every kernel
+    fn files(&self) -> Result<Vec<(String, String)>, &'static str> {
+        let mut files = vec!(("manifest.txt".to_string(), self.manifest()?));
+        files.extend(
+            self.kernels
+                .iter()
+                .map(|kernel| (Self::program_file(kernel), kernel.program.to_string())),
+        );
+        Ok(files)
+    }
+
+    /// Writes all files of the bundle into `dir`
+    pub fn write_to_dir(&self, dir: &Path) -> io::Result<()> {
+        std::fs::create_dir_all(dir)?;
+        for (name, content) in self.files().map_err(io::Error::other)? {
+            std::fs::write(dir.join(name), content)?;
+        }
+        Ok(())
+    }
+
+    /// Writes all files of the bundle as (ustar) tar archive
+    pub fn write_archive(&self, mut out: impl Write) -> io::Result<()> {
+        for (name, content) in self.files().map_err(io::Error::other)? {
+            if name.len() > 100 {
+                return Err(io::Error::other("kernel name too long for tar archive"));
+            }
+            out.write_all(&tar_header(&name, content.len() as u64))?;
+            out.write_all(content.as_bytes())?;
+            out.write_all(&vec!(0; padding(content.len())))?;
+        }
+        // end of archive
+        out.write_all(&[0; 1024])
+    }
+}
+
+fn padding(len: usize) -> usize {
+    (512 - len % 512) % 512
+}
+
+fn tar_header(name: &str, size: u64) -> [u8; 512] {
+    let mut header = [0u8; 512];
+    let mut field = |offset: usize, value: &[u8]| header[offset..offset + value.len()].copy_from_slice(value);
+    field(0, name.as_bytes());
+    field(100, b"0000644\0");
+    field(108, b"0000000\0");
+    field(116, b"0000000\0");
+    field(124, format!("{size:011o}\0").as_bytes());
+    field(136, b"00000000000\0");
+    // regular file
+    field(156, b"0");
+    field(257, b"ustar\0");
+    field(263, b"00");
+    // the checksum is computed with the checksum field filled with spaces
+    header[148..156].copy_from_slice(b"        ");
+    let checksum: u32 = header.iter().map(|byte| *byte as u32).sum();
+    header[148..156].copy_from_slice(format!("{checksum:06o}\0 ").as_bytes());
+    header
+}
@@ -0,0 +1,137 @@
+//! On-disk cache of compiled programs, keyed by a hash of the network, of the architecture (its
+//! [architecture_fingerprint]) and of the [settings](CompilerSettings::snapshot), so that
+//! recompiling the same network, e.g. when rerunning experiments, returns the cached program
+//! instead of compiling it again.
+//!
+//! Programs are stored [serialized](super::artifact), preceded by the hash of their network, hence
+//! cached programs only contain what serialized programs contain: their instructions, row maps and
+//! cost estimates, but e.g. neither their diagnostics nor their decisions. Cache entries which can't
+//! be loaded (e.g. since they have been written by a different compiler version) are treated as
+//! missing and replaced.
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+use eggmock::{Mig, Network};
+use rustc_hash::FxHashMap;
+
+use super::architecture::PRADAArchitecture;
+use super::artifact::{architecture_fingerprint, deserialize, fnv1a, serialize};
+use super::compilation::reachable_nodes;
+use super::error::CompileError;
+use super::program::Program;
+use super::{compile, CompilerSettings};
+
+/// Prefix of the first line of cache entries, which holds the [network_hash] of the network
+const NETWORK_PREFIX: &str = "# network: ";
+
+#[derive(Debug, Clone)]
+pub struct CompilationCache {
+    dir: PathBuf,
+}
+
+impl CompilationCache {
+    /// Cache storing its programs in `dir`, which is created once the first program is stored
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Key of the program compiled from `network` for `architecture` using `settings`
+    pub fn key(
+        architecture: &PRADAArchitecture,
+        network: &impl Network<Node = Mig>,
+        settings: &CompilerSettings,
+    ) -> u64 {
+        fnv1a(&format!(
+            "{:016x} {:016x} {}",
+            network_hash(network),
+            architecture_fingerprint(architecture),
+            settings.snapshot()
+        ))
+    }
+
+    /// File the program with the given `key` is stored in
+    pub fn path(&self, key: u64) -> PathBuf {
+        self.dir.join(format!("{key:016x}.prada"))
+    }
+
+    /// The cached program compiled from `network` for `architecture` using `settings`, if any
+    pub fn lookup<'a>(
+        &self,
+        architecture: &'a PRADAArchitecture,
+        network: &impl Network<Node = Mig>,
+        settings: &CompilerSettings,
+    ) -> Option<Program<'a>> {
+        let text = std::fs::read_to_string(self.path(Self::key(architecture, network, settings))).ok()?;
+        let (hash, serialized) = text.strip_prefix(NETWORK_PREFIX)?.split_once('\n')?;
+        let (program, metadata) = deserialize(serialized, architecture).ok()?;
+        // guards against collisions of the keys
+        let network_matches = u64::from_str_radix(hash, 16) == Ok(network_hash(network));
+        (network_matches && metadata.settings == settings.snapshot()).then_some(program)
+    }
+
+    /// Stores `program`, compiled from `network` using `settings`, replacing any cached program
+    pub fn store(
+        &self,
+        program: &Program,
+        network: &impl Network<Node = Mig>,
+        settings: &CompilerSettings,
+    ) -> std::io::Result<()> {
+        let path = self.path(Self::key(program.architecture, network, settings));
+        let entry = format!("{NETWORK_PREFIX}{:016x}\n{}", network_hash(network), serialize(program, settings));
+        // written to a temporary file first, so that concurrent compilations never read partial
+        // programs
+        let temporary = path.with_extension(format!("{}.tmp", std::process::id()));
+        std::fs::create_dir_all(&self.dir)
+            .and_then(|_| std::fs::write(&temporary, entry))
+            .and_then(|_| std::fs::rename(&temporary, &path))
+    }
+
+    /// Same as [compile], but returns the cached program if there is one and [stores](Self::store)
+    /// the compiled program otherwise. The program is returned even if storing it failed, along
+    /// with the result of storing it (which is `Ok` for cached programs).
+    pub fn compile<'a>(
+        &self,
+        architecture: &'a PRADAArchitecture,
+        network: &impl Network<Node = Mig>,
+        settings: CompilerSettings,
+    ) -> Result<(Program<'a>, std::io::Result<()>), CompileError> {
+        if let Some(program) = self.lookup(architecture, network, &settings) {
+            return Ok((program, Ok(())));
+        }
+        let program = compile(architecture, network, settings)?;
+        let stored = self.store(&program, network, &settings);
+        Ok((program, stored))
+    }
+}
+
+/// Hash of the structure of `network`, independent of the ids of its nodes (but not of the
+/// order of the operands of MAJs)
+pub fn network_hash(network: &impl Network<Node = Mig>) -> u64 {
+    let mut description = String::new();
+    let mut indices: FxHashMap<_, usize> = FxHashMap::default();
+    // `reachable_nodes` lists users before their inputs
+    for id in reachable_nodes(network).into_iter().rev() {
+        match network.node(id) {
+            Mig::False => description.push_str("f;"),
+            Mig::Input(index) => write!(description, "i{index};").unwrap(),
+            Mig::Maj(operands) => {
+                description.push('m');
+                for operand in operands {
+                    let inverted = if operand.is_inverted() { "!" } else { "" };
+                    write!(description, " {inverted}{}", indices[&operand.node_id()]).unwrap();
+                }
+                description.push(';');
+            }
+        }
+        indices.insert(id, indices.len());
+    }
+    for output in network.outputs() {
+        let inverted = if output.is_inverted() { "!" } else { "" };
+        write!(description, "o{inverted}{};", indices[&output.node_id()]).unwrap();
+    }
+    fnv1a(&description)
+}
@@ -0,0 +1,121 @@
+//! Bookkeeping of the nodes which are ready to be computed. Candidates are kept ordered by their
+//! [CandidateKey], so choosing the next node takes logarithmic instead of linear time. The only
+//! part of the key changing during compilation is the nr of operands missing in the required
+//! polarity, which is updated for the users of the values inserted into or removed from the
+//! [ValueStates] since the last node has been chosen.
+use std::cmp::Reverse;
+use std::collections::BTreeSet;
+
+use eggmock::{Id, Mig, Signal};
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use super::architecture::RowAddress;
+use super::dense::{node_id, node_index, SignalMap};
+
+/// Rank of a candidate, the least one is computed next: candidates are ranked by the nr of
+/// operands missing in the required polarity, then by the earliest deadline of the outputs
+/// depending on them (see [cone_deadlines](super::timing::cone_deadlines)), then by their priority
+/// (higher first, see [SchedulingPolicy](super::SchedulingPolicy)), then by their nr of users and
+/// finally outputs first
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct CandidateKey {
+    pub missing_operands: u8,
+    /// `u64::MAX` for candidates no output with a deadline depends on
+    pub deadline: u64,
+    pub priority: Reverse<usize>,
+    pub users: usize,
+    pub not_output: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CandidateQueue {
+    /// Candidates by rank, with the node index breaking ties
+    queue: BTreeSet<(CandidateKey, usize)>,
+    candidates: FxHashMap<Id, (CandidateKey, Mig)>,
+}
+
+impl CandidateQueue {
+    pub fn len(&self) -> usize {
+        self.candidates.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.candidates.is_empty()
+    }
+
+    /// Key and node of the candidate `id`
+    pub fn get(&self, id: Id) -> Option<(CandidateKey, Mig)> {
+        self.candidates.get(&id).copied()
+    }
+
+    /// Candidate with the least key
+    pub fn first(&self) -> Option<(Id, CandidateKey, Mig)> {
+        let &(key, index) = self.queue.first()?;
+        let id = node_id(index);
+        Some((id, key, self.candidates[&id].1))
+    }
+
+    /// Adds `id` as candidate or updates its key
+    pub fn insert(&mut self, id: Id, node: Mig, key: CandidateKey) {
+        if let Some((previous, _)) = self.candidates.insert(id, (key, node)) {
+            self.queue.remove(&(previous, node_index(id)));
+        }
+        self.queue.insert((key, node_index(id)));
+    }
+
+    /// Removes the candidate `id`, returning whether it has been a candidate
+    pub fn remove(&mut self, id: Id) -> bool {
+        match self.candidates.remove(&id) {
+            Some((key, _)) => {
+                self.queue.remove(&(key, node_index(id)));
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Row in which each value is stored, recording the nodes whose values have been inserted or
+/// removed so that the keys of their users can be updated
+#[derive(Debug, Clone, Default)]
+pub struct ValueStates {
+    rows: SignalMap<RowAddress>,
+    changed: FxHashSet<Id>,
+}
+
+impl ValueStates {
+    pub fn new(rows: FxHashMap<Signal, RowAddress>) -> Self {
+        let mut states = Self::default();
+        for (signal, row) in rows {
+            states.rows.insert(signal, row);
+        }
+        states
+    }
+
+    pub fn get(&self, signal: &Signal) -> Option<&RowAddress> {
+        self.rows.get(signal)
+    }
+
+    pub fn contains_key(&self, signal: &Signal) -> bool {
+        self.rows.contains_key(signal)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Signal, RowAddress)> + '_ {
+        self.rows.iter().map(|(signal, row)| (signal, *row))
+    }
+
+    pub fn insert(&mut self, signal: Signal, row: RowAddress) -> Option<RowAddress> {
+        self.changed.insert(signal.node_id());
+        self.rows.insert(signal, row)
+    }
+
+    pub fn remove(&mut self, signal: &Signal) -> Option<RowAddress> {
+        self.changed.insert(signal.node_id());
+        self.rows.remove(signal)
+    }
+
+    /// Returns the nodes whose values have been inserted or removed since the last call
+    pub fn take_changed(&mut self) -> FxHashSet<Id> {
+        std::mem::take(&mut self.changed)
+    }
+}
@@ -0,0 +1,118 @@
+//! Combinational equivalence checking (CEC) of two networks using a SAT solver, e.g. of the network
+//! chosen by the extractor against the network before rewriting (see
+//! [CompilerSettings::verify_rewrite](super::CompilerSettings::verify_rewrite)), without exporting
+//! both and round-tripping through ABC. Compiled programs are checked against their network by
+//! evaluating them symbolically, see [verify_program].
+//!
+//! Both networks are encoded into CNF (Tseitin) sharing the variables of their inputs, the miter
+//! asserts that at least one pair of outputs differs. If that is unsatisfiable, the networks are
+//! equivalent, otherwise the model is an input assignment distinguishing them.
+use eggmock::{Id, Mig, Network, Signal};
+use rustc_hash::FxHashMap;
+use varisat::{ExtendFormula, Lit, Solver};
+
+use super::compilation::reachable_nodes;
+use super::program::Program;
+use super::symbolic::evaluate_program;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Equivalence {
+    Equivalent,
+    /// Value of every input (by index) for which the outputs of the networks differ
+    Counterexample(Vec<bool>),
+}
+
+/// Checks whether `a` and `b` compute the same function, matching inputs by index and outputs by
+/// position
+pub fn check_equivalence(
+    a: &impl Network<Node = Mig>,
+    b: &impl Network<Node = Mig>,
+) -> Result<Equivalence, &'static str> {
+    if a.outputs().count() != b.outputs().count() {
+        return Err("networks have different nrs of outputs");
+    }
+    let mut encoder = Encoder::default();
+    let outputs_a = encoder.encode(a);
+    let outputs_b = encoder.encode(b);
+
+    // d_i <-> (a_i xor b_i), at least one d_i
+    let mut differences = vec!();
+    for (x, y) in outputs_a.into_iter().zip(outputs_b) {
+        let d = encoder.solver.new_lit();
+        encoder.solver.add_clause(&[!d, x, y]);
+        encoder.solver.add_clause(&[!d, !x, !y]);
+        encoder.solver.add_clause(&[d, !x, y]);
+        encoder.solver.add_clause(&[d, x, !y]);
+        differences.push(d);
+    }
+    encoder.solver.add_clause(&differences);
+
+    if !encoder.solver.solve().map_err(|_| "SAT solver failed")? {
+        return Ok(Equivalence::Equivalent);
+    }
+    let model = encoder.solver.model().ok_or("SAT solver returned no model")?;
+    let nr_inputs = encoder.inputs.keys().map(|index| index + 1).max().unwrap_or(0);
+    let values = (0..nr_inputs)
+        .map(|index| encoder.inputs.get(&index).is_some_and(|input| model.contains(input)))
+        .collect();
+    Ok(Equivalence::Counterexample(values))
+}
+
+/// Checks whether running `program` computes the outputs of `network` for all inputs. Unlike
+/// simulation this covers networks with too many inputs for exhaustive simulation, but not
+/// programs communicating between bitlines.
+pub fn verify_program(network: &impl Network<Node = Mig>, program: &Program) -> Result<Equivalence, &'static str> {
+    check_equivalence(network, &evaluate_program(program)?.network)
+}
+
+#[derive(Default)]
+struct Encoder {
+    solver: Solver<'static>,
+    /// Variables of the inputs, shared by both networks
+    inputs: FxHashMap<u64, Lit>,
+    constant: Option<Lit>,
+}
+
+impl Encoder {
+    /// Encodes the nodes reachable from the outputs of `network`, returning the literals of the
+    /// outputs
+    fn encode(&mut self, network: &impl Network<Node = Mig>) -> Vec<Lit> {
+        let mut lits: FxHashMap<Id, Lit> = FxHashMap::default();
+        let lit = |lits: &FxHashMap<Id, Lit>, signal: Signal| {
+            let lit = lits[&signal.node_id()];
+            if signal.is_inverted() {
+                !lit
+            } else {
+                lit
+            }
+        };
+        // `reachable_nodes` lists users before their inputs
+        for id in reachable_nodes(network).into_iter().rev() {
+            let node_lit = match network.node(id) {
+                Mig::False => self.constant_false(),
+                Mig::Input(index) => *self.inputs.entry(index).or_insert_with(|| self.solver.new_lit()),
+                Mig::Maj(operands) => {
+                    let [a, b, c] = operands.map(|operand| lit(&lits, operand));
+                    let m = self.solver.new_lit();
+                    for (x, y) in [(a, b), (a, c), (b, c)] {
+                        self.solver.add_clause(&[!x, !y, m]);
+                        self.solver.add_clause(&[x, y, !m]);
+                    }
+                    m
+                }
+            };
+            lits.insert(id, node_lit);
+        }
+        network.outputs().map(|output| lit(&lits, output)).collect()
+    }
+
+    fn constant_false(&mut self) -> Lit {
+        if let Some(constant) = self.constant {
+            return constant;
+        }
+        let constant = self.solver.new_lit();
+        self.solver.add_clause(&[!constant]);
+        self.constant = Some(constant);
+        constant
+    }
+}
@@ -1,40 +1,199 @@
 use super::{
     architecture::{PRADAArchitecture},
+    timing::cone_deadlines,
 };
-use crate::prada::{architecture::{RowAddress, ARCHITECTURE, ROWS_PER_SUBARRAY}, extraction::CompilingCost, program::{Instruction, Program}};
-use eggmock::{Id, Mig, NetworkWithBackwardEdges, Node, Signal};
+use crate::prada::{architecture::{Capabilities, RowAddress, SubarrayId, ROW_ID_BITMASK}, candidates::{CandidateKey, CandidateQueue, ValueStates}, constants::ConstantRows, dense::{NodeMap, SignalMap}, coverage::{self, CodePath}, decisions::{CopyReason, Decision, Replay, ReplayReport}, diagnostics::Strictness, error::CompileError, metrics::AllocatorMetrics, program::{AllocationStatistics, ControlRow, Instruction, PersistentRows, Program, RowInit}};
+use eggmock::{Id, Mig, Network, NetworkWithBackwardEdges, Node, Signal};
 use rustc_hash::{FxHashMap, FxHashSet};
+use std::cmp::Reverse;
 use std::collections::HashMap;
 
-/// Stores the current state of a row at a concrete compilations step
-#[derive(Default)] // by default not a compute_row, no live-value and no constant inside row
-pub struct RowState {
-    /// `compute_rows` are reservered rows which solely exist for performing computations, see [`Compiler::compute_row_activations`]
-    is_compute_row: bool,
-    /// `None` if the value inside this row is currently not live
-    live_value: Option<Signal>,
-    /// Mostly 0s/1s (for initializing reference subarray), see [`CompilationState::constant_values`]
-    constant: Option<usize>,
-}
-
 pub struct CompilationState<'n, N> {
-    /// For each row in the dram-module store its state (whether it's a compute row or if not whether/which value is stored inside it
-    dram_state: HashMap<RowAddress, RowState>,
-    /// For each subarray it stores the row in which the `Signal` is located
+    /// Value stored in each occupied row. Only the live value is kept per row, as this map and
+    /// `value_states` are accessed for every operand; rarely needed information about rows is
+    /// kept apart (e.g. [Self::input_map], [Self::placed_rows]).
+    dram_state: FxHashMap<RowAddress, Signal>,
+    /// For each subarray it stores the row in which the `Signal` is located, recording changes for
+    /// updating the keys of the candidates
     /// Assumption: currently only uses a single subarray
-    value_states: HashMap<Signal, RowAddress>,
+    value_states: ValueStates,
     /// For each Subarray store which rows are free (and hence can be used for storing values)
     /// - for now we'll limit ourselves to a single subarray
     free_rows_per_subarray: Vec<RowAddress>,
+    /// Rows which have to be initialized by the host (inputs and constants)
+    input_map: Vec<(RowAddress, RowInit)>,
 
     network: &'n N,
     program: Vec<Instruction>,
     /// contains all not yet computed network nodes that can be immediately computed (i.e. all
     /// inputs of the node are already computed)
-    candidates: FxHashSet<(Id, Mig)>,
+    candidates: CandidateQueue,
 
     outputs: FxHashSet<Id>,
-    leftover_use_count: FxHashMap<Id, usize>,
+    leftover_use_count: NodeMap<usize>,
+
+    /// MUX structures which are lowered onto masked row copies, by id of their root (OR) node
+    muxes: FxHashMap<Id, Mux>,
+    /// XOR/XNOR structures which are lowered onto [Instruction::Xor], by id of their root node
+    xors: FxHashMap<Id, XorGate>,
+    /// MAJs with a constant operand which are lowered onto [Instruction::And] resp.
+    /// [Instruction::Or], see [Capabilities::AND_OR]
+    and_ors: FxHashMap<Id, AndOrGate>,
+    /// Maps the nodes absorbed into a MUX or XOR to the id of its root node
+    absorbed_by: FxHashMap<Id, Id>,
+    /// Nodes in the order in which they have been computed
+    schedule: Vec<Id>,
+    /// Rows prescribed by the user for some signals, see [compile_with_placement]. These rows are
+    /// never allocated to other values.
+    placement: FxHashMap<Signal, RowAddress>,
+    /// Rows of [Self::placement], for checking whether a row is reserved without scanning it
+    placed_rows: FxHashSet<RowAddress>,
+    /// Rows which are never overwritten nor reused, see [CompileOptions::pin_inputs]
+    pinned_rows: FxHashSet<RowAddress>,
+    /// Capabilities of the architecture compiled for
+    capabilities: Capabilities,
+    /// Negated copies are created by [Instruction::DccNot] instead of a copy followed by an
+    /// [Instruction::N], since the architecture provides DCC rows
+    dcc_not: bool,
+    /// Values which have been evacuated into the spill subarray (see
+    /// [CompileOptions::spill_subarray]) and the row they're stored in there
+    spilled: FxHashMap<Signal, RowAddress>,
+    /// Unused rows of the spill subarray
+    free_spill_rows: Vec<RowAddress>,
+    /// Index of the instruction which last accessed a value, used for choosing the value which is
+    /// spilled
+    last_access: SignalMap<usize>,
+    /// Rows holding the operands of the operation currently being compiled, which must not be
+    /// spilled
+    protected_rows: FxHashSet<RowAddress>,
+    /// See [CompileOptions::rematerialize]
+    rematerialize: bool,
+    /// See [CompileOptions::scratch_row_budget]
+    scratch_row_budget: Option<u64>,
+    /// Values which have been dropped to be recomputed once needed again, with the operands they
+    /// are recomputed from
+    dropped: FxHashMap<Signal, [Signal; 3]>,
+    /// For each node the nr of dropped values which have to be recomputed from it. These nodes are
+    /// kept alive even if they aren't used by the network anymore.
+    remat_uses: FxHashMap<Id, usize>,
+    /// Nodes which aren't used by the network anymore, but only discarded once no dropped value
+    /// depends on them
+    deferred_discards: FxHashSet<Id>,
+    allocation: AllocationStatistics,
+    /// Priority of each node according to the [SchedulingPolicy], candidates with higher priority
+    /// are computed first (if all of their operands are present)
+    priorities: FxHashMap<Id, usize>,
+    /// Earliest deadline of the outputs depending on each node, see [cone_deadlines]
+    deadlines: FxHashMap<Id, u64>,
+    /// Decisions taken so far, if [CompileOptions::log_decisions] is set
+    decisions: Option<Vec<Decision>>,
+    /// Recorded decisions to follow, see [compile_replaying]
+    replay: Option<Replay>,
+    /// Metrics of the row allocator, if [CompileOptions::allocator_metrics] is set
+    metrics: Option<AllocatorMetrics>,
+    /// See [PRADAArchitecture::row_reliability]
+    row_reliability: Vec<u32>,
+    /// Nodes on deep dependency chains, whose values are preferably stored in reliable rows (only
+    /// determined if the architecture has reliability weights)
+    deep_nodes: FxHashSet<Id>,
+    /// Whether the node currently being compiled is one of [Self::deep_nodes]
+    allocating_deep: bool,
+}
+
+/// `a ^ b` (or `!(a ^ b)` if `inverted`), found in the network as `AND(OR(a, b), !AND(a, b))`
+/// resp. `OR(!OR(a, b), AND(a, b))`
+#[derive(Copy, Clone, Debug)]
+pub struct XorGate {
+    a: Signal,
+    b: Signal,
+    inverted: bool,
+    /// The OR and AND node which are not computed explicitly when lowering the XOR
+    inner_nodes: [Id; 2],
+}
+
+/// `a & b` (or `a | b` if `or`), found in the network as a MAJ with a constant 0 (resp. 1) operand
+#[derive(Copy, Clone, Debug)]
+pub struct AndOrGate {
+    a: Signal,
+    b: Signal,
+    or: bool,
+}
+
+/// Options changing how the network is mapped onto rows
+#[derive(Debug, Copy, Clone, Default)]
+pub struct CompileOptions {
+    /// Never overwrite the rows initialized by the host (inputs and constants): they are neither
+    /// reused as scratch rows nor used as TRA operands, instead operands are explicitly copied
+    /// into scratch rows first. By default input rows are consumed destructively.
+    pub pin_inputs: bool,
+    /// Compute complemented signals as separate (dual) MAJs instead of negating rows, like the
+    /// inputs are stored in both polarities. This eliminates all N instructions at the cost of
+    /// more TRAs and rows, see [push_inversions_to_leaves](super::legalization::push_inversions_to_leaves).
+    /// Only honored when compiling through
+    /// [compile_legalized](super::legalization::compile_legalized).
+    pub dual_rail: bool,
+    /// Copy the outputs into the contiguous rows `output_base..output_base + nr_outputs` of
+    /// [Self::output_subarray] (output `i` into row `output_base + i`) once the program has
+    /// finished, instead of leaving them in the rows they have been computed in. The rows must
+    /// neither hold pinned inputs nor values placed by the user.
+    pub output_base: Option<u64>,
+    /// Subarray of the packed outputs, subarray 0 if `None`. Packing into another subarray keeps
+    /// the outputs clear of the rows used during compilation, but takes inter-subarray copies and
+    /// must not be the [Self::spill_subarray].
+    pub output_subarray: Option<SubarrayId>,
+    /// Subarray into which live values are evacuated (using inter-subarray copies) when subarray
+    /// 0 runs out of rows. Spilled values are copied back once they're needed again. Without a
+    /// spill subarray compilation fails as soon as all rows are occupied.
+    pub spill_subarray: Option<SubarrayId>,
+    /// When running out of rows, drop values whose MAJ operands are all still resident and
+    /// recompute them once needed again instead of spilling them. Recomputing takes more
+    /// instructions than a spill and reload, but neither requires a spill subarray nor
+    /// inter-subarray copies, which are considerably slower than copies within a subarray.
+    pub rematerialize: bool,
+    /// Order in which ready nodes are computed
+    pub scheduling: SchedulingPolicy,
+    /// Record every decision taken into [Program::decisions]
+    pub log_decisions: bool,
+    /// Maximal nr of rows of subarray 0 the compiler may occupy (for inputs, constants and
+    /// intermediate values), e.g. because the remaining rows hold application data. Only the
+    /// highest rows are used, the lower ones are left untouched; rows prescribed by a placement or
+    /// [Self::output_base] aren't counted. Once the budget is exhausted values are spilled or
+    /// rematerialized if enabled, otherwise compilation fails.
+    pub scratch_row_budget: Option<u64>,
+    /// Collect [AllocatorMetrics] into [Program::allocator_metrics]
+    pub allocator_metrics: bool,
+    /// Diagnostics failing the compilation, only honored when compiling through
+    /// [compile_legalized](super::legalization::compile_legalized)
+    pub strictness: Strictness,
+}
+
+/// Decides which of the nodes whose operands are available is computed next. All policies prefer
+/// nodes whose operands are present in the required polarity and, on ties, nodes with fewer users
+/// and non-outputs, since their operand rows can be reused soonest.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+#[repr(C)]
+pub enum SchedulingPolicy {
+    /// Only the criteria described above
+    #[default]
+    Greedy,
+    /// Additionally prefer nodes on the longest remaining path to an output. Finishing long
+    /// chains early typically shortens the time their operands and intermediate values are live
+    /// next to those of other chains, and thus reduces row pressure.
+    CriticalPath,
+    /// Compute subtrees one after another, starting with the one requiring the most rows (by
+    /// Sethi-Ullman numbering, see [sethi_ullman_labels]), which minimizes the nr of
+    /// simultaneously live values in tree-like regions
+    SethiUllman,
+}
+
+/// `select ? then : otherwise`, found in the network as `OR(AND(select, then), AND(!select, otherwise))`
+#[derive(Copy, Clone, Debug)]
+pub struct Mux {
+    select: Signal,
+    then: Signal,
+    otherwise: Signal,
+    /// The two AND nodes which are not computed explicitly when lowering the MUX
+    and_nodes: [Id; 2],
 }
 
 /// Main function
@@ -42,35 +201,156 @@ pub struct CompilationState<'n, N> {
 pub fn compile<'a>(
     architecture: &'a PRADAArchitecture,
     network: &impl NetworkWithBackwardEdges<Node = Mig>,
-) -> Result<Program<'a>, &'static str> {
+) -> Result<Program<'a>, CompileError> {
+    compile_with_options(architecture, network, CompileOptions::default())
+}
+
+/// Same as [compile], but with non-default [CompileOptions]
+pub fn compile_with_options<'a>(
+    architecture: &'a PRADAArchitecture,
+    network: &impl NetworkWithBackwardEdges<Node = Mig>,
+    options: CompileOptions,
+) -> Result<Program<'a>, CompileError> {
+    compile_placed(architecture, network, HashMap::new(), options).map(|(program, _)| program)
+}
+
+/// Same as [compile], but additionally returns the nodes in the order in which they are computed
+/// by the program
+pub fn compile_with_schedule<'a>(
+    architecture: &'a PRADAArchitecture,
+    network: &impl NetworkWithBackwardEdges<Node = Mig>,
+) -> Result<(Program<'a>, Vec<Id>), CompileError> {
+    compile_placed(architecture, network, HashMap::new(), CompileOptions::default())
+}
+
+/// Same as [compile], but places the given signals into the given rows (of subarray 0), e.g.
+/// because the host's data layout dictates where inputs are stored and outputs are read from
+/// - inputs are initialized directly in their rows
+/// - outputs end up in their rows when the program finishes
+/// - intermediate values are stored into their rows right after being computed
+///
+/// Copies are only inserted where a value ends up in a different row than the placement demands.
+/// Placed rows are never used for other values, which allows to keep [PersistentRows] in them.
+pub fn compile_with_placement<'a>(
+    architecture: &'a PRADAArchitecture,
+    network: &impl NetworkWithBackwardEdges<Node = Mig>,
+    placement: HashMap<Signal, RowAddress>,
+) -> Result<Program<'a>, CompileError> {
+    compile_placed(architecture, network, placement, CompileOptions::default()).map(|(program, _)| program)
+}
+
+/// Compiles the network with the given placement (see [compile_with_placement]) and options and
+/// additionally returns the schedule (see [compile_with_schedule])
+pub fn compile_placed<'a>(
+    architecture: &'a PRADAArchitecture,
+    network: &impl NetworkWithBackwardEdges<Node = Mig>,
+    placement: HashMap<Signal, RowAddress>,
+    options: CompileOptions,
+) -> Result<(Program<'a>, Vec<Id>), CompileError> {
+    compile_placed_replaying(architecture, network, placement, options, None, &[])
+        .map(|(program, schedule, _)| (program, schedule))
+}
+
+/// Same as [compile_with_options], but computes the fan-in cones of outputs with a deadline (in ns
+/// from the start of the program, by output index) first, those with earlier deadlines before
+/// those with later ones. Whether the deadlines are met is reported by [Program::timing].
+pub fn compile_with_deadlines<'a>(
+    architecture: &'a PRADAArchitecture,
+    network: &impl NetworkWithBackwardEdges<Node = Mig>,
+    options: CompileOptions,
+    deadlines: &[Option<u64>],
+) -> Result<Program<'a>, CompileError> {
+    compile_placed_replaying(architecture, network, HashMap::new(), options, None, deadlines)
+        .map(|(program, _, _)| program)
+}
+
+/// Same as [compile_with_options], but computes the nodes in the order of the given [Replay] and
+/// allocates the rows it recorded wherever possible, e.g. for compiling a modified network or for a
+/// modified cost model with otherwise identical decisions
+pub fn compile_replaying<'a>(
+    architecture: &'a PRADAArchitecture,
+    network: &impl NetworkWithBackwardEdges<Node = Mig>,
+    options: CompileOptions,
+    replay: Replay,
+) -> Result<(Program<'a>, ReplayReport), CompileError> {
+    compile_placed_replaying(architecture, network, HashMap::new(), options, Some(replay), &[])
+        .map(|(program, _, report)| (program, report.unwrap_or_default()))
+}
+
+fn compile_placed_replaying<'a>(
+    architecture: &'a PRADAArchitecture,
+    network: &impl NetworkWithBackwardEdges<Node = Mig>,
+    placement: HashMap<Signal, RowAddress>,
+    options: CompileOptions,
+    replay: Option<Replay>,
+    deadlines: &[Option<u64>],
+) -> Result<(Program<'a>, Vec<Id>, Option<ReplayReport>), CompileError> {
+    if placement.values().any(|row| row.0 >= architecture.rows_per_subarray) {
+        return Err(CompileError::Other("placement refers to rows outside of subarray 0"));
+    }
+    if let Some(spill_subarray) = options.spill_subarray {
+        if spill_subarray.0 == 0 || spill_subarray.0 >= architecture.nr_subarrays {
+            return Err(CompileError::Other("spill subarray has to be a subarray other than 0"));
+        }
+    }
+    if let Some(output_subarray) = options.output_subarray {
+        if options.output_base.is_none() {
+            return Err(CompileError::Other("an output subarray requires packed outputs"));
+        }
+        if output_subarray.0 >= architecture.nr_subarrays {
+            return Err(CompileError::Other("output subarray doesn't exist"));
+        }
+        if options.spill_subarray == Some(output_subarray) {
+            return Err(CompileError::Other("packed outputs overlap the spill subarray"));
+        }
+        if output_subarray.0 != 0 && !architecture.supports(Capabilities::INTERSUBARRAY_ROWCLONE) {
+            return Err(CompileError::UnsupportedOperation {
+                operation: "packing outputs into another subarray",
+                missing: Capabilities::INTERSUBARRAY_ROWCLONE,
+            });
+        }
+    }
+    if let Some(budget) = options.scratch_row_budget {
+        // both polarities of every input and both constants occupy a row for the whole program
+        let leaf_rows = network
+            .leafs()
+            .into_iter()
+            .flat_map(|leaf| [Signal::new(leaf, false), Signal::new(leaf, true)])
+            .filter(|signal| network.node(signal.node_id()) == Mig::False || !placement.contains_key(signal))
+            .count() as u64;
+        if leaf_rows > budget {
+            return Err(CompileError::Other("scratch row budget is too small to hold the inputs"));
+        }
+    }
 
     // init candidates, dram_state etc.
-    let mut state = CompilationState::new(architecture, network);
+    let mut state = CompilationState::new(architecture, network, placement, options, deadlines);
+    state.replay = replay;
 
     // dbg!("{:?}", state.value_states.clone());
 
     while !state.candidates.is_empty() {
         // choose next candidate
-        let (id, node, _, _, _) = state
-            .candidates
-            .iter()
-            .copied()
-            .map(|(id, node)| {
-                let outputs = state.network.node_outputs(id).count();
-                let output = state.network.outputs().any(|out| out.node_id() == id);
-                let not_present = node
-                    .inputs()
-                    .iter()
-                    .map(|signal| {
-                        // TODO: check if this is legit
-                        let present = state.value_states.contains_key(signal);
-                        !present as u8
-                    })
-                    .sum::<u8>();
-                (id, node, not_present, outputs, output)
-            })
-            .min_by_key(|(_, _, not_present, outputs, output)| (*not_present, *outputs, !output))
-            .unwrap();
+        state.update_candidate_keys();
+        let nr_candidates = state.candidates.len();
+        let recorded = state.replay.as_mut().and_then(Replay::next_node);
+        let replayed = recorded.and_then(|recorded| state.candidates.get(recorded).map(|(key, node)| (recorded, key, node)));
+        if let (Some(replay), Some(_)) = (&mut state.replay, recorded) {
+            if replayed.is_some() {
+                replay.report.followed_schedule += 1;
+            } else {
+                replay.report.diverged_schedule += 1;
+            }
+        }
+        let (id, key, node) = replayed.or_else(|| state.candidates.first()).expect("candidates should not be empty");
+        state.log(Decision::Schedule {
+            node: id,
+            candidates: nr_candidates,
+            missing_operands: key.missing_operands,
+            priority: key.priority.0,
+            users: key.users,
+            output: !key.not_output,
+        });
 
         // if state.outputs.contains(&id) {
         //     println!("Computing outputs...");
@@ -109,37 +389,52 @@ pub fn compile<'a>(
         //         }
         //     }
         // } else {
-        state.compute(id, node, None);
+        state.schedule.push(id);
+        if state.muxes.contains_key(&id) {
+            state.compute_mux(id)?;
+        } else if state.xors.contains_key(&id) {
+            state.compute_xor(id)?;
+        } else if state.and_ors.contains_key(&id) {
+            state.compute_and_or(id, node)?;
+        } else {
+            state.compute(id, node, None)?;
+        }
+        state.apply_placement(id)?;
+        #[cfg(debug_assertions)]
+        state.check_invariants();
         // }
     }
 
-    // print in which rows the outputs have been placed
-    for output in network.outputs() {
-        if let Some(row) = state.value_states.get(&output) {
-            println!("Output {output:?} in row {row}");
-        } else {
-            // check if inverted signal of `output` is there
-            let row = if let Some(&inv_sig_row) = state.value_states.get(&output.invert()) {
-                if !network.outputs().any(|o| o == output.invert()) {
-                    state.program.push(Instruction::N(inv_sig_row));
-                    state.value_states.remove(&output);
-                    state.value_states.insert(output.invert(), inv_sig_row);
-                    state.dram_state.insert(inv_sig_row, RowState{ is_compute_row: false, live_value: Some(output.invert()), constant: None});
-                    inv_sig_row
-                } else {
-                    // if inverted signal is also an output we can't just overwrite it and have to
-                    // save it in a separate row
-                    let free_row = state.free_rows_per_subarray.pop().expect("OOM");
-                    state.program.push(Instruction::AAPRowCopy(inv_sig_row, free_row));
-                    state.program.push(Instruction::N(free_row));
-                    inv_sig_row
+    state.reload_outputs()?;
+
+    let mut output_map = state.finalize_outputs()?;
+
+    let targets: Vec<Option<RowAddress>> = match options.output_base {
+        // packed outputs take precedence over placed ones
+        Some(base) => {
+            let end = base + output_map.len() as u64;
+            if end > architecture.rows_per_subarray {
+                return Err(CompileError::Other("packed outputs exceed subarray"));
+            }
+            let subarray = options.output_subarray.unwrap_or(SubarrayId(0));
+            if subarray.0 == 0 {
+                if (base..end).any(|row| state.pinned_rows.contains(&RowAddress(row))) {
+                    return Err(CompileError::Other("packed outputs overlap pinned input rows"));
                 }
-            } else {
-                panic!("Output {output:?} nor its inverse are in a row??");
-            };
-            println!("Output {output:?} in row {row}");
+                let outputs: Vec<Signal> = network.outputs().collect();
+                if state
+                    .placement
+                    .iter()
+                    .any(|(signal, row)| (base..end).contains(&row.0) && !outputs.contains(signal))
+                {
+                    return Err(CompileError::Other("packed outputs overlap rows placed by the user"));
+                }
+            }
+            (base..end).map(|row| Some(RowAddress(row).local_rowaddress_to_subarray_id(subarray))).collect()
         }
-    }
+        None => network.outputs().map(|output| state.placement.get(&output).copied()).collect(),
+    };
+    state.place_outputs(&mut output_map, &targets)?;
 
     // outputs that are directly derived from inputs will not be computed by the loop above
     // let's do that here
@@ -152,188 +447,1192 @@ pub fn compile<'a>(
     //         .signal_copy(output_sig, RowAddress(idx as u64));
     // }
 
-    let CompilingCost{ runtime,  energy_consumption} = state.program.iter().map(|instr| {
-        match instr {
-            Instruction::N(_) => CompilingCost { runtime: 35, energy_consumption: 100},
-            Instruction::AAPTRA(_,_,_) => CompilingCost { runtime: 49, energy_consumption: 150},
-            Instruction::AAPRowCopy(_,_) => CompilingCost { runtime: 100, energy_consumption: 50},
-        }
-    }).sum();
-
     // println!("{:?}", state.program);
 
-    let program = Program { architecture: &ARCHITECTURE , instructions: state.program, runtime_estimate: runtime, energy_consumption_estimate: energy_consumption };
-    Ok(program)
+    let mut program = Program { architecture, instructions: state.program, runtime_estimate: 0, energy_consumption_estimate: 0, input_map: state.input_map, output_map, allocation: state.allocation, persistent_rows: PersistentRows::default(), decisions: state.decisions.unwrap_or_default(), allocator_metrics: state.metrics, diagnostics: vec!(), metadata: vec!() };
+    program.update_cost_estimates(&architecture.cost_model);
+    Ok((program, state.schedule, state.replay.map(|replay| replay.report)))
 }
 
 impl<'a, 'n, N: NetworkWithBackwardEdges<Node = Mig>> CompilationState<'n, N> {
-    pub fn new(architecture: &'a PRADAArchitecture, network: &'n N) -> Self {
-        let mut candidates = FxHashSet::default();
-        // check all parents of leafs whether they have only leaf children, in which case they are
-        // candidates
-        for leaf in network.leafs() {
-            for candidate_id in network.node_outputs(leaf) {
-                let candidate = network.node(candidate_id);
-                if candidate
-                    .inputs()
-                    .iter()
-                    .all(|signal| network.node(signal.node_id()).is_leaf())
-                {
-                    candidates.insert((candidate_id, candidate));
-                }
-            }
-        }
-        let outputs = network.outputs().map(|sig| sig.node_id()).collect();
+    pub fn new(
+        architecture: &'a PRADAArchitecture,
+        network: &'n N,
+        placement: HashMap<Signal, RowAddress>,
+        options: CompileOptions,
+        deadlines: &[Option<u64>],
+    ) -> Self {
+        let outputs: FxHashSet<Id> = network.outputs().map(|sig| sig.node_id()).collect();
+        let muxes = if architecture.supports(Capabilities::MASKED_COPY) {
+            find_muxes(network, &outputs)
+        } else {
+            FxHashMap::default()
+        };
+        let xors = if architecture.supports(Capabilities::XOR) {
+            find_xors(network, &outputs, &muxes)
+        } else {
+            FxHashMap::default()
+        };
+        let absorbed_by = muxes
+            .iter()
+            .flat_map(|(root, mux)| mux.and_nodes.map(|and| (and, *root)))
+            .chain(xors.iter().flat_map(|(root, xor)| xor.inner_nodes.map(|inner| (inner, *root))))
+            .collect();
+        let and_ors = if architecture.supports(Capabilities::AND_OR) {
+            find_and_ors(network, &muxes, &xors, &absorbed_by)
+        } else {
+            FxHashMap::default()
+        };
 
-        let (dram_state, value_states, free_rows) = CompilationState::get_init_states(network, (0..ROWS_PER_SUBARRAY) .map(RowAddress::from) .collect());
-        Self {
+        let placement: FxHashMap<Signal, RowAddress> = placement.into_iter().collect();
+        let placed_rows: FxHashSet<RowAddress> = placement.values().copied().collect();
+        let mut free_rows: Vec<RowAddress> = (0..architecture.rows_per_subarray)
+            .map(RowAddress::from)
+            .filter(|row| !placed_rows.contains(row))
+            .collect();
+        if let Some(budget) = options.scratch_row_budget {
+            // rows are allocated from the end, hence the lowest rows are dropped
+            free_rows.drain(..free_rows.len().saturating_sub(budget as usize));
+        }
+        let (dram_state, value_states, free_rows, input_map) = CompilationState::get_init_states(network, free_rows, &placement);
+        let pinned_rows = if options.pin_inputs {
+            input_map.iter().map(|(row, _)| *row).collect()
+        } else {
+            FxHashSet::default()
+        };
+        let free_spill_rows = options.spill_subarray.map_or(vec!(), |subarray| {
+            (0..architecture.rows_per_subarray)
+                .rev()
+                .map(|row| RowAddress(row).local_rowaddress_to_subarray_id(subarray))
+                .collect()
+        });
+        let mut state = Self {
             dram_state,
-            value_states,
+            value_states: ValueStates::new(value_states),
             // initially all rows are free
             free_rows_per_subarray: free_rows,
+            input_map,
             network,
-            candidates,
+            candidates: CandidateQueue::default(),
             // start with empty program (no instructions inside)
             program: vec!(),
             outputs,
-            leftover_use_count: FxHashMap::default(),
+            leftover_use_count: NodeMap::new(),
+            muxes,
+            xors,
+            and_ors,
+            absorbed_by,
+            schedule: vec!(),
+            placement,
+            placed_rows,
+            pinned_rows,
+            capabilities: architecture.capabilities,
+            dcc_not: architecture.supports(Capabilities::DCC) && architecture.nr_dcc_rows > 0,
+            spilled: FxHashMap::default(),
+            free_spill_rows,
+            last_access: SignalMap::new(),
+            protected_rows: FxHashSet::default(),
+            rematerialize: options.rematerialize,
+            scratch_row_budget: options.scratch_row_budget,
+            dropped: FxHashMap::default(),
+            remat_uses: FxHashMap::default(),
+            deferred_discards: FxHashSet::default(),
+            allocation: AllocationStatistics::default(),
+            priorities: match options.scheduling {
+                SchedulingPolicy::Greedy => FxHashMap::default(),
+                SchedulingPolicy::CriticalPath => remaining_path_lengths(network),
+                SchedulingPolicy::SethiUllman => sethi_ullman_priorities(network),
+            },
+            deadlines: cone_deadlines(network, deadlines),
+            decisions: options.log_decisions.then(Vec::new),
+            replay: None,
+            metrics: options.allocator_metrics.then(AllocatorMetrics::new),
+            row_reliability: architecture.row_reliability.clone(),
+            deep_nodes: if architecture.row_reliability.is_empty() {
+                FxHashSet::default()
+            } else {
+                deep_nodes(network)
+            },
+            allocating_deep: false,
+        };
+        // check all parents of leafs whether they have only leaf children, in which case they are
+        // candidates
+        for leaf in network.leafs() {
+            state.add_candidate_parents(leaf);
+        }
+        state
+    }
+
+    /// Returns the signals which have to be present to compute the given node. For MUX and XOR
+    /// roots these are the operands of the MUX resp. XOR instead of the absorbed nodes.
+    pub fn operand_signals(&self, id: Id, node: Mig) -> Vec<Signal> {
+        if let Some(mux) = self.muxes.get(&id) {
+            return vec!(mux.select, mux.then, mux.otherwise);
+        }
+        if let Some(xor) = self.xors.get(&id) {
+            return vec!(xor.a, xor.b);
+        }
+        if let Some(gate) = self.and_ors.get(&id) {
+            return vec!(gate.a, gate.b);
+        }
+        node.inputs().to_vec()
+    }
+
+    /// Adds all parents of `id` whose operands are all present to the candidates
+    fn add_candidate_parents(&mut self, id: Id) {
+        for parent_id in self.network.node_outputs(id) {
+            // nodes absorbed by a MUX or XOR are never computed, their root is computed directly
+            let parent_id = *self.absorbed_by.get(&parent_id).unwrap_or(&parent_id);
+            let parent_node = self.network.node(parent_id);
+            if self
+                .operand_signals(parent_id, parent_node)
+                .iter()
+                .all(|s| self.is_available(*s) || self.is_available(s.invert()))
+            {
+                let key = self.candidate_key(parent_id, parent_node);
+                self.candidates.insert(parent_id, parent_node, key);
+            }
+        }
+    }
+
+    /// Rank of the candidate `id`, see [CandidateKey]
+    fn candidate_key(&self, id: Id, node: Mig) -> CandidateKey {
+        CandidateKey {
+            missing_operands: self
+                .operand_signals(id, node)
+                .iter()
+                .filter(|signal| !self.value_states.contains_key(signal))
+                .count() as u8,
+            deadline: self.deadlines.get(&id).copied().unwrap_or(u64::MAX),
+            priority: Reverse(self.priorities.get(&id).copied().unwrap_or(0)),
+            users: self.network.node_outputs(id).count(),
+            not_output: !self.outputs.contains(&id),
+        }
+    }
+
+    /// Updates the keys of the candidates using a value which has been inserted into or removed
+    /// from `value_states` since the last update
+    fn update_candidate_keys(&mut self) {
+        for changed in self.value_states.take_changed() {
+            for user in self.network.node_outputs(changed) {
+                let user = *self.absorbed_by.get(&user).unwrap_or(&user);
+                if let Some((_, node)) = self.candidates.get(user) {
+                    let key = self.candidate_key(user, node);
+                    self.candidates.insert(user, node, key);
+                }
+            }
+        }
+    }
+
+    /// Returns true iff `signal` is still stored in its row in subarray 0. Operands consumed by a
+    /// TRA may still be listed in `value_states` although their row has been overwritten.
+    fn is_resident(&self, signal: Signal) -> bool {
+        self.value_states
+            .get(&signal)
+            .is_some_and(|row| self.dram_state.get(row) == Some(&signal))
+    }
+
+    /// Returns true iff `signal` is stored in a row, possibly in the spill subarray, or can be
+    /// recomputed
+    fn is_available(&self, signal: Signal) -> bool {
+        self.value_states.contains_key(&signal) || self.spilled.contains_key(&signal) || self.dropped.contains_key(&signal)
+    }
+
+    /// Returns the row containing `signal`, creating it from the inverted signal if necessary.
+    /// Spilled values are reloaded, dropped values recomputed. The returned row is protected from being spilled until the
+    /// next operation is compiled.
+    fn get_or_create_signal_row(&mut self, signal: Signal) -> Result<RowAddress, CompileError> {
+        let row = if let Some(row) = self.value_states.get(&signal) {
+            *row
+        } else if self.spilled.contains_key(&signal) {
+            self.reload(signal)?
+        } else if self.dropped.contains_key(&signal) {
+            self.recompute(signal)?
+        } else {
+            if !self.is_available(signal.invert()) {
+                panic!("Signal {signal:?} nor its inverted version are present");
+            }
+            let row_inv_sig = self.get_or_create_signal_row(signal.invert())?;
+            let free_row = self.alloc_row()?;
+            self.copy_negated(row_inv_sig, free_row, CopyReason::Negation)?;
+            self.value_states.insert(signal, free_row);
+            self.dram_state.insert(free_row, signal);
+            free_row
+        };
+        self.last_access.insert(signal, self.program.len());
+        self.protected_rows.insert(row);
+        Ok(row)
+    }
+
+    /// Returns a free row of subarray 0. If there is none, the least recently accessed value which
+    /// is neither protected, pinned nor placed is evicted to make room for it: it's dropped if it
+    /// can be rematerialized (see [Self::rematerializable_operands]) and spilled otherwise.
+    fn alloc_row(&mut self) -> Result<RowAddress, CompileError> {
+        if let Some(row) = self.take_free_row(&[]) {
+            return Ok(row);
+        }
+        let can_spill = !self.free_spill_rows.is_empty();
+        if !can_spill && !self.rematerialize {
+            return Err(CompileError::Other(if self.scratch_row_budget.is_some() {
+                "scratch row budget exhausted"
+            } else {
+                "out of rows"
+            }));
+        }
+        if can_spill && !self.capabilities.contains(Capabilities::INTERSUBARRAY_ROWCLONE) {
+            return Err(CompileError::UnsupportedOperation { operation: "spilling", missing: Capabilities::INTERSUBARRAY_ROWCLONE });
+        }
+        let (victim, row) = self
+            .value_states
+            .iter()
+            .filter(|(signal, row)| {
+                self.is_resident(*signal)
+                    && !self.protected_rows.contains(row)
+                    && !self.pinned_rows.contains(row)
+                    && !self.placed_rows.contains(row)
+                    && (can_spill || self.rematerializable_operands(*signal).is_some())
+            })
+            .min_by_key(|(signal, row)| (self.last_access.get(signal).copied().unwrap_or(0), row.0))
+            .ok_or(CompileError::Other("out of rows, all rows are in use by the current operation"))?;
+        let operands = self.rematerializable_operands(victim);
+        self.value_states.remove(&victim);
+        self.dram_state.remove(&row);
+        if let Some(operands) = operands {
+            for operand in operands {
+                *self.remat_uses.entry(operand.node_id()).or_insert(0) += 1;
+            }
+            self.dropped.insert(victim, operands);
+            self.allocation.rematerializations += 1;
+            coverage::hit(CodePath::Rematerialization);
+            self.log(Decision::Evicted { row, spill_row: None });
+        } else {
+            let spill_row = self.free_spill_rows.pop().expect("checked above");
+            self.push_copy(row, spill_row, CopyReason::Spill);
+            self.spilled.insert(victim, spill_row);
+            self.allocation.spills += 1;
+            coverage::hit(CodePath::Spill);
+            self.log(Decision::Evicted { row, spill_row: Some(spill_row) });
+        }
+        self.log(Decision::RowAllocated { row });
+        Ok(row)
+    }
+
+    /// Returns the operands `signal` can be recomputed from if rematerialization is enabled and
+    /// recomputing is cheap, i.e. `signal` is computed by a plain MAJ whose operands all reside in
+    /// subarray 0 in the required polarity (so that neither reloads nor N are needed)
+    fn rematerializable_operands(&self, signal: Signal) -> Option<[Signal; 3]> {
+        let id = signal.node_id();
+        if !self.rematerialize || signal.is_inverted() || self.muxes.contains_key(&id) || self.xors.contains_key(&id) {
+            return None;
+        }
+        let Mig::Maj(operands) = self.network.node(id) else {
+            return None;
+        };
+        operands.iter().all(|operand| self.is_resident(*operand)).then_some(operands)
+    }
+
+    /// Recomputes the dropped `signal` from its operands, which are copied first since the TRA
+    /// destroys them
+    fn recompute(&mut self, signal: Signal) -> Result<RowAddress, CompileError> {
+        let operands = self.dropped.remove(&signal).expect("signal should have been dropped");
+        let mut rows = vec!();
+        for operand in operands {
+            let row = self.get_or_create_signal_row(operand)?;
+            let scratch_row = self.alloc_row()?;
+            self.protected_rows.insert(scratch_row);
+            self.push_copy(row, scratch_row, CopyReason::Rematerialization);
+            rows.push(scratch_row);
+        }
+        self.program.push(Instruction::AAPTRA(rows[0], rows[1], rows[2]));
+        self.free_row(rows[1]);
+        self.free_row(rows[2]);
+        self.value_states.insert(signal, rows[0]);
+        self.dram_state.insert(rows[0], signal);
+        self.release_remat_operands(operands);
+        Ok(rows[0])
+    }
+
+    /// Releases the operands of a dropped value which has been recomputed or isn't needed anymore,
+    /// discarding those which have only been kept alive for the recomputation
+    fn release_remat_operands(&mut self, operands: [Signal; 3]) {
+        for operand in operands {
+            let id = operand.node_id();
+            let uses = self.remat_uses.get_mut(&id).expect("operand should be kept alive");
+            *uses -= 1;
+            if *uses == 0 {
+                self.remat_uses.remove(&id);
+                if self.deferred_discards.remove(&id) {
+                    self.discard_value(id);
+                }
+            }
+        }
+    }
+
+    /// Copies the spilled `signal` back into subarray 0
+    fn reload(&mut self, signal: Signal) -> Result<RowAddress, CompileError> {
+        let row = self.alloc_row()?;
+        let spill_row = self.spilled.remove(&signal).expect("signal should be spilled");
+        self.push_copy(spill_row, row, CopyReason::Reload);
+        self.free_spill_rows.push(spill_row);
+        coverage::hit(CodePath::Reload);
+        self.value_states.insert(signal, row);
+        self.dram_state.insert(row, signal);
+        self.allocation.reloads += 1;
+        Ok(row)
+    }
+
+    /// Reloads or recomputes the outputs which aren't present in subarray 0 in any polarity
+    fn reload_outputs(&mut self) -> Result<(), CompileError> {
+        self.protected_rows.clear();
+        let outputs: Vec<Signal> = self.network.outputs().collect();
+        for output in &outputs {
+            for signal in [*output, output.invert()] {
+                if let Some(row) = self.value_states.get(&signal) {
+                    self.protected_rows.insert(*row);
+                }
+            }
+        }
+        for output in outputs {
+            if self.value_states.contains_key(&output) || self.value_states.contains_key(&output.invert()) {
+                continue;
+            }
+            let signal = if self.spilled.contains_key(&output) || self.dropped.contains_key(&output) {
+                output
+            } else {
+                output.invert()
+            };
+            self.get_or_create_signal_row(signal)?;
+        }
+        Ok(())
+    }
+
+    /// Drops both polarities of the given node, which isn't needed anymore, freeing their rows.
+    /// Nodes from which dropped values have to be recomputed are discarded only after that.
+    fn discard_value(&mut self, id: Id) {
+        if self.remat_uses.contains_key(&id) {
+            self.deferred_discards.insert(id);
+            return;
+        }
+        for signal in [Signal::new(id, false), Signal::new(id, true)] {
+            if let Some(row) = self.value_states.remove(&signal) {
+                self.dram_state.remove(&row);
+                self.free_row(row);
+            }
+            if let Some(spill_row) = self.spilled.remove(&signal) {
+                self.free_spill_rows.push(spill_row);
+            }
+            if let Some(operands) = self.dropped.remove(&signal) {
+                self.release_remat_operands(operands);
+            }
+        }
+    }
+
+    /// Negates the given row in place
+    fn emit_not(&mut self, row: RowAddress) -> Result<(), CompileError> {
+        if !self.capabilities.contains(Capabilities::NOT) {
+            return Err(CompileError::UnsupportedOperation { operation: "N", missing: Capabilities::NOT });
+        }
+        self.program.push(Instruction::N(row));
+        Ok(())
+    }
+
+    /// Stores the inverse of `from` into `to`, using [Instruction::DccNot] if available
+    fn copy_negated(&mut self, from: RowAddress, to: RowAddress, reason: CopyReason) -> Result<(), CompileError> {
+        if self.dcc_not {
+            self.program.push(Instruction::DccNot(from, to));
+            self.log(Decision::Copy { from, to, reason });
+            Ok(())
+        } else {
+            self.push_copy(from, to, reason);
+            self.emit_not(to)
+        }
+    }
+
+    /// Computes a MUX by copying the `otherwise` operand into a new row and overwriting it with the
+    /// `then` operand wherever the `select` operand is set. In contrast to TRAs this doesn't
+    /// destroy the operands.
+    pub fn compute_mux(&mut self, id: Id) -> Result<(), CompileError> {
+        if !self.candidates.remove(id) {
+            panic!("not a candidate");
+        }
+        self.allocating_deep = self.deep_nodes.contains(&id);
+        let mux = self.muxes[&id];
+        coverage::hit(CodePath::Mux);
+        self.protected_rows.clear();
+        let select = self.get_or_create_signal_row(mux.select)?;
+        let then = self.get_or_create_signal_row(mux.then)?;
+        let otherwise = self.get_or_create_signal_row(mux.otherwise)?;
+
+        let out_row = self.alloc_row()?;
+        self.push_copy(otherwise, out_row, CopyReason::Mux);
+        self.program.push(Instruction::MaskedRowCopy(select, then, out_row));
+        self.value_states.insert(Signal::new(id, false), out_row);
+        self.last_access.insert(Signal::new(id, false), self.program.len());
+        self.dram_state.insert(out_row, Signal::new(id, false));
+
+        // operands stay intact, hence their rows can only be freed once they're not used anymore
+        // by any other node (each of the absorbed ANDs counted as one use of its operands)
+        let mut operand_uses = vec!();
+        for (select, operand) in [(mux.select, mux.then), (mux.select, mux.otherwise)] {
+            operand_uses.push(select.node_id());
+            if operand.node_id() != select.node_id() {
+                operand_uses.push(operand.node_id());
+            }
+        }
+        self.release_operands(operand_uses);
+
+        self.add_candidate_parents(id);
+        Ok(())
+    }
+
+    /// Computes a XOR (or XNOR) using [Instruction::Xor], which doesn't destroy the operands
+    pub fn compute_xor(&mut self, id: Id) -> Result<(), CompileError> {
+        if !self.candidates.remove(id) {
+            panic!("not a candidate");
         }
+        self.allocating_deep = self.deep_nodes.contains(&id);
+        let xor = self.xors[&id];
+        coverage::hit(CodePath::Xor);
+        self.protected_rows.clear();
+        let a = self.get_or_create_signal_row(xor.a)?;
+        let b = self.get_or_create_signal_row(xor.b)?;
+
+        let out_row = self.alloc_row()?;
+        self.program.push(Instruction::Xor(a, b, out_row));
+        if xor.inverted {
+            self.emit_not(out_row)?;
+        }
+        self.value_states.insert(Signal::new(id, false), out_row);
+        self.last_access.insert(Signal::new(id, false), self.program.len());
+        self.dram_state.insert(out_row, Signal::new(id, false));
+
+        // both absorbed nodes use both operands once
+        let (a, b) = (xor.a.node_id(), xor.b.node_id());
+        self.release_operands(vec!(a, b, a, b));
+
+        self.add_candidate_parents(id);
+        Ok(())
     }
 
-    pub fn get_init_states(ntk: &'n N, mut free_rows_per_subarray: Vec<RowAddress> ) ->  (HashMap<RowAddress, RowState>, HashMap<Signal, RowAddress>, Vec<RowAddress>) {
-        let mut dram_state = HashMap::new();
-        let mut value_states = HashMap::new();
-        // 0. Place constants `True`&`False`
-        let row_for_false = free_rows_per_subarray.pop().expect("No more free rows");
-        let row_state = RowState{ is_compute_row: false, live_value: None, constant: Some(0) }; // False
-        dram_state.insert(row_for_false, row_state);
-        println!("Place 0s into {row_for_false}");
-        let row_for_true = free_rows_per_subarray.pop().expect("No more free rows");
-        let row_state = RowState{ is_compute_row: false, live_value: None, constant: Some(std::usize::MAX) }; // True
-        dram_state.insert(row_for_true, row_state);
-        println!("Place 1s into {row_for_true}");
+    /// Computes a MAJ with a constant operand using [Instruction::And] resp. [Instruction::Or],
+    /// which neither needs the constant row nor destroys the operands
+    pub fn compute_and_or(&mut self, id: Id, node: Mig) -> Result<(), CompileError> {
+        if !self.candidates.remove(id) {
+            panic!("not a candidate");
+        }
+        self.allocating_deep = self.deep_nodes.contains(&id);
+        let gate = self.and_ors[&id];
+        coverage::hit(CodePath::AndOr);
+        self.protected_rows.clear();
+        let a = self.get_or_create_signal_row(gate.a)?;
+        let b = self.get_or_create_signal_row(gate.b)?;
+
+        let out_row = self.alloc_row()?;
+        if gate.or {
+            self.program.push(Instruction::Or(a, b, out_row));
+        } else {
+            self.program.push(Instruction::And(a, b, out_row));
+        }
+        self.value_states.insert(Signal::new(id, false), out_row);
+        self.last_access.insert(Signal::new(id, false), self.program.len());
+        self.dram_state.insert(out_row, Signal::new(id, false));
+
+        self.release_operands(node.inputs().iter().map(|input| input.node_id()).collect());
+
+        self.add_candidate_parents(id);
+        Ok(())
+    }
+
+    /// Decrements the leftover uses of the given operands (once per occurrence) and frees the
+    /// rows of operands which aren't needed anymore
+    fn release_operands(&mut self, operand_uses: Vec<Id>) {
+        for operand in operand_uses {
+            let leftover_uses = self.leftover_use_count(operand);
+            *leftover_uses = leftover_uses.saturating_sub(1);
+            if *leftover_uses == 0 && !self.network.node(operand).is_leaf() {
+                self.discard_value(operand);
+            }
+        }
+    }
+
+    pub fn get_init_states(ntk: &'n N, mut free_rows_per_subarray: Vec<RowAddress>, placement: &FxHashMap<Signal, RowAddress>) ->  (FxHashMap<RowAddress, Signal>, FxHashMap<Signal, RowAddress>, Vec<RowAddress>, Vec<(RowAddress, RowInit)>) {
+        let mut dram_state = FxHashMap::default();
+        let mut value_states = FxHashMap::default();
+        let mut input_map = vec!();
+        // constants are only placed if the network uses them
+        let mut constants = ConstantRows::new();
 
         let leafs = ntk.leafs();
         for id in leafs {
             let node = ntk.node(id);
-            let next_row = free_rows_per_subarray.pop().expect("No more free rows");
             match node {
                 Mig::Input(i) => {
+                    let next_row = match placement.get(&Signal::new(id, false)) {
+                        Some(row) => *row,
+                        None => free_rows_per_subarray.pop().expect("No more free rows"),
+                    };
                     println!("Input {id:?} placed in row {next_row}");
                     // TODO: check whether inverted or non-inverted version is needed and place only
                     // that one
-                    let row_state = RowState{ is_compute_row: false, live_value: Some(Signal::new(id, false)), constant: None };
                     value_states.insert(Signal::new(id, false), next_row);
-                    dram_state.insert(next_row, row_state);
+                    dram_state.insert(next_row, Signal::new(id, false));
+                    input_map.push((next_row, RowInit::Input { index: i, inverted: false }));
 
-                    let next_row = free_rows_per_subarray.pop().expect("No more free rows");
-                    let row_state = RowState{ is_compute_row: false, live_value: Some(Signal::new(id, true)), constant: None };
+                    let next_row = match placement.get(&Signal::new(id, true)) {
+                        Some(row) => *row,
+                        None => free_rows_per_subarray.pop().expect("No more free rows"),
+                    };
                     value_states.insert(Signal::new(id, true), next_row);
-                    dram_state.insert(next_row, row_state);
+                    dram_state.insert(next_row, Signal::new(id, true));
+                    input_map.push((next_row, RowInit::Input { index: i, inverted: true }));
                 }
                 Mig::False => {
-                    let row_state = RowState{ is_compute_row: false, live_value: Some(Signal::new(id, false)), constant: Some(0) };
+                    let mut alloc = || free_rows_per_subarray.pop().ok_or("No more free rows");
+                    let row_for_false = constants.materialize(SubarrayId(0), false, &mut input_map, &mut alloc).expect("No more free rows");
+                    println!("Place 0s into {row_for_false}");
+                    let row_for_true = constants.materialize(SubarrayId(0), true, &mut input_map, &mut alloc).expect("No more free rows");
+                    println!("Place 1s into {row_for_true}");
+
                     value_states.insert(Signal::new(id, false), row_for_false);
-                    dram_state.insert(row_for_false, row_state);
+                    dram_state.insert(row_for_false, Signal::new(id, false));
 
-                    let row_state = RowState{ is_compute_row: false, live_value: Some(Signal::new(id, true)), constant: Some(std::usize::MAX) };
                     value_states.insert(Signal::new(id, true), row_for_true);
-                    dram_state.insert(row_for_true, row_state);
+                    dram_state.insert(row_for_true, Signal::new(id, true));
                 }
                 _ => unreachable!("leaf node should be either an input or a constant"),
             };
         }
 
-        (dram_state, value_states, free_rows_per_subarray)
+        (dram_state, value_states, free_rows_per_subarray, input_map)
+    }
+
+    /// Checks that `value_states`, `dram_state` and `free_rows_per_subarray` are consistent: free
+    /// rows are listed once, aren't reserved and hold no live value, and every live value of a row
+    /// is mapped to that row. Catches allocator bugs right after the operation introducing them
+    /// rather than as wrong results of the program.
+    #[cfg(debug_assertions)]
+    fn check_invariants(&self) {
+        let mut free_rows = FxHashSet::default();
+        for row in &self.free_rows_per_subarray {
+            assert!(free_rows.insert(*row), "row {row} is listed as free twice");
+            assert!(
+                !self.pinned_rows.contains(row) && !self.placed_rows.contains(row),
+                "reserved row {row} is listed as free"
+            );
+            if let Some(signal) = self.dram_state.get(row) {
+                assert_ne!(self.value_states.get(signal), Some(row), "free row {row} holds live value {signal:?}");
+            }
+        }
+        for (row, signal) in &self.dram_state {
+            assert_eq!(self.value_states.get(signal), Some(row), "live value {signal:?} of row {row} isn't mapped to it");
+        }
+    }
+
+    /// Returns the row to the pool of free rows, unless it is reserved by the placement or pinned
+    fn free_row(&mut self, row: RowAddress) {
+        if !self.pinned_rows.contains(&row) && !self.placed_rows.contains(&row) {
+            self.free_rows_per_subarray.push(row);
+            self.log(Decision::RowFreed { row });
+            if let Some(metrics) = &mut self.metrics {
+                metrics.record_free(row, self.program.len());
+            }
+        }
+    }
+
+    fn log(&mut self, decision: Decision) {
+        if let Some(decisions) = &mut self.decisions {
+            decisions.push(decision);
+        }
+    }
+
+    fn push_copy(&mut self, from: RowAddress, to: RowAddress, reason: CopyReason) {
+        self.program.push(Instruction::AAPRowCopy(from, to));
+        self.log(Decision::Copy { from, to, reason });
+    }
+
+    /// Moves the phases of the just computed node `id` which have been placed by the user into
+    /// their rows
+    fn apply_placement(&mut self, id: Id) -> Result<(), CompileError> {
+        for signal in [Signal::new(id, false), Signal::new(id, true)] {
+            let Some(&target) = self.placement.get(&signal) else {
+                continue;
+            };
+            if self.value_states.get(&signal) == Some(&target) {
+                continue;
+            }
+            if let Some(other) = self.dram_state.get(&target) {
+                if *other != signal && self.value_states.get(other) == Some(&target) {
+                    return Err(CompileError::Other("placed row is still occupied by another live value"));
+                }
+            }
+            if let Some(&row) = self.value_states.get(&signal) {
+                // move the value, its previous row isn't needed anymore
+                self.push_copy(row, target, CopyReason::Placement);
+                self.dram_state.remove(&row);
+                self.free_row(row);
+            } else if let Some(&row) = self.value_states.get(&signal.invert()) {
+                self.copy_negated(row, target, CopyReason::Placement)?;
+            } else {
+                continue;
+            }
+            coverage::hit(CodePath::PlacedValue);
+            self.value_states.insert(signal, target);
+            self.dram_state.insert(target, signal);
+        }
+        Ok(())
+    }
+
+    /// Determines the row of every output once all nodes have been computed. Outputs present in
+    /// the required polarity are used as they are, with duplicate outputs sharing a row. Otherwise
+    /// the inverse is negated: in place if it isn't needed anymore, on a copy if it is an output
+    /// itself, placed or pinned. Inputs and constants which haven't been placed (as no MAJ uses
+    /// them) get a row initialized by the host.
+    fn finalize_outputs(&mut self) -> Result<Vec<RowAddress>, CompileError> {
+        let outputs: Vec<Signal> = self.network.outputs().collect();
+        let mut rows: FxHashMap<Signal, RowAddress> = FxHashMap::default();
+        for &output in &outputs {
+            if rows.contains_key(&output) {
+                continue;
+            }
+            let inverse = output.invert();
+            let row = if let Some(&row) = self.value_states.get(&output) {
+                row
+            } else if let Some(&inverse_row) = self.value_states.get(&inverse) {
+                let keep_inverse = outputs.contains(&inverse)
+                    || self.placement.get(&inverse) == Some(&inverse_row)
+                    || self.pinned_rows.contains(&inverse_row);
+                let row = if keep_inverse {
+                    coverage::hit(CodePath::InvertedOutputCopy);
+                    let row = self.alloc_row()?;
+                    self.copy_negated(inverse_row, row, CopyReason::InvertedOutput)?;
+                    row
+                } else {
+                    coverage::hit(CodePath::InvertedOutput);
+                    self.emit_not(inverse_row)?;
+                    self.value_states.remove(&inverse);
+                    inverse_row
+                };
+                self.value_states.insert(output, row);
+                self.dram_state.insert(row, output);
+                row
+            } else if let Some(init) = leaf_init(self.network.node(output.node_id()), output.is_inverted()) {
+                // leaves which are only used as outputs, e.g. in networks without any MAJ
+                coverage::hit(CodePath::LeafOutput);
+                let row = self.alloc_row()?;
+                self.input_map.push((row, init));
+                self.value_states.insert(output, row);
+                self.dram_state.insert(row, output);
+                row
+            } else {
+                return Err(CompileError::Other("neither an output nor its inverse has been computed"));
+            };
+            self.protected_rows.insert(row);
+            rows.insert(output, row);
+        }
+        Ok(outputs.iter().map(|output| rows[output]).collect())
+    }
+
+    /// Copies the outputs into the given target rows once the program has finished, using the
+    /// minimal nr of copies. `output_map` contains the row each output currently resides in and
+    /// is updated accordingly.
+    fn place_outputs(&mut self, output_map: &mut [RowAddress], targets: &[Option<RowAddress>]) -> Result<(), CompileError> {
+        // (output index, source row, target row) of all outputs which have to be moved
+        let mut pending: Vec<(usize, RowAddress, RowAddress)> = output_map
+            .iter()
+            .zip(targets)
+            .enumerate()
+            .filter_map(|(idx, (source, target))| target.filter(|target| target != source).map(|target| (idx, *source, target)))
+            .collect();
+        // outputs without target residing in the target row of another output have to be saved
+        for idx in 0..output_map.len() {
+            if targets[idx].is_none() && targets.contains(&Some(output_map[idx])) {
+                let temp = self.pop_free_row_excluding(targets)?;
+                pending.push((idx, output_map[idx], temp));
+            }
+        }
+        while !pending.is_empty() {
+            // a copy can be done once its target isn't the source of another pending copy
+            let ready = pending
+                .iter()
+                .position(|(_, _, target)| !pending.iter().any(|(_, source, _)| source == target));
+            match ready {
+                Some(pos) => {
+                    let (idx, source, target) = pending.swap_remove(pos);
+                    self.push_copy(source, target, CopyReason::OutputPacking);
+                    output_map[idx] = target;
+                }
+                None => {
+                    // the remaining copies form cycles, which are broken by saving one of the
+                    // sources into a temporary row
+                    coverage::hit(CodePath::OutputCopyCycle);
+                    let temp = self.pop_free_row_excluding(targets)?;
+                    let source = pending[0].1;
+                    self.push_copy(source, temp, CopyReason::OutputPacking);
+                    for (_, pending_source, _) in pending.iter_mut() {
+                        if *pending_source == source {
+                            *pending_source = temp;
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn pop_free_row_excluding(&mut self, excluded: &[Option<RowAddress>]) -> Result<RowAddress, CompileError> {
+        self.take_free_row(excluded).ok_or(CompileError::Other("no free row for relocating output"))
+    }
+
+    /// Removes the most recently freed row not contained in `excluded` from the free rows, unless
+    /// the [Replay] recorded a row which is free
+    fn take_free_row(&mut self, excluded: &[Option<RowAddress>]) -> Option<RowAddress> {
+        let allowed = |row: &RowAddress| !excluded.contains(&Some(*row));
+        let recorded = self.replay.as_mut().and_then(Replay::next_row);
+        let recorded_pos = recorded
+            .and_then(|recorded| self.free_rows_per_subarray.iter().position(|row| *row == recorded && allowed(row)));
+        if let (Some(replay), Some(_)) = (&mut self.replay, recorded) {
+            if recorded_pos.is_some() {
+                replay.report.followed_rows += 1;
+            } else {
+                replay.report.diverged_rows += 1;
+            }
+        }
+        let pos = recorded_pos.or_else(|| self.preferred_free_row(allowed))?;
+        let row = self.free_rows_per_subarray.remove(pos);
+        self.log(Decision::RowAllocated { row });
+        if let Some(metrics) = &mut self.metrics {
+            metrics.record_allocation(row, self.program.len(), &self.free_rows_per_subarray);
+        }
+        Some(row)
+    }
+
+    /// Position of the free row to allocate next among the `allowed` ones: the most recently freed
+    /// one, unless the architecture has reliability weights, in which case values of
+    /// [Self::deep_nodes] get the most reliable row and all other values the least reliable one
+    fn preferred_free_row(&self, allowed: impl Fn(&RowAddress) -> bool) -> Option<usize> {
+        if self.row_reliability.is_empty() {
+            return self.free_rows_per_subarray.iter().rposition(allowed);
+        }
+        let weight =
+            |row: &RowAddress| self.row_reliability.get((row.0 & ROW_ID_BITMASK) as usize).copied().unwrap_or(0);
+        let candidates = self.free_rows_per_subarray.iter().enumerate().filter(|(_, row)| allowed(row));
+        // ties are broken in favor of the most recently freed row
+        let (pos, _) = if self.allocating_deep {
+            candidates.max_by_key(|(pos, row)| (weight(row), *pos))
+        } else {
+            candidates.min_by_key(|(pos, row)| (weight(row), Reverse(*pos)))
+        }?;
+        Some(pos)
     }
 
     pub fn leftover_use_count(&mut self, id: Id) -> &mut usize {
-        self.leftover_use_count.entry(id).or_insert_with(|| {
+        self.leftover_use_count.get_or_insert_with(id, || {
             // or if node hasn't been touched yet: init `leftover_use_count` with nr uses
             self.network.node_outputs(id).count() + self.outputs.contains(&id) as usize
         })
     }
 
     /// Actually compute the operation behind `node` and store it into `out_address`
-    pub fn compute(&mut self, id: Id, node: Mig, out_address: Option<RowAddress>) {
+    pub fn compute(&mut self, id: Id, node: Mig, out_address: Option<RowAddress>) -> Result<(), CompileError> {
         // dbg!("Computing {:?}", id);
         // dbg!("Candidates: {:?}", &self.candidates);
-        if !self.candidates.remove(&(id, node)) {
+        if !self.candidates.remove(id) {
             panic!("not a candidate");
         }
+        self.allocating_deep = self.deep_nodes.contains(&id);
         let Mig::Maj(signals) = node else {
             panic!("can only compute majs")
         };
+        // the constant operand of a MAJ is replaced by a control row if available, leaving only
+        // two operands
+        let control = if self.capabilities.contains(Capabilities::CONTROL_ROWS) {
+            [false, true].into_iter().find_map(|value| {
+                maj_with_constant(self.network, Signal::new(id, false), value).map(|operands| (operands, ControlRow::holding(value)))
+            })
+        } else {
+            None
+        };
+        let signals: Vec<Signal> = match control {
+            Some((operands, _)) => operands.to_vec(),
+            None => signals.to_vec(),
+        };
 
-        // get row addresses of require input operands
-        let row_addresses: Vec<RowAddress> = signals.iter().map(|signal|
-            if !self.value_states.contains_key(signal) {
-                if let Some(row_inv_sig) = self.value_states.get(&signal.invert()) {
-                    // if signal isn't there, first create it using the inverted signal
-                    let free_row = self.free_rows_per_subarray.pop().expect("OOM");
-                    self.program.push(Instruction::AAPRowCopy(*row_inv_sig, free_row));
-                    self.program.push(Instruction::N(free_row));
-
-                    self.value_states.insert(*signal, free_row);
-                    self.dram_state.insert(free_row, RowState { is_compute_row: false, live_value: Some(*signal), constant: None});
-                    free_row
-                } else {
-                    panic!("Input Signal {signal:?} nor its inverted version are present. Why is {id:?} a candidate then?");
-                }
+        // get row addresses of require input operands (if signal isn't there, first create it
+        // using the inverted signal)
+        self.protected_rows.clear();
+        let row_addresses: Vec<RowAddress> = signals.iter().map(|signal| {
+            let row = self.get_or_create_signal_row(*signal)?;
+            if self.pinned_rows.contains(&row) {
+                // pinned rows must not be clobbered by the TRA, hence compute on a copy
+                let scratch_row = self.alloc_row()?;
+                self.protected_rows.insert(scratch_row);
+                self.push_copy(row, scratch_row, CopyReason::PinnedOperand);
+                Ok(scratch_row)
             } else {
-                *self.value_states.get(signal).unwrap()
+                Ok(row)
             }
-        ).collect();
+        }).collect::<Result<_, CompileError>>()?;
 
 
         // update `leftover_use_count` of parent of this signal & free row if operand is not needed
         // anymore
-        if ! self.network.outputs().any(|x| x.node_id() == id) {
+        if !self.outputs.contains(&id) {
             self.network.node_outputs(id).for_each(|parent| {
-                self.leftover_use_count.entry(parent).and_modify(|v| *v -= 1);
-                if self.leftover_use_count.get(&parent) == Some(&1) {
-                    if let Some(row) = self.value_states.remove(&Signal::new(parent, false)) {
-                        self.dram_state.remove(&row);
-                        self.free_rows_per_subarray.push(row);
-                    }
-                    if let Some(row) = self.value_states.remove(&Signal::new(parent, true)) {
-                        self.dram_state.remove(&row);
-                        self.free_rows_per_subarray.push(row);
-                    }
+                if let Some(uses) = self.leftover_use_count.get_mut(parent) {
+                    *uses -= 1;
+                }
+                if self.leftover_use_count.get(parent) == Some(&1) {
+                    self.discard_value(parent);
                 }
             });
         }
 
-        // move values into safe rows if they're needed in future (=still live)
-        for signal in signals {
-            if *self.leftover_use_count(signal.node_id()) > 1  {
-                let next_free_row = self.free_rows_per_subarray.pop().expect("OOM");
+        // move values into safe rows if they're needed in future (=still live), also for
+        // recomputing dropped values
+        for &signal in &signals {
+            let pinned = self.value_states.get(&signal).is_some_and(|row| self.pinned_rows.contains(row));
+            let needed = *self.leftover_use_count(signal.node_id()) > 1 || self.remat_uses.contains_key(&signal.node_id());
+            if needed && !pinned {
+                let next_free_row = self.alloc_row()?;
                 let row_addr = *self.value_states.get(&signal).unwrap_or_else(|| panic!("Input Signal with node-id={:?} not present. Why is {id:?} a candidate then?", signal.node_id()));
                 self.value_states.insert(signal, next_free_row);
-                self.dram_state.insert(next_free_row, RowState { is_compute_row: false, live_value: Some(signal), constant: None});
-                self.program.push(Instruction::AAPRowCopy(row_addr, next_free_row));
+                self.dram_state.insert(next_free_row, signal);
+                self.push_copy(row_addr, next_free_row, CopyReason::LiveOperand);
             }
         }
 
         // perform MAJ3
-        self.program.push(Instruction::AAPTRA(row_addresses[0], row_addresses[1], row_addresses[2]));
+        match control {
+            Some((_, control)) => self.program.push(Instruction::ControlTra(row_addresses[0], row_addresses[1], control)),
+            None => self.program.push(Instruction::AAPTRA(row_addresses[0], row_addresses[1], row_addresses[2])),
+        }
         self.value_states.insert(Signal::new(id, false), row_addresses[0]);
-        self.dram_state.insert(row_addresses[0], RowState { is_compute_row: false, live_value: Some(Signal::new(id, false)), constant: None } );
+        self.last_access.insert(Signal::new(id, false), self.program.len());
+        self.dram_state.insert(row_addresses[0], Signal::new(id, false));
         // keep result only in one of the addresses, free the remaining rows
-        self.dram_state.remove(&row_addresses[1]);
-        self.dram_state.remove(&row_addresses[2]);
-        self.free_rows_per_subarray.append(&mut vec!(row_addresses[1], row_addresses[2]));
+        for &row in &row_addresses[1..] {
+            self.dram_state.remove(&row);
+            self.free_row(row);
+        }
 
         // lastly, determine new candidates
-        for parent_id in self.network.node_outputs(id) {
-            let parent_node = self.network.node(parent_id);
-            if parent_node
-                .inputs()
-                .iter()
-                .all(|s| self.value_states.contains_key(s) || self.value_states.contains_key(&s.invert()))
-            {
-                self.candidates.insert((parent_id, parent_node));
+        self.add_candidate_parents(id);
+        Ok(())
+    }
+}
+
+/// Initialization of a row holding the given leaf, or `None` if `node` isn't a leaf
+fn leaf_init(node: Mig, inverted: bool) -> Option<RowInit> {
+    match node {
+        Mig::False => Some(RowInit::Constant(inverted)),
+        Mig::Input(index) => Some(RowInit::Input { index, inverted }),
+        Mig::Maj(_) => None,
+    }
+}
+
+/// Estimates the nr of rows of a subarray required for compiling the network (as is, i.e.
+/// without rewriting), which allows to check whether a kernel fits into a subarray before spending
+/// time on rewriting. This assumes that
+/// - the constants (if used) and both polarities of every input occupy a row for the whole program
+/// - nodes are computed in topological order, each occupying a row from being computed until its
+///   last use (outputs until the end)
+/// - every TRA needs copies of at most three operands
+///
+/// The actual usage depends on the scheduling of the compiler. For programs compiled from the
+/// network as is, the estimate bounds their [Program::peak_row_usage] from above.
+pub fn estimate_rows_needed(network: &impl NetworkWithBackwardEdges<Node = Mig>) -> u64 {
+    // operands of a TRA, which are copied into the rows the TRA is performed on
+    const TRA_OPERANDS: u64 = 3;
+    let mut nodes = reachable_nodes(network);
+    nodes.reverse();
+    let nr_inputs = nodes.iter().filter(|id| matches!(network.node(**id), Mig::Input(_))).count() as u64;
+    // one row holding 0 and one holding 1
+    let nr_constant_rows = if nodes.iter().any(|id| network.node(*id) == Mig::False) { 2 } else { 0 };
+    let schedule: Vec<Id> = nodes.into_iter().filter(|id| !network.node(*id).is_leaf()).collect();
+    let position: FxHashMap<Id, usize> = schedule.iter().enumerate().map(|(idx, id)| (*id, idx)).collect();
+    let outputs: FxHashSet<Id> = network.outputs().map(|sig| sig.node_id()).collect();
+
+    // nr of values becoming dead after each step
+    let mut deaths = vec!(0u64; schedule.len() + 1);
+    for (idx, id) in schedule.iter().enumerate() {
+        let last_use = if outputs.contains(id) {
+            schedule.len()
+        } else {
+            network
+                .node_outputs(*id)
+                .filter_map(|parent| position.get(&parent).copied())
+                .max()
+                .unwrap_or(idx)
+        };
+        deaths[last_use] += 1;
+    }
+    let (mut live, mut peak) = (0u64, 0u64);
+    for dead in deaths.iter().take(schedule.len()) {
+        live += 1;
+        peak = peak.max(live);
+        live -= dead;
+    }
+    // both polarities of every input, the computed values alive at the same time and the operand
+    // copies of the TRA currently performed
+    nr_constant_rows + 2 * nr_inputs + peak + TRA_OPERANDS
+}
+
+/// Returns for every node reachable from the outputs the nr of nodes on the longest path from it
+/// to an output (including the node itself)
+pub fn remaining_path_lengths(network: &impl NetworkWithBackwardEdges<Node = Mig>) -> FxHashMap<Id, usize> {
+    let mut lengths: FxHashMap<Id, usize> = FxHashMap::default();
+    // users come before their inputs, hence their lengths are known already
+    for id in reachable_nodes(network) {
+        let longest_user = network
+            .node_outputs(id)
+            .filter_map(|user| lengths.get(&user).copied())
+            .max()
+            .unwrap_or(0);
+        lengths.insert(id, longest_user + 1);
+    }
+    lengths
+}
+
+/// Returns the nodes whose longest path to an output is at least half as long as the longest path
+/// of the network, i.e. the nodes on deep dependency chains
+pub fn deep_nodes(network: &impl NetworkWithBackwardEdges<Node = Mig>) -> FxHashSet<Id> {
+    let lengths = remaining_path_lengths(network);
+    let longest = lengths.values().copied().max().unwrap_or(0);
+    lengths.into_iter().filter(|(_, length)| 2 * length >= longest).map(|(id, _)| id).collect()
+}
+
+/// Returns the Sethi-Ullman number of every node reachable from the outputs, i.e. the nr of rows
+/// required for computing it if its operands are computed one after another, the operand requiring
+/// the most rows first: leaves need one row, a MAJ whose operands need `l1 >= l2 >= l3` rows needs
+/// `max(l1, l2 + 1, l3 + 2)` rows (the results of the operands computed first have to be kept while
+/// computing the remaining ones). Shared nodes are labeled as if they were trees, hence the numbers
+/// are only exact for tree-like regions.
+pub fn sethi_ullman_labels(network: &impl Network<Node = Mig>) -> FxHashMap<Id, usize> {
+    let mut labels: FxHashMap<Id, usize> = FxHashMap::default();
+    let mut nodes = reachable_nodes(network);
+    // inputs before their users
+    nodes.reverse();
+    for id in nodes {
+        let label = match network.node(id) {
+            Mig::Maj(inputs) => {
+                let mut operand_labels = inputs.map(|input| labels[&input.node_id()]);
+                operand_labels.sort_unstable_by(|a, b| b.cmp(a));
+                let [l1, l2, l3] = operand_labels;
+                l1.max(l2 + 1).max(l3 + 2)
+            }
+            _ => 1,
+        };
+        labels.insert(id, label);
+    }
+    labels
+}
+
+/// Returns priorities which make the compiler follow the Sethi-Ullman evaluation order: a
+/// post-order traversal from the outputs visiting the operands of every node in order of
+/// decreasing [sethi_ullman_labels], earlier nodes having higher priorities
+pub fn sethi_ullman_priorities(network: &impl Network<Node = Mig>) -> FxHashMap<Id, usize> {
+    let labels = sethi_ullman_labels(network);
+    let by_decreasing_label = |ids: &mut Vec<Id>| {
+        // ties are broken by id to keep the order deterministic
+        ids.sort_by_key(|id| (Reverse(labels[id]), usize::from(eggmock::egg::Id::from(*id))));
+    };
+    let mut outputs: Vec<Id> = network.outputs().map(|sig| sig.node_id()).collect();
+    by_decreasing_label(&mut outputs);
+
+    let mut visited = FxHashSet::default();
+    let mut order = vec!();
+    // (node, whether its inputs have already been visited), the top of the stack is visited first
+    let mut stack: Vec<(Id, bool)> = outputs.into_iter().rev().map(|id| (id, false)).collect();
+    while let Some((id, expanded)) = stack.pop() {
+        if expanded {
+            order.push(id);
+            continue;
+        }
+        if !visited.insert(id) {
+            continue;
+        }
+        let node = network.node(id);
+        if node.is_leaf() {
+            continue;
+        }
+        stack.push((id, true));
+        let mut inputs: Vec<Id> = node.inputs().iter().map(|sig| sig.node_id()).collect();
+        by_decreasing_label(&mut inputs);
+        stack.extend(inputs.into_iter().rev().map(|id| (id, false)));
+    }
+    order
+        .iter()
+        .enumerate()
+        .map(|(idx, id)| (*id, order.len() - idx))
+        .collect()
+}
+
+/// Returns the ids of all nodes reachable from the outputs of the network in topological order,
+/// i.e. every node comes before all of its inputs
+pub fn reachable_nodes(network: &impl Network<Node = Mig>) -> Vec<Id> {
+    let mut visited = FxHashSet::default();
+    // (node, whether its inputs have already been visited)
+    let mut stack: Vec<(Id, bool)> = network.outputs().map(|sig| (sig.node_id(), false)).collect();
+    let mut nodes = vec!();
+    while let Some((id, expanded)) = stack.pop() {
+        if expanded {
+            nodes.push(id);
+            continue;
+        }
+        if !visited.insert(id) {
+            continue;
+        }
+        stack.push((id, true));
+        stack.extend(network.node(id).inputs().iter().map(|sig| (sig.node_id(), false)));
+    }
+    // post-order yields inputs before the nodes using them
+    nodes.reverse();
+    nodes
+}
+
+/// Returns true iff `signal` is the constant `value`
+fn is_constant(network: &impl Network<Node = Mig>, signal: Signal, value: bool) -> bool {
+    signal.is_inverted() == value && network.node(signal.node_id()) == Mig::False
+}
+
+/// Returns the two non-constant operands if `signal` is a (non-inverted) MAJ with a constant
+/// operand of the given value, i.e. an AND (`value = false`) or OR (`value = true`)
+fn maj_with_constant(network: &impl Network<Node = Mig>, signal: Signal, value: bool) -> Option<[Signal; 2]> {
+    if signal.is_inverted() {
+        return None;
+    }
+    let Mig::Maj(inputs) = network.node(signal.node_id()) else {
+        return None;
+    };
+    let constant_idx = inputs.iter().position(|input| is_constant(network, *input, value))?;
+    let mut operands = inputs.iter().enumerate().filter(|(idx, _)| *idx != constant_idx).map(|(_, sig)| *sig);
+    Some([operands.next()?, operands.next()?])
+}
+
+/// Searches for MAJs with a constant operand (i.e. MIG-ANDs and -ORs) which aren't part of a MUX
+/// or XOR
+fn find_and_ors(
+    network: &impl Network<Node = Mig>,
+    muxes: &FxHashMap<Id, Mux>,
+    xors: &FxHashMap<Id, XorGate>,
+    absorbed_by: &FxHashMap<Id, Id>,
+) -> FxHashMap<Id, AndOrGate> {
+    reachable_nodes(network)
+        .into_iter()
+        .filter(|id| !muxes.contains_key(id) && !xors.contains_key(id) && !absorbed_by.contains_key(id))
+        .filter_map(|id| {
+            let signal = Signal::new(id, false);
+            if let Some([a, b]) = maj_with_constant(network, signal, false) {
+                return Some((id, AndOrGate { a, b, or: false }));
             }
+            maj_with_constant(network, signal, true).map(|[a, b]| (id, AndOrGate { a, b, or: true }))
+        })
+        .collect()
+}
+
+/// Searches for `OR(AND(s, a), AND(!s, b))` structures (with MIG-ANDs and -ORs being MAJs with a
+/// constant 0 resp. 1 operand) whose AND nodes are used by the OR only
+fn find_muxes(network: &impl NetworkWithBackwardEdges<Node = Mig>, outputs: &FxHashSet<Id>) -> FxHashMap<Id, Mux> {
+    let maj_with_constant = |signal: Signal, value: bool| maj_with_constant(network, signal, value);
+    let exclusively_used = |id: Id| network.node_outputs(id).count() == 1 && !outputs.contains(&id);
+
+    let mut muxes: FxHashMap<Id, Mux> = FxHashMap::default();
+    let mut absorbed: FxHashSet<Id> = FxHashSet::default();
+    for id in reachable_nodes(network) {
+        let Some([x, y]) = maj_with_constant(Signal::new(id, false), true) else {
+            continue;
+        };
+        if x.node_id() == y.node_id()
+            || !exclusively_used(x.node_id())
+            || !exclusively_used(y.node_id())
+            || absorbed.contains(&x.node_id())
+            || absorbed.contains(&y.node_id())
+            || muxes.contains_key(&x.node_id())
+            || muxes.contains_key(&y.node_id())
+        {
+            continue;
+        }
+        let (Some(x_ops), Some(y_ops)) = (maj_with_constant(x, false), maj_with_constant(y, false)) else {
+            continue;
+        };
+        // find the select signal, which has to occur in both ANDs with different polarity
+        let mux = x_ops.iter().enumerate().find_map(|(i, select)| {
+            let j = y_ops.iter().position(|sig| *sig == select.invert())?;
+            Some(Mux {
+                select: *select,
+                then: x_ops[1 - i],
+                otherwise: y_ops[1 - j],
+                and_nodes: [x.node_id(), y.node_id()],
+            })
+        });
+        if let Some(mux) = mux {
+            absorbed.extend(mux.and_nodes);
+            muxes.insert(id, mux);
+        }
+    }
+    // MUX roots must not have been absorbed by another MUX
+    muxes.retain(|root, _| !absorbed.contains(root));
+    muxes
+}
+
+/// Searches for the three-MAJ XOR construction `AND(OR(a, b), !AND(a, b))` and the XNOR
+/// construction `OR(!OR(a, b), AND(a, b))` whose inner OR and AND are used by the root only. Nodes
+/// which are part of a MUX are skipped.
+fn find_xors(
+    network: &impl NetworkWithBackwardEdges<Node = Mig>,
+    outputs: &FxHashSet<Id>,
+    muxes: &FxHashMap<Id, Mux>,
+) -> FxHashMap<Id, XorGate> {
+    let maj_with_constant = |signal: Signal, value: bool| maj_with_constant(network, signal, value);
+    let exclusively_used = |id: Id| network.node_outputs(id).count() == 1 && !outputs.contains(&id);
+    let in_mux: FxHashSet<Id> = muxes
+        .iter()
+        .flat_map(|(root, mux)| [*root, mux.and_nodes[0], mux.and_nodes[1]])
+        .collect();
+
+    let find_xor = |id: Id| -> Option<XorGate> {
+        for (root_constant, inverted) in [(false, false), (true, true)] {
+            let Some([x, y]) = maj_with_constant(Signal::new(id, false), root_constant) else {
+                continue;
+            };
+            for (or, and) in [(x, y), (y, x)] {
+                // the OR is inverted for XNORs, the AND for XORs
+                let (or, and) = if inverted { (or.invert(), and) } else { (or, and.invert()) };
+                let (Some(or_ops), Some(and_ops)) = (maj_with_constant(or, true), maj_with_constant(and, false)) else {
+                    continue;
+                };
+                let same_operands = or_ops == and_ops || or_ops == [and_ops[1], and_ops[0]];
+                if same_operands && or_ops[0].node_id() != or_ops[1].node_id() {
+                    return Some(XorGate {
+                        a: or_ops[0],
+                        b: or_ops[1],
+                        inverted,
+                        inner_nodes: [or.node_id(), and.node_id()],
+                    });
+                }
+            }
+        }
+        None
+    };
+
+    let mut xors: FxHashMap<Id, XorGate> = FxHashMap::default();
+    let mut absorbed: FxHashSet<Id> = FxHashSet::default();
+    for id in reachable_nodes(network) {
+        if in_mux.contains(&id) || absorbed.contains(&id) {
+            continue;
+        }
+        let Some(xor) = find_xor(id) else {
+            continue;
+        };
+        let absorbable = xor.inner_nodes.iter().all(|inner| {
+            exclusively_used(*inner) && !in_mux.contains(inner) && !absorbed.contains(inner) && !xors.contains_key(inner)
+        });
+        if absorbable {
+            absorbed.extend(xor.inner_nodes);
+            xors.insert(id, xor);
         }
     }
+    // XOR roots must not have been absorbed by another XOR
+    xors.retain(|root, _| !absorbed.contains(root));
+    xors
 }
@@ -0,0 +1,51 @@
+//! Management of the rows holding the constants 0 and 1. TRA operands have to be located in the
+//! subarray of the TRA, hence every subarray computing with constants needs constant rows of its
+//! own. These are materialized lazily, i.e. only once a subarray actually uses a constant, and
+//! shared by all of its users afterwards.
+use super::architecture::{RowAddress, SubarrayId};
+use super::program::RowInit;
+use rustc_hash::FxHashMap;
+
+#[derive(Debug, Clone, Default)]
+pub struct ConstantRows {
+    rows: FxHashMap<(SubarrayId, bool), RowAddress>,
+}
+
+impl ConstantRows {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Row of `subarray` holding `value`, if it has been materialized already
+    pub fn get(&self, subarray: SubarrayId, value: bool) -> Option<RowAddress> {
+        self.rows.get(&(subarray, value)).copied()
+    }
+
+    /// Returns the row of `subarray` holding `value`. On first use the row is obtained from
+    /// `alloc` (which has to return a row of `subarray`) and registered in `input_map`, so that
+    /// the host initializes it.
+    pub fn materialize<E>(
+        &mut self,
+        subarray: SubarrayId,
+        value: bool,
+        input_map: &mut Vec<(RowAddress, RowInit)>,
+        alloc: impl FnOnce() -> Result<RowAddress, E>,
+    ) -> Result<RowAddress, E> {
+        if let Some(row) = self.get(subarray, value) {
+            return Ok(row);
+        }
+        let row = alloc()?;
+        self.rows.insert((subarray, value), row);
+        input_map.push((row, RowInit::Constant(value)));
+        Ok(row)
+    }
+
+    /// Nr of materialized constant rows over all subarrays
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+}
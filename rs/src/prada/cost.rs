@@ -0,0 +1,457 @@
+//! Cost model estimating the runtime and energy consumption of programs, independently of how they
+//! have been created (compiled, linked, generated by the [stdlib](super::stdlib) or transformed
+//! afterwards).
+pub use super::extraction::CompilingCost;
+use super::architecture::RowAddress;
+use super::program::{HostTransfer, Instruction, Program};
+use std::fmt::{Display, Formatter};
+
+/// Instructions of the same class are assumed to have the same cost
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum InstructionClass {
+    RowCopy,
+    Tra,
+    Not,
+    MaskedRowCopy,
+    ColumnShift,
+    Xor,
+    And,
+    Or,
+    ControlTra,
+    DccNot,
+}
+
+impl InstructionClass {
+    pub const ALL: [Self; 10] = [
+        Self::RowCopy,
+        Self::Tra,
+        Self::Not,
+        Self::MaskedRowCopy,
+        Self::ColumnShift,
+        Self::Xor,
+        Self::And,
+        Self::Or,
+        Self::ControlTra,
+        Self::DccNot,
+    ];
+
+    /// Returns the class of the given instruction or `None` for loop markers, which are handled by
+    /// the sequencer and don't issue any DRAM commands
+    pub fn of<A>(instruction: &Instruction<A>) -> Option<Self> {
+        Some(match instruction {
+            Instruction::AAPRowCopy(_, _) => Self::RowCopy,
+            Instruction::AAPTRA(_, _, _) => Self::Tra,
+            Instruction::N(_) => Self::Not,
+            Instruction::MaskedRowCopy(_, _, _) => Self::MaskedRowCopy,
+            Instruction::ColumnShift(_, _) => Self::ColumnShift,
+            Instruction::Xor(_, _, _) => Self::Xor,
+            Instruction::And(_, _, _) => Self::And,
+            Instruction::Or(_, _, _) => Self::Or,
+            Instruction::ControlTra(_, _, _) => Self::ControlTra,
+            Instruction::DccNot(_, _) => Self::DccNot,
+            Instruction::LoopBegin(_) | Instruction::LoopEnd => return None,
+        })
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::RowCopy => "row_copy",
+            Self::Tra => "tra",
+            Self::Not => "not",
+            Self::MaskedRowCopy => "masked_row_copy",
+            Self::ColumnShift => "column_shift",
+            Self::Xor => "xor",
+            Self::And => "and",
+            Self::Or => "or",
+            Self::ControlTra => "control_tra",
+            Self::DccNot => "dcc_not",
+        }
+    }
+
+    /// Inverse of [Self::name]
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|class| class.name() == name)
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+impl Display for InstructionClass {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Cost of executing a single instruction of each [InstructionClass]
+#[derive(Debug, Clone)]
+pub struct CostModel {
+    costs: [CompilingCost; InstructionClass::ALL.len()],
+    /// Where the costs come from, `None` for the built-in defaults
+    pub provenance: Option<CostProvenance>,
+}
+
+/// Origin of the costs of a calibrated [CostModel]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CostProvenance {
+    /// Description of the measurements, e.g. the path of the CSV file or the experiment setup
+    pub source: String,
+    /// Nr of measured executions of every calibrated class. Classes without measurements keep
+    /// their default cost.
+    pub samples: Vec<(InstructionClass, u64)>,
+}
+
+impl Default for CostModel {
+    fn default() -> Self {
+        let cost = |runtime, energy_consumption| CompilingCost { runtime, energy_consumption };
+        Self {
+            // in the order of `InstructionClass::ALL`
+            costs: [
+                cost(100, 50),
+                cost(49, 150),
+                cost(35, 100),
+                cost(135, 75),
+                cost(60, 80),
+                cost(70, 170),
+                cost(55, 160),
+                cost(55, 160),
+                cost(100, 180),
+                cost(100, 60),
+            ],
+            provenance: None,
+        }
+    }
+}
+
+impl CostModel {
+    pub fn cost(&self, class: InstructionClass) -> CompilingCost {
+        self.costs[class.index()]
+    }
+
+    pub fn set_cost(&mut self, class: InstructionClass, cost: CompilingCost) {
+        self.costs[class.index()] = cost;
+    }
+
+    /// Cost of executing the given instruction once
+    pub fn instruction_cost<A>(&self, instruction: &Instruction<A>) -> CompilingCost {
+        InstructionClass::of(instruction).map_or(
+            CompilingCost { runtime: 0, energy_consumption: 0 },
+            |class| self.cost(class),
+        )
+    }
+
+    /// Fits the costs to measured latencies and energies, given as CSV with the columns
+    /// `operation` (an [InstructionClass::name]), `latency` (in ns), `energy` (in mJ/KOps) and
+    /// optionally `count`, in any order and named in a header line. A line with a count of `n`
+    /// contains the total latency and energy of `n` executions (1 if the column is missing).
+    ///
+    /// The cost of every class is fitted by least squares (`sum(count * total) / sum(count^2)`),
+    /// i.e. the mean of the samples if every line describes a single execution. Classes without
+    /// measurements keep their default cost.
+    pub fn calibrate(measurements: &str, source: impl Into<String>) -> Result<Self, &'static str> {
+        let mut lines = measurements.lines().map(str::trim).filter(|line| !line.is_empty());
+        let header: Vec<&str> = lines.next().ok_or("missing header")?.split(',').map(str::trim).collect();
+        let column = |name: &str| header.iter().position(|column| *column == name);
+        let operation_column = column("operation").ok_or("missing column `operation`")?;
+        let latency_column = column("latency").ok_or("missing column `latency`")?;
+        let energy_column = column("energy").ok_or("missing column `energy`")?;
+        let count_column = column("count");
+
+        // per class: (sum of count * latency, sum of count * energy, sum of count^2, sum of count)
+        let mut sums = [(0u128, 0u128, 0u128, 0u64); InstructionClass::ALL.len()];
+        for line in lines {
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            let field = |idx: usize| fields.get(idx).copied().ok_or("missing field");
+            let class = InstructionClass::from_name(field(operation_column)?).ok_or("unknown operation")?;
+            let latency: u64 = field(latency_column)?.parse().map_err(|_| "invalid latency")?;
+            let energy: u64 = field(energy_column)?.parse().map_err(|_| "invalid energy")?;
+            let count: u64 = match count_column {
+                Some(idx) => field(idx)?.parse().map_err(|_| "invalid count")?,
+                None => 1,
+            };
+            let sum = &mut sums[class.index()];
+            sum.0 += count as u128 * latency as u128;
+            sum.1 += count as u128 * energy as u128;
+            sum.2 += count as u128 * count as u128;
+            sum.3 += count;
+        }
+
+        let mut model = Self::default();
+        let mut samples = vec!();
+        for class in InstructionClass::ALL {
+            let (latency, energy, count_squared, count) = sums[class.index()];
+            if count == 0 {
+                continue;
+            }
+            // rounded to the nearest integer
+            let fit = |total: u128| ((2 * total + count_squared) / (2 * count_squared)) as u64;
+            model.set_cost(class, CompilingCost { runtime: fit(latency), energy_consumption: fit(energy) });
+            samples.push((class, count));
+        }
+        model.provenance = Some(CostProvenance { source: source.into(), samples });
+        Ok(model)
+    }
+
+    pub fn estimate(&self, program: &Program) -> CostReport {
+        self.estimate_instructions(&program.instructions)
+    }
+
+    /// Estimates the cost of executing the given instructions, taking into account that loop
+    /// bodies are executed multiple times. Unterminated loops are treated as if they were
+    /// terminated at the end. Costs saturate at `u64::MAX` instead of overflowing, e.g. for deeply
+    /// nested loops of loaded programs.
+    pub fn estimate_instructions<A>(&self, instructions: &[Instruction<A>]) -> CostReport {
+        let mut report = CostReport::default();
+        // nr of executions of the instructions of every currently open loop body
+        let mut executions = vec!(1u64);
+        for instruction in instructions {
+            let current = *executions.last().unwrap();
+            match instruction {
+                Instruction::LoopBegin(count) => executions.push(current.saturating_mul(*count)),
+                Instruction::LoopEnd if executions.len() > 1 => {
+                    executions.pop();
+                }
+                _ => {
+                    let Some(class) = InstructionClass::of(instruction) else {
+                        continue;
+                    };
+                    let cost = self.cost(class);
+                    let class_cost = &mut report.per_class[class.index()];
+                    let (runtime, energy_consumption) =
+                        (current.saturating_mul(cost.runtime), current.saturating_mul(cost.energy_consumption));
+                    class_cost.executions = class_cost.executions.saturating_add(current);
+                    class_cost.runtime = class_cost.runtime.saturating_add(runtime);
+                    class_cost.energy_consumption = class_cost.energy_consumption.saturating_add(energy_consumption);
+                    report.runtime = report.runtime.saturating_add(runtime);
+                    report.energy_consumption = report.energy_consumption.saturating_add(energy_consumption);
+                }
+            }
+        }
+        report
+    }
+}
+
+/// Estimated cost of a program, in total and by [InstructionClass]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CostReport {
+    /// in ns
+    pub runtime: u64,
+    /// in mJ/KOps
+    pub energy_consumption: u64,
+    per_class: [ClassCost; InstructionClass::ALL.len()],
+}
+
+/// Share of a single [InstructionClass] in a [CostReport]
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct ClassCost {
+    /// Nr of executed instructions of the class (loop bodies are counted once per iteration)
+    pub executions: u64,
+    pub runtime: u64,
+    pub energy_consumption: u64,
+}
+
+impl CostReport {
+    pub fn class(&self, class: InstructionClass) -> ClassCost {
+        self.per_class[class.index()]
+    }
+
+    pub fn total(&self) -> CompilingCost {
+        CompilingCost { runtime: self.runtime, energy_consumption: self.energy_consumption }
+    }
+}
+
+impl Display for CostReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{:<16} {:>10} {:>12} {:>12}", "class", "executions", "runtime", "energy")?;
+        for class in InstructionClass::ALL {
+            let cost = self.class(class);
+            if cost.executions > 0 {
+                writeln!(f, "{:<16} {:>10} {:>12} {:>12}", class, cost.executions, cost.runtime, cost.energy_consumption)?;
+            }
+        }
+        write!(f, "{:<16} {:>10} {:>12} {:>12}", "total", "", self.runtime, self.energy_consumption)
+    }
+}
+
+/// Simple model of evaluating a network gate by gate (one bitwise MAJ per node) on a CPU, which
+/// processes `word_bits` bitlines per operation while a PIM program processes all `bitlines` of a
+/// row at once. Meant for quoting speedups and energy ratios, calibrate it for the platform at hand.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct CpuBaseline {
+    /// Runtime of evaluating a MAJ on one word (in ns)
+    pub ns_per_gate: f64,
+    /// Energy of evaluating a MAJ on one word (in the unit of [CostModel], mJ/KOps)
+    pub energy_per_gate: f64,
+    pub word_bits: u64,
+    /// Nr of bitlines of a DRAM row, i.e. the nr of independent evaluations of a PIM program
+    pub bitlines: u64,
+}
+
+impl Default for CpuBaseline {
+    fn default() -> Self {
+        Self { ns_per_gate: 1.0, energy_per_gate: 1.0, word_bits: 64, bitlines: 65536 }
+    }
+}
+
+impl CpuBaseline {
+    /// Estimates evaluating a network with `gates` MAJs on all bitlines
+    pub fn estimate(&self, gates: u64) -> CpuEstimate {
+        let words = self.bitlines.div_ceil(self.word_bits.max(1)) as f64;
+        CpuEstimate {
+            gates,
+            runtime: gates as f64 * words * self.ns_per_gate,
+            energy_consumption: gates as f64 * words * self.energy_per_gate,
+        }
+    }
+}
+
+/// Estimated cost of evaluating a network on a [CpuBaseline]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct CpuEstimate {
+    pub gates: u64,
+    /// in ns
+    pub runtime: f64,
+    /// in mJ/KOps
+    pub energy_consumption: f64,
+}
+
+impl CpuEstimate {
+    /// CPU runtime divided by the estimated runtime of `program`
+    pub fn speedup(&self, program: &Program) -> f64 {
+        self.runtime / program.runtime_estimate.max(1) as f64
+    }
+
+    /// CPU energy divided by the estimated energy consumption of `program`
+    pub fn energy_ratio(&self, program: &Program) -> f64 {
+        self.energy_consumption / program.energy_consumption_estimate.max(1) as f64
+    }
+
+    /// CPU runtime divided by the estimated runtime of `program` including loading its inputs and
+    /// reading back its outputs
+    pub fn end_to_end_speedup(&self, program: &Program, transfers: &TransferEstimate) -> f64 {
+        self.runtime / (program.runtime_estimate as f64 + transfers.runtime).max(1.0)
+    }
+
+    /// Single line comparing the CPU to `program`, with and without the transfers from and to the
+    /// host
+    pub fn report_line(&self, program: &Program, transfers: &TransferEstimate) -> String {
+        format!(
+            "baseline CPU ({} gates): {:.0} ns, {:.0} mJ/KOps; PIM speedup {:.2}x, energy ratio {:.2}x; end-to-end speedup {:.2}x ({} rows written, {} rows read by the host)",
+            self.gates,
+            self.runtime,
+            self.energy_consumption,
+            self.speedup(program),
+            self.energy_ratio(program),
+            self.end_to_end_speedup(program, transfers),
+            transfers.rows_written,
+            transfers.rows_read
+        )
+    }
+}
+
+/// Bandwidth-based model of moving rows between the host and the DRAM module using regular writes
+/// and reads, which is needed for loading the inputs of a program and reading back its outputs
+/// unless they already reside in DRAM
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct HostTransferModel {
+    /// Nr of bytes of a row
+    pub row_bytes: u64,
+    /// Bandwidth between host and DRAM module (in bytes per ns, i.e. GB/s)
+    pub bytes_per_ns: f64,
+    /// Energy of moving a single byte (in mJ/KOps)
+    pub energy_per_byte: f64,
+    /// Fixed latency of every row transfer, e.g. opening the row (in ns)
+    pub row_latency: f64,
+}
+
+impl Default for HostTransferModel {
+    fn default() -> Self {
+        // a single DDR4-2400 channel and rows of 65536 bitlines (see [CpuBaseline::bitlines])
+        Self { row_bytes: 8192, bytes_per_ns: 19.2, energy_per_byte: 0.02, row_latency: 50.0 }
+    }
+}
+
+impl HostTransferModel {
+    pub fn estimate(&self, transfers: &[HostTransfer]) -> TransferEstimate {
+        let rows_written =
+            transfers.iter().filter(|transfer| matches!(transfer, HostTransfer::Write(_))).count() as u64;
+        let rows_read = transfers.len() as u64 - rows_written;
+        let rows = (rows_written + rows_read) as f64;
+        let bytes = rows * self.row_bytes as f64;
+        TransferEstimate {
+            rows_written,
+            rows_read,
+            runtime: rows * self.row_latency + bytes / self.bytes_per_ns,
+            energy_consumption: bytes * self.energy_per_byte,
+        }
+    }
+}
+
+/// Estimated cost of the transfers between the host and a program, see [HostTransferModel]
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct TransferEstimate {
+    pub rows_written: u64,
+    pub rows_read: u64,
+    /// in ns
+    pub runtime: f64,
+    /// in mJ/KOps
+    pub energy_consumption: f64,
+}
+
+/// Data-dependent energy model of the charge moved by activating rows, complementing the static
+/// per-instruction energies of the [CostModel]. Every activated row charges all of its bitlines
+/// from the precharge level (half the supply voltage) to full swing, and every bitline whose value
+/// toggles (see [Simulator::bit_flips](super::simulation::Simulator::bit_flips)) additionally
+/// recharges its cell. Since the simulator models 64 bitlines, their toggles are taken as sample of
+/// all bitlines of a row.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ChargeModel {
+    /// in fF
+    pub bitline_capacitance: f64,
+    /// in fF
+    pub cell_capacitance: f64,
+    /// in V
+    pub supply_voltage: f64,
+    /// Nr of bitlines of a row
+    pub bitlines: u64,
+    /// Data-independent energy of activating a row, e.g. of driving its wordline (in pJ)
+    pub wordline_energy: f64,
+}
+
+impl Default for ChargeModel {
+    fn default() -> Self {
+        // DDR4 with rows of 65536 bitlines (see [CpuBaseline::bitlines])
+        Self {
+            bitline_capacitance: 85.0,
+            cell_capacitance: 22.0,
+            supply_voltage: 1.2,
+            bitlines: 65536,
+            wordline_energy: 20.0,
+        }
+    }
+}
+
+impl ChargeModel {
+    /// Nr of rows activated by `instruction`, i.e. its distinct operands plus the control row of a
+    /// [Instruction::ControlTra]
+    pub fn activations(instruction: &Instruction) -> u64 {
+        let mut rows: Vec<RowAddress> = instruction.used_addresses().collect();
+        rows.sort_by_key(|row| row.0);
+        rows.dedup();
+        rows.len() as u64 + matches!(instruction, Instruction::ControlTra(..)) as u64
+    }
+
+    /// Energy of a single row activation (in pJ)
+    pub fn activation_energy(&self) -> f64 {
+        let half_swing = self.supply_voltage / 2.0;
+        // fF * V^2 = fJ
+        self.wordline_energy + self.bitlines as f64 * self.bitline_capacitance * half_swing * half_swing / 1000.0
+    }
+
+    /// Energy of the given nr of toggles of the 64 simulated bitlines (in pJ)
+    pub fn toggle_energy(&self, bit_flips: u64) -> f64 {
+        let toggles = bit_flips as f64 * self.bitlines as f64 / 64.0;
+        toggles * self.cell_capacitance * self.supply_voltage * self.supply_voltage / 1000.0
+    }
+}
@@ -0,0 +1,191 @@
+//! Coverage of the rewrite rules and compiler code paths (spilling, inverted outputs, ...)
+//! exercised by the compilations of a process, e.g. a test binary. Helps to find paths which are
+//! never tested.
+//!
+//! If the environment variable `PRADA_COVERAGE_DIR` is set, the coverage of the process is written
+//! to `<dir>/<process id>.txt` after every compilation, so that the coverage of several test
+//! binaries can be merged afterwards (see [CoverageReport::merge]).
+use std::fmt::{Display, Formatter};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{LazyLock, Mutex};
+
+use rustc_hash::FxHashMap;
+
+use super::rules::{REWRITE_RULES, SHARING_GUIDED_REWRITE_RULES};
+
+/// Compiler code path whose execution is counted
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum CodePath {
+    /// A value has been evacuated into the spill subarray
+    Spill,
+    /// A spilled value has been copied back
+    Reload,
+    /// A value has been dropped to be recomputed later
+    Rematerialization,
+    /// A row has been copied between subarrays while linking fragments
+    InterSubarrayCopy,
+    /// An output has been computed by negating its inverse in place
+    InvertedOutput,
+    /// An output has been computed by negating a copy of its inverse, which is an output too
+    InvertedOutputCopy,
+    /// An input or constant which no MAJ uses has been placed for being an output
+    LeafOutput,
+    /// A value has been copied into the row demanded by the placement
+    PlacedValue,
+    /// A cycle of output copies has been broken using a temporary row
+    OutputCopyCycle,
+    Mux,
+    Xor,
+    /// A MAJ with a constant operand has been lowered onto a native AND resp. OR
+    AndOr,
+    /// Inverted signals have been emulated by dual MAJs
+    EmulatedInversion,
+}
+
+impl CodePath {
+    pub const ALL: [Self; 13] = [
+        Self::Spill,
+        Self::Reload,
+        Self::Rematerialization,
+        Self::InterSubarrayCopy,
+        Self::InvertedOutput,
+        Self::InvertedOutputCopy,
+        Self::LeafOutput,
+        Self::PlacedValue,
+        Self::OutputCopyCycle,
+        Self::Mux,
+        Self::Xor,
+        Self::AndOr,
+        Self::EmulatedInversion,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Spill => "spill",
+            Self::Reload => "reload",
+            Self::Rematerialization => "rematerialization",
+            Self::InterSubarrayCopy => "inter_subarray_copy",
+            Self::InvertedOutput => "inverted_output",
+            Self::InvertedOutputCopy => "inverted_output_copy",
+            Self::LeafOutput => "leaf_output",
+            Self::PlacedValue => "placed_value",
+            Self::OutputCopyCycle => "output_copy_cycle",
+            Self::Mux => "mux",
+            Self::Xor => "xor",
+            Self::AndOr => "and_or",
+            Self::EmulatedInversion => "emulated_inversion",
+        }
+    }
+}
+
+static PATH_HITS: [AtomicU64; CodePath::ALL.len()] = [const { AtomicU64::new(0) }; CodePath::ALL.len()];
+
+static RULE_HITS: LazyLock<Mutex<FxHashMap<String, u64>>> = LazyLock::new(Mutex::default);
+
+/// Records that the given code path has been executed
+pub fn hit(path: CodePath) {
+    PATH_HITS[path as usize].fetch_add(1, Ordering::Relaxed);
+}
+
+/// Records that the given rewrite rule has been applied `count` times
+pub fn hit_rule(name: &str, count: u64) {
+    *RULE_HITS.lock().unwrap().entry(name.to_string()).or_insert(0) += count;
+}
+
+/// Resets all counters
+pub fn reset() {
+    PATH_HITS.iter().for_each(|hits| hits.store(0, Ordering::Relaxed));
+    RULE_HITS.lock().unwrap().clear();
+}
+
+/// Returns the coverage of this process, including all rules and code paths never exercised
+pub fn report() -> CoverageReport {
+    let mut rules: FxHashMap<String, u64> = REWRITE_RULES
+        .iter()
+        .chain(SHARING_GUIDED_REWRITE_RULES.iter())
+        .map(|rule| (rule.name.to_string(), 0))
+        .collect();
+    for (name, hits) in RULE_HITS.lock().unwrap().iter() {
+        *rules.entry(name.clone()).or_insert(0) += hits;
+    }
+    let mut rules: Vec<(String, u64)> = rules.into_iter().collect();
+    rules.sort();
+    CoverageReport {
+        paths: CodePath::ALL
+            .into_iter()
+            .map(|path| (path.name().to_string(), PATH_HITS[path as usize].load(Ordering::Relaxed)))
+            .collect(),
+        rules,
+    }
+}
+
+/// Writes the coverage of this process into `PRADA_COVERAGE_DIR`, if set
+pub(crate) fn record_to_env() {
+    let Some(dir) = std::env::var_os("PRADA_COVERAGE_DIR").map(PathBuf::from) else {
+        return;
+    };
+    let path = dir.join(format!("{}.txt", std::process::id()));
+    if let Err(err) = std::fs::create_dir_all(&dir).and_then(|_| std::fs::write(&path, report().to_string())) {
+        eprintln!("could not write coverage to {}: {err}", path.display());
+    }
+}
+
+/// Nr of executions of every code path and applications of every rewrite rule
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CoverageReport {
+    pub paths: Vec<(String, u64)>,
+    pub rules: Vec<(String, u64)>,
+}
+
+impl CoverageReport {
+    /// Parses a report in the format written by its `Display` implementation
+    pub fn parse(text: &str) -> Result<Self, &'static str> {
+        let mut report = Self::default();
+        for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            let mut fields = line.split_whitespace();
+            let (Some(kind), Some(name), Some(hits), None) = (fields.next(), fields.next(), fields.next(), fields.next()) else {
+                return Err("expected `<path|rule> <name> <hits>`");
+            };
+            let hits = hits.parse().map_err(|_| "invalid nr of hits")?;
+            match kind {
+                "path" => report.paths.push((name.to_string(), hits)),
+                "rule" => report.rules.push((name.to_string(), hits)),
+                _ => return Err("unknown kind of coverage entry"),
+            }
+        }
+        Ok(report)
+    }
+
+    /// Adds the hits of `other`, e.g. of another test binary
+    pub fn merge(&mut self, other: &CoverageReport) {
+        for (entries, other_entries) in [(&mut self.paths, &other.paths), (&mut self.rules, &other.rules)] {
+            for (name, hits) in other_entries {
+                match entries.iter_mut().find(|(entry, _)| entry == name) {
+                    Some((_, entry_hits)) => *entry_hits += hits,
+                    None => entries.push((name.clone(), *hits)),
+                }
+            }
+        }
+    }
+
+    /// Names of the code paths (`path <name>`) and rules (`rule <name>`) which have never been
+    /// exercised
+    pub fn uncovered(&self) -> Vec<String> {
+        let paths = self.paths.iter().filter(|(_, hits)| *hits == 0).map(|(name, _)| format!("path {name}"));
+        let rules = self.rules.iter().filter(|(_, hits)| *hits == 0).map(|(name, _)| format!("rule {name}"));
+        paths.chain(rules).collect()
+    }
+}
+
+impl Display for CoverageReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (name, hits) in &self.paths {
+            writeln!(f, "path {name} {hits}")?;
+        }
+        for (name, hits) in &self.rules {
+            writeln!(f, "rule {name} {hits}")?;
+        }
+        Ok(())
+    }
+}
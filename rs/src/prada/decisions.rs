@@ -0,0 +1,245 @@
+//! Log of the decisions the compiler takes (which node is computed next, which rows are allocated
+//! and freed, why copies are inserted), written as JSON lines so that the compiler's behavior can be
+//! post-processed and visualized without modifying it.
+//!
+//! A recorded log can be replayed (see [Replay]) when compiling a modified network or for a
+//! modified cost model, forcing the same candidate order and row choices wherever possible, which
+//! isolates the effect of a single change.
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use eggmock::Id;
+
+use super::architecture::RowAddress;
+
+/// Reason for inserting a row copy
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CopyReason {
+    /// The inverse of a present value is needed, which is negated on a copy
+    Negation,
+    /// An operand is still needed after the TRA destroying it
+    LiveOperand,
+    /// An operand is stored in a pinned row, which the TRA must not destroy
+    PinnedOperand,
+    /// A value is evacuated into the spill subarray
+    Spill,
+    /// A spilled value is copied back
+    Reload,
+    /// An operand of a dropped value is copied for recomputing the value
+    Rematerialization,
+    /// The else-branch of a MUX is copied into the result row before the masked copy
+    Mux,
+    /// A value is moved into the row demanded by the placement
+    Placement,
+    /// An output is negated on a copy, as its inverse is needed as well (being an output itself,
+    /// placed or pinned)
+    InvertedOutput,
+    /// An output is moved into its packed row (see
+    /// [CompileOptions::output_base](super::compilation::CompileOptions::output_base))
+    OutputPacking,
+}
+
+impl CopyReason {
+    pub const ALL: [Self; 10] = [
+        Self::Negation,
+        Self::LiveOperand,
+        Self::PinnedOperand,
+        Self::Spill,
+        Self::Reload,
+        Self::Rematerialization,
+        Self::Mux,
+        Self::Placement,
+        Self::InvertedOutput,
+        Self::OutputPacking,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Negation => "negation",
+            Self::LiveOperand => "live_operand",
+            Self::PinnedOperand => "pinned_operand",
+            Self::Spill => "spill",
+            Self::Reload => "reload",
+            Self::Rematerialization => "rematerialization",
+            Self::Mux => "mux",
+            Self::Placement => "placement",
+            Self::InvertedOutput => "inverted_output",
+            Self::OutputPacking => "output_packing",
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Decision {
+    /// `node` has been chosen among `candidates` ready nodes. Candidates are ranked by the nr of
+    /// operands missing in the required polarity, then by `priority` (higher first, see
+    /// [SchedulingPolicy](super::SchedulingPolicy)), then by their nr of `users` and finally
+    /// non-outputs first.
+    Schedule {
+        node: Id,
+        candidates: usize,
+        missing_operands: u8,
+        priority: usize,
+        users: usize,
+        output: bool,
+    },
+    RowAllocated { row: RowAddress },
+    RowFreed { row: RowAddress },
+    /// The value in `row` has been evacuated (`spill_row` is set) or dropped to be recomputed
+    /// later, as no row was free
+    Evicted { row: RowAddress, spill_row: Option<RowAddress> },
+    Copy { from: RowAddress, to: RowAddress, reason: CopyReason },
+}
+
+impl Decision {
+    /// Single line JSON object with the kind of decision in field `event`
+    pub fn to_json(&self) -> String {
+        match *self {
+            Decision::Schedule { node, candidates, missing_operands, priority, users, output } => format!(
+                "{{\"event\": \"schedule\", \"node\": {}, \"candidates\": {candidates}, \"missing_operands\": {missing_operands}, \"priority\": {priority}, \"users\": {users}, \"output\": {output}}}",
+                usize::from(eggmock::egg::Id::from(node))
+            ),
+            Decision::RowAllocated { row } => format!("{{\"event\": \"row_allocated\", \"row\": {}}}", row.0),
+            Decision::RowFreed { row } => format!("{{\"event\": \"row_freed\", \"row\": {}}}", row.0),
+            Decision::Evicted { row, spill_row } => format!(
+                "{{\"event\": \"evicted\", \"row\": {}, \"spill_row\": {}}}",
+                row.0,
+                spill_row.map_or("null".to_string(), |spill_row| spill_row.0.to_string())
+            ),
+            Decision::Copy { from, to, reason } => format!(
+                "{{\"event\": \"copy\", \"from\": {}, \"to\": {}, \"reason\": \"{}\"}}",
+                from.0,
+                to.0,
+                reason.name()
+            ),
+        }
+    }
+
+    /// Inverse of [Self::to_json]
+    pub fn parse(line: &str) -> Result<Self, &'static str> {
+        let fields = json_fields(line)?;
+        let field = |name: &str| -> Result<&str, &'static str> {
+            fields
+                .iter()
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| *value)
+                .ok_or("missing field in decision")
+        };
+        let number = |name: &str| -> Result<u64, &'static str> {
+            field(name)?.parse().map_err(|_| "expected a number")
+        };
+        let row = |name: &str| number(name).map(RowAddress);
+        Ok(match field("event")? {
+            "schedule" => Decision::Schedule {
+                node: Id::from(eggmock::egg::Id::from(number("node")? as usize)),
+                candidates: number("candidates")? as usize,
+                missing_operands: number("missing_operands")? as u8,
+                priority: number("priority")? as usize,
+                users: number("users")? as usize,
+                output: field("output")?.parse().map_err(|_| "expected a boolean")?,
+            },
+            "row_allocated" => Decision::RowAllocated { row: row("row")? },
+            "row_freed" => Decision::RowFreed { row: row("row")? },
+            "evicted" => Decision::Evicted {
+                row: row("row")?,
+                spill_row: if field("spill_row")? == "null" { None } else { Some(row("spill_row")?) },
+            },
+            "copy" => {
+                let reason = field("reason")?;
+                Decision::Copy {
+                    from: row("from")?,
+                    to: row("to")?,
+                    reason: CopyReason::ALL
+                        .into_iter()
+                        .find(|candidate| candidate.name() == reason)
+                        .ok_or("unknown copy reason")?,
+                }
+            }
+            _ => return Err("unknown decision"),
+        })
+    }
+}
+
+/// Splits a flat JSON object with string, number, boolean or null values (as written by
+/// [Decision::to_json]) into its fields, with strings unquoted
+fn json_fields(line: &str) -> Result<Vec<(&str, &str)>, &'static str> {
+    let body = line
+        .trim()
+        .strip_prefix('{')
+        .and_then(|line| line.strip_suffix('}'))
+        .ok_or("expected a JSON object")?;
+    body.split(',')
+        .filter(|field| !field.trim().is_empty())
+        .map(|field| {
+            let (key, value) = field.split_once(':').ok_or("expected `\"key\": value`")?;
+            let key = key.trim().strip_prefix('"').and_then(|key| key.strip_suffix('"')).ok_or("expected a quoted key")?;
+            let value = value.trim();
+            Ok((key, value.strip_prefix('"').and_then(|value| value.strip_suffix('"')).unwrap_or(value)))
+        })
+        .collect()
+}
+
+/// Parses a log written by [write_jsonl], ignoring empty lines
+pub fn parse_jsonl(text: &str) -> Result<Vec<Decision>, &'static str> {
+    text.lines().filter(|line| !line.trim().is_empty()).map(Decision::parse).collect()
+}
+
+/// Decisions of a recorded log which are followed when compiling again, see
+/// [compile_replaying](super::compilation::compile_replaying). Nodes are identified by their id,
+/// hence the modified network should keep the ids of unchanged nodes (e.g. by only appending
+/// nodes). Recorded decisions which can't be followed, e.g. because the node isn't ready or the
+/// row isn't free, are skipped and counted as divergences.
+#[derive(Debug, Clone, Default)]
+pub struct Replay {
+    schedule: VecDeque<Id>,
+    rows: VecDeque<RowAddress>,
+    pub report: ReplayReport,
+}
+
+/// How closely a compilation followed a [Replay]
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct ReplayReport {
+    pub followed_schedule: u64,
+    pub diverged_schedule: u64,
+    pub followed_rows: u64,
+    pub diverged_rows: u64,
+}
+
+impl Replay {
+    pub fn new(decisions: &[Decision]) -> Self {
+        let mut replay = Self::default();
+        for decision in decisions {
+            match *decision {
+                Decision::Schedule { node, .. } => replay.schedule.push_back(node),
+                Decision::RowAllocated { row } => replay.rows.push_back(row),
+                _ => {}
+            }
+        }
+        replay
+    }
+
+    /// Returns the next recorded node to compute
+    pub(crate) fn next_node(&mut self) -> Option<Id> {
+        self.schedule.pop_front()
+    }
+
+    /// Returns the next recorded row to allocate
+    pub(crate) fn next_row(&mut self) -> Option<RowAddress> {
+        self.rows.pop_front()
+    }
+}
+
+pub fn write_jsonl(decisions: &[Decision], mut out: impl Write) -> io::Result<()> {
+    for decision in decisions {
+        writeln!(out, "{}", decision.to_json())?;
+    }
+    Ok(())
+}
+
+pub fn write_to_file(decisions: &[Decision], path: &Path) -> io::Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    write_jsonl(decisions, &mut out)?;
+    out.flush()
+}
@@ -0,0 +1,91 @@
+//! Maps keyed by nodes resp. signals, stored in vectors indexed by the node index (and the
+//! polarity). The ids of eggmock networks are dense, hence this saves hashing on every access of
+//! the compiler's per-value state, which dominates compilation of large networks.
+use eggmock::{Id, Signal};
+
+pub fn node_index(id: Id) -> usize {
+    usize::from(eggmock::egg::Id::from(id))
+}
+
+pub fn node_id(index: usize) -> Id {
+    Id::from(eggmock::egg::Id::from(index))
+}
+
+/// Map from node ids to `T`, growing with the largest id inserted
+#[derive(Debug, Clone)]
+pub struct NodeMap<T> {
+    values: Vec<Option<T>>,
+}
+
+impl<T> Default for NodeMap<T> {
+    fn default() -> Self {
+        Self { values: vec!() }
+    }
+}
+
+impl<T> NodeMap<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, id: Id) -> Option<&T> {
+        self.values.get(node_index(id))?.as_ref()
+    }
+
+    pub fn get_mut(&mut self, id: Id) -> Option<&mut T> {
+        self.values.get_mut(node_index(id))?.as_mut()
+    }
+
+    pub fn get_or_insert_with(&mut self, id: Id, value: impl FnOnce() -> T) -> &mut T {
+        let index = node_index(id);
+        if self.values.len() <= index {
+            self.values.resize_with(index + 1, || None);
+        }
+        self.values[index].get_or_insert_with(value)
+    }
+}
+
+/// Map from signals to `T`, storing both polarities of a node next to each other
+#[derive(Debug, Clone)]
+pub struct SignalMap<T> {
+    values: NodeMap<[Option<T>; 2]>,
+}
+
+impl<T> Default for SignalMap<T> {
+    fn default() -> Self {
+        Self { values: NodeMap::new() }
+    }
+}
+
+impl<T> SignalMap<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, signal: &Signal) -> Option<&T> {
+        self.values.get(signal.node_id())?[signal.is_inverted() as usize].as_ref()
+    }
+
+    pub fn contains_key(&self, signal: &Signal) -> bool {
+        self.get(signal).is_some()
+    }
+
+    pub fn insert(&mut self, signal: Signal, value: T) -> Option<T> {
+        self.values.get_or_insert_with(signal.node_id(), || [None, None])[signal.is_inverted() as usize].replace(value)
+    }
+
+    pub fn remove(&mut self, signal: &Signal) -> Option<T> {
+        self.values.get_mut(signal.node_id())?[signal.is_inverted() as usize].take()
+    }
+
+    /// Entries in the order of the node index, non-inverted signals first
+    pub fn iter(&self) -> impl Iterator<Item = (Signal, &T)> {
+        self.values.values.iter().enumerate().flat_map(|(index, polarities)| {
+            polarities.iter().flat_map(move |polarities| {
+                polarities.iter().enumerate().filter_map(move |(inverted, value)| {
+                    value.as_ref().map(|value| (Signal::new(node_id(index), inverted == 1), value))
+                })
+            })
+        })
+    }
+}
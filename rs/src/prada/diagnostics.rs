@@ -0,0 +1,337 @@
+//! Warnings and errors about compiled programs which don't prevent compilation, e.g. constant
+//! outputs or programs close to running out of rows. They are collected into
+//! [Program::diagnostics] while compiling and can be printed as text or written as JSON (see
+//! [CompilerSettings::diagnostics_path](super::CompilerSettings::diagnostics_path)), so that
+//! tools can act on them without parsing the compiler's output.
+//!
+//! Depending on the [Strictness] of a compilation, diagnostics may be escalated into errors failing
+//! the compilation, e.g. for production use where emulated operations or possibly unreliable TRAs
+//! are not acceptable.
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::ops;
+use std::path::Path;
+
+use eggmock::{Mig, Network};
+use rustc_hash::FxHashMap;
+
+use super::architecture::{RowAddress, ROW_ID_BITMASK};
+use super::legalization::LegalizationReport;
+use super::program::{Instruction, Program};
+
+/// Time a row keeps its content without being refreshed (in ns), i.e. the refresh window of DDR4.
+/// PUD operation sequences may hold off regular refreshes, so values kept longer than this by a
+/// single invocation risk losing their content.
+pub const RETENTION_TIME: u64 = 64_000_000;
+
+/// Share of the rows of a subarray from which on the row usage is reported
+pub const ROW_BUDGET_THRESHOLD: f64 = 0.9;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl Severity {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Warning => "warning",
+            Self::Error => "error",
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum DiagnosticCode {
+    /// An output doesn't depend on any input
+    ConstantOutput,
+    /// The program holds values in at least [ROW_BUDGET_THRESHOLD] of the rows of a subarray at
+    /// the same time
+    RowBudget,
+    /// A value is kept in a row for longer than [RETENTION_TIME]
+    RetentionRisk,
+    /// Operations the architecture lacks (e.g. in-place negation) have been emulated
+    CapabilityEmulation,
+    /// A TRA activates rows which aren't among the
+    /// [verified_tra_groups](super::architecture::PRADAArchitecture::verified_tra_groups)
+    UnverifiedTraGrouping,
+    /// The estimated energy consumption exceeds the
+    /// [energy_budget](super::CompilerSettings::energy_budget), even when extracting with the
+    /// energy objective
+    EnergyBudget,
+}
+
+impl DiagnosticCode {
+    pub const ALL: [Self; 6] = [
+        Self::ConstantOutput,
+        Self::RowBudget,
+        Self::RetentionRisk,
+        Self::CapabilityEmulation,
+        Self::UnverifiedTraGrouping,
+        Self::EnergyBudget,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::ConstantOutput => "constant_output",
+            Self::RowBudget => "row_budget",
+            Self::RetentionRisk => "retention_risk",
+            Self::CapabilityEmulation => "capability_emulation",
+            Self::UnverifiedTraGrouping => "unverified_tra_grouping",
+            Self::EnergyBudget => "energy_budget",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|code| code.name() == name)
+    }
+}
+
+/// What a [Diagnostic] refers to
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Location {
+    /// Output of the network resp. program by index
+    Output(usize),
+    /// Index into [Program::instructions]
+    Instruction(usize),
+    Row(RowAddress),
+}
+
+impl Display for Location {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Location::Output(output) => write!(f, "output {output}"),
+            Location::Instruction(instruction) => write!(f, "instruction {instruction}"),
+            Location::Row(row) => write!(f, "row {row}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: DiagnosticCode,
+    pub location: Option<Location>,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn warning(code: DiagnosticCode, location: Option<Location>, message: impl Into<String>) -> Self {
+        Self { severity: Severity::Warning, code, location, message: message.into() }
+    }
+
+    /// Single line JSON object of the diagnostic, with the location as object of its `kind` and
+    /// `index` (outputs, instructions) resp. `row`
+    pub fn to_json(&self) -> String {
+        let location = match self.location {
+            None => "null".to_string(),
+            Some(Location::Output(output)) => format!("{{\"kind\": \"output\", \"index\": {output}}}"),
+            Some(Location::Instruction(index)) => format!("{{\"kind\": \"instruction\", \"index\": {index}}}"),
+            Some(Location::Row(row)) => format!("{{\"kind\": \"row\", \"row\": {}}}", row.0),
+        };
+        format!(
+            "{{\"severity\": \"{}\", \"code\": \"{}\", \"location\": {location}, \"message\": \"{}\"}}",
+            self.severity.name(),
+            self.code.name(),
+            self.message.replace('\\', "\\\\").replace('"', "\\\"")
+        )
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}[{}]: {}", self.severity.name(), self.code.name(), self.message)?;
+        if let Some(location) = &self.location {
+            write!(f, " (at {location})")?;
+        }
+        Ok(())
+    }
+}
+
+/// Set of [DiagnosticCode]s which are escalated into errors
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+#[repr(transparent)]
+pub struct Strictness(u32);
+
+impl Strictness {
+    /// No diagnostic fails the compilation, e.g. for research on architectures which don't exist
+    /// (yet)
+    pub const PERMISSIVE: Self = Self(0);
+    pub const CONSTANT_OUTPUT: Self = Self::of(DiagnosticCode::ConstantOutput);
+    pub const ROW_BUDGET: Self = Self::of(DiagnosticCode::RowBudget);
+    pub const RETENTION_RISK: Self = Self::of(DiagnosticCode::RetentionRisk);
+    pub const CAPABILITY_EMULATION: Self = Self::of(DiagnosticCode::CapabilityEmulation);
+    pub const UNVERIFIED_TRA_GROUPING: Self = Self::of(DiagnosticCode::UnverifiedTraGrouping);
+    pub const ENERGY_BUDGET: Self = Self::of(DiagnosticCode::EnergyBudget);
+    /// Diagnostics which indicate that the program may not run correctly or not as fast as
+    /// expected on the actual module
+    pub const PRODUCTION: Self =
+        Self(Self::RETENTION_RISK.0 | Self::CAPABILITY_EMULATION.0 | Self::UNVERIFIED_TRA_GROUPING.0);
+
+    /// Escalates only `code`
+    pub const fn of(code: DiagnosticCode) -> Self {
+        Self(1 << code as u32)
+    }
+
+    /// Bits of the escalated codes, as in `prada_strictness`
+    pub const fn bits(self) -> u32 {
+        self.0
+    }
+
+    /// Inverse of [Self::bits]
+    pub const fn from_bits(bits: u32) -> Self {
+        Self(bits)
+    }
+
+    pub const fn escalates(self, code: DiagnosticCode) -> bool {
+        self.0 & Self::of(code).0 != 0
+    }
+
+    /// Turns the escalated diagnostics into errors, returning the code of the first one
+    pub fn escalate(self, diagnostics: &mut [Diagnostic]) -> Option<DiagnosticCode> {
+        let mut first = None;
+        for diagnostic in diagnostics.iter_mut().filter(|diagnostic| self.escalates(diagnostic.code)) {
+            diagnostic.severity = Severity::Error;
+            first = first.or(Some(diagnostic.code));
+        }
+        first
+    }
+}
+
+impl ops::BitOr for Strictness {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+/// Collects the diagnostics of `program`, compiled from `network` with the legalization `report`
+pub(crate) fn diagnose(
+    program: &Program,
+    network: &impl Network<Node = Mig>,
+    report: &LegalizationReport,
+) -> Vec<Diagnostic> {
+    let mut diagnostics = vec!();
+    for (output, signal) in network.outputs().enumerate() {
+        if network.node(signal.node_id()) == Mig::False {
+            diagnostics.push(Diagnostic::warning(
+                DiagnosticCode::ConstantOutput,
+                Some(Location::Output(output)),
+                format!("output {output} is constant {}", signal.is_inverted() as u8),
+            ));
+        }
+    }
+
+    let rows = program.architecture.rows_per_subarray;
+    let peak = program.peak_row_usage();
+    if peak as f64 >= ROW_BUDGET_THRESHOLD * rows as f64 {
+        diagnostics.push(Diagnostic::warning(
+            DiagnosticCode::RowBudget,
+            None,
+            format!(
+                "up to {peak} of {rows} rows hold values at the same time ({:.0}%)",
+                100.0 * peak as f64 / rows as f64
+            ),
+        ));
+    }
+
+    if report.emulated_inversions > 0 {
+        diagnostics.push(Diagnostic::warning(
+            DiagnosticCode::CapabilityEmulation,
+            None,
+            format!(
+                "{} inversions have been emulated using {} additional MAJs",
+                report.emulated_inversions, report.additional_majs
+            ),
+        ));
+    }
+
+    diagnostics.extend(unverified_tras(program));
+    diagnostics.extend(retention_risks(program));
+    diagnostics
+}
+
+/// TRAs of rows which aren't among the verified TRA groups of the architecture, if it has any
+fn unverified_tras(program: &Program) -> Vec<Diagnostic> {
+    let groups = &program.architecture.verified_tra_groups;
+    if groups.is_empty() {
+        return vec!();
+    }
+    let mut diagnostics = vec!();
+    for (index, instruction) in program.instructions.iter().enumerate() {
+        if let Instruction::AAPTRA(a, b, c) = instruction {
+            let mut group = [a, b, c].map(|row| row.0 & ROW_ID_BITMASK);
+            group.sort_unstable();
+            if !groups.contains(&group) {
+                diagnostics.push(Diagnostic::warning(
+                    DiagnosticCode::UnverifiedTraGrouping,
+                    Some(Location::Instruction(index)),
+                    format!("TRA of rows {a}, {b} and {c}, which haven't been verified to activate together"),
+                ));
+            }
+        }
+    }
+    diagnostics
+}
+
+/// Rows read longer than [RETENTION_TIME] after their last activation (which restores the charge of
+/// their cells), by the estimates of the architecture's cost model
+fn retention_risks(program: &Program) -> Vec<Diagnostic> {
+    let cost_model = &program.architecture.cost_model;
+    let mut activated: FxHashMap<RowAddress, u64> = program
+        .input_map
+        .iter()
+        .chain(&program.persistent_rows.rows)
+        .map(|(row, _)| (*row, 0))
+        .collect();
+    // longest time each row holds a value without being activated before it is read
+    let mut longest: FxHashMap<RowAddress, u64> = FxHashMap::default();
+    let mut time = 0;
+    for instruction in program.unrolled_instructions() {
+        for row in instruction.input_operands() {
+            if let Some(activation) = activated.get(&row) {
+                let held = longest.entry(row).or_default();
+                *held = (*held).max(time - activation);
+            }
+        }
+        time += cost_model.instruction_cost(&instruction).runtime;
+        for row in instruction.used_addresses() {
+            activated.insert(row, time);
+        }
+    }
+    let mut risks: Vec<(RowAddress, u64)> =
+        longest.into_iter().filter(|(_, held)| *held > RETENTION_TIME).collect();
+    risks.sort_by_key(|(row, _)| row.0);
+    risks
+        .into_iter()
+        .map(|(row, held)| {
+            Diagnostic::warning(
+                DiagnosticCode::RetentionRisk,
+                Some(Location::Row(row)),
+                format!("value in row {row} is held for {:.1}ms without refresh", held as f64 / 1e6),
+            )
+        })
+        .collect()
+}
+
+/// Writes the diagnostics as JSON array if the file extension is `json` and as text (one
+/// diagnostic per line) otherwise
+pub fn write_to_file(diagnostics: &[Diagnostic], path: &Path) -> io::Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    if path.extension().is_some_and(|ext| ext == "json") {
+        writeln!(out, "[")?;
+        for (idx, diagnostic) in diagnostics.iter().enumerate() {
+            let separator = if idx + 1 < diagnostics.len() { "," } else { "" };
+            writeln!(out, "  {}{separator}", diagnostic.to_json())?;
+        }
+        writeln!(out, "]")?;
+    } else {
+        for diagnostic in diagnostics {
+            writeln!(out, "{diagnostic}")?;
+        }
+    }
+    out.flush()
+}
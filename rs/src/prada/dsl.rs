@@ -0,0 +1,191 @@
+//! Tiny s-expression language describing kernels, e.g. for quick experiments without external
+//! netlist tools (see also the `compile_kernel` example):
+//!
+//! ```text
+//! ; full adder
+//! (let carry (maj a b c))
+//! (out sum (maj (not carry) c (maj a b (not c))))
+//! (out carry carry)
+//! ```
+//!
+//! A kernel is a sequence of the forms
+//! - `(input <name> <width>)`, declaring an input word of `<width>` bits
+//! - `(let <name> <expr>)`, naming the value of an expression
+//! - `(out <name> <expr>)`, adding the value of an expression as output word
+//!
+//! where expressions are names, the constants `0` and `1`, `(const <value> <width>)` or
+//! applications of the operations of [WordNetwork]: the bitwise `not`, `maj`, `and`, `or` and `xor`
+//! and the word-level `add`, `sub`, `mul`, `eq`, `lt` and `mux`. Names which have neither been
+//! declared as input nor bound by a `let` are single bit inputs, in the order of their first use.
+//! `;` starts a comment extending to the end of the line.
+use std::fmt::Write;
+
+use eggmock::{Mig, Network, Signal};
+use rustc_hash::FxHashMap;
+
+use super::compilation::reachable_nodes;
+use super::dense::node_index;
+use super::words::{Word, WordNetwork};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Expr {
+    Atom(String),
+    List(Vec<Expr>),
+}
+
+/// Parses a kernel (see the [module docs](self)) into a [WordNetwork]
+pub fn parse_kernel(text: &str) -> Result<WordNetwork, &'static str> {
+    let mut kernel = Kernel::default();
+    for form in parse_exprs(text)? {
+        let Expr::List(form) = form else {
+            return Err("expected `(input ...)`, `(let ...)` or `(out ...)`");
+        };
+        match &form[..] {
+            [Expr::Atom(keyword), Expr::Atom(name), Expr::Atom(width)] if keyword == "input" => {
+                if kernel.names.contains_key(name) {
+                    return Err("input declared twice");
+                }
+                let width = width.parse().map_err(|_| "invalid width")?;
+                let word = kernel.words.input(name, width);
+                kernel.names.insert(name.clone(), word);
+            }
+            [Expr::Atom(keyword), Expr::Atom(name), expr] if keyword == "let" => {
+                let word = kernel.evaluate(expr)?;
+                kernel.names.insert(name.clone(), word);
+            }
+            [Expr::Atom(keyword), Expr::Atom(name), expr] if keyword == "out" => {
+                let word = kernel.evaluate(expr)?;
+                kernel.words.output(name, word);
+            }
+            _ => return Err("expected `(input <name> <width>)`, `(let <name> <expr>)` or `(out <name> <expr>)`"),
+        }
+    }
+    Ok(kernel.words)
+}
+
+/// Kernel computing `network` bit by bit, which [parse_kernel] turns back into the same network:
+/// input `k` becomes the single bit input `i<k>`, every MAJ a `let` binding and output `k` the
+/// output `o<k>`. At least `nr_inputs` inputs are declared, as in
+/// [write_verilog](super::verilog::write_verilog).
+pub fn to_kernel(network: &impl Network<Node = Mig>, nr_inputs: u64) -> String {
+    // `reachable_nodes` lists users before their inputs
+    let nodes: Vec<_> = reachable_nodes(network).into_iter().rev().collect();
+    let nr_inputs = nodes
+        .iter()
+        .filter_map(|id| match network.node(*id) {
+            Mig::Input(index) => Some(index + 1),
+            _ => None,
+        })
+        .fold(nr_inputs, u64::max);
+    let name = |signal: Signal| {
+        let name = match network.node(signal.node_id()) {
+            Mig::False => return if signal.is_inverted() { "1" } else { "0" }.to_string(),
+            Mig::Input(index) => format!("i{index}"),
+            Mig::Maj(_) => format!("n{}", node_index(signal.node_id())),
+        };
+        if signal.is_inverted() {
+            format!("(not {name})")
+        } else {
+            name
+        }
+    };
+
+    let mut kernel = String::new();
+    for index in 0..nr_inputs {
+        writeln!(kernel, "(input i{index} 1)").unwrap();
+    }
+    for id in nodes {
+        if let Mig::Maj(operands) = network.node(id) {
+            let [a, b, c] = operands.map(name);
+            writeln!(kernel, "(let n{} (maj {a} {b} {c}))", node_index(id)).unwrap();
+        }
+    }
+    for (index, output) in network.outputs().enumerate() {
+        writeln!(kernel, "(out o{index} {})", name(output)).unwrap();
+    }
+    kernel
+}
+
+#[derive(Default)]
+struct Kernel {
+    words: WordNetwork,
+    /// Inputs and `let` bindings
+    names: FxHashMap<String, Word>,
+}
+
+impl Kernel {
+    fn evaluate(&mut self, expr: &Expr) -> Result<Word, &'static str> {
+        let items = match expr {
+            Expr::Atom(atom) if atom == "0" || atom == "1" => {
+                return Ok(self.words.constant((atom == "1") as u64, 1))
+            }
+            Expr::Atom(atom) if atom.starts_with(|c: char| c.is_ascii_digit()) => {
+                return Err("literals other than 0 and 1 need a width, use `(const <value> <width>)`")
+            }
+            Expr::Atom(name) => {
+                if let Some(word) = self.names.get(name) {
+                    return Ok(*word);
+                }
+                let word = self.words.input(name, 1);
+                self.names.insert(name.clone(), word);
+                return Ok(word);
+            }
+            Expr::List(items) => items,
+        };
+        let Some((Expr::Atom(operation), operands)) = items.split_first() else {
+            return Err("expected an operation");
+        };
+        if operation == "const" {
+            let [Expr::Atom(value), Expr::Atom(width)] = operands else {
+                return Err("expected `(const <value> <width>)`");
+            };
+            let value = value.parse().map_err(|_| "invalid constant")?;
+            return Ok(self.words.constant(value, width.parse().map_err(|_| "invalid width")?));
+        }
+        let operands = operands.iter().map(|operand| self.evaluate(operand)).collect::<Result<Vec<_>, _>>()?;
+        let words = &mut self.words;
+        match (operation.as_str(), &operands[..]) {
+            ("not", [a]) => Ok(words.not(*a)),
+            ("maj", [a, b, c]) => words.maj(*a, *b, *c),
+            ("and", [a, b]) => words.and(*a, *b),
+            ("or", [a, b]) => words.or(*a, *b),
+            ("xor", [a, b]) => words.xor(*a, *b),
+            ("add", [a, b]) => words.add(*a, *b),
+            ("sub", [a, b]) => words.sub(*a, *b),
+            ("mul", [a, b]) => words.mul(*a, *b),
+            ("eq", [a, b]) => words.eq(*a, *b),
+            ("lt", [a, b]) => words.lt(*a, *b),
+            ("mux", [select, then, otherwise]) => words.mux(*select, *then, *otherwise),
+            ("not" | "maj" | "and" | "or" | "xor" | "add" | "sub" | "mul" | "eq" | "lt" | "mux", _) => {
+                Err("wrong nr of operands")
+            }
+            _ => Err("unknown operation"),
+        }
+    }
+}
+
+/// Splits `text` into its top-level s-expressions
+fn parse_exprs(text: &str) -> Result<Vec<Expr>, &'static str> {
+    // innermost open list last, the top level first
+    let mut stack: Vec<Vec<Expr>> = vec!(vec!());
+    for line in text.lines() {
+        let line = line.split(';').next().unwrap_or("");
+        for token in line.replace('(', " ( ").replace(')', " ) ").split_whitespace() {
+            match token {
+                "(" => stack.push(vec!()),
+                ")" => {
+                    if stack.len() == 1 {
+                        return Err("unbalanced `)`");
+                    }
+                    let list = stack.pop().unwrap();
+                    stack.last_mut().unwrap().push(Expr::List(list));
+                }
+                atom => stack.last_mut().unwrap().push(Expr::Atom(atom.to_string())),
+            }
+        }
+    }
+    if stack.len() > 1 {
+        return Err("unbalanced `(`");
+    }
+    Ok(stack.pop().unwrap())
+}
@@ -0,0 +1,236 @@
+//! Rewriting with [egglog](https://github.com/egraphs-good/egglog) instead of egg (see
+//! [RewriteBackend::Egglog](super::RewriteBackend::Egglog)), e.g. for comparing the saturation
+//! performance of both engines on hard benchmarks. The received e-graph is loaded into egglog as
+//! `let`-bound terms, saturated with the same rules as the egg runner (see [RuleSet]) and the
+//! resulting e-classes are merged back into the e-graph, which keeps the ids of its e-classes valid.
+use std::fmt::Write;
+use std::time::{Duration, Instant};
+
+use eggmock::egg::{EGraph, Id, Language};
+use eggmock::MigLanguage;
+use egraph_serialize::{ClassId, EGraph as SerializedEGraph, Node, NodeId};
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use super::error::CompileError;
+use super::rules::RuleSet;
+
+/// Limits of egg's runner, so that both engines stop at the same e-graph size
+const ITERATION_LIMIT: usize = 30;
+const NODE_LIMIT: usize = 10_000;
+const TIME_LIMIT: Duration = Duration::from_secs(5);
+
+/// Statistics of a saturation run, printed by verbose compilations
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(super) struct EgglogReport {
+    pub iterations: usize,
+    /// Nr of tuples (i.e. e-nodes) in egglog's database after the last iteration
+    pub tuples: usize,
+    /// Whether the rules don't add anything anymore, as opposed to hitting a limit
+    pub saturated: bool,
+}
+
+/// The egglog declarations and rules equivalent to `rule_set`'s rules
+fn rules_program(rule_set: RuleSet) -> String {
+    let mut program = String::from(
+        "(datatype Mig (Maj Mig Mig Mig) (Not Mig) (Input i64) (False))
+(rewrite (Maj a b c) (Maj b a c))
+(rewrite (Maj a b c) (Maj a c b))
+(rewrite (Not (Not a)) a)
+(rewrite (Maj a a b) a)
+(rewrite (Maj a (Not a) b) b)
+(rewrite (Maj a b (Maj c b d)) (Maj d b (Maj c b a)))
+(birewrite (Not (Maj a b c)) (Maj (Not a) (Not b) (Not c)))
+",
+    );
+    if rule_set.sharing_guided_distributivity {
+        // egg's applier checks every order of the operands of the shared MAJ, here commutativity
+        // takes care of the other orders
+        program.push_str(
+            "(rewrite (Maj (Maj a b c) (Maj a b d) e) (Maj a b (Maj c d e)))
+(rule ((= root (Maj a b (Maj c d e))) (= shared (Maj a b c)))
+      ((union root (Maj (Maj a b c) (Maj a b d) e))))
+(rule ((= root (Maj a b (Maj c d e))) (= shared (Maj a b d)))
+      ((union root (Maj (Maj a b c) (Maj a b d) e))))
+",
+        );
+    } else {
+        program.push_str("(birewrite (Maj a b (Maj c d e)) (Maj (Maj a b c) (Maj a b d) e))\n");
+    }
+    program
+}
+
+/// E-classes of `graph` with their children before their users. `graph` has to be acyclic, which
+/// holds for received networks.
+fn topological_order(graph: &EGraph<MigLanguage, ()>) -> Vec<Id> {
+    let mut order = vec!();
+    let mut visited: FxHashSet<Id> = FxHashSet::default();
+    // (class, whether its children have been visited)
+    let mut stack: Vec<(Id, bool)> = graph.classes().map(|class| (class.id, false)).collect();
+    while let Some((id, children_visited)) = stack.pop() {
+        if visited.contains(&id) {
+            continue;
+        }
+        if children_visited {
+            visited.insert(id);
+            order.push(id);
+            continue;
+        }
+        stack.push((id, true));
+        for node in graph[id].iter() {
+            stack.extend(node.children().iter().map(|child| (graph.find(*child), false)));
+        }
+    }
+    order
+}
+
+/// Binds every e-class of `graph` to `c<id>`
+fn terms_program(graph: &EGraph<MigLanguage, ()>) -> String {
+    let mut program = String::new();
+    for id in topological_order(graph) {
+        for (idx, node) in graph[id].iter().enumerate() {
+            let term = term(graph, node);
+            if idx == 0 {
+                writeln!(program, "(let c{id} {term})").unwrap();
+            } else {
+                writeln!(program, "(union c{id} {term})").unwrap();
+            }
+        }
+    }
+    program
+}
+
+fn term(graph: &EGraph<MigLanguage, ()>, node: &MigLanguage) -> String {
+    let child = |id: &Id| format!("c{}", graph.find(*id));
+    match node {
+        MigLanguage::False => "(False)".to_string(),
+        MigLanguage::Input(index) => format!("(Input {index})"),
+        MigLanguage::Not(id) => format!("(Not {})", child(id)),
+        MigLanguage::Maj(ids) => format!("(Maj {} {} {})", child(&ids[0]), child(&ids[1]), child(&ids[2])),
+    }
+}
+
+/// Saturates `graph` with egglog using `rule_set`'s rules. The e-classes of the returned e-graph
+/// include the ones of `graph`, with the same ids.
+pub(super) fn saturate(
+    graph: &EGraph<MigLanguage, ()>,
+    rule_set: RuleSet,
+) -> Result<(EGraph<MigLanguage, ()>, EgglogReport), CompileError> {
+    let start = Instant::now();
+    let mut egglog = egglog::EGraph::default();
+    let run = |egglog: &mut egglog::EGraph, program: &str| {
+        egglog.parse_and_run_program(None, program).map_err(|err| {
+            eprintln!("egglog failed: {err}");
+            CompileError::Other("egglog failed to rewrite the e-graph")
+        })
+    };
+    run(&mut egglog, &rules_program(rule_set))?;
+    run(&mut egglog, &terms_program(graph))?;
+
+    let mut report = EgglogReport { iterations: 0, tuples: egglog.num_tuples(), saturated: false };
+    while report.iterations < ITERATION_LIMIT && report.tuples < NODE_LIMIT && start.elapsed() < TIME_LIMIT {
+        run(&mut egglog, "(run 1)")?;
+        report.iterations += 1;
+        let tuples = egglog.num_tuples();
+        if tuples == report.tuples {
+            report.saturated = true;
+            break;
+        }
+        report.tuples = tuples;
+    }
+
+    let config =
+        egglog::SerializeConfig { max_functions: None, max_calls_per_function: None, ..Default::default() };
+    let serialized = egglog.serialize(config);
+    let mut rewritten = graph.clone();
+    let mut classes: FxHashMap<ClassId, Id> = FxHashMap::default();
+    // e-classes of the received network merged by egglog are merged here as well
+    for (id, class) in locate_classes(graph, &serialized)? {
+        merge(&mut rewritten, &mut classes, class, id);
+    }
+    // adds the nodes of egglog's e-classes until no more nodes have the e-classes of all of their
+    // children in the e-graph, merging the nodes of an e-class as they are added
+    let mut pending: Vec<&Node> = serialized.nodes.values().filter(|node| is_mig(node)).collect();
+    loop {
+        let before = pending.len();
+        pending.retain(|node| {
+            let Some(mig) = to_mig(&serialized, node, &classes) else {
+                return true;
+            };
+            let id = rewritten.add(mig);
+            merge(&mut rewritten, &mut classes, node.eclass.clone(), id);
+            false
+        });
+        if pending.len() == before {
+            break;
+        }
+    }
+    rewritten.rebuild();
+    Ok((rewritten, report))
+}
+
+/// Adds `id` to the e-class corresponding to the egglog e-class `class`
+fn merge(graph: &mut EGraph<MigLanguage, ()>, classes: &mut FxHashMap<ClassId, Id>, class: ClassId, id: Id) {
+    match classes.get(&class) {
+        Some(existing) => {
+            graph.union(*existing, id);
+        }
+        None => {
+            classes.insert(class, id);
+        }
+    }
+}
+
+fn is_mig(node: &Node) -> bool {
+    matches!(node.op.as_str(), "Maj" | "Not" | "Input" | "False")
+}
+
+/// The e-graph node of the egglog `node`, `None` if the e-class of any of its children isn't in
+/// the e-graph yet
+fn to_mig(serialized: &SerializedEGraph, node: &Node, classes: &FxHashMap<ClassId, Id>) -> Option<MigLanguage> {
+    let class = |child: &NodeId| classes.get(&serialized.nodes[child].eclass).copied();
+    match (node.op.as_str(), &node.children[..]) {
+        ("False", []) => Some(MigLanguage::False),
+        ("Input", [index]) => serialized.nodes[index].op.parse().ok().map(MigLanguage::Input),
+        ("Not", [a]) => Some(MigLanguage::Not(class(a)?)),
+        ("Maj", [a, b, c]) => Some(MigLanguage::Maj([class(a)?, class(b)?, class(c)?])),
+        _ => None,
+    }
+}
+
+/// The egglog e-class of every e-class of `graph`, found by looking up its first node in egglog's
+/// e-graph, children before their users
+fn locate_classes(
+    graph: &EGraph<MigLanguage, ()>,
+    serialized: &SerializedEGraph,
+) -> Result<Vec<(Id, ClassId)>, CompileError> {
+    // operation and e-classes of the children (the i64 literal of inputs) of every egglog node
+    let mut nodes: FxHashMap<(&str, Vec<&ClassId>), &ClassId> = FxHashMap::default();
+    let mut literals: FxHashMap<&str, &ClassId> = FxHashMap::default();
+    for node in serialized.nodes.values() {
+        if is_mig(node) {
+            let children = node.children.iter().map(|child| &serialized.nodes[child].eclass).collect();
+            nodes.insert((node.op.as_str(), children), &node.eclass);
+        } else if node.children.is_empty() {
+            literals.insert(node.op.as_str(), &node.eclass);
+        }
+    }
+    let mut egglog_classes: FxHashMap<Id, &ClassId> = FxHashMap::default();
+    let mut located = vec!();
+    for id in topological_order(graph) {
+        let node = &graph[id].nodes[0];
+        let key = match node {
+            MigLanguage::False => Some(("False", vec!())),
+            MigLanguage::Input(index) => {
+                literals.get(index.to_string().as_str()).map(|literal| ("Input", vec!(*literal)))
+            }
+            MigLanguage::Not(_) | MigLanguage::Maj(_) => {
+                let op = if matches!(node, MigLanguage::Not(_)) { "Not" } else { "Maj" };
+                Some((op, node.children().iter().map(|child| egglog_classes[&graph.find(*child)]).collect()))
+            }
+        };
+        let class = key.and_then(|key| nodes.get(&key).copied()).ok_or(CompileError::Other("egglog lost an e-class"))?;
+        egglog_classes.insert(id, class);
+        located.push((id, class.clone()));
+    }
+    Ok(located)
+}
@@ -0,0 +1,56 @@
+use std::fmt::{Display, Formatter};
+
+use super::architecture::Capabilities;
+use super::diagnostics::{Diagnostic, DiagnosticCode};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CompileError {
+    /// The program would require an operation the architecture doesn't support
+    UnsupportedOperation {
+        operation: &'static str,
+        /// The capabilities required for the operation which the architecture is missing
+        missing: Capabilities,
+    },
+    /// A diagnostic has been raised which the
+    /// [strictness](super::CompilerSettings::strictness) escalates into an error
+    Escalated {
+        /// Code of the first escalated diagnostic
+        code: DiagnosticCode,
+        /// All escalated diagnostics, for callers to report them
+        diagnostics: Vec<Diagnostic>,
+    },
+    /// Any other reason for which the network couldn't be compiled
+    Other(&'static str),
+}
+
+impl CompileError {
+    /// Code reported to C callers in the `error` field of `prada_compiler_statistics` (see
+    /// `prada_error` in `prada.h`), 0 is reserved for successful compilations
+    pub fn code(&self) -> u32 {
+        match self {
+            CompileError::UnsupportedOperation { .. } => 1,
+            CompileError::Escalated { .. } => 2,
+            CompileError::Other(_) => 3,
+        }
+    }
+}
+
+impl Display for CompileError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CompileError::UnsupportedOperation { operation, missing } => {
+                write!(f, "unsupported operation {operation}: architecture is missing {missing}")
+            }
+            CompileError::Escalated { code, .. } => write!(f, "diagnostic {} is treated as error", code.name()),
+            CompileError::Other(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+impl std::error::Error for CompileError {}
+
+impl From<&'static str> for CompileError {
+    fn from(message: &'static str) -> Self {
+        CompileError::Other(message)
+    }
+}
@@ -0,0 +1,196 @@
+//! Opt-in SQLite database of compilations (requires the `sqlite` feature), recording the settings,
+//! architecture, benchmark and statistics of every compilation of a research workflow instead of
+//! collecting them in ad hoc CSV files. The `stats` example queries it from the command line:
+//!
+//! ```sh
+//! cargo run --example stats --features sqlite -- experiments.db top --by energy
+//! ```
+//!
+//! Hashes (of networks, see [network_hash], and of architectures, see [architecture_fingerprint])
+//! are stored as hexadecimal text, since SQLite's integers are signed.
+use std::path::Path;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use eggmock::{Mig, Network};
+use rusqlite::{params, Connection, Row};
+
+use super::architecture::PRADAArchitecture;
+use super::artifact::architecture_fingerprint;
+use super::cache::network_hash;
+use super::error::CompileError;
+use super::program::Program;
+use super::{compile, CompilerSettings};
+
+const SCHEMA: &str = "CREATE TABLE IF NOT EXISTS compilations (
+    id INTEGER PRIMARY KEY,
+    timestamp INTEGER NOT NULL,
+    benchmark TEXT NOT NULL,
+    network TEXT NOT NULL,
+    architecture TEXT NOT NULL,
+    settings TEXT NOT NULL,
+    instructions INTEGER NOT NULL,
+    runtime INTEGER NOT NULL,
+    energy INTEGER NOT NULL,
+    spills INTEGER NOT NULL,
+    compile_time_us INTEGER NOT NULL
+)";
+
+/// Statistic experiments are ranked by, see [ExperimentDatabase::top]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Metric {
+    Energy,
+    Runtime,
+    Instructions,
+    CompileTime,
+}
+
+impl Metric {
+    pub const ALL: [Self; 4] = [Self::Energy, Self::Runtime, Self::Instructions, Self::CompileTime];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Energy => "energy",
+            Self::Runtime => "runtime",
+            Self::Instructions => "instructions",
+            Self::CompileTime => "compile_time",
+        }
+    }
+
+    /// Inverse of [Self::name]
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|metric| metric.name() == name)
+    }
+
+    fn column(self) -> &'static str {
+        match self {
+            Self::CompileTime => "compile_time_us",
+            _ => self.name(),
+        }
+    }
+}
+
+/// A recorded compilation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Experiment {
+    pub id: i64,
+    /// Seconds since the Unix epoch
+    pub timestamp: u64,
+    pub benchmark: String,
+    pub network_hash: u64,
+    pub architecture_fingerprint: u64,
+    /// See [CompilerSettings::snapshot]
+    pub settings: String,
+    pub instructions: u64,
+    pub runtime_estimate: u64,
+    pub energy_consumption_estimate: u64,
+    pub spills: u64,
+    pub compile_time: Duration,
+}
+
+impl Experiment {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        let hash = |idx: usize| -> rusqlite::Result<u64> {
+            let text: String = row.get(idx)?;
+            u64::from_str_radix(&text, 16)
+                .map_err(|err| rusqlite::Error::FromSqlConversionFailure(idx, rusqlite::types::Type::Text, err.into()))
+        };
+        Ok(Self {
+            id: row.get(0)?,
+            timestamp: row.get::<_, i64>(1)? as u64,
+            benchmark: row.get(2)?,
+            network_hash: hash(3)?,
+            architecture_fingerprint: hash(4)?,
+            settings: row.get(5)?,
+            instructions: row.get::<_, i64>(6)? as u64,
+            runtime_estimate: row.get::<_, i64>(7)? as u64,
+            energy_consumption_estimate: row.get::<_, i64>(8)? as u64,
+            spills: row.get::<_, i64>(9)? as u64,
+            compile_time: Duration::from_micros(row.get::<_, i64>(10)? as u64),
+        })
+    }
+}
+
+pub struct ExperimentDatabase {
+    connection: Connection,
+}
+
+impl ExperimentDatabase {
+    /// Opens the database at `path`, creating it if it doesn't exist yet
+    pub fn open(path: impl AsRef<Path>) -> rusqlite::Result<Self> {
+        Self::new(Connection::open(path)?)
+    }
+
+    /// Database which only lives as long as the returned value, e.g. for tests
+    pub fn in_memory() -> rusqlite::Result<Self> {
+        Self::new(Connection::open_in_memory()?)
+    }
+
+    fn new(connection: Connection) -> rusqlite::Result<Self> {
+        connection.execute(SCHEMA, [])?;
+        Ok(Self { connection })
+    }
+
+    /// Records the compilation of `network` (named `benchmark`) into `program` using `settings`,
+    /// returning the id of the experiment
+    pub fn record(
+        &self,
+        benchmark: &str,
+        network: &impl Network<Node = Mig>,
+        settings: &CompilerSettings,
+        program: &Program,
+        compile_time: Duration,
+    ) -> rusqlite::Result<i64> {
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map(|time| time.as_secs()).unwrap_or(0);
+        self.connection.execute(
+            "INSERT INTO compilations (timestamp, benchmark, network, architecture, settings, instructions, runtime,
+                energy, spills, compile_time_us) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                timestamp as i64,
+                benchmark,
+                format!("{:016x}", network_hash(network)),
+                format!("{:016x}", architecture_fingerprint(program.architecture)),
+                settings.snapshot(),
+                program.instructions.len() as i64,
+                program.runtime_estimate as i64,
+                program.energy_consumption_estimate as i64,
+                program.allocation.spills as i64,
+                compile_time.as_micros() as i64,
+            ],
+        )?;
+        Ok(self.connection.last_insert_rowid())
+    }
+
+    /// Same as [compile], but records the compilation (see [Self::record]). Failing to record it
+    /// only prints an error.
+    pub fn compile<'a>(
+        &self,
+        benchmark: &str,
+        architecture: &'a PRADAArchitecture,
+        network: &impl Network<Node = Mig>,
+        settings: CompilerSettings,
+    ) -> Result<Program<'a>, CompileError> {
+        let start = Instant::now();
+        let program = compile(architecture, network, settings)?;
+        if let Err(err) = self.record(benchmark, network, &settings, &program, start.elapsed()) {
+            eprintln!("could not record the compilation of {benchmark}: {err}");
+        }
+        Ok(program)
+    }
+
+    /// The `limit` experiments with the lowest `metric`, optionally only those of `benchmark`
+    pub fn top(&self, metric: Metric, benchmark: Option<&str>, limit: usize) -> rusqlite::Result<Vec<Experiment>> {
+        let mut statement = self.connection.prepare(&format!(
+            "SELECT id, timestamp, benchmark, network, architecture, settings, instructions, runtime, energy, spills,
+                compile_time_us FROM compilations WHERE ?1 IS NULL OR benchmark = ?1 ORDER BY {} ASC, id ASC LIMIT ?2",
+            metric.column()
+        ))?;
+        let experiments = statement.query_map(params![benchmark, limit as i64], Experiment::from_row)?;
+        experiments.collect()
+    }
+
+    /// Nr of recorded experiments
+    pub fn count(&self) -> rusqlite::Result<u64> {
+        let count: i64 = self.connection.query_row("SELECT COUNT(*) FROM compilations", [], |row| row.get(0))?;
+        Ok(count as u64)
+    }
+}
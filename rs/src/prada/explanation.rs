@@ -0,0 +1,58 @@
+//! Rewrite provenance using egg's explanations: proofs that the extracted network is equivalent to
+//! the network that was originally sent to the compiler.
+use super::extraction::{extract, ExtractionCostFunction};
+use crate::opt_extractor::{OptCostFunction, OptExtractor};
+use eggmock::egg::{EGraph, Id, Language, RecExpr};
+use eggmock::MigLanguage;
+
+/// Returns the term the extractor chooses for the given e-class
+pub fn extracted_expr(
+    extractor: &OptExtractor<impl OptCostFunction<MigLanguage, ()>, MigLanguage, ()>,
+    root: Id,
+) -> RecExpr<MigLanguage> {
+    extractor
+        .find_best_node(root)
+        .expect("class should be extractable")
+        .build_recexpr(|id| {
+            extractor
+                .find_best_node(id)
+                .expect("class should be extractable")
+                .clone()
+        })
+}
+
+/// Returns a human-readable proof (as sequence of rewrites) that the term originally added to the
+/// e-graph as `original_root` is equivalent to `extracted_root`
+/// - explanations have to be enabled on `graph` before adding the original term
+pub fn why_equivalent(
+    graph: &mut EGraph<MigLanguage, ()>,
+    original_root: Id,
+    extracted_root: &RecExpr<MigLanguage>,
+) -> String {
+    let original = graph.id_to_expr(original_root);
+    graph
+        .explain_equivalence(&original, extracted_root)
+        .get_flat_string()
+}
+
+/// Explains the equivalence of every output before rewriting and after extraction
+/// - this runs a separate extraction, since the e-graph cannot be modified anymore while the
+///   extraction result used for compilation borrows it
+pub fn explain_outputs(
+    graph: &mut EGraph<MigLanguage, ()>,
+    outputs: &[Id],
+    cost_function: impl ExtractionCostFunction,
+) -> Vec<String> {
+    let extracted: Vec<_> = {
+        let extractor = extract(graph, cost_function);
+        outputs
+            .iter()
+            .map(|output| extracted_expr(&extractor, *output))
+            .collect()
+    };
+    outputs
+        .iter()
+        .zip(extracted)
+        .map(|(output, extracted)| why_equivalent(graph, *output, &extracted))
+        .collect()
+}
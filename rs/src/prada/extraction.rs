@@ -1,14 +1,293 @@
-use crate::opt_extractor::OptCostFunction;
-use crate::prada::architecture::PRADAArchitecture;
-use eggmock::egg::{Analysis, EClass, Id, Language};
+use crate::opt_extractor::{OptCostFunction, OptExtractor};
+use crate::prada::architecture::{Capabilities, PRADAArchitecture};
+use crate::prada::cost::InstructionClass;
+use eggmock::egg::{Analysis, EClass, EGraph, Id, Language};
 use eggmock::MigLanguage;
+use rustc_hash::{FxHashMap, FxHasher};
+use std::cell::RefCell;
 use std::cmp::Ordering;
+use std::hash::{Hash, Hasher};
+use std::fmt::Debug;
 use std::iter::Sum;
+use std::marker::PhantomData;
 use std::ops;
-use std::rc::Rc;
+use std::sync::Arc;
 
+#[derive(Clone)]
 pub struct CompilingCostFunction<'a> {
     pub architecture: &'a PRADAArchitecture,
+    /// Estimated nr of inverted signals competing for the architecture's DCC rows
+    pub dcc_pressure: u64,
+}
+
+impl<'a> CompilingCostFunction<'a> {
+    /// Creates a cost function for extracting from `graph`, estimating the DCC row pressure from
+    /// the nr of e-classes which contain an inversion of a non-leaf before rewriting (inverted
+    /// inputs and constants don't occupy DCC rows)
+    pub fn new<A: Analysis<MigLanguage>>(
+        architecture: &'a PRADAArchitecture,
+        graph: &EGraph<MigLanguage, A>,
+    ) -> Self {
+        let is_leaf = |class: Id| {
+            graph[class]
+                .nodes
+                .iter()
+                .any(|node| matches!(node, MigLanguage::False | MigLanguage::Input(_)))
+        };
+        let dcc_pressure = graph
+            .classes()
+            .filter(|class| {
+                class.nodes.iter().any(|node| matches!(node, MigLanguage::Not(child) if !is_leaf(*child)))
+            })
+            .count() as u64;
+        Self {
+            architecture,
+            dcc_pressure,
+        }
+    }
+
+    /// The parts of the architecture the costs of this function depend on
+    pub fn features(&self) -> CostFeatures {
+        CostFeatures {
+            tra: self.architecture.cost_model.cost(InstructionClass::Tra),
+            not: self.not_cost(),
+        }
+    }
+
+    /// Cost of a single inversion
+    /// - without DCC rows every inversion requires an explicit N
+    /// - with DCC rows inversions are free, as long as there are enough DCC rows for all inverted
+    ///   signals; otherwise the share of inverted signals not fitting into DCC rows is charged
+    ///   the cost of an N
+    pub fn not_cost(&self) -> CompilingCost {
+        let n_cost = self.architecture.cost_model.cost(InstructionClass::Not);
+        let nr_dcc_rows = self.architecture.nr_dcc_rows;
+        if !self.architecture.supports(Capabilities::DCC) || nr_dcc_rows == 0 {
+            return n_cost;
+        }
+        if self.dcc_pressure <= nr_dcc_rows {
+            return CompilingCost {
+                runtime: 0,
+                energy_consumption: 0,
+            };
+        }
+        let overflow = self.dcc_pressure - nr_dcc_rows;
+        CompilingCost {
+            runtime: n_cost.runtime * overflow / self.dcc_pressure,
+            energy_consumption: n_cost.energy_consumption * overflow / self.dcc_pressure,
+        }
+    }
+}
+
+/// Extracts the cheapest term of every class of `graph`, in parallel if the
+/// `parallel-extraction` feature is enabled (which chooses the same terms)
+pub fn extract<'g, CF: ExtractionCostFunction>(
+    graph: &'g EGraph<MigLanguage, ()>,
+    cost_function: CF,
+) -> OptExtractor<'g, CF, MigLanguage, ()> {
+    #[cfg(feature = "parallel-extraction")]
+    return OptExtractor::new_parallel(graph, cost_function);
+    #[cfg(not(feature = "parallel-extraction"))]
+    return OptExtractor::new(graph, cost_function);
+}
+
+/// Cost functions the compiler extracts networks with, e.g. a [CostFn] (see
+/// [compile_with_cost_function](super::compile_with_cost_function)). With the `parallel-extraction`
+/// feature, they have to be shareable between threads.
+#[cfg(not(feature = "parallel-extraction"))]
+pub trait ExtractionCostFunction: OptCostFunction<MigLanguage, ()> + Clone {}
+
+#[cfg(not(feature = "parallel-extraction"))]
+impl<CF: OptCostFunction<MigLanguage, ()> + Clone> ExtractionCostFunction for CF {}
+
+#[cfg(feature = "parallel-extraction")]
+pub trait ExtractionCostFunction: OptCostFunction<MigLanguage, (), Cost: Send + Sync> + Clone + Send + Sync {}
+
+#[cfg(feature = "parallel-extraction")]
+impl<CF> ExtractionCostFunction for CF where
+    CF: OptCostFunction<MigLanguage, (), Cost: Send + Sync> + Clone + Send + Sync
+{
+}
+
+/// Cost function given by a closure computing the cost of a node from the costs of its children,
+/// or [None] if the node must not be extracted. E.g. the number of nodes of the extracted network
+/// is minimized by `CostFn::new(|_, children: &[u64]| Some(1 + children.iter().sum::<u64>()))`.
+pub struct CostFn<F, C> {
+    f: F,
+    cost: PhantomData<fn() -> C>,
+}
+
+impl<F, C> CostFn<F, C>
+where
+    F: FnMut(&MigLanguage, &[C]) -> Option<C>,
+    C: PartialOrd + Debug + Clone,
+{
+    pub fn new(f: F) -> Self {
+        Self { f, cost: PhantomData }
+    }
+}
+
+impl<F: Clone, C> Clone for CostFn<F, C> {
+    fn clone(&self) -> Self {
+        Self { f: self.f.clone(), cost: PhantomData }
+    }
+}
+
+impl<F, C, A> OptCostFunction<MigLanguage, A> for CostFn<F, C>
+where
+    F: FnMut(&MigLanguage, &[C]) -> Option<C>,
+    C: PartialOrd + Debug + Clone,
+    A: Analysis<MigLanguage>,
+{
+    type Cost = C;
+
+    fn cost<K>(&mut self, _eclass: &EClass<MigLanguage, A::Data>, enode: &MigLanguage, mut costs: K) -> Option<C>
+    where
+        K: FnMut(Id) -> C,
+    {
+        let children: Vec<C> = enode.children().iter().map(|child| costs(*child)).collect();
+        (self.f)(enode, &children)
+    }
+}
+
+/// Wraps the cost function of a compilation, ignoring nodes which are their own children and
+/// restricting classes to the nodes chosen by an earlier extraction resp. by the user and to the
+/// shallowest nodes in timing-constrained cones
+#[derive(Clone)]
+pub struct Restricted<CF> {
+    pub cost_function: CF,
+    /// Node to choose for every class, recorded by an earlier extraction of the same e-graph with
+    /// the same [CostFeatures] (see [CostMemo]); all other nodes are ignored
+    pub choices: Option<Arc<FxHashMap<Id, MigLanguage>>>,
+    /// Nodes the classes overridden by the user are restricted to, see
+    /// [ExtractionOverride](super::overrides::ExtractionOverride)
+    pub pins: Option<Arc<FxHashMap<Id, Vec<MigLanguage>>>>,
+    /// Shallowest nodes the classes in the fan-in cones of outputs with deadlines are restricted
+    /// to, see [shallowest_nodes](super::timing::shallowest_nodes)
+    pub shallow: Option<Arc<FxHashMap<Id, Vec<MigLanguage>>>>,
+}
+
+impl<CF> Restricted<CF> {
+    pub fn new(cost_function: CF) -> Self {
+        Self {
+            cost_function,
+            choices: None,
+            pins: None,
+            shallow: None,
+        }
+    }
+}
+
+impl<CF: OptCostFunction<MigLanguage, A>, A: Analysis<MigLanguage>> OptCostFunction<MigLanguage, A> for Restricted<CF> {
+    type Cost = CF::Cost;
+
+    fn cost<C>(&mut self, eclass: &EClass<MigLanguage, A::Data>, enode: &MigLanguage, costs: C) -> Option<Self::Cost>
+    where
+        C: FnMut(Id) -> Self::Cost,
+    {
+        if let Some(choices) = &self.choices {
+            if choices.get(&eclass.id) != Some(enode) {
+                return None;
+            }
+        }
+        if let Some(nodes) = self.pins.as_ref().and_then(|pins| pins.get(&eclass.id)) {
+            if !nodes.contains(enode) {
+                return None;
+            }
+        }
+        if let Some(nodes) = self.shallow.as_ref().and_then(|shallow| shallow.get(&eclass.id)) {
+            if !nodes.contains(enode) {
+                return None;
+            }
+        }
+        // detect self-cycles, other cycles will be detected by compiling, which will result in an
+        // error
+        if enode.children().contains(&eclass.id) {
+            return None;
+        }
+        self.cost_function.cost(eclass, enode, costs)
+    }
+}
+
+/// The only parts of an architecture affecting extraction: leaves are free, MAJs cost a TRA and
+/// inversions [CompilingCostFunction::not_cost]. Architectures differing only in other parameters
+/// (e.g. the nr of rows or the cost of a RowClone) extract the same network.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct CostFeatures {
+    pub tra: CompilingCost,
+    pub not: CompilingCost,
+}
+
+/// Extraction choices of earlier compilations, keyed by e-class and [CostFeatures], so that the
+/// points of a DSE sweep sharing the features of an earlier point (see
+/// [Sweep::run](super::report::Sweep::run)) skip exploring the alternatives of every class.
+/// Choices are only reused for the e-graph they were recorded for, i.e. as long as rewriting
+/// yields the same e-graph.
+#[derive(Debug, Default)]
+pub struct CostMemo {
+    entries: RefCell<MemoEntries>,
+}
+
+#[derive(Debug, Default)]
+struct MemoEntries {
+    /// Fingerprint of the e-graph the choices belong to (see [graph_fingerprint])
+    graph: Option<u64>,
+    choices: FxHashMap<CostFeatures, Arc<FxHashMap<Id, MigLanguage>>>,
+    hits: u64,
+}
+
+impl CostMemo {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Nr of extractions which reused recorded choices
+    pub fn hits(&self) -> u64 {
+        self.entries.borrow().hits
+    }
+
+    /// Choices recorded for the e-graph with the given fingerprint and `features`
+    pub(super) fn lookup(&self, graph: u64, features: CostFeatures) -> Option<Arc<FxHashMap<Id, MigLanguage>>> {
+        let mut entries = self.entries.borrow_mut();
+        if entries.graph != Some(graph) {
+            return None;
+        }
+        let choices = entries.choices.get(&features).cloned()?;
+        entries.hits += 1;
+        Some(choices)
+    }
+
+    /// Records the choices of `extractor` for `features`, forgetting the choices of other e-graphs
+    pub(super) fn record(
+        &self,
+        graph: &EGraph<MigLanguage, ()>,
+        fingerprint: u64,
+        features: CostFeatures,
+        extractor: &OptExtractor<impl OptCostFunction<MigLanguage, ()>, MigLanguage, ()>,
+    ) {
+        let mut entries = self.entries.borrow_mut();
+        if entries.graph != Some(fingerprint) {
+            entries.graph = Some(fingerprint);
+            entries.choices.clear();
+        }
+        entries.choices.entry(features).or_insert_with(|| {
+            let choices = graph
+                .classes()
+                .filter_map(|class| Some((class.id, extractor.find_best_node(class.id)?.clone())))
+                .collect();
+            Arc::new(choices)
+        });
+    }
+}
+
+/// Hash of the classes and nodes of `graph`, independent of the order of its classes
+pub fn graph_fingerprint(graph: &EGraph<MigLanguage, ()>) -> u64 {
+    graph.classes().fold(graph.number_of_classes() as u64, |fingerprint, class| {
+        let mut hasher = FxHasher::default();
+        class.id.hash(&mut hasher);
+        class.nodes.hash(&mut hasher);
+        fingerprint.wrapping_add(hasher.finish())
+    })
 }
 
 #[derive(Debug,Copy,Clone)]
@@ -21,37 +300,29 @@ pub struct CompilingCost {
 }
 
 impl<A: Analysis<MigLanguage>> OptCostFunction<MigLanguage, A> for CompilingCostFunction<'_> {
-    type Cost = Rc<CompilingCost>;
+    type Cost = CompilingCost;
 
     fn cost<C>(
         &mut self,
-        eclass: &EClass<MigLanguage, A::Data>,
+        _eclass: &EClass<MigLanguage, A::Data>,
         enode: &MigLanguage,
         mut costs: C,
     ) -> Option<Self::Cost>
     where
         C: FnMut(Id) -> Self::Cost,
     {
-        // detect self-cycles, other cycles will be detected by compiling, which will result in an
-        // error
-        if enode.children().contains(&eclass.id) {
-            return None;
-        }
         let root = enode.clone();
         let op_cost = match enode {
             MigLanguage::False | MigLanguage::Input(_) => CompilingCost::leaf(root),
-            MigLanguage::Not(_) => {
-                CompilingCost {
-                    runtime: 35,
-                    energy_consumption: 100,
-                }
+            // both phases of inputs are stored anyway, which biases extraction towards pushing
+            // inverters to the inputs
+            MigLanguage::Not(child) if costs(*child) == CompilingCost::leaf(root.clone()) => {
+                CompilingCost::leaf(root)
             }
-            MigLanguage::Maj(_) => CompilingCost {
-                runtime: 49,
-                energy_consumption: 150,
-            },
+            MigLanguage::Not(_) => self.not_cost(),
+            MigLanguage::Maj(_) => self.architecture.cost_model.cost(InstructionClass::Tra),
         };
-        Some(Rc::new(enode.fold(op_cost, |sum, id| sum + *(costs(id)))))
+        Some(enode.fold(op_cost, |sum, id| sum + costs(id)))
     }
 }
 
@@ -89,6 +360,15 @@ impl PartialEq for CompilingCost {
     }
 }
 
+impl Eq for CompilingCost {}
+
+impl Hash for CompilingCost {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.runtime.hash(state);
+        self.energy_consumption.hash(state);
+    }
+}
+
 impl PartialOrd for CompilingCost {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         if self.runtime.eq(&other.runtime) {
@@ -98,3 +378,83 @@ impl PartialOrd for CompilingCost {
         }
     }
 }
+
+/// Extraction objective minimizing the energy consumption first and the runtime only second, e.g.
+/// for meeting an [energy_budget](super::CompilerSettings::energy_budget)
+#[derive(Clone)]
+pub struct EnergyObjective<'a>(pub CompilingCostFunction<'a>);
+
+/// [CompilingCost] ordered by the energy consumption first
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct EnergyFirst(pub CompilingCost);
+
+impl PartialOrd for EnergyFirst {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        let key = |cost: &Self| (cost.0.energy_consumption, cost.0.runtime);
+        key(self).partial_cmp(&key(other))
+    }
+}
+
+impl<A: Analysis<MigLanguage>> OptCostFunction<MigLanguage, A> for EnergyObjective<'_> {
+    type Cost = EnergyFirst;
+
+    fn cost<C>(
+        &mut self,
+        eclass: &EClass<MigLanguage, A::Data>,
+        enode: &MigLanguage,
+        mut costs: C,
+    ) -> Option<EnergyFirst>
+    where
+        C: FnMut(Id) -> EnergyFirst,
+    {
+        OptCostFunction::<MigLanguage, A>::cost(&mut self.0, eclass, enode, |id| costs(id).0).map(EnergyFirst)
+    }
+}
+
+/// Durations of extracting the same e-graph sequentially and in parallel, see
+/// [benchmark_extraction]
+#[cfg(feature = "parallel-extraction")]
+#[derive(Debug, Copy, Clone)]
+pub struct ExtractionBenchmark {
+    /// Nr of e-classes after rewriting
+    pub classes: usize,
+    pub sequential: std::time::Duration,
+    pub parallel: std::time::Duration,
+}
+
+/// Rewrites `network` (if enabled in `settings`) and extracts the resulting e-graph once
+/// sequentially and once in parallel, panicking if the extractors choose different terms
+#[cfg(feature = "parallel-extraction")]
+pub fn benchmark_extraction(
+    architecture: &PRADAArchitecture,
+    network: &impl eggmock::Network<Node = eggmock::Mig>,
+    settings: super::CompilerSettings,
+) -> ExtractionBenchmark {
+    use std::time::Instant;
+
+    let (mut graph, _) = network.send(EGraph::<MigLanguage, ()>::new(()));
+    if settings.rewrite {
+        graph = settings.runner().with_egraph(graph).run(settings.rules()).egraph;
+    }
+    let cost_function = Restricted::new(CompilingCostFunction::new(architecture, &graph));
+
+    let start = Instant::now();
+    let sequential = OptExtractor::new(&graph, cost_function.clone());
+    let t_sequential = start.elapsed();
+    let start = Instant::now();
+    let parallel = OptExtractor::new_parallel(&graph, cost_function);
+    let t_parallel = start.elapsed();
+
+    for class in graph.classes() {
+        assert_eq!(
+            sequential.find_best_node(class.id),
+            parallel.find_best_node(class.id),
+            "parallel extraction should choose the same node as sequential extraction"
+        );
+    }
+    ExtractionBenchmark {
+        classes: graph.number_of_classes(),
+        sequential: t_sequential,
+        parallel: t_parallel,
+    }
+}
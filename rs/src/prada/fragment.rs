@@ -0,0 +1,204 @@
+//! Relocatable program fragments: programs whose row operands are (partially) symbolic and only
+//! get assigned physical rows when fragments are composed by the [Linker].
+use super::architecture::{Capabilities, PRADAArchitecture, RowAddress, SubarrayId, ROW_ID_BITMASK};
+use super::constants::ConstantRows;
+use super::coverage::{self, CodePath};
+use super::error::CompileError;
+use super::program::{Instruction, Program, RowInit};
+use rustc_hash::{FxHashMap, FxHashSet};
+
+/// Row operand of an instruction inside a [ProgramFragment]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Operand {
+    /// Physical row which is used as-is by the linker
+    Fixed(RowAddress),
+    /// Fragment-local row which gets assigned a physical row of the fragment's subarray when
+    /// linking
+    Symbol(u32),
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ProgramFragment {
+    pub instructions: Vec<Instruction<Operand>>,
+    /// Rows which have to be initialized before running the fragment, see [Program::input_map]
+    pub input_map: Vec<(Operand, RowInit)>,
+    /// Row holding the i-th output of the fragment
+    pub output_map: Vec<Operand>,
+    /// Rows whose content survives between invocations, see [Program::persistent_rows]
+    pub persistent_rows: Vec<(Operand, RowInit)>,
+    pub runtime_estimate: u64,
+    pub energy_consumption_estimate: u64,
+}
+
+impl ProgramFragment {
+    /// Turns a compiled program into a fragment by replacing every row by a symbol, numbered by
+    /// the local row address inside its subarray
+    pub fn from_program(program: &Program) -> Self {
+        let symbol = |row: &RowAddress| Operand::Symbol((row.0 & ROW_ID_BITMASK) as u32);
+        Self {
+            instructions: program
+                .instructions
+                .iter()
+                .map(|instr| instr.map_addresses(symbol))
+                .collect(),
+            input_map: program
+                .input_map
+                .iter()
+                .map(|(row, init)| (symbol(row), *init))
+                .collect(),
+            output_map: program.output_map.iter().map(symbol).collect(),
+            persistent_rows: program
+                .persistent_rows
+                .rows
+                .iter()
+                .map(|(row, init)| (symbol(row), *init))
+                .collect(),
+            runtime_estimate: program.runtime_estimate,
+            energy_consumption_estimate: program.energy_consumption_estimate,
+        }
+    }
+}
+
+/// Handle to a fragment added to a [Linker]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct FragmentId(pub usize);
+
+/// Composes fragments into a single [Program], assigning physical rows to all symbols
+pub struct Linker<'a> {
+    architecture: &'a PRADAArchitecture,
+    fragments: Vec<(ProgramFragment, SubarrayId)>,
+    /// maps (fragment, input index) to the (fragment, output index) it is connected to
+    connections: FxHashMap<(FragmentId, u64), (FragmentId, usize)>,
+}
+
+impl<'a> Linker<'a> {
+    pub fn new(architecture: &'a PRADAArchitecture) -> Self {
+        Self {
+            architecture,
+            fragments: vec!(),
+            connections: FxHashMap::default(),
+        }
+    }
+
+    /// Adds a fragment whose symbols are placed into `subarray`. Fragments are executed in the
+    /// order they are added.
+    pub fn add(&mut self, fragment: ProgramFragment, subarray: SubarrayId) -> FragmentId {
+        self.fragments.push((fragment, subarray));
+        FragmentId(self.fragments.len() - 1)
+    }
+
+    /// Feeds output `output` of fragment `from` into input `input` of fragment `to`, which has to
+    /// be added after `from`
+    pub fn connect(&mut self, from: FragmentId, output: usize, to: FragmentId, input: u64) {
+        self.connections.insert((to, input), (from, output));
+    }
+
+    /// Assigns a physical row to every symbol and concatenates the fragments. Inputs which are
+    /// connected to the output of a previous fragment are initialized by copying (and negating,
+    /// for inverted inputs) that output. Constant rows which a fragment only reads are shared with
+    /// the other fragments of its subarray, see [ConstantRows].
+    pub fn link(&self) -> Result<Program<'a>, CompileError> {
+        let mut program = Program::new(self.architecture, vec!());
+        let mut next_free_row: FxHashMap<SubarrayId, u64> = FxHashMap::default();
+        let mut output_rows: Vec<Vec<RowAddress>> = Vec::with_capacity(self.fragments.len());
+        let mut constants = ConstantRows::new();
+
+        for (idx, (fragment, subarray)) in self.fragments.iter().enumerate() {
+            let mut symbols: FxHashMap<u32, RowAddress> = FxHashMap::default();
+            let written: FxHashSet<Operand> = fragment
+                .instructions
+                .iter()
+                .flat_map(|instruction| instruction.output_operands())
+                .collect();
+            let mut shared = FxHashSet::default();
+            for (operand, init) in &fragment.input_map {
+                if let (Operand::Symbol(symbol), RowInit::Constant(value)) = (operand, init) {
+                    if !written.contains(operand) {
+                        let row = constants.materialize(*subarray, *value, &mut program.input_map, || {
+                            self.allocate_row(&mut next_free_row, *subarray)
+                        })?;
+                        symbols.insert(*symbol, row);
+                        shared.insert(*operand);
+                    }
+                }
+            }
+            let mut resolve = |operand: &Operand| -> Result<RowAddress, CompileError> {
+                match operand {
+                    Operand::Fixed(row) => Ok(*row),
+                    Operand::Symbol(symbol) => {
+                        if let Some(row) = symbols.get(symbol) {
+                            return Ok(*row);
+                        }
+                        let row = self.allocate_row(&mut next_free_row, *subarray)?;
+                        symbols.insert(*symbol, row);
+                        Ok(row)
+                    }
+                }
+            };
+
+            for (operand, init) in &fragment.input_map {
+                if shared.contains(operand) {
+                    continue;
+                }
+                let row = resolve(operand)?;
+                let connection = match init {
+                    RowInit::Input { index, inverted } => self
+                        .connections
+                        .get(&(FragmentId(idx), *index))
+                        .map(|source| (*source, *inverted)),
+                    RowInit::Constant(_) => None,
+                };
+                match connection {
+                    Some(((from, output), inverted)) => {
+                        let source_row = *output_rows
+                            .get(from.0)
+                            .and_then(|rows| rows.get(output))
+                            .ok_or(CompileError::Other("fragment input is connected to an unknown output"))?;
+                        if source_row.get_subarray_id() != row.get_subarray_id() {
+                            self.architecture.require(Capabilities::INTERSUBARRAY_ROWCLONE, "inter-subarray row copy")?;
+                            coverage::hit(CodePath::InterSubarrayCopy);
+                        }
+                        program.instructions.push(Instruction::AAPRowCopy(source_row, row));
+                        if inverted {
+                            self.architecture.require(Capabilities::NOT, "N")?;
+                            program.instructions.push(Instruction::N(row));
+                        }
+                    }
+                    None => program.input_map.push((row, *init)),
+                }
+            }
+            for instruction in &fragment.instructions {
+                program.instructions.push(instruction.try_map_addresses(&mut resolve)?);
+            }
+            let outputs = fragment
+                .output_map
+                .iter()
+                .map(&mut resolve)
+                .collect::<Result<Vec<_>, _>>()?;
+            program.output_map.extend(outputs.iter().copied());
+            output_rows.push(outputs);
+            for (operand, init) in &fragment.persistent_rows {
+                let row = resolve(operand)?;
+                program.persistent_rows.declare(row, *init);
+            }
+        }
+
+        program.update_cost_estimates(&self.architecture.cost_model);
+        Ok(program)
+    }
+
+    /// Returns the next unused row of `subarray`
+    fn allocate_row(
+        &self,
+        next_free_row: &mut FxHashMap<SubarrayId, u64>,
+        subarray: SubarrayId,
+    ) -> Result<RowAddress, CompileError> {
+        let next_row = next_free_row.entry(subarray).or_insert(0);
+        if *next_row >= self.architecture.rows_per_subarray {
+            return Err(CompileError::Other("not enough rows in subarray to link fragment"));
+        }
+        let row = RowAddress(*next_row).local_rowaddress_to_subarray_id(subarray);
+        *next_row += 1;
+        Ok(row)
+    }
+}
@@ -0,0 +1,172 @@
+//! Incremental recompilation for interactive synthesis loops: when only a few nodes of a network
+//! change, only the fan-in cones of the outputs which differ are compiled, and the previously
+//! compiled program is patched instead of compiling the whole network again.
+//!
+//! Outputs are matched by the structure of their cones (inputs by index, MAJ operands in any
+//! order), not by their function, so restructured but equivalent cones are recompiled as well.
+//! The instructions of the previous program only needed by changed outputs are pruned (see
+//! [prune_dead_instructions]), the cones of the changed outputs are compiled on their own and
+//! appended, with their rows moved to rows the remaining program doesn't use.
+use std::hash::{Hash, Hasher};
+
+use eggmock::{Id, Mig, Network, Signal};
+use rustc_hash::{FxHashMap, FxHashSet, FxHasher};
+
+use super::architecture::{RowAddress, SubarrayId};
+use super::compilation::reachable_nodes;
+use super::error::CompileError;
+use super::program::Program;
+use super::symbolic::prune_dead_instructions;
+use super::{compile, CompilerSettings};
+
+#[derive(Debug, Clone)]
+pub struct Recompilation<'a> {
+    pub program: Program<'a>,
+    /// Indices of the outputs of the new network which have been compiled again, all others are
+    /// computed by the instructions of the previous program
+    pub recompiled_outputs: Vec<usize>,
+    /// Nr of instructions of the previous program which have been pruned
+    pub removed_instructions: usize,
+}
+
+/// Updates `previous`, compiled from `old`, to compute the outputs of `new`, compiling only the
+/// cones of the outputs of `new` which don't occur among the outputs of `old`. The decisions,
+/// allocator metrics and metadata of `previous` are dropped, since they don't describe the patched
+/// program.
+pub fn recompile<'a>(
+    previous: &Program<'a>,
+    old: &impl Network<Node = Mig>,
+    new: &impl Network<Node = Mig>,
+    settings: CompilerSettings,
+) -> Result<Recompilation<'a>, CompileError> {
+    let old_hashes = structural_hashes(old);
+    let mut old_rows: FxHashMap<(u64, bool), RowAddress> = FxHashMap::default();
+    for (output, row) in old.outputs().zip(&previous.output_map) {
+        old_rows.entry(signal_hash(&old_hashes, output)).or_insert(*row);
+    }
+    let new_hashes = structural_hashes(new);
+    let outputs: Vec<Signal> = new.outputs().collect();
+    let reused: Vec<Option<RowAddress>> = outputs
+        .iter()
+        .map(|output| old_rows.get(&signal_hash(&new_hashes, *output)).copied())
+        .collect();
+    let recompiled_outputs: Vec<usize> = (0..outputs.len()).filter(|index| reused[*index].is_none()).collect();
+
+    let architecture = previous.architecture;
+    let mut program = previous.clone();
+    program.decisions.clear();
+    program.allocator_metrics = None;
+    program.metadata.clear();
+    program.output_map = reused.iter().flatten().copied().collect();
+    let removed_instructions = prune_dead_instructions(&mut program).map_err(CompileError::Other)?;
+    let mut used: FxHashSet<RowAddress> =
+        program.instructions.iter().flat_map(|instr| instr.used_addresses()).collect();
+    used.extend(program.output_map.iter().copied());
+    used.extend(program.persistent_rows.iter());
+    program.input_map.retain(|(row, _)| used.contains(row));
+
+    let mut patch_outputs = vec!();
+    if !recompiled_outputs.is_empty() {
+        let cones = Cones {
+            network: new,
+            outputs: recompiled_outputs.iter().map(|index| outputs[*index]).collect(),
+        };
+        let patch = compile(architecture, &cones, settings)?;
+        let relocation = relocation(&patch, &used, architecture.rows_per_subarray)?;
+        let patch = patch.map_rows(|row| relocation.get(&row).copied().unwrap_or(row));
+        program.instructions.extend(patch.instructions);
+        program.input_map.extend(patch.input_map);
+        for (row, init) in patch.persistent_rows.rows {
+            program.persistent_rows.declare(row, init);
+        }
+        patch_outputs = patch.output_map;
+    }
+    let mut patch_outputs = patch_outputs.into_iter();
+    program.output_map = reused
+        .iter()
+        .map(|row| row.or_else(|| patch_outputs.next()).expect("every recompiled output has a row"))
+        .collect();
+    program.update_cost_estimates(&architecture.cost_model);
+    Ok(Recompilation { program, recompiled_outputs, removed_instructions })
+}
+
+/// The fan-in cones of some outputs of a network
+struct Cones<'n, N> {
+    network: &'n N,
+    outputs: Vec<Signal>,
+}
+
+impl<N: Network<Node = Mig>> Network for Cones<'_, N> {
+    type Node = Mig;
+
+    fn outputs(&self) -> impl Iterator<Item = Signal> {
+        self.outputs.iter().copied()
+    }
+
+    fn node(&self, id: Id) -> Self::Node {
+        self.network.node(id)
+    }
+}
+
+/// Hash of the cone of every node reachable from the outputs of `network`, independent of the ids
+/// of the nodes and of the order of the operands of MAJs
+fn structural_hashes(network: &impl Network<Node = Mig>) -> FxHashMap<Id, u64> {
+    let mut hashes = FxHashMap::default();
+    // `reachable_nodes` lists users before their inputs
+    for id in reachable_nodes(network).into_iter().rev() {
+        let mut hasher = FxHasher::default();
+        match network.node(id) {
+            Mig::False => 0u8.hash(&mut hasher),
+            Mig::Input(index) => (1u8, index).hash(&mut hasher),
+            Mig::Maj(operands) => {
+                let mut operands = operands.map(|operand| signal_hash(&hashes, operand));
+                operands.sort_unstable();
+                (2u8, operands).hash(&mut hasher);
+            }
+        }
+        hashes.insert(id, hasher.finish());
+    }
+    hashes
+}
+
+fn signal_hash(hashes: &FxHashMap<Id, u64>, signal: Signal) -> (u64, bool) {
+    (hashes[&signal.node_id()], signal.is_inverted())
+}
+
+/// Moves the rows of `patch` which are `used` by the remaining program to unused rows of the same
+/// subarray, keeping all other rows
+fn relocation(
+    patch: &Program,
+    used: &FxHashSet<RowAddress>,
+    rows_per_subarray: u64,
+) -> Result<FxHashMap<RowAddress, RowAddress>, CompileError> {
+    let mut patch_rows: Vec<RowAddress> = patch
+        .instructions
+        .iter()
+        .flat_map(|instr| instr.used_addresses())
+        .chain(patch.input_map.iter().map(|(row, _)| *row))
+        .chain(patch.persistent_rows.iter())
+        .chain(patch.output_map.iter().copied())
+        .collect();
+    patch_rows.sort_by_key(|row| row.0);
+    patch_rows.dedup();
+    let mut taken: FxHashSet<RowAddress> = used.iter().chain(&patch_rows).copied().collect();
+    let mut next_candidate: FxHashMap<SubarrayId, u64> = FxHashMap::default();
+    let mut relocation = FxHashMap::default();
+    for row in patch_rows.into_iter().filter(|row| used.contains(row)) {
+        let subarray = row.get_subarray_id();
+        let candidate = next_candidate.entry(subarray).or_insert(0);
+        let target = loop {
+            if *candidate >= rows_per_subarray {
+                return Err(CompileError::Other("not enough unused rows to patch the program"));
+            }
+            let target = RowAddress(*candidate).local_rowaddress_to_subarray_id(subarray);
+            *candidate += 1;
+            if taken.insert(target) {
+                break target;
+            }
+        };
+        relocation.insert(row, target);
+    }
+    Ok(relocation)
+}
@@ -0,0 +1,149 @@
+//! The value-interference graph of a compiled network: two values interfere if they are live at
+//! the same time and hence can't share a row. The graph can be exported for external (register)
+//! allocators, whose row assignments can then be imported again.
+use std::fmt::Write;
+
+use super::architecture::{PRADAArchitecture, RowAddress};
+use super::compilation::{compile_with_schedule, reachable_nodes};
+use super::error::CompileError;
+use super::program::Program;
+use eggmock::{Id, Mig, NetworkWithBackwardEdges, Signal};
+use rustc_hash::FxHashMap;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone)]
+pub struct InterferenceGraph {
+    /// The values, i.e. nodes of the network (both phases of a node are live at the same time)
+    pub nodes: Vec<Id>,
+    /// Pairs of indices into `nodes` which are live at the same time
+    pub edges: Vec<(usize, usize)>,
+}
+
+impl InterferenceGraph {
+    /// Computes the interference graph for the order in which the nodes are computed, see
+    /// [compile_with_schedule]. Leaves are live from the beginning, outputs until the end of the
+    /// program and every other node until its last use.
+    pub fn new(network: &impl NetworkWithBackwardEdges<Node = Mig>, schedule: &[Id]) -> Self {
+        let position: FxHashMap<Id, usize> = schedule
+            .iter()
+            .enumerate()
+            .map(|(idx, id)| (*id, idx + 1))
+            .collect();
+        let end = schedule.len() + 1;
+        let outputs: Vec<Id> = network.outputs().map(|sig| sig.node_id()).collect();
+
+        let mut nodes = reachable_nodes(network);
+        nodes.reverse();
+        // [definition, last use] of every node
+        let live_ranges: Vec<(usize, usize)> = nodes
+            .iter()
+            .map(|id| {
+                let definition = position.get(id).copied().unwrap_or(0);
+                let last_use = if outputs.contains(id) {
+                    end
+                } else {
+                    network
+                        .node_outputs(*id)
+                        .filter_map(|parent| position.get(&parent).copied())
+                        .max()
+                        .unwrap_or(definition)
+                };
+                (definition, last_use)
+            })
+            .collect();
+
+        let mut edges = vec!();
+        for (i, (def_i, use_i)) in live_ranges.iter().enumerate() {
+            for (j, (def_j, use_j)) in live_ranges.iter().enumerate().skip(i + 1) {
+                // a value which is last used by an operation may be overwritten by its result
+                if def_i < use_j && def_j < use_i {
+                    edges.push((i, j));
+                }
+            }
+        }
+        Self { nodes, edges }
+    }
+
+    /// Renders the graph in Graphviz' DOT format, vertices are labeled by their node id
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("graph interference {\n");
+        for (idx, id) in self.nodes.iter().enumerate() {
+            writeln!(out, "  {idx} [label=\"{}\"];", id_to_usize(*id)).unwrap();
+        }
+        for (a, b) in &self.edges {
+            writeln!(out, "  {a} -- {b};").unwrap();
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    /// Renders the graph in the DIMACS format used by graph coloring tools. Vertex `i + 1`
+    /// corresponds to `nodes[i]`, the node ids are listed as comments.
+    pub fn to_dimacs(&self) -> String {
+        let mut out = String::new();
+        for (idx, id) in self.nodes.iter().enumerate() {
+            writeln!(out, "c vertex {} node {}", idx + 1, id_to_usize(*id)).unwrap();
+        }
+        writeln!(out, "p edge {} {}", self.nodes.len(), self.edges.len()).unwrap();
+        for (a, b) in &self.edges {
+            writeln!(out, "e {} {}", a + 1, b + 1).unwrap();
+        }
+        out
+    }
+
+    /// Parses a row assignment computed for this graph by an external tool, consisting of one
+    /// `<vertex> <row>` line per assigned vertex (vertices numbered as in [Self::to_dimacs], rows
+    /// being local to a subarray of `architecture`). Empty lines and lines starting with `c` are
+    /// ignored.
+    ///
+    /// Vertices may share a row as long as they don't interfere, assigning a vertex twice or two
+    /// interfering vertices the same row is an error.
+    pub fn parse_row_assignment(
+        &self,
+        architecture: &PRADAArchitecture,
+        text: &str,
+    ) -> Result<HashMap<Signal, RowAddress>, &'static str> {
+        // row of every assigned vertex, by index into `nodes`
+        let mut rows: FxHashMap<usize, u64> = FxHashMap::default();
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('c') {
+                continue;
+            }
+            let mut parts = line.split_whitespace();
+            let (Some(vertex), Some(row), None) = (parts.next(), parts.next(), parts.next()) else {
+                return Err("row assignment lines have to consist of a vertex and a row");
+            };
+            let vertex: usize = vertex.parse().map_err(|_| "invalid vertex in row assignment")?;
+            let row: u64 = row.parse().map_err(|_| "invalid row in row assignment")?;
+            if row >= architecture.rows_per_subarray {
+                return Err("row outside of the subarray in row assignment");
+            }
+            let idx = vertex
+                .checked_sub(1)
+                .filter(|idx| *idx < self.nodes.len())
+                .ok_or("unknown vertex in row assignment")?;
+            if rows.insert(idx, row).is_some() {
+                return Err("vertex is assigned twice in row assignment");
+            }
+        }
+        if self.edges.iter().any(|(a, b)| matches!((rows.get(a), rows.get(b)), (Some(a), Some(b)) if a == b)) {
+            return Err("interfering vertices are assigned the same row");
+        }
+        Ok(rows.into_iter().map(|(idx, row)| (Signal::new(self.nodes[idx], false), RowAddress(row))).collect())
+    }
+}
+
+/// Compiles the network and returns the program together with the interference graph of its
+/// values
+pub fn compile_with_interference<'a>(
+    architecture: &'a PRADAArchitecture,
+    network: &impl NetworkWithBackwardEdges<Node = Mig>,
+) -> Result<(Program<'a>, InterferenceGraph), CompileError> {
+    let (program, schedule) = compile_with_schedule(architecture, network)?;
+    let graph = InterferenceGraph::new(network, &schedule);
+    Ok((program, graph))
+}
+
+fn id_to_usize(id: Id) -> usize {
+    usize::from(eggmock::egg::Id::from(id))
+}
@@ -0,0 +1,71 @@
+//! Analysis of the inverters of a network. Inverted inputs (and constants) are free, since both of
+//! their phases are stored by the host when the input rows are initialized (see
+//! [RowInit::Input](super::program::RowInit::Input)); every other inverted signal costs an N.
+use super::compilation::reachable_nodes;
+use eggmock::egg::EGraph;
+use eggmock::{Id, Mig, MigLanguage, Network, Signal};
+use rustc_hash::{FxHashMap, FxHashSet};
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct InverterCount {
+    /// Nr of distinct inverted signals which are not inputs or constants, i.e. the nr of N
+    /// instructions required when every inverted signal is computed once
+    pub total: u64,
+    /// Maximal nr of such inverters on a single path from a leaf to an output
+    pub max_per_path: u64,
+}
+
+/// Counts the inverters of all nodes reachable from the outputs of the network
+pub fn count_inverters(network: &impl Network<Node = Mig>) -> InverterCount {
+    let is_inverter = |signal: Signal| signal.is_inverted() && !network.node(signal.node_id()).is_leaf();
+
+    let mut inverted: FxHashSet<Id> = FxHashSet::default();
+    let mut per_path: FxHashMap<Id, u64> = FxHashMap::default();
+    let mut nodes = reachable_nodes(network);
+    // visit inputs before the nodes using them
+    nodes.reverse();
+    for id in nodes {
+        let mut count = 0;
+        for input in network.node(id).inputs() {
+            let input_count = per_path.get(&input.node_id()).copied().unwrap_or(0);
+            if is_inverter(*input) {
+                inverted.insert(input.node_id());
+                count = count.max(input_count + 1);
+            } else {
+                count = count.max(input_count);
+            }
+        }
+        per_path.insert(id, count);
+    }
+
+    let mut max_per_path = 0;
+    for output in network.outputs() {
+        let mut count = per_path.get(&output.node_id()).copied().unwrap_or(0);
+        if is_inverter(output) {
+            inverted.insert(output.node_id());
+            count += 1;
+        }
+        max_per_path = max_per_path.max(count);
+    }
+    InverterCount {
+        total: inverted.len() as u64,
+        max_per_path,
+    }
+}
+
+/// Counts the inversions of non-leaf classes in an e-graph that has not been rewritten yet, i.e.
+/// which still contains exactly one node per class. This corresponds to [InverterCount::total] of
+/// the network that was sent to the compiler.
+pub fn count_egraph_inverters(graph: &EGraph<MigLanguage, ()>) -> u64 {
+    let is_leaf = |class: eggmock::egg::Id| {
+        graph[class]
+            .nodes
+            .iter()
+            .any(|node| matches!(node, MigLanguage::False | MigLanguage::Input(_)))
+    };
+    graph
+        .classes()
+        .flat_map(|class| &class.nodes)
+        .filter(|node| matches!(node, MigLanguage::Not(child) if !is_leaf(*child)))
+        .count() as u64
+}
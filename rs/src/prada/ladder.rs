@@ -0,0 +1,148 @@
+//! Retry ladder compiling a network with progressively more expensive settings until the program
+//! meets a [QualityTarget] or the time budget is used up, so that simple networks are compiled
+//! quickly while hard ones still get the full optimization effort.
+//!
+//! The [default rungs](Ladder::new) start without rewriting, then rewrite with the given settings,
+//! then rewrite more exhaustively (applying every rule in every iteration, with sharing-guided
+//! distributivity) and finally additionally schedule along critical paths. If the target bounds
+//! the energy consumption, the last rung also re-runs extraction with the energy objective (see
+//! [CompilerSettings::energy_budget]).
+use std::time::{Duration, Instant};
+
+use eggmock::{Mig, Network};
+
+use super::architecture::PRADAArchitecture;
+use super::compilation::SchedulingPolicy;
+use super::error::CompileError;
+use super::program::Program;
+use super::{compile, CompilerSettings, RunnerScheduler};
+
+/// Upper bounds of the estimated costs of a program, `None` for no bound
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct QualityTarget {
+    /// in ns
+    pub runtime: Option<u64>,
+    /// in mJ/KOps
+    pub energy_consumption: Option<u64>,
+}
+
+impl QualityTarget {
+    pub fn is_met(&self, program: &Program) -> bool {
+        program.runtime_estimate <= self.runtime.unwrap_or(u64::MAX)
+            && program.energy_consumption_estimate <= self.energy_consumption.unwrap_or(u64::MAX)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Rung {
+    pub name: String,
+    pub settings: CompilerSettings,
+}
+
+/// Outcome of compiling with one rung
+#[derive(Debug, Clone)]
+pub struct Attempt {
+    pub rung: String,
+    pub elapsed: Duration,
+    /// Estimated runtime and energy consumption of the program resp. the error compiling failed with
+    pub result: Result<(u64, u64), CompileError>,
+}
+
+#[derive(Debug, Clone)]
+pub struct LadderResult<'a> {
+    /// Best program of all rungs (by runtime, then energy), i.e. the program of the first rung
+    /// meeting the target if any rung did
+    pub program: Program<'a>,
+    /// Index of the rung which produced [Self::program]
+    pub rung: usize,
+    pub target_met: bool,
+    /// Attempts in the order of the rungs; rungs after the one meeting the target or exceeding the
+    /// time budget haven't been attempted
+    pub attempts: Vec<Attempt>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Ladder {
+    pub rungs: Vec<Rung>,
+    pub target: QualityTarget,
+    /// No rung after the first one is started once compiling has taken this long
+    pub time_budget: Option<Duration>,
+}
+
+impl Ladder {
+    /// The default rungs derived from `settings` (see the [module docs](self))
+    pub fn new(settings: CompilerSettings, target: QualityTarget) -> Self {
+        let exhaustive = CompilerSettings {
+            rewrite: true,
+            scheduler: RunnerScheduler::Simple,
+            sharing_guided_distributivity: true,
+            ..settings
+        };
+        let critical_path = CompilerSettings {
+            scheduling: SchedulingPolicy::CriticalPath,
+            energy_budget: target.energy_consumption.unwrap_or(settings.energy_budget),
+            ..exhaustive
+        };
+        let rung = |name: &str, settings| Rung { name: name.to_string(), settings };
+        Self {
+            rungs: vec!(
+                rung("no_rewrite", CompilerSettings { rewrite: false, ..settings }),
+                rung("rewrite", CompilerSettings { rewrite: true, ..settings }),
+                rung("exhaustive_rewrite", exhaustive),
+                rung("critical_path", critical_path),
+            ),
+            target,
+            time_budget: None,
+        }
+    }
+
+    pub fn with_time_budget(self, time_budget: Duration) -> Self {
+        Self { time_budget: Some(time_budget), ..self }
+    }
+
+    /// Compiles `network` with one rung after another until the target is met, the time budget is
+    /// exceeded or all rungs have been attempted. Fails only if no rung compiled the network, with
+    /// the error of the last one.
+    pub fn run<'a>(
+        &self,
+        architecture: &'a PRADAArchitecture,
+        network: &impl Network<Node = Mig>,
+    ) -> Result<LadderResult<'a>, CompileError> {
+        let start = Instant::now();
+        let mut attempts = vec!();
+        let mut best: Option<(usize, Program<'a>)> = None;
+        let mut error = CompileError::Other("the ladder has no rungs");
+        for (index, rung) in self.rungs.iter().enumerate() {
+            if index > 0 && self.time_budget.is_some_and(|budget| start.elapsed() >= budget) {
+                break;
+            }
+            let rung_start = Instant::now();
+            let result = compile(architecture, network, rung.settings);
+            let elapsed = rung_start.elapsed();
+            match result {
+                Ok(program) => {
+                    let costs = (program.runtime_estimate, program.energy_consumption_estimate);
+                    attempts.push(Attempt { rung: rung.name.clone(), elapsed, result: Ok(costs) });
+                    let met = self.target.is_met(&program);
+                    let better = !matches!(
+                        &best,
+                        Some((_, best)) if costs >= (best.runtime_estimate, best.energy_consumption_estimate)
+                    );
+                    if met || better {
+                        best = Some((index, program));
+                    }
+                    if met {
+                        break;
+                    }
+                }
+                Err(err) => {
+                    attempts.push(Attempt { rung: rung.name.clone(), elapsed, result: Err(err.clone()) });
+                    error = err;
+                }
+            }
+        }
+        let (rung, program) = best.ok_or(error)?;
+        let target_met = self.target.is_met(&program);
+        Ok(LadderResult { program, rung, target_met, attempts })
+    }
+}
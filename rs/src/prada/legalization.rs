@@ -0,0 +1,138 @@
+//! Legalization of networks for architectures lacking some capabilities, by emulating the missing
+//! operations instead of failing with [CompileError::UnsupportedOperation].
+//!
+//! Currently this covers missing in-place negation ([Capabilities::NOT]): since MAJ is self-dual
+//! (`!MAJ(a, b, c) = MAJ(!a, !b, !c)`), every inversion can be pushed towards the leaves, whose
+//! inverted versions are initialized by the host anyway. This duplicates the inverted parts of the
+//! network (dual-rail logic) but doesn't require a single N instruction.
+use super::architecture::{Capabilities, PRADAArchitecture};
+use super::compilation::{compile_with_deadlines, reachable_nodes, CompileOptions};
+use super::coverage::{self, CodePath};
+use super::diagnostics::{diagnose, Severity};
+use super::error::CompileError;
+use super::inverters::count_inverters;
+use super::network::MigNetwork;
+use super::passes::run_registered;
+use super::program::Program;
+use eggmock::{Mig, Network, Signal};
+use rustc_hash::{FxHashMap, FxHashSet};
+
+/// Overhead of emulating missing capabilities
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct LegalizationReport {
+    /// Nr of inverted (non-leaf) signals which have been emulated instead of using N
+    pub emulated_inversions: u64,
+    /// Nr of MAJ nodes added for computing the complemented signals
+    pub additional_majs: u64,
+}
+
+/// Compiles the network, legalizing it first if the architecture lacks capabilities which can be
+/// emulated or if [CompileOptions::dual_rail] is set, and runs the registered
+/// [passes](super::passes) on the program
+pub fn compile_legalized<'a>(
+    architecture: &'a PRADAArchitecture,
+    network: &impl Network<Node = Mig>,
+    options: CompileOptions,
+) -> Result<(Program<'a>, LegalizationReport), CompileError> {
+    compile_legalized_with_deadlines(architecture, network, options, &[])
+}
+
+/// Same as [compile_legalized], but schedules the outputs by their deadlines (by output index), see
+/// [compile_with_deadlines]
+pub fn compile_legalized_with_deadlines<'a>(
+    architecture: &'a PRADAArchitecture,
+    network: &impl Network<Node = Mig>,
+    options: CompileOptions,
+    deadlines: &[Option<u64>],
+) -> Result<(Program<'a>, LegalizationReport), CompileError> {
+    if architecture.supports(Capabilities::NOT) && !options.dual_rail {
+        let mut program = compile_with_deadlines(architecture, &network.with_backward_edges(), options, deadlines)?;
+        run_registered(&mut program)?;
+        program.diagnostics = diagnose(&program, network, &LegalizationReport::default());
+        escalate(&mut program, options)?;
+        return Ok((program, LegalizationReport::default()));
+    }
+    let (legalized, report) = push_inversions_to_leaves(network);
+    if report.emulated_inversions > 0 {
+        coverage::hit(CodePath::EmulatedInversion);
+    }
+    let mut program = compile_with_deadlines(architecture, &legalized.with_backward_edges(), options, deadlines)?;
+    run_registered(&mut program)?;
+    // dual-rail logic which has been asked for isn't an emulation
+    let emulated = if architecture.supports(Capabilities::NOT) { LegalizationReport::default() } else { report };
+    program.diagnostics = diagnose(&program, network, &emulated);
+    escalate(&mut program, options)?;
+    Ok((program, report))
+}
+
+/// Fails if any diagnostic of `program` is escalated by [CompileOptions::strictness], returning the
+/// escalated diagnostics with the error
+pub(super) fn escalate(program: &mut Program, options: CompileOptions) -> Result<(), CompileError> {
+    let Some(code) = options.strictness.escalate(&mut program.diagnostics) else {
+        return Ok(());
+    };
+    let diagnostics =
+        program.diagnostics.iter().filter(|diagnostic| diagnostic.severity == Severity::Error).cloned().collect();
+    Err(CompileError::Escalated { code, diagnostics })
+}
+
+/// Returns an equivalent network in which only leaves (inputs and constants) are referenced
+/// inverted. Input indices are preserved.
+pub fn push_inversions_to_leaves(network: &impl Network<Node = Mig>) -> (MigNetwork, LegalizationReport) {
+    // determine the polarities in which every node is needed, starting at the outputs
+    let mut needed: FxHashSet<Signal> = FxHashSet::default();
+    let mut stack: Vec<Signal> = network.outputs().collect();
+    while let Some(signal) = stack.pop() {
+        if !needed.insert(signal) {
+            continue;
+        }
+        if let Mig::Maj(inputs) = network.node(signal.node_id()) {
+            stack.extend(inputs.map(|input| input.maybe_invert(signal.is_inverted())));
+        }
+    }
+
+    let mut legalized = MigNetwork::new();
+    let nodes = reachable_nodes(network);
+    let nr_inputs = nodes
+        .iter()
+        .filter_map(|id| match network.node(*id) {
+            Mig::Input(i) => Some(i + 1),
+            _ => None,
+        })
+        .max()
+        .unwrap_or(0);
+    let inputs: Vec<Signal> = (0..nr_inputs).map(|_| legalized.add_input()).collect();
+
+    let mut mapped: FxHashMap<Signal, Signal> = FxHashMap::default();
+    let mut additional_majs = 0;
+    // visit inputs before the nodes using them
+    for id in nodes.into_iter().rev() {
+        for inverted in [false, true] {
+            let signal = Signal::new(id, inverted);
+            if !needed.contains(&signal) {
+                continue;
+            }
+            let new_signal = match network.node(id) {
+                Mig::False => legalized.constant(inverted),
+                Mig::Input(i) => inputs[i as usize].maybe_invert(inverted),
+                Mig::Maj(maj_inputs) => {
+                    let [a, b, c] = maj_inputs.map(|input| mapped[&input.maybe_invert(inverted)]);
+                    if inverted {
+                        additional_majs += 1;
+                    }
+                    legalized.maj(a, b, c)
+                }
+            };
+            mapped.insert(signal, new_signal);
+        }
+    }
+    for output in network.outputs() {
+        legalized.add_output(mapped[&output]);
+    }
+
+    let report = LegalizationReport {
+        emulated_inversions: count_inverters(network).total,
+        additional_majs,
+    };
+    (legalized, report)
+}
@@ -0,0 +1,153 @@
+//! Reproducibility manifests: everything needed to re-run a compilation exactly, i.e. the compiler
+//! version, the architecture (by its [architecture_fingerprint]), the
+//! [settings](CompilerSettings::snapshot), the [rewrite rules](CompilerSettings::rules_hash) they
+//! select, the extractor and the seed of the experiment, plus the network itself (as
+//! [kernel](super::dsl)) and hashes of the network and of the compiled program to check the re-run
+//! against. The `repro` example re-runs manifests from the command line:
+//!
+//! ```sh
+//! cargo run --example repro experiment.manifest
+//! ```
+use std::fmt::{Display, Formatter};
+
+use eggmock::{Mig, Network};
+
+use super::architecture::PRADAArchitecture;
+use super::artifact::{architecture_fingerprint, fnv1a, serialize, COMPILER_VERSION};
+use super::cache::network_hash;
+use super::dsl::{parse_kernel, to_kernel};
+use super::error::CompileError;
+use super::network::MigNetwork;
+use super::program::Program;
+use super::{compile, CompilerSettings};
+
+const MAGIC: &str = "# prada manifest";
+
+/// Extractor of [compile], the only one manifests can be re-run with, since custom cost functions
+/// (see [compile_with_cost_function](super::compile_with_cost_function)) can't be recorded
+pub const DEFAULT_EXTRACTOR: &str = "compiling";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Manifest {
+    pub compiler_version: String,
+    pub architecture: u64,
+    /// [CompilerSettings::rules_hash]
+    pub rules: u64,
+    pub extractor: String,
+    /// [CompilerSettings::seed], i.e. the seed of the stochastic parts of the experiment, e.g. of the
+    /// inputs the program is verified on
+    pub seed: u64,
+    /// [CompilerSettings::snapshot]
+    pub settings: String,
+    /// [network_hash] of the compiled network
+    pub network: u64,
+    /// Hash of the [serialized](super::artifact::serialize) program
+    pub program: u64,
+    /// The compiled network, see [to_kernel]
+    pub kernel: String,
+}
+
+impl Manifest {
+    /// Manifest of compiling `network` into `program` with [compile] using `settings`
+    pub fn new(network: &impl Network<Node = Mig>, settings: &CompilerSettings, program: &Program) -> Self {
+        Self {
+            compiler_version: COMPILER_VERSION.to_string(),
+            architecture: architecture_fingerprint(program.architecture),
+            rules: settings.rules_hash(),
+            extractor: DEFAULT_EXTRACTOR.to_string(),
+            seed: settings.seed,
+            settings: settings.snapshot(),
+            network: network_hash(network),
+            program: fnv1a(&serialize(program, settings)),
+            kernel: to_kernel(network, 0),
+        }
+    }
+
+    /// Loads a manifest written by its [Display] implementation
+    pub fn parse(text: &str) -> Result<Self, &'static str> {
+        let mut lines = text.lines();
+        let mut field = |name: &str| -> Result<String, &'static str> {
+            let line = lines.next().ok_or("incomplete manifest")?;
+            Ok(line.strip_prefix(name).ok_or("malformed manifest")?.trim().to_string())
+        };
+        if !field(MAGIC)?.is_empty() {
+            return Err("not a manifest");
+        }
+        let parse_hash = |hash: String| u64::from_str_radix(&hash, 16).map_err(|_| "malformed hash");
+        Ok(Self {
+            compiler_version: field("compiler:")?,
+            architecture: parse_hash(field("architecture:")?)?,
+            rules: parse_hash(field("rules:")?)?,
+            extractor: field("extractor:")?,
+            seed: field("seed:")?.parse().map_err(|_| "malformed seed")?,
+            settings: field("settings:")?,
+            network: parse_hash(field("network:")?)?,
+            program: parse_hash(field("program:")?)?,
+            kernel: lines.map(|line| format!("{line}\n")).collect(),
+        })
+    }
+
+    /// The recorded settings (see [CompilerSettings::from_snapshot]) with the recorded seed
+    pub fn settings(&self) -> Result<CompilerSettings, &'static str> {
+        Ok(CompilerSettings { seed: self.seed, ..CompilerSettings::from_snapshot(&self.settings)? })
+    }
+
+    /// The recorded network
+    pub fn network(&self) -> Result<MigNetwork, &'static str> {
+        let network = parse_kernel(&self.kernel)?.network().clone();
+        if network_hash(&network) != self.network {
+            return Err("the kernel doesn't match the hash of the network");
+        }
+        Ok(network)
+    }
+
+    /// Re-runs the recorded compilation for `architecture`, failing if anything which affects the
+    /// program differs from the recorded configuration or if the program differs from the recorded
+    /// one
+    pub fn reproduce<'a>(&self, architecture: &'a PRADAArchitecture) -> Result<Program<'a>, CompileError> {
+        if self.compiler_version != COMPILER_VERSION {
+            return Err(CompileError::Other("the manifest has been recorded by a different compiler version"));
+        }
+        if self.architecture != architecture_fingerprint(architecture) {
+            return Err(CompileError::Other("the manifest has been recorded for a different architecture"));
+        }
+        if self.extractor != DEFAULT_EXTRACTOR {
+            return Err(CompileError::Other("only compilations using the default extractor can be re-run"));
+        }
+        let settings = self.settings()?;
+        if settings.rules_hash() != self.rules {
+            return Err(CompileError::Other("the manifest has been recorded with different rewrite rules"));
+        }
+        let program = compile(architecture, &self.network()?, settings)?;
+        if fnv1a(&serialize(&program, &settings)) != self.program {
+            return Err(CompileError::Other("the re-run compiled a different program"));
+        }
+        Ok(program)
+    }
+}
+
+impl Display for Manifest {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{MAGIC}")?;
+        writeln!(f, "compiler: {}", self.compiler_version)?;
+        writeln!(f, "architecture: {:016x}", self.architecture)?;
+        writeln!(f, "rules: {:016x}", self.rules)?;
+        writeln!(f, "extractor: {}", self.extractor)?;
+        writeln!(f, "seed: {}", self.seed)?;
+        writeln!(f, "settings: {}", self.settings)?;
+        writeln!(f, "network: {:016x}", self.network)?;
+        writeln!(f, "program: {:016x}", self.program)?;
+        write!(f, "{}", self.kernel)
+    }
+}
+
+/// Same as [compile], but also returns the [Manifest] of the compilation
+pub fn compile_reproducible<'a>(
+    architecture: &'a PRADAArchitecture,
+    network: &impl Network<Node = Mig>,
+    settings: CompilerSettings,
+) -> Result<(Program<'a>, Manifest), CompileError> {
+    let program = compile(architecture, network, settings)?;
+    let manifest = Manifest::new(network, &settings, &program);
+    Ok((program, manifest))
+}
@@ -0,0 +1,197 @@
+//! Metadata frontends attach to the nodes of a network (e.g. the names and bit indices of the RTL
+//! signals or timing constraints), which survives rewriting and extraction and ends up in
+//! [Program::metadata], so that e.g. the outputs in the listing of a program carry their RTL names.
+//!
+//! Like an e-class analysis, the metadata is tracked per e-class: the annotated nodes are located
+//! in the e-graph before rewriting and the metadata of classes which rewriting merges is merged as
+//! well (see [NodeMetadata::merge]). It is kept beside the e-graph, so that the rewrite rules stay
+//! independent of it.
+use std::collections::HashMap;
+
+use eggmock::egg::{EGraph, Id, Language};
+use eggmock::{Mig, MigLanguage, Network, Signal};
+use rustc_hash::FxHashMap;
+
+use super::compilation::reachable_nodes;
+use super::diagnostics::Location;
+use super::overrides::TermBuilder;
+use super::program::{Program, RowInit};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct NodeMetadata {
+    /// Name of the signal, e.g. of the wire in the RTL description
+    pub name: Option<String>,
+    /// Index of the bit within the bus [Self::name] refers to
+    pub bit: Option<u32>,
+    /// Time (in ns) from the start of the program until which the value has to be computed
+    pub deadline: Option<u64>,
+}
+
+impl NodeMetadata {
+    pub fn named(name: impl Into<String>) -> Self {
+        Self { name: Some(name.into()), ..Self::default() }
+    }
+
+    /// Metadata of bit `bit` of the bus `name`
+    pub fn bit(name: impl Into<String>, bit: u32) -> Self {
+        Self { name: Some(name.into()), bit: Some(bit), ..Self::default() }
+    }
+
+    pub fn with_deadline(self, deadline: u64) -> Self {
+        Self { deadline: Some(deadline), ..self }
+    }
+
+    /// Name of the signal including the bit index, e.g. `sum[3]`
+    pub fn label(&self) -> Option<String> {
+        let name = self.name.as_ref()?;
+        Some(match self.bit {
+            Some(bit) => format!("{name}[{bit}]"),
+            None => name.clone(),
+        })
+    }
+
+    /// Merges the metadata of an equivalent node into this one: the (lexicographically) smaller
+    /// label is kept and the tighter deadline applies. Merging is commutative, so the result
+    /// doesn't depend on the order in which rewriting merges classes.
+    pub fn merge(&mut self, other: &Self) {
+        let keep_own = match (&self.name, &other.name) {
+            (Some(_), None) => true,
+            (None, Some(_)) => false,
+            _ => (&self.name, self.bit) <= (&other.name, other.bit),
+        };
+        if !keep_own {
+            self.name.clone_from(&other.name);
+            self.bit = other.bit;
+        }
+        self.deadline = match (self.deadline, other.deadline) {
+            (Some(own), Some(other)) => Some(own.min(other)),
+            (own, other) => own.or(other),
+        };
+    }
+}
+
+/// Terms of the annotated signals, independent of the e-graph they are added to
+#[derive(Debug, Clone, Default)]
+pub(crate) struct MetadataTerms {
+    terms: TermBuilder,
+    /// Node of `terms` of every annotated signal
+    roots: Vec<(Id, NodeMetadata)>,
+}
+
+impl MetadataTerms {
+    pub fn new(network: &impl Network<Node = Mig>, metadata: &HashMap<Signal, NodeMetadata>) -> Self {
+        let mut terms = TermBuilder::default();
+        let roots = metadata
+            .iter()
+            .map(|(signal, metadata)| (terms.add_signal(network, *signal), metadata.clone()))
+            .collect();
+        Self { terms, roots }
+    }
+
+    /// Locates the annotated signals in `graph`, which has to hold the network as received, i.e.
+    /// must not have been rewritten yet. Signals which don't drive any output aren't part of the
+    /// e-graph and are skipped.
+    pub fn locate(&self, graph: &EGraph<MigLanguage, ()>) -> Vec<(Id, NodeMetadata)> {
+        let mut ids: Vec<Option<Id>> = vec!();
+        for node in self.terms.expr.as_ref() {
+            let id = node
+                .clone()
+                .try_map_children(|child| ids[usize::from(child)].ok_or(()))
+                .ok()
+                .and_then(|node| graph.lookup(node));
+            ids.push(id);
+        }
+        self.roots
+            .iter()
+            .filter_map(|(root, metadata)| Some((ids[usize::from(*root)]?, metadata.clone())))
+            .collect()
+    }
+}
+
+/// Metadata of every class of `graph` after rewriting, merging the metadata of the classes which
+/// have been merged by rewriting
+pub(crate) fn canonical_metadata(
+    graph: &EGraph<MigLanguage, ()>,
+    located: &[(Id, NodeMetadata)],
+) -> FxHashMap<Id, NodeMetadata> {
+    let mut metadata: FxHashMap<Id, NodeMetadata> = FxHashMap::default();
+    for (id, node_metadata) in located {
+        metadata
+            .entry(graph.find(*id))
+            .and_modify(|existing| existing.merge(node_metadata))
+            .or_insert_with(|| node_metadata.clone());
+    }
+    metadata
+}
+
+/// Metadata of the inputs (by index and polarity) and outputs of a network, resolved before
+/// compiling it
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ResolvedMetadata {
+    inputs: FxHashMap<(u64, bool), NodeMetadata>,
+    outputs: Vec<Option<NodeMetadata>>,
+}
+
+impl ResolvedMetadata {
+    /// Resolves the metadata of the classes of `graph` (see [canonical_metadata]) for the network
+    /// extracted from `graph` with the given `outputs`
+    pub fn from_graph(graph: &EGraph<MigLanguage, ()>, outputs: &[Id], metadata: &FxHashMap<Id, NodeMetadata>) -> Self {
+        if metadata.is_empty() {
+            return Self::default();
+        }
+        let class_metadata = |id: Option<Id>| id.and_then(|id| metadata.get(&graph.find(id))).cloned();
+        let mut inputs = FxHashMap::default();
+        for class in graph.classes() {
+            for node in class.iter() {
+                if let MigLanguage::Input(index) = node {
+                    for (inverted, id) in [(false, Some(class.id)), (true, graph.lookup(MigLanguage::Not(class.id)))] {
+                        if let Some(metadata) = class_metadata(id) {
+                            inputs.insert((*index, inverted), metadata);
+                        }
+                    }
+                }
+            }
+        }
+        let outputs = outputs.iter().map(|output| class_metadata(Some(*output))).collect();
+        Self { inputs, outputs }
+    }
+
+    /// Resolves the metadata of the signals of `network` for compiling it as received
+    pub fn from_network(network: &impl Network<Node = Mig>, metadata: &HashMap<Signal, NodeMetadata>) -> Self {
+        let mut inputs = FxHashMap::default();
+        for id in reachable_nodes(network) {
+            if let Mig::Input(index) = network.node(id) {
+                for inverted in [false, true] {
+                    if let Some(metadata) = metadata.get(&Signal::new(id, inverted)) {
+                        inputs.insert((index, inverted), metadata.clone());
+                    }
+                }
+            }
+        }
+        let outputs = network.outputs().map(|output| metadata.get(&output).cloned()).collect();
+        Self { inputs, outputs }
+    }
+
+    /// Deadline of every output (by index), see [NodeMetadata::deadline]
+    pub fn deadlines(&self) -> Vec<Option<u64>> {
+        self.outputs.iter().map(|metadata| metadata.as_ref().and_then(|metadata| metadata.deadline)).collect()
+    }
+
+    /// Sets [Program::metadata] of `program`, which has been compiled from the network
+    pub fn annotate(&self, program: &mut Program) {
+        let mut annotations = vec!();
+        for (row, init) in &program.input_map {
+            if let RowInit::Input { index, inverted } = init {
+                if let Some(metadata) = self.inputs.get(&(*index, *inverted)) {
+                    annotations.push((Location::Row(*row), metadata.clone()));
+                }
+            }
+        }
+        for (output, metadata) in self.outputs.iter().enumerate() {
+            if let Some(metadata) = metadata {
+                annotations.push((Location::Output(output), metadata.clone()));
+            }
+        }
+        program.metadata = annotations;
+    }
+}
@@ -0,0 +1,101 @@
+//! Opt-in metrics on the behavior of the row allocator, see
+//! [CompileOptions::allocator_metrics](super::compilation::CompileOptions::allocator_metrics):
+//! how long rows stay free before being reused and how fragmented the free rows are. Written as
+//! JSON, so that allocator changes can be judged by data collected over many networks.
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use rustc_hash::FxHashMap;
+
+use super::architecture::RowAddress;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AllocatorMetrics {
+    /// Histogram of the reuse distances, i.e. the nr of instructions between freeing a row and
+    /// allocating it again. Bucket 0 counts distance 0, bucket `i > 0` distances in
+    /// `2^(i-1)..2^i`.
+    pub reuse_distances: Vec<u64>,
+    /// Nr of allocations of rows which haven't been freed before
+    pub first_uses: u64,
+    pub allocations: u64,
+    /// Mean resp. maximal fragmentation of the free rows over all allocations, where the
+    /// fragmentation is the share of free rows outside of the largest contiguous range of free
+    /// rows (0 if all free rows are contiguous)
+    pub average_fragmentation: f64,
+    pub max_fragmentation: f64,
+    /// Least nr of free rows observed at an allocation
+    pub min_free_rows: Option<u64>,
+    /// Instruction count at which each currently free row has been freed
+    freed_at: FxHashMap<RowAddress, usize>,
+}
+
+impl AllocatorMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records that `row` has been allocated after `instruction` instructions, taking it from
+    /// `free_rows` (from which it already has been removed)
+    pub fn record_allocation(&mut self, row: RowAddress, instruction: usize, free_rows: &[RowAddress]) {
+        match self.freed_at.remove(&row) {
+            Some(freed_at) => {
+                let distance = (instruction - freed_at) as u64;
+                let bucket = (u64::BITS - distance.leading_zeros()) as usize;
+                if self.reuse_distances.len() <= bucket {
+                    self.reuse_distances.resize(bucket + 1, 0);
+                }
+                self.reuse_distances[bucket] += 1;
+            }
+            None => self.first_uses += 1,
+        }
+
+        let fragmentation = fragmentation(free_rows);
+        self.average_fragmentation =
+            (self.average_fragmentation * self.allocations as f64 + fragmentation) / (self.allocations + 1) as f64;
+        self.max_fragmentation = self.max_fragmentation.max(fragmentation);
+        self.allocations += 1;
+        let nr_free_rows = free_rows.len() as u64;
+        self.min_free_rows = Some(self.min_free_rows.map_or(nr_free_rows, |min| min.min(nr_free_rows)));
+    }
+
+    /// Records that `row` has been freed after `instruction` instructions
+    pub fn record_free(&mut self, row: RowAddress, instruction: usize) {
+        self.freed_at.insert(row, instruction);
+    }
+
+    /// Single line JSON object of the metrics
+    pub fn to_json(&self) -> String {
+        let histogram: Vec<String> = self.reuse_distances.iter().map(u64::to_string).collect();
+        format!(
+            "{{\"allocations\": {}, \"first_uses\": {}, \"reuse_distances\": [{}], \"average_fragmentation\": {:.4}, \"max_fragmentation\": {:.4}, \"min_free_rows\": {}}}",
+            self.allocations,
+            self.first_uses,
+            histogram.join(", "),
+            self.average_fragmentation,
+            self.max_fragmentation,
+            self.min_free_rows.map_or("null".to_string(), |rows| rows.to_string())
+        )
+    }
+
+    pub fn write_to_file(&self, path: &Path) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        writeln!(out, "{}", self.to_json())?;
+        out.flush()
+    }
+}
+
+/// Share of `free_rows` outside of their largest contiguous range
+pub fn fragmentation(free_rows: &[RowAddress]) -> f64 {
+    if free_rows.is_empty() {
+        return 0.0;
+    }
+    let mut rows: Vec<u64> = free_rows.iter().map(|row| row.0).collect();
+    rows.sort_unstable();
+    let (mut largest, mut current) = (1, 1);
+    for pair in rows.windows(2) {
+        current = if pair[1] == pair[0] + 1 { current + 1 } else { 1 };
+        largest = largest.max(current);
+    }
+    1.0 - largest as f64 / rows.len() as f64
+}
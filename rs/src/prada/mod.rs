@@ -1,24 +1,115 @@
-mod architecture;
+pub mod activity;
+pub mod annotation;
+pub mod approximate;
+pub mod architecture;
+pub mod artifact;
+pub mod bnn;
+pub mod bundle;
+pub mod cache;
+pub mod candidates;
+pub mod cec;
 mod compilation;
+pub mod constants;
+pub mod cost;
+pub mod coverage;
+pub mod decisions;
+pub mod diagnostics;
+pub mod dense;
+pub mod dsl;
+#[cfg(feature = "egglog")]
+mod egglog_backend;
+pub mod error;
+#[cfg(feature = "sqlite")]
+pub mod experiments;
+mod explanation;
 mod extraction;
-mod program;
+pub mod fragment;
+pub mod incremental;
+pub mod interference;
+mod inverters;
+pub mod ladder;
+mod legalization;
+pub mod manifest;
+pub mod metadata;
+pub mod metrics;
+pub mod module;
+pub mod network;
+pub mod obfuscation;
+#[cfg(feature = "onnx")]
+pub mod onnx;
+mod overrides;
+pub mod passes;
+pub mod program;
+pub mod random;
+pub mod reference;
+pub mod report;
 mod rows;
+pub mod rowhammer;
+mod rules;
+pub mod sequential;
+pub mod simulation;
+pub mod stdlib;
+pub mod stubs;
+pub mod symbolic;
+pub mod symbols;
+mod telemetry;
+pub mod timing;
+pub mod trace;
+pub mod verilog;
+pub mod waveform;
+pub mod words;
 
-use std::sync::LazyLock;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ffi::{c_char, CStr};
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::sync::Arc;
 use std::time::Instant;
 
-use self::compilation::compile;
-use self::extraction::CompilingCostFunction;
+use self::annotation::AnnotationReceiverFFI;
+use self::cec::{check_equivalence, Equivalence};
+use self::compilation::reachable_nodes;
+pub use self::architecture::PRADAArchitecture as Architecture;
+pub use self::compilation::{
+    compile_replaying, compile_with_placement, estimate_rows_needed, CompileOptions, SchedulingPolicy,
+};
+pub use self::diagnostics::Strictness;
+pub use self::error::CompileError;
+pub use self::extraction::{
+    CompilingCostFunction, CostFeatures, CostFn, CostMemo, EnergyFirst, EnergyObjective, ExtractionCostFunction,
+};
+#[cfg(feature = "parallel-extraction")]
+pub use self::extraction::{benchmark_extraction, ExtractionBenchmark};
+pub use self::fragment::{Linker, ProgramFragment};
+pub use self::metadata::NodeMetadata;
+pub use self::module::{Module, ModuleLibrary};
+pub use self::network::MigNetwork;
+pub use self::overrides::ExtractionOverride;
+pub use self::program::{ControlRow, Instruction, Program, RowInit};
+pub use self::simulation::Simulator;
+use self::explanation::explain_outputs;
+use self::diagnostics::{Diagnostic, DiagnosticCode};
+use self::extraction::{extract, graph_fingerprint, Restricted};
+use self::inverters::{count_egraph_inverters, count_inverters};
+use self::legalization::{compile_legalized_with_deadlines, escalate, LegalizationReport};
+use self::metadata::{canonical_metadata, MetadataTerms, ResolvedMetadata};
+use self::overrides::{canonical_pins, locate_pinned_nodes, pinned_terms, scoped_terms, PinnedTerm};
+use self::rules::{REWRITE_RULES, SHARING_GUIDED_REWRITE_RULES};
+use self::telemetry::{with_telemetry, EGraphTelemetry};
+use self::timing::shallowest_nodes;
 
+pub use crate::opt_extractor::OptCostFunction;
 use crate::opt_extractor::{OptExtractionNetwork, OptExtractor};
-use crate::prada::architecture::{PRADAArchitecture, ARCHITECTURE};
-use eggmock::egg::{rewrite, EGraph, Rewrite, Runner};
-use eggmock::{Mig, MigLanguage, MigReceiverFFI, Network, Receiver, ReceiverFFI};
+use crate::prada::architecture::{PRADAArchitecture, SubarrayId, ARCHITECTURE};
+use crate::prada::cost::{CpuBaseline, HostTransferModel};
+use eggmock::egg::{BackoffScheduler, EGraph, Id, Rewrite, Runner, SimpleScheduler};
+use eggmock::{Mig, MigLanguage, MigReceiverFFI, Network, Receiver, ReceiverFFI, Signal};
 use program::*;
 use rows::*;
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
-pub enum BitwiseOperand {
+pub(crate) enum BitwiseOperand {
     T(u8),
 }
 
@@ -35,22 +126,14 @@ impl BitwiseOperand {
 }
 
 
-static REWRITE_RULES: LazyLock<Vec<Rewrite<MigLanguage, ()>>> = LazyLock::new(|| {
-    let mut rules = vec![
-        rewrite!("commute_1"; "(maj ?a ?b ?c)" => "(maj ?b ?a ?c)"),
-        rewrite!("commute_2"; "(maj ?a ?b ?c)" => "(maj ?a ?c ?b)"),
-        rewrite!("not_not"; "(! (! ?a))" => "?a"),
-        rewrite!("maj_1"; "(maj ?a ?a ?b)" => "?a"),
-        rewrite!("maj_2"; "(maj ?a (! ?a) ?b)" => "?b"),
-        rewrite!("associativity"; "(maj ?a ?b (maj ?c ?b ?d))" => "(maj ?d ?b (maj ?c ?b ?a))"),
-    ];
-    rules.extend(rewrite!("invert"; "(! (maj ?a ?b ?c))" <=> "(maj (! ?a) (! ?b) (! ?c))"));
-    rules.extend(rewrite!("distributivity"; "(maj ?a ?b (maj ?c ?d ?e))" <=> "(maj (maj ?a ?b ?c) (maj ?a ?b ?d) ?e)"));
-    rules
-});
-
-struct CompilingReceiverResult<'a> {
-    output: CompilerOutput<'a>,
+struct CompilingReceiverResult<'a, CF: ExtractionCostFunction> {
+    output: CompilerOutput<'a, CF>,
+    /// For every output a proof of its equivalence to the original network, if explanations are
+    /// enabled
+    explanations: Option<Vec<String>>,
+    /// Nr of inverters of the network before rewriting, see [inverters::InverterCount::total]
+    inverters_before: u64,
+    legalization: LegalizationReport,
 
     t_runner: u128,
     t_extractor: u128,
@@ -58,25 +141,102 @@ struct CompilingReceiverResult<'a> {
 }
 
 #[ouroboros::self_referencing]
-struct CompilerOutput<'a> {
+struct CompilerOutput<'a, CF: ExtractionCostFunction> {
     graph: EGraph<MigLanguage, ()>,
     #[borrows(graph)]
     #[covariant]
-    ntk: OptExtractionNetwork<OptExtractor<'this, CompilingCostFunction<'a>, MigLanguage, ()>>,
+    ntk: OptExtractionNetwork<OptExtractor<'this, Restricted<CF>, MigLanguage, ()>>,
     #[borrows(ntk)]
     program: Program<'a>,
 }
 
-fn compiling_receiver<'a>(
+/// Rewrites `graph` with egglog (see [RewriteBackend::Egglog]), using the rules selected by
+/// `settings`
+#[cfg(feature = "egglog")]
+fn rewrite_with_egglog(
+    graph: &EGraph<MigLanguage, ()>,
+    settings: CompilerSettings,
+) -> Result<EGraph<MigLanguage, ()>, CompileError> {
+    let rule_set = rules::RuleSet { sharing_guided_distributivity: settings.sharing_guided_distributivity };
+    let (graph, report) = egglog_backend::saturate(graph, rule_set)?;
+    if settings.verbose {
+        println!("== egglog Report");
+        println!("iterations: {}, tuples: {}, saturated: {}", report.iterations, report.tuples, report.saturated);
+    }
+    Ok(graph)
+}
+
+#[cfg(not(feature = "egglog"))]
+fn rewrite_with_egglog(
+    _: &EGraph<MigLanguage, ()>,
+    _: CompilerSettings,
+) -> Result<EGraph<MigLanguage, ()>, CompileError> {
+    Err(CompileError::Other("rewriting with egglog requires the `egglog` feature"))
+}
+
+/// Creates the [CompilingCostFunction] for the e-graph received by the compiling receiver
+fn compiling_cost_function<'a>(
+    architecture: &'a PRADAArchitecture,
+) -> impl FnOnce(&EGraph<MigLanguage, ()>) -> CompilingCostFunction<'a> + 'a {
+    move |graph| CompilingCostFunction::new(architecture, graph)
+}
+
+/// Rewrites and extracts the received network and compiles the result. The cost function for
+/// extracting is created from the e-graph before rewriting by `cost_function`. Memoized choices
+/// are only valid for the [CompilingCostFunction] (see [compile_memoized]).
+fn compiling_receiver<'a, CF: ExtractionCostFunction + 'a>(
     architecture: &'a PRADAArchitecture,
     rules: &'a [Rewrite<MigLanguage, ()>],
     settings: CompilerSettings,
-) -> impl Receiver<Result = CompilingReceiverResult<'a>, Node = Mig> + 'a {
-    EGraph::<MigLanguage, _>::new(()).map(move |(mut graph, outputs)| {
-        let t_runner = if settings.rewrite {
+    memo: Option<&'a CostMemo>,
+    pinned: Vec<PinnedTerm>,
+    metadata: MetadataTerms,
+    cost_function: impl FnOnce(&EGraph<MigLanguage, ()>) -> CF + 'a,
+) -> impl Receiver<Result = Result<CompilingReceiverResult<'a, CF>, CompileError>, Node = Mig> + 'a {
+    let graph = EGraph::<MigLanguage, _>::new(());
+    let graph = if settings.explanations {
+        graph.with_explanations_enabled()
+    } else {
+        graph
+    };
+    graph.map(move |(mut graph, outputs)| {
+        let mut cost_function = Restricted::new(cost_function(&graph));
+        let memo = memo.map(|memo| (memo, CompilingCostFunction::new(architecture, &graph).features()));
+        let pinned = locate_pinned_nodes(&graph, &pinned)?;
+        let metadata = metadata.locate(&graph);
+        let inverters_before = count_egraph_inverters(&graph);
+        let original = settings.verify_rewrite.then(|| (graph.clone(), outputs.clone()));
+        let t_runner = if settings.rewrite && !settings.passthrough && settings.backend == RewriteBackend::Egglog {
+            // egglog only knows the rule sets of the settings
+            if rules::rules_hash(rules) != settings.rules_hash() {
+                return Err(CompileError::Other("egglog only supports the rules selected by the settings"));
+            }
+            let t_runner = std::time::Instant::now();
+            graph = rewrite_with_egglog(&graph, settings)?;
+            t_runner.elapsed().as_millis()
+        } else if settings.rewrite && !settings.passthrough {
             let t_runner = std::time::Instant::now();
-            let runner = Runner::default().with_egraph(graph).run(rules);
+            let telemetry = settings
+                .telemetry_path()
+                .map(|path| (path, Rc::new(RefCell::new(EGraphTelemetry::default()))));
+            let mut runner = settings.runner().with_egraph(graph);
+            if let Some((_, telemetry)) = &telemetry {
+                runner = with_telemetry(runner, telemetry.clone());
+            }
+            let runner = runner.run(rules);
             let t_runner = t_runner.elapsed().as_millis();
+            for iteration in &runner.iterations {
+                for (rule, applications) in &iteration.applied {
+                    coverage::hit_rule(rule.as_str(), *applications as u64);
+                }
+            }
+            if let Some((path, telemetry)) = telemetry {
+                let mut telemetry = telemetry.borrow_mut();
+                telemetry.record(runner.iterations.len(), &runner.egraph, t_runner);
+                if let Err(err) = telemetry.write_to_file(&path) {
+                    eprintln!("could not write e-graph telemetry to {}: {err}", path.display());
+                }
+            }
             if settings.verbose {
                 println!("== Runner Report");
                 runner.print_report();
@@ -87,55 +247,730 @@ fn compiling_receiver<'a>(
             0
         };
 
+        // explanations add terms to the e-graph, which would invalidate its fingerprint
+        let memo = memo
+            .filter(|_| !settings.explanations)
+            .map(|(memo, features)| (memo, graph_fingerprint(&graph), features));
+        if !pinned.is_empty() {
+            cost_function.pins = Some(Arc::new(canonical_pins(&graph, &pinned)));
+        }
+        if let Some((memo, fingerprint, features)) = memo {
+            cost_function.choices = memo.lookup(fingerprint, features);
+        }
+        let metadata = ResolvedMetadata::from_graph(&graph, &outputs, &canonical_metadata(&graph, &metadata));
+        let deadlines = metadata.deadlines();
+        let constrained: Vec<Id> =
+            outputs.iter().zip(&deadlines).filter(|(_, deadline)| deadline.is_some()).map(|(id, _)| *id).collect();
+        if !constrained.is_empty() && cost_function.choices.is_none() {
+            let mut shallow = shallowest_nodes(&graph, &constrained);
+            // the choices of the user take precedence
+            if let Some(pins) = &cost_function.pins {
+                shallow.retain(|id, _| !pins.contains_key(id));
+            }
+            cost_function.shallow = Some(Arc::new(shallow));
+        }
+
+        let explanations = settings
+            .explanations
+            .then(|| explain_outputs(&mut graph, &outputs, cost_function.clone()));
+        if let (true, Some(explanations)) = (settings.verbose, &explanations) {
+            println!("== Explanations");
+            for (idx, explanation) in explanations.iter().enumerate() {
+                println!("Output {idx}:\n{explanation}");
+            }
+        }
+
         let mut t_extractor = 0;
         let mut t_compiler = 0;
+        let mut legalization = LegalizationReport::default();
 
-        let output = CompilerOutput::new(
+        let output = CompilerOutput::try_new(
             graph,
             |graph| {
                 let start_time = Instant::now();
-                let extractor = OptExtractor::new(graph, CompilingCostFunction { architecture });
+                let extractor = extract(graph, cost_function);
                 t_extractor = start_time.elapsed().as_millis();
-                OptExtractionNetwork(extractor, outputs)
+                if let Some((memo, fingerprint, features)) = memo {
+                    memo.record(graph, fingerprint, features, &extractor);
+                }
+                Ok(OptExtractionNetwork(extractor, outputs))
             },
             |ntk| {
                 let start_time = Instant::now();
-                let program = compile(architecture, &ntk.with_backward_edges())
-                    .expect("network should be compilable");
+                let options = settings.compile_options();
+                let (mut program, report) = compile_legalized_with_deadlines(architecture, ntk, options, &deadlines)?;
+                metadata.annotate(&mut program);
+                legalization = report;
                 t_compiler = start_time.elapsed().as_millis();
-                if settings.print_program || settings.verbose {
-                    if settings.verbose {
-                        println!("== Program")
-                    }
-                    println!("{program}");
-                }
-                program
+                report_program(&program, ntk, settings);
+                Ok(program)
             },
-        );
+        )?;
+        if let Some((original, outputs)) = &original {
+            // without rewriting, every class holds exactly the node which has been received
+            let original = OptExtractionNetwork(
+                OptExtractor::new(original, CompilingCostFunction::new(architecture, original)),
+                outputs.clone(),
+            );
+            if let Equivalence::Counterexample(inputs) = check_equivalence(&original, output.borrow_ntk())? {
+                eprintln!("rewriting changed the function of the network, e.g. for the inputs {inputs:?}");
+                return Err(CompileError::Other("rewriting changed the function of the network"));
+            }
+        }
+        if let Some(path) = settings.verilog_path() {
+            let nr_inputs = count_egraph_inputs(output.borrow_graph());
+            if let Err(err) = verilog::write_to_file(output.borrow_ntk(), nr_inputs, &path) {
+                eprintln!("could not write extracted network to {}: {err}", path.display());
+            }
+        }
         if settings.verbose {
             println!("== Timings");
             println!("t_runner: {t_runner}ms");
             println!("t_extractor: {t_extractor}ms");
             println!("t_compiler: {t_compiler}ms");
         }
-        CompilingReceiverResult {
+        Ok(CompilingReceiverResult {
             output,
+            explanations,
+            inverters_before,
+            legalization,
             t_runner,
             t_extractor,
             t_compiler,
-        }
+        })
     })
 }
 
+fn write_diagnostics(program: &Program, settings: CompilerSettings) {
+    if let Some(path) = settings.diagnostics_path() {
+        if let Err(err) = diagnostics::write_to_file(&program.diagnostics, &path) {
+            eprintln!("could not write diagnostics to {}: {err}", path.display());
+        }
+    }
+}
+
+/// Writes the logs of a compiled program and prints it as requested by `settings`
+fn report_program(program: &Program, network: &impl Network<Node = Mig>, settings: CompilerSettings) {
+    coverage::record_to_env();
+    if let Some(path) = settings.decision_log_path() {
+        if let Err(err) = decisions::write_to_file(&program.decisions, &path) {
+            eprintln!("could not write decision log to {}: {err}", path.display());
+        }
+    }
+    if let (Some(path), Some(metrics)) = (settings.allocator_metrics_path(), &program.allocator_metrics) {
+        if let Err(err) = metrics.write_to_file(&path) {
+            eprintln!("could not write allocator metrics to {}: {err}", path.display());
+        }
+    }
+    write_diagnostics(program, settings);
+    if settings.verbose && !program.diagnostics.is_empty() {
+        println!("== Diagnostics");
+        for diagnostic in &program.diagnostics {
+            println!("{diagnostic}");
+        }
+    }
+    if settings.print_program || settings.verbose {
+        if settings.verbose {
+            println!("== Program")
+        }
+        println!("{program}");
+        if settings.verbose {
+            println!("== Layout");
+            print!("{}", program.layout());
+            println!("== Efficiency");
+            print!("{}", program.efficiency(count_majs(network)));
+            let timing = program.timing();
+            if timing.iter().any(|output| output.deadline.is_some()) {
+                println!("== Timing");
+                for (idx, output) in timing.iter().enumerate() {
+                    match output.slack() {
+                        Some(slack) => println!("output {idx}: done after {}ns, slack {slack}ns", output.completion),
+                        None => println!("output {idx}: done after {}ns", output.completion),
+                    }
+                }
+            }
+        }
+    }
+    if settings.cpu_baseline {
+        let transfers = HostTransferModel::default().estimate(&program.host_transfers());
+        println!("{}", CpuBaseline::default().estimate(count_majs(network)).report_line(&program, &transfers));
+    }
+}
+
+/// Options of a compilation. New options are only ever appended, so that C callers compiled against
+/// an older `prada.h` keep working through the `*_sized_ffi` entry points (see [settings_from_ffi]).
+/// Rust code should use [CompilerSettings::builder] instead of struct literals to stay compatible.
 #[derive(Debug, Copy, Clone)]
 #[repr(C)]
-struct CompilerSettings {
-    print_program: bool,
-    verbose: bool,
-    rewrite: bool,
+pub struct CompilerSettings {
+    pub print_program: bool,
+    pub verbose: bool,
+    pub rewrite: bool,
+    /// Record the rewrites applied to the e-graph so that the equivalence of the extracted
+    /// network to the original one can be explained
+    pub explanations: bool,
+    /// Scheduler deciding which rules are applied in each iteration of the runner of
+    /// [RewriteBackend::Egg]
+    pub scheduler: RunnerScheduler,
+    /// Initial match limit of the [RunnerScheduler::Backoff] scheduler (egg's default is 1000)
+    pub backoff_match_limit: u64,
+    /// Initial nr of iterations a rule of the [RunnerScheduler::Backoff] scheduler is banned after
+    /// exceeding its match limit (egg's default is 5)
+    pub backoff_ban_length: u64,
+    /// Only apply distributivity where it increases operand sharing, see
+    /// [RuleSet::sharing_guided_distributivity](rules::RuleSet::sharing_guided_distributivity)
+    pub sharing_guided_distributivity: bool,
+    /// Path of a file to which the e-graph size after every runner iteration is written (as JSON
+    /// if the path ends with `.json`, as CSV otherwise), or null to disable telemetry
+    pub telemetry_path: *const c_char,
+    /// Never overwrite the rows holding inputs, see [CompileOptions::pin_inputs]
+    pub pin_inputs: bool,
+    /// Compute complemented signals by dual MAJs instead of N, see [CompileOptions::dual_rail]
+    pub dual_rail: bool,
+    /// Copy output `i` into row `output_base + i` at the end of the program, see
+    /// [CompileOptions::output_base]
+    pub pack_outputs: bool,
+    pub output_base: u64,
+    /// Evacuate live values into subarray `spill_subarray` when running out of rows instead of
+    /// failing, see [CompileOptions::spill_subarray]
+    pub spill: bool,
+    pub spill_subarray: u64,
+    /// Recompute cheap values instead of spilling them, see [CompileOptions::rematerialize]
+    pub rematerialize: bool,
+    /// Order in which the compiler computes nodes
+    pub scheduling: SchedulingPolicy,
+    /// Print an estimate of evaluating the network on a baseline CPU and the resulting speedup, with
+    /// and without moving the inputs and outputs between host and DRAM, see [CpuBaseline] and
+    /// [HostTransferModel]
+    pub cpu_baseline: bool,
+    /// Path of a file to which every decision of the compiler is written as JSON lines (see
+    /// [decisions]), or null to disable the log
+    pub decision_log_path: *const c_char,
+    /// Maximal nr of rows of subarray 0 the compiler may occupy, or 0 for no limit, see
+    /// [CompileOptions::scratch_row_budget]
+    pub scratch_row_budget: u64,
+    /// Path of a file to which metrics of the row allocator are written as JSON (see [metrics]),
+    /// or null to not collect them
+    pub allocator_metrics_path: *const c_char,
+    /// Subarray into which the outputs are packed if [Self::pack_outputs] is set, see
+    /// [CompileOptions::output_subarray]
+    pub output_subarray: u64,
+    /// Compile the network exactly as received, skipping rewriting and extraction, e.g. as
+    /// baseline or to find out whether a miscompilation is caused by the e-graph or the compiler.
+    /// Networks sent from C++ are still received into an e-graph, which only merges structurally
+    /// equal nodes.
+    pub passthrough: bool,
+    /// Path of a file to which the extracted network is written as Verilog (see [verilog]), e.g.
+    /// for checking its equivalence to the original network with ABC, or null to not write it
+    pub verilog_path: *const c_char,
+    /// Check the extracted network for equivalence to the network before rewriting using a SAT
+    /// solver (see [cec]), failing the compilation if rewriting changed its function
+    pub verify_rewrite: bool,
+    /// Path of a file to which the [diagnostics] of the compiled program are written (as JSON if
+    /// the path ends with `.json`, as text otherwise), or null to not write them
+    pub diagnostics_path: *const c_char,
+    /// Diagnostics which fail the compilation instead of only being reported, e.g.
+    /// [Strictness::PRODUCTION] for programs running on actual modules
+    pub strictness: Strictness,
+    /// Maximal estimated energy consumption of the program (in mJ/KOps), or 0 for no budget. If the
+    /// program exceeds it, extraction is re-run minimizing the energy consumption first, and a
+    /// [DiagnosticCode::EnergyBudget](diagnostics::DiagnosticCode::EnergyBudget) is reported if the
+    /// budget still isn't met. Only honored by the Rust entry points (e.g. [compile]), since the
+    /// network has to be sent again.
+    pub energy_budget: u64,
+    /// Seed of all stochastic components run with these settings (e.g. the sampled verification of
+    /// [reference::differential_test_seeded]), see [random]. Doesn't affect the compiled program and
+    /// hence isn't part of [Self::snapshot].
+    pub seed: u64,
+    /// Engine rewriting the e-graph, [Self::scheduler] and the backoff parameters only apply to
+    /// [RewriteBackend::Egg]
+    pub backend: RewriteBackend,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[repr(C)]
+pub enum RunnerScheduler {
+    /// egg's [BackoffScheduler], which bans rules with too many matches for some iterations
+    Backoff,
+    /// egg's [SimpleScheduler], which applies all rules in every iteration
+    Simple,
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 #[repr(C)]
+pub enum RewriteBackend {
+    /// egg's [Runner], scheduled by [CompilerSettings::scheduler]
+    Egg,
+    /// egglog, applying the same rules until saturation or egg's limits (requires the `egglog`
+    /// feature)
+    Egglog,
+}
+
+/// Same as the defaults of `prada_compiler_settings` in `prada.h`
+impl Default for CompilerSettings {
+    fn default() -> Self {
+        Self {
+            print_program: false,
+            verbose: false,
+            rewrite: true,
+            explanations: false,
+            scheduler: RunnerScheduler::Backoff,
+            backoff_match_limit: 1000,
+            backoff_ban_length: 5,
+            sharing_guided_distributivity: false,
+            telemetry_path: std::ptr::null(),
+            pin_inputs: false,
+            dual_rail: false,
+            pack_outputs: false,
+            output_base: 0,
+            spill: false,
+            spill_subarray: 1,
+            rematerialize: false,
+            scheduling: SchedulingPolicy::Greedy,
+            cpu_baseline: false,
+            decision_log_path: std::ptr::null(),
+            scratch_row_budget: 0,
+            allocator_metrics_path: std::ptr::null(),
+            output_subarray: 0,
+            passthrough: false,
+            verilog_path: std::ptr::null(),
+            verify_rewrite: false,
+            diagnostics_path: std::ptr::null(),
+            strictness: Strictness::PERMISSIVE,
+            energy_budget: 0,
+            seed: 0,
+            backend: RewriteBackend::Egg,
+        }
+    }
+}
+
+/// Builds [CompilerSettings] starting from the defaults, so that code using it keeps compiling (and
+/// behaving the same) when options are added
+#[derive(Debug, Copy, Clone, Default)]
+pub struct SettingsBuilder {
+    settings: CompilerSettings,
+}
+
+impl SettingsBuilder {
+    pub fn print_program(mut self, print_program: bool) -> Self {
+        self.settings.print_program = print_program;
+        self
+    }
+
+    pub fn verbose(mut self, verbose: bool) -> Self {
+        self.settings.verbose = verbose;
+        self
+    }
+
+    pub fn rewrite(mut self, rewrite: bool) -> Self {
+        self.settings.rewrite = rewrite;
+        self
+    }
+
+    pub fn explanations(mut self, explanations: bool) -> Self {
+        self.settings.explanations = explanations;
+        self
+    }
+
+    pub fn scheduler(mut self, scheduler: RunnerScheduler) -> Self {
+        self.settings.scheduler = scheduler;
+        self
+    }
+
+    /// See [CompilerSettings::backoff_match_limit] and [CompilerSettings::backoff_ban_length]
+    pub fn backoff(mut self, match_limit: u64, ban_length: u64) -> Self {
+        self.settings.backoff_match_limit = match_limit;
+        self.settings.backoff_ban_length = ban_length;
+        self
+    }
+
+    pub fn sharing_guided_distributivity(mut self, enabled: bool) -> Self {
+        self.settings.sharing_guided_distributivity = enabled;
+        self
+    }
+
+    pub fn telemetry_path(mut self, path: &'static CStr) -> Self {
+        self.settings.telemetry_path = path.as_ptr();
+        self
+    }
+
+    pub fn pin_inputs(mut self, pin_inputs: bool) -> Self {
+        self.settings.pin_inputs = pin_inputs;
+        self
+    }
+
+    pub fn dual_rail(mut self, dual_rail: bool) -> Self {
+        self.settings.dual_rail = dual_rail;
+        self
+    }
+
+    /// Copies output `i` into row `output_base + i`, see [CompilerSettings::pack_outputs]
+    pub fn pack_outputs(mut self, output_base: u64) -> Self {
+        self.settings.pack_outputs = true;
+        self.settings.output_base = output_base;
+        self
+    }
+
+    /// Copies output `i` into row `output_base + i` of `subarray`, see
+    /// [CompilerSettings::output_subarray]
+    pub fn pack_outputs_into(mut self, subarray: u64, output_base: u64) -> Self {
+        self.settings.output_subarray = subarray;
+        self.pack_outputs(output_base)
+    }
+
+    /// Spills into the given subarray, see [CompilerSettings::spill]
+    pub fn spill(mut self, subarray: u64) -> Self {
+        self.settings.spill = true;
+        self.settings.spill_subarray = subarray;
+        self
+    }
+
+    pub fn rematerialize(mut self, rematerialize: bool) -> Self {
+        self.settings.rematerialize = rematerialize;
+        self
+    }
+
+    pub fn scheduling(mut self, scheduling: SchedulingPolicy) -> Self {
+        self.settings.scheduling = scheduling;
+        self
+    }
+
+    pub fn cpu_baseline(mut self, cpu_baseline: bool) -> Self {
+        self.settings.cpu_baseline = cpu_baseline;
+        self
+    }
+
+    pub fn decision_log_path(mut self, path: &'static CStr) -> Self {
+        self.settings.decision_log_path = path.as_ptr();
+        self
+    }
+
+    /// Limits the rows the compiler may occupy, see [CompilerSettings::scratch_row_budget]
+    pub fn scratch_row_budget(mut self, rows: u64) -> Self {
+        self.settings.scratch_row_budget = rows;
+        self
+    }
+
+    pub fn allocator_metrics_path(mut self, path: &'static CStr) -> Self {
+        self.settings.allocator_metrics_path = path.as_ptr();
+        self
+    }
+
+    /// Compiles networks as received, see [CompilerSettings::passthrough]
+    pub fn passthrough(mut self, passthrough: bool) -> Self {
+        self.settings.passthrough = passthrough;
+        self
+    }
+
+    pub fn verilog_path(mut self, path: &'static CStr) -> Self {
+        self.settings.verilog_path = path.as_ptr();
+        self
+    }
+
+    pub fn verify_rewrite(mut self, verify_rewrite: bool) -> Self {
+        self.settings.verify_rewrite = verify_rewrite;
+        self
+    }
+
+    pub fn diagnostics_path(mut self, path: &'static CStr) -> Self {
+        self.settings.diagnostics_path = path.as_ptr();
+        self
+    }
+
+    /// Escalates diagnostics into errors, see [CompilerSettings::strictness]
+    pub fn strictness(mut self, strictness: Strictness) -> Self {
+        self.settings.strictness = strictness;
+        self
+    }
+
+    /// Re-runs extraction minimizing the energy consumption if the program exceeds `budget`, see
+    /// [CompilerSettings::energy_budget]
+    pub fn energy_budget(mut self, budget: u64) -> Self {
+        self.settings.energy_budget = budget;
+        self
+    }
+
+    /// See [CompilerSettings::seed]
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.settings.seed = seed;
+        self
+    }
+
+    pub fn backend(mut self, backend: RewriteBackend) -> Self {
+        self.settings.backend = backend;
+        self
+    }
+
+    pub fn build(self) -> CompilerSettings {
+        self.settings
+    }
+}
+
+/// Reads the settings a C caller passed as pointer and `sizeof`. As fields are only appended to
+/// [CompilerSettings], a caller compiled against an older header passes a prefix of the current
+/// layout, whose missing fields keep their defaults. Only fields lying completely within `size`
+/// bytes are read, hence larger sizes (newer callers) are truncated. Enums and bools are
+/// validated, since C callers may pass any value for them.
+///
+/// # Safety
+/// `settings` has to point to `size` readable bytes which are a prefix of [CompilerSettings]
+unsafe fn settings_from_ffi(settings: *const CompilerSettings, size: usize) -> Result<CompilerSettings, CompileError> {
+    let mut result = CompilerSettings::default();
+    let base = settings as *const u8;
+    // every field of `CompilerSettings`, in the order of the struct
+    macro_rules! read_fields {
+        ($($field:ident),* $(,)?) => {
+            $(
+                let offset = std::mem::offset_of!(CompilerSettings, $field);
+                if offset + std::mem::size_of_val(&result.$field) <= size {
+                    unsafe { read_ffi_field(&mut result.$field, base.add(offset)) }?;
+                }
+            )*
+        };
+    }
+    read_fields!(
+        print_program, verbose, rewrite, explanations, scheduler, backoff_match_limit, backoff_ban_length,
+        sharing_guided_distributivity, telemetry_path, pin_inputs, dual_rail, pack_outputs, output_base, spill,
+        spill_subarray, rematerialize, scheduling, cpu_baseline, decision_log_path, scratch_row_budget,
+        allocator_metrics_path, output_subarray, passthrough, verilog_path, verify_rewrite, diagnostics_path,
+        strictness, energy_budget, seed, backend,
+    );
+    Ok(result)
+}
+
+/// Field of [CompilerSettings] as passed by a C caller
+trait FfiField: Sized {
+    /// # Safety
+    /// `field` has to point to `size_of::<Self>()` readable bytes
+    unsafe fn read(field: *const u8) -> Result<Self, CompileError>;
+}
+
+unsafe fn read_ffi_field<T: FfiField>(target: &mut T, field: *const u8) -> Result<(), CompileError> {
+    *target = unsafe { T::read(field) }?;
+    Ok(())
+}
+
+impl FfiField for bool {
+    unsafe fn read(field: *const u8) -> Result<Self, CompileError> {
+        match unsafe { field.read() } {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(CompileError::Other("invalid bool in settings")),
+        }
+    }
+}
+
+impl FfiField for RunnerScheduler {
+    unsafe fn read(field: *const u8) -> Result<Self, CompileError> {
+        match unsafe { (field as *const u32).read_unaligned() } {
+            0 => Ok(RunnerScheduler::Backoff),
+            1 => Ok(RunnerScheduler::Simple),
+            _ => Err(CompileError::Other("invalid runner scheduler in settings")),
+        }
+    }
+}
+
+impl FfiField for RewriteBackend {
+    unsafe fn read(field: *const u8) -> Result<Self, CompileError> {
+        match unsafe { (field as *const u32).read_unaligned() } {
+            0 => Ok(RewriteBackend::Egg),
+            1 => Ok(RewriteBackend::Egglog),
+            _ => Err(CompileError::Other("invalid rewrite backend in settings")),
+        }
+    }
+}
+
+impl FfiField for SchedulingPolicy {
+    unsafe fn read(field: *const u8) -> Result<Self, CompileError> {
+        match unsafe { (field as *const u32).read_unaligned() } {
+            0 => Ok(SchedulingPolicy::Greedy),
+            1 => Ok(SchedulingPolicy::CriticalPath),
+            2 => Ok(SchedulingPolicy::SethiUllman),
+            _ => Err(CompileError::Other("invalid scheduling policy in settings")),
+        }
+    }
+}
+
+/// Fields every bit pattern is valid for
+macro_rules! plain_ffi_fields {
+    ($($ty:ty),*) => {
+        $(
+            impl FfiField for $ty {
+                unsafe fn read(field: *const u8) -> Result<Self, CompileError> {
+                    Ok(unsafe { (field as *const $ty).read_unaligned() })
+                }
+            }
+        )*
+    };
+}
+
+plain_ffi_fields!(u64, *const c_char, Strictness);
+
+/// Reads a path passed as (nullable) C string
+fn path_setting(path: *const c_char) -> Option<PathBuf> {
+    if path.is_null() {
+        return None;
+    }
+    let path = unsafe { CStr::from_ptr(path) };
+    Some(PathBuf::from(path.to_string_lossy().into_owned()))
+}
+
+impl CompilerSettings {
+    pub fn builder() -> SettingsBuilder {
+        SettingsBuilder::default()
+    }
+
+    /// Values of the options affecting the compiled program (i.e. all but the output options, paths
+    /// and the seed) as `name=value` pairs separated by spaces, see [artifact]
+    pub fn snapshot(&self) -> String {
+        let options = [
+            ("rewrite", self.rewrite.to_string()),
+            ("explanations", self.explanations.to_string()),
+            ("scheduler", format!("{:?}", self.scheduler)),
+            ("backoff_match_limit", self.backoff_match_limit.to_string()),
+            ("backoff_ban_length", self.backoff_ban_length.to_string()),
+            ("sharing_guided_distributivity", self.sharing_guided_distributivity.to_string()),
+            ("pin_inputs", self.pin_inputs.to_string()),
+            ("dual_rail", self.dual_rail.to_string()),
+            ("pack_outputs", self.pack_outputs.to_string()),
+            ("output_base", self.output_base.to_string()),
+            ("spill", self.spill.to_string()),
+            ("spill_subarray", self.spill_subarray.to_string()),
+            ("rematerialize", self.rematerialize.to_string()),
+            ("scheduling", format!("{:?}", self.scheduling)),
+            ("scratch_row_budget", self.scratch_row_budget.to_string()),
+            ("output_subarray", self.output_subarray.to_string()),
+            ("passthrough", self.passthrough.to_string()),
+            ("strictness", format!("{:?}", self.strictness)),
+            ("energy_budget", self.energy_budget.to_string()),
+            ("backend", format!("{:?}", self.backend)),
+        ];
+        options.map(|(name, value)| format!("{name}={value}")).join(" ")
+    }
+
+    /// Inverse of [Self::snapshot], starting from the defaults for the options which snapshots
+    /// don't contain
+    pub fn from_snapshot(snapshot: &str) -> Result<Self, &'static str> {
+        let mut settings = Self::default();
+        for option in snapshot.split_whitespace() {
+            let (name, value) = option.split_once('=').ok_or("options have to be `name=value` pairs")?;
+            let flag = || value.parse::<bool>().map_err(|_| "invalid boolean option");
+            let number = || value.parse::<u64>().map_err(|_| "invalid numeric option");
+            match name {
+                "rewrite" => settings.rewrite = flag()?,
+                "explanations" => settings.explanations = flag()?,
+                "scheduler" => {
+                    settings.scheduler = match value {
+                        "Backoff" => RunnerScheduler::Backoff,
+                        "Simple" => RunnerScheduler::Simple,
+                        _ => return Err("unknown scheduler"),
+                    }
+                }
+                "backoff_match_limit" => settings.backoff_match_limit = number()?,
+                "backoff_ban_length" => settings.backoff_ban_length = number()?,
+                "sharing_guided_distributivity" => settings.sharing_guided_distributivity = flag()?,
+                "pin_inputs" => settings.pin_inputs = flag()?,
+                "dual_rail" => settings.dual_rail = flag()?,
+                "pack_outputs" => settings.pack_outputs = flag()?,
+                "output_base" => settings.output_base = number()?,
+                "spill" => settings.spill = flag()?,
+                "spill_subarray" => settings.spill_subarray = number()?,
+                "rematerialize" => settings.rematerialize = flag()?,
+                "scheduling" => {
+                    settings.scheduling = match value {
+                        "Greedy" => SchedulingPolicy::Greedy,
+                        "CriticalPath" => SchedulingPolicy::CriticalPath,
+                        "SethiUllman" => SchedulingPolicy::SethiUllman,
+                        _ => return Err("unknown scheduling policy"),
+                    }
+                }
+                "scratch_row_budget" => settings.scratch_row_budget = number()?,
+                "output_subarray" => settings.output_subarray = number()?,
+                "passthrough" => settings.passthrough = flag()?,
+                "strictness" => {
+                    let bits = value.strip_prefix("Strictness(").and_then(|bits| bits.strip_suffix(')'));
+                    let bits = bits.and_then(|bits| bits.parse().ok()).ok_or("invalid strictness")?;
+                    settings.strictness = Strictness::from_bits(bits);
+                }
+                "energy_budget" => settings.energy_budget = number()?,
+                "backend" => {
+                    settings.backend = match value {
+                        "Egg" => RewriteBackend::Egg,
+                        "Egglog" => RewriteBackend::Egglog,
+                        _ => return Err("unknown rewrite backend"),
+                    }
+                }
+                _ => return Err("unknown option"),
+            }
+        }
+        Ok(settings)
+    }
+
+    /// Hash of the rewrite rules these settings select, see [rules::rules_hash]
+    pub fn rules_hash(&self) -> u64 {
+        rules::rules_hash(self.rules())
+    }
+
+    fn rules(&self) -> &'static [Rewrite<MigLanguage, ()>] {
+        if self.sharing_guided_distributivity {
+            SHARING_GUIDED_REWRITE_RULES.as_slice()
+        } else {
+            REWRITE_RULES.as_slice()
+        }
+    }
+
+    fn telemetry_path(&self) -> Option<PathBuf> {
+        path_setting(self.telemetry_path)
+    }
+
+    fn decision_log_path(&self) -> Option<PathBuf> {
+        path_setting(self.decision_log_path)
+    }
+
+    fn allocator_metrics_path(&self) -> Option<PathBuf> {
+        path_setting(self.allocator_metrics_path)
+    }
+
+    fn verilog_path(&self) -> Option<PathBuf> {
+        path_setting(self.verilog_path)
+    }
+
+    fn diagnostics_path(&self) -> Option<PathBuf> {
+        path_setting(self.diagnostics_path)
+    }
+
+    fn compile_options(&self) -> CompileOptions {
+        CompileOptions {
+            pin_inputs: self.pin_inputs,
+            dual_rail: self.dual_rail,
+            output_base: self.pack_outputs.then_some(self.output_base),
+            output_subarray: (self.pack_outputs && self.output_subarray > 0)
+                .then_some(SubarrayId(self.output_subarray)),
+            spill_subarray: self.spill.then_some(SubarrayId(self.spill_subarray)),
+            rematerialize: self.rematerialize,
+            scheduling: self.scheduling,
+            log_decisions: self.decision_log_path().is_some(),
+            scratch_row_budget: (self.scratch_row_budget > 0).then_some(self.scratch_row_budget),
+            allocator_metrics: self.allocator_metrics_path().is_some(),
+            strictness: self.strictness,
+        }
+    }
+
+    fn runner(&self) -> Runner<MigLanguage, ()> {
+        let runner = Runner::default();
+        match self.scheduler {
+            RunnerScheduler::Backoff => runner.with_scheduler(
+                BackoffScheduler::default()
+                    .with_initial_match_limit(self.backoff_match_limit as usize)
+                    .with_ban_length(self.backoff_ban_length as usize),
+            ),
+            RunnerScheduler::Simple => runner.with_scheduler(SimpleScheduler),
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Default)]
 struct CompilerStatistics {
     egraph_classes: u64,
     egraph_nodes: u64,
@@ -145,9 +980,206 @@ struct CompilerStatistics {
     runtime_estimate: u64,
     energy_consumption_estimate: u64,
 
+    inverters_before: u64,
+    inverters_after: u64,
+    emulated_inversions: u64,
+    emulation_majs: u64,
+
+    spills: u64,
+    reloads: u64,
+    rematerializations: u64,
+
+    tra_count: u64,
+    /// Nr of MAJs of the extracted network, see [Efficiency::maj_count]
+    maj_count: u64,
+    copy_count: u64,
+    row_activations: u64,
+
     t_runner: u64,
     t_extractor: u64,
     t_compiler: u64,
+
+    /// 0 if the network has been compiled, otherwise the [CompileError::code] of the error, in
+    /// which case all other statistics are 0
+    error: u32,
+}
+
+/// Runs rewriting, extraction and compilation on the given network, like [prada_compile_ffi] does
+/// for networks sent from C++
+pub fn compile<'a>(
+    architecture: &'a PRADAArchitecture,
+    network: &impl Network<Node = Mig>,
+    settings: CompilerSettings,
+) -> Result<Program<'a>, CompileError> {
+    let cost_function = compiling_cost_function(architecture);
+    compile_pipeline(architecture, network, settings, None, vec!(), &HashMap::new(), cost_function)
+}
+
+/// Same as [compile], but overrides the extractor's choices for the given signals of `network`,
+/// e.g. to keep a sub-block as received (see [ExtractionOverride])
+pub fn compile_with_overrides<'a>(
+    architecture: &'a PRADAArchitecture,
+    network: &impl Network<Node = Mig>,
+    settings: CompilerSettings,
+    overrides: &HashMap<Signal, ExtractionOverride>,
+) -> Result<Program<'a>, CompileError> {
+    let pinned = pinned_terms(network, overrides);
+    let cost_function = compiling_cost_function(architecture);
+    compile_pipeline(architecture, network, settings, None, pinned, &HashMap::new(), cost_function)
+}
+
+/// Same as [compile], but only rewrites the fan-in cones of `regions` (e.g. the outputs of a kernel
+/// which should be optimized for PIM), the rest of `network` is compiled as received
+pub fn compile_scoped<'a>(
+    architecture: &'a PRADAArchitecture,
+    network: &impl Network<Node = Mig>,
+    settings: CompilerSettings,
+    regions: &[Signal],
+) -> Result<Program<'a>, CompileError> {
+    let pinned = scoped_terms(network, regions);
+    let cost_function = compiling_cost_function(architecture);
+    compile_pipeline(architecture, network, settings, None, pinned, &HashMap::new(), cost_function)
+}
+
+/// Same as [compile], but reuses the extraction choices recorded in `memo` by an earlier
+/// compilation of the same network for an architecture with the same [CostFeatures], e.g. another
+/// point of a DSE sweep
+pub fn compile_memoized<'a>(
+    architecture: &'a PRADAArchitecture,
+    network: &impl Network<Node = Mig>,
+    settings: CompilerSettings,
+    memo: &'a CostMemo,
+) -> Result<Program<'a>, CompileError> {
+    let cost_function = compiling_cost_function(architecture);
+    compile_pipeline(architecture, network, settings, Some(memo), vec!(), &HashMap::new(), cost_function)
+}
+
+/// Same as [compile], but extracts the network minimizing `cost_function` instead of the estimated
+/// costs of computing it on `architecture`, e.g. for research into other extraction objectives (see
+/// [CostFn] for cost functions given by closures)
+pub fn compile_with_cost_function<'a, CF: ExtractionCostFunction + 'a>(
+    architecture: &'a PRADAArchitecture,
+    network: &impl Network<Node = Mig>,
+    settings: CompilerSettings,
+    cost_function: CF,
+) -> Result<Program<'a>, CompileError> {
+    compile_pipeline(architecture, network, settings, None, vec!(), &HashMap::new(), move |_| cost_function)
+}
+
+/// Same as [compile], but attaches `metadata` (e.g. the RTL names of the signals) to the signals of
+/// `network`, which is tracked through rewriting and extraction and ends up in [Program::metadata]
+/// for the inputs and outputs of the network
+pub fn compile_annotated<'a>(
+    architecture: &'a PRADAArchitecture,
+    network: &impl Network<Node = Mig>,
+    settings: CompilerSettings,
+    metadata: &HashMap<Signal, NodeMetadata>,
+) -> Result<Program<'a>, CompileError> {
+    let cost_function = compiling_cost_function(architecture);
+    compile_pipeline(architecture, network, settings, None, vec!(), metadata, cost_function)
+}
+
+/// Compiles `network` (see [compile_once]) and, if the program exceeds the
+/// [energy budget](CompilerSettings::energy_budget), compiles it again extracting with the
+/// [EnergyObjective], keeping the program consuming less energy
+fn compile_pipeline<'a, CF: ExtractionCostFunction + 'a>(
+    architecture: &'a PRADAArchitecture,
+    network: &impl Network<Node = Mig>,
+    settings: CompilerSettings,
+    memo: Option<&'a CostMemo>,
+    pinned: Vec<PinnedTerm>,
+    metadata: &HashMap<Signal, NodeMetadata>,
+    cost_function: impl FnOnce(&EGraph<MigLanguage, ()>) -> CF + 'a,
+) -> Result<Program<'a>, CompileError> {
+    let budget = settings.energy_budget;
+    // without extraction, there is nothing to re-run
+    let retry = (budget > 0 && !settings.passthrough).then(|| pinned.clone());
+    let program = compile_once(architecture, network, settings, memo, pinned, metadata, cost_function)?;
+    if budget == 0 || program.energy_consumption_estimate <= budget {
+        return Ok(program);
+    }
+    let Some(pinned) = retry else {
+        return check_energy_budget(program, settings);
+    };
+    // memoized choices belong to the default objective
+    let energy_objective = move |graph: &EGraph<MigLanguage, ()>| {
+        EnergyObjective(CompilingCostFunction::new(architecture, graph))
+    };
+    let retried = compile_once(architecture, network, settings, None, pinned, metadata, energy_objective);
+    if settings.verbose {
+        println!("== Energy budget");
+        match &retried {
+            Ok(retried) => println!(
+                "{} mJ/KOps exceed the budget of {budget} mJ/KOps, {} mJ/KOps with the energy objective",
+                program.energy_consumption_estimate, retried.energy_consumption_estimate
+            ),
+            Err(err) => println!("compiling with the energy objective failed: {err}"),
+        }
+    }
+    match retried {
+        Ok(retried) if retried.energy_consumption_estimate < program.energy_consumption_estimate => {
+            check_energy_budget(retried, settings)
+        }
+        _ => check_energy_budget(program, settings),
+    }
+}
+
+/// Reports a [DiagnosticCode::EnergyBudget](diagnostics::DiagnosticCode::EnergyBudget) if `program`
+/// exceeds the energy budget, failing if the [strictness](CompilerSettings::strictness) escalates it
+fn check_energy_budget<'a>(mut program: Program<'a>, settings: CompilerSettings) -> Result<Program<'a>, CompileError> {
+    let budget = settings.energy_budget;
+    if budget == 0 || program.energy_consumption_estimate <= budget {
+        return Ok(program);
+    }
+    program.diagnostics.push(Diagnostic::warning(
+        DiagnosticCode::EnergyBudget,
+        None,
+        format!(
+            "estimated energy consumption of {} mJ/KOps exceeds the budget of {budget} mJ/KOps",
+            program.energy_consumption_estimate
+        ),
+    ));
+    write_diagnostics(&program, settings);
+    escalate(&mut program, settings.compile_options())?;
+    Ok(program)
+}
+
+/// Sends `network` to the compiling receiver, unless the settings ask for compiling it as received
+/// (see [CompilerSettings::passthrough])
+fn compile_once<'a, CF: ExtractionCostFunction + 'a>(
+    architecture: &'a PRADAArchitecture,
+    network: &impl Network<Node = Mig>,
+    settings: CompilerSettings,
+    memo: Option<&'a CostMemo>,
+    pinned: Vec<PinnedTerm>,
+    metadata: &HashMap<Signal, NodeMetadata>,
+    cost_function: impl FnOnce(&EGraph<MigLanguage, ()>) -> CF + 'a,
+) -> Result<Program<'a>, CompileError> {
+    if settings.passthrough {
+        let metadata = ResolvedMetadata::from_network(network, metadata);
+        let options = settings.compile_options();
+        let (mut program, _) = compile_legalized_with_deadlines(architecture, network, options, &metadata.deadlines())?;
+        metadata.annotate(&mut program);
+        report_program(&program, network, settings);
+        if let Some(path) = settings.verilog_path() {
+            if let Err(err) = verilog::write_to_file(network, count_network_inputs(network), &path) {
+                eprintln!("could not write network to {}: {err}", path.display());
+            }
+        }
+        return Ok(program);
+    }
+    let metadata = MetadataTerms::new(network, metadata);
+    let receiver = compiling_receiver(architecture, settings.rules(), settings, memo, pinned, metadata, cost_function);
+    let res = network.send(receiver)?;
+    Ok(res.output.borrow_program().clone())
+}
+
+type FfiResult = Result<CompilingReceiverResult<'static, CompilingCostFunction<'static>>, CompileError>;
+
+/// Compiling receiver of the FFI entry points, which compile for [ARCHITECTURE]
+fn ffi_receiver(settings: CompilerSettings) -> impl Receiver<Result = FfiResult, Node = Mig> {
+    let cost_function = compiling_cost_function(&ARCHITECTURE);
+    compiling_receiver(&ARCHITECTURE, settings.rules(), settings, None, vec!(), MetadataTerms::default(), cost_function)
 }
 
 #[no_mangle]
@@ -155,25 +1187,111 @@ extern "C" fn prada_rewrite_ffi(
     settings: CompilerSettings,
     receiver: MigReceiverFFI<()>,
 ) -> MigReceiverFFI<CompilerStatistics> {
-    let receiver =
-        compiling_receiver(&ARCHITECTURE, REWRITE_RULES.as_slice(), settings).map(|res| {
+    let receiver = ffi_receiver(settings).map(|res| match res {
+        Ok(res) => {
             res.output.borrow_ntk().send(receiver);
             CompilerStatistics::from_result(res)
-        });
+        }
+        Err(error) => {
+            MigNetwork::new().send(receiver);
+            CompilerStatistics::from_error(error)
+        }
+    });
+    MigReceiverFFI::new(receiver)
+}
+
+/// Same as [prada_rewrite_ffi], but additionally sends annotations for every node of the extracted
+/// network to `annotations`
+#[no_mangle]
+extern "C" fn prada_rewrite_annotated_ffi(
+    settings: CompilerSettings,
+    receiver: MigReceiverFFI<()>,
+    annotations: AnnotationReceiverFFI,
+) -> MigReceiverFFI<CompilerStatistics> {
+    let receiver = ffi_receiver(settings).map(move |res| match res {
+        Ok(res) => {
+            let ntk = res.output.borrow_ntk();
+            ntk.send(receiver);
+            annotations.send(ntk);
+            CompilerStatistics::from_result(res)
+        }
+        Err(error) => {
+            MigNetwork::new().send(receiver);
+            CompilerStatistics::from_error(error)
+        }
+    });
     MigReceiverFFI::new(receiver)
 }
 
 #[no_mangle]
 extern "C" fn prada_compile_ffi(settings: CompilerSettings) -> MigReceiverFFI<CompilerStatistics> {
-    env_logger::init();
-    let receiver = compiling_receiver(&ARCHITECTURE, REWRITE_RULES.as_slice(), settings)
-        .map(CompilerStatistics::from_result);
+    let _ = env_logger::try_init();
+    let receiver = ffi_receiver(settings).map(|res| match res {
+        Ok(res) => CompilerStatistics::from_result(res),
+        Err(error) => CompilerStatistics::from_error(error),
+    });
+    MigReceiverFFI::new(receiver)
+}
+
+/// Same as [prada_compile_ffi], but takes the settings as pointer and size, see [settings_from_ffi]
+#[no_mangle]
+unsafe extern "C" fn prada_compile_sized_ffi(
+    settings: *const CompilerSettings,
+    size: usize,
+) -> MigReceiverFFI<CompilerStatistics> {
+    match unsafe { settings_from_ffi(settings, size) } {
+        Ok(settings) => prada_compile_ffi(settings),
+        Err(error) => failing_ffi_receiver(error, None),
+    }
+}
+
+/// Same as [prada_rewrite_ffi], but takes the settings as pointer and size, see [settings_from_ffi]
+#[no_mangle]
+unsafe extern "C" fn prada_rewrite_sized_ffi(
+    settings: *const CompilerSettings,
+    size: usize,
+    receiver: MigReceiverFFI<()>,
+) -> MigReceiverFFI<CompilerStatistics> {
+    match unsafe { settings_from_ffi(settings, size) } {
+        Ok(settings) => prada_rewrite_ffi(settings, receiver),
+        Err(error) => failing_ffi_receiver(error, Some(receiver)),
+    }
+}
+
+/// Same as [prada_rewrite_annotated_ffi], but takes the settings as pointer and size, see
+/// [settings_from_ffi]
+#[no_mangle]
+unsafe extern "C" fn prada_rewrite_annotated_sized_ffi(
+    settings: *const CompilerSettings,
+    size: usize,
+    receiver: MigReceiverFFI<()>,
+    annotations: AnnotationReceiverFFI,
+) -> MigReceiverFFI<CompilerStatistics> {
+    match unsafe { settings_from_ffi(settings, size) } {
+        Ok(settings) => prada_rewrite_annotated_ffi(settings, receiver, annotations),
+        Err(error) => failing_ffi_receiver(error, Some(receiver)),
+    }
+}
+
+/// Receiver of the FFI entry points which reports `error` (e.g. for invalid settings) for any
+/// network it receives, sending an empty network to `receiver`
+fn failing_ffi_receiver(
+    error: CompileError,
+    receiver: Option<MigReceiverFFI<()>>,
+) -> MigReceiverFFI<CompilerStatistics> {
+    let receiver = EGraph::<MigLanguage, ()>::new(()).map(move |_| {
+        if let Some(receiver) = receiver {
+            MigNetwork::new().send(receiver);
+        }
+        CompilerStatistics::from_error(error)
+    });
     MigReceiverFFI::new(receiver)
 }
 
 impl CompilerStatistics {
-    fn from_result(res: CompilingReceiverResult) -> Self {
+    fn from_result(res: CompilingReceiverResult<CompilingCostFunction>) -> Self {
         let graph = res.output.borrow_graph();
+        let efficiency = res.output.borrow_program().efficiency(count_majs(res.output.borrow_ntk()));
         CompilerStatistics {
             egraph_classes: graph.number_of_classes() as u64,
             egraph_nodes: graph.total_number_of_nodes() as u64,
@@ -181,9 +1299,66 @@ impl CompilerStatistics {
             instruction_count: res.output.borrow_program().instructions.len() as u64,
             runtime_estimate: res.output.borrow_program().runtime_estimate,
             energy_consumption_estimate: res.output.borrow_program().energy_consumption_estimate,
+            inverters_before: res.inverters_before,
+            inverters_after: count_inverters(res.output.borrow_ntk()).total,
+            emulated_inversions: res.legalization.emulated_inversions,
+            emulation_majs: res.legalization.additional_majs,
+            spills: res.output.borrow_program().allocation.spills,
+            reloads: res.output.borrow_program().allocation.reloads,
+            rematerializations: res.output.borrow_program().allocation.rematerializations,
+            tra_count: efficiency.tra_count,
+            maj_count: efficiency.maj_count,
+            copy_count: efficiency.copy_count,
+            row_activations: efficiency.row_activations,
             t_runner: res.t_runner as u64,
             t_extractor: res.t_extractor as u64,
             t_compiler: res.t_compiler as u64,
+            error: 0,
+        }
+    }
+
+    /// Statistics reported to C callers instead of panicking if the network can't be compiled.
+    /// On failure the rewriting entry points send an empty network to their receiver.
+    fn from_error(error: CompileError) -> Self {
+        eprintln!("could not compile network: {error}");
+        if let CompileError::Escalated { diagnostics, .. } = &error {
+            for diagnostic in diagnostics {
+                eprintln!("{diagnostic}");
+            }
         }
+        CompilerStatistics { error: error.code(), ..CompilerStatistics::default() }
     }
 }
+
+/// Nr of inputs of the network sent to the e-graph, i.e. one more than the highest input index
+fn count_egraph_inputs(graph: &EGraph<MigLanguage, ()>) -> u64 {
+    graph
+        .classes()
+        .flat_map(|class| class.iter())
+        .filter_map(|node| match node {
+            MigLanguage::Input(index) => Some(index + 1),
+            _ => None,
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+/// Nr of inputs of the network as received, i.e. one more than the highest input index
+fn count_network_inputs(network: &impl Network<Node = Mig>) -> u64 {
+    reachable_nodes(network)
+        .into_iter()
+        .filter_map(|id| match network.node(id) {
+            Mig::Input(index) => Some(index + 1),
+            _ => None,
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+/// Nr of MAJs reachable from the outputs of the network
+fn count_majs(network: &impl Network<Node = Mig>) -> u64 {
+    reachable_nodes(network)
+        .into_iter()
+        .filter(|id| matches!(network.node(*id), Mig::Maj(_)))
+        .count() as u64
+}
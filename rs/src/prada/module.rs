@@ -0,0 +1,113 @@
+//! Hierarchical compilation: sub-networks which occur multiple times (e.g. the full-adders of a
+//! multiplier) are compiled once into a [Module] and then instantiated in different subarrays by
+//! relocating the compiled program.
+use super::architecture::{PRADAArchitecture, SubarrayId};
+use super::error::CompileError;
+use super::fragment::{FragmentId, Linker, ProgramFragment};
+use super::program::Program;
+use super::{compile, CompilerSettings};
+use eggmock::{Mig, Network};
+use rustc_hash::FxHashMap;
+
+/// A sub-network which has been compiled once and can be instantiated arbitrarily often
+#[derive(Debug, Clone)]
+pub struct Module<'a> {
+    pub name: String,
+    /// Program of the module compiled into subarray 0
+    pub program: Program<'a>,
+}
+
+impl<'a> Module<'a> {
+    /// Rewrites, extracts and compiles `network` like [compile] does
+    pub fn compile(
+        architecture: &'a PRADAArchitecture,
+        name: impl Into<String>,
+        network: &impl Network<Node = Mig>,
+        settings: CompilerSettings,
+    ) -> Result<Self, CompileError> {
+        Ok(Self {
+            name: name.into(),
+            program: compile(architecture, network, settings)?,
+        })
+    }
+
+    /// Returns the module as a relocatable fragment
+    pub fn fragment(&self) -> ProgramFragment {
+        ProgramFragment::from_program(&self.program)
+    }
+
+    /// Returns the program of this module relocated into `subarray`
+    /// - every instance should be placed into its own compute/reference subarray pair, otherwise
+    ///   the instances overwrite each other's rows
+    pub fn instantiate(&self, subarray: SubarrayId) -> Result<Program<'a>, CompileError> {
+        if subarray.0 >= self.program.architecture.nr_subarrays {
+            return Err(CompileError::Other("subarray of module instance does not exist"));
+        }
+        Ok(self.program.relocate(subarray))
+    }
+}
+
+/// Caches compiled modules by name so that each module is only compiled once
+pub struct ModuleLibrary<'a> {
+    architecture: &'a PRADAArchitecture,
+    /// Settings all modules of the library are compiled with
+    settings: CompilerSettings,
+    modules: FxHashMap<String, Module<'a>>,
+}
+
+impl<'a> ModuleLibrary<'a> {
+    pub fn new(architecture: &'a PRADAArchitecture, settings: CompilerSettings) -> Self {
+        Self {
+            architecture,
+            settings,
+            modules: FxHashMap::default(),
+        }
+    }
+
+    /// Returns the module with the given name, compiling `network` only if the module hasn't been
+    /// compiled yet
+    pub fn get_or_compile(
+        &mut self,
+        name: &str,
+        network: &impl Network<Node = Mig>,
+    ) -> Result<&Module<'a>, CompileError> {
+        if !self.modules.contains_key(name) {
+            let module = Module::compile(self.architecture, name, network, self.settings)?;
+            self.modules.insert(name.to_string(), module);
+        }
+        Ok(&self.modules[name])
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Module<'a>> {
+        self.modules.get(name)
+    }
+
+    /// Instantiates the given modules into their subarrays and links them into a single program.
+    /// Since every instance lives in its own subarray the instances don't interfere with each
+    /// other.
+    pub fn instantiate_all(
+        &self,
+        instances: &[(&str, SubarrayId)],
+    ) -> Result<Program<'a>, CompileError> {
+        let mut linker = Linker::new(self.architecture);
+        for (name, subarray) in instances {
+            self.instantiate(&mut linker, name, *subarray)?;
+        }
+        linker.link()
+    }
+
+    /// Adds an instance of the given module to `linker`, so that it can be connected to other
+    /// fragments
+    pub fn instantiate(
+        &self,
+        linker: &mut Linker<'a>,
+        name: &str,
+        subarray: SubarrayId,
+    ) -> Result<FragmentId, CompileError> {
+        if subarray.0 >= self.architecture.nr_subarrays {
+            return Err(CompileError::Other("subarray of module instance does not exist"));
+        }
+        let module = self.get(name).ok_or(CompileError::Other("module has not been compiled"))?;
+        Ok(linker.add(module.fragment(), subarray))
+    }
+}
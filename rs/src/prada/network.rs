@@ -0,0 +1,265 @@
+//! A minimal MIG which can be built from Rust, e.g. by kernel generators or tests, and then be sent
+//! to any eggmock receiver.
+use eggmock::{Mig, Network, Signal};
+use rustc_hash::FxHashMap;
+use std::cmp::Reverse;
+
+#[derive(Debug, Clone)]
+pub struct MigNetwork {
+    /// node `i` has id `i`, node 0 is always the constant `False`
+    nodes: Vec<Mig>,
+    /// Nr of MAJ nodes on the longest path from a leaf to (and including) node `i`
+    levels: Vec<u64>,
+    outputs: Vec<Signal>,
+    nr_inputs: u64,
+}
+
+impl Default for MigNetwork {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MigNetwork {
+    pub fn new() -> Self {
+        Self {
+            nodes: vec!(Mig::False),
+            levels: vec!(0),
+            outputs: vec!(),
+            nr_inputs: 0,
+        }
+    }
+
+    fn add_node(&mut self, node: Mig) -> Signal {
+        let level = match node {
+            Mig::Maj(inputs) => 1 + inputs.iter().map(|input| self.level(*input)).max().unwrap_or(0),
+            _ => 0,
+        };
+        self.nodes.push(node);
+        self.levels.push(level);
+        Signal::new(to_id(self.nodes.len() - 1), false)
+    }
+
+    pub fn constant(&self, value: bool) -> Signal {
+        Signal::new(to_id(0), value)
+    }
+
+    /// Adds a new input, inputs are numbered in the order they are added
+    pub fn add_input(&mut self) -> Signal {
+        self.nr_inputs += 1;
+        self.add_node(Mig::Input(self.nr_inputs - 1))
+    }
+
+    pub fn maj(&mut self, a: Signal, b: Signal, c: Signal) -> Signal {
+        self.add_node(Mig::Maj([a, b, c]))
+    }
+
+    /// Majority of an odd nr of signals, decomposed into MAJ-3 nodes before being added to the
+    /// network (see [Self::maj_n_decomposition])
+    pub fn maj_n(&mut self, inputs: &[Signal]) -> Result<Signal, &'static str> {
+        if inputs.len() % 2 == 0 {
+            return Err("majority requires an odd nr of inputs");
+        }
+        // constant operands lower the threshold (true, the majority is constantly true once it reaches
+        // 0) or drop out (false)
+        let mut threshold = inputs.len().div_ceil(2);
+        let mut operands = vec!();
+        for input in inputs {
+            match self.constant_value(*input) {
+                Some(true) => threshold = threshold.saturating_sub(1),
+                Some(false) => (),
+                None => operands.push(*input),
+            }
+        }
+        // operands close to the root of the decomposition pass the fewest MAJs, hence the operands
+        // arriving last are placed there to keep the critical path short
+        operands.sort_by_key(|operand| Reverse(self.level(*operand)));
+        Ok(self.maj_n_decomposition(&operands, threshold, &mut FxHashMap::default()))
+    }
+
+    /// Decomposes the threshold function "at least `threshold` of `operands` are set" using the
+    /// expansion `T_k(x, rest) = x ? T_{k-1}(rest) : T_k(rest) = MAJ(x, T_{k-1}(rest), T_k(rest))`,
+    /// which is valid since `T_{k-1}(rest)` is implied by `T_k(rest)`. The sub-functions are
+    /// shared, leading to `O(n^2)` MAJ-3 nodes, but only `n` levels for `n` operands. Three
+    /// operands with a threshold of two are mapped onto a single MAJ.
+    fn maj_n_decomposition(
+        &mut self,
+        operands: &[Signal],
+        threshold: usize,
+        memo: &mut FxHashMap<(usize, usize), Signal>,
+    ) -> Signal {
+        if threshold == 0 {
+            return self.constant(true);
+        }
+        if threshold > operands.len() {
+            return self.constant(false);
+        }
+        if operands.len() == 1 {
+            return operands[0];
+        }
+        if let Some(signal) = memo.get(&(operands.len(), threshold)) {
+            return *signal;
+        }
+        let signal = if operands.len() == 3 && threshold == 2 {
+            self.maj(operands[0], operands[1], operands[2])
+        } else {
+            let implied = self.maj_n_decomposition(&operands[1..], threshold - 1, memo);
+            let implying = self.maj_n_decomposition(&operands[1..], threshold, memo);
+            self.maj(operands[0], implied, implying)
+        };
+        memo.insert((operands.len(), threshold), signal);
+        signal
+    }
+
+    fn constant_value(&self, signal: Signal) -> Option<bool> {
+        (self.nodes[usize::from(eggmock::egg::Id::from(signal.node_id()))] == Mig::False)
+            .then(|| signal.is_inverted())
+    }
+
+    /// Nr of MAJ nodes on the longest path from a leaf to the given signal
+    pub fn level(&self, signal: Signal) -> u64 {
+        self.levels[usize::from(eggmock::egg::Id::from(signal.node_id()))]
+    }
+
+    pub fn and(&mut self, a: Signal, b: Signal) -> Signal {
+        self.maj(a, b, self.constant(false))
+    }
+
+    pub fn or(&mut self, a: Signal, b: Signal) -> Signal {
+        self.maj(a, b, self.constant(true))
+    }
+
+    pub fn xor(&mut self, a: Signal, b: Signal) -> Signal {
+        let or = self.or(a, b);
+        let and = self.and(a, b);
+        self.and(or, and.invert())
+    }
+
+    /// `select ? then : otherwise`
+    pub fn mux(&mut self, select: Signal, then: Signal, otherwise: Signal) -> Signal {
+        let then = self.and(select, then);
+        let otherwise = self.and(select.invert(), otherwise);
+        self.or(then, otherwise)
+    }
+
+    /// Returns `(sum, carry)` of the full adder `a + b + c`, using `sum = MAJ(!carry, c, MAJ(a, b, !c))`
+    pub fn full_adder(&mut self, a: Signal, b: Signal, c: Signal) -> (Signal, Signal) {
+        let carry = self.maj(a, b, c);
+        let inner = self.maj(a, b, c.invert());
+        (self.maj(carry.invert(), c, inner), carry)
+    }
+
+    /// Returns the nr of set signals as binary number (least significant bit first), reducing the
+    /// bits of every weight with full and half adders until a single bit per weight is left
+    pub fn popcount(&mut self, signals: &[Signal]) -> Vec<Signal> {
+        // bits of weight 2^i
+        let mut columns = vec!(signals.to_vec());
+        let mut result = vec!();
+        let mut weight = 0;
+        while weight < columns.len() {
+            while columns[weight].len() > 1 {
+                let mut bits = std::mem::take(&mut columns[weight]);
+                let (sum, carry) = if bits.len() >= 3 {
+                    let [a, b, c] = [bits.pop(), bits.pop(), bits.pop()].map(Option::unwrap);
+                    self.full_adder(a, b, c)
+                } else {
+                    let [a, b] = [bits.pop(), bits.pop()].map(Option::unwrap);
+                    (self.xor(a, b), self.and(a, b))
+                };
+                // the sum is reduced last to keep the adder chains short
+                bits.insert(0, sum);
+                columns[weight] = bits;
+                if columns.len() == weight + 1 {
+                    columns.push(vec!());
+                }
+                columns[weight + 1].push(carry);
+            }
+            result.push(columns[weight].first().copied().unwrap_or(self.constant(false)));
+            weight += 1;
+        }
+        result
+    }
+
+    /// Returns the bits of `table[x]` (least significant bit first, `output_width` bits), where
+    /// bit `i` of the index `x` is `inputs[i]`, i.e. `table` has `2^inputs.len()` entries. Each
+    /// output is decomposed by Shannon expansion on the most significant input first, sharing
+    /// equal sub-functions between all outputs.
+    pub fn lookup(&mut self, inputs: &[Signal], table: &[u64], output_width: usize) -> Vec<Signal> {
+        assert_eq!(table.len(), 1 << inputs.len(), "table needs an entry for every input combination");
+        let mut memo = FxHashMap::default();
+        (0..output_width)
+            .map(|bit| {
+                let function: Vec<bool> = table.iter().map(|entry| entry & (1 << bit) != 0).collect();
+                self.shannon_decomposition(inputs, &function, &mut memo)
+            })
+            .collect()
+    }
+
+    fn shannon_decomposition(
+        &mut self,
+        inputs: &[Signal],
+        function: &[bool],
+        memo: &mut FxHashMap<Vec<bool>, Signal>,
+    ) -> Signal {
+        if function.iter().all(|value| *value == function[0]) {
+            return self.constant(function[0]);
+        }
+        if let Some(signal) = memo.get(function) {
+            return *signal;
+        }
+        let (otherwise, then) = function.split_at(function.len() / 2);
+        let (select, inputs) = inputs.split_last().expect("non-constant function depends on an input");
+        let signal = if then == otherwise {
+            self.shannon_decomposition(inputs, then, memo)
+        } else {
+            let then = self.shannon_decomposition(inputs, then, memo);
+            let otherwise = self.shannon_decomposition(inputs, otherwise, memo);
+            match (self.constant_value(then), self.constant_value(otherwise)) {
+                (Some(true), _) => self.or(*select, otherwise),
+                (Some(false), _) => self.and(select.invert(), otherwise),
+                (_, Some(true)) => self.or(select.invert(), then),
+                (_, Some(false)) => self.and(*select, then),
+                _ if then == otherwise.invert() => self.xor(*select, otherwise),
+                _ => self.mux(*select, then, otherwise),
+            }
+        };
+        memo.insert(function.to_vec(), signal);
+        signal
+    }
+
+    pub fn add_output(&mut self, signal: Signal) {
+        self.outputs.push(signal);
+    }
+
+    pub fn nr_inputs(&self) -> u64 {
+        self.nr_inputs
+    }
+
+    pub fn nr_nodes(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Returns the input with the given index
+    pub fn input(&self, index: u64) -> Option<Signal> {
+        self.nodes
+            .iter()
+            .position(|node| *node == Mig::Input(index))
+            .map(|idx| Signal::new(to_id(idx), false))
+    }
+}
+
+impl Network for MigNetwork {
+    type Node = Mig;
+
+    fn outputs(&self) -> impl Iterator<Item = Signal> {
+        self.outputs.iter().copied()
+    }
+
+    fn node(&self, id: eggmock::Id) -> Self::Node {
+        self.nodes[usize::from(eggmock::egg::Id::from(id))]
+    }
+}
+
+fn to_id(idx: usize) -> eggmock::Id {
+    eggmock::Id::from(eggmock::egg::Id::from(idx))
+}
@@ -0,0 +1,96 @@
+//! Keyed randomization of the row layout of compiled programs: the rows of every subarray are
+//! permuted by a permutation derived from a per-deployment key, so that the physical placement of
+//! data can't be predicted from the compiler (e.g. by co-located tenants in shared-memory PIM
+//! settings). The [RowRemapping] maps the rows of the compiled program to the rows of the
+//! deployed one and back, so that results can still be related to the compiled program.
+use std::fmt::Write;
+
+use super::architecture::{RowAddress, ROW_ID_BITMASK};
+use super::program::Program;
+use super::random::SplitMix;
+
+/// Permutation of the local rows of a subarray, applied to every subarray alike
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RowRemapping {
+    /// Deployed local row of every compiled local row
+    table: Vec<u64>,
+    /// Inverse of `table`
+    inverse: Vec<u64>,
+}
+
+impl RowRemapping {
+    /// Derives the permutation of `rows_per_subarray` rows from `key` (Fisher-Yates shuffle), the
+    /// same key always yielding the same permutation
+    pub fn new(key: u64, rows_per_subarray: u64) -> Self {
+        let mut random = SplitMix::new(key);
+        let mut table: Vec<u64> = (0..rows_per_subarray).collect();
+        for i in (1..table.len()).rev() {
+            let j = (random.next_u64() % (i as u64 + 1)) as usize;
+            table.swap(i, j);
+        }
+        Self::from_table(table).expect("shuffled identity is a permutation")
+    }
+
+    /// Remapping given by the deployed local row of every compiled local row, `None` if the table
+    /// isn't a permutation
+    pub fn from_table(table: Vec<u64>) -> Option<Self> {
+        let mut inverse = vec![u64::MAX; table.len()];
+        for (row, mapped) in table.iter().enumerate() {
+            let slot = inverse.get_mut(*mapped as usize)?;
+            if *slot != u64::MAX {
+                return None;
+            }
+            *slot = row as u64;
+        }
+        Some(Self { table, inverse })
+    }
+
+    pub fn table(&self) -> &[u64] {
+        &self.table
+    }
+
+    /// Deployed row of the compiled `row`
+    pub fn map(&self, row: RowAddress) -> RowAddress {
+        Self::apply(&self.table, row)
+    }
+
+    /// Compiled row of the deployed `row`
+    pub fn unmap(&self, row: RowAddress) -> RowAddress {
+        Self::apply(&self.inverse, row)
+    }
+
+    /// Rows beyond the end of the table are kept
+    fn apply(table: &[u64], row: RowAddress) -> RowAddress {
+        let local = row.0 & ROW_ID_BITMASK;
+        RowAddress(row.0 - local + table.get(local as usize).copied().unwrap_or(local))
+    }
+
+    /// Serializes the table as one line per row, holding the compiled and the deployed local row
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+        for (row, mapped) in self.table.iter().enumerate() {
+            writeln!(out, "{row} {mapped}").unwrap();
+        }
+        out
+    }
+
+    /// Inverse of [Self::to_text]
+    pub fn parse(text: &str) -> Result<Self, &'static str> {
+        let mut table = vec!();
+        for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            let (row, mapped) = line.split_once(' ').ok_or("expected `<compiled row> <deployed row>`")?;
+            if row.parse::<usize>().map_err(|_| "invalid row")? != table.len() {
+                return Err("rows have to be listed in order");
+            }
+            table.push(mapped.trim().parse().map_err(|_| "invalid row")?);
+        }
+        Self::from_table(table).ok_or("table isn't a permutation")
+    }
+}
+
+/// Returns `program` with its rows permuted by the remapping derived from `key`, together with the
+/// remapping
+pub fn randomize_layout<'a>(program: &Program<'a>, key: u64) -> (Program<'a>, RowRemapping) {
+    let remapping = RowRemapping::new(key, program.architecture.rows_per_subarray);
+    (program.map_rows(|row| remapping.map(row)), remapping)
+}
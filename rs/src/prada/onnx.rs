@@ -0,0 +1,350 @@
+//! Front-end reading binarized models in the ONNX format (only available with the `onnx` feature).
+//!
+//! Supported are sequential models of fully connected layers, each consisting of a `MatMul` (or a
+//! `Gemm` without scaling) with bipolar weights (-1 or +1), an optional `Add` of a bias and a
+//! `Sign` activation. `Flatten`, `Reshape` and `Identity` of activations are ignored. Every layer
+//! is mapped onto a [BinaryLayer] and compiled into its own kernel of a [Bundle].
+use super::architecture::PRADAArchitecture;
+use super::bnn::BinaryLayer;
+use super::bundle::Bundle;
+use super::error::CompileError;
+use super::CompilerSettings;
+use rustc_hash::FxHashMap;
+
+/// Sequence of binarized layers, the activations of each layer being the neurons of the previous
+/// one (the model inputs for the first layer)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BinaryModel {
+    /// Name (of the `Sign` node) and layer
+    pub layers: Vec<(String, BinaryLayer)>,
+}
+
+/// Value of a tensor of the ONNX graph while mapping it onto layers
+enum Tensor {
+    /// Bipolar activations, the neurons of the last layer or the model inputs
+    Activations,
+    /// Result of a `MatMul` or `Gemm` (plus bias) before the activation function
+    Preactivation { weights: Vec<Vec<bool>>, bias: Vec<f32> },
+}
+
+impl BinaryModel {
+    /// Parses a serialized ONNX `ModelProto`
+    pub fn from_onnx(bytes: &[u8]) -> Result<Self, &'static str> {
+        let graph = Message::parse(bytes)?
+            .message(7)?
+            .ok_or("model without graph")?;
+
+        let mut initializers: FxHashMap<String, (Vec<u64>, Vec<f32>)> = FxHashMap::default();
+        for tensor in graph.messages(5)? {
+            let name = tensor.string(8)?.unwrap_or_default();
+            initializers.insert(name, parse_tensor(&tensor)?);
+        }
+        let mut tensors: FxHashMap<String, Tensor> = FxHashMap::default();
+        for input in graph.messages(11)? {
+            let name = input.string(1)?.unwrap_or_default();
+            if !initializers.contains_key(&name) {
+                tensors.insert(name, Tensor::Activations);
+            }
+        }
+
+        let mut layers = vec!();
+        let mut latest_activations = None;
+        for node in graph.messages(1)? {
+            let op_type = node.string(4)?.unwrap_or_default();
+            let inputs = node.strings(1)?;
+            let outputs = node.strings(2)?;
+            let output = outputs.first().cloned().ok_or("node without output")?;
+            let input = |idx: usize| inputs.get(idx).ok_or("missing operand");
+            let tensor = match op_type.as_str() {
+                "Flatten" | "Reshape" | "Identity" => match tensors.remove(input(0)?) {
+                    Some(Tensor::Activations) => {
+                        if latest_activations.as_ref() == Some(input(0)?) {
+                            latest_activations = Some(output.clone());
+                        }
+                        Tensor::Activations
+                    }
+                    _ => return Err("reshaping is only supported for activations"),
+                },
+                "MatMul" | "Gemm" => {
+                    let source = input(0)?;
+                    if !matches!(tensors.get(source), Some(Tensor::Activations)) {
+                        return Err("layers have to be applied to activations");
+                    }
+                    if latest_activations.as_ref().is_some_and(|latest| latest != source) {
+                        return Err("only sequential models are supported");
+                    }
+                    let (dims, values) = initializers.get(input(1)?).ok_or("weights have to be constant")?;
+                    let [rows, columns] = dims[..] else {
+                        return Err("weights have to be a matrix");
+                    };
+                    let transposed = op_type == "Gemm" && node.int_attribute("transB")?.unwrap_or(0) != 0;
+                    if op_type == "Gemm" && node.int_attribute("transA")?.unwrap_or(0) != 0 {
+                        return Err("transposed activations are not supported");
+                    }
+                    // weights[j][i] connects input i to neuron j
+                    let (nr_inputs, nr_neurons) = if transposed { (columns, rows) } else { (rows, columns) };
+                    let weight = |i: u64, j: u64| {
+                        let value = if transposed { values[(j * columns + i) as usize] } else { values[(i * columns + j) as usize] };
+                        if value == 1.0 {
+                            Ok(true)
+                        } else if value == -1.0 {
+                            Ok(false)
+                        } else {
+                            Err("weights are not binarized")
+                        }
+                    };
+                    let weights = (0..nr_neurons)
+                        .map(|j| (0..nr_inputs).map(|i| weight(i, j)).collect::<Result<Vec<bool>, _>>())
+                        .collect::<Result<Vec<Vec<bool>>, _>>()?;
+                    let mut bias = vec!(0.0; nr_neurons as usize);
+                    if let Some(name) = inputs.get(2).filter(|_| op_type == "Gemm") {
+                        add_bias(&mut bias, initializers.get(name))?;
+                    }
+                    Tensor::Preactivation { weights, bias }
+                }
+                "Add" => {
+                    let (source, constant) = if tensors.contains_key(input(0)?) {
+                        (input(0)?, input(1)?)
+                    } else {
+                        (input(1)?, input(0)?)
+                    };
+                    match tensors.remove(source) {
+                        Some(Tensor::Preactivation { weights, mut bias }) => {
+                            add_bias(&mut bias, initializers.get(constant))?;
+                            Tensor::Preactivation { weights, bias }
+                        }
+                        _ => return Err("biases can only be added to the result of a layer"),
+                    }
+                }
+                "Sign" => match tensors.remove(input(0)?) {
+                    Some(Tensor::Preactivation { weights, bias }) => {
+                        let nr_inputs = weights.first().map_or(0, Vec::len) as f32;
+                        // sum = 2 * agreements - nr_inputs, the neuron fires iff sum + bias >= 0
+                        let thresholds = bias
+                            .iter()
+                            .map(|bias| ((nr_inputs - bias) / 2.0).ceil().max(0.0) as u64)
+                            .collect();
+                        let layer = BinaryLayer::new(weights, thresholds)?;
+                        let name = node.string(3)?.filter(|name| !name.is_empty()).unwrap_or(output.clone());
+                        layers.push((name, layer));
+                        latest_activations = Some(output.clone());
+                        Tensor::Activations
+                    }
+                    _ => return Err("`Sign` is only supported as activation of a layer"),
+                },
+                _ => return Err("unsupported operator"),
+            };
+            tensors.insert(output, tensor);
+        }
+        if layers.is_empty() {
+            return Err("model without binarized layers");
+        }
+        Ok(Self { layers })
+    }
+
+    /// Compiles every layer (see [BinaryLayer::compile]) into a kernel of the returned bundle,
+    /// named after the layer, and hands the neurons of every layer over to the activations of the
+    /// next one
+    pub fn compile<'a>(
+        &self,
+        architecture: &'a PRADAArchitecture,
+        settings: CompilerSettings,
+    ) -> Result<Bundle<'a>, CompileError> {
+        let mut bundle = Bundle::new(architecture);
+        let names: Vec<String> = self
+            .layers
+            .iter()
+            .map(|(name, _)| name.chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }).collect())
+            .collect();
+        for (name, (_, layer)) in names.iter().zip(&self.layers) {
+            bundle.add(name.clone(), layer.compile(architecture, settings)?.program)?;
+        }
+        for (idx, (_, layer)) in self.layers.iter().enumerate().skip(1) {
+            for neuron in 0..layer.input_width() {
+                bundle.connect(&names[idx - 1], neuron, &names[idx], neuron as u64)?;
+            }
+        }
+        Ok(bundle)
+    }
+}
+
+fn add_bias(bias: &mut [f32], constant: Option<&(Vec<u64>, Vec<f32>)>) -> Result<(), &'static str> {
+    let (_, values) = constant.ok_or("biases have to be constant")?;
+    match values.len() {
+        1 => bias.iter_mut().for_each(|bias| *bias += values[0]),
+        n if n == bias.len() => bias.iter_mut().zip(values).for_each(|(bias, value)| *bias += value),
+        _ => return Err("bias does not match the nr of neurons"),
+    }
+    Ok(())
+}
+
+/// Returns the dimensions and values of a `TensorProto`
+fn parse_tensor(tensor: &Message) -> Result<(Vec<u64>, Vec<f32>), &'static str> {
+    let dims = tensor.varints(1)?;
+    let data_type = tensor.varints(2)?.first().copied().unwrap_or(0);
+    let raw = tensor.bytes(9)?;
+    let values: Vec<f32> = match (data_type, raw) {
+        // FLOAT
+        (1, Some(raw)) => raw.chunks_exact(4).map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]])).collect(),
+        (1, None) => tensor.fixed32s(4)?.into_iter().map(f32::from_bits).collect(),
+        // INT8
+        (3, Some(raw)) => raw.iter().map(|b| *b as i8 as f32).collect(),
+        // INT32
+        (6, Some(raw)) => raw.chunks_exact(4).map(|b| i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f32).collect(),
+        (3 | 6, None) => tensor.varints(5)?.into_iter().map(|v| v as i32 as f32).collect(),
+        // INT64
+        (7, Some(raw)) => raw
+            .chunks_exact(8)
+            .map(|b| i64::from_le_bytes(b.try_into().unwrap()) as f32)
+            .collect(),
+        (7, None) => tensor.varints(7)?.into_iter().map(|v| v as i64 as f32).collect(),
+        _ => return Err("unsupported tensor data type"),
+    };
+    if values.len() as u64 != dims.iter().product::<u64>() {
+        return Err("tensor size does not match its dimensions");
+    }
+    Ok((dims, values))
+}
+
+/// Value of a protobuf field
+#[derive(Copy, Clone)]
+enum Field<'b> {
+    Varint(u64),
+    /// Not used by ONNX messages relevant for binarized models, hence skipped
+    Fixed64,
+    Bytes(&'b [u8]),
+    Fixed32(u32),
+}
+
+/// Decoded protobuf message, only the subset of the wire format used by ONNX is supported
+struct Message<'b> {
+    fields: Vec<(u64, Field<'b>)>,
+}
+
+impl<'b> Message<'b> {
+    fn parse(mut bytes: &'b [u8]) -> Result<Self, &'static str> {
+        let mut fields = vec!();
+        while !bytes.is_empty() {
+            let key = read_varint(&mut bytes)?;
+            let field = match key & 0b111 {
+                0 => Field::Varint(read_varint(&mut bytes)?),
+                1 => {
+                    take(&mut bytes, 8)?;
+                    Field::Fixed64
+                }
+                2 => {
+                    let len = read_varint(&mut bytes)?;
+                    Field::Bytes(take(&mut bytes, len as usize)?)
+                }
+                5 => Field::Fixed32(u32::from_le_bytes(take(&mut bytes, 4)?.try_into().unwrap())),
+                _ => return Err("unsupported protobuf wire type"),
+            };
+            fields.push((key >> 3, field));
+        }
+        Ok(Self { fields })
+    }
+
+    fn all(&self, number: u64) -> impl Iterator<Item = Field<'b>> + '_ {
+        self.fields.iter().filter(move |(n, _)| *n == number).map(|(_, field)| *field)
+    }
+
+    fn bytes(&self, number: u64) -> Result<Option<&'b [u8]>, &'static str> {
+        match self.all(number).last() {
+            Some(Field::Bytes(bytes)) => Ok(Some(bytes)),
+            Some(_) => Err("expected length-delimited field"),
+            None => Ok(None),
+        }
+    }
+
+    fn message(&self, number: u64) -> Result<Option<Message<'b>>, &'static str> {
+        self.bytes(number)?.map(Message::parse).transpose()
+    }
+
+    fn messages(&self, number: u64) -> Result<Vec<Message<'b>>, &'static str> {
+        self.all(number)
+            .map(|field| match field {
+                Field::Bytes(bytes) => Message::parse(bytes),
+                _ => Err("expected message"),
+            })
+            .collect()
+    }
+
+    fn string(&self, number: u64) -> Result<Option<String>, &'static str> {
+        self.bytes(number)?
+            .map(|bytes| String::from_utf8(bytes.to_vec()).map_err(|_| "invalid string"))
+            .transpose()
+    }
+
+    fn strings(&self, number: u64) -> Result<Vec<String>, &'static str> {
+        self.all(number)
+            .map(|field| match field {
+                Field::Bytes(bytes) => String::from_utf8(bytes.to_vec()).map_err(|_| "invalid string"),
+                _ => Err("expected string"),
+            })
+            .collect()
+    }
+
+    /// Repeated varint field, either packed or not
+    fn varints(&self, number: u64) -> Result<Vec<u64>, &'static str> {
+        let mut values = vec!();
+        for field in self.all(number) {
+            match field {
+                Field::Varint(value) => values.push(value),
+                Field::Bytes(mut bytes) => {
+                    while !bytes.is_empty() {
+                        values.push(read_varint(&mut bytes)?);
+                    }
+                }
+                _ => return Err("expected varint"),
+            }
+        }
+        Ok(values)
+    }
+
+    /// Repeated fixed32 field, either packed or not
+    fn fixed32s(&self, number: u64) -> Result<Vec<u32>, &'static str> {
+        let mut values = vec!();
+        for field in self.all(number) {
+            match field {
+                Field::Fixed32(value) => values.push(value),
+                Field::Bytes(bytes) if bytes.len() % 4 == 0 => values.extend(
+                    bytes.chunks_exact(4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]])),
+                ),
+                _ => return Err("expected fixed32"),
+            }
+        }
+        Ok(values)
+    }
+
+    /// Integer attribute (`AttributeProto.i`) of a `NodeProto`
+    fn int_attribute(&self, name: &str) -> Result<Option<i64>, &'static str> {
+        for attribute in self.messages(5)? {
+            if attribute.string(1)?.as_deref() == Some(name) {
+                return Ok(attribute.varints(3)?.first().map(|value| *value as i64));
+            }
+        }
+        Ok(None)
+    }
+}
+
+fn read_varint(bytes: &mut &[u8]) -> Result<u64, &'static str> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let (byte, rest) = bytes.split_first().ok_or("truncated varint")?;
+        *bytes = rest;
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err("varint too long")
+}
+
+fn take<'b>(bytes: &mut &'b [u8], len: usize) -> Result<&'b [u8], &'static str> {
+    if bytes.len() < len {
+        return Err("truncated field");
+    }
+    let (taken, rest) = bytes.split_at(len);
+    *bytes = rest;
+    Ok(taken)
+}
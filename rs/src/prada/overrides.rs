@@ -0,0 +1,143 @@
+//! Overrides of the extractor's choices for parts of the network, e.g. to keep the structure of a
+//! sensitive sub-block as it has been received, to find out whether a miscompilation is caused by
+//! rewriting or to only optimize some kernels of a larger netlist. The overridden nodes are located
+//! in the e-graph before rewriting and the extractor is restricted to them afterwards, so the rest
+//! of the network is still optimized around them.
+use std::collections::HashMap;
+
+use eggmock::egg::{EGraph, Id, Language, RecExpr};
+use eggmock::{Mig, MigLanguage, Network, Signal};
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use super::error::CompileError;
+
+/// What to keep of the structure rooted at a signal
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ExtractionOverride {
+    /// Extract the node of the signal as received, while its operands may still be rewritten
+    KeepNode,
+    /// Extract the whole fan-in cone of the signal as received, i.e. don't rewrite it at all
+    KeepCone,
+}
+
+/// Term of the overridden signals, independent of the e-graph it is added to
+#[derive(Debug, Clone)]
+pub struct PinnedTerm {
+    expr: RecExpr<MigLanguage>,
+    /// Whether the extractor is restricted to the respective node of `expr`
+    pinned: Vec<bool>,
+}
+
+/// Builds the terms of the overridden signals of `network`
+pub fn pinned_terms(
+    network: &impl Network<Node = Mig>,
+    overrides: &HashMap<Signal, ExtractionOverride>,
+) -> Vec<PinnedTerm> {
+    overrides
+        .iter()
+        .map(|(signal, marker)| {
+            let mut term = TermBuilder::default();
+            term.add_signal(network, *signal);
+            let nr_nodes = term.signals.len();
+            // the root is the last node of the term
+            let pinned = (0..nr_nodes)
+                .map(|index| *marker == ExtractionOverride::KeepCone || index == nr_nodes - 1)
+                .collect();
+            PinnedTerm { expr: term.expr, pinned }
+        })
+        .collect()
+}
+
+/// Builds the term pinning every node of `network` outside of the fan-in cones of `regions`, so
+/// that only the regions are rewritten
+pub fn scoped_terms(network: &impl Network<Node = Mig>, regions: &[Signal]) -> Vec<PinnedTerm> {
+    let mut region = TermBuilder::default();
+    for signal in regions {
+        region.add_signal(network, *signal);
+    }
+    let region: FxHashSet<Id> = region.signals.iter().map(Signal::node_id).collect();
+
+    let mut term = TermBuilder::default();
+    for output in network.outputs() {
+        term.add_signal(network, output);
+    }
+    let pinned = term.signals.iter().map(|signal| !region.contains(&signal.node_id())).collect();
+    vec!(PinnedTerm { expr: term.expr, pinned })
+}
+
+/// Translates the fan-in cones of signals into a term, whose nodes are ordered children first
+#[derive(Debug, Clone, Default)]
+pub(super) struct TermBuilder {
+    pub(super) expr: RecExpr<MigLanguage>,
+    /// Signal of every node of `expr`
+    signals: Vec<Signal>,
+    ids: FxHashMap<Signal, Id>,
+}
+
+impl TermBuilder {
+    pub(super) fn add_signal(&mut self, network: &impl Network<Node = Mig>, signal: Signal) -> Id {
+        if let Some(id) = self.ids.get(&signal) {
+            return *id;
+        }
+        let node = if signal.is_inverted() {
+            MigLanguage::Not(self.add_signal(network, Signal::new(signal.node_id(), false)))
+        } else {
+            match network.node(signal.node_id()) {
+                Mig::False => MigLanguage::False,
+                Mig::Input(index) => MigLanguage::Input(index),
+                Mig::Maj(inputs) => MigLanguage::Maj(inputs.map(|input| self.add_signal(network, input))),
+            }
+        };
+        let id = self.expr.add(node);
+        self.signals.push(signal);
+        self.ids.insert(signal, id);
+        id
+    }
+}
+
+/// Locates the pinned nodes in `graph`, which has to hold the network as received, i.e. must not
+/// have been rewritten yet
+pub fn locate_pinned_nodes(
+    graph: &EGraph<MigLanguage, ()>,
+    terms: &[PinnedTerm],
+) -> Result<Vec<(Id, MigLanguage)>, CompileError> {
+    let mut pinned = vec!();
+    for term in terms {
+        let nodes = term.expr.as_ref();
+        let mut ids: Vec<Id> = vec!();
+        for (index, node) in nodes.iter().enumerate() {
+            let node = node.clone().map_children(|child| ids[usize::from(child)]);
+            let id = graph
+                .lookup(node.clone())
+                .ok_or(CompileError::Other("overridden signal doesn't drive any output"))?;
+            ids.push(id);
+            if term.pinned[index] {
+                pinned.push((id, node));
+            }
+        }
+    }
+    Ok(pinned)
+}
+
+/// The pinned nodes of every class of `graph` after rewriting, all other nodes of these classes
+/// are ignored by the extractor. Nodes which rewriting proved equivalent to one of their own
+/// operands (e.g. `MAJ(a, b, MAJ(a, b, c))` and `MAJ(a, b, c)`) can't be extracted anymore, so they
+/// are dropped and their class is extracted as usual if none of its pinned nodes remains.
+pub fn canonical_pins(
+    graph: &EGraph<MigLanguage, ()>,
+    pinned: &[(Id, MigLanguage)],
+) -> FxHashMap<Id, Vec<MigLanguage>> {
+    let mut pins: FxHashMap<Id, Vec<MigLanguage>> = FxHashMap::default();
+    for (id, node) in pinned {
+        let class = graph.find(*id);
+        let node = node.clone().map_children(|child| graph.find(child));
+        if node.children().contains(&class) {
+            continue;
+        }
+        let nodes = pins.entry(class).or_default();
+        if !nodes.contains(&node) {
+            nodes.push(node);
+        }
+    }
+    pins
+}
@@ -0,0 +1,160 @@
+//! Passes transforming compiled programs, e.g. experimental optimizations. The passes of the
+//! [PassRegistry] of the process run on every program right after it has been compiled (and before
+//! it is checked for [diagnostics](super::diagnostics)), ordered by their order key.
+//!
+//! Downstream crates insert their own [ProgramPass]es via [with_registry] instead of forking the
+//! crate. With the `plugins` feature, passes can also be registered by dynamic libraries, see
+//! [load_plugin].
+use std::sync::{Arc, LazyLock, RwLock};
+
+use super::error::CompileError;
+use super::program::Program;
+use super::symbolic::prune_dead_instructions;
+
+pub trait ProgramPass: Send + Sync {
+    /// Name of the pass, unique within a [PassRegistry]
+    fn name(&self) -> &str;
+
+    /// Transforms `program` in place. The cost estimates of the program are updated after all
+    /// passes have run.
+    fn run(&self, program: &mut Program) -> Result<(), CompileError>;
+}
+
+/// Removes instructions which don't contribute to any output, see [prune_dead_instructions]
+#[derive(Debug, Copy, Clone, Default)]
+pub struct PruneDeadInstructions;
+
+impl ProgramPass for PruneDeadInstructions {
+    fn name(&self) -> &str {
+        "prune_dead_instructions"
+    }
+
+    fn run(&self, program: &mut Program) -> Result<(), CompileError> {
+        prune_dead_instructions(program).map(|_| ()).map_err(CompileError::Other)
+    }
+}
+
+struct RegisteredPass {
+    order: i32,
+    pass: Arc<dyn ProgramPass>,
+}
+
+/// Passes run on compiled programs. Passes with a lower order run first, passes with the same order
+/// in the order of their registration (unless inserted relative to another pass).
+#[derive(Default)]
+pub struct PassRegistry {
+    passes: Vec<RegisteredPass>,
+}
+
+impl PassRegistry {
+    /// Adds `pass` after all passes with an order less than or equal to `order`. Fails if a pass
+    /// with the same name has already been registered.
+    pub fn register(&mut self, order: i32, pass: impl ProgramPass + 'static) -> Result<(), &'static str> {
+        self.check_unregistered(&pass)?;
+        let index = self.passes.partition_point(|registered| registered.order <= order);
+        self.passes.insert(index, RegisteredPass { order, pass: Arc::new(pass) });
+        Ok(())
+    }
+
+    /// Adds `pass` with the order of the pass named `name`, right before it
+    pub fn register_before(&mut self, name: &str, pass: impl ProgramPass + 'static) -> Result<(), &'static str> {
+        self.check_unregistered(&pass)?;
+        let index = self.position(name)?;
+        let order = self.passes[index].order;
+        self.passes.insert(index, RegisteredPass { order, pass: Arc::new(pass) });
+        Ok(())
+    }
+
+    /// Adds `pass` with the order of the pass named `name`, right after it
+    pub fn register_after(&mut self, name: &str, pass: impl ProgramPass + 'static) -> Result<(), &'static str> {
+        self.check_unregistered(&pass)?;
+        let index = self.position(name)?;
+        let order = self.passes[index].order;
+        self.passes.insert(index + 1, RegisteredPass { order, pass: Arc::new(pass) });
+        Ok(())
+    }
+
+    /// Removes the pass named `name`, returning whether it had been registered
+    pub fn unregister(&mut self, name: &str) -> bool {
+        let len = self.passes.len();
+        self.passes.retain(|registered| registered.pass.name() != name);
+        self.passes.len() != len
+    }
+
+    /// Names of the registered passes in the order in which they run
+    pub fn names(&self) -> Vec<String> {
+        self.passes.iter().map(|registered| registered.pass.name().to_string()).collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.passes.is_empty()
+    }
+
+    /// Runs all passes on `program` and updates its cost estimates if there are any
+    pub fn run(&self, program: &mut Program) -> Result<(), CompileError> {
+        if self.passes.is_empty() {
+            return Ok(());
+        }
+        for registered in &self.passes {
+            registered.pass.run(program)?;
+        }
+        let architecture = program.architecture;
+        program.update_cost_estimates(&architecture.cost_model);
+        Ok(())
+    }
+
+    fn check_unregistered(&self, pass: &impl ProgramPass) -> Result<(), &'static str> {
+        match self.position(pass.name()) {
+            Ok(_) => Err("a pass with this name has already been registered"),
+            Err(_) => Ok(()),
+        }
+    }
+
+    fn position(&self, name: &str) -> Result<usize, &'static str> {
+        self.passes
+            .iter()
+            .position(|registered| registered.pass.name() == name)
+            .ok_or("no pass with this name has been registered")
+    }
+}
+
+static REGISTRY: LazyLock<RwLock<PassRegistry>> = LazyLock::new(RwLock::default);
+
+/// Calls `f` with the registry of this process, e.g. for registering passes
+pub fn with_registry<R>(f: impl FnOnce(&mut PassRegistry) -> R) -> R {
+    f(&mut REGISTRY.write().unwrap())
+}
+
+/// Runs the registered passes on a freshly compiled program
+pub(crate) fn run_registered(program: &mut Program) -> Result<(), CompileError> {
+    REGISTRY.read().unwrap().run(program)
+}
+
+/// Symbol of the function registering the passes of a plugin, of type [PluginEntry]
+#[cfg(feature = "plugins")]
+pub const PLUGIN_ENTRY: &[u8] = b"prada_register_passes";
+
+/// Function registering the passes of a plugin. Since it uses the Rust ABI, plugins have to be
+/// built by the same compiler against the same version of this crate.
+#[cfg(feature = "plugins")]
+pub type PluginEntry = unsafe extern "Rust" fn(&mut PassRegistry);
+
+#[cfg(feature = "plugins")]
+static PLUGINS: std::sync::Mutex<Vec<libloading::Library>> = std::sync::Mutex::new(vec!());
+
+/// Loads the dynamic library at `path` and calls its [PLUGIN_ENTRY] with the registry of this
+/// process. The library is kept loaded until the process exits, since its passes remain registered.
+///
+/// # Safety
+/// Loading a library runs its initialization routines, and its entry point must have the type
+/// [PluginEntry].
+#[cfg(feature = "plugins")]
+pub unsafe fn load_plugin(path: &std::path::Path) -> Result<(), libloading::Error> {
+    let library = unsafe { libloading::Library::new(path)? };
+    {
+        let entry = unsafe { library.get::<PluginEntry>(PLUGIN_ENTRY)? };
+        with_registry(|registry| unsafe { entry(registry) });
+    }
+    PLUGINS.lock().unwrap().push(library);
+    Ok(())
+}
@@ -1,25 +1,138 @@
-use crate::prada::architecture::{PRADAArchitecture, RowAddress};
+use crate::prada::architecture::{PRADAArchitecture, RowAddress, SubarrayId};
 
+use super::cost::{CompilingCost, CostModel};
+use super::decisions::Decision;
+use super::diagnostics::{Diagnostic, Location};
+use super::metadata::NodeMetadata;
+use super::metrics::AllocatorMetrics;
+use super::symbols::SymbolTable;
+use super::timing::{output_timing, OutputTiming};
 use super::{BitwiseOperand, BitwiseRow};
+use rustc_hash::FxHashMap;
 use std::fmt::{Display, Formatter};
 
+/// Operands are physical [RowAddress]es by default, but may also be e.g. relative rows of a
+/// [ProgramFragment](super::fragment::ProgramFragment)
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
-pub enum Instruction {
+pub enum Instruction<A = RowAddress> {
     /// Row Copy to a single destination
-    AAPRowCopy(RowAddress, RowAddress),
+    AAPRowCopy(A, A),
     /// TRA on given RowAddresses
-    AAPTRA(RowAddress, RowAddress, RowAddress),
+    AAPTRA(A, A, A),
     /// Negate operand in row
-    N(RowAddress),
+    N(A),
+    /// `MaskedRowCopy(mask, from, to)`: copies `from` into `to`, but only on bitlines on which the
+    /// mask row is set (i.e. `to = (mask & from) | (!mask & to)`)
+    MaskedRowCopy(A, A, A),
+    /// `Xor(a, b, out)`: stores `a ^ b` into `out` without modifying the operands, only available
+    /// on architectures supporting in-DRAM XOR
+    Xor(A, A, A),
+    /// `And(a, b, out)`: stores `a & b` into `out` without modifying the operands, only available
+    /// on architectures supporting in-DRAM AND/OR
+    And(A, A, A),
+    /// `Or(a, b, out)`: stores `a | b` into `out` without modifying the operands, see
+    /// [Instruction::And]
+    Or(A, A, A),
+    /// `ControlTra(a, b, control)`: TRA of `a`, `b` and a preset control row (Ambit's AND resp.
+    /// OR), leaving `a & b` (for [ControlRow::C0]) resp. `a | b` (for [ControlRow::C1]) in `a` and
+    /// `b`. Only available on architectures with control rows.
+    ControlTra(A, A, ControlRow),
+    /// `DccNot(from, to)`: stores `!from` into `to` by copying `from` into a dual-contact cell
+    /// (Ambit's NOT), without modifying `from`
+    DccNot(A, A),
+    /// Shifts the content of the row by the given nr of bitlines towards higher bitline indices
+    /// (towards lower ones for negative offsets), filling in 0s
+    ColumnShift(A, i64),
+    /// Repeat the instructions up to the matching [Instruction::LoopEnd] the given number of times
+    LoopBegin(u64),
+    /// End of the loop body started by the last unmatched [Instruction::LoopBegin]
+    LoopEnd,
 }
 
-impl Instruction {
+/// Preset row of Ambit's bitwise group, holding all 0s (`C0`) resp. all 1s (`C1`), which replaces
+/// a constant row as third TRA operand, see [Instruction::ControlTra]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ControlRow {
+    C0,
+    C1,
+}
+
+impl ControlRow {
+    /// Control row holding `value` on every bitline
+    pub fn holding(value: bool) -> Self {
+        if value { Self::C1 } else { Self::C0 }
+    }
+
+    pub fn value(self) -> bool {
+        self == Self::C1
+    }
+}
+
+impl Display for ControlRow {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "C{}", self.value() as u8)
+    }
+}
+
+impl<A> Instruction<A> {
     pub fn get_latency_in_ns(&self) -> u64 {
-        match self {
-            Instruction::N(_) => 35,
-            _ => todo!(),
+        self.cost().runtime
+    }
+
+    /// Estimated cost of executing this instruction once according to the default [CostModel]
+    pub fn cost(&self) -> CompilingCost {
+        CostModel::default().instruction_cost(self)
+    }
+
+    /// Returns the same instruction, but with every operand replaced by `f(operand)`
+    pub fn map_addresses<B>(&self, mut f: impl FnMut(&A) -> B) -> Instruction<B> {
+        match self.try_map_addresses(|a| Ok::<_, ()>(f(a))) {
+            Ok(instruction) => instruction,
+            Err(()) => unreachable!(),
         }
     }
+
+    /// Same as [Instruction::map_addresses], but stops at the first operand for which `f` fails
+    pub fn try_map_addresses<B, E>(&self, mut f: impl FnMut(&A) -> std::result::Result<B, E>) -> std::result::Result<Instruction<B>, E> {
+        Ok(match self {
+            Instruction::AAPRowCopy(from, to) => Instruction::AAPRowCopy(f(from)?, f(to)?),
+            Instruction::AAPTRA(a, b, c) => Instruction::AAPTRA(f(a)?, f(b)?, f(c)?),
+            Instruction::N(a) => Instruction::N(f(a)?),
+            Instruction::MaskedRowCopy(mask, from, to) => Instruction::MaskedRowCopy(f(mask)?, f(from)?, f(to)?),
+            Instruction::ColumnShift(a, offset) => Instruction::ColumnShift(f(a)?, *offset),
+            Instruction::Xor(a, b, out) => Instruction::Xor(f(a)?, f(b)?, f(out)?),
+            Instruction::And(a, b, out) => Instruction::And(f(a)?, f(b)?, f(out)?),
+            Instruction::Or(a, b, out) => Instruction::Or(f(a)?, f(b)?, f(out)?),
+            Instruction::ControlTra(a, b, control) => Instruction::ControlTra(f(a)?, f(b)?, *control),
+            Instruction::DccNot(from, to) => Instruction::DccNot(f(from)?, f(to)?),
+            Instruction::LoopBegin(count) => Instruction::LoopBegin(*count),
+            Instruction::LoopEnd => Instruction::LoopEnd,
+        })
+    }
+}
+
+/// Estimates the cost of executing the given instructions according to the default [CostModel],
+/// see [CostModel::estimate_instructions]
+pub fn estimate_cost<A>(instructions: &[Instruction<A>]) -> CompilingCost {
+    CostModel::default().estimate_instructions(instructions).total()
+}
+
+/// Content the host has to place into a row before running a program
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum RowInit {
+    /// Input with the given index, stored inverted if `inverted` is set
+    Input { index: u64, inverted: bool },
+    /// Row filled with 0s (`false`) or 1s (`true`)
+    Constant(bool),
+}
+
+/// Regular DRAM access of the host moving a whole row, see [Program::host_transfers]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum HostTransfer {
+    /// The host writes the initial content of the row
+    Write(RowAddress),
+    /// The host reads the content of the row
+    Read(RowAddress),
 }
 
 #[derive(Debug, Clone)]
@@ -28,6 +141,111 @@ pub struct Program<'a> {
     pub instructions: Vec<Instruction>,
     pub runtime_estimate: u64,
     pub energy_consumption_estimate: u64,
+    /// Rows which have to be initialized before running the program
+    pub input_map: Vec<(RowAddress, RowInit)>,
+    /// Row in which the i-th output of the network is placed after running the program. If the
+    /// outputs have been packed (see [CompileOptions::output_base]) these are consecutive rows
+    /// starting at the output base.
+    ///
+    /// [CompileOptions::output_base]: super::compilation::CompileOptions::output_base
+    pub output_map: Vec<RowAddress>,
+    /// Decisions the row allocator had to take when running out of rows
+    pub allocation: AllocationStatistics,
+    /// Rows whose content survives between invocations of the program
+    pub persistent_rows: PersistentRows,
+    /// Decisions the compiler took, only recorded if
+    /// [CompileOptions::log_decisions](super::compilation::CompileOptions::log_decisions) is set.
+    /// Rows refer to the program as compiled, i.e. before any relocation.
+    pub decisions: Vec<Decision>,
+    /// Metrics of the row allocator, only collected if
+    /// [CompileOptions::allocator_metrics](super::compilation::CompileOptions::allocator_metrics)
+    /// is set
+    pub allocator_metrics: Option<AllocatorMetrics>,
+    /// Warnings about the program collected while compiling it
+    pub diagnostics: Vec<Diagnostic>,
+    /// Metadata the frontend attached to the inputs (located by their rows) and outputs of the
+    /// network, see [compile_annotated](super::compile_annotated)
+    pub metadata: Vec<(Location, NodeMetadata)>,
+}
+
+/// Rows whose content must survive between invocations of a program, e.g. accumulators, counters
+/// or the state of a sequential circuit. The host initializes them once before the first
+/// invocation and must neither modify nor reuse them afterwards, programs never use them as
+/// scratch rows.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PersistentRows {
+    /// Persistent row and its initial content, inputs referring to the initial values provided by
+    /// the host
+    pub rows: Vec<(RowAddress, RowInit)>,
+}
+
+impl PersistentRows {
+    pub fn declare(&mut self, row: RowAddress, init: RowInit) {
+        self.rows.push((row, init));
+    }
+
+    pub fn contains(&self, row: RowAddress) -> bool {
+        self.rows.iter().any(|(persistent, _)| *persistent == row)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = RowAddress> + '_ {
+        self.rows.iter().map(|(row, _)| *row)
+    }
+}
+
+/// Counts of the measures the compiler took because subarray 0 ran out of rows, see
+/// [CompileOptions::spill_subarray](super::compilation::CompileOptions::spill_subarray) and
+/// [CompileOptions::rematerialize](super::compilation::CompileOptions::rematerialize)
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct AllocationStatistics {
+    /// Nr of values evacuated into the spill subarray
+    pub spills: u64,
+    /// Nr of values copied back from the spill subarray
+    pub reloads: u64,
+    /// Nr of values dropped to be recomputed instead of being spilled
+    pub rematerializations: u64,
+}
+
+/// Overhead of a program compared to the ideal mapping of its network, in which every MAJ is
+/// computed by a single TRA without any copies (counting unrolled instructions)
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct Efficiency {
+    pub tra_count: u64,
+    /// Nr of MAJs of the compiled network, i.e. the minimal nr of TRAs
+    pub maj_count: u64,
+    /// Nr of (masked) row copies
+    pub copy_count: u64,
+    /// Nr of rows activated by all instructions, i.e. the total nr of their operands
+    pub row_activations: u64,
+}
+
+impl Efficiency {
+    /// TRAs per MAJ of the network (1 for the ideal mapping; XOR instructions may bring it below)
+    pub fn tra_overhead(&self) -> f64 {
+        self.tra_count as f64 / self.maj_count.max(1) as f64
+    }
+
+    /// Row copies per TRA
+    pub fn copy_overhead(&self) -> f64 {
+        self.copy_count as f64 / self.tra_count.max(1) as f64
+    }
+
+    /// MAJs of the network per activated row (1/3 for the ideal mapping)
+    pub fn majs_per_activation(&self) -> f64 {
+        self.maj_count as f64 / self.row_activations.max(1) as f64
+    }
+}
+
+impl Display for Efficiency {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "TRAs: {} for {} MAJs ({:.2}x)", self.tra_count, self.maj_count, self.tra_overhead())?;
+        writeln!(f, "row copies: {} ({:.2} per TRA)", self.copy_count, self.copy_overhead())?;
+        writeln!(f, "row activations: {} ({:.3} MAJs per activation)", self.row_activations, self.majs_per_activation())
+    }
 }
 
 impl<'a> Program<'a> {
@@ -37,7 +255,290 @@ impl<'a> Program<'a> {
             instructions,
             runtime_estimate: 0,
             energy_consumption_estimate: 0,
+            input_map: vec!(),
+            output_map: vec!(),
+            allocation: AllocationStatistics::default(),
+            persistent_rows: PersistentRows::default(),
+            decisions: vec!(),
+            allocator_metrics: None,
+            diagnostics: vec!(),
+            metadata: vec!(),
+        }
+    }
+
+    /// Returns a copy of this program with all rows moved to the same local row address in
+    /// `subarray`. Assumes that the program only uses rows of a single subarray.
+    pub fn relocate(&self, subarray: SubarrayId) -> Self {
+        self.map_rows(|row| row.local_rowaddress_to_subarray_id(subarray))
+    }
+
+    /// Returns a copy of this program with every row replaced by `f(row)`, which has to be
+    /// injective on the rows used by the program
+    pub fn map_rows(&self, f: impl Fn(RowAddress) -> RowAddress) -> Self {
+        Self {
+            architecture: self.architecture,
+            instructions: self
+                .instructions
+                .iter()
+                .map(|instr| instr.map_addresses(|row| f(*row)))
+                .collect(),
+            runtime_estimate: self.runtime_estimate,
+            energy_consumption_estimate: self.energy_consumption_estimate,
+            input_map: self
+                .input_map
+                .iter()
+                .map(|(row, init)| (f(*row), *init))
+                .collect(),
+            output_map: self.output_map.iter().copied().map(&f).collect(),
+            allocation: self.allocation,
+            persistent_rows: PersistentRows {
+                rows: self
+                    .persistent_rows
+                    .rows
+                    .iter()
+                    .map(|(row, init)| (f(*row), *init))
+                    .collect(),
+            },
+            decisions: self.decisions.clone(),
+            allocator_metrics: self.allocator_metrics.clone(),
+            diagnostics: self
+                .diagnostics
+                .iter()
+                .map(|diagnostic| Diagnostic {
+                    location: match diagnostic.location {
+                        Some(Location::Row(row)) => Some(Location::Row(f(row))),
+                        location => location,
+                    },
+                    ..diagnostic.clone()
+                })
+                .collect(),
+            metadata: self
+                .metadata
+                .iter()
+                .map(|(location, metadata)| match location {
+                    Location::Row(row) => (Location::Row(f(*row)), metadata.clone()),
+                    location => (*location, metadata.clone()),
+                })
+                .collect(),
+        }
+    }
+
+    /// Re-estimates [Self::runtime_estimate] and [Self::energy_consumption_estimate] using the
+    /// given cost model and the transfer costs of the architecture's
+    /// [Hierarchy](super::architecture::Hierarchy), which has to be done after modifying the
+    /// instructions
+    pub fn update_cost_estimates(&mut self, model: &CostModel) {
+        let report = model.estimate(self);
+        let transfers = self.architecture.hierarchy.transfer_cost(&self.instructions);
+        self.runtime_estimate = report.runtime + transfers.runtime;
+        self.energy_consumption_estimate = report.energy_consumption + transfers.energy_consumption;
+    }
+
+    /// Rows the host has to write before resp. read after every invocation of the program, i.e. the
+    /// input rows and the (distinct) output rows. Persistent rows are only initialized once and
+    /// hence not included.
+    pub fn host_transfers(&self) -> Vec<HostTransfer> {
+        let mut transfers: Vec<HostTransfer> =
+            self.input_map.iter().map(|(row, _)| HostTransfer::Write(*row)).collect();
+        for row in &self.output_map {
+            if !transfers.contains(&HostTransfer::Read(*row)) {
+                transfers.push(HostTransfer::Read(*row));
+            }
         }
+        transfers
+    }
+
+    /// Label of the value at `location` given by its [Self::metadata], e.g. ` (sum[3])`, or an
+    /// empty string
+    fn label(&self, location: Location) -> String {
+        self.metadata
+            .iter()
+            .find(|(annotated, _)| *annotated == location)
+            .and_then(|(_, metadata)| metadata.label())
+            .map(|label| format!(" ({label})"))
+            .unwrap_or_default()
+    }
+
+    /// Names of the inputs, outputs and the rows holding them, see [SymbolTable]
+    pub fn symbols(&self) -> SymbolTable {
+        SymbolTable::new(self)
+    }
+
+    /// Completion time, deadline and slack of every output, see [output_timing]
+    pub fn timing(&self) -> Vec<OutputTiming> {
+        output_timing(self)
+    }
+
+    /// Describes which rows the host has to initialize before and read after running the program
+    pub fn layout(&self) -> String {
+        let mut out = String::new();
+        for (row, init) in &self.input_map {
+            out += &format!("input {row}: {}{}\n", describe_init(init), self.label(Location::Row(*row)));
+        }
+        for (idx, row) in self.output_map.iter().enumerate() {
+            out += &format!("output {row}: output {idx}{}\n", self.label(Location::Output(idx)));
+        }
+        for (row, init) in &self.persistent_rows.rows {
+            out += &format!("persistent {row}: initially {}\n", describe_init(init));
+        }
+        out
+    }
+
+    /// Compares this program against the ideal mapping of a network with `maj_count` MAJs
+    pub fn efficiency(&self, maj_count: u64) -> Efficiency {
+        let mut efficiency = Efficiency { maj_count, ..Efficiency::default() };
+        for instruction in self.unrolled_instructions() {
+            match instruction {
+                Instruction::AAPTRA(..) | Instruction::ControlTra(..) => efficiency.tra_count += 1,
+                Instruction::AAPRowCopy(..) | Instruction::MaskedRowCopy(..) | Instruction::DccNot(..) => {
+                    efficiency.copy_count += 1
+                }
+                _ => {}
+            }
+            efficiency.row_activations += instruction.used_addresses().count() as u64;
+        }
+        efficiency
+    }
+
+    /// Appends a loop executing `body` `count` times
+    pub fn push_loop(&mut self, count: u64, body: impl IntoIterator<Item = Instruction>) {
+        self.instructions.push(Instruction::LoopBegin(count));
+        self.instructions.extend(body);
+        self.instructions.push(Instruction::LoopEnd);
+    }
+
+    /// Returns the maximal nr of rows holding a value which is still needed at the same time
+    /// (high-water mark), including inputs and outputs. A value occupies its row from being written
+    /// until its last read, outputs until the end of the program.
+    pub fn peak_row_usage(&self) -> usize {
+        let instructions = self.unrolled_instructions();
+        let end = instructions.len() + 1;
+        // (definition, last use) of the value currently stored in each row
+        let mut current: FxHashMap<RowAddress, (usize, usize)> = FxHashMap::default();
+        let mut live_ranges = vec!();
+        for (row, _) in &self.input_map {
+            current.insert(*row, (0, 0));
+        }
+        for row in self.persistent_rows.iter() {
+            current.insert(row, (0, 0));
+        }
+        for (idx, instruction) in instructions.iter().enumerate() {
+            let time = idx + 1;
+            for row in instruction.input_operands() {
+                if let Some(range) = current.get_mut(&row) {
+                    range.1 = time;
+                }
+            }
+            for row in instruction.output_operands() {
+                if let Some(range) = current.insert(row, (time, time)) {
+                    live_ranges.push(range);
+                }
+            }
+        }
+        // persistent rows are read by the next invocation
+        for row in self.output_map.iter().copied().chain(self.persistent_rows.iter()) {
+            if let Some(range) = current.get_mut(&row) {
+                range.1 = end;
+            }
+        }
+        live_ranges.extend(current.into_values());
+
+        // a value occupies its row on [definition, last use), unused values for a single step
+        let mut events: Vec<(usize, isize)> = live_ranges
+            .into_iter()
+            .flat_map(|(definition, last_use)| [(definition, 1), (last_use.max(definition + 1), -1)])
+            .collect();
+        // process frees before allocations happening at the same time
+        events.sort();
+        let (mut live, mut peak) = (0isize, 0isize);
+        for (_, delta) in events {
+            live += delta;
+            peak = peak.max(live);
+        }
+        peak as usize
+    }
+
+    /// Returns the instructions of this program with all loops unrolled, see [unrolled_indices]
+    pub fn unrolled_instructions(&self) -> Vec<Instruction> {
+        unrolled_indices(&self.instructions).map(|index| self.instructions[index]).collect()
+    }
+}
+
+/// Indices into `instructions` of the instructions in the order they are executed, i.e. with loops
+/// unrolled: the body of `LoopBegin(count)` runs `count` times, hence it's skipped for a count of
+/// 0. Unbalanced loops aren't rejected (see [check_loops]), a `LoopEnd` without `LoopBegin` is
+/// skipped and a `LoopBegin` without `LoopEnd` runs its body once.
+pub fn unrolled_indices(instructions: &[Instruction]) -> UnrolledIndices<'_> {
+    UnrolledIndices { instructions, loops: vec!(), index: 0 }
+}
+
+/// Fails if the `LoopBegin`s and `LoopEnd`s of `instructions` aren't balanced
+pub fn check_loops(instructions: &[Instruction]) -> Result<(), &'static str> {
+    let mut depth = 0usize;
+    for instruction in instructions {
+        match instruction {
+            Instruction::LoopBegin(_) => depth += 1,
+            Instruction::LoopEnd => depth = depth.checked_sub(1).ok_or("`LoopEnd` without `LoopBegin`")?,
+            _ => (),
+        }
+    }
+    match depth {
+        0 => Ok(()),
+        _ => Err("`LoopBegin` without `LoopEnd`"),
+    }
+}
+
+/// Iterator returned by [unrolled_indices]
+#[derive(Debug, Clone)]
+pub struct UnrolledIndices<'a> {
+    instructions: &'a [Instruction],
+    /// Start of the body and remaining iterations of every open loop
+    loops: Vec<(usize, u64)>,
+    index: usize,
+}
+
+impl Iterator for UnrolledIndices<'_> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        while let Some(&instruction) = self.instructions.get(self.index) {
+            let index = self.index;
+            self.index += 1;
+            match instruction {
+                Instruction::LoopBegin(0) => {
+                    // skip the body, including nested loops, and its `LoopEnd`
+                    let mut depth = 1;
+                    while depth > 0 && self.index < self.instructions.len() {
+                        match self.instructions[self.index] {
+                            Instruction::LoopBegin(_) => depth += 1,
+                            Instruction::LoopEnd => depth -= 1,
+                            _ => (),
+                        }
+                        self.index += 1;
+                    }
+                }
+                Instruction::LoopBegin(count) => self.loops.push((self.index, count)),
+                Instruction::LoopEnd => match self.loops.last_mut() {
+                    Some((start, remaining)) if *remaining > 1 => {
+                        *remaining -= 1;
+                        self.index = *start;
+                    }
+                    _ => {
+                        self.loops.pop();
+                    }
+                },
+                _ => return Some(index),
+            }
+        }
+        None
+    }
+}
+
+pub(crate) fn describe_init(init: &RowInit) -> String {
+    match init {
+        RowInit::Input { index, inverted: false } => format!("input {index}"),
+        RowInit::Input { index, inverted: true } => format!("inverted input {index}"),
+        RowInit::Constant(value) => format!("constant {}", *value as u8),
     }
 }
 
@@ -46,9 +547,17 @@ impl Instruction {
         &self,
     ) -> impl Iterator<Item = RowAddress> + 'a {
         match self {
-            Instruction::AAPRowCopy(from, _) => vec!(*from).into_iter(),
+            Instruction::AAPRowCopy(from, to) => vec!(*from, *to).into_iter(),
             Instruction::AAPTRA(a, b, c ) => vec!(*a,*b,*c).into_iter(),
             Instruction::N(a) => vec!(*a).into_iter(),
+            Instruction::MaskedRowCopy(mask, from, to) => vec!(*mask, *from, *to).into_iter(),
+            Instruction::ColumnShift(a, _) => vec!(*a).into_iter(),
+            Instruction::Xor(a, b, out) | Instruction::And(a, b, out) | Instruction::Or(a, b, out) => {
+                vec!(*a, *b, *out).into_iter()
+            }
+            Instruction::ControlTra(a, b, _) => vec!(*a, *b).into_iter(),
+            Instruction::DccNot(from, to) => vec!(*from, *to).into_iter(),
+            Instruction::LoopBegin(_) | Instruction::LoopEnd => vec!().into_iter(),
         }
     }
 
@@ -59,6 +568,36 @@ impl Instruction {
             Instruction::AAPRowCopy(from, _) => vec!(*from).into_iter(),
             Instruction::AAPTRA(a, b, c ) => vec!(*a,*b,*c).into_iter(),
             Instruction::N(a) => vec!(*a).into_iter(),
+            // the previous content of `to` is kept on bitlines where the mask isn't set
+            Instruction::MaskedRowCopy(mask, from, to) => vec!(*mask, *from, *to).into_iter(),
+            Instruction::ColumnShift(a, _) => vec!(*a).into_iter(),
+            Instruction::Xor(a, b, _) | Instruction::And(a, b, _) | Instruction::Or(a, b, _) => {
+                vec!(*a, *b).into_iter()
+            }
+            Instruction::ControlTra(a, b, _) => vec!(*a, *b).into_iter(),
+            Instruction::DccNot(from, _) => vec!(*from).into_iter(),
+            Instruction::LoopBegin(_) | Instruction::LoopEnd => vec!().into_iter(),
+        }
+    }
+}
+
+impl<A: Copy> Instruction<A> {
+    /// Rows written by this instruction
+    pub fn output_operands<'a>(
+        &self,
+    ) -> impl Iterator<Item = A> + 'a where A: 'a {
+        match self {
+            Instruction::AAPRowCopy(_, to) => vec!(*to).into_iter(),
+            Instruction::AAPTRA(a, b, c ) => vec!(*a,*b,*c).into_iter(),
+            Instruction::N(a) => vec!(*a).into_iter(),
+            Instruction::MaskedRowCopy(_, _, to) => vec!(*to).into_iter(),
+            Instruction::ColumnShift(a, _) => vec!(*a).into_iter(),
+            Instruction::Xor(_, _, out) | Instruction::And(_, _, out) | Instruction::Or(_, _, out) => {
+                vec!(*out).into_iter()
+            }
+            Instruction::ControlTra(a, b, _) => vec!(*a, *b).into_iter(),
+            Instruction::DccNot(_, to) => vec!(*to).into_iter(),
+            Instruction::LoopBegin(_) | Instruction::LoopEnd => vec!().into_iter(),
         }
     }
 }
@@ -72,24 +611,64 @@ impl From<BitwiseRow> for BitwiseOperand {
 }
 
 impl Display for Program<'_> {
+    /// The alternate form (`{:#}`) lists the names of the operands (see [Program::symbols]) in a
+    /// comment after every instruction
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let symbols = f.alternate().then(|| self.symbols());
+        let mut depth = 0;
         for instruction in &self.instructions {
+            if *instruction == Instruction::LoopEnd {
+                depth = usize::saturating_sub(depth, 1);
+            }
+            write!(f, "{}", "  ".repeat(depth))?;
             match instruction {
                 Instruction::AAPRowCopy(a, b) => {
                     write!(f, "AAPRowCopy {a}")?;
                     write!(f, " {b}")?;
-                    writeln!(f)?;
                 }
                 Instruction::AAPTRA(a, b, c) => {
                     write!(f, "AAPTRA {a}")?;
                     write!(f, " {b} {c}")?;
-                    writeln!(f)?;
                 },
                 Instruction::N(a) => {
                     write!(f, "N {a}")?;
-                    writeln!(f)?;
                 },
+                Instruction::MaskedRowCopy(mask, from, to) => {
+                    write!(f, "MaskedRowCopy {mask} {from} {to}")?;
+                },
+                Instruction::ColumnShift(a, offset) => {
+                    write!(f, "ColumnShift {a} {offset}")?;
+                },
+                Instruction::Xor(a, b, out) => {
+                    write!(f, "Xor {a} {b} {out}")?;
+                },
+                Instruction::And(a, b, out) => {
+                    write!(f, "And {a} {b} {out}")?;
+                },
+                Instruction::Or(a, b, out) => {
+                    write!(f, "Or {a} {b} {out}")?;
+                },
+                Instruction::ControlTra(a, b, control) => {
+                    write!(f, "ControlTra {a} {b} {control}")?;
+                },
+                Instruction::DccNot(from, to) => {
+                    write!(f, "DccNot {from} {to}")?;
+                },
+                Instruction::LoopBegin(count) => {
+                    write!(f, "LoopBegin {count}")?;
+                    depth += 1;
+                },
+                Instruction::LoopEnd => {
+                    write!(f, "LoopEnd")?;
+                },
+            }
+            if let Some(symbols) = &symbols {
+                let names: Vec<&str> = instruction.used_addresses().filter_map(|row| symbols.row(row)).collect();
+                if !names.is_empty() {
+                    write!(f, " ; {}", names.join(", "))?;
+                }
             }
+            writeln!(f)?;
         }
         Ok(())
     }
@@ -0,0 +1,65 @@
+//! Deterministic pseudo-random numbers of the stochastic components (e.g. the input vectors of
+//! [differential tests](super::reference::differential_test_seeded), of the
+//! [approximation](super::approximate) and of the [activity search](super::activity) or
+//! [random networks](super::stdlib::random_network)), so that experiments are repeatable
+//! bit-for-bit. All of them can be seeded from the single [CompilerSettings::seed], from which
+//! every component derives its own [Stream].
+use super::CompilerSettings;
+
+/// xorshift64, which is cheap and good enough for sampling input vectors
+#[derive(Debug, Clone)]
+pub struct Xorshift(u64);
+
+impl Xorshift {
+    /// xorshift must not be seeded with 0, which is hence replaced by a fixed nonzero seed
+    pub fn new(seed: u64) -> Self {
+        Self(if seed == 0 { 0x9e37_79b9_7f4a_7c15 } else { seed })
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+}
+
+/// splitmix64, which (unlike [Xorshift]) has no degenerate seed, so that every seed counts
+#[derive(Debug, Clone)]
+pub struct SplitMix(u64);
+
+impl SplitMix {
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+/// Stochastic component drawing numbers derived from [CompilerSettings::seed]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Stream {
+    Verification,
+    Approximation,
+    Activity,
+    Networks,
+}
+
+/// Seed of the `stream` of the experiment seeded with `seed`, so that components seeded from the
+/// same settings don't draw the same numbers
+pub fn derive(seed: u64, stream: Stream) -> u64 {
+    SplitMix::new(seed.wrapping_add((stream as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15))).next_u64()
+}
+
+impl CompilerSettings {
+    /// Seed of `stream`, see [derive]
+    pub fn stream_seed(&self, stream: Stream) -> u64 {
+        derive(self.seed, stream)
+    }
+}
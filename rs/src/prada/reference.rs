@@ -0,0 +1,150 @@
+//! Deliberately naive reference backend and differential testing of optimized programs against it.
+//!
+//! The reference compiler neither shares compute rows nor reuses rows: every node gets rows of its
+//! own which are never overwritten, operands are copied before every TRA and an unlimited number of
+//! (virtual) rows is assumed. The resulting programs ignore the row limits of the architecture and
+//! are only meant to be simulated, serving as an obviously correct oracle.
+use super::architecture::{PRADAArchitecture, RowAddress};
+use super::program::{Instruction, Program, RowInit};
+use super::random::{Stream, Xorshift};
+use super::simulation::simulate;
+use super::CompilerSettings;
+use eggmock::{Id, Mig, Network, Signal};
+use rustc_hash::FxHashMap;
+
+struct ReferenceCompiler<'a> {
+    program: Program<'a>,
+    /// Row storing the (non-inverted) value of every computed node
+    rows: FxHashMap<Id, RowAddress>,
+    next_row: u64,
+}
+
+impl<'a> ReferenceCompiler<'a> {
+    fn fresh_row(&mut self) -> RowAddress {
+        self.next_row += 1;
+        RowAddress(self.next_row - 1)
+    }
+
+    /// Copies the value of `signal` into a fresh row, negating it if `signal` is inverted
+    fn copy(&mut self, signal: Signal) -> RowAddress {
+        let row = self.fresh_row();
+        self.program.instructions.push(Instruction::AAPRowCopy(self.rows[&signal.node_id()], row));
+        if signal.is_inverted() {
+            self.program.instructions.push(Instruction::N(row));
+        }
+        row
+    }
+
+    fn compute(&mut self, network: &impl Network<Node = Mig>, id: Id) {
+        let row = match network.node(id) {
+            Mig::False => {
+                let row = self.fresh_row();
+                self.program.input_map.push((row, RowInit::Constant(false)));
+                row
+            }
+            Mig::Input(index) => {
+                let row = self.fresh_row();
+                self.program.input_map.push((row, RowInit::Input { index, inverted: false }));
+                row
+            }
+            Mig::Maj(inputs) => {
+                let [a, b, c] = inputs.map(|input| self.copy(input));
+                self.program.instructions.push(Instruction::AAPTRA(a, b, c));
+                a
+            }
+        };
+        self.rows.insert(id, row);
+    }
+}
+
+/// Compiles `network` without any optimization, see the module documentation
+pub fn compile_reference<'a>(architecture: &'a PRADAArchitecture, network: &impl Network<Node = Mig>) -> Program<'a> {
+    let mut compiler = ReferenceCompiler { program: Program::new(architecture, vec!()), rows: FxHashMap::default(), next_row: 0 };
+    for output in network.outputs() {
+        // compute the nodes in post-order, iteratively to avoid overflowing the stack on deep networks
+        let mut stack = vec!(output.node_id());
+        while let Some(&id) = stack.last() {
+            if compiler.rows.contains_key(&id) {
+                stack.pop();
+                continue;
+            }
+            let missing: Vec<Id> = network
+                .node(id)
+                .inputs()
+                .iter()
+                .map(|input| input.node_id())
+                .filter(|input| !compiler.rows.contains_key(input))
+                .collect();
+            if missing.is_empty() {
+                compiler.compute(network, id);
+                stack.pop();
+            } else {
+                stack.extend(missing);
+            }
+        }
+        let row = compiler.copy(output);
+        compiler.program.output_map.push(row);
+    }
+    compiler.program
+}
+
+/// Output on which an optimized program and the reference program disagree
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mismatch {
+    /// Input values (one `u64` per input, bit `j` belonging to the `j`-th input vector)
+    pub inputs: Vec<u64>,
+    pub output: usize,
+    pub expected: u64,
+    pub actual: u64,
+}
+
+impl Mismatch {
+    /// Index of the first input vector (bitline) on which the outputs differ
+    pub fn bitline(&self) -> u32 {
+        (self.expected ^ self.actual).trailing_zeros()
+    }
+}
+
+/// Simulates `program` and the reference program of `network` on `rounds` times 64 pseudo-random
+/// input vectors derived from `seed`, returning the first mismatch found
+pub fn differential_test(
+    network: &impl Network<Node = Mig>,
+    program: &Program,
+    rounds: usize,
+    seed: u64,
+) -> Result<Option<Mismatch>, &'static str> {
+    let reference = compile_reference(program.architecture, network);
+    let nr_inputs = reference
+        .input_map
+        .iter()
+        .filter_map(|(_, init)| match init {
+            RowInit::Input { index, .. } => Some(index + 1),
+            RowInit::Constant(_) => None,
+        })
+        .max()
+        .unwrap_or(0);
+    let mut random = Xorshift::new(seed);
+    for _ in 0..rounds {
+        let inputs: Vec<u64> = (0..nr_inputs).map(|_| random.next_u64()).collect();
+        let expected = simulate(&reference, &inputs)?;
+        let actual = simulate(program, &inputs)?;
+        if actual.len() != expected.len() {
+            return Err("program and network have a different nr of outputs");
+        }
+        if let Some(output) = (0..expected.len()).find(|&output| actual[output] != expected[output]) {
+            return Ok(Some(Mismatch { inputs, output, expected: expected[output], actual: actual[output] }));
+        }
+    }
+    Ok(None)
+}
+
+/// Same as [differential_test], but derives the input vectors from the
+/// [seed](CompilerSettings::seed) of `settings`
+pub fn differential_test_seeded(
+    network: &impl Network<Node = Mig>,
+    program: &Program,
+    rounds: usize,
+    settings: &CompilerSettings,
+) -> Result<Option<Mismatch>, &'static str> {
+    differential_test(network, program, rounds, settings.stream_seed(Stream::Verification))
+}
@@ -0,0 +1,312 @@
+//! Rendering of compilation results and design space exploration (DSE) sweeps into self-contained
+//! Markdown or HTML reports, so that results can be shared without external plotting scripts.
+use std::fmt::Write;
+
+use super::architecture::PRADAArchitecture;
+use super::error::CompileError;
+use super::program::Program;
+use super::{compile, compile_memoized, CompilerSettings, CostMemo};
+use eggmock::{Mig, Network};
+
+/// Summary of a single compiled program
+#[derive(Debug, Clone)]
+pub struct ProgramSummary {
+    pub name: String,
+    pub instruction_count: u64,
+    pub runtime_estimate: u64,
+    pub energy_consumption_estimate: u64,
+}
+
+impl ProgramSummary {
+    pub fn new(name: impl Into<String>, program: &Program) -> Self {
+        Self {
+            name: name.into(),
+            instruction_count: program.instructions.len() as u64,
+            runtime_estimate: program.runtime_estimate,
+            energy_consumption_estimate: program.energy_consumption_estimate,
+        }
+    }
+}
+
+/// Results of compiling the same network for a range of values of one architecture parameter
+#[derive(Debug, Clone)]
+pub struct Sweep {
+    pub parameter: String,
+    /// (parameter value, summary of the program compiled for it)
+    pub points: Vec<(u64, ProgramSummary)>,
+    /// (parameter value, reason) of the values the network couldn't be compiled for
+    pub failures: Vec<(u64, CompileError)>,
+}
+
+impl Sweep {
+    /// Compiles `network` once for every value, using the architecture returned by `architecture`
+    /// for that value. Points sharing the [CostFeatures](super::CostFeatures) of an earlier point
+    /// reuse its extraction choices, see [CostMemo]. Values the network can't be compiled for are
+    /// recorded as failures instead of aborting the sweep.
+    pub fn run(
+        parameter: impl Into<String>,
+        values: impl IntoIterator<Item = u64>,
+        network: &impl Network<Node = Mig>,
+        settings: CompilerSettings,
+        architecture: impl Fn(u64) -> PRADAArchitecture,
+    ) -> Self {
+        let parameter = parameter.into();
+        let memo = CostMemo::new();
+        let (mut points, mut failures) = (vec!(), vec!());
+        for value in values {
+            let architecture = architecture(value);
+            match compile_memoized(&architecture, network, settings, &memo) {
+                Ok(program) => points.push((value, ProgramSummary::new(format!("{parameter}={value}"), &program))),
+                Err(err) => failures.push((value, err)),
+            }
+        }
+        Self { parameter, points, failures }
+    }
+}
+
+/// Compiles `network` once single-rail (inverting rows using N) and once dual-rail (computing
+/// complemented signals by dual MAJs), see [CompilerSettings::dual_rail]
+pub fn compare_dual_rail(
+    architecture: &PRADAArchitecture,
+    network: &impl Network<Node = Mig>,
+    settings: CompilerSettings,
+) -> Result<[ProgramSummary; 2], CompileError> {
+    let summary = |name, dual_rail| {
+        let settings = CompilerSettings { dual_rail, ..settings };
+        Ok(ProgramSummary::new(name, &compile(architecture, network, settings)?))
+    };
+    Ok([summary("single-rail", false)?, summary("dual-rail", true)?])
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Report {
+    pub title: String,
+    pub programs: Vec<ProgramSummary>,
+    pub sweeps: Vec<Sweep>,
+}
+
+impl Report {
+    pub fn new(title: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            ..Self::default()
+        }
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::new();
+        writeln!(out, "# {}\n", self.title).unwrap();
+        if !self.programs.is_empty() {
+            writeln!(out, "## Programs\n").unwrap();
+            write_markdown_table(&mut out, "Program", self.programs.iter().map(|p| (p.name.clone(), p)));
+        }
+        for sweep in &self.sweeps {
+            writeln!(out, "## Sweep over `{}`\n", sweep.parameter).unwrap();
+            write_markdown_table(
+                &mut out,
+                &sweep.parameter,
+                sweep.points.iter().map(|(value, p)| (value.to_string(), p)),
+            );
+            for (value, err) in &sweep.failures {
+                writeln!(out, "- {} = {value} failed: {}", sweep.parameter, escape_markdown(&err.to_string())).unwrap();
+            }
+            if !sweep.failures.is_empty() {
+                writeln!(out).unwrap();
+            }
+        }
+        out
+    }
+
+    /// Renders the report as a single HTML document, including an SVG chart of runtime and energy
+    /// over the parameter for every sweep
+    pub fn to_html(&self) -> String {
+        let mut out = String::new();
+        writeln!(out, "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">").unwrap();
+        writeln!(out, "<title>{}</title>", escape(&self.title)).unwrap();
+        writeln!(
+            out,
+            "<style>body {{ font-family: sans-serif; }} table {{ border-collapse: collapse; }} \
+             td, th {{ border: 1px solid #999; padding: 2px 8px; text-align: right; }}</style>"
+        )
+        .unwrap();
+        writeln!(out, "</head>\n<body>\n<h1>{}</h1>", escape(&self.title)).unwrap();
+        if !self.programs.is_empty() {
+            writeln!(out, "<h2>Programs</h2>").unwrap();
+            write_html_table(&mut out, "Program", self.programs.iter().map(|p| (p.name.clone(), p)));
+        }
+        for sweep in &self.sweeps {
+            writeln!(out, "<h2>Sweep over <code>{}</code></h2>", escape(&sweep.parameter)).unwrap();
+            write_html_table(
+                &mut out,
+                &sweep.parameter,
+                sweep.points.iter().map(|(value, p)| (value.to_string(), p)),
+            );
+            if !sweep.failures.is_empty() {
+                writeln!(out, "<ul>").unwrap();
+                for (value, err) in &sweep.failures {
+                    let parameter = escape(&sweep.parameter);
+                    writeln!(out, "<li>{parameter} = {value} failed: {}</li>", escape(&err.to_string())).unwrap();
+                }
+                writeln!(out, "</ul>").unwrap();
+            }
+            let values = |metric: fn(&ProgramSummary) -> u64| -> Vec<(u64, u64)> {
+                sweep.points.iter().map(|(value, p)| (*value, metric(p))).collect()
+            };
+            out.push_str(&svg_chart(&sweep.parameter, "runtime [ns]", &values(|p| p.runtime_estimate)));
+            out.push_str(&svg_chart(
+                &sweep.parameter,
+                "energy [mJ/KOps]",
+                &values(|p| p.energy_consumption_estimate),
+            ));
+        }
+        writeln!(out, "</body>\n</html>").unwrap();
+        out
+    }
+}
+
+const COLUMNS: [&str; 3] = ["Instructions", "Runtime [ns]", "Energy [mJ/KOps]"];
+
+fn columns(summary: &ProgramSummary) -> [u64; 3] {
+    [
+        summary.instruction_count,
+        summary.runtime_estimate,
+        summary.energy_consumption_estimate,
+    ]
+}
+
+fn write_markdown_table<'s>(
+    out: &mut String,
+    first_column: &str,
+    rows: impl Iterator<Item = (String, &'s ProgramSummary)>,
+) {
+    writeln!(out, "| {} | {} |", escape_markdown(first_column), COLUMNS.join(" | ")).unwrap();
+    writeln!(out, "|---|{}", "---:|".repeat(COLUMNS.len())).unwrap();
+    for (label, summary) in rows {
+        let [instructions, runtime, energy] = columns(summary);
+        writeln!(out, "| {} | {instructions} | {runtime} | {energy} |", escape_markdown(&label)).unwrap();
+    }
+    writeln!(out).unwrap();
+}
+
+fn write_html_table<'s>(
+    out: &mut String,
+    first_column: &str,
+    rows: impl Iterator<Item = (String, &'s ProgramSummary)>,
+) {
+    write!(out, "<table>\n<tr><th>{}</th>", escape(first_column)).unwrap();
+    for column in COLUMNS {
+        write!(out, "<th>{}</th>", escape(column)).unwrap();
+    }
+    writeln!(out, "</tr>").unwrap();
+    for (label, summary) in rows {
+        write!(out, "<tr><td>{}</td>", escape(&label)).unwrap();
+        for value in columns(summary) {
+            write!(out, "<td>{value}</td>").unwrap();
+        }
+        writeln!(out, "</tr>").unwrap();
+    }
+    writeln!(out, "</table>").unwrap();
+}
+
+/// Renders a line chart of the given (x, y) points, both axes starting at their minimum value
+fn svg_chart(x_label: &str, y_label: &str, points: &[(u64, u64)]) -> String {
+    const WIDTH: f64 = 480.0;
+    const HEIGHT: f64 = 240.0;
+    const MARGIN: f64 = 50.0;
+    let mut out = String::new();
+    writeln!(
+        out,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{WIDTH}\" height=\"{HEIGHT}\">"
+    )
+    .unwrap();
+    if let (Some(x_range), Some(y_range)) = (
+        range(points.iter().map(|(x, _)| *x)),
+        range(points.iter().map(|(_, y)| *y)),
+    ) {
+        let scale = |value: u64, (min, max): (u64, u64), length: f64| {
+            if max == min {
+                length / 2.0
+            } else {
+                (value - min) as f64 / (max - min) as f64 * length
+            }
+        };
+        let (plot_width, plot_height) = (WIDTH - 2.0 * MARGIN, HEIGHT - 2.0 * MARGIN);
+        let coordinates: Vec<(f64, f64)> = points
+            .iter()
+            .map(|(x, y)| {
+                (
+                    MARGIN + scale(*x, x_range, plot_width),
+                    HEIGHT - MARGIN - scale(*y, y_range, plot_height),
+                )
+            })
+            .collect();
+        let polyline: Vec<String> = coordinates.iter().map(|(x, y)| format!("{x:.1},{y:.1}")).collect();
+        writeln!(
+            out,
+            "<polyline fill=\"none\" stroke=\"steelblue\" stroke-width=\"2\" points=\"{}\"/>",
+            polyline.join(" ")
+        )
+        .unwrap();
+        for (x, y) in &coordinates {
+            writeln!(out, "<circle cx=\"{x:.1}\" cy=\"{y:.1}\" r=\"3\" fill=\"steelblue\"/>").unwrap();
+        }
+        // axes with their minimum and maximum values
+        writeln!(
+            out,
+            "<path d=\"M{MARGIN},{MARGIN} V{} H{}\" fill=\"none\" stroke=\"black\"/>",
+            HEIGHT - MARGIN,
+            WIDTH - MARGIN
+        )
+        .unwrap();
+        for (value, x) in [(x_range.0, MARGIN), (x_range.1, WIDTH - MARGIN)] {
+            writeln!(
+                out,
+                "<text x=\"{x}\" y=\"{}\" font-size=\"10\" text-anchor=\"middle\">{value}</text>",
+                HEIGHT - MARGIN + 12.0
+            )
+            .unwrap();
+        }
+        for (value, y) in [(y_range.0, HEIGHT - MARGIN), (y_range.1, MARGIN)] {
+            writeln!(
+                out,
+                "<text x=\"{}\" y=\"{y}\" font-size=\"10\" text-anchor=\"end\">{value}</text>",
+                MARGIN - 4.0
+            )
+            .unwrap();
+        }
+    }
+    writeln!(
+        out,
+        "<text x=\"{}\" y=\"{}\" font-size=\"12\" text-anchor=\"middle\">{}</text>",
+        WIDTH / 2.0,
+        HEIGHT - 10.0,
+        escape(x_label)
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "<text x=\"12\" y=\"{}\" font-size=\"12\" text-anchor=\"middle\" transform=\"rotate(-90 12 {})\">{}</text>",
+        HEIGHT / 2.0,
+        HEIGHT / 2.0,
+        escape(y_label)
+    )
+    .unwrap();
+    writeln!(out, "</svg>").unwrap();
+    out
+}
+
+fn range(values: impl Iterator<Item = u64> + Clone) -> Option<(u64, u64)> {
+    Some((values.clone().min()?, values.max()?))
+}
+
+/// Escapes the column separators of Markdown tables
+fn escape_markdown(text: &str) -> String {
+    text.replace('|', "\\|")
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,197 @@
+//! Row-hammer safety of programs: every activation of a row disturbs the cells of its neighboring
+//! rows, which may flip once the neighbors of a row have been activated too often before the row
+//! is refreshed. PUD programs activate the same few compute rows over and over, so this counts the
+//! activations of the neighbors of every row holding data since it has last been refreshed, which
+//! happens periodically every refresh window and whenever the row itself is activated (e.g. by a
+//! dummy refresh inserted by [insert_refreshes]).
+//!
+//! Time is estimated using the architecture's [CostModel], loops are unrolled.
+use std::collections::VecDeque;
+
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use super::architecture::{RowAddress, ROW_ID_BITMASK};
+use super::cost::CostModel;
+use super::program::{Instruction, Program};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RowHammerConfig {
+    /// Maximal nr of activations of the neighbors of a row before it has to be refreshed
+    pub threshold: u64,
+    /// Interval in which every row is refreshed (in ns)
+    pub refresh_window: u64,
+}
+
+impl Default for RowHammerConfig {
+    fn default() -> Self {
+        // maximum activation count and refresh window of DDR4
+        Self { threshold: 4800, refresh_window: 64_000_000 }
+    }
+}
+
+/// A row whose neighbors have been activated more often than the threshold since its last refresh
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Violation {
+    pub row: RowAddress,
+    /// Index of the refresh window, counted from the start of the program
+    pub window: u64,
+    /// Index of the (unrolled) instruction exceeding the threshold
+    pub instruction: usize,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RowHammerReport {
+    /// Nr of activations of every row in the refresh window in which it has been activated most
+    pub activations: FxHashMap<RowAddress, u64>,
+    /// Most activations of the neighbors of every row holding data between two of its refreshes
+    pub max_disturbance: FxHashMap<RowAddress, u64>,
+    /// First violation of every row between two of its refreshes
+    pub violations: Vec<Violation>,
+}
+
+impl RowHammerReport {
+    pub fn is_safe(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+/// Counts the activations of the rows of `program` and flags rows exceeding the threshold
+pub fn analyze(program: &Program, config: &RowHammerConfig) -> RowHammerReport {
+    let mut report = RowHammerReport::default();
+    let mut counter = DisturbanceCounter::new(program, &program.architecture.cost_model, config);
+    let mut activations: FxHashMap<RowAddress, u64> = FxHashMap::default();
+    // rows which have exceeded the threshold since their last refresh
+    let mut flagged: FxHashSet<RowAddress> = FxHashSet::default();
+    let mut window = 0;
+    for (index, instruction) in program.unrolled_instructions().iter().enumerate() {
+        let disturbed = counter.execute(instruction);
+        if counter.window() != window {
+            window = counter.window();
+            activations.clear();
+            flagged.clear();
+        }
+        let activated: FxHashSet<RowAddress> = instruction.used_addresses().collect();
+        for row in activated {
+            flagged.remove(&row);
+            let count = activations.entry(row).or_default();
+            *count += 1;
+            let maximum = report.activations.entry(row).or_default();
+            *maximum = (*maximum).max(*count);
+        }
+        for (row, disturbance) in disturbed {
+            let maximum = report.max_disturbance.entry(row).or_default();
+            *maximum = (*maximum).max(disturbance);
+            if disturbance > config.threshold && flagged.insert(row) {
+                report.violations.push(Violation { row, window, instruction: index });
+            }
+        }
+    }
+    report
+}
+
+/// Unrolls the loops of `program` and inserts a dummy refresh (a copy of the row onto itself) of
+/// every row holding data shortly before its disturbance reaches the threshold, keeping a margin
+/// for the activations of the refreshes themselves. Returns the nr of inserted refreshes.
+pub fn insert_refreshes(program: &mut Program, config: &RowHammerConfig) -> Result<usize, &'static str> {
+    // the refreshes disturb their neighbors, which mustn't require refreshes in turn
+    if config.threshold < 4 {
+        return Err("row-hammer threshold is too low for dummy refreshes");
+    }
+    let architecture = program.architecture;
+    let mut counter = DisturbanceCounter::new(program, &architecture.cost_model, config);
+    let mut pending: VecDeque<Instruction> = program.unrolled_instructions().into();
+    let mut instructions = vec!();
+    let mut refreshes = 0;
+    while let Some(instruction) = pending.pop_front() {
+        let mut exposed: Vec<RowAddress> = counter
+            .execute(&instruction)
+            .into_iter()
+            .filter(|(_, disturbance)| disturbance + 2 >= config.threshold)
+            .map(|(row, _)| row)
+            .collect();
+        // refreshed in ascending order, so that every row is disturbed by at most one of them
+        exposed.sort_by_key(|row| std::cmp::Reverse(row.0));
+        for row in exposed {
+            pending.push_front(Instruction::AAPRowCopy(row, row));
+            refreshes += 1;
+        }
+        instructions.push(instruction);
+    }
+    program.instructions = instructions;
+    program.update_cost_estimates(&architecture.cost_model);
+    Ok(refreshes)
+}
+
+/// Activations of the neighbors of every row holding data since the row has last been refreshed
+struct DisturbanceCounter<'c> {
+    cost_model: &'c CostModel,
+    refresh_window: u64,
+    rows_per_subarray: u64,
+    holding_data: FxHashSet<RowAddress>,
+    disturbance: FxHashMap<RowAddress, u64>,
+    /// Estimated time at which the next instruction starts (in ns)
+    time: u64,
+}
+
+impl<'c> DisturbanceCounter<'c> {
+    fn new(program: &Program, cost_model: &'c CostModel, config: &RowHammerConfig) -> Self {
+        Self {
+            cost_model,
+            refresh_window: config.refresh_window.max(1),
+            rows_per_subarray: program.architecture.rows_per_subarray,
+            holding_data: program
+                .input_map
+                .iter()
+                .chain(&program.persistent_rows.rows)
+                .map(|(row, _)| *row)
+                .collect(),
+            disturbance: FxHashMap::default(),
+            time: 0,
+        }
+    }
+
+    /// Index of the current refresh window
+    fn window(&self) -> u64 {
+        self.time / self.refresh_window
+    }
+
+    /// Executes `instruction`, returning the disturbed rows with their updated disturbance
+    fn execute(&mut self, instruction: &Instruction) -> Vec<(RowAddress, u64)> {
+        let window = self.window();
+        self.time += self.cost_model.instruction_cost(instruction).runtime;
+        if self.window() != window {
+            // the periodic refresh has refreshed all rows
+            self.disturbance.clear();
+        }
+        self.holding_data.extend(instruction.output_operands());
+
+        let mut activated: Vec<RowAddress> = instruction.used_addresses().collect();
+        activated.sort_by_key(|row| row.0);
+        activated.dedup();
+        for row in &activated {
+            self.disturbance.remove(row);
+        }
+        let mut disturbed = vec!();
+        for row in &activated {
+            for neighbor in self.neighbors(*row) {
+                if self.holding_data.contains(&neighbor) && !activated.contains(&neighbor) {
+                    *self.disturbance.entry(neighbor).or_default() += 1;
+                    disturbed.push(neighbor);
+                }
+            }
+        }
+        disturbed.sort_by_key(|row| row.0);
+        disturbed.dedup();
+        disturbed.into_iter().map(|row| (row, self.disturbance[&row])).collect()
+    }
+
+    /// The rows next to `row` in its subarray
+    fn neighbors(&self, row: RowAddress) -> impl Iterator<Item = RowAddress> {
+        let local = row.0 & ROW_ID_BITMASK;
+        let base = row.0 - local;
+        [local.checked_sub(1), Some(local + 1).filter(|next| *next < self.rows_per_subarray)]
+            .into_iter()
+            .flatten()
+            .map(move |local| RowAddress(base + local))
+    }
+}
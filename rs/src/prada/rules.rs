@@ -0,0 +1,111 @@
+//! Rewrite rules used for exploring equivalent networks in the e-graph
+use std::fmt::Write;
+use std::sync::LazyLock;
+
+use eggmock::egg::{
+    rewrite, Applier, EGraph, Id, Pattern, PatternAst, Rewrite, Searcher, Subst, Symbol, Var,
+};
+use eggmock::MigLanguage;
+
+use super::artifact::fnv1a;
+
+pub static REWRITE_RULES: LazyLock<Vec<Rewrite<MigLanguage, ()>>> =
+    LazyLock::new(|| RuleSet::default().rules());
+
+pub static SHARING_GUIDED_REWRITE_RULES: LazyLock<Vec<Rewrite<MigLanguage, ()>>> =
+    LazyLock::new(|| {
+        RuleSet {
+            sharing_guided_distributivity: true,
+        }
+        .rules()
+    });
+
+/// Selects which variants of the rewrite rules are used
+#[derive(Debug, Copy, Clone, Default)]
+pub struct RuleSet {
+    /// Only apply distributivity (in the expanding direction) if one of the MAJs it creates already
+    /// exists in the e-graph, i.e. if it increases operand sharing. This avoids most of the
+    /// e-graph blowup caused by distributivity while keeping its useful instances.
+    pub sharing_guided_distributivity: bool,
+}
+
+impl RuleSet {
+    pub fn rules(&self) -> Vec<Rewrite<MigLanguage, ()>> {
+        let mut rules = vec![
+            rewrite!("commute_1"; "(maj ?a ?b ?c)" => "(maj ?b ?a ?c)"),
+            rewrite!("commute_2"; "(maj ?a ?b ?c)" => "(maj ?a ?c ?b)"),
+            rewrite!("not_not"; "(! (! ?a))" => "?a"),
+            rewrite!("maj_1"; "(maj ?a ?a ?b)" => "?a"),
+            rewrite!("maj_2"; "(maj ?a (! ?a) ?b)" => "?b"),
+            rewrite!("associativity"; "(maj ?a ?b (maj ?c ?b ?d))" => "(maj ?d ?b (maj ?c ?b ?a))"),
+        ];
+        rules.extend(rewrite!("invert"; "(! (maj ?a ?b ?c))" <=> "(maj (! ?a) (! ?b) (! ?c))"));
+        if self.sharing_guided_distributivity {
+            rules.push(rewrite!("distributivity-rev"; "(maj (maj ?a ?b ?c) (maj ?a ?b ?d) ?e)" => "(maj ?a ?b (maj ?c ?d ?e))"));
+            rules.push(
+                Rewrite::new(
+                    "distributivity",
+                    "(maj ?a ?b (maj ?c ?d ?e))".parse::<Pattern<MigLanguage>>().unwrap(),
+                    SharingDistributivity::new(),
+                )
+                .unwrap(),
+            );
+        } else {
+            rules.extend(rewrite!("distributivity"; "(maj ?a ?b (maj ?c ?d ?e))" <=> "(maj (maj ?a ?b ?c) (maj ?a ?b ?d) ?e)"));
+        }
+        rules
+    }
+}
+
+/// Hash of the names and patterns of `rules`, identifying the rules programs have been compiled with
+/// (e.g. in [manifests](super::manifest)) across compiler versions changing them
+pub fn rules_hash(rules: &[Rewrite<MigLanguage, ()>]) -> u64 {
+    let mut description = String::new();
+    let pattern = |ast: Option<&PatternAst<MigLanguage>>| ast.map(|ast| ast.to_string()).unwrap_or_default();
+    for rule in rules {
+        let (searcher, applier) = (rule.searcher.get_pattern_ast(), rule.applier.get_pattern_ast());
+        writeln!(description, "{} {} {}", rule.name, pattern(searcher), pattern(applier)).unwrap();
+    }
+    fnv1a(&description)
+}
+
+/// Applies `(maj ?a ?b (maj ?c ?d ?e)) => (maj (maj ?a ?b ?c) (maj ?a ?b ?d) ?e)` only if
+/// `(maj ?a ?b ?c)` or `(maj ?a ?b ?d)` is already present in the e-graph
+struct SharingDistributivity {
+    result: Pattern<MigLanguage>,
+    vars: [Var; 4],
+}
+
+impl SharingDistributivity {
+    fn new() -> Self {
+        Self {
+            result: "(maj (maj ?a ?b ?c) (maj ?a ?b ?d) ?e)".parse().unwrap(),
+            vars: ["?a", "?b", "?c", "?d"].map(|var| var.parse().unwrap()),
+        }
+    }
+}
+
+/// Returns true iff a MAJ node with the given children (in any order) exists in the e-graph
+fn maj_exists(egraph: &EGraph<MigLanguage, ()>, a: Id, b: Id, c: Id) -> bool {
+    [[a, b, c], [a, c, b], [b, a, c], [b, c, a], [c, a, b], [c, b, a]]
+        .into_iter()
+        .any(|children| egraph.lookup(MigLanguage::Maj(children)).is_some())
+}
+
+impl Applier<MigLanguage, ()> for SharingDistributivity {
+    fn apply_one(
+        &self,
+        egraph: &mut EGraph<MigLanguage, ()>,
+        eclass: Id,
+        subst: &Subst,
+        searcher_ast: Option<&PatternAst<MigLanguage>>,
+        rule_name: Symbol,
+    ) -> Vec<Id> {
+        let [a, b, c, d] = self.vars.map(|var| subst[var]);
+        if !maj_exists(egraph, a, b, c) && !maj_exists(egraph, a, b, d) {
+            return vec![];
+        }
+        self.result
+            .apply_one(egraph, eclass, subst, searcher_ast, rule_name)
+    }
+}
@@ -0,0 +1,205 @@
+//! Sequential circuits: networks with state elements (latches), which are either unrolled into a
+//! combinational network for a fixed nr of time steps or compiled into a program computing a
+//! single cycle, keeping the state in dedicated rows between invocations.
+use std::collections::HashMap;
+
+use super::architecture::{Capabilities, PRADAArchitecture, RowAddress};
+use super::compilation::{compile_placed, reachable_nodes, CompileOptions};
+use super::error::CompileError;
+use super::network::MigNetwork;
+use super::program::{Instruction, Program, RowInit};
+use eggmock::{Mig, Network, Signal};
+use rustc_hash::FxHashMap;
+
+/// A sequential circuit given by its combinational part:
+/// - inputs `0..nr_primary_inputs` are the primary inputs, the remaining inputs are the current
+///   values of the latches (one per latch)
+/// - the outputs are the primary outputs, followed by the next-state functions of the latches
+#[derive(Debug, Clone)]
+pub struct SequentialNetwork {
+    pub combinational: MigNetwork,
+    /// Initial value of every latch
+    pub latches: Vec<bool>,
+}
+
+/// Program computing a single cycle of a [SequentialNetwork]
+#[derive(Debug, Clone)]
+pub struct CycleProgram<'a> {
+    /// Reads the primary inputs of the cycle from the rows of its [Program::input_map] and the
+    /// state from the state rows, writes the primary outputs into the rows of its
+    /// [Program::output_map] and the next state into the state rows
+    pub program: Program<'a>,
+    /// Row holding the value of the i-th latch between invocations, declared as
+    /// [Program::persistent_rows] together with the initial values of the latches
+    pub state_rows: Vec<RowAddress>,
+}
+
+impl SequentialNetwork {
+    pub fn new(combinational: MigNetwork, latches: Vec<bool>) -> Result<Self, &'static str> {
+        if combinational.nr_inputs() < latches.len() as u64 {
+            return Err("fewer inputs than latches");
+        }
+        if combinational.outputs().count() < latches.len() {
+            return Err("fewer outputs than latches");
+        }
+        Ok(Self { combinational, latches })
+    }
+
+    pub fn nr_primary_inputs(&self) -> u64 {
+        self.combinational.nr_inputs() - self.latches.len() as u64
+    }
+
+    pub fn nr_primary_outputs(&self) -> usize {
+        self.combinational.outputs().count() - self.latches.len()
+    }
+
+    /// Returns a combinational network computing `steps` cycles, starting in the initial state:
+    /// input `t * nr_primary_inputs + i` is primary input `i` in cycle `t`, output
+    /// `t * nr_primary_outputs + i` is primary output `i` in cycle `t`. The state after the last
+    /// cycle is appended to the outputs.
+    pub fn unroll(&self, steps: u64) -> MigNetwork {
+        let nr_primary_inputs = self.nr_primary_inputs();
+        let nr_primary_outputs = self.nr_primary_outputs();
+        let mut nodes = reachable_nodes(&self.combinational);
+        // inputs before their users
+        nodes.reverse();
+
+        let mut unrolled = MigNetwork::new();
+        let mut state: Vec<Signal> = self.latches.iter().map(|init| unrolled.constant(*init)).collect();
+        for _ in 0..steps {
+            let inputs: Vec<Signal> = (0..nr_primary_inputs).map(|_| unrolled.add_input()).collect();
+            let mut signals: FxHashMap<eggmock::Id, Signal> = FxHashMap::default();
+            for id in &nodes {
+                let signal = match self.combinational.node(*id) {
+                    Mig::False => unrolled.constant(false),
+                    Mig::Input(i) if i < nr_primary_inputs => inputs[i as usize],
+                    Mig::Input(i) => state[(i - nr_primary_inputs) as usize],
+                    Mig::Maj(operands) => {
+                        let [a, b, c] = operands.map(|operand| signals[&operand.node_id()].maybe_invert(operand.is_inverted()));
+                        unrolled.maj(a, b, c)
+                    }
+                };
+                signals.insert(*id, signal);
+            }
+            let outputs: Vec<Signal> = self
+                .combinational
+                .outputs()
+                .map(|output| signals[&output.node_id()].maybe_invert(output.is_inverted()))
+                .collect();
+            for output in &outputs[..nr_primary_outputs] {
+                unrolled.add_output(*output);
+            }
+            state = outputs[nr_primary_outputs..].to_vec();
+        }
+        for signal in state {
+            unrolled.add_output(signal);
+        }
+        unrolled
+    }
+
+    /// Compiles a program computing a single cycle, which can be invoked repeatedly. The latches
+    /// are stored in the topmost rows of subarray 0, below them the inverted latches, which are
+    /// recomputed at the beginning of every cycle. Requires [Capabilities::NOT].
+    pub fn compile_cycle<'a>(&self, architecture: &'a PRADAArchitecture) -> Result<CycleProgram<'a>, CompileError> {
+        architecture.require(Capabilities::NOT, "inverting latches")?;
+        let nr_latches = self.latches.len() as u64;
+        if 2 * nr_latches >= architecture.rows_per_subarray {
+            return Err(CompileError::Other("too many latches for a subarray"));
+        }
+        let state_rows: Vec<RowAddress> = (0..nr_latches)
+            .map(|latch| RowAddress(architecture.rows_per_subarray - 1 - latch))
+            .collect();
+        let inverted_state_rows: Vec<RowAddress> = (0..nr_latches)
+            .map(|latch| RowAddress(architecture.rows_per_subarray - 1 - nr_latches - latch))
+            .collect();
+
+        let mut placement = HashMap::new();
+        for latch in 0..nr_latches {
+            let input = self
+                .combinational
+                .input(self.nr_primary_inputs() + latch)
+                .expect("every latch has an input");
+            placement.insert(input, state_rows[latch as usize]);
+            placement.insert(input.invert(), inverted_state_rows[latch as usize]);
+        }
+        // the state must survive until the next state has been computed completely
+        let options = CompileOptions { pin_inputs: true, ..CompileOptions::default() };
+        let (compiled, _) = compile_placed(architecture, &self.combinational.with_backward_edges(), placement, options)?;
+
+        let mut program = Program::new(architecture, vec!());
+        for (state_row, inverted_row) in state_rows.iter().zip(&inverted_state_rows) {
+            program.instructions.push(Instruction::AAPRowCopy(*state_row, *inverted_row));
+            program.instructions.push(Instruction::N(*inverted_row));
+        }
+        program.instructions.extend(compiled.instructions);
+        let nr_primary_outputs = self.nr_primary_outputs();
+        let mut output_map = compiled.output_map[..nr_primary_outputs].to_vec();
+        let mut sources = compiled.output_map[nr_primary_outputs..].to_vec();
+        let mut targets = state_rows.clone();
+        // rows which are neither read nor written by the state update anymore
+        let mut free_rows: Vec<RowAddress> = (0..architecture.rows_per_subarray)
+            .map(RowAddress)
+            .filter(|row| !compiled.output_map.contains(row) && !state_rows.contains(row))
+            .collect();
+        // primary outputs which are stored in a state row would be overwritten by the next state
+        for output in output_map.iter_mut().filter(|row| state_rows.contains(row)) {
+            let row = free_rows.pop().ok_or(CompileError::Other("no free row for saving outputs"))?;
+            sources.push(*output);
+            targets.push(row);
+            *output = row;
+        }
+        program.instructions.extend(parallel_copy(&sources, &targets, free_rows.pop())?);
+
+        program.input_map = compiled
+            .input_map
+            .into_iter()
+            .filter(|(row, _)| !state_rows.contains(row) && !inverted_state_rows.contains(row))
+            .collect();
+        program.output_map = output_map;
+        program.allocation = compiled.allocation;
+        for (row, init) in state_rows.iter().zip(&self.latches) {
+            program.persistent_rows.declare(*row, RowInit::Constant(*init));
+        }
+        program.update_cost_estimates(&architecture.cost_model);
+        Ok(CycleProgram { program, state_rows })
+    }
+}
+
+/// Returns copies moving `sources[i]` into `targets[i]` for all `i` as if they happened at the
+/// same time, i.e. every source is read before it is overwritten. Cyclic dependencies are broken
+/// using the `temp` row.
+fn parallel_copy(
+    sources: &[RowAddress],
+    targets: &[RowAddress],
+    temp: Option<RowAddress>,
+) -> Result<Vec<Instruction>, CompileError> {
+    let mut instructions = vec!();
+    let mut pending: Vec<(RowAddress, RowAddress)> = sources
+        .iter()
+        .copied()
+        .zip(targets.iter().copied())
+        .filter(|(source, target)| source != target)
+        .collect();
+    while !pending.is_empty() {
+        let ready = pending
+            .iter()
+            .position(|(_, target)| !pending.iter().any(|(source, _)| source == target));
+        match ready {
+            Some(pos) => {
+                let (source, target) = pending.swap_remove(pos);
+                instructions.push(Instruction::AAPRowCopy(source, target));
+            }
+            None => {
+                let temp = temp.ok_or(CompileError::Other("no free row for breaking cyclic state updates"))?;
+                let source = pending[0].0;
+                instructions.push(Instruction::AAPRowCopy(source, temp));
+                for (pending_source, _) in pending.iter_mut() {
+                    if *pending_source == source {
+                        *pending_source = temp;
+                    }
+                }
+            }
+        }
+    }
+    Ok(instructions)
+}
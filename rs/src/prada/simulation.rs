@@ -0,0 +1,374 @@
+//! Functional simulation of PRADA programs.
+//!
+//! Every row is modeled by 64 bitlines (one `u64`, bit `j` being bitline `j`). As long as a program
+//! doesn't communicate between bitlines (see [Instruction::ColumnShift]) this allows to simulate
+//! 64 independent input vectors at once.
+use super::architecture::RowAddress;
+use super::program::{check_loops, unrolled_indices, Instruction, Program, RowInit};
+use eggmock::{Id, Mig, Network, Signal};
+use rustc_hash::FxHashMap;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{Display, Formatter};
+
+#[derive(Debug, Clone, Default)]
+pub struct Simulator {
+    rows: FxHashMap<RowAddress, u64>,
+    /// Nr of executed instructions (loop bodies are counted once per iteration)
+    pub executed_instructions: u64,
+    /// Nr of bitline values changed by executed instructions, the data-dependent part of the
+    /// energy consumption. Writes to uninitialized rows count their set bits.
+    pub bit_flips: u64,
+}
+
+impl Simulator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Initializes all rows of the program's [Program::input_map], where `inputs[i]` contains the
+    /// values of the i-th input
+    pub fn load_inputs(&mut self, program: &Program, inputs: &[u64]) -> Result<(), &'static str> {
+        for (row, init) in &program.input_map {
+            self.set_row(*row, init_value(init, inputs)?);
+        }
+        Ok(())
+    }
+
+    /// Initializes the program's [Program::persistent_rows], which has to be done once before the
+    /// first invocation, where `inputs[i]` contains the initial value of the i-th input
+    pub fn load_persistent_rows(&mut self, program: &Program, inputs: &[u64]) -> Result<(), &'static str> {
+        for (row, init) in &program.persistent_rows.rows {
+            self.set_row(*row, init_value(init, inputs)?);
+        }
+        Ok(())
+    }
+
+    pub fn set_row(&mut self, row: RowAddress, value: u64) {
+        self.rows.insert(row, value);
+    }
+
+    /// Same as [Self::set_row], but counts the changed bits, for writes by instructions
+    fn write(&mut self, row: RowAddress, value: u64) {
+        let previous = self.rows.insert(row, value).unwrap_or(0);
+        self.bit_flips += (previous ^ value).count_ones() as u64;
+    }
+
+    /// Returns the content of all rows written so far
+    pub fn snapshot(&self) -> RowImage {
+        RowImage { rows: self.rows.iter().map(|(row, value)| (row.0, *value)).collect() }
+    }
+
+    /// Initializes the rows contained in the image, leaving all other rows as they are
+    pub fn load_image(&mut self, image: &RowImage) {
+        for (row, value) in &image.rows {
+            self.set_row(RowAddress(*row), *value);
+        }
+    }
+
+    /// Returns the content of the given row or `None` if it has never been written
+    pub fn row(&self, row: RowAddress) -> Option<u64> {
+        self.rows.get(&row).copied()
+    }
+
+    fn read(&self, row: RowAddress) -> Result<u64, &'static str> {
+        self.row(row).ok_or("read of uninitialized row")
+    }
+
+    /// Executes a single non-loop instruction
+    pub fn step(&mut self, instruction: &Instruction) -> Result<(), &'static str> {
+        match *instruction {
+            Instruction::AAPRowCopy(from, to) => {
+                let value = self.read(from)?;
+                self.write(to, value);
+            }
+            Instruction::AAPTRA(a, b, c) => {
+                let (a_val, b_val, c_val) = (self.read(a)?, self.read(b)?, self.read(c)?);
+                let maj = (a_val & b_val) | (a_val & c_val) | (b_val & c_val);
+                // charge sharing leaves the result in all three activated rows
+                for row in [a, b, c] {
+                    self.write(row, maj);
+                }
+            }
+            Instruction::N(a) => {
+                let value = self.read(a)?;
+                self.write(a, !value);
+            }
+            Instruction::MaskedRowCopy(mask, from, to) => {
+                let (mask, from_val, to_val) = (self.read(mask)?, self.read(from)?, self.read(to)?);
+                self.write(to, (mask & from_val) | (!mask & to_val));
+            }
+            Instruction::ColumnShift(a, offset) => {
+                let value = self.read(a)?;
+                let shifted = match offset {
+                    0 => value,
+                    1..=63 => value << offset,
+                    -63..=-1 => value >> -offset,
+                    _ => 0,
+                };
+                self.write(a, shifted);
+            }
+            Instruction::Xor(a, b, out) => {
+                let value = self.read(a)? ^ self.read(b)?;
+                self.write(out, value);
+            }
+            Instruction::And(a, b, out) => {
+                let value = self.read(a)? & self.read(b)?;
+                self.write(out, value);
+            }
+            Instruction::Or(a, b, out) => {
+                let value = self.read(a)? | self.read(b)?;
+                self.write(out, value);
+            }
+            Instruction::ControlTra(a, b, control) => {
+                let (a_val, b_val) = (self.read(a)?, self.read(b)?);
+                let value = if control.value() { a_val | b_val } else { a_val & b_val };
+                for row in [a, b] {
+                    self.write(row, value);
+                }
+            }
+            Instruction::DccNot(from, to) => {
+                let value = self.read(from)?;
+                self.write(to, !value);
+            }
+            Instruction::LoopBegin(_) | Instruction::LoopEnd => {
+                return Err("loops have to be executed using `run`")
+            }
+        }
+        self.executed_instructions += 1;
+        Ok(())
+    }
+
+    /// Executes the given instructions, including loops
+    pub fn run(&mut self, instructions: &[Instruction]) -> Result<(), &'static str> {
+        self.execute(instructions, None)
+    }
+
+    /// Same as [Self::run], but calls `observer` after every executed instruction
+    pub fn run_observed(
+        &mut self,
+        instructions: &[Instruction],
+        observer: &mut impl SimulationObserver,
+    ) -> Result<(), &'static str> {
+        self.execute(instructions, Some(observer))
+    }
+
+    fn execute(
+        &mut self,
+        instructions: &[Instruction],
+        mut observer: Option<&mut dyn SimulationObserver>,
+    ) -> Result<(), &'static str> {
+        check_loops(instructions)?;
+        for index in unrolled_indices(instructions) {
+            let instruction = &instructions[index];
+            match observer.as_deref_mut() {
+                Some(observer) => self.step_observed(index, instruction, observer)?,
+                None => self.step(instruction)?,
+            }
+        }
+        Ok(())
+    }
+
+    fn step_observed(
+        &mut self,
+        index: usize,
+        instruction: &Instruction,
+        observer: &mut dyn SimulationObserver,
+    ) -> Result<(), &'static str> {
+        let previous: Vec<(RowAddress, Option<u64>)> =
+            instruction.output_operands().map(|row| (row, self.row(row))).collect();
+        self.step(instruction)?;
+        let writes = previous
+            .into_iter()
+            .map(|(row, previous)| RowWrite { row, previous, value: self.rows[&row] })
+            .collect();
+        observer.executed(&ExecutedInstruction { index, instruction, writes }, self);
+        Ok(())
+    }
+
+    /// Returns the values of all outputs according to the program's [Program::output_map]
+    pub fn outputs(&self, program: &Program) -> Result<Vec<u64>, &'static str> {
+        program.output_map.iter().map(|row| self.read(*row)).collect()
+    }
+}
+
+/// Callback of [Simulator::run_observed], e.g. for co-simulating programs with custom power models
+/// or for visualizing them. Implemented by all closures taking the same arguments as
+/// [Self::executed].
+pub trait SimulationObserver {
+    /// Called after `executed` has been executed by `simulator`, whose rows may be inspected using
+    /// e.g. [Simulator::row] or [Simulator::snapshot]
+    fn executed(&mut self, executed: &ExecutedInstruction, simulator: &Simulator);
+}
+
+impl<F: FnMut(&ExecutedInstruction, &Simulator)> SimulationObserver for F {
+    fn executed(&mut self, executed: &ExecutedInstruction, simulator: &Simulator) {
+        self(executed, simulator)
+    }
+}
+
+/// Instruction executed by a [Simulator], as passed to [SimulationObserver]s
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExecutedInstruction<'a> {
+    /// Index of the instruction in the executed instructions, which repeats for every iteration of
+    /// a loop
+    pub index: usize,
+    pub instruction: &'a Instruction,
+    /// Rows written by the instruction in the order of [Instruction::output_operands]
+    pub writes: Vec<RowWrite>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RowWrite {
+    pub row: RowAddress,
+    /// Content of the row before the instruction, `None` if it had never been written
+    pub previous: Option<u64>,
+    pub value: u64,
+}
+
+impl RowWrite {
+    /// Nr of bitlines whose value has been changed by the write (see [Simulator::bit_flips])
+    pub fn bit_flips(&self) -> u64 {
+        (self.previous.unwrap_or(0) ^ self.value).count_ones() as u64
+    }
+}
+
+/// Content of the (initialized) rows of a DRAM module, used as initial or final state of a
+/// [Simulator], e.g. for comparisons against golden images.
+///
+/// Serialized as text with one row per line: the (global) row address in decimal and the content
+/// as 16 hex digits, bitline 0 being the least significant bit. Empty lines and lines starting with
+/// `#` are ignored.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RowImage {
+    pub rows: BTreeMap<u64, u64>,
+}
+
+impl RowImage {
+    pub fn parse(text: &str) -> Result<Self, &'static str> {
+        let mut rows = BTreeMap::new();
+        for line in text.lines().map(str::trim).filter(|line| !line.is_empty() && !line.starts_with('#')) {
+            let (row, value) = line.split_once(char::is_whitespace).ok_or("expected `<row> <content>`")?;
+            let row = row.parse().map_err(|_| "invalid row address")?;
+            let value = u64::from_str_radix(value.trim(), 16).map_err(|_| "invalid row content")?;
+            if rows.insert(row, value).is_some() {
+                return Err("row occurs multiple times");
+            }
+        }
+        Ok(Self { rows })
+    }
+
+    /// Rows whose content differs between the two images, with their content in `self` and
+    /// `other` (`None` if not contained)
+    pub fn diff(&self, other: &RowImage) -> Vec<(RowAddress, Option<u64>, Option<u64>)> {
+        let rows: BTreeSet<u64> = self.rows.keys().chain(other.rows.keys()).copied().collect();
+        rows.into_iter()
+            .map(|row| (RowAddress(row), self.rows.get(&row).copied(), other.rows.get(&row).copied()))
+            .filter(|(_, a, b)| a != b)
+            .collect()
+    }
+}
+
+impl Display for RowImage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "# PRADA row image: <row> <content, bitline 0 = LSB>")?;
+        for (row, value) in &self.rows {
+            writeln!(f, "{row} {value:016x}")?;
+        }
+        Ok(())
+    }
+}
+
+fn init_value(init: &RowInit, inputs: &[u64]) -> Result<u64, &'static str> {
+    Ok(match init {
+        RowInit::Constant(false) => 0,
+        RowInit::Constant(true) => u64::MAX,
+        RowInit::Input { index, inverted } => {
+            let value = *inputs.get(*index as usize).ok_or("missing value for input")?;
+            if *inverted {
+                !value
+            } else {
+                value
+            }
+        }
+    })
+}
+
+/// Runs the program on the given input values and returns the values of its outputs. Persistent
+/// rows are initialized without inputs, see [simulate_invocations].
+pub fn simulate(program: &Program, inputs: &[u64]) -> Result<Vec<u64>, &'static str> {
+    simulate_invocations(program, &[], &[inputs]).map(|mut outputs| outputs.remove(0))
+}
+
+/// Same as [simulate], but calls `observer` after every executed instruction
+pub fn simulate_observed(
+    program: &Program,
+    inputs: &[u64],
+    observer: &mut impl SimulationObserver,
+) -> Result<Vec<u64>, &'static str> {
+    let mut simulator = Simulator::new();
+    simulator.load_persistent_rows(program, &[])?;
+    simulator.load_inputs(program, inputs)?;
+    simulator.run_observed(&program.instructions, observer)?;
+    simulator.outputs(program)
+}
+
+/// Initializes the program's [Program::persistent_rows] using `initial` once and then runs the
+/// program once per element of `invocations` (which contains the input values of that invocation),
+/// returning the outputs of every invocation
+pub fn simulate_invocations(
+    program: &Program,
+    initial: &[u64],
+    invocations: &[&[u64]],
+) -> Result<Vec<Vec<u64>>, &'static str> {
+    let mut simulator = Simulator::new();
+    simulator.load_persistent_rows(program, initial)?;
+    invocations
+        .iter()
+        .map(|inputs| {
+            simulator.load_inputs(program, inputs)?;
+            simulator.run(&program.instructions)?;
+            simulator.outputs(program)
+        })
+        .collect()
+}
+
+/// Evaluates the network itself on the given input values, which serves as reference for checking
+/// the results of simulated programs
+pub fn evaluate_network(network: &impl Network<Node = Mig>, inputs: &[u64]) -> Result<Vec<u64>, &'static str> {
+    let mut values: FxHashMap<Id, u64> = FxHashMap::default();
+    let mut evaluate_signal = |signal: Signal| -> Result<u64, &'static str> {
+        // evaluate iteratively in post-order to avoid overflowing the stack on deep networks
+        let mut stack = vec!(signal.node_id());
+        while let Some(&id) = stack.last() {
+            if values.contains_key(&id) {
+                stack.pop();
+                continue;
+            }
+            let value = match network.node(id) {
+                Mig::False => 0,
+                Mig::Input(i) => *inputs.get(i as usize).ok_or("missing value for input")?,
+                Mig::Maj(maj_inputs) => {
+                    let missing: Vec<Id> = maj_inputs
+                        .iter()
+                        .map(|sig| sig.node_id())
+                        .filter(|id| !values.contains_key(id))
+                        .collect();
+                    if !missing.is_empty() {
+                        stack.extend(missing);
+                        continue;
+                    }
+                    let [a, b, c] = maj_inputs.map(|sig| {
+                        let value = values[&sig.node_id()];
+                        if sig.is_inverted() { !value } else { value }
+                    });
+                    (a & b) | (a & c) | (b & c)
+                }
+            };
+            values.insert(id, value);
+            stack.pop();
+        }
+        let value = values[&signal.node_id()];
+        Ok(if signal.is_inverted() { !value } else { value })
+    };
+    network.outputs().map(&mut evaluate_signal).collect()
+}
@@ -0,0 +1,298 @@
+//! Generators for commonly used kernels, emitting PRADA programs directly instead of going through
+//! rewriting and extraction, or building networks which are compiled by the standard pipeline.
+use super::architecture::{Capabilities, PRADAArchitecture, RowAddress};
+use super::error::CompileError;
+use super::network::MigNetwork;
+use super::program::{Instruction, Program, RowInit};
+use super::random::Xorshift;
+use super::{compile, CompilerSettings};
+
+/// Adds two `word_width`-bit words stored horizontally, i.e. bit `j` of a word lies on bitline `j`
+/// of the row (least significant bit on bitline 0).
+///
+/// The carries are propagated between neighboring bitlines using
+/// [Instruction::ColumnShift]: after `word_width` iterations of `carry = shift(MAJ(a, b, carry))`
+/// every bitline holds its final carry-in. The sum is then computed with the MIG full-adder
+/// identity `sum = MAJ(!MAJ(a, b, c), c, MAJ(a, b, !c))`.
+///
+/// Input 0 and 1 are the two words, output 0 is the sum (without the final carry-out).
+pub fn horizontal_adder(
+    architecture: &PRADAArchitecture,
+    word_width: u64,
+) -> Result<Program<'_>, CompileError> {
+    architecture.require(Capabilities::COLUMN_SHIFT | Capabilities::NOT, "horizontal adder")?;
+    let [a, b, zero, carry, t0, t1, t2] = [0, 1, 2, 3, 4, 5, 6].map(RowAddress);
+    let mut program = Program::new(architecture, vec!());
+    program.input_map = vec!(
+        (a, RowInit::Input { index: 0, inverted: false }),
+        (b, RowInit::Input { index: 1, inverted: false }),
+        (zero, RowInit::Constant(false)),
+    );
+
+    program.instructions.push(Instruction::AAPRowCopy(zero, carry));
+    program.push_loop(
+        word_width,
+        [
+            Instruction::AAPRowCopy(a, t0),
+            Instruction::AAPRowCopy(b, t1),
+            Instruction::AAPRowCopy(carry, t2),
+            Instruction::AAPTRA(t0, t1, t2),
+            Instruction::AAPRowCopy(t0, carry),
+            Instruction::ColumnShift(carry, 1),
+        ],
+    );
+    program.instructions.extend([
+        // t0 = !MAJ(a, b, c)
+        Instruction::AAPRowCopy(a, t0),
+        Instruction::AAPRowCopy(b, t1),
+        Instruction::AAPRowCopy(carry, t2),
+        Instruction::AAPTRA(t0, t1, t2),
+        Instruction::N(t0),
+        // t1 = MAJ(a, b, !c), the constant row isn't needed after initializing the carry anymore and
+        // serves as third scratch row
+        Instruction::AAPRowCopy(a, t1),
+        Instruction::AAPRowCopy(b, t2),
+        Instruction::AAPRowCopy(carry, zero),
+        Instruction::N(zero),
+        Instruction::AAPTRA(t1, t2, zero),
+        // t0 = MAJ(t0, c, t1)
+        Instruction::AAPRowCopy(carry, t2),
+        Instruction::AAPTRA(t0, t2, t1),
+    ]);
+    program.output_map = vec!(t0);
+
+    program.update_cost_estimates(&architecture.cost_model);
+    Ok(program)
+}
+
+/// Predicate applied by a [bitmap_scan] to every data row and the query row
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum BitmapOp {
+    /// `data & query`
+    And,
+    /// `data | query`
+    Or,
+    /// `data & !query`
+    AndNot,
+}
+
+/// Program of a [bitmap_scan] together with its throughput estimates
+#[derive(Debug, Clone)]
+pub struct BitmapScan<'a> {
+    pub program: Program<'a>,
+    pub n_rows: u64,
+}
+
+impl BitmapScan<'_> {
+    /// Estimated runtime per data row in ns
+    pub fn runtime_per_row(&self) -> f64 {
+        self.program.runtime_estimate as f64 / self.n_rows as f64
+    }
+
+    /// Estimated energy consumption per data row in mJ/KOps
+    pub fn energy_per_row(&self) -> f64 {
+        self.program.energy_consumption_estimate as f64 / self.n_rows as f64
+    }
+
+    /// Estimated nr of data rows processed per second, all bitlines of a row in parallel
+    pub fn rows_per_second(&self) -> f64 {
+        1e9 / self.runtime_per_row()
+    }
+}
+
+/// Applies `op` to each of `n_rows` data rows and a query row, as done by bitmap index scans.
+///
+/// Every predicate is a single TRA `MAJ(data, query, constant)` with a constant of 0 (AND) or 1
+/// (OR). For [BitmapOp::AndNot] the host stores the query inverted, so no N is necessary. As the
+/// TRA destroys its operands, the data row, the query and the constant are copied into the result
+/// row and two scratch rows before.
+///
+/// Inputs `0..n_rows` are the data rows, input `n_rows` is the query, output `i` is the result for
+/// data row `i`.
+pub fn bitmap_scan(
+    architecture: &PRADAArchitecture,
+    op: BitmapOp,
+    n_rows: u64,
+) -> Result<BitmapScan<'_>, CompileError> {
+    if n_rows == 0 {
+        return Err(CompileError::Other("bitmap scan without data rows"));
+    }
+    // data and result rows, query, constant and two scratch rows
+    if 2 * n_rows + 4 > architecture.rows_per_subarray {
+        return Err(CompileError::Other("too many data rows for a subarray"));
+    }
+    let data = |i: u64| RowAddress(i);
+    let result = |i: u64| RowAddress(n_rows + i);
+    let [query, constant, t0, t1] = [0, 1, 2, 3].map(|offset| RowAddress(2 * n_rows + offset));
+
+    let mut program = Program::new(architecture, vec!());
+    program.input_map = (0..n_rows)
+        .map(|i| (data(i), RowInit::Input { index: i, inverted: false }))
+        .collect();
+    program.input_map.extend([
+        (query, RowInit::Input { index: n_rows, inverted: op == BitmapOp::AndNot }),
+        (constant, RowInit::Constant(op == BitmapOp::Or)),
+    ]);
+    for i in 0..n_rows {
+        program.instructions.extend([
+            Instruction::AAPRowCopy(data(i), result(i)),
+            Instruction::AAPRowCopy(query, t0),
+            Instruction::AAPRowCopy(constant, t1),
+            Instruction::AAPTRA(result(i), t0, t1),
+        ]);
+    }
+    program.output_map = (0..n_rows).map(result).collect();
+
+    program.update_cost_estimates(&architecture.cost_model);
+    Ok(BitmapScan { program, n_rows })
+}
+
+/// Network computing the Hamming distance between a stored and a query word of `width` bits, both
+/// stored bit-serially (bit `i` of every word in its own row, one word per bitline): inputs
+/// `0..width` are the bits of the stored words, inputs `width..2 * width` the bits of the query
+/// (replicated on every bitline) and the outputs the distance as binary number, least significant
+/// bit first.
+pub fn hamming_distance_network(width: u64) -> MigNetwork {
+    let mut network = MigNetwork::new();
+    let stored: Vec<_> = (0..width).map(|_| network.add_input()).collect();
+    let query: Vec<_> = (0..width).map(|_| network.add_input()).collect();
+    let differences: Vec<_> = stored
+        .into_iter()
+        .zip(query)
+        .map(|(stored, query)| network.xor(stored, query))
+        .collect();
+    for bit in network.popcount(&differences) {
+        network.add_output(bit);
+    }
+    network
+}
+
+/// Random network of `nr_majs` MAJs over `nr_inputs` inputs (at least one), e.g. for fuzzing or
+/// scaling experiments: every MAJ takes three (possibly inverted) operands among the inputs and the
+/// earlier MAJs, the last `nr_outputs` MAJs are the outputs. The same seed (e.g. one derived by
+/// [CompilerSettings::stream_seed]) always yields the same network.
+pub fn random_network(nr_inputs: u64, nr_majs: u64, nr_outputs: u64, seed: u64) -> MigNetwork {
+    let mut random = Xorshift::new(seed);
+    let mut network = MigNetwork::new();
+    let mut signals: Vec<_> = (0..nr_inputs.max(1)).map(|_| network.add_input()).collect();
+    for _ in 0..nr_majs {
+        let [a, b, c] = [(); 3].map(|_| {
+            let bits = random.next_u64();
+            let signal = signals[(bits >> 1) as usize % signals.len()];
+            if bits & 1 == 1 { signal.invert() } else { signal }
+        });
+        signals.push(network.maj(a, b, c));
+    }
+    for signal in &signals[signals.len() - nr_outputs.min(signals.len() as u64) as usize..] {
+        network.add_output(*signal);
+    }
+    network
+}
+
+/// Compiles [hamming_distance_network] through rewriting, extraction and compilation, comparing
+/// the query to as many stored words as there are bitlines at once
+pub fn hamming_distance(
+    architecture: &PRADAArchitecture,
+    width: u64,
+    settings: CompilerSettings,
+) -> Result<Program<'_>, CompileError> {
+    compile(architecture, &hamming_distance_network(width), settings)
+}
+
+/// Multiplication in GF(2^8) modulo the AES polynomial `x^8 + x^4 + x^3 + x + 1`
+fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0;
+    while b != 0 {
+        if b & 1 != 0 {
+            product ^= a;
+        }
+        a = (a << 1) ^ if a & 0x80 != 0 { 0x1b } else { 0 };
+        b >>= 1;
+    }
+    product
+}
+
+/// The AES S-box: the multiplicative inverse in GF(2^8) (0 for 0), followed by the affine
+/// transformation of AES
+pub fn aes_sbox_table() -> [u8; 256] {
+    let mut table = [0; 256];
+    for (x, entry) in table.iter_mut().enumerate() {
+        let inverse = (1..=255u8).find(|y| gf_mul(x as u8, *y) == 1).unwrap_or(0);
+        *entry = inverse
+            ^ inverse.rotate_left(1)
+            ^ inverse.rotate_left(2)
+            ^ inverse.rotate_left(3)
+            ^ inverse.rotate_left(4)
+            ^ 0x63;
+    }
+    table
+}
+
+/// Network computing the AES S-box as 8 -> 8 Boolean function, input `i` and output `i` being bit
+/// `i` of the input and output byte
+pub fn aes_sbox_network() -> MigNetwork {
+    let mut network = MigNetwork::new();
+    let inputs: Vec<_> = (0..8).map(|_| network.add_input()).collect();
+    let table: Vec<u64> = aes_sbox_table().into_iter().map(u64::from).collect();
+    for bit in network.lookup(&inputs, &table, 8) {
+        network.add_output(bit);
+    }
+    network
+}
+
+/// The 4-bit S-box of PRESENT
+pub const PRESENT_SBOX: [u8; 16] = [0xc, 0x5, 0x6, 0xb, 0x9, 0x0, 0xa, 0xd, 0x3, 0xe, 0xf, 0x8, 0x4, 0x7, 0x1, 0x2];
+
+/// Position bit `i` of the state is moved to by the permutation layer of PRESENT
+pub fn present_permutation(i: usize) -> usize {
+    if i == 63 {
+        63
+    } else {
+        16 * i % 63
+    }
+}
+
+/// Network computing a single round of the PRESENT block cipher (key addition, S-box layer,
+/// permutation layer): inputs `0..64` are the bits of the state, inputs `64..128` the bits of the
+/// round key, output `i` is bit `i` of the new state
+pub fn present_round_network() -> MigNetwork {
+    let mut network = MigNetwork::new();
+    let state: Vec<_> = (0..64).map(|_| network.add_input()).collect();
+    let key: Vec<_> = (0..64).map(|_| network.add_input()).collect();
+    let keyed: Vec<_> = state
+        .into_iter()
+        .zip(key)
+        .map(|(state, key)| network.xor(state, key))
+        .collect();
+    let table: Vec<u64> = PRESENT_SBOX.into_iter().map(u64::from).collect();
+    let substituted: Vec<_> = keyed
+        .chunks(4)
+        .flat_map(|nibble| network.lookup(nibble, &table, 4))
+        .collect();
+    let mut permuted = substituted.clone();
+    for (i, bit) in substituted.into_iter().enumerate() {
+        permuted[present_permutation(i)] = bit;
+    }
+    for bit in permuted {
+        network.add_output(bit);
+    }
+    network
+}
+
+/// Compiles [aes_sbox_network] through rewriting, extraction and compilation, substituting as many
+/// bytes as there are bitlines at once
+pub fn aes_sbox(
+    architecture: &PRADAArchitecture,
+    settings: CompilerSettings,
+) -> Result<Program<'_>, CompileError> {
+    compile(architecture, &aes_sbox_network(), settings)
+}
+
+/// Compiles [present_round_network] through rewriting, extraction and compilation, encrypting as
+/// many blocks as there are bitlines at once
+pub fn present_round(
+    architecture: &PRADAArchitecture,
+    settings: CompilerSettings,
+) -> Result<Program<'_>, CompileError> {
+    compile(architecture, &present_round_network(), settings)
+}
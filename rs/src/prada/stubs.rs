@@ -0,0 +1,411 @@
+//! Generation of host stubs (C and Rust) for compiled programs: they write the host's buffers into
+//! the rows of the program's [Program::input_map], issue the program through a driver provided by
+//! the user and read the outputs back from the rows of the [Program::output_map].
+//!
+//! Every buffer holds the content of one row (`row_bytes` bytes), in the bit and byte order given
+//! by the [Marshalling], so the stubs relieve the host from placing inputs into the right rows, in
+//! the right polarity and order, and from initializing constant rows.
+use std::fmt::Write;
+
+use super::architecture::RowAddress;
+use super::error::CompileError;
+use super::program::{Instruction, Program, RowInit};
+use super::symbols::{identifier, SymbolTable};
+
+/// Order of the bits inside each byte of a host buffer
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum BitOrder {
+    /// Bit `j` (value `1 << j`) of byte `k` is bitline `8k + j`
+    #[default]
+    LsbFirst,
+    /// Bit `7 - j` of byte `k` is bitline `8k + j`
+    MsbFirst,
+}
+
+/// Order of the bytes of a host buffer across the columns of a row
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum ByteOrder {
+    /// Byte `k` holds bitlines `8k..8k + 8`
+    #[default]
+    LittleEndian,
+    /// The buffer consists of big-endian words of the given nr of bytes, i.e. the bytes inside
+    /// every word are stored in reverse order
+    BigEndian { word_bytes: usize },
+}
+
+/// Layout of the host buffers, relative to the row images written to and read from the DRAM
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct Marshalling {
+    pub bit_order: BitOrder,
+    pub byte_order: ByteOrder,
+}
+
+impl Marshalling {
+    /// Whether buffers are identical to their row images
+    pub fn is_identity(&self) -> bool {
+        self.bit_order == BitOrder::LsbFirst && self.word_bytes() <= 1
+    }
+
+    fn word_bytes(&self) -> usize {
+        match self.byte_order {
+            ByteOrder::LittleEndian => 1,
+            ByteOrder::BigEndian { word_bytes } => word_bytes,
+        }
+    }
+
+    /// Converts a host buffer into a row image (byte `k`, bit `j` holding bitline `8k + j`) in
+    /// place. The conversion is its own inverse, i.e. also converts row images into host buffers.
+    /// Trailing bytes not forming a complete word are left as is.
+    pub fn convert(&self, data: &mut [u8]) {
+        if self.bit_order == BitOrder::MsbFirst {
+            data.iter_mut().for_each(|byte| *byte = byte.reverse_bits());
+        }
+        let word_bytes = self.word_bytes();
+        if word_bytes > 1 {
+            data.chunks_exact_mut(word_bytes).for_each(<[u8]>::reverse);
+        }
+    }
+
+    fn describe(&self) -> String {
+        let bits = match self.bit_order {
+            BitOrder::LsbFirst => "LSB first",
+            BitOrder::MsbFirst => "MSB first",
+        };
+        match self.byte_order {
+            ByteOrder::LittleEndian => format!("bits {bits}, bytes little-endian"),
+            ByteOrder::BigEndian { word_bytes } => format!("bits {bits}, big-endian words of {word_bytes} bytes"),
+        }
+    }
+}
+
+/// Returns a C header defining `<name>_run` (and `<name>_init` for the [Program::persistent_rows])
+pub fn c_stub(program: &Program, name: &str, marshalling: Marshalling) -> Result<String, CompileError> {
+    check_identifier(name)?;
+    let mut out = String::new();
+    let guard = name.to_uppercase();
+    writeln!(out, "/* Generated by lime-rs, do not edit. Host buffers: {} */", marshalling.describe()).unwrap();
+    writeln!(out, "#ifndef {guard}_H\n#define {guard}_H\n").unwrap();
+    writeln!(out, "#include <stddef.h>\n#include <stdint.h>\n#include <string.h>\n").unwrap();
+    out += C_PRELUDE;
+    writeln!(out, "\n#define {guard}_NR_INPUTS {}", nr_inputs(program)).unwrap();
+    writeln!(out, "#define {guard}_NR_OUTPUTS {}", program.output_map.len()).unwrap();
+    writeln!(out, "#define {guard}_PROGRAM_LENGTH {}", program.instructions.len()).unwrap();
+    let symbols = program.symbols();
+    for (constant, index, name) in named_buffers(program, &symbols) {
+        writeln!(out, "#define {guard}_{constant} {index} /* {name} */").unwrap();
+    }
+    writeln!(out).unwrap();
+
+    if program.instructions.is_empty() {
+        writeln!(out, "static const prada_instruction *const {name}_program = NULL;\n").unwrap();
+    } else {
+        writeln!(out, "static const prada_instruction {name}_program[] = {{").unwrap();
+        for instruction in &program.instructions {
+            let (opcode, operands) = encode(instruction);
+            writeln!(out, "    {{PRADA_OP_{opcode}, {{{}, {}, {}}}}},", operands[0], operands[1], operands[2]).unwrap();
+        }
+        writeln!(out, "}};\n").unwrap();
+    }
+
+    let convert = !marshalling.is_identity();
+    if convert {
+        writeln!(out, "/* Converts between host buffers and row images, in both directions */").unwrap();
+        writeln!(out, "static void {name}_convert(uint8_t *data, size_t row_bytes) {{").unwrap();
+        if marshalling.bit_order == BitOrder::MsbFirst {
+            writeln!(out, "    for (size_t i = 0; i < row_bytes; i++) {{").unwrap();
+            writeln!(out, "        uint8_t reversed = 0;").unwrap();
+            writeln!(out, "        for (int j = 0; j < 8; j++) reversed |= (uint8_t)(((data[i] >> j) & 1) << (7 - j));").unwrap();
+            writeln!(out, "        data[i] = reversed;\n    }}").unwrap();
+        }
+        let word_bytes = marshalling.word_bytes();
+        if word_bytes > 1 {
+            writeln!(out, "    for (size_t w = 0; w + {word_bytes} <= row_bytes; w += {word_bytes}) {{").unwrap();
+            writeln!(out, "        for (size_t i = 0; i < {}; i++) {{", word_bytes / 2).unwrap();
+            writeln!(out, "            uint8_t byte = data[w + i];").unwrap();
+            writeln!(out, "            data[w + i] = data[w + {} - i];", word_bytes - 1).unwrap();
+            writeln!(out, "            data[w + {} - i] = byte;\n        }}\n    }}", word_bytes - 1).unwrap();
+        }
+        writeln!(out, "}}\n").unwrap();
+    }
+
+    let write_rows = |out: &mut String, rows: &[(RowAddress, RowInit)]| {
+        for (row, init) in rows {
+            let source = match init {
+                RowInit::Input { index, inverted } if convert || *inverted => {
+                    writeln!(out, "    memcpy(scratch, inputs[{index}], row_bytes);").unwrap();
+                    if convert {
+                        writeln!(out, "    {name}_convert(scratch, row_bytes);").unwrap();
+                    }
+                    if *inverted {
+                        writeln!(out, "    for (size_t i = 0; i < row_bytes; i++) scratch[i] = (uint8_t)~scratch[i];").unwrap();
+                    }
+                    "scratch".to_string()
+                }
+                RowInit::Input { index, .. } => format!("inputs[{index}]"),
+                RowInit::Constant(value) => {
+                    writeln!(out, "    memset(scratch, {}, row_bytes);", if *value { "0xff" } else { "0x00" }).unwrap();
+                    "scratch".to_string()
+                }
+            };
+            let comment = row_comment(&symbols, *row);
+            writeln!(out, "    driver->write_row(driver->ctx, {}u, {source}, row_bytes); /* {comment} */", row.0).unwrap();
+        }
+    };
+
+    if !program.persistent_rows.is_empty() {
+        writeln!(out, "/* Initializes the persistent rows, once before the first invocation of {name}_run */").unwrap();
+        writeln!(
+            out,
+            "static void {name}_init(const prada_driver *driver, const uint8_t *const *inputs, uint8_t *scratch, size_t row_bytes) {{"
+        )
+        .unwrap();
+        writeln!(out, "    (void)inputs;\n    (void)scratch;").unwrap();
+        write_rows(&mut out, &program.persistent_rows.rows);
+        writeln!(out, "}}\n").unwrap();
+    }
+
+    writeln!(out, "/* `scratch` has to provide `row_bytes` bytes */").unwrap();
+    writeln!(
+        out,
+        "static void {name}_run(const prada_driver *driver, const uint8_t *const *inputs, uint8_t *const *outputs, uint8_t *scratch, size_t row_bytes) {{"
+    )
+    .unwrap();
+    writeln!(out, "    (void)inputs;\n    (void)scratch;").unwrap();
+    write_rows(&mut out, &program.input_map);
+    writeln!(out, "    driver->execute(driver->ctx, {name}_program, {guard}_PROGRAM_LENGTH);").unwrap();
+    for (idx, row) in program.output_map.iter().enumerate() {
+        let comment = format!("row {row}: {}", symbols.output(idx).unwrap_or_default());
+        writeln!(out, "    driver->read_row(driver->ctx, {}u, outputs[{idx}], row_bytes); /* {comment} */", row.0).unwrap();
+        if convert {
+            writeln!(out, "    {name}_convert(outputs[{idx}], row_bytes);").unwrap();
+        }
+    }
+    writeln!(out, "}}\n\n#endif").unwrap();
+    Ok(out)
+}
+
+const C_PRELUDE: &str = "#ifndef PRADA_DRIVER_DEFINED
+#define PRADA_DRIVER_DEFINED
+typedef enum {
+    PRADA_OP_AAP_ROW_COPY,
+    PRADA_OP_AAP_TRA,
+    PRADA_OP_N,
+    PRADA_OP_MASKED_ROW_COPY,
+    PRADA_OP_XOR,
+    PRADA_OP_AND,
+    PRADA_OP_OR,
+    PRADA_OP_CONTROL_TRA,
+    PRADA_OP_DCC_NOT,
+    PRADA_OP_COLUMN_SHIFT,
+    PRADA_OP_LOOP_BEGIN,
+    PRADA_OP_LOOP_END,
+} prada_opcode;
+
+/* operands are row addresses, except for the offset of COLUMN_SHIFT (2nd operand), the control
+ * row of CONTROL_TRA (3rd operand, 0 for C0 and 1 for C1) and the iteration count of LOOP_BEGIN
+ * (1st operand) */
+typedef struct {
+    prada_opcode opcode;
+    int64_t operands[3];
+} prada_instruction;
+
+/* Implemented by the user to access the DRAM module */
+typedef struct {
+    void *ctx;
+    void (*write_row)(void *ctx, uint64_t row, const uint8_t *data, size_t row_bytes);
+    void (*read_row)(void *ctx, uint64_t row, uint8_t *data, size_t row_bytes);
+    void (*execute)(void *ctx, const prada_instruction *program, size_t length);
+} prada_driver;
+#endif
+";
+
+/// Returns a Rust module `<name>` defining `run` (and `init` for the [Program::persistent_rows]),
+/// which doesn't depend on this crate
+pub fn rust_stub(program: &Program, name: &str, marshalling: Marshalling) -> Result<String, CompileError> {
+    check_identifier(name)?;
+    let mut out = String::new();
+    writeln!(out, "// Generated by lime-rs, do not edit. Host buffers: {}", marshalling.describe()).unwrap();
+    writeln!(out, "pub mod {name} {{").unwrap();
+    out += RUST_PRELUDE;
+    writeln!(out, "\n    pub const NR_INPUTS: usize = {};", nr_inputs(program)).unwrap();
+    writeln!(out, "    pub const NR_OUTPUTS: usize = {};", program.output_map.len()).unwrap();
+    let symbols = program.symbols();
+    for (constant, index, name) in named_buffers(program, &symbols) {
+        writeln!(out, "    /// Index of `{name}`\n    pub const {constant}: usize = {index};").unwrap();
+    }
+    writeln!(out).unwrap();
+    writeln!(out, "    pub const PROGRAM: &[Instruction] = &[").unwrap();
+    for instruction in &program.instructions {
+        let operand = |row: &RowAddress| row.0.to_string();
+        let encoded = match instruction.map_addresses(operand) {
+            Instruction::AAPRowCopy(a, b) => format!("AapRowCopy({a}, {b})"),
+            Instruction::AAPTRA(a, b, c) => format!("AapTra({a}, {b}, {c})"),
+            Instruction::N(a) => format!("N({a})"),
+            Instruction::MaskedRowCopy(mask, from, to) => format!("MaskedRowCopy({mask}, {from}, {to})"),
+            Instruction::Xor(a, b, result) => format!("Xor({a}, {b}, {result})"),
+            Instruction::And(a, b, result) => format!("And({a}, {b}, {result})"),
+            Instruction::Or(a, b, result) => format!("Or({a}, {b}, {result})"),
+            Instruction::ControlTra(a, b, control) => format!("ControlTra({a}, {b}, {})", control.value()),
+            Instruction::DccNot(from, to) => format!("DccNot({from}, {to})"),
+            Instruction::ColumnShift(a, offset) => format!("ColumnShift({a}, {offset})"),
+            Instruction::LoopBegin(count) => format!("LoopBegin({count})"),
+            Instruction::LoopEnd => "LoopEnd".to_string(),
+        };
+        writeln!(out, "        Instruction::{encoded},").unwrap();
+    }
+    writeln!(out, "    ];").unwrap();
+
+    let convert = !marshalling.is_identity();
+    if convert {
+        writeln!(out, "\n    /// Converts between host buffers and row images, in both directions").unwrap();
+        writeln!(out, "    pub fn convert(data: &mut [u8]) {{").unwrap();
+        if marshalling.bit_order == BitOrder::MsbFirst {
+            writeln!(out, "        data.iter_mut().for_each(|byte| *byte = byte.reverse_bits());").unwrap();
+        }
+        if marshalling.word_bytes() > 1 {
+            writeln!(out, "        data.chunks_exact_mut({}).for_each(<[u8]>::reverse);", marshalling.word_bytes()).unwrap();
+        }
+        writeln!(out, "    }}").unwrap();
+    }
+
+    let write_rows = |out: &mut String, rows: &[(RowAddress, RowInit)]| {
+        for (row, init) in rows {
+            let data = match init {
+                RowInit::Input { index, inverted } if convert || *inverted => {
+                    writeln!(out, "        let mut data = inputs[{index}].to_vec();").unwrap();
+                    if convert {
+                        writeln!(out, "        convert(&mut data);").unwrap();
+                    }
+                    if *inverted {
+                        writeln!(out, "        data.iter_mut().for_each(|byte| *byte = !*byte);").unwrap();
+                    }
+                    "&data".to_string()
+                }
+                RowInit::Input { index, .. } => format!("inputs[{index}]"),
+                RowInit::Constant(value) => format!("&vec![{}u8; row_bytes]", if *value { "0xff" } else { "0x00" }),
+            };
+            let comment = row_comment(&symbols, *row);
+            writeln!(out, "        // {comment}\n        driver.write_row({}, {data});", row.0).unwrap();
+        }
+    };
+
+    if !program.persistent_rows.is_empty() {
+        writeln!(out, "\n    /// Initializes the persistent rows, once before the first invocation of [run]").unwrap();
+        writeln!(out, "    #[allow(unused_variables)]").unwrap();
+        writeln!(out, "    pub fn init(driver: &mut impl Driver, inputs: &[&[u8]], row_bytes: usize) {{").unwrap();
+        write_rows(&mut out, &program.persistent_rows.rows);
+        writeln!(out, "    }}").unwrap();
+    }
+
+    writeln!(out, "\n    /// Every buffer has to contain `row_bytes` bytes").unwrap();
+    writeln!(out, "    #[allow(unused_variables)]").unwrap();
+    writeln!(
+        out,
+        "    pub fn run(driver: &mut impl Driver, inputs: &[&[u8]], outputs: &mut [&mut [u8]], row_bytes: usize) {{"
+    )
+    .unwrap();
+    writeln!(out, "        assert_eq!(inputs.len(), NR_INPUTS);\n        assert_eq!(outputs.len(), NR_OUTPUTS);").unwrap();
+    write_rows(&mut out, &program.input_map);
+    writeln!(out, "        driver.execute(PROGRAM);").unwrap();
+    for (idx, row) in program.output_map.iter().enumerate() {
+        let comment = format!("row {row}: {}", symbols.output(idx).unwrap_or_default());
+        writeln!(out, "        // {comment}\n        driver.read_row({}, outputs[{idx}]);", row.0).unwrap();
+        if convert {
+            writeln!(out, "        convert(outputs[{idx}]);").unwrap();
+        }
+    }
+    writeln!(out, "    }}\n}}").unwrap();
+    Ok(out)
+}
+
+const RUST_PRELUDE: &str = "    /// Operands are row addresses
+    #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+    pub enum Instruction {
+        AapRowCopy(u64, u64),
+        AapTra(u64, u64, u64),
+        N(u64),
+        MaskedRowCopy(u64, u64, u64),
+        Xor(u64, u64, u64),
+        And(u64, u64, u64),
+        Or(u64, u64, u64),
+        /// the flag selects control row C1 (OR) instead of C0 (AND)
+        ControlTra(u64, u64, bool),
+        DccNot(u64, u64),
+        ColumnShift(u64, i64),
+        LoopBegin(u64),
+        LoopEnd,
+    }
+
+    /// Implemented by the user to access the DRAM module
+    pub trait Driver {
+        fn write_row(&mut self, row: u64, data: &[u8]);
+        fn read_row(&mut self, row: u64, data: &mut [u8]);
+        fn execute(&mut self, program: &[Instruction]);
+    }
+";
+
+/// Opcode (name of a `PRADA_OP_*` constant) and operands of the instruction for the C stub
+fn encode(instruction: &Instruction) -> (&'static str, [i64; 3]) {
+    let row = |row: &RowAddress| row.0 as i64;
+    match instruction {
+        Instruction::AAPRowCopy(a, b) => ("AAP_ROW_COPY", [row(a), row(b), 0]),
+        Instruction::AAPTRA(a, b, c) => ("AAP_TRA", [row(a), row(b), row(c)]),
+        Instruction::N(a) => ("N", [row(a), 0, 0]),
+        Instruction::MaskedRowCopy(mask, from, to) => ("MASKED_ROW_COPY", [row(mask), row(from), row(to)]),
+        Instruction::Xor(a, b, out) => ("XOR", [row(a), row(b), row(out)]),
+        Instruction::And(a, b, out) => ("AND", [row(a), row(b), row(out)]),
+        Instruction::Or(a, b, out) => ("OR", [row(a), row(b), row(out)]),
+        Instruction::ControlTra(a, b, control) => ("CONTROL_TRA", [row(a), row(b), control.value() as i64]),
+        Instruction::DccNot(from, to) => ("DCC_NOT", [row(from), row(to), 0]),
+        Instruction::ColumnShift(a, offset) => ("COLUMN_SHIFT", [row(a), *offset, 0]),
+        Instruction::LoopBegin(count) => ("LOOP_BEGIN", [*count as i64, 0, 0]),
+        Instruction::LoopEnd => ("LOOP_END", [0, 0, 0]),
+    }
+}
+
+/// Constant name (e.g. `INPUT_A_0`), index and name of every input and output buffer. Buffers whose
+/// names don't yield a unique identifier are skipped.
+fn named_buffers<'a>(program: &Program, symbols: &'a SymbolTable) -> Vec<(String, u64, &'a str)> {
+    let inputs = (0..nr_inputs(program)).filter_map(|index| Some(("INPUT", index, symbols.input(index)?)));
+    let outputs =
+        (0..program.output_map.len()).filter_map(|idx| Some(("OUTPUT", idx as u64, symbols.output(idx)?)));
+    let mut buffers: Vec<(String, u64, &str)> = vec!();
+    for (kind, index, name) in inputs.chain(outputs) {
+        let suffix = identifier(name);
+        let constant = format!("{kind}_{suffix}");
+        if !suffix.is_empty() && buffers.iter().all(|(existing, _, _)| *existing != constant) {
+            buffers.push((constant, index, name));
+        }
+    }
+    buffers
+}
+
+/// Address and name of `row`
+fn row_comment(symbols: &SymbolTable, row: RowAddress) -> String {
+    match symbols.row(row) {
+        Some(name) => format!("row {row}: {name}"),
+        None => format!("row {row}"),
+    }
+}
+
+/// Nr of inputs the host has to provide, i.e. the highest referenced input index + 1
+fn nr_inputs(program: &Program) -> u64 {
+    program
+        .input_map
+        .iter()
+        .filter_map(|(_, init)| match init {
+            RowInit::Input { index, .. } => Some(index + 1),
+            RowInit::Constant(_) => None,
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+fn check_identifier(name: &str) -> Result<(), CompileError> {
+    let valid = name.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid {
+        return Err(CompileError::Other("stub name has to be a valid identifier"));
+    }
+    Ok(())
+}
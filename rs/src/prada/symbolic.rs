@@ -0,0 +1,171 @@
+//! Symbolic evaluation of programs: instead of bit vectors, every row holds a signal of a
+//! [MigNetwork] over the inputs of the program, i.e. the Boolean function its content is of the
+//! inputs. Independently of any input values this finds reads of uninitialized rows and
+//! instructions without any effect on the result, and it is the basis of verifying programs using
+//! SAT (see [verify_program](super::cec::verify_program)).
+use eggmock::Signal;
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use super::architecture::RowAddress;
+use super::network::MigNetwork;
+use super::program::{unrolled_indices, Instruction, Program, RowInit};
+
+#[derive(Debug, Clone)]
+pub struct SymbolicEvaluation {
+    /// Network computing the content of the rows from the inputs of the program, whose outputs are
+    /// the output rows of the program
+    pub network: MigNetwork,
+    /// Content of every initialized row after running the program
+    pub rows: FxHashMap<RowAddress, Signal>,
+}
+
+impl SymbolicEvaluation {
+    pub fn row(&self, row: RowAddress) -> Option<Signal> {
+        self.rows.get(&row).copied()
+    }
+}
+
+/// Evaluates a single invocation of `program` symbolically, failing on reads of uninitialized rows
+/// and on programs shifting between bitlines (which can't be expressed per bitline)
+pub fn evaluate_program(program: &Program) -> Result<SymbolicEvaluation, &'static str> {
+    let mut evaluator = Evaluator::new(program);
+    for instruction in program.unrolled_instructions() {
+        evaluator.execute(instruction)?;
+    }
+    let Evaluator { mut network, rows, .. } = evaluator;
+    for row in &program.output_map {
+        let signal = *rows.get(row).ok_or("read of uninitialized row")?;
+        network.add_output(signal);
+    }
+    Ok(SymbolicEvaluation { network, rows })
+}
+
+/// Indices into `program.instructions` of the instructions none of whose results are read later
+/// or left in an output or persistent row, including instructions writing the content a row
+/// already holds. Instructions in loops are only dead if they are dead in every iteration.
+pub fn dead_instructions(program: &Program) -> Result<Vec<usize>, &'static str> {
+    let instances: Vec<usize> = unrolled_indices(&program.instructions).collect();
+    let mut evaluator = Evaluator::new(program);
+    // rows read resp. changed by every executed instance
+    let mut accesses = vec!();
+    for &index in &instances {
+        let instruction = program.instructions[index];
+        let reads: Vec<RowAddress> = instruction.input_operands().collect();
+        accesses.push((reads, evaluator.execute(instruction)?));
+    }
+
+    let mut live: FxHashSet<RowAddress> = program
+        .output_map
+        .iter()
+        .copied()
+        .chain(program.persistent_rows.rows.iter().map(|(row, _)| *row))
+        .collect();
+    let mut needed = vec![false; program.instructions.len()];
+    for (&index, (reads, changed)) in instances.iter().zip(accesses).rev() {
+        if !changed.iter().any(|row| live.contains(row)) {
+            continue;
+        }
+        needed[index] = true;
+        for row in &changed {
+            live.remove(row);
+        }
+        live.extend(reads);
+    }
+    Ok(program
+        .instructions
+        .iter()
+        .enumerate()
+        .filter(|(index, instruction)| {
+            !needed[*index] && !matches!(instruction, Instruction::LoopBegin(_) | Instruction::LoopEnd)
+        })
+        .map(|(index, _)| index)
+        .collect())
+}
+
+/// Removes the [dead_instructions] from `program`, returning their nr. The cost estimates have to
+/// be updated afterwards, see [Program::update_cost_estimates].
+pub fn prune_dead_instructions(program: &mut Program) -> Result<usize, &'static str> {
+    let dead: FxHashSet<usize> = dead_instructions(program)?.into_iter().collect();
+    let mut index = 0;
+    program.instructions.retain(|_| {
+        index += 1;
+        !dead.contains(&(index - 1))
+    });
+    Ok(dead.len())
+}
+
+struct Evaluator {
+    network: MigNetwork,
+    rows: FxHashMap<RowAddress, Signal>,
+}
+
+impl Evaluator {
+    /// Evaluator holding the initial content of the input and persistent rows
+    fn new(program: &Program) -> Self {
+        let mut network = MigNetwork::new();
+        let mut inputs: Vec<Signal> = vec!();
+        let mut rows = FxHashMap::default();
+        for (row, init) in program.persistent_rows.rows.iter().chain(&program.input_map) {
+            let signal = match *init {
+                RowInit::Constant(value) => network.constant(value),
+                RowInit::Input { index, inverted } => {
+                    while inputs.len() <= index as usize {
+                        inputs.push(network.add_input());
+                    }
+                    let input = inputs[index as usize];
+                    if inverted {
+                        input.invert()
+                    } else {
+                        input
+                    }
+                }
+            };
+            rows.insert(*row, signal);
+        }
+        Self { network, rows }
+    }
+
+    fn read(&self, row: RowAddress) -> Result<Signal, &'static str> {
+        self.rows.get(&row).copied().ok_or("read of uninitialized row")
+    }
+
+    /// Executes `instruction`, returning the rows whose content has changed
+    fn execute(&mut self, instruction: Instruction) -> Result<Vec<RowAddress>, &'static str> {
+        let writes: Vec<(RowAddress, Signal)> = match instruction {
+            Instruction::AAPRowCopy(from, to) => vec!((to, self.read(from)?)),
+            Instruction::AAPTRA(a, b, c) => {
+                let maj = self.network.maj(self.read(a)?, self.read(b)?, self.read(c)?);
+                vec!((a, maj), (b, maj), (c, maj))
+            }
+            Instruction::N(a) => vec!((a, self.read(a)?.invert())),
+            Instruction::MaskedRowCopy(mask, from, to) => {
+                let value = self.network.mux(self.read(mask)?, self.read(from)?, self.read(to)?);
+                vec!((to, value))
+            }
+            Instruction::Xor(a, b, out) => vec!((out, self.network.xor(self.read(a)?, self.read(b)?))),
+            Instruction::And(a, b, out) => vec!((out, self.network.and(self.read(a)?, self.read(b)?))),
+            Instruction::Or(a, b, out) => vec!((out, self.network.or(self.read(a)?, self.read(b)?))),
+            Instruction::ControlTra(a, b, control) => {
+                let (a_value, b_value) = (self.read(a)?, self.read(b)?);
+                let value = if control.value() {
+                    self.network.or(a_value, b_value)
+                } else {
+                    self.network.and(a_value, b_value)
+                };
+                vec!((a, value), (b, value))
+            }
+            Instruction::DccNot(from, to) => vec!((to, self.read(from)?.invert())),
+            Instruction::ColumnShift(..) => {
+                return Err("programs shifting between bitlines can't be evaluated symbolically")
+            }
+            Instruction::LoopBegin(_) | Instruction::LoopEnd => return Err("loops have to be unrolled"),
+        };
+        let mut changed = vec!();
+        for (row, value) in writes {
+            if self.rows.insert(row, value) != Some(value) {
+                changed.push(row);
+            }
+        }
+        Ok(changed)
+    }
+}
@@ -0,0 +1,126 @@
+//! Symbol table of a compiled program, naming its inputs, outputs and the rows holding them. Names
+//! are taken from the [Program::metadata] (see [NodeMetadata::label](super::NodeMetadata::label))
+//! and generated otherwise: `in<index>` for inputs, `out<index>` for outputs, `state<index>` for
+//! persistent rows and `const0`/`const1` for constant rows. Inverted inputs are named `!<name>`.
+//!
+//! The names are used by the annotated listing of programs (`{:#}`, see [Program]) and the
+//! [host stubs](super::stubs).
+use rustc_hash::FxHashMap;
+
+use super::architecture::RowAddress;
+use super::diagnostics::Location;
+use super::program::{Program, RowInit};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SymbolTable {
+    inputs: Vec<String>,
+    outputs: Vec<String>,
+    /// Name of every named row, in the order in which the rows have been named
+    rows: Vec<(RowAddress, String)>,
+    row_index: FxHashMap<RowAddress, usize>,
+}
+
+impl SymbolTable {
+    pub fn new(program: &Program) -> Self {
+        let label = |location| {
+            program
+                .metadata
+                .iter()
+                .find(|(annotated, _)| *annotated == location)
+                .and_then(|(_, metadata)| metadata.label())
+        };
+        let nr_inputs = program
+            .input_map
+            .iter()
+            .filter_map(|(_, init)| match init {
+                RowInit::Input { index, .. } => Some(*index as usize + 1),
+                RowInit::Constant(_) => None,
+            })
+            .max()
+            .unwrap_or(0);
+        let mut inputs: Vec<Option<String>> = vec!(None; nr_inputs);
+        for (row, init) in &program.input_map {
+            if let RowInit::Input { index, inverted: false } = init {
+                if inputs[*index as usize].is_none() {
+                    inputs[*index as usize] = label(Location::Row(*row));
+                }
+            }
+        }
+        let inputs: Vec<String> = inputs
+            .into_iter()
+            .enumerate()
+            .map(|(index, name)| name.unwrap_or_else(|| format!("in{index}")))
+            .collect();
+        let outputs = (0..program.output_map.len())
+            .map(|idx| label(Location::Output(idx)).unwrap_or_else(|| format!("out{idx}")))
+            .collect();
+
+        let mut symbols = Self { inputs, outputs, ..Self::default() };
+        for (row, init) in &program.input_map {
+            let name = match init {
+                RowInit::Input { index, inverted } => label(Location::Row(*row)).unwrap_or_else(|| {
+                    let name = &symbols.inputs[*index as usize];
+                    if *inverted { format!("!{name}") } else { name.clone() }
+                }),
+                RowInit::Constant(value) => format!("const{}", *value as u8),
+            };
+            symbols.name_row(*row, name);
+        }
+        for (idx, (row, _)) in program.persistent_rows.rows.iter().enumerate() {
+            symbols.name_row(*row, format!("state{idx}"));
+        }
+        for (idx, row) in program.output_map.iter().enumerate() {
+            let name = symbols.outputs[idx].clone();
+            symbols.name_row(*row, name);
+        }
+        symbols
+    }
+
+    /// Names `row` unless it has already been named, i.e. rows holding inputs keep the names of
+    /// the inputs even if they hold an output at the end of the program
+    fn name_row(&mut self, row: RowAddress, name: String) {
+        if !self.row_index.contains_key(&row) {
+            self.row_index.insert(row, self.rows.len());
+            self.rows.push((row, name));
+        }
+    }
+
+    /// Name of the input with the given index (see [RowInit::Input])
+    pub fn input(&self, index: u64) -> Option<&str> {
+        self.inputs.get(index as usize).map(String::as_str)
+    }
+
+    /// Name of the output with the given index into [Program::output_map]
+    pub fn output(&self, idx: usize) -> Option<&str> {
+        self.outputs.get(idx).map(String::as_str)
+    }
+
+    /// Name of `row` if it holds an input, output, persistent value or constant
+    pub fn row(&self, row: RowAddress) -> Option<&str> {
+        self.row_index.get(&row).map(|index| self.rows[*index].1.as_str())
+    }
+
+    /// Row with the given name. If several rows have the same name (e.g. constant rows), the first
+    /// named one is returned.
+    pub fn lookup(&self, name: &str) -> Option<RowAddress> {
+        self.rows.iter().find(|(_, row_name)| row_name == name).map(|(row, _)| *row)
+    }
+
+    /// All named rows, input rows first, followed by persistent and output rows
+    pub fn rows(&self) -> impl Iterator<Item = (RowAddress, &str)> + '_ {
+        self.rows.iter().map(|(row, name)| (*row, name.as_str()))
+    }
+}
+
+/// Turns `name` into an upper-case identifier, e.g. `sum[3]` into `SUM_3`
+pub(crate) fn identifier(name: &str) -> String {
+    let mut identifier = String::new();
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() {
+            identifier.push(c.to_ascii_uppercase());
+        } else if !identifier.is_empty() && !identifier.ends_with('_') {
+            identifier.push('_');
+        }
+    }
+    identifier.trim_end_matches('_').to_string()
+}
@@ -0,0 +1,113 @@
+//! Per-iteration e-graph size telemetry, which helps to find the runner iteration in which the
+//! e-graph blows up and to tune the runner's limits accordingly.
+use std::cell::RefCell;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::mem::size_of;
+use std::path::Path;
+use std::rc::Rc;
+use std::time::Instant;
+
+use eggmock::egg::{EClass, EGraph, Id, Runner};
+use eggmock::MigLanguage;
+
+#[derive(Debug, Copy, Clone)]
+pub struct IterationSample {
+    /// Nr of runner iterations completed when the sample was taken
+    pub iteration: usize,
+    pub egraph_classes: usize,
+    pub egraph_nodes: usize,
+    pub egraph_size: usize,
+    /// Rough estimate of the memory used by the e-graph, see [estimate_memory]
+    pub memory_estimate: usize,
+    /// Time since the runner was started
+    pub t_elapsed: u128,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct EGraphTelemetry {
+    pub samples: Vec<IterationSample>,
+}
+
+impl EGraphTelemetry {
+    pub fn record(&mut self, iteration: usize, graph: &EGraph<MigLanguage, ()>, t_elapsed: u128) {
+        self.samples.push(IterationSample {
+            iteration,
+            egraph_classes: graph.number_of_classes(),
+            egraph_nodes: graph.total_number_of_nodes(),
+            egraph_size: graph.total_size(),
+            memory_estimate: estimate_memory(graph),
+            t_elapsed,
+        });
+    }
+
+    pub fn write_csv(&self, mut out: impl Write) -> io::Result<()> {
+        writeln!(out, "iteration,egraph_classes,egraph_nodes,egraph_size,memory_estimate,t_elapsed")?;
+        for sample in &self.samples {
+            writeln!(
+                out,
+                "{},{},{},{},{},{}",
+                sample.iteration,
+                sample.egraph_classes,
+                sample.egraph_nodes,
+                sample.egraph_size,
+                sample.memory_estimate,
+                sample.t_elapsed
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn write_json(&self, mut out: impl Write) -> io::Result<()> {
+        writeln!(out, "[")?;
+        for (idx, sample) in self.samples.iter().enumerate() {
+            let separator = if idx + 1 < self.samples.len() { "," } else { "" };
+            writeln!(
+                out,
+                "  {{\"iteration\": {}, \"egraph_classes\": {}, \"egraph_nodes\": {}, \"egraph_size\": {}, \"memory_estimate\": {}, \"t_elapsed\": {}}}{separator}",
+                sample.iteration,
+                sample.egraph_classes,
+                sample.egraph_nodes,
+                sample.egraph_size,
+                sample.memory_estimate,
+                sample.t_elapsed
+            )?;
+        }
+        writeln!(out, "]")
+    }
+
+    /// Writes the samples as JSON if the file extension is `json` and as CSV otherwise
+    pub fn write_to_file(&self, path: &Path) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        if path.extension().is_some_and(|ext| ext == "json") {
+            self.write_json(&mut out)?;
+        } else {
+            self.write_csv(&mut out)?;
+        }
+        out.flush()
+    }
+}
+
+/// Estimates the memory used by the e-graph: every e-node is stored in its e-class and in the
+/// hashcons (together with its class id), plus the e-classes themselves. Allocation overhead and
+/// the union-find are ignored.
+pub fn estimate_memory(graph: &EGraph<MigLanguage, ()>) -> usize {
+    graph.total_size() * (2 * size_of::<MigLanguage>() + size_of::<Id>())
+        + graph.number_of_classes() * size_of::<EClass<MigLanguage, ()>>()
+}
+
+/// Adds a hook to the runner which records a sample before every iteration. Since hooks are not
+/// run after the last iteration, the final state has to be recorded separately once the runner
+/// stops.
+pub fn with_telemetry(
+    runner: Runner<MigLanguage, ()>,
+    telemetry: Rc<RefCell<EGraphTelemetry>>,
+) -> Runner<MigLanguage, ()> {
+    let start = Instant::now();
+    runner.with_hook(move |runner| {
+        telemetry
+            .borrow_mut()
+            .record(runner.iterations.len(), &runner.egraph, start.elapsed().as_millis());
+        Ok(())
+    })
+}
@@ -0,0 +1,147 @@
+//! Timing constraints of compilations, i.e. deadlines (in ns from the start of the program) by
+//! which outputs have to be computed, given by [NodeMetadata::deadline](super::NodeMetadata) of the
+//! outputs (see [compile_annotated](super::compile_annotated)):
+//! - extraction restricts the classes in the fan-in cones of constrained outputs to their
+//!   shallowest implementations (see [shallowest_nodes])
+//! - the compiler schedules the nodes of the cones with the earliest deadlines first (see
+//!   [cone_deadlines])
+//! - [Program::timing] reports the slack of every output
+use eggmock::egg::{EGraph, Id, Language};
+use eggmock::{Mig, MigLanguage, Network};
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use super::architecture::RowAddress;
+use super::compilation::reachable_nodes;
+use super::diagnostics::Location;
+use super::program::Program;
+
+/// Completion time and deadline of an output of a program
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct OutputTiming {
+    /// Estimated time (in ns) from the start of the program until the last write to the row of the
+    /// output has finished
+    pub completion: u64,
+    pub deadline: Option<u64>,
+}
+
+impl OutputTiming {
+    /// Time by which the output is computed before its deadline, negative if the deadline is missed
+    pub fn slack(&self) -> Option<i64> {
+        self.deadline.map(|deadline| deadline as i64 - self.completion as i64)
+    }
+
+    pub fn meets_deadline(&self) -> bool {
+        self.slack().unwrap_or(0) >= 0
+    }
+}
+
+/// Timing of every output of `program` according to the cost model of its architecture, with the
+/// deadlines taken from [Program::metadata]
+pub fn output_timing(program: &Program) -> Vec<OutputTiming> {
+    let mut time = 0;
+    let mut last_write: FxHashMap<RowAddress, u64> = FxHashMap::default();
+    for instruction in program.unrolled_instructions() {
+        time += program.architecture.cost_model.instruction_cost(&instruction).runtime;
+        for row in instruction.output_operands() {
+            last_write.insert(row, time);
+        }
+    }
+    program
+        .output_map
+        .iter()
+        .enumerate()
+        .map(|(idx, row)| OutputTiming {
+            completion: last_write.get(row).copied().unwrap_or(0),
+            deadline: program
+                .metadata
+                .iter()
+                .find(|(location, _)| *location == Location::Output(idx))
+                .and_then(|(_, metadata)| metadata.deadline),
+        })
+        .collect()
+}
+
+/// Earliest deadline of the outputs in whose fan-in cone each node lies, for the `deadlines` of the
+/// outputs of `network` (by index). Nodes outside of all constrained cones are omitted.
+pub fn cone_deadlines(network: &impl Network<Node = Mig>, deadlines: &[Option<u64>]) -> FxHashMap<Id, u64> {
+    let mut cones: FxHashMap<Id, u64> = FxHashMap::default();
+    let tighten = |cones: &mut FxHashMap<Id, u64>, id: Id, deadline: u64| {
+        cones.entry(id).and_modify(|existing| *existing = deadline.min(*existing)).or_insert(deadline);
+    };
+    for (output, deadline) in network.outputs().zip(deadlines) {
+        if let Some(deadline) = deadline {
+            tighten(&mut cones, output.node_id(), *deadline);
+        }
+    }
+    if cones.is_empty() {
+        return cones;
+    }
+    // users come before their operands, which hence inherit the final deadlines of their users
+    for id in reachable_nodes(network) {
+        if let (Some(deadline), Mig::Maj(operands)) = (cones.get(&id).copied(), network.node(id)) {
+            for operand in operands {
+                tighten(&mut cones, operand.node_id(), deadline);
+            }
+        }
+    }
+    cones
+}
+
+/// Restricts the classes in the fan-in cones of `roots` to the nodes of minimal depth (in MAJs),
+/// i.e. to the shallowest implementations of the roots. Classes outside of the cones aren't
+/// contained in the result.
+pub fn shallowest_nodes(graph: &EGraph<MigLanguage, ()>, roots: &[Id]) -> FxHashMap<Id, Vec<MigLanguage>> {
+    if roots.is_empty() {
+        return FxHashMap::default();
+    }
+    let depths = min_depths(graph);
+    let depth_of = |node: &MigLanguage| node_depth(graph, &depths, node);
+    let mut restricted = FxHashMap::default();
+    let mut visited = FxHashSet::default();
+    let mut stack: Vec<Id> = roots.iter().map(|root| graph.find(*root)).collect();
+    while let Some(id) = stack.pop() {
+        if !visited.insert(id) {
+            continue;
+        }
+        let Some(&depth) = depths.get(&id) else {
+            continue;
+        };
+        let nodes: Vec<MigLanguage> =
+            graph[id].iter().filter(|node| depth_of(node) == Some(depth)).cloned().collect();
+        stack.extend(nodes.iter().flat_map(|node| node.children().iter().map(|child| graph.find(*child))));
+        restricted.insert(id, nodes);
+    }
+    restricted
+}
+
+/// Minimal depth (in MAJs) of every class of `graph`
+fn min_depths(graph: &EGraph<MigLanguage, ()>) -> FxHashMap<Id, u64> {
+    let mut depths = FxHashMap::default();
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for class in graph.classes() {
+            let depth = class.iter().filter_map(|node| node_depth(graph, &depths, node)).min();
+            if let Some(depth) = depth {
+                if depth < depths.get(&class.id).copied().unwrap_or(u64::MAX) {
+                    depths.insert(class.id, depth);
+                    changed = true;
+                }
+            }
+        }
+    }
+    depths
+}
+
+/// Depth of `node` given the (so far known) depths of the classes, `None` if the depth of a child
+/// isn't known
+fn node_depth(graph: &EGraph<MigLanguage, ()>, depths: &FxHashMap<Id, u64>, node: &MigLanguage) -> Option<u64> {
+    let depth = |child: &Id| depths.get(&graph.find(*child)).copied();
+    match node {
+        MigLanguage::False | MigLanguage::Input(_) => Some(0),
+        MigLanguage::Not(child) => depth(child),
+        MigLanguage::Maj(children) => {
+            children.iter().map(depth).try_fold(0, |max, child| Some(max.max(child?))).map(|max| max + 1)
+        }
+    }
+}
@@ -0,0 +1,143 @@
+//! Export of programs as DRAM command traces for cycle-accurate simulation (e.g. using Ramulator)
+//! and cross-validation of the [CostModel] against the latencies measured by the simulator.
+use std::fmt::Write;
+
+use super::architecture::RowAddress;
+use super::cost::{CostModel, InstructionClass};
+use super::program::{Instruction, Program};
+
+/// Returns the DRAM commands issued by the program (with loops unrolled), one command per line in
+/// the format `<instruction index> <command> <rows...>`:
+/// - `AAPRowCopy(a, b)` issues `ACT a`, `ACT b`, `PRE`
+/// - `AAPTRA(a, b, c)` issues `TRA a b c`, `PRE`
+/// - `N(a)` issues `NOT a`, `PRE`
+/// - `MaskedRowCopy(m, a, b)` issues `MCOPY m a b`, `PRE`
+/// - `ColumnShift(a, offset)` issues `SHIFT a offset`, `PRE`
+/// - `Xor(a, b, out)` issues `XOR a b out`, `PRE`
+/// - `And(a, b, out)` resp. `Or(a, b, out)` issue `AND a b out` resp. `OR a b out`, `PRE`
+/// - `ControlTra(a, b, control)` issues `TRA a b C0` resp. `TRA a b C1`, `PRE`
+/// - `DccNot(from, to)` issues `DNOT from to`, `PRE`
+///
+/// The instruction index allows to attribute the simulated latencies to the instructions again,
+/// see [parse_instruction_latencies].
+pub fn ramulator_trace(program: &Program) -> String {
+    let mut out = String::new();
+    for (idx, instruction) in program.unrolled_instructions().iter().enumerate() {
+        let rows = |rows: &[RowAddress]| rows.iter().map(|row| row.0.to_string()).collect::<Vec<_>>().join(" ");
+        let commands = match instruction {
+            Instruction::AAPRowCopy(from, to) => vec!(format!("ACT {}", from.0), format!("ACT {}", to.0)),
+            Instruction::AAPTRA(a, b, c) => vec!(format!("TRA {}", rows(&[*a, *b, *c]))),
+            Instruction::N(a) => vec!(format!("NOT {}", a.0)),
+            Instruction::MaskedRowCopy(mask, from, to) => vec!(format!("MCOPY {}", rows(&[*mask, *from, *to]))),
+            Instruction::ColumnShift(a, offset) => vec!(format!("SHIFT {} {offset}", a.0)),
+            Instruction::Xor(a, b, out) => vec!(format!("XOR {}", rows(&[*a, *b, *out]))),
+            Instruction::And(a, b, out) => vec!(format!("AND {}", rows(&[*a, *b, *out]))),
+            Instruction::Or(a, b, out) => vec!(format!("OR {}", rows(&[*a, *b, *out]))),
+            Instruction::ControlTra(a, b, control) => vec!(format!("TRA {} {control}", rows(&[*a, *b]))),
+            Instruction::DccNot(from, to) => vec!(format!("DNOT {}", rows(&[*from, *to]))),
+            Instruction::LoopBegin(_) | Instruction::LoopEnd => unreachable!("loops have been unrolled"),
+        };
+        for command in commands.iter().map(String::as_str).chain(["PRE"]) {
+            writeln!(out, "{idx} {command}").unwrap();
+        }
+    }
+    out
+}
+
+/// Parses the latencies (in ns) the simulator measured for every instruction of a trace exported
+/// by [ramulator_trace], given as CSV lines `<instruction index>,<latency>` (a header line is
+/// skipped). The latencies of an instruction occurring multiple times are summed up.
+pub fn parse_instruction_latencies(text: &str, nr_instructions: usize) -> Result<Vec<u64>, &'static str> {
+    let mut latencies = vec!(None; nr_instructions);
+    for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        let (idx, latency) = line.split_once(',').ok_or("expected `<instruction index>,<latency>`")?;
+        let Ok(idx) = idx.trim().parse::<usize>() else {
+            if line.chars().any(|c| c.is_ascii_digit()) {
+                return Err("invalid instruction index");
+            }
+            // header
+            continue;
+        };
+        let latency: u64 = latency.trim().parse().map_err(|_| "invalid latency")?;
+        let entry = latencies.get_mut(idx).ok_or("instruction index out of range")?;
+        *entry = Some(entry.unwrap_or(0) + latency);
+    }
+    latencies.into_iter().map(|latency| latency.ok_or("missing latency of instruction")).collect()
+}
+
+/// Estimated and simulated runtime of all instructions of one class
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ClassDiscrepancy {
+    pub class: InstructionClass,
+    pub executions: u64,
+    pub estimated_runtime: u64,
+    pub simulated_runtime: u64,
+}
+
+impl ClassDiscrepancy {
+    /// `(estimated - simulated) / simulated`, i.e. positive if the cost model overestimates
+    pub fn relative_error(&self) -> f64 {
+        (self.estimated_runtime as f64 - self.simulated_runtime as f64) / self.simulated_runtime as f64
+    }
+
+    /// Simulated runtime of a single instruction of this class on average
+    pub fn simulated_latency(&self) -> f64 {
+        self.simulated_runtime as f64 / self.executions as f64
+    }
+}
+
+/// Comparison of the runtime estimated by a [CostModel] to the one simulated cycle-accurately, by
+/// instruction class, which helps to calibrate the latencies of the cost model
+#[derive(Debug, Clone)]
+pub struct CrossValidation {
+    /// Only classes occurring in the program
+    pub classes: Vec<ClassDiscrepancy>,
+}
+
+impl CrossValidation {
+    /// `latencies` contains the simulated latency of every instruction of the unrolled program,
+    /// see [parse_instruction_latencies]
+    pub fn new(model: &CostModel, program: &Program, latencies: &[u64]) -> Result<Self, &'static str> {
+        let instructions = program.unrolled_instructions();
+        if instructions.len() != latencies.len() {
+            return Err("nr of latencies differs from nr of instructions");
+        }
+        let report = model.estimate_instructions(&instructions);
+        let classes = InstructionClass::ALL
+            .into_iter()
+            .filter(|class| report.class(*class).executions > 0)
+            .map(|class| ClassDiscrepancy {
+                class,
+                executions: report.class(class).executions,
+                estimated_runtime: report.class(class).runtime,
+                simulated_runtime: instructions
+                    .iter()
+                    .zip(latencies)
+                    .filter(|(instruction, _)| InstructionClass::of(*instruction) == Some(class))
+                    .map(|(_, latency)| *latency)
+                    .sum(),
+            })
+            .collect();
+        Ok(Self { classes })
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::new();
+        writeln!(out, "| Class | Executions | Estimated [ns] | Simulated [ns] | Error | Simulated latency [ns] |").unwrap();
+        writeln!(out, "|---|---:|---:|---:|---:|---:|").unwrap();
+        for class in &self.classes {
+            writeln!(
+                out,
+                "| {} | {} | {} | {} | {:+.1}% | {:.1} |",
+                class.class,
+                class.executions,
+                class.estimated_runtime,
+                class.simulated_runtime,
+                100.0 * class.relative_error(),
+                class.simulated_latency(),
+            )
+            .unwrap();
+        }
+        out
+    }
+}
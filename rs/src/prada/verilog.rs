@@ -0,0 +1,77 @@
+//! Export of networks, e.g. the network chosen by the extractor, as gate-level Verilog in the format
+//! written by mockturtle's `write_verilog`, so that their equivalence to the original network can
+//! be checked independently of this crate, e.g. by ABC's `cec`.
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use eggmock::{Mig, Network, Signal};
+
+use super::compilation::reachable_nodes;
+use super::dense::node_index;
+
+/// Writes `network` as module `top` with inputs `x0, x1, ...` and outputs `y0, y1, ...`. At least
+/// `nr_inputs` inputs are declared, so that inputs which don't influence any output anymore (e.g.
+/// after rewriting) keep the interface of the original network.
+pub fn write_verilog(network: &impl Network<Node = Mig>, nr_inputs: u64, out: &mut impl Write) -> io::Result<()> {
+    // `reachable_nodes` lists users before their inputs
+    let nodes: Vec<_> = reachable_nodes(network).into_iter().rev().collect();
+    let outputs: Vec<Signal> = network.outputs().collect();
+    let nr_inputs = nodes
+        .iter()
+        .filter_map(|id| match network.node(*id) {
+            Mig::Input(index) => Some(index + 1),
+            _ => None,
+        })
+        .fold(nr_inputs, u64::max);
+    let name = |signal: Signal| {
+        let name = match network.node(signal.node_id()) {
+            Mig::False => return if signal.is_inverted() { "1'b1" } else { "1'b0" }.to_string(),
+            Mig::Input(index) => format!("x{index}"),
+            Mig::Maj(_) => format!("n{}", node_index(signal.node_id())),
+        };
+        if signal.is_inverted() {
+            format!("~{name}")
+        } else {
+            name
+        }
+    };
+
+    let inputs: Vec<String> = (0..nr_inputs).map(|index| format!("x{index}")).collect();
+    let output_names: Vec<String> = (0..outputs.len()).map(|index| format!("y{index}")).collect();
+    let wires: Vec<String> = nodes
+        .iter()
+        .filter(|id| matches!(network.node(**id), Mig::Maj(_)))
+        .map(|id| format!("n{}", node_index(*id)))
+        .collect();
+    let ports: Vec<&str> = inputs.iter().chain(&output_names).map(String::as_str).collect();
+    writeln!(out, "module top( {} );", ports.join(" , "))?;
+    for (kind, names) in [("input", &inputs), ("output", &output_names), ("wire", &wires)] {
+        if !names.is_empty() {
+            writeln!(out, "  {kind} {} ;", names.join(" , "))?;
+        }
+    }
+    for id in nodes {
+        if let Mig::Maj(operands) = network.node(id) {
+            let [a, b, c] = operands.map(name);
+            writeln!(out, "  assign n{} = ( {a} & {b} ) | ( {a} & {c} ) | ( {b} & {c} ) ;", node_index(id))?;
+        }
+    }
+    for (index, output) in outputs.into_iter().enumerate() {
+        writeln!(out, "  assign y{index} = {} ;", name(output))?;
+    }
+    writeln!(out, "endmodule")
+}
+
+/// Same as [write_verilog], but returns the Verilog as string
+pub fn to_verilog(network: &impl Network<Node = Mig>, nr_inputs: u64) -> String {
+    let mut out = vec!();
+    write_verilog(network, nr_inputs, &mut out).expect("writing to a Vec shouldn't fail");
+    String::from_utf8(out).expect("Verilog should be valid UTF-8")
+}
+
+pub fn write_to_file(network: &impl Network<Node = Mig>, nr_inputs: u64, path: &Path) -> io::Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    write_verilog(network, nr_inputs, &mut out)?;
+    out.flush()
+}
@@ -0,0 +1,138 @@
+//! Waveforms of simulated programs in the Value Change Dump format (VCD, IEEE 1364), viewable in
+//! e.g. GTKWave: the content of selected rows over "instruction time", i.e. time step `t` holds the
+//! rows after the `t`-th executed instruction. Every row becomes a 64-bit signal (bit `j` being
+//! bitline `j`) named after the [symbol table](super::symbols) of the program.
+use std::fmt::Write;
+
+use rustc_hash::FxHashMap;
+
+use super::architecture::RowAddress;
+use super::program::Program;
+use super::simulation::{ExecutedInstruction, SimulationObserver, Simulator};
+
+/// [SimulationObserver] recording the changes of the content of the given rows
+#[derive(Debug, Clone)]
+pub struct VcdRecorder {
+    /// VCD identifier of every recorded row
+    codes: FxHashMap<RowAddress, String>,
+    /// Declarations and initial values
+    header: String,
+    changes: String,
+    /// Nr of instructions executed so far
+    time: u64,
+    /// Time of the last recorded change
+    last_change: u64,
+}
+
+impl VcdRecorder {
+    /// Records the `rows` (with the names of the signals) starting from their current content in
+    /// `simulator`, which has to be the simulator the recorder is passed to
+    pub fn new<'a>(simulator: &Simulator, rows: impl IntoIterator<Item = (RowAddress, &'a str)>) -> Self {
+        let mut header = format!(
+            "$version {} {} $end\n$comment time unit: one executed instruction $end\n$timescale 1ns $end\n",
+            env!("CARGO_PKG_NAME"),
+            env!("CARGO_PKG_VERSION"),
+        );
+        header.push_str("$scope module program $end\n");
+        let mut codes = FxHashMap::default();
+        let mut references: FxHashMap<String, usize> = FxHashMap::default();
+        let mut initial = String::new();
+        for (row, name) in rows {
+            if codes.contains_key(&row) {
+                continue;
+            }
+            let code = identifier_code(codes.len());
+            let mut reference = reference(name);
+            // rows with the same name (e.g. constant rows) are told apart by their addresses
+            let duplicates = references.entry(reference.clone()).or_insert(0);
+            *duplicates += 1;
+            if *duplicates > 1 {
+                reference = format!("{reference}@{}", row.0);
+            }
+            writeln!(header, "$var wire 64 {code} {reference} $end").unwrap();
+            writeln!(initial, "{}", value_change(simulator.row(row), &code)).unwrap();
+            codes.insert(row, code);
+        }
+        write!(header, "$upscope $end\n$enddefinitions $end\n#0\n$dumpvars\n{initial}$end\n").unwrap();
+        Self { codes, header, changes: String::new(), time: 0, last_change: 0 }
+    }
+
+    /// Records the rows named by the symbol table of `program`, i.e. its inputs, persistent rows and
+    /// outputs (see [SymbolTable::rows](super::symbols::SymbolTable::rows))
+    pub fn named_rows(simulator: &Simulator, program: &Program) -> Self {
+        let symbols = program.symbols();
+        Self::new(simulator, symbols.rows())
+    }
+
+    /// The recorded waveform
+    pub fn finish(self) -> String {
+        let mut waveform = self.header + &self.changes;
+        // the last time step marks the end of the program, so that viewers show the final content
+        if self.time > self.last_change {
+            writeln!(waveform, "#{}", self.time).unwrap();
+        }
+        waveform
+    }
+}
+
+impl SimulationObserver for VcdRecorder {
+    fn executed(&mut self, executed: &ExecutedInstruction, _: &Simulator) {
+        self.time += 1;
+        for write in &executed.writes {
+            let Some(code) = self.codes.get(&write.row) else {
+                continue;
+            };
+            if write.previous == Some(write.value) {
+                continue;
+            }
+            if self.last_change < self.time {
+                self.last_change = self.time;
+                writeln!(self.changes, "#{}", self.time).unwrap();
+            }
+            writeln!(self.changes, "{}", value_change(Some(write.value), code)).unwrap();
+        }
+    }
+}
+
+/// Simulates `program` on the given input values (see [simulate](super::simulation::simulate)) and
+/// returns the waveform of the rows named by its symbol table
+pub fn vcd(program: &Program, inputs: &[u64]) -> Result<String, &'static str> {
+    let mut simulator = Simulator::new();
+    simulator.load_persistent_rows(program, &[])?;
+    simulator.load_inputs(program, inputs)?;
+    let mut recorder = VcdRecorder::named_rows(&simulator, program);
+    simulator.run_observed(&program.instructions, &mut recorder)?;
+    Ok(recorder.finish())
+}
+
+/// Value change of a 64-bit signal, `x` if the row has never been written
+fn value_change(value: Option<u64>, code: &str) -> String {
+    match value {
+        Some(value) => format!("b{value:b} {code}"),
+        None => format!("bx {code}"),
+    }
+}
+
+/// Short identifier of the `index`-th signal, using the printable ASCII characters
+fn identifier_code(mut index: usize) -> String {
+    const FIRST: u8 = b'!';
+    const NR_CHARACTERS: usize = (b'~' - FIRST + 1) as usize;
+    let mut code = String::new();
+    loop {
+        code.push((FIRST + (index % NR_CHARACTERS) as u8) as char);
+        index /= NR_CHARACTERS;
+        if index == 0 {
+            return code;
+        }
+        index -= 1;
+    }
+}
+
+/// Turns `name` into a VCD reference, which mustn't contain whitespace and which viewers would take
+/// a trailing `[<index>]` of as bit select, e.g. `sum[3]` into `sum_3`
+fn reference(name: &str) -> String {
+    let reference: String =
+        name.chars().map(|c| if c.is_whitespace() || c == '[' || c == ']' { '_' } else { c }).collect();
+    let reference = reference.trim_end_matches('_');
+    if reference.is_empty() { "_".to_string() } else { reference.to_string() }
+}
@@ -0,0 +1,206 @@
+//! Word-level frontend: kernels are described by operations on n-bit words (bitwise operations,
+//! additions, multiplications, comparisons and multiplexers), which are bit-blasted into a [MigNetwork] using
+//! its generators (e.g. [MigNetwork::full_adder]) as they are added.
+//!
+//! Words are stored least significant bit first. Every input word occupies consecutive network
+//! inputs in the order the words are added, and every output word consecutive network outputs, so
+//! that e.g. the `i`-th bit of the first input word is network input `i`. All arithmetic wraps
+//! around, i.e. results have the width of the operands.
+use std::collections::HashMap;
+
+use eggmock::Signal;
+
+use super::architecture::PRADAArchitecture;
+use super::error::CompileError;
+use super::metadata::NodeMetadata;
+use super::network::MigNetwork;
+use super::program::Program;
+use super::{compile_annotated, CompilerSettings};
+
+/// Handle of a word of a [WordNetwork]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Word(usize);
+
+#[derive(Debug, Clone, Default)]
+pub struct WordNetwork {
+    network: MigNetwork,
+    /// Bits of every word, least significant bit first
+    words: Vec<Vec<Signal>>,
+    /// Names of the bits of the input and output words
+    metadata: HashMap<Signal, NodeMetadata>,
+}
+
+impl WordNetwork {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn add_word(&mut self, bits: Vec<Signal>) -> Word {
+        self.words.push(bits);
+        Word(self.words.len() - 1)
+    }
+
+    pub fn width(&self, word: Word) -> usize {
+        self.words[word.0].len()
+    }
+
+    /// Bits of `word`, least significant bit first
+    pub fn bits(&self, word: Word) -> &[Signal] {
+        &self.words[word.0]
+    }
+
+    /// Adds an input word of `width` bits, named `name` (see [NodeMetadata::bit])
+    pub fn input(&mut self, name: &str, width: usize) -> Word {
+        let bits: Vec<Signal> = (0..width).map(|_| self.network.add_input()).collect();
+        for (bit, signal) in bits.iter().enumerate() {
+            self.metadata.insert(*signal, NodeMetadata::bit(name, bit as u32));
+        }
+        self.add_word(bits)
+    }
+
+    /// Word of `width` bits holding the lowest `width` bits of `value`
+    pub fn constant(&mut self, value: u64, width: usize) -> Word {
+        let bits = (0..width).map(|bit| self.network.constant(bit < 64 && (value >> bit) & 1 == 1)).collect();
+        self.add_word(bits)
+    }
+
+    /// Adds the bits of `word` as outputs, named `name`
+    pub fn output(&mut self, name: &str, word: Word) {
+        for (bit, signal) in self.words[word.0].clone().into_iter().enumerate() {
+            self.network.add_output(signal);
+            self.metadata.insert(signal, NodeMetadata::bit(name, bit as u32));
+        }
+    }
+
+    /// Bits of `a` and `b`, which have to have the same width
+    fn operands(&self, a: Word, b: Word) -> Result<(Vec<Signal>, Vec<Signal>), &'static str> {
+        if self.width(a) != self.width(b) {
+            return Err("operands have to have the same width");
+        }
+        Ok((self.words[a.0].clone(), self.words[b.0].clone()))
+    }
+
+    /// Bitwise inversion, which doesn't add any nodes
+    pub fn not(&mut self, a: Word) -> Word {
+        let bits = self.words[a.0].iter().map(|bit| bit.invert()).collect();
+        self.add_word(bits)
+    }
+
+    /// Bitwise majority of three words of the same width
+    pub fn maj(&mut self, a: Word, b: Word, c: Word) -> Result<Word, &'static str> {
+        let (_, c) = self.operands(a, c)?;
+        let (a, b) = self.operands(a, b)?;
+        let bits = a.iter().zip(&b).zip(&c).map(|((a, b), c)| self.network.maj(*a, *b, *c)).collect();
+        Ok(self.add_word(bits))
+    }
+
+    fn bitwise(
+        &mut self,
+        a: Word,
+        b: Word,
+        operation: fn(&mut MigNetwork, Signal, Signal) -> Signal,
+    ) -> Result<Word, &'static str> {
+        let (a, b) = self.operands(a, b)?;
+        let bits = a.iter().zip(&b).map(|(a, b)| operation(&mut self.network, *a, *b)).collect();
+        Ok(self.add_word(bits))
+    }
+
+    pub fn and(&mut self, a: Word, b: Word) -> Result<Word, &'static str> {
+        self.bitwise(a, b, MigNetwork::and)
+    }
+
+    pub fn or(&mut self, a: Word, b: Word) -> Result<Word, &'static str> {
+        self.bitwise(a, b, MigNetwork::or)
+    }
+
+    pub fn xor(&mut self, a: Word, b: Word) -> Result<Word, &'static str> {
+        self.bitwise(a, b, MigNetwork::xor)
+    }
+
+    fn add_bits(&mut self, a: &[Signal], b: &[Signal], mut carry: Signal) -> (Vec<Signal>, Signal) {
+        let mut sum = Vec::with_capacity(a.len());
+        for (a, b) in a.iter().zip(b) {
+            let (bit, next) = self.network.full_adder(*a, *b, carry);
+            sum.push(bit);
+            carry = next;
+        }
+        (sum, carry)
+    }
+
+    pub fn add(&mut self, a: Word, b: Word) -> Result<Word, &'static str> {
+        let (a, b) = self.operands(a, b)?;
+        let (sum, _) = self.add_bits(&a, &b, self.network.constant(false));
+        Ok(self.add_word(sum))
+    }
+
+    /// `a - b`, computed as `a + !b + 1`
+    pub fn sub(&mut self, a: Word, b: Word) -> Result<Word, &'static str> {
+        let (a, b) = self.operands(a, b)?;
+        let b: Vec<Signal> = b.iter().map(|bit| bit.invert()).collect();
+        let (difference, _) = self.add_bits(&a, &b, self.network.constant(true));
+        Ok(self.add_word(difference))
+    }
+
+    /// `a * b` (shift and add)
+    pub fn mul(&mut self, a: Word, b: Word) -> Result<Word, &'static str> {
+        let (a, b) = self.operands(a, b)?;
+        let width = a.len();
+        let mut product = vec!(self.network.constant(false); width);
+        for (shift, b) in b.iter().enumerate() {
+            // bits of the product below `shift` don't change anymore
+            let partial: Vec<Signal> = a[..width - shift].iter().map(|a| self.network.and(*a, *b)).collect();
+            let (sum, _) = self.add_bits(&product[shift..], &partial, self.network.constant(false));
+            product.splice(shift.., sum);
+        }
+        Ok(self.add_word(product))
+    }
+
+    /// Single bit word which is set iff `a == b`
+    pub fn eq(&mut self, a: Word, b: Word) -> Result<Word, &'static str> {
+        let (a, b) = self.operands(a, b)?;
+        let mut equal = self.network.constant(true);
+        for (a, b) in a.iter().zip(&b) {
+            let differs = self.network.xor(*a, *b);
+            equal = self.network.and(equal, differs.invert());
+        }
+        Ok(self.add_word(vec!(equal)))
+    }
+
+    /// Single bit word which is set iff `a < b` (unsigned), i.e. iff `a - b` borrows
+    pub fn lt(&mut self, a: Word, b: Word) -> Result<Word, &'static str> {
+        let (a, b) = self.operands(a, b)?;
+        let b: Vec<Signal> = b.iter().map(|bit| bit.invert()).collect();
+        let (_, carry) = self.add_bits(&a, &b, self.network.constant(true));
+        Ok(self.add_word(vec!(carry.invert())))
+    }
+
+    /// `select ? then : otherwise`, where `select` has to be a single bit word
+    pub fn mux(&mut self, select: Word, then: Word, otherwise: Word) -> Result<Word, &'static str> {
+        let [select] = self.words[select.0][..] else {
+            return Err("the selector has to be a single bit");
+        };
+        let (then, otherwise) = self.operands(then, otherwise)?;
+        let bits =
+            then.iter().zip(&otherwise).map(|(then, otherwise)| self.network.mux(select, *then, *otherwise)).collect();
+        Ok(self.add_word(bits))
+    }
+
+    /// The bit-blasted network
+    pub fn network(&self) -> &MigNetwork {
+        &self.network
+    }
+
+    /// Names of the bits of the input and output words, for [compile_annotated]
+    pub fn metadata(&self) -> &HashMap<Signal, NodeMetadata> {
+        &self.metadata
+    }
+
+    /// Compiles the bit-blasted network, naming the rows after the input and output words
+    pub fn compile<'a>(
+        &self,
+        architecture: &'a PRADAArchitecture,
+        settings: CompilerSettings,
+    ) -> Result<Program<'a>, CompileError> {
+        compile_annotated(architecture, &self.network, settings, &self.metadata)
+    }
+}
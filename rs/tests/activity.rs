@@ -0,0 +1,46 @@
+//! Search for inputs maximizing the switching activity of compiled programs.
+use lime_rs::prada::activity::{bit_flips, power_report, search_activity, ActivitySearch};
+use lime_rs::prada::architecture::{PRADAArchitecture, ARCHITECTURE};
+use lime_rs::prada::cost::ChargeModel;
+use lime_rs::prada::stdlib::hamming_distance_network;
+use lime_rs::prada::{compile, CompilerSettings};
+
+#[test]
+fn worst_case_activity_bounds_average() {
+    let network = hamming_distance_network(4);
+    let program = compile(&ARCHITECTURE, &network, CompilerSettings::default()).expect("network should be compilable");
+    let search = ActivitySearch { samples: 16, rounds: 1, ..ActivitySearch::default() };
+    let report = search_activity(&program, search).expect("program should be executable");
+
+    assert!(report.average_bit_flips > 0.0);
+    assert!(report.worst_bit_flips as f64 >= report.average_bit_flips);
+    assert_eq!(bit_flips(&program, &report.worst_inputs), Ok(report.worst_bit_flips));
+    assert!(report.worst_case_energy(&program) >= program.energy_consumption_estimate as f64);
+
+    // neighbouring seeds sample different inputs
+    let seeded = |seed| search_activity(&program, ActivitySearch { seed, rounds: 0, ..search }).unwrap();
+    assert_ne!(seeded(2).worst_inputs, seeded(3).worst_inputs);
+}
+
+#[test]
+fn charge_model_reports_activations_and_toggles() {
+    let network = hamming_distance_network(4);
+    let program = compile(&ARCHITECTURE, &network, CompilerSettings::default()).expect("network should be compilable");
+    let inputs: Vec<u64> =
+        (0..network.nr_inputs()).map(|input| 0x0123_4567_89ab_cdef_u64.rotate_right(input as u32)).collect();
+    let report = power_report(&program, &inputs).expect("program should be executable");
+    assert_eq!(report.static_energy, program.energy_consumption_estimate);
+    assert_eq!(Ok(report.bit_flips), bit_flips(&program, &inputs));
+    assert!(report.activations >= program.unrolled_instructions().len() as u64);
+    assert_eq!(report.charge_energy(), report.activation_energy + report.toggle_energy);
+    assert!(report.to_string().contains(&format!("{} activations", report.activations)));
+
+    // the toggles scale with the capacitance of the cells, the activations don't
+    let cell_capacitance = 2.0 * ARCHITECTURE.charge_model.cell_capacitance;
+    let charge_model = ChargeModel { cell_capacitance, ..ChargeModel::default() };
+    let architecture = PRADAArchitecture { charge_model, ..ARCHITECTURE.clone() };
+    let program = compile(&architecture, &network, CompilerSettings::default()).expect("network should be compilable");
+    let scaled = power_report(&program, &inputs).unwrap();
+    assert_eq!(scaled.activation_energy, report.activation_energy);
+    assert!((scaled.toggle_energy - 2.0 * report.toggle_energy).abs() < 1e-6 * report.toggle_energy.max(1.0));
+}
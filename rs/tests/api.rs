@@ -0,0 +1,17 @@
+//! Compiles and simulates a network using only the items of the prelude, as a library user would.
+use lime_rs::prelude::*;
+
+#[test]
+fn compile_and_simulate_through_prelude() {
+    let architecture = Architecture::new(4, 512);
+    let mut network = MigNetwork::new();
+    let [a, b, c] = [(); 3].map(|_| network.add_input());
+    let maj = network.maj(a, b.invert(), c);
+    network.add_output(maj);
+
+    let program: Program =
+        compile(&architecture, &network, CompilerSettings::default()).expect("network should be compilable");
+    let inputs = [0b1100, 0b1010, 0b0110];
+    let expected = (inputs[0] & !inputs[1]) | (inputs[0] & inputs[2]) | (!inputs[1] & inputs[2]);
+    assert_eq!(simulate(&program, &inputs).unwrap(), vec!(expected));
+}
@@ -0,0 +1,45 @@
+//! Checks approximating networks within an error budget.
+use lime_rs::prada::approximate::{approximate, compile_approximate, ErrorBudget};
+use lime_rs::prelude::*;
+
+/// Sum of two 6-bit numbers, with the bits of the sum weighted by their significance
+fn adder() -> (MigNetwork, Vec<u64>) {
+    let mut network = MigNetwork::new();
+    let a: Vec<Signal> = (0..6).map(|_| network.add_input()).collect();
+    let b: Vec<Signal> = (0..6).map(|_| network.add_input()).collect();
+    let mut carry = network.constant(false);
+    for (a, b) in a.into_iter().zip(b) {
+        let (sum, next) = network.full_adder(a, b, carry);
+        network.add_output(sum);
+        carry = next;
+    }
+    network.add_output(carry);
+    (network, (0..7).map(|bit| 1 << bit).collect())
+}
+
+#[test]
+fn budgets_bound_the_error_rate() {
+    let (network, significance) = adder();
+    let exact = ErrorBudget { error_rate: 0.0, significance: significance.clone(), ..ErrorBudget::default() };
+    let (_, report) = approximate(&network, &exact).unwrap();
+    assert_eq!(report.error_rate, 0.0);
+
+    let budget = ErrorBudget { error_rate: 0.3, significance, ..ErrorBudget::default() };
+    let (approximated, report) = approximate(&network, &budget).unwrap();
+    assert!(report.replaced > 0 && report.approximate_majs < report.exact_majs);
+    assert!(report.error_rate > 0.0 && report.error_rate <= 0.3);
+    assert_eq!(approximated.nr_inputs(), network.nr_inputs());
+    assert_eq!(report.verified_error_rate, None);
+}
+
+#[test]
+fn compiled_approximations_are_verified_by_simulation() {
+    let (network, significance) = adder();
+    let budget = ErrorBudget { error_rate: 0.2, significance, ..ErrorBudget::default() };
+    let (program, report) = compile_approximate(&ARCHITECTURE, &network, CompilerSettings::default(), &budget).unwrap();
+    assert!(report.approximate_majs < report.exact_majs);
+    assert!(!program.instructions.is_empty());
+    // the budget is met on the sampled vectors, independent vectors may deviate slightly
+    let verified = report.verified_error_rate.expect("program should be verified");
+    assert!(verified <= 2.0 * budget.error_rate);
+}
@@ -0,0 +1,86 @@
+//! Checks serializing programs together with their integrity metadata.
+use lime_rs::prada::artifact::{architecture_fingerprint, deserialize, serialize, COMPILER_VERSION};
+use lime_rs::prada::program::ControlRow;
+use lime_rs::prada::reference::differential_test;
+use lime_rs::prada::stdlib::hamming_distance_network;
+use lime_rs::prelude::*;
+
+#[test]
+fn programs_survive_a_round_trip() {
+    let network = hamming_distance_network(4);
+    let settings = CompilerSettings::default();
+    let program = compile(&ARCHITECTURE, &network, settings).unwrap();
+    let text = serialize(&program, &settings);
+
+    let (loaded, metadata) = deserialize(&text, &ARCHITECTURE).unwrap();
+    assert_eq!(metadata.compiler_version, COMPILER_VERSION);
+    assert_eq!(metadata.architecture, architecture_fingerprint(&ARCHITECTURE));
+    assert_eq!(metadata.settings, settings.snapshot());
+    assert_eq!(loaded.instructions, program.instructions);
+    assert_eq!(loaded.input_map, program.input_map);
+    assert_eq!(loaded.output_map, program.output_map);
+    assert_eq!(loaded.persistent_rows, program.persistent_rows);
+    assert_eq!(loaded.runtime_estimate, program.runtime_estimate);
+    assert_eq!(serialize(&loaded, &settings), text);
+    assert_eq!(differential_test(&network, &loaded, 4, 3).expect("program should be executable"), None);
+}
+
+#[test]
+fn all_instructions_survive_a_round_trip() {
+    let [a, b, c] = [0, 1, 2].map(|row| RowAddress(row).local_rowaddress_to_subarray_id(SubarrayId(3)));
+    let mut program = Program::new(
+        &ARCHITECTURE,
+        vec!(
+            Instruction::AAPRowCopy(a, b),
+            Instruction::AAPTRA(a, b, c),
+            Instruction::MaskedRowCopy(a, b, c),
+            Instruction::ColumnShift(c, -1),
+            Instruction::Xor(a, b, c),
+            Instruction::And(a, b, c),
+            Instruction::Or(a, b, c),
+            Instruction::ControlTra(a, b, ControlRow::C1),
+            Instruction::DccNot(a, b),
+        ),
+    );
+    program.push_loop(3, [Instruction::N(c), Instruction::AAPRowCopy(c, a)]);
+    program.input_map = vec!((a, RowInit::Input { index: 0, inverted: true }), (b, RowInit::Constant(true)));
+    program.persistent_rows.declare(c, RowInit::Constant(false));
+    program.output_map = vec!(c);
+
+    let settings = CompilerSettings::default();
+    let (loaded, _) = deserialize(&serialize(&program, &settings), &ARCHITECTURE).unwrap();
+    assert_eq!(loaded.instructions, program.instructions);
+    assert_eq!(loaded.input_map, program.input_map);
+    assert_eq!(loaded.persistent_rows, program.persistent_rows);
+    assert_eq!(loaded.output_map, program.output_map);
+}
+
+#[test]
+fn modified_programs_are_rejected() {
+    let network = hamming_distance_network(4);
+    let settings = CompilerSettings::default();
+    let program = compile(&ARCHITECTURE, &network, settings).unwrap();
+    let text = serialize(&program, &settings);
+
+    let tampered = format!("{text}N 0.0\n");
+    assert_eq!(deserialize(&tampered, &ARCHITECTURE).err(), Some("content doesn't match its hash"));
+    let reversioned = text.replacen(COMPILER_VERSION, "lime-rs 0.0.0", 1);
+    assert!(deserialize(&reversioned, &ARCHITECTURE).is_err());
+    assert!(deserialize(&text[..text.len() / 2], &ARCHITECTURE).is_err());
+    assert!(deserialize("AAPRowCopy 0.0 0.1\n", &ARCHITECTURE).is_err());
+}
+
+#[test]
+fn programs_for_other_architectures_are_rejected() {
+    let network = hamming_distance_network(4);
+    let settings = CompilerSettings::default();
+    let program = compile(&ARCHITECTURE, &network, settings).unwrap();
+    let text = serialize(&program, &settings);
+
+    let other = Architecture { nr_dcc_rows: ARCHITECTURE.nr_dcc_rows + 1, ..ARCHITECTURE.clone() };
+    assert_ne!(architecture_fingerprint(&other), architecture_fingerprint(&ARCHITECTURE));
+    assert_eq!(
+        deserialize(&text, &other).err(),
+        Some("program has been compiled for a different architecture")
+    );
+}
@@ -0,0 +1,56 @@
+//! Checks compiled binarized neural network layers against a software implementation.
+use lime_rs::prada::architecture::ARCHITECTURE;
+use lime_rs::prada::bnn::BinaryLayer;
+use lime_rs::prada::random::Xorshift;
+use lime_rs::prada::simulation::simulate;
+use lime_rs::prada::{CompileError, CompilerSettings};
+
+const INPUT_WIDTH: usize = 12;
+
+/// Deterministic pseudo-random values
+fn random(n: usize, seed: u64) -> Vec<u64> {
+    let mut random = Xorshift::new(seed);
+    (0..n).map(|_| random.next_u64()).collect()
+}
+
+#[test]
+fn binary_layer_matches_software() {
+    let weights: Vec<Vec<bool>> = random(4, 0x0123_4567_89ab_cdef)
+        .into_iter()
+        .map(|bits| (0..INPUT_WIDTH).map(|i| bits & (1 << i) != 0).collect())
+        .collect();
+    let thresholds = vec!(1, 5, 6, 9);
+    let layer = BinaryLayer::new(weights.clone(), thresholds.clone()).unwrap();
+
+    let compiled = layer.compile(&ARCHITECTURE, CompilerSettings::default()).expect("layer should be compilable");
+    assert_eq!(compiled.layout.inputs.len(), INPUT_WIDTH);
+    assert_eq!(compiled.layout.outputs.len(), thresholds.len());
+
+    // one sample per bitline
+    let activations = random(INPUT_WIDTH, 0xdead_beef_cafe_babe);
+    let outputs = simulate(&compiled.program, &activations).expect("program should be executable");
+    for bitline in 0..64 {
+        for (neuron, (weights, threshold)) in weights.iter().zip(&thresholds).enumerate() {
+            let agreements = activations
+                .iter()
+                .zip(weights)
+                .filter(|(activation, weight)| ((*activation >> bitline) & 1 != 0) == **weight)
+                .count() as u64;
+            assert_eq!(
+                (outputs[neuron] >> bitline) & 1 != 0,
+                agreements >= *threshold,
+                "wrong activation of neuron {neuron} on bitline {bitline}"
+            );
+        }
+    }
+}
+
+#[test]
+fn compile_errors_are_returned() {
+    let layer = BinaryLayer::new(vec!(vec!(true; INPUT_WIDTH)), vec!(INPUT_WIDTH as u64)).unwrap();
+    let settings = CompilerSettings { scratch_row_budget: 4, ..CompilerSettings::default() };
+    assert_eq!(
+        layer.compile(&ARCHITECTURE, settings).err(),
+        Some(CompileError::Other("scratch row budget is too small to hold the inputs"))
+    );
+}
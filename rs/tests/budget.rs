@@ -0,0 +1,45 @@
+//! Compilation within a limited nr of scratch rows, leaving the remaining rows untouched.
+use lime_rs::prada::architecture::ARCHITECTURE;
+use lime_rs::prada::reference::differential_test;
+use lime_rs::prada::stdlib::hamming_distance_network;
+use lime_rs::prada::{compile, CompileError, CompilerSettings};
+
+#[test]
+fn budget_too_small_for_inputs_is_reported() {
+    let network = hamming_distance_network(2);
+    let settings = CompilerSettings::builder().rewrite(false).scratch_row_budget(4).build();
+    assert_eq!(
+        compile(&ARCHITECTURE, &network, settings).err(),
+        Some(CompileError::Other("scratch row budget is too small to hold the inputs"))
+    );
+}
+
+#[test]
+fn exhausted_budget_is_reported() {
+    let network = hamming_distance_network(2);
+    let settings = CompilerSettings::builder().rewrite(false).scratch_row_budget(10).build();
+    assert_eq!(
+        compile(&ARCHITECTURE, &network, settings).err(),
+        Some(CompileError::Other("scratch row budget exhausted"))
+    );
+}
+
+#[test]
+fn spilling_keeps_within_budget() {
+    let network = hamming_distance_network(2);
+    let budget = 14;
+    let settings = CompilerSettings::builder().rewrite(false).scratch_row_budget(budget).spill(1).build();
+    let program = compile(&ARCHITECTURE, &network, settings).expect("network should fit when spilling");
+
+    let lowest_row = ARCHITECTURE.rows_per_subarray - budget;
+    let rows = program
+        .instructions
+        .iter()
+        .flat_map(|instruction| instruction.used_addresses())
+        .chain(program.input_map.iter().map(|(row, _)| *row))
+        .filter(|row| row.get_subarray_id().0 == 0);
+    for row in rows {
+        assert!(row.0 >= lowest_row, "row {row} is outside of the budget");
+    }
+    assert_eq!(differential_test(&network, &program, 4, 3).expect("program should be executable"), None);
+}
@@ -0,0 +1,35 @@
+//! Checks the handoff contracts and serialization of bundles of several kernels.
+use lime_rs::prada::architecture::{RowAddress, ARCHITECTURE};
+use lime_rs::prada::bundle::{Bundle, HandoffCopy};
+use lime_rs::prada::stdlib::{bitmap_scan, BitmapOp};
+
+fn bundle() -> Bundle<'static> {
+    let mut bundle = Bundle::new(&ARCHITECTURE);
+    let and = bitmap_scan(&ARCHITECTURE, BitmapOp::And, 2).unwrap().program;
+    let or = bitmap_scan(&ARCHITECTURE, BitmapOp::Or, 2).unwrap().program;
+    bundle.add("and", and).unwrap();
+    bundle.add("or", or).unwrap();
+    bundle.connect("and", 1, "or", 0).unwrap();
+    bundle
+}
+
+#[test]
+fn handoffs_copy_outputs_into_inputs() {
+    let mut bundle = bundle();
+    assert!(bundle.connect("or", 0, "and", 0).is_err(), "handoffs must not go backwards");
+    let copies = bundle.handoff_copies(&bundle.handoffs[0]).unwrap();
+    // the results follow the two data rows
+    assert_eq!(copies, vec!(HandoffCopy { from: RowAddress(3), to: RowAddress(0), inverted: false }));
+}
+
+#[test]
+fn archive_contains_manifest_and_programs() {
+    let bundle = bundle();
+    let mut archive = vec!();
+    bundle.write_archive(&mut archive).unwrap();
+    assert_eq!(archive.len() % 512, 0);
+    assert!(archive.starts_with(b"manifest.txt\0"));
+    let manifest = bundle.manifest().unwrap();
+    assert!(manifest.contains("[kernel and]") && manifest.contains("[kernel or]"));
+    assert!(manifest.contains("[handoff and.output 1 -> or.input 0]"));
+}
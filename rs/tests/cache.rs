@@ -0,0 +1,93 @@
+//! Checks caching compiled programs on disk, keyed by the network, architecture and settings.
+use lime_rs::prada::cache::{network_hash, CompilationCache};
+use lime_rs::prada::stdlib::hamming_distance_network;
+use lime_rs::prelude::*;
+
+fn cache(name: &str) -> CompilationCache {
+    let dir = std::env::temp_dir().join(format!("prada-cache-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    CompilationCache::new(dir)
+}
+
+#[test]
+fn repeated_compilations_return_the_cached_program() {
+    let cache = cache("repeated");
+    let network = hamming_distance_network(4);
+    let settings = CompilerSettings::default();
+    assert!(cache.lookup(&ARCHITECTURE, &network, &settings).is_none());
+    let (compiled, stored) = cache.compile(&ARCHITECTURE, &network, settings).unwrap();
+    assert!(stored.is_ok());
+    assert!(cache.path(CompilationCache::key(&ARCHITECTURE, &network, &settings)).exists());
+
+    let cached = cache.lookup(&ARCHITECTURE, &network, &settings).expect("program should be cached");
+    assert_eq!(cached.instructions, compiled.instructions);
+    assert_eq!((cached.input_map.clone(), cached.output_map.clone()), (compiled.input_map, compiled.output_map));
+    assert_eq!(cached.runtime_estimate, compiled.runtime_estimate);
+    assert_eq!(cache.compile(&ARCHITECTURE, &network, settings).unwrap().0.instructions, cached.instructions);
+
+    let other = CompilerSettings::builder().rewrite(false).build();
+    let key = |settings| CompilationCache::key(&ARCHITECTURE, &network, settings);
+    assert_ne!(key(&other), key(&settings));
+    assert!(cache.lookup(&ARCHITECTURE, &network, &other).is_none());
+    let _ = std::fs::remove_dir_all(cache.dir());
+}
+
+#[test]
+fn corrupted_entries_are_recompiled() {
+    let cache = cache("corrupted");
+    let network = hamming_distance_network(2);
+    let settings = CompilerSettings::default();
+    let (compiled, _) = cache.compile(&ARCHITECTURE, &network, settings).unwrap();
+    let path = cache.path(CompilationCache::key(&ARCHITECTURE, &network, &settings));
+    std::fs::write(&path, std::fs::read_to_string(&path).unwrap() + "LoopEnd\n").unwrap();
+    assert!(cache.lookup(&ARCHITECTURE, &network, &settings).is_none());
+    assert_eq!(cache.compile(&ARCHITECTURE, &network, settings).unwrap().0.instructions, compiled.instructions);
+    assert!(cache.lookup(&ARCHITECTURE, &network, &settings).is_some());
+    let _ = std::fs::remove_dir_all(cache.dir());
+}
+
+#[test]
+fn entries_of_other_networks_are_ignored() {
+    let cache = cache("collision");
+    let settings = CompilerSettings::default();
+    let (network, other) = (hamming_distance_network(2), hamming_distance_network(3));
+    cache.compile(&ARCHITECTURE, &network, settings).unwrap();
+
+    // an entry of `network` stored under the key of `other`, as if their keys collided
+    let key = |network| CompilationCache::key(&ARCHITECTURE, network, &settings);
+    std::fs::copy(cache.path(key(&network)), cache.path(key(&other))).unwrap();
+    assert!(cache.lookup(&ARCHITECTURE, &other, &settings).is_none());
+    let (compiled, stored) = cache.compile(&ARCHITECTURE, &other, settings).unwrap();
+    assert!(stored.is_ok());
+    assert_eq!(cache.lookup(&ARCHITECTURE, &other, &settings).unwrap().instructions, compiled.instructions);
+    let _ = std::fs::remove_dir_all(cache.dir());
+}
+
+#[test]
+fn failing_to_store_programs_is_reported() {
+    // the directory of the cache can't be created where a file exists
+    let file = std::env::temp_dir().join(format!("prada-cache-file-{}", std::process::id()));
+    std::fs::write(&file, "").unwrap();
+    let cache = CompilationCache::new(&file);
+    let network = hamming_distance_network(2);
+    let (program, stored) = cache.compile(&ARCHITECTURE, &network, CompilerSettings::default()).unwrap();
+    assert!(stored.is_err());
+    assert!(!program.instructions.is_empty());
+    let _ = std::fs::remove_file(&file);
+}
+
+#[test]
+fn network_hashes_depend_on_the_structure_only() {
+    let build = |inputs_first: bool, invert: bool| {
+        let mut network = MigNetwork::new();
+        let [a, b] = [(); 2].map(|_| network.add_input());
+        if !inputs_first {
+            network.or(b, a);
+        }
+        let and = network.and(a, b);
+        network.add_output(if invert { and.invert() } else { and });
+        network
+    };
+    assert_eq!(network_hash(&build(true, false)), network_hash(&build(false, false)));
+    assert_ne!(network_hash(&build(true, false)), network_hash(&build(true, true)));
+}
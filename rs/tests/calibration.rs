@@ -0,0 +1,77 @@
+//! Calibration of the cost model from measurements and its use for the estimates of compiled
+//! programs.
+use lime_rs::prada::architecture::PRADAArchitecture;
+use lime_rs::prada::cost::{CostModel, CostProvenance, InstructionClass};
+use lime_rs::prelude::*;
+
+#[test]
+fn costs_are_fitted_to_measurements() {
+    let measurements = "
+        operation, latency, energy
+        tra, 40, 100
+        tra, 50, 110
+        not, 11, 20
+    ";
+    let model = CostModel::calibrate(measurements, "single executions").unwrap();
+    let cost = |class| {
+        let cost = model.cost(class);
+        (cost.runtime, cost.energy_consumption)
+    };
+    assert_eq!(cost(InstructionClass::Tra), (45, 105));
+    assert_eq!(cost(InstructionClass::Not), (11, 20));
+    let default = CostModel::default().cost(InstructionClass::RowCopy);
+    assert_eq!(cost(InstructionClass::RowCopy), (default.runtime, default.energy_consumption));
+    assert_eq!(
+        model.provenance,
+        Some(CostProvenance {
+            source: "single executions".to_string(),
+            samples: vec!((InstructionClass::Tra, 2), (InstructionClass::Not, 1)),
+        })
+    );
+    assert_eq!(CostModel::default().provenance, None);
+
+    // every line contains the totals of `count` executions, columns in any order
+    let measurements = "count,energy,operation,latency\n10,1000,xor,600\n30,2900,xor,1700\n";
+    let model = CostModel::calibrate(measurements, "batches").unwrap();
+    let xor = model.cost(InstructionClass::Xor);
+    // (10 * 600 + 30 * 1700) / (10^2 + 30^2) = 57, resp. (10 * 1000 + 30 * 2900) / 1000 = 97
+    assert_eq!((xor.runtime, xor.energy_consumption), (57, 97));
+    assert_eq!(model.provenance.unwrap().samples, vec!((InstructionClass::Xor, 40)));
+}
+
+#[test]
+fn malformed_measurements_are_rejected() {
+    for (measurements, error) in [
+        ("", "missing header"),
+        ("latency,energy\n", "missing column `operation`"),
+        ("operation,energy\n", "missing column `latency`"),
+        ("operation,latency\n", "missing column `energy`"),
+        ("operation,latency,energy\nmaj,1,1", "unknown operation"),
+        ("operation,latency,energy\ntra,1", "missing field"),
+        ("operation,latency,energy\ntra,-1,1", "invalid latency"),
+        ("operation,latency,energy\ntra,1,x", "invalid energy"),
+        ("operation,latency,energy,count\ntra,1,1,", "invalid count"),
+    ] {
+        assert_eq!(CostModel::calibrate(measurements, "").unwrap_err(), error, "{measurements}");
+    }
+}
+
+#[test]
+fn compiled_programs_are_estimated_with_the_calibrated_model() {
+    let mut network = MigNetwork::new();
+    let [a, b, c] = [(); 3].map(|_| network.add_input());
+    let (sum, carry) = network.full_adder(a, b, c);
+    network.add_output(sum);
+    network.add_output(carry);
+
+    let model = CostModel::calibrate("operation,latency,energy\ntra,1000,1\n", "slow TRAs").unwrap();
+    let architecture = PRADAArchitecture { cost_model: model.clone(), ..ARCHITECTURE.clone() };
+    let program = compile(&architecture, &network, CompilerSettings::default()).expect("network should be compilable");
+    let mut reestimated = program.clone();
+    reestimated.update_cost_estimates(&model);
+    assert_eq!(program.runtime_estimate, reestimated.runtime_estimate);
+    reestimated.update_cost_estimates(&CostModel::default());
+    let tras = model.estimate(&program).class(InstructionClass::Tra).executions;
+    let default_tra = CostModel::default().cost(InstructionClass::Tra).runtime;
+    assert_eq!(program.runtime_estimate - reestimated.runtime_estimate, tras * (1000 - default_tra));
+}
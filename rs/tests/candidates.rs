@@ -0,0 +1,63 @@
+//! Ordering of the candidate queue and tracking of changed values in the value states.
+use std::cmp::Reverse;
+
+use eggmock::{Id, Mig, Signal};
+use lime_rs::prada::candidates::{CandidateKey, CandidateQueue, ValueStates};
+use lime_rs::prelude::*;
+use rustc_hash::{FxHashMap, FxHashSet};
+
+fn id(index: usize) -> Id {
+    Id::from(eggmock::egg::Id::from(index))
+}
+
+fn key(missing_operands: u8, priority: usize) -> CandidateKey {
+    CandidateKey { missing_operands, deadline: u64::MAX, priority: Reverse(priority), users: 1, not_output: true }
+}
+
+#[test]
+fn candidates_are_ordered_by_key() {
+    let node = Mig::Input(0);
+    let mut queue = CandidateQueue::default();
+    assert!(queue.is_empty() && queue.first().is_none());
+    queue.insert(id(1), node, key(1, 0));
+    queue.insert(id(2), node, key(0, 1));
+    queue.insert(id(3), node, key(0, 5));
+    assert_eq!(queue.len(), 3);
+    // higher priorities first
+    assert_eq!(queue.first(), Some((id(3), key(0, 5), node)));
+
+    // updating a key replaces the previous one
+    queue.insert(id(3), node, key(2, 5));
+    assert_eq!(queue.len(), 3);
+    assert_eq!(queue.get(id(3)), Some((key(2, 5), node)));
+    assert_eq!(queue.first(), Some((id(2), key(0, 1), node)));
+
+    // ties are broken by the node index
+    queue.insert(id(1), node, key(0, 1));
+    assert_eq!(queue.first().map(|(id, ..)| id), Some(id(1)));
+
+    assert!(queue.remove(id(1)));
+    assert!(!queue.remove(id(1)));
+    assert_eq!(queue.get(id(1)), None);
+    assert_eq!(queue.first().map(|(id, ..)| id), Some(id(2)));
+    assert!(queue.remove(id(2)) && queue.remove(id(3)));
+    assert!(queue.is_empty() && queue.first().is_none());
+}
+
+#[test]
+fn changed_values_are_tracked() {
+    let (a, b) = (Signal::new(id(1), false), Signal::new(id(2), true));
+    let mut states = ValueStates::new(FxHashMap::from_iter([(a, RowAddress(10))]));
+    assert_eq!(states.get(&a), Some(&RowAddress(10)));
+    // the initial values don't count as changed
+    assert!(states.take_changed().is_empty());
+
+    assert_eq!(states.insert(b, RowAddress(11)), None);
+    assert_eq!(states.insert(b, RowAddress(12)), Some(RowAddress(11)));
+    assert!(states.contains_key(&b) && !states.contains_key(&b.invert()));
+    assert_eq!(states.remove(&a), Some(RowAddress(10)));
+    assert_eq!(states.remove(&a.invert()), None);
+    assert_eq!(states.take_changed(), FxHashSet::from_iter([id(1), id(2)]));
+    assert!(states.take_changed().is_empty());
+    assert_eq!(states.iter().collect::<Vec<_>>(), [(b, RowAddress(12))]);
+}
@@ -0,0 +1,59 @@
+//! Capabilities of architectures and the errors reported for operations they don't support.
+use std::collections::HashMap;
+
+use lime_rs::prada::architecture::PRADAArchitecture;
+use lime_rs::prada::compile_with_placement;
+use lime_rs::prada::simulation::evaluate_network;
+use lime_rs::prelude::*;
+
+const INPUTS: [u64; 3] = [0xf0f0_f0f0_f0f0_f0f0, 0xcccc_cccc_cccc_cccc, 0xaaaa_aaaa_aaaa_aaaa];
+
+#[test]
+fn capability_sets() {
+    let mut capabilities = Capabilities::DEFAULT;
+    assert!(capabilities.contains(Capabilities::NOT | Capabilities::INTERSUBARRAY_ROWCLONE));
+    assert!(!capabilities.contains(Capabilities::NOT | Capabilities::XOR));
+    assert_eq!(format!("{capabilities}"), "{not, intersubarray_rowclone, tra_result_in_all_rows}");
+    assert_eq!(format!("{:?}", Capabilities::XOR), "Capabilities{xor}");
+
+    capabilities.insert(Capabilities::XOR);
+    capabilities.remove(Capabilities::NOT | Capabilities::TRA_RESULT_IN_ALL_ROWS);
+    assert_eq!(capabilities, Capabilities::INTERSUBARRAY_ROWCLONE | Capabilities::XOR);
+    assert_eq!(capabilities.difference(Capabilities::XOR), Capabilities::INTERSUBARRAY_ROWCLONE);
+    assert!(capabilities.difference(capabilities).is_empty());
+    assert_eq!(Capabilities::default(), Capabilities::empty());
+    assert_eq!(format!("{}", Capabilities::empty()), "{}");
+}
+
+#[test]
+fn missing_capabilities_are_reported() {
+    assert_eq!(ARCHITECTURE.require(Capabilities::NOT, "N"), Ok(()));
+    let error = ARCHITECTURE.require(Capabilities::NOT | Capabilities::COLUMN_SHIFT | Capabilities::XOR, "shifted XOR");
+    let missing = Capabilities::COLUMN_SHIFT | Capabilities::XOR;
+    assert_eq!(error, Err(CompileError::UnsupportedOperation { operation: "shifted XOR", missing }));
+    assert_eq!(
+        error.unwrap_err().to_string(),
+        "unsupported operation shifted XOR: architecture is missing {column_shift, xor}"
+    );
+}
+
+#[test]
+fn negations_require_not() {
+    let mut network = MigNetwork::new();
+    let [a, b, c] = [(); 3].map(|_| network.add_input());
+    let maj = network.maj(a, b, c);
+    let out = network.maj(maj.invert(), a, b);
+    network.add_output(out);
+    let edges = network.with_backward_edges();
+
+    let program = compile_with_placement(&ARCHITECTURE, &edges, HashMap::new()).expect("network should be compilable");
+    assert_eq!(simulate(&program, &INPUTS).unwrap(), evaluate_network(&network, &INPUTS).unwrap());
+
+    let mut capabilities = Capabilities::DEFAULT;
+    capabilities.remove(Capabilities::NOT);
+    let architecture = PRADAArchitecture { capabilities, ..ARCHITECTURE.clone() };
+    assert_eq!(
+        compile_with_placement(&architecture, &edges, HashMap::new()).unwrap_err(),
+        CompileError::UnsupportedOperation { operation: "N", missing: Capabilities::NOT }
+    );
+}
@@ -0,0 +1,75 @@
+//! Checks the SAT-based equivalence checking of networks.
+use lime_rs::prada::cec::{check_equivalence, verify_program, Equivalence};
+use lime_rs::prada::simulation::evaluate_network;
+use lime_rs::prada::stdlib::hamming_distance_network;
+use lime_rs::prelude::*;
+
+#[test]
+fn equivalent_networks() {
+    // XOR built from AND/OR vs. from MAJs directly
+    let mut a = MigNetwork::new();
+    let [x, y] = [(); 2].map(|_| a.add_input());
+    let xor = a.xor(x, y);
+    a.add_output(xor);
+
+    let mut b = MigNetwork::new();
+    let [x, y] = [(); 2].map(|_| b.add_input());
+    let zero = b.constant(false);
+    let one = b.constant(true);
+    let or = b.maj(x, y, one);
+    let nand = b.maj(x.invert(), y.invert(), one);
+    let xor = b.maj(or, nand, zero);
+    b.add_output(xor);
+
+    assert_eq!(check_equivalence(&a, &b), Ok(Equivalence::Equivalent));
+}
+
+#[test]
+fn counterexamples_distinguish_the_networks() {
+    let mut a = MigNetwork::new();
+    let [x, y, z] = [(); 3].map(|_| a.add_input());
+    let maj = a.maj(x, y, z);
+    a.add_output(maj);
+
+    let mut b = MigNetwork::new();
+    let [x, y, _] = [(); 3].map(|_| b.add_input());
+    let and = b.and(x, y);
+    b.add_output(and);
+
+    let Ok(Equivalence::Counterexample(inputs)) = check_equivalence(&a, &b) else {
+        panic!("MAJ and AND should differ");
+    };
+    // the functions only differ if z is set and exactly one of x and y
+    assert_eq!(inputs.len(), 3);
+    assert!(inputs[2] && inputs[0] != inputs[1], "{inputs:?}");
+}
+
+#[test]
+fn networks_with_different_outputs_are_rejected() {
+    let mut a = MigNetwork::new();
+    let x = a.add_input();
+    a.add_output(x);
+    assert!(check_equivalence(&a, &MigNetwork::new()).is_err());
+}
+
+#[test]
+fn rewriting_is_verified() {
+    let settings = CompilerSettings::builder().verify_rewrite(true).build();
+    compile(&ARCHITECTURE, &hamming_distance_network(8), settings).expect("rewriting should be correct");
+}
+
+#[test]
+fn compiled_programs_are_verified() {
+    // 32 inputs are too many for exhaustive simulation
+    let network = hamming_distance_network(16);
+    let mut program = compile(&ARCHITECTURE, &network, CompilerSettings::default()).unwrap();
+    assert_eq!(verify_program(&network, &program), Ok(Equivalence::Equivalent));
+
+    // inverting the row of the least significant output bit breaks the program
+    program.instructions.push(Instruction::N(program.output_map[0]));
+    let Ok(Equivalence::Counterexample(inputs)) = verify_program(&network, &program) else {
+        panic!("corrupted program should be detected");
+    };
+    let inputs: Vec<u64> = inputs.into_iter().map(|value| if value { u64::MAX } else { 0 }).collect();
+    assert_ne!(simulate(&program, &inputs).unwrap(), evaluate_network(&network, &inputs).unwrap());
+}
@@ -0,0 +1,32 @@
+//! Constant rows are only materialized if the compiled network uses a constant.
+use lime_rs::prelude::*;
+
+fn constant_rows(program: &Program) -> Vec<bool> {
+    program
+        .input_map
+        .iter()
+        .filter_map(|(_, init)| match init {
+            RowInit::Constant(value) => Some(*value),
+            RowInit::Input { .. } => None,
+        })
+        .collect()
+}
+
+#[test]
+fn constants_are_materialized_on_use() {
+    let settings = CompilerSettings::builder().rewrite(false).build();
+    let mut network = MigNetwork::new();
+    let [a, b, c] = [(); 3].map(|_| network.add_input());
+    let maj = network.maj(a, b, c);
+    network.add_output(maj);
+    let program = compile(&ARCHITECTURE, &network, settings).expect("network should be compilable");
+    assert_eq!(constant_rows(&program), vec!());
+
+    let mut network = MigNetwork::new();
+    let [a, b] = [(); 2].map(|_| network.add_input());
+    let and = network.and(a, b);
+    network.add_output(and);
+    let program = compile(&ARCHITECTURE, &network, settings).expect("network should be compilable");
+    assert_eq!(constant_rows(&program), vec!(false, true));
+    assert_eq!(simulate(&program, &[0b1100, 0b1010]).unwrap(), vec!(0b1000));
+}
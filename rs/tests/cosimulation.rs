@@ -0,0 +1,61 @@
+//! Checks observing the instructions executed by the simulator, e.g. for co-simulating programs.
+use lime_rs::prada::architecture::RowAddress;
+use lime_rs::prada::simulation::{simulate_observed, ExecutedInstruction, SimulationObserver};
+use lime_rs::prada::stdlib::hamming_distance_network;
+use lime_rs::prelude::*;
+
+/// Power model charging every changed bitline, more for writes to previously unwritten rows
+#[derive(Default)]
+struct PowerModel {
+    charge: u64,
+}
+
+impl SimulationObserver for PowerModel {
+    fn executed(&mut self, executed: &ExecutedInstruction, _: &Simulator) {
+        for write in &executed.writes {
+            self.charge += write.bit_flips() * if write.previous.is_some() { 2 } else { 3 };
+        }
+    }
+}
+
+#[test]
+fn observers_see_every_executed_instruction() {
+    let network = hamming_distance_network(4);
+    let program = compile(&ARCHITECTURE, &network, CompilerSettings::default()).unwrap();
+    let inputs: Vec<u64> =
+        (0..network.nr_inputs()).map(|input| 0x1234_5678_9abc_def0u64.rotate_left(input as u32)).collect();
+
+    let mut simulator = Simulator::new();
+    simulator.load_persistent_rows(&program, &[]).unwrap();
+    simulator.load_inputs(&program, &inputs).unwrap();
+    let mut events = vec!();
+    simulator
+        .run_observed(&program.instructions, &mut |executed: &ExecutedInstruction, simulator: &Simulator| {
+            for write in &executed.writes {
+                assert_eq!(simulator.row(write.row), Some(write.value));
+            }
+            events.push(executed.writes.clone());
+        })
+        .unwrap();
+    assert_eq!(events.len() as u64, simulator.executed_instructions);
+    assert_eq!(events.iter().flatten().map(|write| write.bit_flips()).sum::<u64>(), simulator.bit_flips);
+
+    let mut power = PowerModel::default();
+    assert_eq!(simulate_observed(&program, &inputs, &mut power).unwrap(), simulate(&program, &inputs).unwrap());
+    assert!(power.charge >= 2 * simulator.bit_flips);
+}
+
+#[test]
+fn loop_iterations_repeat_the_index() {
+    let row = RowAddress(0);
+    let instructions = [Instruction::LoopBegin(3), Instruction::N(row), Instruction::LoopEnd];
+    let mut simulator = Simulator::new();
+    simulator.set_row(row, 0);
+    let mut executed = vec!();
+    simulator
+        .run_observed(&instructions, &mut |instruction: &ExecutedInstruction, _: &Simulator| {
+            executed.push((instruction.index, instruction.writes[0].previous, instruction.writes[0].value))
+        })
+        .unwrap();
+    assert_eq!(executed, vec!((1, Some(0), u64::MAX), (1, Some(u64::MAX), 0), (1, Some(0), u64::MAX)));
+}
@@ -0,0 +1,53 @@
+//! Checks compiling with extraction cost functions supplied by the user.
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use eggmock::egg::{Analysis, EClass, Id};
+use eggmock::MigLanguage;
+use lime_rs::prada::reference::differential_test;
+use lime_rs::prada::stdlib::hamming_distance_network;
+use lime_rs::prada::{compile_with_cost_function, CostFn, OptCostFunction};
+use lime_rs::prelude::*;
+
+/// Minimizes the depth of the extracted network, counting the nodes it is asked for
+#[derive(Clone, Default)]
+struct Depth {
+    evaluated: Arc<AtomicUsize>,
+}
+
+impl<A: Analysis<MigLanguage>> OptCostFunction<MigLanguage, A> for Depth {
+    type Cost = u64;
+
+    fn cost<C>(&mut self, _eclass: &EClass<MigLanguage, A::Data>, enode: &MigLanguage, mut costs: C) -> Option<u64>
+    where
+        C: FnMut(Id) -> u64,
+    {
+        self.evaluated.fetch_add(1, Ordering::Relaxed);
+        let depth = match enode {
+            MigLanguage::False | MigLanguage::Input(_) => 0,
+            MigLanguage::Not(child) => costs(*child),
+            MigLanguage::Maj(children) => 1 + children.iter().map(|child| costs(*child)).max().unwrap_or(0),
+        };
+        Some(depth)
+    }
+}
+
+#[test]
+fn custom_cost_functions_are_used_for_extraction() {
+    let network = hamming_distance_network(4);
+    let depth = Depth::default();
+    let program = compile_with_cost_function(&ARCHITECTURE, &network, CompilerSettings::default(), depth.clone())
+        .expect("network should be compilable");
+    assert!(depth.evaluated.load(Ordering::Relaxed) > 0);
+    assert_eq!(differential_test(&network, &program, 4, 5).expect("program should be executable"), None);
+}
+
+#[test]
+fn closures_define_cost_functions() {
+    let network = hamming_distance_network(4);
+    // nr of nodes of the extracted network
+    let size = CostFn::new(|_: &MigLanguage, children: &[u64]| Some(1 + children.iter().sum::<u64>()));
+    let program = compile_with_cost_function(&ARCHITECTURE, &network, CompilerSettings::default(), size)
+        .expect("network should be compilable");
+    assert_eq!(differential_test(&network, &program, 4, 9).expect("program should be executable"), None);
+}
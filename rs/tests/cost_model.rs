@@ -0,0 +1,92 @@
+//! Re-estimating the cost of programs with the instruction costs of a [CostModel].
+use lime_rs::prada::cost::{CompilingCost, CostModel, InstructionClass};
+use lime_rs::prelude::*;
+
+#[test]
+fn instruction_classes() {
+    for class in InstructionClass::ALL {
+        assert_eq!(InstructionClass::from_name(class.name()), Some(class));
+        assert_eq!(class.to_string(), class.name());
+    }
+    assert_eq!(InstructionClass::from_name("maj"), None);
+
+    let row = RowAddress(0);
+    assert_eq!(InstructionClass::of(&Instruction::AAPTRA(row, row, row)), Some(InstructionClass::Tra));
+    assert_eq!(InstructionClass::of(&Instruction::N(row)), Some(InstructionClass::Not));
+    assert_eq!(InstructionClass::of::<RowAddress>(&Instruction::LoopEnd), None);
+    let marker = CostModel::default().instruction_cost::<RowAddress>(&Instruction::LoopBegin(4));
+    assert_eq!((marker.runtime, marker.energy_consumption), (0, 0));
+}
+
+#[test]
+fn loop_bodies_are_estimated_per_iteration() {
+    let model = CostModel::default();
+    let (tra, copy) = (model.cost(InstructionClass::Tra), model.cost(InstructionClass::RowCopy));
+    let row = RowAddress(0);
+    let instructions = [
+        Instruction::AAPRowCopy(row, row),
+        Instruction::LoopBegin(3),
+        Instruction::AAPTRA(row, row, row),
+        Instruction::LoopBegin(2),
+        Instruction::AAPRowCopy(row, row),
+        Instruction::LoopEnd,
+        Instruction::LoopEnd,
+        // unterminated
+        Instruction::LoopBegin(5),
+        Instruction::AAPTRA(row, row, row),
+    ];
+    let report = model.estimate_instructions(&instructions);
+    assert_eq!(report.class(InstructionClass::Tra).executions, 3 + 5);
+    assert_eq!(report.class(InstructionClass::RowCopy).executions, 1 + 3 * 2);
+    assert_eq!(report.class(InstructionClass::Not).executions, 0);
+    assert_eq!(report.class(InstructionClass::Tra).runtime, 8 * tra.runtime);
+    assert_eq!(report.runtime, 8 * tra.runtime + 7 * copy.runtime);
+    assert_eq!(report.energy_consumption, 8 * tra.energy_consumption + 7 * copy.energy_consumption);
+    assert_eq!(report.total().runtime, report.runtime);
+
+    let table = report.to_string();
+    let tra_line = format!("tra 8 {} {}", 8 * tra.runtime, 8 * tra.energy_consumption);
+    assert!(table.lines().any(|line| line.split_whitespace().collect::<Vec<_>>().join(" ") == tra_line));
+    assert!(!table.contains("not"));
+    assert!(table.lines().last().unwrap().starts_with("total"));
+}
+
+#[test]
+fn estimates_of_huge_loops_saturate() {
+    let model = CostModel::default();
+    let row = RowAddress(0);
+    let instructions = [
+        Instruction::LoopBegin(u64::MAX / 2),
+        Instruction::LoopBegin(3),
+        Instruction::AAPTRA(row, row, row),
+        Instruction::LoopEnd,
+        Instruction::AAPTRA(row, row, row),
+        Instruction::LoopEnd,
+    ];
+    let report = model.estimate_instructions(&instructions);
+    assert_eq!(report.class(InstructionClass::Tra).executions, u64::MAX);
+    assert_eq!((report.runtime, report.energy_consumption), (u64::MAX, u64::MAX));
+}
+
+#[test]
+fn programs_are_reestimated_with_other_costs() {
+    let mut network = MigNetwork::new();
+    let [a, b, c] = [(); 3].map(|_| network.add_input());
+    let (sum, carry) = network.full_adder(a, b, c);
+    network.add_output(sum);
+    network.add_output(carry);
+    let mut program =
+        compile(&ARCHITECTURE, &network, CompilerSettings::default()).expect("network should be compilable");
+    let estimate = (program.runtime_estimate, program.energy_consumption_estimate);
+    program.update_cost_estimates(&ARCHITECTURE.cost_model);
+    assert_eq!((program.runtime_estimate, program.energy_consumption_estimate), estimate);
+
+    let mut model = CostModel::default();
+    let tra = model.cost(InstructionClass::Tra);
+    model.set_cost(InstructionClass::Tra, CompilingCost { runtime: tra.runtime + 10, energy_consumption: 0 });
+    let tras = model.estimate(&program).class(InstructionClass::Tra).executions;
+    assert!(tras > 0);
+    program.update_cost_estimates(&model);
+    assert_eq!(program.runtime_estimate, estimate.0 + 10 * tras);
+    assert_eq!(program.energy_consumption_estimate, estimate.1 - tras * tra.energy_consumption);
+}
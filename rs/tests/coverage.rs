@@ -0,0 +1,34 @@
+//! Checks that the compiler records the code paths exercised while compiling.
+use lime_rs::prada::architecture::{Capabilities, PRADAArchitecture};
+use lime_rs::prada::coverage::{self, CodePath, CoverageReport};
+use lime_rs::prada::network::MigNetwork;
+use lime_rs::prada::sequential::SequentialNetwork;
+
+fn hits(report: &CoverageReport, path: CodePath) -> u64 {
+    report.paths.iter().find(|(name, _)| name == path.name()).map_or(0, |(_, hits)| *hits)
+}
+
+#[test]
+fn exercised_paths_are_reported() {
+    let mut architecture = PRADAArchitecture::new(4, 512);
+    architecture.capabilities.insert(Capabilities::XOR);
+    let mut network = MigNetwork::new();
+    let input = network.add_input();
+    let parity = network.add_input();
+    let next = network.xor(parity, input);
+    network.add_output(next);
+    let sequential = SequentialNetwork::new(network, vec!(false)).unwrap();
+
+    coverage::reset();
+    assert_eq!(coverage::report().uncovered().len(), CodePath::ALL.len() + coverage::report().rules.len());
+    sequential.compile_cycle(&architecture).expect("circuit should be compilable");
+    let report = coverage::report();
+    assert!(hits(&report, CodePath::Xor) > 0, "{report}");
+    assert!(report.uncovered().contains(&format!("path {}", CodePath::Spill.name())));
+
+    let parsed = CoverageReport::parse(&report.to_string()).unwrap();
+    assert_eq!(parsed, report);
+    let mut merged = parsed.clone();
+    merged.merge(&report);
+    assert_eq!(hits(&merged, CodePath::Xor), 2 * hits(&report, CodePath::Xor));
+}
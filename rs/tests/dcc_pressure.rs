@@ -0,0 +1,45 @@
+//! Cost of inversions during extraction, depending on the pressure on the DCC rows.
+use eggmock::egg::EGraph;
+use eggmock::MigLanguage;
+use lime_rs::prada::architecture::PRADAArchitecture;
+use lime_rs::prada::cost::{CompilingCost, InstructionClass};
+use lime_rs::prada::CompilingCostFunction;
+use lime_rs::prelude::*;
+
+fn dcc_architecture(nr_dcc_rows: u64) -> PRADAArchitecture {
+    PRADAArchitecture { capabilities: Capabilities::DEFAULT | Capabilities::DCC, nr_dcc_rows, ..ARCHITECTURE.clone() }
+}
+
+#[test]
+fn inverted_leaves_do_not_count_towards_the_pressure() {
+    let mut graph = EGraph::<MigLanguage, ()>::default();
+    let [a, b, c] = [0, 1, 2].map(|index| graph.add(MigLanguage::Input(index)));
+    let not_a = graph.add(MigLanguage::Not(a));
+    let zero = graph.add(MigLanguage::False);
+    let one = graph.add(MigLanguage::Not(zero));
+    let maj = graph.add(MigLanguage::Maj([not_a, b, one]));
+    let and = graph.add(MigLanguage::Maj([maj, c, zero]));
+    graph.add(MigLanguage::Not(maj));
+    graph.add(MigLanguage::Not(and));
+    graph.rebuild();
+    assert_eq!(CompilingCostFunction::new(&ARCHITECTURE, &graph).dcc_pressure, 2);
+}
+
+#[test]
+fn inversions_are_charged_by_dcc_pressure() {
+    let n = ARCHITECTURE.cost_model.cost(InstructionClass::Not);
+    let not_cost = |architecture: &PRADAArchitecture, dcc_pressure| {
+        CompilingCostFunction { architecture, dcc_pressure }.not_cost()
+    };
+
+    // without DCC rows every inversion is an N
+    assert_eq!(not_cost(&ARCHITECTURE, 4), n);
+    assert_eq!(not_cost(&dcc_architecture(0), 4), n);
+    // enough DCC rows for every inverted signal
+    let free = CompilingCost { runtime: 0, energy_consumption: 0 };
+    assert_eq!(not_cost(&dcc_architecture(4), 4), free);
+    assert_eq!(not_cost(&dcc_architecture(8), 4), free);
+    // half of the inverted signals don't fit into the DCC rows
+    let overflow = CompilingCost { runtime: n.runtime / 2, energy_consumption: n.energy_consumption / 2 };
+    assert_eq!(not_cost(&dcc_architecture(2), 4), overflow);
+}
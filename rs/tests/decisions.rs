@@ -0,0 +1,70 @@
+//! Checks the JSON-lines log of the compiler's decisions.
+use std::ffi::CString;
+
+use eggmock::Network;
+use lime_rs::prada::architecture::ARCHITECTURE;
+use lime_rs::prada::decisions::{parse_jsonl, write_jsonl, Replay};
+use lime_rs::prada::network::MigNetwork;
+use lime_rs::prada::simulation::{evaluate_network, simulate};
+use lime_rs::prada::{compile, compile_replaying, CompileOptions, CompilerSettings};
+
+fn full_adder() -> MigNetwork {
+    let mut network = MigNetwork::new();
+    let [a, b, c] = [(); 3].map(|_| network.add_input());
+    let carry = network.maj(a, b, c);
+    let inner = network.maj(a, b, c.invert());
+    let sum = network.maj(carry.invert(), c, inner);
+    network.add_output(sum);
+    network.add_output(carry);
+    network
+}
+
+#[test]
+fn decisions_are_logged_as_json_lines() {
+    let network = full_adder();
+    let path = std::env::temp_dir().join(format!("prada-decisions-{}.jsonl", std::process::id()));
+    let c_path = CString::new(path.to_str().unwrap()).unwrap();
+    let settings = CompilerSettings { decision_log_path: c_path.as_ptr(), ..CompilerSettings::default() };
+    let program = compile(&ARCHITECTURE, &network, settings).expect("network should be compilable");
+    let log = std::fs::read_to_string(&path).expect("decision log should have been written");
+    std::fs::remove_file(&path).unwrap();
+
+    let lines: Vec<&str> = log.lines().collect();
+    assert_eq!(lines.len(), program.decisions.len());
+    assert!(lines.iter().all(|line| line.starts_with("{\"event\": \"") && line.ends_with('}')));
+    assert!(lines.iter().any(|line| line.contains("\"event\": \"schedule\"")));
+    assert!(lines.iter().any(|line| line.contains("\"event\": \"row_allocated\"")));
+
+    // without a path nothing is recorded
+    let program = compile(&ARCHITECTURE, &network, CompilerSettings::default()).expect("network should be compilable");
+    assert!(program.decisions.is_empty());
+}
+
+#[test]
+fn replaying_a_log_reproduces_the_program() {
+    let network = full_adder();
+    let options = CompileOptions { log_decisions: true, ..CompileOptions::default() };
+    let (recorded, _) = compile_replaying(&ARCHITECTURE, &network.with_backward_edges(), options, Replay::default())
+        .expect("network should be compilable");
+    let mut log = vec!();
+    write_jsonl(&recorded.decisions, &mut log).unwrap();
+    let decisions = parse_jsonl(&String::from_utf8(log).unwrap()).expect("log should be parsable");
+    assert_eq!(decisions, recorded.decisions);
+
+    let (replayed, report) = compile_replaying(&ARCHITECTURE, &network.with_backward_edges(), options, Replay::new(&decisions))
+        .expect("network should be compilable");
+    assert_eq!(replayed.instructions, recorded.instructions);
+    assert_eq!((report.diverged_schedule, report.diverged_rows), (0, 0));
+    assert!(report.followed_schedule > 0 && report.followed_rows > 0);
+
+    // appending an output keeps the ids of all other nodes, hence the recorded decisions still apply
+    let mut modified = full_adder();
+    let inputs: Vec<_> = (0..3).map(|idx| modified.input(idx).unwrap()).collect();
+    let extra = modified.maj(inputs[0].invert(), inputs[1], inputs[2]);
+    modified.add_output(extra);
+    let (program, report) = compile_replaying(&ARCHITECTURE, &modified.with_backward_edges(), options, Replay::new(&decisions))
+        .expect("modified network should be compilable");
+    assert!(report.followed_schedule > 0);
+    let inputs = [0b1111_0000, 0b1100_1100, 0b1010_1010];
+    assert_eq!(simulate(&program, &inputs).unwrap(), evaluate_network(&modified, &inputs).unwrap());
+}
@@ -0,0 +1,37 @@
+//! Maps keyed by nodes resp. signals stored in vectors indexed by the node index.
+use eggmock::Signal;
+use lime_rs::prada::dense::{node_id, node_index, NodeMap, SignalMap};
+
+#[test]
+fn node_maps_grow_with_the_largest_id() {
+    assert_eq!(node_index(node_id(42)), 42);
+    let mut map = NodeMap::new();
+    assert_eq!(map.get(node_id(3)), None);
+    assert_eq!(*map.get_or_insert_with(node_id(3), || "three"), "three");
+    assert_eq!(*map.get_or_insert_with(node_id(3), || "other"), "three");
+    assert_eq!(map.get(node_id(0)), None);
+    assert_eq!(map.get(node_id(4)), None);
+    *map.get_mut(node_id(3)).unwrap() = "changed";
+    assert_eq!(map.get(node_id(3)), Some(&"changed"));
+    assert_eq!(map.get_mut(node_id(7)), None);
+}
+
+#[test]
+fn signal_maps_keep_polarities_apart() {
+    let (a, b) = (Signal::new(node_id(2), false), Signal::new(node_id(5), false));
+    let mut map = SignalMap::new();
+    assert_eq!(map.insert(b.invert(), 1), None);
+    assert_eq!(map.insert(a, 2), None);
+    assert_eq!(map.insert(a.invert(), 3), None);
+    assert_eq!(map.insert(a, 4), Some(2));
+    assert!(map.contains_key(&b.invert()) && !map.contains_key(&b));
+    assert_eq!(map.get(&a), Some(&4));
+    // by node index, non-inverted signals first
+    assert_eq!(map.iter().collect::<Vec<_>>(), [(a, &4), (a.invert(), &3), (b.invert(), &1)]);
+
+    assert_eq!(map.remove(&a), Some(4));
+    assert_eq!(map.remove(&a), None);
+    assert_eq!(map.remove(&Signal::new(node_id(9), false)), None);
+    assert_eq!(map.get(&a.invert()), Some(&3));
+    assert_eq!(map.iter().count(), 2);
+}
@@ -0,0 +1,58 @@
+//! Checks the diagnostics collected while compiling and their JSON and text forms.
+use lime_rs::prada::diagnostics::{Diagnostic, DiagnosticCode, Location};
+use lime_rs::prada::stdlib::hamming_distance_network;
+use lime_rs::prelude::*;
+
+fn codes(program: &Program) -> Vec<DiagnosticCode> {
+    program.diagnostics.iter().map(|diagnostic| diagnostic.code).collect()
+}
+
+#[test]
+fn constant_outputs_are_reported() {
+    let mut network = MigNetwork::new();
+    let [a, b] = [(); 2].map(|_| network.add_input());
+    let and = network.and(a, b);
+    network.add_output(and);
+    network.add_output(network.constant(true));
+
+    let program = compile(&ARCHITECTURE, &network, CompilerSettings::default()).unwrap();
+    assert_eq!(codes(&program), vec!(DiagnosticCode::ConstantOutput));
+    assert_eq!(program.diagnostics[0].location, Some(Location::Output(1)));
+    assert_eq!(
+        program.diagnostics[0].to_string(),
+        "warning[constant_output]: output 1 is constant 1 (at output 1)"
+    );
+}
+
+#[test]
+fn emulated_capabilities_are_reported() {
+    let network = hamming_distance_network(4);
+    let program = compile(&ARCHITECTURE, &network, CompilerSettings::default()).unwrap();
+    assert!(program.diagnostics.is_empty());
+
+    let mut architecture = ARCHITECTURE.clone();
+    architecture.capabilities.remove(Capabilities::NOT);
+    let program = compile(&architecture, &network, CompilerSettings::default()).unwrap();
+    assert_eq!(codes(&program), vec!(DiagnosticCode::CapabilityEmulation));
+
+    // dual-rail logic which has been asked for isn't reported
+    let settings = CompilerSettings::builder().dual_rail(true).build();
+    let program = compile(&ARCHITECTURE, &network, settings).unwrap();
+    assert!(program.diagnostics.is_empty());
+}
+
+#[test]
+fn diagnostics_are_serialized_as_json() {
+    let diagnostic = Diagnostic::warning(
+        DiagnosticCode::RetentionRisk,
+        Some(Location::Row(RowAddress(3))),
+        "value in \"row\" 3",
+    );
+    assert_eq!(
+        diagnostic.to_json(),
+        r#"{"severity": "warning", "code": "retention_risk", "location": {"kind": "row", "row": 3}, "message": "value in \"row\" 3"}"#
+    );
+    for code in DiagnosticCode::ALL {
+        assert_eq!(DiagnosticCode::from_name(code.name()), Some(code));
+    }
+}
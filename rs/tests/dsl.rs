@@ -0,0 +1,49 @@
+//! Checks parsing kernels written in the s-expression language.
+use lime_rs::prada::dsl::parse_kernel;
+use lime_rs::prada::simulation::evaluate_network;
+use lime_rs::prelude::*;
+
+#[test]
+fn gate_level_kernels_declare_inputs_on_first_use() {
+    let kernel = parse_kernel(
+        "; full adder
+        (let carry (maj a b c))
+        (out sum (maj (not carry) c (maj a b (not c))))
+        (out carry carry)",
+    )
+    .unwrap();
+    assert_eq!(kernel.network().nr_inputs(), 3);
+    let outputs = evaluate_network(kernel.network(), &[0b1111_0000, 0b1100_1100, 0b1010_1010]).unwrap();
+    assert_eq!(outputs, vec!(0b1001_0110, 0b1110_1000));
+
+    let program = kernel.compile(&ARCHITECTURE, CompilerSettings::default()).unwrap();
+    assert_eq!(program.symbols().output(0), Some("sum[0]"));
+    assert_eq!(simulate(&program, &[0b1111_0000, 0b1100_1100, 0b1010_1010]).unwrap(), outputs);
+}
+
+#[test]
+fn word_level_kernels_are_bit_blasted() {
+    let kernel = parse_kernel(
+        "(input x 4) (input y 4)
+        (let sum (add x y))
+        (out clamped (mux (lt sum x) (const 15 4) sum))
+        (out zero (eq (and x y) (const 0 4)))",
+    )
+    .unwrap();
+    assert_eq!(kernel.network().nr_inputs(), 8);
+    // x = 9 and y = 8 on bitline 0, x = 3 and y = 4 on bitline 1
+    let inputs = [0b11, 0b10, 0b00, 0b01, 0b00, 0b00, 0b10, 0b01];
+    let outputs: Vec<u64> =
+        evaluate_network(kernel.network(), &inputs).unwrap().into_iter().map(|output| output & 0b11).collect();
+    // clamped: 15 and 7, zero: false and true
+    assert_eq!(outputs, vec!(0b11, 0b11, 0b11, 0b01, 0b10));
+}
+
+#[test]
+fn malformed_kernels_are_rejected() {
+    assert_eq!(parse_kernel("(out y (maj a b))").unwrap_err(), "wrong nr of operands");
+    assert_eq!(parse_kernel("(out y (nand a b))").unwrap_err(), "unknown operation");
+    assert_eq!(parse_kernel("(out y (and a b)").unwrap_err(), "unbalanced `(`");
+    assert!(parse_kernel("(out y 5)").is_err());
+    assert!(parse_kernel("(input x 4) (out y (add x a))").is_err());
+}
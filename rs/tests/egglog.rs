@@ -0,0 +1,20 @@
+//! Checks rewriting with egglog instead of egg.
+#![cfg(feature = "egglog")]
+use lime_rs::prada::reference::differential_test;
+use lime_rs::prada::stdlib::hamming_distance_network;
+use lime_rs::prelude::*;
+
+#[test]
+fn egglog_rewriting_preserves_the_function() {
+    let network = hamming_distance_network(4);
+    for sharing_guided in [false, true] {
+        let settings = CompilerSettings::builder()
+            .rewrite(true)
+            .backend(RewriteBackend::Egglog)
+            .sharing_guided_distributivity(sharing_guided)
+            .build();
+        let program = compile(&ARCHITECTURE, &network, settings).unwrap();
+        assert_eq!(differential_test(&network, &program, 4, 1).expect("program should be executable"), None);
+    }
+}
+
@@ -0,0 +1,48 @@
+//! Checks compiling with an energy budget, re-running extraction with the energy objective.
+use lime_rs::prada::cost::CompilingCost;
+use lime_rs::prada::diagnostics::DiagnosticCode;
+use lime_rs::prada::stdlib::hamming_distance_network;
+use lime_rs::prada::EnergyFirst;
+use lime_rs::prelude::*;
+
+fn energy_budget_diagnostics(program: &Program) -> usize {
+    program.diagnostics.iter().filter(|diagnostic| diagnostic.code == DiagnosticCode::EnergyBudget).count()
+}
+
+#[test]
+fn met_budgets_keep_the_program() {
+    let network = hamming_distance_network(4);
+    let unbudgeted = compile(&ARCHITECTURE, &network, CompilerSettings::default()).unwrap();
+    let settings = CompilerSettings::builder().energy_budget(unbudgeted.energy_consumption_estimate).build();
+    let program = compile(&ARCHITECTURE, &network, settings).unwrap();
+    assert_eq!(program.instructions, unbudgeted.instructions);
+    assert_eq!(energy_budget_diagnostics(&program), 0);
+}
+
+#[test]
+fn unachievable_budgets_are_reported() {
+    let network = hamming_distance_network(4);
+    let unbudgeted = compile(&ARCHITECTURE, &network, CompilerSettings::default()).unwrap();
+    for passthrough in [false, true] {
+        let settings = CompilerSettings::builder().energy_budget(1).passthrough(passthrough).build();
+        let program = compile(&ARCHITECTURE, &network, settings).unwrap();
+        assert_eq!(energy_budget_diagnostics(&program), 1);
+        if !passthrough {
+            assert!(program.energy_consumption_estimate <= unbudgeted.energy_consumption_estimate);
+        }
+    }
+
+    let strict = CompilerSettings::builder().energy_budget(1).strictness(Strictness::ENERGY_BUDGET).build();
+    assert!(matches!(
+        compile(&ARCHITECTURE, &network, strict).unwrap_err(),
+        CompileError::Escalated { code: DiagnosticCode::EnergyBudget, .. }
+    ));
+}
+
+#[test]
+fn energy_objective_orders_by_energy_first() {
+    let cheap = EnergyFirst(CompilingCost { runtime: 100, energy_consumption: 1 });
+    let fast = EnergyFirst(CompilingCost { runtime: 1, energy_consumption: 2 });
+    assert!(cheap < fast);
+    assert!(cheap.0 > fast.0);
+}
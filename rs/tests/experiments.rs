@@ -0,0 +1,44 @@
+//! Checks recording compilations in the experiment database and ranking them.
+#![cfg(feature = "sqlite")]
+use lime_rs::prada::artifact::architecture_fingerprint;
+use lime_rs::prada::cache::network_hash;
+use lime_rs::prada::experiments::{ExperimentDatabase, Metric};
+use lime_rs::prada::stdlib::hamming_distance_network;
+use lime_rs::prelude::*;
+
+#[test]
+fn compilations_are_recorded_with_their_statistics() {
+    let database = ExperimentDatabase::in_memory().unwrap();
+    let network = hamming_distance_network(4);
+    let settings = CompilerSettings::builder().rewrite(false).build();
+    let program = database.compile("hamming_4", &ARCHITECTURE, &network, settings).unwrap();
+    assert_eq!(database.count().unwrap(), 1);
+
+    let [experiment] = &database.top(Metric::Energy, None, 10).unwrap()[..] else {
+        panic!("expected a single experiment");
+    };
+    assert_eq!(experiment.benchmark, "hamming_4");
+    assert_eq!(experiment.network_hash, network_hash(&network));
+    assert_eq!(experiment.architecture_fingerprint, architecture_fingerprint(&ARCHITECTURE));
+    assert_eq!(experiment.settings, settings.snapshot());
+    assert_eq!(experiment.instructions, program.instructions.len() as u64);
+    assert_eq!(experiment.runtime_estimate, program.runtime_estimate);
+    assert_eq!(experiment.energy_consumption_estimate, program.energy_consumption_estimate);
+}
+
+#[test]
+fn top_ranks_by_the_metric_and_filters_by_benchmark() {
+    let database = ExperimentDatabase::in_memory().unwrap();
+    for bits in [8, 2, 4] {
+        let network = hamming_distance_network(bits);
+        database.compile(&format!("hamming_{bits}"), &ARCHITECTURE, &network, CompilerSettings::default()).unwrap();
+    }
+    let top = database.top(Metric::Runtime, None, 2).unwrap();
+    assert_eq!(top.len(), 2);
+    assert!(top[0].runtime_estimate <= top[1].runtime_estimate);
+    assert_eq!(top[0].benchmark, "hamming_2");
+
+    let filtered = database.top(Metric::Instructions, Some("hamming_8"), 10).unwrap();
+    assert_eq!(filtered.iter().map(|experiment| experiment.benchmark.as_str()).collect::<Vec<_>>(), ["hamming_8"]);
+    assert_eq!(Metric::from_name("compile_time"), Some(Metric::CompileTime));
+}
@@ -0,0 +1,17 @@
+//! Checks that parallel extraction chooses the same terms as sequential extraction.
+#![cfg(feature = "parallel-extraction")]
+use lime_rs::prada::architecture::ARCHITECTURE;
+use lime_rs::prada::stdlib::{aes_sbox_network, hamming_distance_network};
+use lime_rs::prada::{benchmark_extraction, CompilerSettings};
+
+#[test]
+fn parallel_extraction_matches_sequential_extraction() {
+    // `benchmark_extraction` panics if the extractors disagree on any class
+    let rewriting = CompilerSettings::builder().rewrite(true).build();
+    let result = benchmark_extraction(&ARCHITECTURE, &hamming_distance_network(64), rewriting);
+    assert!(result.classes > 0);
+    benchmark_extraction(&ARCHITECTURE, &aes_sbox_network(), rewriting);
+
+    let without_rewriting = CompilerSettings::builder().rewrite(false).build();
+    benchmark_extraction(&ARCHITECTURE, &hamming_distance_network(256), without_rewriting);
+}
@@ -0,0 +1,243 @@
+//! Drives the compiler through the same FFI entry points the C++ host uses and checks the compiled
+//! programs against a functional evaluation of the input network.
+use std::ffi::c_void;
+use std::mem::MaybeUninit;
+
+use eggmock::egg::EGraph;
+use eggmock::{MigLanguage, MigReceiverFFI, Network, Receiver};
+use lime_rs::prada::annotation::{AnnotationReceiverFFI, NodeAnnotation};
+use lime_rs::prada::architecture::ARCHITECTURE;
+use lime_rs::prada::network::MigNetwork;
+use lime_rs::prada::simulation::{evaluate_network, simulate};
+use lime_rs::prada::{compile, CompileError, CompilerSettings, SchedulingPolicy};
+
+/// Mirrors `prada_compiler_statistics` of `prada.h`
+#[repr(C)]
+#[derive(Debug)]
+struct Statistics {
+    egraph_classes: u64,
+    egraph_nodes: u64,
+    egraph_size: u64,
+
+    instruction_count: u64,
+    runtime_estimate: u64,
+    energy_consumption_estimate: u64,
+
+    inverters_before: u64,
+    inverters_after: u64,
+    emulated_inversions: u64,
+    emulation_majs: u64,
+
+    spills: u64,
+    reloads: u64,
+    rematerializations: u64,
+
+    tra_count: u64,
+    maj_count: u64,
+    copy_count: u64,
+    row_activations: u64,
+
+    t_runner: u64,
+    t_extractor: u64,
+    t_compiler: u64,
+
+    error: u32,
+}
+
+extern "C" {
+    fn prada_compile_ffi(settings: CompilerSettings) -> MigReceiverFFI<Statistics>;
+    fn prada_compile_sized_ffi(settings: *const CompilerSettings, size: usize) -> MigReceiverFFI<Statistics>;
+    fn prada_rewrite_annotated_ffi(
+        settings: CompilerSettings,
+        receiver: MigReceiverFFI<()>,
+        annotations: AnnotationReceiverFFI,
+    ) -> MigReceiverFFI<Statistics>;
+}
+
+/// Assigns every input all combinations of values, one combination per bitline
+fn exhaustive_inputs(nr_inputs: u64) -> Vec<u64> {
+    assert!(nr_inputs <= 6, "exhaustive patterns only fit into 64 bitlines for up to 6 inputs");
+    (0..nr_inputs)
+        .map(|input| {
+            (0..64).fold(0u64, |value, bitline| value | (((bitline >> input) & 1) << bitline))
+        })
+        .collect()
+}
+
+fn check_roundtrip(network: &MigNetwork) {
+    let stats = network.send(unsafe { prada_compile_ffi(CompilerSettings::default()) });
+    let program = compile(&ARCHITECTURE, network, CompilerSettings::default()).expect("network should be compilable");
+    assert_eq!(stats.error, 0);
+    assert!(stats.instruction_count > 0);
+    assert_eq!(stats.instruction_count, program.instructions.len() as u64);
+    assert_eq!(stats.runtime_estimate, program.runtime_estimate);
+    let efficiency = program.efficiency(stats.maj_count);
+    assert_eq!(stats.tra_count, efficiency.tra_count);
+    assert_eq!(stats.copy_count, efficiency.copy_count);
+    assert_eq!(stats.row_activations, efficiency.row_activations);
+
+    let inputs = exhaustive_inputs(network.nr_inputs());
+    assert_eq!(
+        simulate(&program, &inputs).expect("program should be executable"),
+        evaluate_network(network, &inputs).unwrap(),
+        "simulated program differs from network for program\n{program}"
+    );
+}
+
+#[test]
+fn single_majority() {
+    let mut network = MigNetwork::new();
+    let [a, b, c] = [(); 3].map(|_| network.add_input());
+    let maj = network.maj(a, b, c);
+    network.add_output(maj);
+    check_roundtrip(&network);
+}
+
+#[test]
+fn full_adder() {
+    let mut network = MigNetwork::new();
+    let [a, b, c] = [(); 3].map(|_| network.add_input());
+    let carry = network.maj(a, b, c);
+    let inner = network.maj(a, b, c.invert());
+    let sum = network.maj(carry.invert(), c, inner);
+    network.add_output(sum);
+    network.add_output(carry);
+    check_roundtrip(&network);
+}
+
+#[test]
+fn mux() {
+    let mut network = MigNetwork::new();
+    let [select, then, otherwise] = [(); 3].map(|_| network.add_input());
+    let mux = network.mux(select, then, otherwise);
+    network.add_output(mux);
+    check_roundtrip(&network);
+}
+
+#[test]
+fn settings_of_older_callers_get_defaults() {
+    let mut network = MigNetwork::new();
+    let [a, b, c] = [(); 3].map(|_| network.add_input());
+    let maj = network.maj(a, b, c);
+    network.add_output(maj);
+
+    // the fields from `dual_rail` on are unknown to the caller and must not be read
+    let settings = CompilerSettings { dual_rail: true, spill_subarray: 0, ..CompilerSettings::default() };
+    let size = std::mem::offset_of!(CompilerSettings, dual_rail);
+    let stats = network.send(unsafe { prada_compile_sized_ffi(&settings, size) });
+    let defaults = CompilerSettings::builder().build();
+    assert!(!defaults.dual_rail);
+    let program = compile(&ARCHITECTURE, &network, defaults).expect("network should be compilable");
+    assert_eq!(stats.instruction_count, program.instructions.len() as u64);
+}
+
+#[test]
+fn invalid_settings_are_reported() {
+    let mut network = MigNetwork::new();
+    let [a, b, c] = [(); 3].map(|_| network.add_input());
+    let maj = network.maj(a, b, c);
+    network.add_output(maj);
+
+    // C callers may pass any value for enums and bools
+    for (offset, value) in [
+        (std::mem::offset_of!(CompilerSettings, scheduler), 7u32),
+        (std::mem::offset_of!(CompilerSettings, scheduling), 3),
+        (std::mem::offset_of!(CompilerSettings, backend), 2),
+    ] {
+        let mut settings = MaybeUninit::new(CompilerSettings::default());
+        unsafe { (settings.as_mut_ptr() as *mut u8).add(offset).cast::<u32>().write_unaligned(value) };
+        let size = std::mem::size_of::<CompilerSettings>();
+        let stats = network.send(unsafe { prada_compile_sized_ffi(settings.as_ptr(), size) });
+        assert_eq!(stats.error, CompileError::Other("").code());
+    }
+    let mut settings = MaybeUninit::new(CompilerSettings::default());
+    let rewrite = std::mem::offset_of!(CompilerSettings, rewrite);
+    unsafe { (settings.as_mut_ptr() as *mut u8).add(rewrite).write(2) };
+    let size = std::mem::size_of::<CompilerSettings>();
+    let stats = network.send(unsafe { prada_compile_sized_ffi(settings.as_ptr(), size) });
+    assert_eq!(stats.error, CompileError::Other("").code());
+
+    // fields not completely within the size are unknown to the caller
+    let size = std::mem::offset_of!(CompilerSettings, scheduler) + 2;
+    let stats = network.send(unsafe { prada_compile_sized_ffi(settings.as_ptr(), size) });
+    assert_eq!(stats.error, CompileError::Other("").code(), "`rewrite` is still read");
+    let valid = CompilerSettings { scheduling: SchedulingPolicy::SethiUllman, ..CompilerSettings::default() };
+    let size = std::mem::offset_of!(CompilerSettings, scheduling) + 2;
+    let stats = network.send(unsafe { prada_compile_sized_ffi(&valid, size) });
+    assert_eq!(stats.error, 0);
+}
+
+#[test]
+fn errors_are_reported_as_codes() {
+    let mut network = MigNetwork::new();
+    let [a, b, c] = [(); 3].map(|_| network.add_input());
+    let maj = network.maj(a, b, c);
+    network.add_output(maj);
+
+    // not even the inputs fit into the budget
+    let settings = CompilerSettings { scratch_row_budget: 2, ..CompilerSettings::default() };
+    let error = compile(&ARCHITECTURE, &network, settings).unwrap_err();
+    assert!(matches!(error, CompileError::Other(_)));
+    let stats = network.send(unsafe { prada_compile_ffi(settings) });
+    assert_eq!(stats.error, error.code());
+    assert_eq!((stats.instruction_count, stats.runtime_estimate, stats.t_compiler), (0, 0, 0));
+
+    let size = std::mem::size_of::<CompilerSettings>();
+    assert_eq!(network.send(unsafe { prada_compile_sized_ffi(&settings, size) }).error, error.code());
+}
+
+/// Annotations collected by the callbacks of an [AnnotationReceiverFFI]
+#[derive(Default)]
+struct Annotations {
+    nodes: Vec<NodeAnnotation>,
+    outputs: Vec<(u64, u64)>,
+}
+
+extern "C" fn annotate_node(data: *mut c_void, annotation: NodeAnnotation) {
+    unsafe { &mut *(data as *mut Annotations) }.nodes.push(annotation);
+}
+
+extern "C" fn annotate_output(data: *mut c_void, index: u64, id: u64) {
+    unsafe { &mut *(data as *mut Annotations) }.outputs.push((index, id));
+}
+
+#[test]
+fn annotations_are_sent_through_callbacks() {
+    let mut network = MigNetwork::new();
+    let [a, b, c] = [(); 3].map(|_| network.add_input());
+    let (sum, carry) = network.full_adder(a, b, c);
+    network.add_output(sum);
+    network.add_output(carry);
+
+    let mut collected = Annotations::default();
+    let annotations = AnnotationReceiverFFI {
+        data: &mut collected as *mut Annotations as *mut c_void,
+        annotate_node,
+        annotate_output,
+    };
+    let receiver = MigReceiverFFI::new(EGraph::<MigLanguage, ()>::new(()).map(|_| ()));
+    let settings = CompilerSettings::default();
+    let stats = network.send(unsafe { prada_rewrite_annotated_ffi(settings, receiver, annotations) });
+    assert_eq!(stats.error, 0);
+
+    // every output points to an annotated MAJ with a nonzero cost
+    let indices: Vec<u64> = collected.outputs.iter().map(|(index, _)| *index).collect();
+    assert_eq!(indices, [0, 1]);
+    let mut output_depths = vec!();
+    for (_, id) in &collected.outputs {
+        let node = collected.nodes.iter().find(|node| node.id == *id).expect("outputs should be annotated");
+        assert!(node.depth >= 1);
+        assert!(node.runtime_estimate > 0);
+        assert!(node.energy_consumption_estimate > 0);
+        output_depths.push(node.depth);
+    }
+    // all nodes are reachable from the outputs, hence the deepest ones are outputs
+    let max_depth = collected.nodes.iter().map(|node| node.depth).max();
+    assert_eq!(max_depth, output_depths.into_iter().max());
+    // the inputs are leaves
+    assert!(collected.nodes.iter().any(|node| node.depth == 0));
+    let mut ids: Vec<u64> = collected.nodes.iter().map(|node| node.id).collect();
+    ids.sort();
+    ids.dedup();
+    assert_eq!(ids.len(), collected.nodes.len(), "every node is annotated once");
+}
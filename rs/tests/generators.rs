@@ -0,0 +1,76 @@
+//! Checks the programs emitted directly by the generators of the stdlib against the operations
+//! they implement.
+use lime_rs::prada::architecture::PRADAArchitecture;
+use lime_rs::prada::random::Xorshift;
+use lime_rs::prada::stdlib::{bitmap_scan, horizontal_adder, BitmapOp};
+use lime_rs::prelude::*;
+
+fn shifting_architecture() -> PRADAArchitecture {
+    PRADAArchitecture { capabilities: Capabilities::DEFAULT | Capabilities::COLUMN_SHIFT, ..ARCHITECTURE.clone() }
+}
+
+#[test]
+fn horizontal_adder_adds_words() {
+    let architecture = shifting_architecture();
+    let mut random = Xorshift::new(0x5eed);
+    for width in [1, 4, 8, 16, 33, 64] {
+        let program = horizontal_adder(&architecture, width).expect("architecture supports the adder");
+        let mask = u64::MAX >> (64 - width);
+        let mut samples: Vec<(u64, u64)> =
+            (0..8).map(|_| (random.next_u64() & mask, random.next_u64() & mask)).collect();
+        // carries rippling through all bits
+        samples.extend([(mask, 1), (mask, mask), (0, 0)]);
+        for (a, b) in samples {
+            let sum = simulate(&program, &[a, b]).expect("program should be executable")[0];
+            assert_eq!(sum & mask, a.wrapping_add(b) & mask, "{a:#x} + {b:#x} with {width} bits");
+        }
+    }
+}
+
+#[test]
+fn horizontal_adder_requires_shifts_and_negation() {
+    assert_eq!(
+        horizontal_adder(&ARCHITECTURE, 8).unwrap_err(),
+        CompileError::UnsupportedOperation { operation: "horizontal adder", missing: Capabilities::COLUMN_SHIFT }
+    );
+    let architecture = PRADAArchitecture { capabilities: Capabilities::COLUMN_SHIFT, ..ARCHITECTURE.clone() };
+    assert_eq!(
+        horizontal_adder(&architecture, 8).unwrap_err(),
+        CompileError::UnsupportedOperation { operation: "horizontal adder", missing: Capabilities::NOT }
+    );
+}
+
+#[test]
+fn bitmap_scan_applies_predicates() {
+    let mut random = Xorshift::new(0xb17);
+    let predicates: [(BitmapOp, fn(u64, u64) -> u64); 3] = [
+        (BitmapOp::And, |data, query| data & query),
+        (BitmapOp::Or, |data, query| data | query),
+        (BitmapOp::AndNot, |data, query| data & !query),
+    ];
+    for (op, predicate) in predicates {
+        for n_rows in [1, 5] {
+            let scan = bitmap_scan(&ARCHITECTURE, op, n_rows).expect("rows should fit into a subarray");
+            let inputs: Vec<u64> = (0..=n_rows).map(|_| random.next_u64()).collect();
+            let query = inputs[n_rows as usize];
+            let expected: Vec<u64> = inputs[..n_rows as usize].iter().map(|data| predicate(*data, query)).collect();
+            assert_eq!(simulate(&scan.program, &inputs).unwrap(), expected, "{op:?} of {n_rows} rows");
+            assert_eq!(scan.runtime_per_row() * n_rows as f64, scan.program.runtime_estimate as f64);
+            assert_eq!(scan.rows_per_second(), 1e9 / scan.runtime_per_row());
+        }
+    }
+}
+
+#[test]
+fn bitmap_scan_checks_nr_of_rows() {
+    assert_eq!(
+        bitmap_scan(&ARCHITECTURE, BitmapOp::And, 0).unwrap_err(),
+        CompileError::Other("bitmap scan without data rows")
+    );
+    let max_rows = (ARCHITECTURE.rows_per_subarray - 4) / 2;
+    assert!(bitmap_scan(&ARCHITECTURE, BitmapOp::Or, max_rows).is_ok());
+    assert_eq!(
+        bitmap_scan(&ARCHITECTURE, BitmapOp::Or, max_rows + 1).unwrap_err(),
+        CompileError::Other("too many data rows for a subarray")
+    );
+}
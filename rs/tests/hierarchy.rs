@@ -0,0 +1,67 @@
+//! Checks the address mapping and transfer costs of architectures spanning several banks, ranks
+//! and channels.
+use lime_rs::prada::architecture::{Hierarchy, HierarchyLevel, SubarrayLocation, TransferCosts};
+use lime_rs::prelude::*;
+
+fn hierarchy() -> Hierarchy {
+    Hierarchy {
+        nr_channels: 2,
+        ranks_per_channel: 2,
+        banks_per_rank: 8,
+        subarrays_per_bank: 16,
+        transfer_costs: TransferCosts::default(),
+    }
+}
+
+#[test]
+fn subarrays_are_mapped_across_the_hierarchy() {
+    let hierarchy = hierarchy();
+    assert_eq!(hierarchy.nr_subarrays(), 512);
+    for id in 0..hierarchy.nr_subarrays() {
+        assert_eq!(hierarchy.subarray_id(hierarchy.locate(SubarrayId(id))), Some(SubarrayId(id)));
+    }
+    assert_eq!(
+        hierarchy.locate(SubarrayId(300)),
+        SubarrayLocation { channel: 1, rank: 0, bank: 2, subarray: 12 }
+    );
+    assert_eq!(hierarchy.subarray_id(SubarrayLocation { channel: 2, rank: 0, bank: 0, subarray: 0 }), None);
+
+    assert_eq!(hierarchy.common_level(SubarrayId(0), SubarrayId(0)), HierarchyLevel::Subarray);
+    assert_eq!(hierarchy.common_level(SubarrayId(0), SubarrayId(15)), HierarchyLevel::Bank);
+    assert_eq!(hierarchy.common_level(SubarrayId(0), SubarrayId(16)), HierarchyLevel::Rank);
+    assert_eq!(hierarchy.common_level(SubarrayId(0), SubarrayId(128)), HierarchyLevel::Channel);
+    assert_eq!(hierarchy.common_level(SubarrayId(0), SubarrayId(300)), HierarchyLevel::System);
+}
+
+#[test]
+fn rows_beyond_a_single_bank_are_addressable() {
+    let architecture = Architecture::new(128, 512).with_hierarchy(hierarchy());
+    assert_eq!(architecture.nr_subarrays, 512);
+    let row = RowAddress(7).local_rowaddress_to_subarray_id(SubarrayId(300));
+    assert_eq!(row.get_subarray_id(), SubarrayId(300));
+    assert_eq!(row.to_string(), "300.7");
+}
+
+#[test]
+fn transfers_add_to_the_estimates() {
+    let architecture = Architecture::new(128, 512).with_hierarchy(hierarchy());
+    let row = |subarray| RowAddress(0).local_rowaddress_to_subarray_id(SubarrayId(subarray));
+    let estimate = |to| {
+        let mut program = Program::new(&architecture, vec!(Instruction::AAPRowCopy(row(0), row(to))));
+        program.update_cost_estimates(&architecture.cost_model);
+        program.runtime_estimate
+    };
+    let costs = TransferCosts::default();
+    let copy = estimate(1);
+    assert_eq!(estimate(16), copy + costs.bank.runtime);
+    assert_eq!(estimate(128), copy + costs.rank.runtime);
+    assert_eq!(estimate(300), copy + costs.channel.runtime);
+
+    // loop bodies are charged once per iteration
+    let mut program = Program::new(
+        &architecture,
+        vec!(Instruction::LoopBegin(3), Instruction::AAPRowCopy(row(0), row(16)), Instruction::LoopEnd),
+    );
+    program.update_cost_estimates(&architecture.cost_model);
+    assert_eq!(program.runtime_estimate, 3 * (copy + costs.bank.runtime));
+}
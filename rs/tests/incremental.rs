@@ -0,0 +1,52 @@
+//! Checks patching compiled programs when only some outputs of the network change.
+use lime_rs::prada::incremental::recompile;
+use lime_rs::prada::reference::differential_test;
+use lime_rs::prelude::*;
+
+/// Two 4-bit adders: `a + b` and `c + d` (if `subtract` is unset) resp. `c - d`
+fn adders(subtract: bool) -> MigNetwork {
+    let mut network = MigNetwork::new();
+    let inputs: Vec<Signal> = (0..16).map(|_| network.add_input()).collect();
+    for (x, y, invert) in [(&inputs[0..4], &inputs[4..8], false), (&inputs[8..12], &inputs[12..16], subtract)] {
+        let mut carry = network.constant(invert);
+        for (x, y) in x.iter().zip(y) {
+            let (sum, next) = network.full_adder(*x, if invert { y.invert() } else { *y }, carry);
+            network.add_output(sum);
+            carry = next;
+        }
+    }
+    network
+}
+
+#[test]
+fn only_changed_outputs_are_recompiled() {
+    let settings = CompilerSettings::default();
+    let old = adders(false);
+    let new = adders(true);
+    let previous = compile(&ARCHITECTURE, &old, settings).unwrap();
+
+    let recompilation = recompile(&previous, &old, &new, settings).unwrap();
+    assert_eq!(recompilation.recompiled_outputs, vec!(4, 5, 6, 7));
+    assert!(recompilation.removed_instructions > 0);
+    assert_eq!(recompilation.program.output_map[..4], previous.output_map[..4]);
+    assert_eq!(differential_test(&new, &recompilation.program, 4, 7).expect("program should be executable"), None);
+
+    // and back again
+    let restored = recompile(&recompilation.program, &new, &old, settings).unwrap();
+    assert_eq!(restored.recompiled_outputs, vec!(4, 5, 6, 7));
+    assert_eq!(differential_test(&old, &restored.program, 4, 11).expect("program should be executable"), None);
+}
+
+#[test]
+fn unchanged_networks_keep_their_program() {
+    let settings = CompilerSettings::default();
+    let network = adders(false);
+    let previous = compile(&ARCHITECTURE, &network, settings).unwrap();
+
+    // rebuilding the network yields the same cones
+    let recompilation = recompile(&previous, &network, &adders(false), settings).unwrap();
+    assert!(recompilation.recompiled_outputs.is_empty());
+    assert_eq!(recompilation.program.output_map, previous.output_map);
+    assert!(recompilation.program.instructions.len() <= previous.instructions.len());
+    assert_eq!(differential_test(&network, &recompilation.program, 4, 3).expect("program should be executable"), None);
+}
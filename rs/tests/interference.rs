@@ -0,0 +1,88 @@
+//! Exports the interference graph of compiled networks and compiles them with row assignments fed
+//! back from an external allocator.
+use eggmock::Network;
+use lime_rs::prada::architecture::ARCHITECTURE;
+use lime_rs::prada::compile_with_placement;
+use lime_rs::prada::interference::{compile_with_interference, InterferenceGraph};
+use lime_rs::prada::network::MigNetwork;
+use lime_rs::prada::simulation::{evaluate_network, simulate};
+
+const INPUTS: [u64; 3] = [0xf0f0_f0f0_f0f0_f0f0, 0xcccc_cccc_cccc_cccc, 0xaaaa_aaaa_aaaa_aaaa];
+
+/// Full adder followed by a chain of MAJs, whose intermediate values are dead before the end
+fn network() -> MigNetwork {
+    let mut network = MigNetwork::new();
+    let [a, b, c] = [(); 3].map(|_| network.add_input());
+    let (sum, carry) = network.full_adder(a, b, c);
+    let mut chain = network.maj(sum, a, b);
+    for _ in 0..3 {
+        chain = network.maj(chain, a, c);
+    }
+    network.add_output(carry);
+    network.add_output(chain);
+    network
+}
+
+fn graph(network: &MigNetwork) -> InterferenceGraph {
+    compile_with_interference(&ARCHITECTURE, &network.with_backward_edges()).expect("network should be compilable").1
+}
+
+#[test]
+fn exported_assignments_are_compiled() {
+    let network = network();
+    let graph = graph(&network);
+    assert!(graph.to_dot().starts_with("graph interference {\n"));
+    let dimacs = graph.to_dimacs();
+    assert!(dimacs.lines().any(|line| line == format!("p edge {} {}", graph.nodes.len(), graph.edges.len())));
+    assert_eq!(dimacs.lines().filter(|line| line.starts_with("e ")).count(), graph.edges.len());
+
+    // every vertex gets a row of its own, which trivially is a valid assignment
+    let assignment: String = (1..=graph.nodes.len()).map(|vertex| format!("{vertex} {}\n", 10 + vertex)).collect();
+    let text = format!("c computed externally\n\n{assignment}");
+    let placement = graph.parse_row_assignment(&ARCHITECTURE, &text).unwrap();
+    assert_eq!(placement.len(), graph.nodes.len());
+
+    let program = compile_with_placement(&ARCHITECTURE, &network.with_backward_edges(), placement.clone())
+        .expect("assignment should be compilable");
+    assert_eq!(simulate(&program, &INPUTS).unwrap(), evaluate_network(&network, &INPUTS).unwrap());
+    for (output, row) in network.outputs().zip(&program.output_map) {
+        assert_eq!(placement.get(&output), Some(row));
+    }
+}
+
+#[test]
+fn only_non_interfering_vertices_share_rows() {
+    let graph = graph(&network());
+    let n = graph.nodes.len();
+    let (a, b) = (0..n)
+        .flat_map(|a| (a + 1..n).map(move |b| (a, b)))
+        .find(|pair| !graph.edges.contains(pair))
+        .expect("values of the chain should be dead before the end");
+    let shared = graph.parse_row_assignment(&ARCHITECTURE, &format!("{} 10\n{} 10", a + 1, b + 1)).unwrap();
+    assert_eq!(shared.len(), 2);
+
+    let (a, b) = graph.edges[0];
+    assert_eq!(
+        graph.parse_row_assignment(&ARCHITECTURE, &format!("{} 10\n{} 10", a + 1, b + 1)),
+        Err("interfering vertices are assigned the same row")
+    );
+}
+
+#[test]
+fn malformed_assignments_are_rejected() {
+    let graph = graph(&network());
+    let unknown = format!("{} 10", graph.nodes.len() + 1);
+    let outside = format!("1 {}", ARCHITECTURE.rows_per_subarray);
+    for (text, error) in [
+        ("1", "row assignment lines have to consist of a vertex and a row"),
+        ("1 10 11", "row assignment lines have to consist of a vertex and a row"),
+        ("x 10", "invalid vertex in row assignment"),
+        ("1 -10", "invalid row in row assignment"),
+        ("0 10", "unknown vertex in row assignment"),
+        (unknown.as_str(), "unknown vertex in row assignment"),
+        (outside.as_str(), "row outside of the subarray in row assignment"),
+        ("1 10\n1 11", "vertex is assigned twice in row assignment"),
+    ] {
+        assert_eq!(graph.parse_row_assignment(&ARCHITECTURE, text), Err(error), "{text}");
+    }
+}
@@ -0,0 +1,40 @@
+//! Compiles networks along the different allocation paths of the compiler. In debug builds the
+//! consistency of the row states is checked after every computed node, hence allocator bugs
+//! surface as panics right where they are introduced.
+use lime_rs::prada::architecture::PRADAArchitecture;
+use lime_rs::prada::reference::differential_test;
+use lime_rs::prada::stdlib::{hamming_distance_network, random_network};
+use lime_rs::prelude::*;
+
+fn check(architecture: &PRADAArchitecture, network: &MigNetwork, settings: CompilerSettings) {
+    let program = compile(architecture, network, settings).expect("network should be compilable");
+    assert_eq!(differential_test(network, &program, 4, 0x5eed).expect("program should be executable"), None);
+}
+
+#[test]
+fn row_states_stay_consistent() {
+    let lowering = PRADAArchitecture {
+        capabilities: Capabilities::DEFAULT | Capabilities::MASKED_COPY | Capabilities::XOR,
+        ..ARCHITECTURE.clone()
+    };
+    let as_is = CompilerSettings::builder().rewrite(false);
+    for seed in 1..=4 {
+        let network = random_network(5, 30, 4, seed);
+        for architecture in [&ARCHITECTURE, &lowering] {
+            let policies = [SchedulingPolicy::Greedy, SchedulingPolicy::CriticalPath, SchedulingPolicy::SethiUllman];
+            for scheduling in policies {
+                check(architecture, &network, as_is.scheduling(scheduling).build());
+            }
+            check(architecture, &network, as_is.pin_inputs(true).build());
+            check(architecture, &network, as_is.pack_outputs(3).build());
+        }
+    }
+}
+
+#[test]
+fn row_states_stay_consistent_when_running_out_of_rows() {
+    let network = hamming_distance_network(2);
+    let budget = CompilerSettings::builder().rewrite(false).scratch_row_budget(14).spill(1);
+    check(&ARCHITECTURE, &network, budget.build());
+    check(&ARCHITECTURE, &network, budget.rematerialize(true).build());
+}
@@ -0,0 +1,42 @@
+//! Checks the retry ladder compiling with progressively more expensive settings.
+use std::time::Duration;
+
+use lime_rs::prada::ladder::{Ladder, QualityTarget};
+use lime_rs::prada::reference::differential_test;
+use lime_rs::prada::stdlib::hamming_distance_network;
+use lime_rs::prelude::*;
+
+#[test]
+fn unconstrained_targets_stop_at_the_first_rung() {
+    let network = hamming_distance_network(4);
+    let ladder = Ladder::new(CompilerSettings::default(), QualityTarget::default());
+    let result = ladder.run(&ARCHITECTURE, &network).unwrap();
+    assert_eq!((result.rung, result.attempts.len()), (0, 1));
+    assert!(result.target_met);
+    assert_eq!(result.attempts[0].rung, "no_rewrite");
+    assert_eq!(differential_test(&network, &result.program, 4, 1).expect("program should be executable"), None);
+}
+
+#[test]
+fn unmet_targets_climb_every_rung_and_keep_the_best_program() {
+    let network = hamming_distance_network(4);
+    let target = QualityTarget { runtime: Some(1), energy_consumption: None };
+    let ladder = Ladder::new(CompilerSettings::default(), target);
+    let result = ladder.run(&ARCHITECTURE, &network).unwrap();
+    assert!(!result.target_met);
+    assert_eq!(result.attempts.len(), ladder.rungs.len());
+    let runtimes: Vec<u64> = result.attempts.iter().map(|attempt| attempt.result.as_ref().unwrap().0).collect();
+    assert_eq!(result.program.runtime_estimate, *runtimes.iter().min().unwrap());
+    assert_eq!(runtimes[result.rung], result.program.runtime_estimate);
+    assert_eq!(differential_test(&network, &result.program, 4, 2).expect("program should be executable"), None);
+}
+
+#[test]
+fn exhausted_time_budgets_stop_climbing() {
+    let network = hamming_distance_network(4);
+    let target = QualityTarget { runtime: Some(1), energy_consumption: None };
+    let ladder = Ladder::new(CompilerSettings::default(), target).with_time_budget(Duration::ZERO);
+    let result = ladder.run(&ARCHITECTURE, &network).unwrap();
+    assert_eq!((result.rung, result.attempts.len()), (0, 1));
+    assert!(!result.target_met);
+}
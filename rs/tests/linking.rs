@@ -0,0 +1,94 @@
+//! Links relocatable fragments of separately compiled networks into one program.
+use lime_rs::prada::fragment::Operand;
+use lime_rs::prada::simulation::evaluate_network;
+use lime_rs::prada::ProgramFragment;
+use lime_rs::prelude::*;
+
+const INPUTS: [u64; 3] = [0xf0f0_f0f0_f0f0_f0f0, 0xcccc_cccc_cccc_cccc, 0xaaaa_aaaa_aaaa_aaaa];
+
+fn fragment(network: &MigNetwork) -> ProgramFragment {
+    let program = compile(&ARCHITECTURE, network, CompilerSettings::default()).expect("network should be compilable");
+    ProgramFragment::from_program(&program)
+}
+
+#[test]
+fn connected_fragments_match_their_networks() {
+    let mut adder = MigNetwork::new();
+    let [a, b, c] = [(); 3].map(|_| adder.add_input());
+    let (sum, carry) = adder.full_adder(a, b, c);
+    adder.add_output(sum);
+    adder.add_output(carry);
+
+    let mut and_xor = MigNetwork::new();
+    let [x, y] = [(); 2].map(|_| and_xor.add_input());
+    let and = and_xor.and(x, y);
+    let xor = and_xor.xor(x, y);
+    and_xor.add_output(and);
+    and_xor.add_output(xor);
+
+    let mut linker = Linker::new(&ARCHITECTURE);
+    let first = linker.add(fragment(&adder), SubarrayId(0));
+    let second = linker.add(fragment(&and_xor), SubarrayId(0));
+    // input 1 of the second fragment stays unconnected and reads input 1 of the linked program
+    linker.connect(first, 1, second, 0);
+    let program = linker.link().expect("fragments should be linkable");
+
+    let adder_outputs = evaluate_network(&adder, &INPUTS).unwrap();
+    let and_xor_outputs = evaluate_network(&and_xor, &[adder_outputs[1], INPUTS[1]]).unwrap();
+    assert_eq!(simulate(&program, &INPUTS).unwrap(), [adder_outputs, and_xor_outputs].concat());
+}
+
+/// `out = a & b`, computed on copies of the operands, i.e. the constant row is only read
+fn and_fragment() -> ProgramFragment {
+    ProgramFragment {
+        instructions: vec!(
+            Instruction::AAPRowCopy(Operand::Symbol(0), Operand::Symbol(3)),
+            Instruction::AAPRowCopy(Operand::Symbol(1), Operand::Symbol(4)),
+            Instruction::AAPRowCopy(Operand::Symbol(2), Operand::Symbol(5)),
+            Instruction::AAPTRA(Operand::Symbol(3), Operand::Symbol(4), Operand::Symbol(5)),
+        ),
+        input_map: vec!(
+            (Operand::Symbol(0), RowInit::Input { index: 0, inverted: false }),
+            (Operand::Symbol(1), RowInit::Input { index: 1, inverted: false }),
+            (Operand::Symbol(2), RowInit::Constant(false)),
+        ),
+        output_map: vec!(Operand::Symbol(3)),
+        ..ProgramFragment::default()
+    }
+}
+
+fn constant_rows(program: &Program) -> usize {
+    program.input_map.iter().filter(|(_, init)| matches!(init, RowInit::Constant(_))).count()
+}
+
+#[test]
+fn read_only_constants_are_shared_per_subarray() {
+    let expected = vec!(INPUTS[0] & INPUTS[1]; 2);
+
+    let mut linker = Linker::new(&ARCHITECTURE);
+    linker.add(and_fragment(), SubarrayId(0));
+    linker.add(and_fragment(), SubarrayId(0));
+    let program = linker.link().expect("fragments should be linkable");
+    assert_eq!(constant_rows(&program), 1);
+    assert_eq!(simulate(&program, &INPUTS).unwrap(), expected);
+
+    let mut linker = Linker::new(&ARCHITECTURE);
+    linker.add(and_fragment(), SubarrayId(0));
+    linker.add(and_fragment(), SubarrayId(2));
+    let program = linker.link().expect("fragments should be linkable");
+    assert_eq!(constant_rows(&program), 2);
+    assert_eq!(simulate(&program, &INPUTS).unwrap(), expected);
+
+    // the TRA overwrites the constant row, hence every fragment needs its own
+    let overwriting = ProgramFragment {
+        instructions: vec!(Instruction::AAPTRA(Operand::Symbol(0), Operand::Symbol(1), Operand::Symbol(2))),
+        output_map: vec!(Operand::Symbol(0)),
+        ..and_fragment()
+    };
+    let mut linker = Linker::new(&ARCHITECTURE);
+    linker.add(overwriting.clone(), SubarrayId(0));
+    linker.add(overwriting, SubarrayId(0));
+    let program = linker.link().expect("fragments should be linkable");
+    assert_eq!(constant_rows(&program), 2);
+    assert_eq!(simulate(&program, &INPUTS).unwrap(), expected);
+}
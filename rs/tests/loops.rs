@@ -0,0 +1,93 @@
+//! Loops of the program format behave like their unrolled bodies in the simulator and cost model.
+use lime_rs::prada::program::{check_loops, unrolled_indices};
+use lime_rs::prada::simulation::{evaluate_network, ExecutedInstruction};
+use lime_rs::prelude::*;
+
+const INPUTS: [u64; 3] = [0xf0f0_f0f0_f0f0_f0f0, 0xcccc_cccc_cccc_cccc, 0xaaaa_aaaa_aaaa_aaaa];
+
+fn full_adder() -> MigNetwork {
+    let mut network = MigNetwork::new();
+    let [a, b, c] = [(); 3].map(|_| network.add_input());
+    let (sum, carry) = network.full_adder(a, b, c);
+    network.add_output(sum);
+    network.add_output(carry);
+    network
+}
+
+/// `program` with its instructions replaced by `instructions`
+fn with_instructions<'a>(program: &Program<'a>, instructions: Vec<Instruction>) -> Program<'a> {
+    Program { instructions, ..program.clone() }
+}
+
+/// Runs `program` and returns its outputs and the nr of executed instructions
+fn run(program: &Program) -> (Vec<u64>, u64) {
+    let mut simulator = Simulator::new();
+    simulator.load_inputs(program, &INPUTS).unwrap();
+    simulator.run(&program.instructions).expect("program should be executable");
+    (simulator.outputs(program).unwrap(), simulator.executed_instructions)
+}
+
+#[test]
+fn loops_behave_like_their_unrolled_body() {
+    let network = full_adder();
+    let compiled = compile(&ARCHITECTURE, &network, CompilerSettings::default()).expect("network should be compilable");
+    let body = compiled.instructions.clone();
+
+    let mut once = with_instructions(&compiled, vec!());
+    once.push_loop(1, body.clone());
+    assert_eq!(run(&once), (evaluate_network(&network, &INPUTS).unwrap(), body.len() as u64));
+
+    let mut looped = with_instructions(&compiled, vec!());
+    looped.push_loop(3, body.clone());
+    let unrolled = with_instructions(&compiled, [body.clone(), body.clone(), body.clone()].concat());
+    assert_eq!(looped.instructions.len(), body.len() + 2);
+    assert_eq!(looped.unrolled_instructions(), unrolled.instructions);
+    assert_eq!(run(&looped), run(&unrolled));
+    assert_eq!(ARCHITECTURE.cost_model.estimate(&looped), ARCHITECTURE.cost_model.estimate(&unrolled));
+    assert_eq!(looped.efficiency(0), unrolled.efficiency(0));
+}
+
+#[test]
+fn nested_and_empty_loops() {
+    let row = RowAddress(0);
+    let mut program = Program::new(&ARCHITECTURE, vec!());
+    program.input_map.push((row, RowInit::Input { index: 0, inverted: false }));
+    program.output_map.push(row);
+    // 2 * (1 + 3) negations, the empty loop is skipped
+    program.push_loop(2, [Instruction::N(row), Instruction::LoopBegin(3), Instruction::N(row), Instruction::LoopEnd]);
+    program.push_loop(0, [Instruction::N(row)]);
+    assert_eq!(run(&program), (vec!(INPUTS[0]), 8));
+    assert_eq!(program.unrolled_instructions(), vec!(Instruction::N(row); 8));
+
+    program.push_loop(5, [Instruction::N(row)]);
+    assert_eq!(run(&program), (vec!(!INPUTS[0]), 13));
+    let negation = ARCHITECTURE.cost_model.estimate_instructions(&[Instruction::N(row)]);
+    assert_eq!(ARCHITECTURE.cost_model.estimate(&program).runtime, 13 * negation.runtime);
+
+    // empty loops nested into other loops are skipped in every iteration
+    program.push_loop(2, [Instruction::LoopBegin(0), Instruction::N(row), Instruction::LoopEnd, Instruction::N(row)]);
+    assert_eq!(run(&program), (vec!(!INPUTS[0]), 15));
+    let mut executed = vec!();
+    let mut simulator = Simulator::new();
+    simulator.load_inputs(&program, &INPUTS).unwrap();
+    let mut observer = |instruction: &ExecutedInstruction, _: &Simulator| executed.push(instruction.index);
+    simulator.run_observed(&program.instructions, &mut observer).unwrap();
+    assert_eq!(executed, unrolled_indices(&program.instructions).collect::<Vec<_>>());
+    assert_eq!(program.unrolled_instructions().len(), 15);
+}
+
+#[test]
+fn unbalanced_loops_are_rejected() {
+    let row = RowAddress(0);
+    let mut simulator = Simulator::new();
+    simulator.set_row(row, 0);
+    assert_eq!(simulator.run(&[Instruction::LoopBegin(2), Instruction::N(row)]), Err("`LoopBegin` without `LoopEnd`"));
+    assert_eq!(simulator.run(&[Instruction::N(row), Instruction::LoopEnd]), Err("`LoopEnd` without `LoopBegin`"));
+    assert_eq!(simulator.step(&Instruction::LoopBegin(2)), Err("loops have to be executed using `run`"));
+    assert_eq!(simulator.executed_instructions, 0);
+
+    // unrolling doesn't check the loops
+    let unbalanced = [Instruction::N(row), Instruction::LoopEnd, Instruction::N(row)];
+    assert!(check_loops(&unbalanced).is_err());
+    assert_eq!(unrolled_indices(&unbalanced).collect::<Vec<_>>(), vec!(0, 2));
+}
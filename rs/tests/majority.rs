@@ -0,0 +1,63 @@
+//! Checks the decomposition of MAJ-n into MAJ-3 nodes against truth tables.
+use lime_rs::prada::simulation::evaluate_network;
+use lime_rs::prelude::*;
+
+/// Values of `nr_inputs` inputs assigning the combinations `64 * batch..64 * (batch + 1)` to the
+/// bitlines, one combination per bitline
+fn combinations(nr_inputs: usize, batch: u64) -> Vec<u64> {
+    (0..nr_inputs)
+        .map(|input| (0..64).fold(0, |value, bitline| value | ((((64 * batch + bitline) >> input) & 1) << bitline)))
+        .collect()
+}
+
+/// Truth table of the majority of `nr_inputs` inputs for the combinations of the given batch
+fn majority(nr_inputs: usize, batch: u64) -> u64 {
+    (0..64).fold(0, |value, bitline| {
+        let set = (64 * batch + bitline).count_ones() as usize;
+        value | (((2 * set > nr_inputs) as u64) << bitline)
+    })
+}
+
+#[test]
+fn decomposition_matches_truth_table() {
+    for nr_inputs in [3, 5, 7] {
+        let mut network = MigNetwork::new();
+        let inputs: Vec<Signal> = (0..nr_inputs).map(|_| network.add_input()).collect();
+        let maj = network.maj_n(&inputs).unwrap();
+        network.add_output(maj);
+        for batch in 0..(1u64 << nr_inputs).div_ceil(64) {
+            let valid = u64::MAX >> 64u64.saturating_sub(1 << nr_inputs);
+            let outputs = evaluate_network(&network, &combinations(nr_inputs, batch)).unwrap();
+            assert_eq!(outputs[0] & valid, majority(nr_inputs, batch) & valid, "MAJ-{nr_inputs}, batch {batch}");
+        }
+    }
+}
+
+#[test]
+fn constant_operands() {
+    let mut network = MigNetwork::new();
+    let [x, y, z] = [(); 3].map(|_| network.add_input());
+    let (t, f) = (network.constant(true), network.constant(false));
+
+    // more constant true operands than the threshold must not underflow it
+    assert_eq!(network.maj_n(&[t, t, t]), Ok(t));
+    assert_eq!(network.maj_n(&[t, t, t, t, x]), Ok(t));
+    assert_eq!(network.maj_n(&[f, f, f]), Ok(f));
+    assert_eq!(network.maj_n(&[f, f, f, f, x]), Ok(f));
+    assert_eq!(network.maj_n(&[x, y]), Err("majority requires an odd nr of inputs"));
+
+    let outputs = [
+        network.maj_n(&[x, y, z, t, f]).unwrap(),
+        network.maj_n(&[x, f, y]).unwrap(),
+        network.maj_n(&[x, t, t, y, f]).unwrap(),
+        network.maj_n(&[x, f, f, y, t]).unwrap(),
+        network.maj_n(&[x, y, z, t, f, f, t]).unwrap(),
+    ];
+    for output in outputs {
+        network.add_output(output);
+    }
+    let inputs = combinations(3, 0);
+    let [x, y, z] = [inputs[0], inputs[1], inputs[2]];
+    let maj = (x & y) | (x & z) | (y & z);
+    assert_eq!(evaluate_network(&network, &inputs).unwrap(), vec!(maj, x & y, x | y, x & y, maj));
+}
@@ -0,0 +1,64 @@
+//! Checks recording compilations in reproducibility manifests and re-running them.
+use lime_rs::prada::artifact::{deserialize, serialize};
+use lime_rs::prada::cache::network_hash;
+use lime_rs::prada::dsl::{parse_kernel, to_kernel};
+use lime_rs::prada::manifest::{compile_reproducible, Manifest};
+use lime_rs::prada::stdlib::hamming_distance_network;
+use lime_rs::prelude::*;
+
+#[test]
+fn manifests_reproduce_the_recorded_program() {
+    let network = hamming_distance_network(4);
+    let settings = CompilerSettings::builder().sharing_guided_distributivity(true).seed(42).build();
+    let (program, manifest) = compile_reproducible(&ARCHITECTURE, &network, settings).unwrap();
+    let loaded = Manifest::parse(&manifest.to_string()).unwrap();
+    assert_eq!(loaded, manifest);
+    assert_eq!(loaded.seed, 42);
+    assert_eq!(loaded.settings().unwrap().seed, 42);
+    assert_eq!(loaded.rules, settings.rules_hash());
+    let reproduced = loaded.reproduce(&ARCHITECTURE).unwrap();
+    assert_eq!(reproduced.instructions, program.instructions);
+    assert_eq!(serialize(&reproduced, &settings), serialize(&program, &settings));
+}
+
+#[test]
+fn changed_configurations_are_rejected() {
+    let network = hamming_distance_network(2);
+    let (_, manifest) = compile_reproducible(&ARCHITECTURE, &network, CompilerSettings::default()).unwrap();
+    let tampered = Manifest { rules: manifest.rules ^ 1, ..manifest.clone() };
+    assert!(tampered.reproduce(&ARCHITECTURE).is_err());
+    let tampered = Manifest { program: manifest.program ^ 1, ..manifest.clone() };
+    assert!(tampered.reproduce(&ARCHITECTURE).is_err());
+    let tampered = Manifest { network: manifest.network ^ 1, ..manifest };
+    assert!(tampered.network().is_err());
+}
+
+#[test]
+fn settings_survive_their_snapshot() {
+    let settings = CompilerSettings::builder()
+        .rewrite(false)
+        .scheduler(RunnerScheduler::Simple)
+        .backend(RewriteBackend::Egglog)
+        .scheduling(SchedulingPolicy::SethiUllman)
+        .strictness(Strictness::PRODUCTION)
+        .energy_budget(123)
+        .build();
+    let parsed = CompilerSettings::from_snapshot(&settings.snapshot()).unwrap();
+    assert_eq!(parsed.snapshot(), settings.snapshot());
+    assert!(CompilerSettings::from_snapshot("rewrite=maybe").is_err());
+    assert!(CompilerSettings::from_snapshot("unknown=1").is_err());
+    assert!(CompilerSettings::from_snapshot("scheduler=Egglog").is_err());
+}
+
+#[test]
+fn kernels_rebuild_networks_and_programs_record_their_rules() {
+    let network = hamming_distance_network(3);
+    let rebuilt = parse_kernel(&to_kernel(&network, 0)).unwrap();
+    assert_eq!(network_hash(rebuilt.network()), network_hash(&network));
+    assert_eq!(rebuilt.network().nr_inputs(), network.nr_inputs());
+
+    let settings = CompilerSettings::default();
+    let program = compile(&ARCHITECTURE, &network, settings).unwrap();
+    let (_, metadata) = deserialize(&serialize(&program, &settings), &ARCHITECTURE).unwrap();
+    assert_eq!(metadata.rules, settings.rules_hash());
+}
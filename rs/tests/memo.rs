@@ -0,0 +1,38 @@
+//! Checks that extraction choices are reused across architectures with the same cost features.
+use lime_rs::prada::architecture::PRADAArchitecture;
+use lime_rs::prada::cost::{CompilingCost, InstructionClass};
+use lime_rs::prada::report::Sweep;
+use lime_rs::prada::stdlib::hamming_distance_network;
+use lime_rs::prada::{compile, compile_memoized, CompilerSettings, CostMemo};
+
+#[test]
+fn memoized_compilation_matches_compilation() {
+    let network = hamming_distance_network(6);
+    let settings = CompilerSettings::default();
+    let memo = CostMemo::new();
+    for rows_per_subarray in [256, 512, 1024] {
+        let architecture = PRADAArchitecture::new(4, rows_per_subarray);
+        let memoized = compile_memoized(&architecture, &network, settings, &memo).unwrap();
+        let program = compile(&architecture, &network, settings).unwrap();
+        assert_eq!(memoized.instructions, program.instructions);
+    }
+    // the nr of rows doesn't affect extraction
+    assert_eq!(memo.hits(), 2);
+
+    let mut architecture = PRADAArchitecture::new(4, 256);
+    architecture
+        .cost_model
+        .set_cost(InstructionClass::Tra, CompilingCost { runtime: 1000, energy_consumption: 1000 });
+    let memoized = compile_memoized(&architecture, &network, settings, &memo).unwrap();
+    assert_eq!(memo.hits(), 2, "other TRA costs should extract again");
+    assert_eq!(memoized.instructions, compile(&architecture, &network, settings).unwrap().instructions);
+}
+
+#[test]
+fn sweeps_reuse_extraction_choices() {
+    let network = hamming_distance_network(4);
+    let sweep = Sweep::run("rows", [256, 512], &network, CompilerSettings::default(), |rows| {
+        PRADAArchitecture::new(4, rows)
+    });
+    assert_eq!(sweep.points[0].1.instruction_count, sweep.points[1].1.instruction_count);
+}
@@ -0,0 +1,77 @@
+//! Checks attaching metadata to the signals of networks and finding it in the compiled programs.
+use std::collections::HashMap;
+
+use lime_rs::prada::diagnostics::Location;
+use lime_rs::prada::{compile_annotated, NodeMetadata};
+use lime_rs::prelude::*;
+
+/// 2-bit adder of `a` and `b` into `sum` (including the carry) with the RTL names of its signals
+fn named_adder() -> (MigNetwork, HashMap<Signal, NodeMetadata>) {
+    let mut network = MigNetwork::new();
+    let mut metadata = HashMap::new();
+    let a = [(); 2].map(|_| network.add_input());
+    let b = [(); 2].map(|_| network.add_input());
+    for (bit, (a, b)) in a.iter().zip(&b).enumerate() {
+        metadata.insert(*a, NodeMetadata::bit("a", bit as u32));
+        metadata.insert(*b, NodeMetadata::bit("b", bit as u32));
+    }
+    let mut carry = network.constant(false);
+    for (bit, (a, b)) in a.into_iter().zip(b).enumerate() {
+        let (sum, next) = network.full_adder(a, b, carry);
+        network.add_output(sum);
+        metadata.insert(sum, NodeMetadata::bit("sum", bit as u32));
+        carry = next;
+    }
+    network.add_output(carry);
+    metadata.insert(carry, NodeMetadata::bit("sum", 2).with_deadline(500));
+    (network, metadata)
+}
+
+fn output_labels(program: &Program) -> Vec<Option<String>> {
+    (0..program.output_map.len())
+        .map(|output| {
+            program
+                .metadata
+                .iter()
+                .find(|(location, _)| *location == Location::Output(output))
+                .and_then(|(_, metadata)| metadata.label())
+        })
+        .collect()
+}
+
+#[test]
+fn outputs_keep_their_names_through_rewriting() {
+    let (network, metadata) = named_adder();
+    for settings in [CompilerSettings::default(), CompilerSettings::builder().passthrough(true).build()] {
+        let program = compile_annotated(&ARCHITECTURE, &network, settings, &metadata).unwrap();
+        assert_eq!(
+            output_labels(&program),
+            vec!(Some("sum[0]".to_string()), Some("sum[1]".to_string()), Some("sum[2]".to_string()))
+        );
+        let layout = program.layout();
+        assert!(layout.contains(&format!("output {}: output 0 (sum[0])", program.output_map[0])));
+        assert!(layout.contains("input 0 (a[0])"));
+        assert!(layout.contains("input 3 (b[1])"));
+    }
+}
+
+#[test]
+fn unannotated_compilations_have_no_metadata() {
+    let (network, _) = named_adder();
+    let program = compile(&ARCHITECTURE, &network, CompilerSettings::default()).unwrap();
+    assert!(program.metadata.is_empty());
+    assert!(!program.layout().contains('('));
+}
+
+#[test]
+fn merged_metadata_doesnt_depend_on_the_order() {
+    let a = NodeMetadata::named("x").with_deadline(100);
+    let b = NodeMetadata::bit("carry", 1).with_deadline(50);
+    let mut ab = a.clone();
+    ab.merge(&b);
+    let mut ba = b.clone();
+    ba.merge(&a);
+    assert_eq!(ab, ba);
+    assert_eq!(ab.label().as_deref(), Some("carry[1]"));
+    assert_eq!(ab.deadline, Some(50));
+}
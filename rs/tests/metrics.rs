@@ -0,0 +1,40 @@
+//! Checks the opt-in metrics of the row allocator.
+use std::ffi::CString;
+
+use lime_rs::prada::architecture::{RowAddress, ARCHITECTURE};
+use lime_rs::prada::metrics::fragmentation;
+use lime_rs::prada::stdlib::hamming_distance_network;
+use lime_rs::prada::{compile, CompilerSettings};
+
+#[test]
+fn allocator_metrics_are_written_as_json() {
+    let network = hamming_distance_network(4);
+    let path = std::env::temp_dir().join(format!("prada-allocator-metrics-{}.json", std::process::id()));
+    let c_path = CString::new(path.to_str().unwrap()).unwrap();
+    let settings = CompilerSettings { allocator_metrics_path: c_path.as_ptr(), ..CompilerSettings::default() };
+    let program = compile(&ARCHITECTURE, &network, settings).expect("network should be compilable");
+    let json = std::fs::read_to_string(&path).expect("allocator metrics should have been written");
+    std::fs::remove_file(&path).unwrap();
+
+    let metrics = program.allocator_metrics.expect("allocator metrics should have been collected");
+    assert_eq!(json.trim(), metrics.to_json());
+    assert!(json.starts_with("{\"allocations\": ") && json.contains("\"reuse_distances\": ["));
+    assert!(metrics.allocations > 0);
+    // every allocation either reuses a freed row or uses a row for the first time
+    assert_eq!(metrics.reuse_distances.iter().sum::<u64>() + metrics.first_uses, metrics.allocations);
+    assert!(metrics.reuse_distances.iter().sum::<u64>() > 0, "rows should be reused");
+    assert!((0.0..1.0).contains(&metrics.average_fragmentation));
+
+    // without a path nothing is collected
+    let program = compile(&ARCHITECTURE, &network, CompilerSettings::default()).expect("network should be compilable");
+    assert_eq!(program.allocator_metrics, None);
+}
+
+#[test]
+fn fragmentation_is_the_share_outside_of_the_largest_range() {
+    let rows = |rows: &[u64]| rows.iter().copied().map(RowAddress).collect::<Vec<_>>();
+    assert_eq!(fragmentation(&rows(&[])), 0.0);
+    assert_eq!(fragmentation(&rows(&[7, 5, 6, 4])), 0.0);
+    assert_eq!(fragmentation(&rows(&[1, 2, 3, 10])), 0.25);
+    assert_eq!(fragmentation(&rows(&[0, 2, 4, 6])), 0.75);
+}
@@ -0,0 +1,60 @@
+//! Compiles sub-networks once as modules and instantiates them in several subarrays.
+use lime_rs::prada::simulation::evaluate_network;
+use lime_rs::prelude::*;
+
+fn full_adder() -> MigNetwork {
+    let mut network = MigNetwork::new();
+    let [a, b, c] = [(); 3].map(|_| network.add_input());
+    let (sum, carry) = network.full_adder(a, b, c);
+    network.add_output(sum);
+    network.add_output(carry);
+    network
+}
+
+const INPUTS: [u64; 3] = [0xf0f0_f0f0_f0f0_f0f0, 0xcccc_cccc_cccc_cccc, 0xaaaa_aaaa_aaaa_aaaa];
+
+#[test]
+fn modules_are_compiled_once() {
+    let network = full_adder();
+    let mut library = ModuleLibrary::new(&ARCHITECTURE, CompilerSettings::default());
+    let instructions = library.get_or_compile("full_adder", &network).unwrap().program.instructions.clone();
+    // the cached module is returned instead of compiling the (different) network again
+    let module = library.get_or_compile("full_adder", &MigNetwork::new()).unwrap();
+    assert_eq!(module.name, "full_adder");
+    assert_eq!(module.program.instructions, instructions);
+    assert!(library.get("multiplier").is_none());
+}
+
+#[test]
+fn instances_are_relocated_into_their_subarray() {
+    let network = full_adder();
+    let mut library = ModuleLibrary::new(&ARCHITECTURE, CompilerSettings::default());
+    let module = library.get_or_compile("full_adder", &network).unwrap();
+
+    let instance = module.instantiate(SubarrayId(4)).unwrap();
+    assert!(instance.input_map.iter().all(|(row, _)| row.get_subarray_id() == SubarrayId(4)));
+    assert!(instance.output_map.iter().all(|row| row.get_subarray_id() == SubarrayId(4)));
+    assert_eq!(simulate(&instance, &INPUTS).unwrap(), evaluate_network(&network, &INPUTS).unwrap());
+
+    assert_eq!(
+        module.instantiate(SubarrayId(ARCHITECTURE.nr_subarrays)).unwrap_err(),
+        CompileError::Other("subarray of module instance does not exist")
+    );
+}
+
+#[test]
+fn instances_are_linked_into_one_program() {
+    let network = full_adder();
+    let mut library = ModuleLibrary::new(&ARCHITECTURE, CompilerSettings::default());
+    library.get_or_compile("full_adder", &network).unwrap();
+
+    let program = library.instantiate_all(&[("full_adder", SubarrayId(0)), ("full_adder", SubarrayId(2))]).unwrap();
+    // both instances read the same inputs and append their outputs
+    let expected = evaluate_network(&network, &INPUTS).unwrap();
+    assert_eq!(simulate(&program, &INPUTS).unwrap(), [expected.clone(), expected].concat());
+
+    assert_eq!(
+        library.instantiate_all(&[("multiplier", SubarrayId(0))]).unwrap_err(),
+        CompileError::Other("module has not been compiled")
+    );
+}
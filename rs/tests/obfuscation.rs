@@ -0,0 +1,34 @@
+//! Checks the keyed randomization of the row layout.
+use lime_rs::prada::obfuscation::{randomize_layout, RowRemapping};
+use lime_rs::prada::reference::differential_test;
+use lime_rs::prada::stdlib::hamming_distance_network;
+use lime_rs::prelude::*;
+
+#[test]
+fn remappings_are_keyed_permutations() {
+    let remapping = RowRemapping::new(42, 512);
+    assert_eq!(remapping, RowRemapping::new(42, 512));
+    assert_ne!(remapping, RowRemapping::new(43, 512));
+
+    let mut rows = remapping.table().to_vec();
+    rows.sort_unstable();
+    assert_eq!(rows, (0..512).collect::<Vec<u64>>());
+
+    let row = RowAddress(7).local_rowaddress_to_subarray_id(SubarrayId(3));
+    assert_eq!(remapping.map(row).get_subarray_id(), SubarrayId(3));
+    assert_eq!(remapping.unmap(remapping.map(row)), row);
+
+    assert_eq!(RowRemapping::parse(&remapping.to_text()), Ok(remapping));
+    assert!(RowRemapping::parse("0 1\n1 1\n").is_err());
+}
+
+#[test]
+fn randomized_programs_compute_the_same_function() {
+    let network = hamming_distance_network(8);
+    let program = compile(&ARCHITECTURE, &network, CompilerSettings::default()).unwrap();
+    let (randomized, remapping) = randomize_layout(&program, 0xdead_beef);
+    assert_ne!(randomized.output_map, program.output_map);
+    let restored: Vec<RowAddress> = randomized.output_map.iter().map(|row| remapping.unmap(*row)).collect();
+    assert_eq!(restored, program.output_map);
+    assert_eq!(differential_test(&network, &randomized, 4, 7).expect("program should be executable"), None);
+}
@@ -0,0 +1,103 @@
+//! Checks that binarized ONNX models are mapped onto layers computing the same as the model.
+#![cfg(feature = "onnx")]
+use lime_rs::prada::architecture::ARCHITECTURE;
+use lime_rs::prada::onnx::BinaryModel;
+use lime_rs::prada::simulation::simulate;
+use lime_rs::prada::CompilerSettings;
+
+const WEIGHTS: [[f32; 3]; 6] = [
+    [1.0, -1.0, 1.0],
+    [-1.0, -1.0, 1.0],
+    [1.0, 1.0, -1.0],
+    [1.0, -1.0, -1.0],
+    [-1.0, 1.0, 1.0],
+    [1.0, 1.0, 1.0],
+];
+const BIAS: [f32; 3] = [0.0, 2.0, -3.0];
+
+fn varint(mut value: u64) -> Vec<u8> {
+    let mut out = vec!();
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return out;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+fn bytes_field(number: u64, bytes: &[u8]) -> Vec<u8> {
+    [varint(number << 3 | 2), varint(bytes.len() as u64), bytes.to_vec()].concat()
+}
+
+fn varint_field(number: u64, value: u64) -> Vec<u8> {
+    [varint(number << 3), varint(value)].concat()
+}
+
+fn float_tensor(name: &str, dims: &[u64], values: &[f32]) -> Vec<u8> {
+    let mut tensor: Vec<u8> = dims.iter().flat_map(|dim| varint_field(1, *dim)).collect();
+    tensor.extend(varint_field(2, 1));
+    tensor.extend(bytes_field(8, name.as_bytes()));
+    let raw: Vec<u8> = values.iter().flat_map(|value| value.to_le_bytes()).collect();
+    tensor.extend(bytes_field(9, &raw));
+    tensor
+}
+
+fn node(op_type: &str, inputs: &[&str], output: &str) -> Vec<u8> {
+    let mut node: Vec<u8> = inputs.iter().flat_map(|input| bytes_field(1, input.as_bytes())).collect();
+    node.extend(bytes_field(2, output.as_bytes()));
+    node.extend(bytes_field(4, op_type.as_bytes()));
+    node
+}
+
+/// `Sign(x * W + B)`
+fn model() -> Vec<u8> {
+    let weights: Vec<f32> = WEIGHTS.iter().flatten().copied().collect();
+    let graph = [
+        bytes_field(1, &node("MatMul", &["x", "W"], "h")),
+        bytes_field(1, &node("Add", &["h", "B"], "a")),
+        bytes_field(1, &node("Sign", &["a"], "y")),
+        bytes_field(5, &float_tensor("W", &[6, 3], &weights)),
+        bytes_field(5, &float_tensor("B", &[3], &BIAS)),
+        bytes_field(11, &bytes_field(1, b"x")),
+    ]
+    .concat();
+    bytes_field(7, &graph)
+}
+
+#[test]
+fn binarized_model_matches_software() {
+    let model = BinaryModel::from_onnx(&model()).expect("model should be supported");
+    assert_eq!(model.layers.len(), 1);
+    let bundle = model.compile(&ARCHITECTURE, CompilerSettings::default()).expect("model should be compilable");
+    let program = &bundle.kernels[0].program;
+
+    let activations = [
+        0x0123_4567_89ab_cdef,
+        0xfedc_ba98_7654_3210,
+        0x0f0f_0f0f_f0f0_f0f0,
+        0x3333_cccc_5555_aaaa,
+        0xdead_beef_cafe_babe,
+        0x0000_ffff_ffff_0000,
+    ];
+    let outputs = simulate(program, &activations).expect("program should be executable");
+    for bitline in 0..64 {
+        for neuron in 0..3 {
+            let sum: f32 = activations
+                .iter()
+                .zip(WEIGHTS)
+                .map(|(activation, weights)| {
+                    let activation = if (activation >> bitline) & 1 != 0 { 1.0 } else { -1.0 };
+                    activation * weights[neuron]
+                })
+                .sum();
+            assert_eq!(
+                (outputs[neuron] >> bitline) & 1 != 0,
+                sum + BIAS[neuron] >= 0.0,
+                "wrong activation of neuron {neuron} on bitline {bitline}"
+            );
+        }
+    }
+}
@@ -0,0 +1,132 @@
+//! Outputs which are duplicated, complementary to each other, constant or (in networks without
+//! any MAJ) inputs.
+use lime_rs::prada::reference::differential_test;
+use lime_rs::prelude::*;
+
+/// Compiles `network` with and without packed outputs, comparing both against the reference
+fn check(network: &MigNetwork) {
+    let as_is = CompilerSettings::builder().rewrite(false).build();
+    for settings in [as_is, CompilerSettings::builder().rewrite(false).pack_outputs(100).build()] {
+        let program = compile(&ARCHITECTURE, network, settings).expect("network should be compilable");
+        assert_eq!(program.output_map.len(), network.outputs().count());
+        let mismatch = differential_test(network, &program, 4, 0x5eed).expect("programs should be executable");
+        assert_eq!(mismatch, None);
+    }
+}
+
+#[test]
+fn duplicate_outputs() {
+    let mut network = MigNetwork::new();
+    let [a, b, c] = [(); 3].map(|_| network.add_input());
+    let maj = network.maj(a, b, c);
+    network.add_output(maj);
+    network.add_output(maj);
+    network.add_output(maj.invert());
+    network.add_output(maj.invert());
+    check(&network);
+
+    // duplicates which are only present inverted
+    let mut network = MigNetwork::new();
+    let [a, b, c] = [(); 3].map(|_| network.add_input());
+    let maj = network.maj(a, b, c);
+    network.add_output(maj.invert());
+    network.add_output(maj.invert());
+    check(&network);
+}
+
+#[test]
+fn complementary_outputs() {
+    let mut network = MigNetwork::new();
+    let [a, b, c, d] = [(); 4].map(|_| network.add_input());
+    let first = network.maj(a, b, c);
+    let second = network.maj(first.invert(), c, d);
+    network.add_output(second.invert());
+    network.add_output(first);
+    network.add_output(second);
+    network.add_output(first.invert());
+    network.add_output(a.invert());
+    network.add_output(a);
+    check(&network);
+}
+
+#[test]
+fn constant_outputs() {
+    let mut network = MigNetwork::new();
+    let [a, b] = [(); 2].map(|_| network.add_input());
+    let and = network.and(a, b);
+    network.add_output(network.constant(false));
+    network.add_output(and);
+    network.add_output(network.constant(true));
+    network.add_output(network.constant(false));
+    check(&network);
+
+    let settings = CompilerSettings::builder().rewrite(false).build();
+    let program = compile(&ARCHITECTURE, &network, settings).expect("network should be compilable");
+    assert_eq!(simulate(&program, &[0b1100, 0b1010]).unwrap(), vec!(0, 0b1000, u64::MAX, 0));
+}
+
+#[test]
+fn networks_without_majs_compile_to_copy_programs() {
+    let mut network = MigNetwork::new();
+    let [a, b] = [(); 2].map(|_| network.add_input());
+    network.add_output(b);
+    network.add_output(a.invert());
+    network.add_output(network.constant(true));
+    network.add_output(a);
+    check(&network);
+
+    let as_is = CompilerSettings::builder().rewrite(false);
+    for settings in [
+        as_is.build(),
+        CompilerSettings::builder().rewrite(true).build(),
+        as_is.pin_inputs(true).build(),
+        as_is.dual_rail(true).build(),
+        as_is.pack_outputs(100).build(),
+    ] {
+        let program = compile(&ARCHITECTURE, &network, settings).expect("network should be compilable");
+        assert!(program
+            .instructions
+            .iter()
+            .all(|instruction| matches!(instruction, Instruction::AAPRowCopy(..) | Instruction::N(_) | Instruction::DccNot(..))));
+        assert_eq!(simulate(&program, &[0b1100, 0b1010]).unwrap(), vec!(0b1010, !0b1100, u64::MAX, 0b1100));
+    }
+
+    // only constants, i.e. no inputs at all
+    let mut network = MigNetwork::new();
+    network.add_output(network.constant(false));
+    network.add_output(network.constant(true));
+    check(&network);
+}
+
+#[test]
+fn outputs_are_packed_into_the_requested_subarray() {
+    let mut network = MigNetwork::new();
+    let [a, b, c] = [(); 3].map(|_| network.add_input());
+    let maj = network.maj(a, b, c);
+    network.add_output(maj);
+    network.add_output(maj.invert());
+    network.add_output(a);
+
+    let packed = CompilerSettings::builder().rewrite(false).pack_outputs_into(2, 3).build();
+    let program = compile(&ARCHITECTURE, &network, packed).expect("network should be compilable");
+    let expected: Vec<RowAddress> =
+        (3..6).map(|row| RowAddress(row).local_rowaddress_to_subarray_id(SubarrayId(2))).collect();
+    assert_eq!(program.output_map, expected);
+    assert_eq!(differential_test(&network, &program, 4, 0x5eed).unwrap(), None);
+
+    let overlapping = [
+        (
+            CompilerSettings { spill: true, spill_subarray: 2, ..packed },
+            "packed outputs overlap the spill subarray",
+        ),
+        (CompilerSettings { output_subarray: ARCHITECTURE.nr_subarrays, ..packed }, "output subarray doesn't exist"),
+        (
+            // inputs are placed into the highest rows
+            CompilerSettings { pin_inputs: true, output_base: ARCHITECTURE.rows_per_subarray - 3, output_subarray: 0, ..packed },
+            "packed outputs overlap pinned input rows",
+        ),
+    ];
+    for (settings, error) in overlapping {
+        assert_eq!(compile(&ARCHITECTURE, &network, settings).unwrap_err(), CompileError::Other(error));
+    }
+}
@@ -0,0 +1,65 @@
+//! Checks that overridden signals are extracted as received.
+use std::collections::HashMap;
+
+use lime_rs::prada::cost::InstructionClass;
+use lime_rs::prada::reference::differential_test;
+use lime_rs::prada::{compile_scoped, compile_with_overrides, ExtractionOverride};
+use lime_rs::prelude::*;
+
+fn nr_inversions(program: &Program) -> usize {
+    program
+        .instructions
+        .iter()
+        .filter(|instruction| {
+            matches!(InstructionClass::of(*instruction), Some(InstructionClass::Not | InstructionClass::DccNot))
+        })
+        .count()
+}
+
+#[test]
+fn overridden_signals_are_extracted_as_received() {
+    // rewriting pushes the inversion to the inputs, which are stored inverted for free
+    let mut network = MigNetwork::new();
+    let (a, b, c) = (network.add_input(), network.add_input(), network.add_input());
+    let inverted = network.maj(a, b, c).invert();
+    network.add_output(inverted);
+
+    let settings = CompilerSettings::default();
+    let rewritten = compile_with_overrides(&ARCHITECTURE, &network, settings, &HashMap::new()).unwrap();
+    assert_eq!(nr_inversions(&rewritten), 0);
+
+    for marker in [ExtractionOverride::KeepNode, ExtractionOverride::KeepCone] {
+        let overrides = HashMap::from([(inverted, marker)]);
+        let program = compile_with_overrides(&ARCHITECTURE, &network, settings, &overrides).unwrap();
+        assert!(nr_inversions(&program) > 0, "{marker:?} should keep the inversion of the MAJ");
+        assert_eq!(differential_test(&network, &program, 4, 0x5eed).unwrap(), None);
+    }
+}
+
+#[test]
+fn overrides_of_unused_signals_are_rejected() {
+    let mut network = MigNetwork::new();
+    let (a, b, c) = (network.add_input(), network.add_input(), network.add_input());
+    let unused = network.maj(a, b, c);
+    network.add_output(a);
+    let overrides = HashMap::from([(unused, ExtractionOverride::KeepNode)]);
+    let err = compile_with_overrides(&ARCHITECTURE, &network, CompilerSettings::default(), &overrides).unwrap_err();
+    assert_eq!(err, CompileError::Other("overridden signal doesn't drive any output"));
+}
+
+#[test]
+fn scoped_rewriting_leaves_other_outputs_untouched() {
+    let mut network = MigNetwork::new();
+    let inputs: Vec<_> = (0..6).map(|_| network.add_input()).collect();
+    let kernel = network.maj(inputs[0], inputs[1], inputs[2]).invert();
+    let other = network.maj(inputs[3], inputs[4], inputs[5]).invert();
+    network.add_output(kernel);
+    network.add_output(other);
+
+    let settings = CompilerSettings::default();
+    let everything = compile_scoped(&ARCHITECTURE, &network, settings, &[kernel, other]).unwrap();
+    assert_eq!(nr_inversions(&everything), 0);
+    let scoped = compile_scoped(&ARCHITECTURE, &network, settings, &[kernel]).unwrap();
+    assert_eq!(nr_inversions(&scoped), 1, "only the inversion outside of the kernel should remain");
+    assert_eq!(differential_test(&network, &scoped, 4, 0x5eed).unwrap(), None);
+}
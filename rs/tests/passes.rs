@@ -0,0 +1,77 @@
+//! Checks registering custom passes which transform compiled programs.
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use lime_rs::prada::passes::{with_registry, PassRegistry, ProgramPass, PruneDeadInstructions};
+use lime_rs::prada::stdlib::hamming_distance_network;
+use lime_rs::prelude::*;
+
+/// Appends its name to a shared log whenever it runs
+struct Recording {
+    name: &'static str,
+    log: Arc<Mutex<Vec<&'static str>>>,
+}
+
+impl ProgramPass for Recording {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn run(&self, _program: &mut Program) -> Result<(), CompileError> {
+        self.log.lock().unwrap().push(self.name);
+        Ok(())
+    }
+}
+
+struct Counting(Arc<AtomicUsize>);
+
+impl ProgramPass for Counting {
+    fn name(&self) -> &str {
+        "counting"
+    }
+
+    fn run(&self, _program: &mut Program) -> Result<(), CompileError> {
+        self.0.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+#[test]
+fn passes_run_by_order() {
+    let log = Arc::new(Mutex::new(vec!()));
+    let recording = |name| Recording { name, log: log.clone() };
+    let mut registry = PassRegistry::default();
+    registry.register(10, recording("late")).unwrap();
+    registry.register(0, recording("early")).unwrap();
+    registry.register(10, recording("later")).unwrap();
+    registry.register_before("late", recording("before_late")).unwrap();
+    registry.register_after("early", PruneDeadInstructions).unwrap();
+    assert!(registry.register(5, recording("late")).is_err());
+    assert!(registry.register_after("missing", recording("other")).is_err());
+    assert_eq!(registry.names(), vec!("early", "prune_dead_instructions", "before_late", "late", "later"));
+
+    let [a, b, scratch] = [0, 1, 2].map(RowAddress);
+    let mut program = Program::new(
+        &ARCHITECTURE,
+        vec!(Instruction::AAPRowCopy(a, scratch), Instruction::AAPRowCopy(a, b), Instruction::N(b)),
+    );
+    program.input_map = vec!((a, RowInit::Input { index: 0, inverted: false }));
+    program.output_map = vec!(b);
+    registry.run(&mut program).unwrap();
+    assert_eq!(program.instructions, vec!(Instruction::AAPRowCopy(a, b), Instruction::N(b)));
+    assert_eq!(*log.lock().unwrap(), vec!("early", "before_late", "late", "later"));
+
+    assert!(registry.unregister("late"));
+    assert!(!registry.unregister("late"));
+    assert_eq!(registry.names(), vec!("early", "prune_dead_instructions", "before_late", "later"));
+}
+
+#[test]
+fn registered_passes_run_on_compiled_programs() {
+    let runs = Arc::new(AtomicUsize::new(0));
+    with_registry(|registry| registry.register(0, Counting(runs.clone()))).unwrap();
+    let network = hamming_distance_network(4);
+    compile(&ARCHITECTURE, &network, CompilerSettings::default()).unwrap();
+    assert_eq!(runs.load(Ordering::Relaxed), 1);
+    assert!(with_registry(|registry| registry.unregister("counting")));
+}
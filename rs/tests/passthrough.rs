@@ -0,0 +1,34 @@
+//! Checks that networks are compiled exactly as received when rewriting and extraction are skipped.
+use lime_rs::prada::cost::InstructionClass;
+use lime_rs::prada::reference::differential_test;
+use lime_rs::prelude::*;
+
+fn nr_tras(program: &Program) -> usize {
+    program
+        .instructions
+        .iter()
+        .filter(|instruction| InstructionClass::of(*instruction) == Some(InstructionClass::Tra))
+        .count()
+}
+
+#[test]
+fn passthrough_keeps_the_structure_of_the_network() {
+    // two structurally equal MAJs, which the e-graph would merge, feeding a MAJ which rewriting
+    // would remove (MAJ(x, x, y) = x)
+    let mut network = MigNetwork::new();
+    let [a, b, c] = [(); 3].map(|_| network.add_input());
+    let first = network.maj(a, b, c);
+    let second = network.maj(a, b, c);
+    let redundant = network.maj(first, second, c);
+    network.add_output(redundant);
+
+    let rewritten = compile(&ARCHITECTURE, &network, CompilerSettings::default()).unwrap();
+    assert_eq!(nr_tras(&rewritten), 1);
+    let unrewritten = compile(&ARCHITECTURE, &network, CompilerSettings::builder().rewrite(false).build()).unwrap();
+    assert_eq!(nr_tras(&unrewritten), 2);
+
+    let settings = CompilerSettings::builder().passthrough(true).build();
+    let program = compile(&ARCHITECTURE, &network, settings).unwrap();
+    assert_eq!(nr_tras(&program), 3);
+    assert_eq!(differential_test(&network, &program, 4, 0x5eed).unwrap(), None);
+}
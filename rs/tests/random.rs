@@ -0,0 +1,55 @@
+//! Checks that all stochastic components are seeded from the settings and repeatable.
+use lime_rs::prada::activity::{search_activity, ActivitySearch};
+use lime_rs::prada::approximate::ErrorBudget;
+use lime_rs::prada::cache::network_hash;
+use lime_rs::prada::random::{derive, SplitMix, Stream, Xorshift};
+use lime_rs::prada::reference::{differential_test, differential_test_seeded};
+use lime_rs::prada::stdlib::random_network;
+use lime_rs::prelude::*;
+
+#[test]
+fn random_networks_only_depend_on_the_seed() {
+    let network = random_network(6, 40, 4, 7);
+    assert_eq!(network_hash(&random_network(6, 40, 4, 7)), network_hash(&network));
+    assert_ne!(network_hash(&random_network(6, 40, 4, 8)), network_hash(&network));
+    assert_eq!(network.outputs().count(), 4);
+
+    let settings = CompilerSettings::builder().seed(7).build();
+    let program = compile(&ARCHITECTURE, &network, settings).unwrap();
+    assert_eq!(differential_test_seeded(&network, &program, 4, &settings).unwrap(), None);
+}
+
+#[test]
+fn streams_of_the_same_seed_differ() {
+    let settings = CompilerSettings::builder().seed(42).build();
+    let streams = [Stream::Verification, Stream::Approximation, Stream::Activity, Stream::Networks];
+    let seeds: Vec<u64> = streams.iter().map(|stream| settings.stream_seed(*stream)).collect();
+    for (idx, seed) in seeds.iter().enumerate() {
+        assert!(!seeds[..idx].contains(seed));
+    }
+    assert_eq!(derive(42, Stream::Activity), seeds[2]);
+    assert_ne!(derive(43, Stream::Activity), seeds[2]);
+    assert_eq!(ErrorBudget::seeded(&settings).seed, settings.stream_seed(Stream::Approximation));
+    assert_eq!(ActivitySearch::seeded(&settings).seed, settings.stream_seed(Stream::Activity));
+
+    // 0 is a degenerate seed of xorshift
+    let mut zero = Xorshift::new(0);
+    assert_ne!(zero.next_u64(), 0);
+    // every other seed counts
+    assert_ne!(Xorshift::new(2).next_u64(), Xorshift::new(3).next_u64());
+    assert_ne!(SplitMix::new(2).next_u64(), SplitMix::new(3).next_u64());
+}
+
+#[test]
+fn seeded_components_are_repeatable() {
+    let network = random_network(8, 30, 3, 1);
+    let settings = CompilerSettings::builder().seed(3).build();
+    let program = compile(&ARCHITECTURE, &network, settings).unwrap();
+    let search = ActivitySearch { samples: 8, rounds: 1, ..ActivitySearch::seeded(&settings) };
+    assert_eq!(search_activity(&program, search).unwrap(), search_activity(&program, search).unwrap());
+    let seed = settings.stream_seed(Stream::Verification);
+    assert_eq!(
+        differential_test(&network, &program, 2, seed).unwrap(),
+        differential_test_seeded(&network, &program, 2, &settings).unwrap()
+    );
+}
@@ -0,0 +1,98 @@
+//! Differential tests of the optimizing pipeline against the naive reference compiler.
+use lime_rs::prada::architecture::{Capabilities, PRADAArchitecture, ARCHITECTURE};
+use lime_rs::prada::network::MigNetwork;
+use lime_rs::prada::reference::differential_test;
+use lime_rs::prada::stdlib::hamming_distance_network;
+use lime_rs::prada::{compile, CompilerSettings, ControlRow, Instruction, Program, SchedulingPolicy};
+
+/// Selects one of four inputs and XORs it with the parity of the select bits
+fn mux_xor() -> MigNetwork {
+    let mut network = MigNetwork::new();
+    let inputs: Vec<_> = (0..6).map(|_| network.add_input()).collect();
+    let low = network.mux(inputs[4], inputs[1], inputs[0]);
+    let high = network.mux(inputs[4], inputs[3], inputs[2]);
+    let selected = network.mux(inputs[5], high, low);
+    let parity = network.xor(inputs[4], inputs[5]);
+    let out = network.xor(selected, parity);
+    network.add_output(out);
+    network.add_output(out.invert());
+    network.add_output(selected);
+    network
+}
+
+#[test]
+fn optimized_programs_match_reference() {
+    let variants = [
+        CompilerSettings::default(),
+        CompilerSettings { rewrite: false, ..CompilerSettings::default() },
+        CompilerSettings { dual_rail: true, ..CompilerSettings::default() },
+        CompilerSettings { pack_outputs: true, output_base: 100, ..CompilerSettings::default() },
+        CompilerSettings { scheduling: SchedulingPolicy::CriticalPath, ..CompilerSettings::default() },
+    ];
+    for network in [mux_xor(), hamming_distance_network(4)] {
+        for settings in variants {
+            let program = compile(&ARCHITECTURE, &network, settings).expect("network should be compilable");
+            let mismatch = differential_test(&network, &program, 8, 0x5eed).expect("programs should be executable");
+            assert_eq!(mismatch, None);
+        }
+    }
+}
+
+#[test]
+fn mismatches_are_reported() {
+    let network = mux_xor();
+    let mut program =
+        compile(&ARCHITECTURE, &network, CompilerSettings::default()).expect("network should be compilable");
+    program.output_map.swap(0, 1);
+    let mismatch = differential_test(&network, &program, 1, 42)
+        .expect("program should be executable")
+        .expect("swapped outputs should be detected");
+    assert_eq!(mismatch.output, 0);
+    assert_eq!(mismatch.expected, !mismatch.actual);
+    assert_eq!(mismatch.bitline(), 0);
+}
+
+/// Computes `(a & !b) | c`, `!(a & !b)` and `a & !b` using MAJs with a constant operand
+fn and_or() -> MigNetwork {
+    let mut network = MigNetwork::new();
+    let [a, b, c] = [(); 3].map(|_| network.add_input());
+    let and = network.and(a, b.invert());
+    let or = network.or(and, c);
+    network.add_output(or);
+    network.add_output(and.invert());
+    network.add_output(and);
+    network
+}
+
+fn count(program: &Program, instruction: fn(&Instruction) -> bool) -> usize {
+    program.instructions.iter().filter(|candidate| instruction(candidate)).count()
+}
+
+#[test]
+fn constant_majs_are_lowered_onto_native_and_or() {
+    let architecture = PRADAArchitecture { capabilities: Capabilities::DEFAULT | Capabilities::AND_OR, ..ARCHITECTURE.clone() };
+    let network = and_or();
+    let settings = CompilerSettings { rewrite: false, ..CompilerSettings::default() };
+    let program = compile(&architecture, &network, settings).expect("network should be compilable");
+    assert!(count(&program, |instruction| matches!(instruction, Instruction::And(..))) > 0, "{program}");
+    assert!(count(&program, |instruction| matches!(instruction, Instruction::Or(..))) > 0, "{program}");
+    assert_eq!(count(&program, |instruction| matches!(instruction, Instruction::AAPTRA(..))), 0, "{program}");
+    assert_eq!(differential_test(&network, &program, 4, 7).expect("program should be executable"), None);
+}
+
+#[test]
+fn ambit_targets_use_control_rows_and_dcc_negation() {
+    let architecture = PRADAArchitecture {
+        capabilities: Capabilities::DEFAULT | Capabilities::CONTROL_ROWS | Capabilities::DCC,
+        nr_dcc_rows: 2,
+        ..ARCHITECTURE.clone()
+    };
+    let network = and_or();
+    let settings = CompilerSettings { rewrite: false, ..CompilerSettings::default() };
+    let program = compile(&architecture, &network, settings).expect("network should be compilable");
+    assert!(count(&program, |instruction| matches!(instruction, Instruction::ControlTra(_, _, ControlRow::C0))) > 0, "{program}");
+    assert!(count(&program, |instruction| matches!(instruction, Instruction::ControlTra(_, _, ControlRow::C1))) > 0, "{program}");
+    assert!(count(&program, |instruction| matches!(instruction, Instruction::DccNot(..))) > 0, "{program}");
+    assert_eq!(count(&program, |instruction| matches!(instruction, Instruction::AAPTRA(..) | Instruction::N(..))), 0, "{program}");
+    assert_eq!(differential_test(&network, &program, 4, 7).expect("program should be executable"), None);
+}
@@ -0,0 +1,28 @@
+//! Checks that values on deep dependency chains are preferably stored in reliable rows.
+use lime_rs::prada::reference::differential_test;
+use lime_rs::prada::stdlib::hamming_distance_network;
+use lime_rs::prelude::*;
+
+/// Mean reliability weight of the rows taking part in TRAs
+fn mean_tra_weight(program: &Program, weights: &[u32]) -> f64 {
+    let rows: Vec<RowAddress> = program
+        .instructions
+        .iter()
+        .filter(|instruction| matches!(instruction, Instruction::AAPTRA(..)))
+        .flat_map(|instruction| instruction.used_addresses())
+        .collect();
+    rows.iter().map(|row| weights[row.0 as usize] as f64).sum::<f64>() / rows.len() as f64
+}
+
+#[test]
+fn deep_chains_are_placed_into_reliable_rows() {
+    let network = hamming_distance_network(8);
+    // rows close to the sense amplifiers (low addresses) are the most reliable
+    let weights: Vec<u32> = (0..ARCHITECTURE.rows_per_subarray as u32).rev().collect();
+    let architecture = Architecture { row_reliability: weights.clone(), ..ARCHITECTURE.clone() };
+
+    let uniform = compile(&ARCHITECTURE, &network, CompilerSettings::default()).unwrap();
+    let weighted = compile(&architecture, &network, CompilerSettings::default()).unwrap();
+    assert!(mean_tra_weight(&weighted, &weights) > mean_tra_weight(&uniform, &weights));
+    assert_eq!(differential_test(&network, &weighted, 4, 7).expect("program should be executable"), None);
+}
@@ -0,0 +1,71 @@
+//! Markdown and HTML reports of compiled programs and sweeps, including the sweeps' failures.
+use lime_rs::prada::architecture::PRADAArchitecture;
+use lime_rs::prada::report::{ProgramSummary, Report, Sweep};
+use lime_rs::prelude::*;
+
+fn summary(name: &str, runtime_estimate: u64, energy_consumption_estimate: u64) -> ProgramSummary {
+    ProgramSummary { name: name.to_string(), instruction_count: 3, runtime_estimate, energy_consumption_estimate }
+}
+
+fn report() -> Report {
+    let sweep = Sweep {
+        parameter: "rows".to_string(),
+        points: vec!((1, summary("rows=1", 10, 7)), (2, summary("rows=2", 20, 7))),
+        failures: vec!((0, CompileError::Other("out of <rows>"))),
+    };
+    Report { programs: vec!(summary("a|b", 5, 6)), sweeps: vec!(sweep), ..Report::new("PIM & co") }
+}
+
+#[test]
+fn markdown_tables() {
+    let markdown = report().to_markdown();
+    let lines: Vec<&str> = markdown.lines().collect();
+    assert_eq!(lines[0], "# PIM & co");
+    assert!(lines.contains(&"| Program | Instructions | Runtime [ns] | Energy [mJ/KOps] |"));
+    assert!(lines.contains(&"| a\\|b | 3 | 5 | 6 |"), "separators in labels are escaped");
+    assert!(lines.contains(&"## Sweep over `rows`"));
+    assert!(lines.contains(&"| 2 | 3 | 20 | 7 |"));
+    assert!(lines.contains(&"- rows = 0 failed: out of <rows>"));
+}
+
+#[test]
+fn html_document_with_charts() {
+    let html = report().to_html();
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert!(html.trim_end().ends_with("</html>"));
+    assert!(html.contains("<title>PIM &amp; co</title>"));
+    assert!(html.contains("<tr><td>a|b</td><td>3</td><td>5</td><td>6</td></tr>"));
+    assert!(html.contains("<li>rows = 0 failed: out of &lt;rows&gt;</li>"));
+
+    // one chart of the runtime and one of the energy, spanning the whole plot area
+    assert_eq!(html.matches("<svg").count(), 2);
+    assert!(html.contains("points=\"50.0,190.0 430.0,50.0\""));
+    assert_eq!(html.matches("<circle").count(), 4);
+    // the constant energy is centered
+    assert!(html.contains("points=\"50.0,120.0 430.0,120.0\""));
+}
+
+#[test]
+fn sweeps_record_failures() {
+    let mut network = MigNetwork::new();
+    let [a, b, c] = [(); 3].map(|_| network.add_input());
+    let maj = network.maj(a, b, c);
+    let out = network.maj(maj.invert(), a, b);
+    network.add_output(out);
+
+    // the network can't be compiled without negations
+    let settings = CompilerSettings::builder().rewrite(false).build();
+    let sweep = Sweep::run("negations", [0, 1], &network, settings, |negations| {
+        let mut capabilities = Capabilities::DEFAULT;
+        if negations == 0 {
+            capabilities.remove(Capabilities::NOT);
+        }
+        PRADAArchitecture { capabilities, ..ARCHITECTURE.clone() }
+    });
+    assert_eq!(sweep.points.iter().map(|(value, _)| *value).collect::<Vec<_>>(), [1]);
+    assert_eq!(sweep.failures.len(), 1);
+    assert!(matches!(
+        sweep.failures[0],
+        (0, CompileError::UnsupportedOperation { missing: Capabilities::NOT, .. })
+    ));
+}
@@ -0,0 +1,62 @@
+//! Rows reserved by a placement or by pinned inputs are never handed out for other values, and
+//! outputs are recognized as such while scheduling.
+use std::collections::HashMap;
+
+use lime_rs::prada::decisions::{Decision, Replay};
+use lime_rs::prada::simulation::evaluate_network;
+use lime_rs::prada::{compile_replaying, compile_with_placement, CompileOptions};
+use lime_rs::prelude::*;
+
+const INPUTS: [u64; 3] = [0xf0f0_f0f0_f0f0_f0f0, 0xcccc_cccc_cccc_cccc, 0xaaaa_aaaa_aaaa_aaaa];
+
+/// Full adder whose carry is used by another output as well
+fn network() -> MigNetwork {
+    let mut network = MigNetwork::new();
+    let [a, b, c] = [(); 3].map(|_| network.add_input());
+    let (sum, carry) = network.full_adder(a, b, c);
+    let chained = network.maj(carry, sum, a.invert());
+    network.add_output(sum);
+    network.add_output(carry);
+    network.add_output(chained);
+    network
+}
+
+#[test]
+fn pinned_rows_are_never_reallocated() {
+    let network = network();
+    let options = CompileOptions { pin_inputs: true, log_decisions: true, ..CompileOptions::default() };
+    let (program, _) = compile_replaying(&ARCHITECTURE, &network.with_backward_edges(), options, Replay::default())
+        .expect("network should be compilable");
+    assert_eq!(simulate(&program, &INPUTS).unwrap(), evaluate_network(&network, &INPUTS).unwrap());
+
+    let pinned: Vec<RowAddress> = program.input_map.iter().map(|(row, _)| *row).collect();
+    for decision in &program.decisions {
+        if let Decision::RowAllocated { row } | Decision::RowFreed { row } = decision {
+            assert!(!pinned.contains(row), "pinned row {row} is reallocated");
+        }
+    }
+
+    let outputs: Vec<_> = network.outputs().map(|output| output.node_id()).collect();
+    let mut scheduled = 0;
+    for decision in &program.decisions {
+        if let Decision::Schedule { node, output, .. } = decision {
+            assert_eq!(*output, outputs.contains(node));
+            scheduled += 1;
+        }
+    }
+    assert!(scheduled >= outputs.len());
+}
+
+#[test]
+fn placed_rows_are_reserved() {
+    let network = network();
+    let outputs: Vec<Signal> = network.outputs().collect();
+    let placement: HashMap<Signal, RowAddress> =
+        outputs.iter().enumerate().map(|(idx, output)| (*output, RowAddress(idx as u64))).collect();
+    let program = compile_with_placement(&ARCHITECTURE, &network.with_backward_edges(), placement)
+        .expect("placement should be compilable");
+    assert_eq!(program.output_map, [RowAddress(0), RowAddress(1), RowAddress(2)]);
+    assert_eq!(simulate(&program, &INPUTS).unwrap(), evaluate_network(&network, &INPUTS).unwrap());
+    // the placed rows aren't handed out for other values, e.g. the inputs
+    assert!(program.input_map.iter().all(|(row, _)| row.0 >= 3));
+}
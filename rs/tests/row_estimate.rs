@@ -0,0 +1,23 @@
+//! Checks the estimate of the rows needed for compiling a network against compiled programs.
+use lime_rs::prada::estimate_rows_needed;
+use lime_rs::prada::stdlib::{hamming_distance_network, random_network};
+use lime_rs::prelude::*;
+
+#[test]
+fn estimate_bounds_peak_row_usage() {
+    let mut adder = MigNetwork::new();
+    let [a, b, c] = [(); 3].map(|_| adder.add_input());
+    let (sum, carry) = adder.full_adder(a, b, c);
+    adder.add_output(sum);
+    adder.add_output(carry);
+
+    let mut networks = vec!(adder, hamming_distance_network(4));
+    networks.extend((1..=4).map(|seed| random_network(6, 40, 4, seed)));
+    for network in networks {
+        let estimate = estimate_rows_needed(&network.with_backward_edges());
+        let settings = CompilerSettings::builder().rewrite(false).build();
+        let program = compile(&ARCHITECTURE, &network, settings).expect("network should be compilable");
+        let peak = program.peak_row_usage() as u64;
+        assert!(peak <= estimate, "peak row usage {peak} exceeds the estimate {estimate}");
+    }
+}
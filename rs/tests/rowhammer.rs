@@ -0,0 +1,50 @@
+//! Checks the row-hammer analysis and the insertion of dummy refreshes.
+use lime_rs::prada::rowhammer::{analyze, insert_refreshes, RowHammerConfig, Violation};
+use lime_rs::prelude::*;
+
+/// Hammers rows 0 and 2, disturbing row 1 twice per iteration
+fn hammering_program() -> Program<'static> {
+    let [a, b, c] = [0, 1, 2].map(RowAddress);
+    let mut program = Program::new(
+        &ARCHITECTURE,
+        vec!(Instruction::LoopBegin(10), Instruction::AAPRowCopy(a, c), Instruction::LoopEnd),
+    );
+    program.input_map = (0..3).map(|index| (RowAddress(index), RowInit::Input { index, inverted: false })).collect();
+    program.output_map = vec!(b, c);
+    program
+}
+
+#[test]
+fn disturbances_beyond_the_threshold_are_flagged() {
+    let config = RowHammerConfig { threshold: 10, refresh_window: 64_000_000 };
+    let report = analyze(&hammering_program(), &config);
+    assert_eq!(report.activations[&RowAddress(0)], 10);
+    assert_eq!(report.max_disturbance[&RowAddress(1)], 20);
+    // the 6th copy disturbs row 1 for the 11th and 12th time
+    assert_eq!(report.violations, vec!(Violation { row: RowAddress(1), window: 0, instruction: 5 }));
+}
+
+#[test]
+fn periodic_refreshes_reset_the_disturbance() {
+    // a refresh every 4 row copies
+    let copy = ARCHITECTURE.cost_model.instruction_cost(&Instruction::AAPRowCopy(RowAddress(0), RowAddress(2)));
+    let config = RowHammerConfig { threshold: 10, refresh_window: 4 * copy.runtime };
+    let report = analyze(&hammering_program(), &config);
+    assert!(report.is_safe(), "{:?}", report.violations);
+    assert_eq!(report.max_disturbance[&RowAddress(1)], 6);
+}
+
+#[test]
+fn dummy_refreshes_make_programs_safe() {
+    let config = RowHammerConfig { threshold: 10, refresh_window: 64_000_000 };
+    let mut program = hammering_program();
+    let inputs = [0b0011, 0b0101, 0b1001];
+    let before = simulate(&program, &inputs).unwrap();
+
+    assert!(insert_refreshes(&mut program, &config).unwrap() > 0);
+    assert!(analyze(&program, &config).is_safe());
+    assert!(program.instructions.contains(&Instruction::AAPRowCopy(RowAddress(1), RowAddress(1))));
+    assert_eq!(simulate(&program, &inputs).unwrap(), before);
+
+    assert!(insert_refreshes(&mut program, &RowHammerConfig { threshold: 3, ..config }).is_err());
+}
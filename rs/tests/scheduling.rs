@@ -0,0 +1,79 @@
+//! Compares the scheduling policies of the compiler regarding row usage and checks that they don't
+//! change the function of the compiled programs.
+use eggmock::Signal;
+use lime_rs::prada::architecture::ARCHITECTURE;
+use lime_rs::prada::network::MigNetwork;
+use lime_rs::prada::simulation::{evaluate_network, simulate};
+use lime_rs::prada::{compile, CompilerSettings, SchedulingPolicy};
+
+const NR_INPUTS: usize = 6;
+
+/// Carry logic of a tree of full adders: every MAJ combines the carries of three subtrees, the
+/// `3^depth` leaves are MAJs of (structurally distinct) combinations of the inputs
+fn carry_tree(depth: u32) -> MigNetwork {
+    let mut network = MigNetwork::new();
+    let inputs: Vec<Signal> = (0..NR_INPUTS).map(|_| network.add_input()).collect();
+    let mut level: Vec<Signal> = (0..3usize.pow(depth))
+        .map(|i| {
+            let polarities = i / NR_INPUTS;
+            let operand = |offset: usize, bit: usize| {
+                inputs[(i + offset) % NR_INPUTS].maybe_invert(polarities & (1 << bit) != 0)
+            };
+            let (a, b, c) = (operand(0, 0), operand(2, 1), operand(4, 2));
+            // swap operands once all polarities have been used, so that no leaf occurs twice
+            if polarities < 8 {
+                network.maj(a, b, c)
+            } else {
+                network.maj(a, c, b)
+            }
+        })
+        .collect();
+    while level.len() > 1 {
+        level = level
+            .chunks(3)
+            .map(|carries| network.maj(carries[0], carries[1], carries[2]))
+            .collect();
+    }
+    network.add_output(level[0]);
+    network
+}
+
+fn peak_row_usage(network: &MigNetwork, scheduling: SchedulingPolicy) -> usize {
+    // compile the network as is, rewriting might restructure the trees
+    let settings = CompilerSettings::builder().rewrite(false).scheduling(scheduling).build();
+    compile(&ARCHITECTURE, network, settings).expect("network should be compilable").peak_row_usage()
+}
+
+#[test]
+fn sethi_ullman_reduces_peak_row_usage() {
+    let network = carry_tree(4);
+    let greedy = peak_row_usage(&network, SchedulingPolicy::Greedy);
+    let sethi_ullman = peak_row_usage(&network, SchedulingPolicy::SethiUllman);
+    assert!(
+        sethi_ullman < greedy,
+        "Sethi-Ullman scheduling should need fewer rows than greedy scheduling ({sethi_ullman} vs. {greedy})"
+    );
+}
+
+#[test]
+fn policies_preserve_function() {
+    let network = carry_tree(3);
+    let inputs = [
+        0x0123_4567_89ab_cdef,
+        0xfedc_ba98_7654_3210,
+        0x0f0f_0f0f_f0f0_f0f0,
+        0x3333_cccc_5555_aaaa,
+        0xdead_beef_cafe_babe,
+        0x0000_ffff_ffff_0000,
+    ];
+    let expected = evaluate_network(&network, &inputs).unwrap();
+    for scheduling in [SchedulingPolicy::Greedy, SchedulingPolicy::CriticalPath, SchedulingPolicy::SethiUllman] {
+        let settings = CompilerSettings::builder().rewrite(false).scheduling(scheduling).build();
+        let program = compile(&ARCHITECTURE, &network, settings).expect("network should be compilable");
+        assert_eq!(
+            simulate(&program, &inputs).expect("program should be executable"),
+            expected,
+            "simulated program differs from network for {scheduling:?}"
+        );
+    }
+}
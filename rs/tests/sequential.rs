@@ -0,0 +1,38 @@
+//! Checks that per-cycle programs of sequential circuits, whose state is kept in persistent rows
+//! between invocations, compute the same as the unrolled circuit.
+use lime_rs::prada::architecture::ARCHITECTURE;
+use lime_rs::prada::network::MigNetwork;
+use lime_rs::prada::sequential::SequentialNetwork;
+use lime_rs::prada::simulation::{evaluate_network, simulate_invocations};
+
+/// Parity of all inputs seen so far: the output is the parity before the current cycle, i.e. it is
+/// read directly from the state
+fn running_parity() -> SequentialNetwork {
+    let mut network = MigNetwork::new();
+    let input = network.add_input();
+    let parity = network.add_input();
+    let next = network.xor(parity, input);
+    network.add_output(parity);
+    network.add_output(next);
+    SequentialNetwork::new(network, vec!(false)).unwrap()
+}
+
+#[test]
+fn cycle_program_matches_unrolled_network() {
+    let sequential = running_parity();
+    let inputs = [0x0123_4567_89ab_cdef, 0xfedc_ba98_7654_3210, 0x3333_cccc_5555_aaaa, 0xdead_beef_cafe_babe];
+
+    let cycle = sequential.compile_cycle(&ARCHITECTURE).expect("circuit should be compilable");
+    assert!(
+        cycle.state_rows.iter().all(|row| cycle.program.persistent_rows.contains(*row)),
+        "state rows should be declared persistent"
+    );
+    let invocations: Vec<&[u64]> = inputs.iter().map(std::slice::from_ref).collect();
+    let outputs: Vec<u64> = simulate_invocations(&cycle.program, &[], &invocations)
+        .expect("program should be executable")
+        .concat();
+
+    let unrolled = sequential.unroll(inputs.len() as u64);
+    let expected = evaluate_network(&unrolled, &inputs).unwrap();
+    assert_eq!(outputs, expected[..inputs.len()]);
+}
@@ -0,0 +1,30 @@
+//! Checks the row image snapshots of the simulator against a golden image.
+use lime_rs::prada::architecture::{RowAddress, ARCHITECTURE};
+use lime_rs::prada::simulation::{RowImage, Simulator};
+use lime_rs::prada::stdlib::{bitmap_scan, BitmapOp};
+
+const GOLDEN: &str = "
+# data, result, query, constant, scratch
+0 00000000ffff0000
+1 00000000ffff0000
+2 0000000000ff0000
+3 0000000000ff0000
+4 00ff00ff00ff00ff
+5 0000000000000000
+6 0000000000ff0000
+7 0000000000ff0000
+";
+
+#[test]
+fn final_state_matches_golden_image() {
+    let program = bitmap_scan(&ARCHITECTURE, BitmapOp::And, 2).unwrap().program;
+    let mut simulator = Simulator::new();
+    simulator.load_image(&RowImage::parse("0 00000000ffff0000\n1 00000000ffff0000\n4 00ff00ff00ff00ff\n5 0").unwrap());
+    simulator.run(&program.instructions).unwrap();
+
+    let image = simulator.snapshot();
+    let golden = RowImage::parse(GOLDEN).unwrap();
+    assert_eq!(image.diff(&golden), vec!());
+    assert_eq!(RowImage::parse(&image.to_string()).unwrap(), image);
+    assert_eq!(simulator.row(RowAddress(3)), Some(0x0000_0000_00ff_0000));
+}
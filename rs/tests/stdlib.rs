@@ -0,0 +1,101 @@
+//! Checks the kernels of the stdlib which are compiled through the standard pipeline against
+//! software implementations.
+use lime_rs::prada::architecture::ARCHITECTURE;
+use lime_rs::prada::random::Xorshift;
+use lime_rs::prada::simulation::simulate;
+use lime_rs::prada::stdlib::{
+    aes_sbox, aes_sbox_table, hamming_distance, present_permutation, present_round, PRESENT_SBOX,
+};
+use lime_rs::prada::CompilerSettings;
+
+/// Deterministic pseudo-random rows
+fn random_rows(n: usize, seed: u64) -> Vec<u64> {
+    let mut random = Xorshift::new(seed);
+    (0..n).map(|_| random.next_u64()).collect()
+}
+
+/// Transposes bit-serial rows into the words stored on each of the 64 bitlines
+fn words(rows: &[u64]) -> Vec<u64> {
+    (0..64)
+        .map(|bitline| {
+            rows.iter()
+                .enumerate()
+                .map(|(bit, row)| ((row >> bitline) & 1) << bit)
+                .sum()
+        })
+        .collect()
+}
+
+#[test]
+fn hamming_distance_matches_software() {
+    const WIDTH: usize = 8;
+    let stored = random_rows(WIDTH, 0x0123_4567_89ab_cdef);
+    // the query is replicated on every bitline
+    let query: u64 = 0b1011_0010;
+    let query_rows: Vec<u64> = (0..WIDTH).map(|bit| if query & (1 << bit) != 0 { u64::MAX } else { 0 }).collect();
+
+    let settings = CompilerSettings::default();
+    let program = hamming_distance(&ARCHITECTURE, WIDTH as u64, settings).expect("network should be compilable");
+    let inputs = [stored.clone(), query_rows].concat();
+    let outputs = simulate(&program, &inputs).expect("program should be executable");
+
+    let distances = words(&outputs);
+    for (bitline, word) in words(&stored).into_iter().enumerate() {
+        assert_eq!(
+            distances[bitline],
+            (word ^ query).count_ones() as u64,
+            "wrong distance on bitline {bitline}"
+        );
+    }
+}
+
+/// Returns the `n` rows storing `words[j]` bit-serially on bitline `j`
+fn rows(words: &[u64], n: usize) -> Vec<u64> {
+    (0..n)
+        .map(|bit| {
+            words
+                .iter()
+                .enumerate()
+                .map(|(bitline, word)| ((word >> bit) & 1) << bitline)
+                .sum()
+        })
+        .collect()
+}
+
+#[test]
+fn aes_sbox_matches_table() {
+    let program = aes_sbox(&ARCHITECTURE, CompilerSettings::default()).expect("network should be compilable");
+    let table = aes_sbox_table();
+    // 64 bytes per run
+    for batch in 0..4u64 {
+        let bytes: Vec<u64> = (0..64).map(|bitline| 64 * batch + bitline).collect();
+        let outputs = simulate(&program, &rows(&bytes, 8)).expect("program should be executable");
+        for (byte, substituted) in bytes.iter().zip(words(&outputs)) {
+            assert_eq!(substituted, table[*byte as usize] as u64, "wrong substitution of {byte:#04x}");
+        }
+    }
+}
+
+fn present_round_reference(state: u64, key: u64) -> u64 {
+    let keyed = state ^ key;
+    let substituted = (0..16).fold(0, |acc, nibble| {
+        acc | (PRESENT_SBOX[((keyed >> (4 * nibble)) & 0xf) as usize] as u64) << (4 * nibble)
+    });
+    (0..64).fold(0, |acc, bit| acc | ((substituted >> bit) & 1) << present_permutation(bit))
+}
+
+#[test]
+fn present_round_matches_software() {
+    let program = present_round(&ARCHITECTURE, CompilerSettings::default()).expect("network should be compilable");
+    let states = random_rows(64, 0xdead_beef_cafe_babe);
+    let keys = random_rows(64, 0x0f0f_0f0f_f0f0_f0f0);
+    let inputs = [rows(&states, 64), rows(&keys, 64)].concat();
+    let outputs = simulate(&program, &inputs).expect("program should be executable");
+    for (bitline, state) in words(&outputs).into_iter().enumerate() {
+        assert_eq!(
+            state,
+            present_round_reference(states[bitline], keys[bitline]),
+            "wrong round result on bitline {bitline}"
+        );
+    }
+}
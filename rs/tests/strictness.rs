@@ -0,0 +1,69 @@
+//! Checks escalating selected diagnostics into errors depending on the strictness of compilations.
+use lime_rs::prada::diagnostics::{DiagnosticCode, Severity};
+use lime_rs::prada::stdlib::hamming_distance_network;
+use lime_rs::prelude::*;
+
+/// Architecture of which only a TRA group of rows outside of its subarrays has been verified
+fn unverified_architecture() -> Architecture {
+    Architecture { verified_tra_groups: vec!([u64::MAX - 2, u64::MAX - 1, u64::MAX]), ..ARCHITECTURE.clone() }
+}
+
+#[test]
+fn permissive_compilations_keep_warnings() {
+    let architecture = unverified_architecture();
+    let network = hamming_distance_network(4);
+    let program = compile(&architecture, &network, CompilerSettings::default()).unwrap();
+    assert!(!program.diagnostics.is_empty());
+    for diagnostic in &program.diagnostics {
+        assert_eq!(diagnostic.code, DiagnosticCode::UnverifiedTraGrouping);
+        assert_eq!(diagnostic.severity, Severity::Warning);
+    }
+}
+
+#[test]
+fn production_compilations_fail_on_escalated_diagnostics() {
+    let settings = CompilerSettings::builder().strictness(Strictness::PRODUCTION).build();
+    let network = hamming_distance_network(4);
+    assert!(compile(&ARCHITECTURE, &network, settings).is_ok());
+
+    let error = compile(&unverified_architecture(), &network, settings).unwrap_err();
+    let CompileError::Escalated { code, diagnostics } = error else {
+        panic!("unverified TRA groupings should be escalated");
+    };
+    assert_eq!(code, DiagnosticCode::UnverifiedTraGrouping);
+    // the escalated diagnostics are returned for the caller to report them
+    assert!(!diagnostics.is_empty());
+    for diagnostic in &diagnostics {
+        assert_eq!((diagnostic.code, diagnostic.severity), (DiagnosticCode::UnverifiedTraGrouping, Severity::Error));
+    }
+
+    let mut architecture = ARCHITECTURE.clone();
+    architecture.capabilities.remove(Capabilities::NOT);
+    assert!(matches!(
+        compile(&architecture, &network, settings).unwrap_err(),
+        CompileError::Escalated { code: DiagnosticCode::CapabilityEmulation, .. }
+    ));
+}
+
+#[test]
+fn only_selected_diagnostics_are_escalated() {
+    let mut network = MigNetwork::new();
+    let [a, b] = [(); 2].map(|_| network.add_input());
+    let and = network.and(a, b);
+    network.add_output(and);
+    network.add_output(network.constant(false));
+
+    let settings = CompilerSettings::builder().strictness(Strictness::PRODUCTION).build();
+    let program = compile(&ARCHITECTURE, &network, settings).unwrap();
+    assert_eq!(program.diagnostics[0].code, DiagnosticCode::ConstantOutput);
+
+    let settings = CompilerSettings::builder()
+        .strictness(Strictness::CONSTANT_OUTPUT | Strictness::ROW_BUDGET)
+        .build();
+    assert!(matches!(
+        compile(&ARCHITECTURE, &network, settings).unwrap_err(),
+        CompileError::Escalated { code: DiagnosticCode::ConstantOutput, .. }
+    ));
+    assert!(Strictness::PRODUCTION.escalates(DiagnosticCode::RetentionRisk));
+    assert!(!Strictness::PRODUCTION.escalates(DiagnosticCode::RowBudget));
+}
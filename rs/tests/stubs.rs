@@ -0,0 +1,43 @@
+//! Checks that the generated host stubs initialize every input row and read every output row.
+use lime_rs::prada::architecture::ARCHITECTURE;
+use lime_rs::prada::stdlib::{bitmap_scan, BitmapOp};
+use lime_rs::prada::stubs::{c_stub, rust_stub, BitOrder, ByteOrder, Marshalling};
+
+#[test]
+fn stubs_cover_input_and_output_rows() {
+    let program = bitmap_scan(&ARCHITECTURE, BitmapOp::AndNot, 3).unwrap().program;
+    let c = c_stub(&program, "scan", Marshalling::default()).unwrap();
+    let rust = rust_stub(&program, "scan", Marshalling::default()).unwrap();
+
+    assert!(c.contains("#define SCAN_NR_INPUTS 4") && c.contains("#define SCAN_NR_OUTPUTS 3"));
+    assert!(c.contains(&format!("#define SCAN_PROGRAM_LENGTH {}", program.instructions.len())));
+    assert!(rust.contains("pub const NR_INPUTS: usize = 4;"));
+    for (row, _) in &program.input_map {
+        assert!(c.contains(&format!("driver->write_row(driver->ctx, {}u,", row.0)));
+        assert!(rust.contains(&format!("driver.write_row({},", row.0)));
+    }
+    for (idx, row) in program.output_map.iter().enumerate() {
+        assert!(c.contains(&format!("driver->read_row(driver->ctx, {}u, outputs[{idx}], row_bytes);", row.0)));
+        assert!(rust.contains(&format!("driver.read_row({}, outputs[{idx}]);", row.0)));
+    }
+    // the query of ANDNOT is stored inverted
+    assert!(c.contains("scratch[i] = (uint8_t)~scratch[i];"));
+    assert!(c_stub(&program, "3scan", Marshalling::default()).is_err());
+}
+
+#[test]
+fn marshalling_converts_between_buffers_and_row_images() {
+    let marshalling = Marshalling { bit_order: BitOrder::MsbFirst, byte_order: ByteOrder::BigEndian { word_bytes: 2 } };
+    // bitline 0 set, i.e. the MSB of the second byte of the first big-endian word
+    let mut buffer = vec!(0x00, 0x80, 0x00, 0x00, 0x01);
+    marshalling.convert(&mut buffer);
+    assert_eq!(buffer, vec!(0x01, 0x00, 0x00, 0x00, 0x80));
+    marshalling.convert(&mut buffer);
+    assert_eq!(buffer, vec!(0x00, 0x80, 0x00, 0x00, 0x01));
+
+    let program = bitmap_scan(&ARCHITECTURE, BitmapOp::And, 1).unwrap().program;
+    let c = c_stub(&program, "scan", marshalling).unwrap();
+    let rust = rust_stub(&program, "scan", marshalling).unwrap();
+    assert!(c.contains("scan_convert(scratch, row_bytes);") && c.contains("scan_convert(outputs[0], row_bytes);"));
+    assert!(rust.contains("convert(&mut data);") && rust.contains("convert(outputs[0]);"));
+}
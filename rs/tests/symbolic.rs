@@ -0,0 +1,78 @@
+//! Checks the symbolic evaluation of programs and the pruning of dead instructions.
+use lime_rs::prada::cec::{check_equivalence, Equivalence};
+use lime_rs::prada::simulation::evaluate_network;
+use lime_rs::prada::stdlib::hamming_distance_network;
+use lime_rs::prada::symbolic::{dead_instructions, evaluate_program, prune_dead_instructions};
+use lime_rs::prelude::*;
+
+fn handwritten_program() -> Program<'static> {
+    let [a, b, c, scratch] = [0, 1, 2, 3].map(RowAddress);
+    let mut program = Program::new(
+        &ARCHITECTURE,
+        vec!(
+            // dead: overwritten before being read
+            Instruction::AAPRowCopy(a, scratch),
+            // dead: `c` is never read afterwards
+            Instruction::N(c),
+            Instruction::AAPRowCopy(b, scratch),
+            // dead: `scratch` already holds `b`
+            Instruction::AAPRowCopy(b, scratch),
+            Instruction::LoopBegin(2),
+            Instruction::N(scratch),
+            Instruction::LoopEnd,
+            Instruction::And(a, scratch, c),
+        ),
+    );
+    program.input_map = vec!(
+        (a, RowInit::Input { index: 0, inverted: false }),
+        (b, RowInit::Input { index: 1, inverted: true }),
+        (c, RowInit::Constant(false)),
+    );
+    program.output_map = vec!(c);
+    program
+}
+
+#[test]
+fn rows_hold_the_functions_of_the_inputs() {
+    let program = handwritten_program();
+    let evaluation = evaluate_program(&program).unwrap();
+
+    // c = a & !b
+    let mut expected = MigNetwork::new();
+    let [a, b] = [(); 2].map(|_| expected.add_input());
+    let and = expected.and(a, b.invert());
+    expected.add_output(and);
+    assert_eq!(check_equivalence(&evaluation.network, &expected), Ok(Equivalence::Equivalent));
+    assert_eq!(evaluation.row(RowAddress(0)), Some(a));
+    assert_eq!(evaluation.row(RowAddress(4)), None);
+}
+
+#[test]
+fn dead_instructions_are_pruned() {
+    let mut program = handwritten_program();
+    assert_eq!(dead_instructions(&program), Ok(vec!(0, 1, 3)));
+
+    let inputs = [0b0011, 0b0101];
+    let before = simulate(&program, &inputs).unwrap();
+    assert_eq!(prune_dead_instructions(&mut program), Ok(3));
+    assert_eq!(program.instructions.len(), 5);
+    assert_eq!(simulate(&program, &inputs).unwrap(), before);
+    assert_eq!(dead_instructions(&program), Ok(vec!()));
+}
+
+#[test]
+fn compiled_programs_keep_their_function_when_pruned() {
+    let network = hamming_distance_network(4);
+    let mut program = compile(&ARCHITECTURE, &network, CompilerSettings::default()).unwrap();
+    prune_dead_instructions(&mut program).unwrap();
+
+    let inputs: Vec<u64> = (0..8).map(|index| 0x0123_4567_89ab_cdef_u64.rotate_left(index * 8)).collect();
+    assert_eq!(simulate(&program, &inputs).unwrap(), evaluate_network(&network, &inputs).unwrap());
+}
+
+#[test]
+fn reads_of_uninitialized_rows_are_rejected() {
+    let mut program = handwritten_program();
+    program.instructions.push(Instruction::AAPRowCopy(RowAddress(4), RowAddress(0)));
+    assert!(evaluate_program(&program).is_err());
+}
@@ -0,0 +1,56 @@
+//! Checks naming the inputs, outputs and rows of programs after the metadata of their signals.
+use std::collections::HashMap;
+
+use lime_rs::prada::stubs::{c_stub, rust_stub, Marshalling};
+use lime_rs::prada::{compile_annotated, NodeMetadata};
+use lime_rs::prelude::*;
+
+/// `out = a & b` for the inputs `a` and `b`, of which only `a` is named
+fn and_network() -> (MigNetwork, HashMap<Signal, NodeMetadata>) {
+    let mut network = MigNetwork::new();
+    let a = network.add_input();
+    let b = network.add_input();
+    let and = network.and(a, b);
+    network.add_output(and);
+    let metadata = HashMap::from([(a, NodeMetadata::named("a")), (and, NodeMetadata::bit("result", 0))]);
+    (network, metadata)
+}
+
+#[test]
+fn symbols_use_metadata_or_generated_names() {
+    let (network, metadata) = and_network();
+    let program = compile_annotated(&ARCHITECTURE, &network, CompilerSettings::default(), &metadata).unwrap();
+    let symbols = program.symbols();
+    assert_eq!(symbols.input(0), Some("a"));
+    assert_eq!(symbols.input(1), Some("in1"));
+    assert_eq!(symbols.output(0), Some("result[0]"));
+
+    let result = symbols.row(program.output_map[0]).unwrap();
+    assert_eq!(symbols.lookup(result), Some(program.output_map[0]));
+    for (row, init) in &program.input_map {
+        if let RowInit::Input { index: 0, inverted: false } = init {
+            assert_eq!(symbols.row(*row), Some("a"));
+        }
+    }
+
+    // the rows of the listing carry their names, which the plain listing leaves out
+    let listing = format!("{program:#}");
+    let names = |line: &str| line.split(" ; ").nth(1).map(|names| names.split(", ").map(str::to_string).collect());
+    assert!(listing.lines().any(|line| names(line).is_some_and(|names: Vec<String>| names.contains(&"a".into()))));
+    assert!(!program.to_string().contains(';'));
+    assert_eq!(listing.lines().count(), program.to_string().lines().count());
+}
+
+#[test]
+fn stubs_name_buffers_and_rows() {
+    let (network, metadata) = and_network();
+    let program = compile_annotated(&ARCHITECTURE, &network, CompilerSettings::default(), &metadata).unwrap();
+    let c = c_stub(&program, "and", Marshalling::default()).unwrap();
+    let rust = rust_stub(&program, "and", Marshalling::default()).unwrap();
+    assert!(c.contains("#define AND_INPUT_A 0 /* a */") && c.contains("#define AND_INPUT_IN1 1 /* in1 */"));
+    assert!(c.contains("#define AND_OUTPUT_RESULT_0 0 /* result[0] */"));
+    assert!(rust.contains("pub const INPUT_A: usize = 0;") && rust.contains("pub const OUTPUT_RESULT_0: usize = 0;"));
+    let output = program.output_map[0];
+    assert!(c.contains(&format!("/* row {output}: result[0] */")));
+    assert!(rust.contains(&format!("// row {output}: result[0]")));
+}
@@ -0,0 +1,64 @@
+//! Checks compiling networks with deadlines of their outputs and reporting the slack of the outputs.
+use std::collections::HashMap;
+
+use lime_rs::prada::reference::differential_test;
+use lime_rs::prada::timing::{cone_deadlines, OutputTiming};
+use lime_rs::prada::{compile_annotated, NodeMetadata};
+use lime_rs::prelude::*;
+
+/// Popcount of 7 inputs followed by the AND of 4 other inputs, which has to be computed within
+/// `deadline` ns
+fn popcount_and(deadline: u64) -> (MigNetwork, HashMap<Signal, NodeMetadata>) {
+    let mut network = MigNetwork::new();
+    let bits: Vec<Signal> = (0..7).map(|_| network.add_input()).collect();
+    let [a, b, c, d] = [(); 4].map(|_| network.add_input());
+    for bit in network.popcount(&bits) {
+        network.add_output(bit);
+    }
+    let ab = network.and(a, b);
+    let cd = network.and(c, d);
+    let and = network.and(ab, cd);
+    network.add_output(and);
+    (network, HashMap::from([(and, NodeMetadata::named("ready").with_deadline(deadline))]))
+}
+
+#[test]
+fn constrained_cones_are_scheduled_first() {
+    let (network, metadata) = popcount_and(10_000);
+    let settings = CompilerSettings::builder().passthrough(true).build();
+    let program = compile_annotated(&ARCHITECTURE, &network, settings, &metadata).unwrap();
+    assert_eq!(differential_test(&network, &program, 4, 3).expect("program should be executable"), None);
+
+    let timing = program.timing();
+    let (constrained, others) = timing.split_last().unwrap();
+    assert!(others.iter().all(|output| output.deadline.is_none() && output.completion > constrained.completion));
+    assert_eq!(constrained.slack(), Some(10_000 - constrained.completion as i64));
+}
+
+#[test]
+fn slack_is_reported_after_rewriting() {
+    let (network, metadata) = popcount_and(1);
+    let program = compile_annotated(&ARCHITECTURE, &network, CompilerSettings::default(), &metadata).unwrap();
+    assert_eq!(differential_test(&network, &program, 4, 7).expect("program should be executable"), None);
+    let constrained = program.timing().last().copied().unwrap();
+    assert_eq!(constrained.deadline, Some(1));
+    assert!(!constrained.meets_deadline());
+    assert!(OutputTiming { completion: 5, deadline: None }.meets_deadline());
+}
+
+#[test]
+fn operands_inherit_the_earliest_deadline() {
+    let mut network = MigNetwork::new();
+    let [a, b, c] = [(); 3].map(|_| network.add_input());
+    let maj = network.maj(a, b, c);
+    let late = network.and(maj, a);
+    let early = network.or(maj, b);
+    network.add_output(late);
+    network.add_output(early);
+    let deadlines = cone_deadlines(&network, &[Some(100), Some(50)]);
+    assert_eq!(deadlines[&late.node_id()], 100);
+    assert_eq!(deadlines[&early.node_id()], 50);
+    assert_eq!(deadlines[&maj.node_id()], 50);
+    assert_eq!(deadlines[&a.node_id()], 50);
+    assert!(cone_deadlines(&network, &[None, None]).is_empty());
+}
@@ -0,0 +1,65 @@
+//! DRAM command traces of programs and the cross-validation of the cost model against latencies
+//! simulated for them.
+use lime_rs::prada::cost::{CostModel, InstructionClass};
+use lime_rs::prada::trace::{parse_instruction_latencies, ramulator_trace, CrossValidation};
+use lime_rs::prelude::*;
+
+fn program() -> Program<'static> {
+    let [a, b, c] = [RowAddress(1), RowAddress(2), RowAddress(3)];
+    let mut program = Program::new(&ARCHITECTURE, vec!(Instruction::AAPRowCopy(a, b)));
+    program.push_loop(2, [Instruction::AAPTRA(a, b, c), Instruction::N(c)]);
+    program
+}
+
+#[test]
+fn traces_unroll_loops() {
+    let trace = ramulator_trace(&program());
+    let expected = [
+        "0 ACT 1", "0 ACT 2", "0 PRE", "1 TRA 1 2 3", "1 PRE", "2 NOT 3", "2 PRE", "3 TRA 1 2 3", "3 PRE", "4 NOT 3",
+        "4 PRE",
+    ];
+    assert_eq!(trace.lines().collect::<Vec<_>>(), expected);
+}
+
+#[test]
+fn latencies_are_parsed_per_instruction() {
+    let text = "instruction,latency\n0,40\n0,20\n\n1, 7\n2,3\n";
+    assert_eq!(parse_instruction_latencies(text, 3), Ok(vec!(60, 7, 3)));
+    for (text, error) in [
+        ("0,1\n", "missing latency of instruction"),
+        ("0,1\n1,2\n2,3\n3,4\n", "instruction index out of range"),
+        ("0,1\n1\n2,3\n", "expected `<instruction index>,<latency>`"),
+        ("0,1\n1,x\n2,3\n", "invalid latency"),
+        ("0,1\n1a,2\n2,3\n", "invalid instruction index"),
+    ] {
+        assert_eq!(parse_instruction_latencies(text, 3), Err(error), "{text}");
+    }
+}
+
+#[test]
+fn discrepancies_by_class() {
+    let program = program();
+    let model = CostModel::default();
+    let (copy, tra, not) = (
+        model.cost(InstructionClass::RowCopy).runtime,
+        model.cost(InstructionClass::Tra).runtime,
+        model.cost(InstructionClass::Not).runtime,
+    );
+    // TRAs are simulated to take twice as long as estimated, the other classes as estimated
+    let latencies = [copy, 2 * tra, not, 2 * tra, not];
+    let validation = CrossValidation::new(&model, &program, &latencies).unwrap();
+    let classes: Vec<_> = validation.classes.iter().map(|class| (class.class, class.executions)).collect();
+    assert_eq!(classes, [(InstructionClass::RowCopy, 1), (InstructionClass::Tra, 2), (InstructionClass::Not, 2)]);
+    let errors: Vec<f64> = validation.classes.iter().map(|class| class.relative_error()).collect();
+    assert_eq!(errors, [0.0, -0.5, 0.0]);
+    assert_eq!(validation.classes[1].simulated_latency(), 2.0 * tra as f64);
+
+    let markdown = validation.to_markdown();
+    assert_eq!(markdown.lines().count(), 2 + 3);
+    assert!(markdown.contains(&format!("| tra | 2 | {} | {} | -50.0% |", 2 * tra, 4 * tra)));
+
+    assert_eq!(
+        CrossValidation::new(&model, &program, &latencies[1..]).unwrap_err(),
+        "nr of latencies differs from nr of instructions"
+    );
+}
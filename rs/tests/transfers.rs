@@ -0,0 +1,39 @@
+//! Checks the estimates of moving inputs and outputs between host and DRAM.
+use lime_rs::prada::cost::{CpuBaseline, HostTransferModel};
+use lime_rs::prada::program::HostTransfer;
+use lime_rs::prada::stdlib::hamming_distance_network;
+use lime_rs::prelude::*;
+
+#[test]
+fn inputs_are_written_and_outputs_read() {
+    let network = hamming_distance_network(4);
+    let program = compile(&ARCHITECTURE, &network, CompilerSettings::default()).unwrap();
+    let transfers = program.host_transfers();
+    for (row, _) in &program.input_map {
+        assert!(transfers.contains(&HostTransfer::Write(*row)));
+    }
+    for row in &program.output_map {
+        assert!(transfers.contains(&HostTransfer::Read(*row)));
+    }
+}
+
+#[test]
+fn transfers_are_limited_by_bandwidth() {
+    let model = HostTransferModel { row_bytes: 1000, bytes_per_ns: 10.0, energy_per_byte: 0.5, row_latency: 20.0 };
+    let [a, b] = [RowAddress(0), RowAddress(1)];
+    let estimate = model.estimate(&[HostTransfer::Write(a), HostTransfer::Write(b), HostTransfer::Read(a)]);
+    assert_eq!((estimate.rows_written, estimate.rows_read), (2, 1));
+    assert_eq!(estimate.runtime, 3.0 * (20.0 + 100.0));
+    assert_eq!(estimate.energy_consumption, 1500.0);
+}
+
+#[test]
+fn end_to_end_speedup_includes_transfers() {
+    let network = hamming_distance_network(4);
+    let program = compile(&ARCHITECTURE, &network, CompilerSettings::default()).unwrap();
+    let cpu = CpuBaseline::default().estimate(100);
+    let transfers = HostTransferModel::default().estimate(&program.host_transfers());
+    assert!(transfers.runtime > 0.0);
+    assert!(cpu.end_to_end_speedup(&program, &transfers) < cpu.speedup(&program));
+    assert!(cpu.report_line(&program, &transfers).contains("end-to-end speedup"));
+}
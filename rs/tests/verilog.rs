@@ -0,0 +1,66 @@
+//! Checks the Verilog export of networks.
+use std::ffi::CString;
+
+use lime_rs::prada::verilog::to_verilog;
+use lime_rs::prelude::*;
+
+#[test]
+fn networks_are_written_like_mockturtle_does() {
+    let mut network = MigNetwork::new();
+    let [a, b, c] = [(); 3].map(|_| network.add_input());
+    let maj = network.maj(a, b.invert(), c);
+    network.add_output(maj.invert());
+    network.add_output(network.constant(true));
+
+    let expected = "\
+module top( x0 , x1 , x2 , y0 , y1 );
+  input x0 , x1 , x2 ;
+  output y0 , y1 ;
+  wire n4 ;
+  assign n4 = ( x0 & ~x1 ) | ( x0 & x2 ) | ( ~x1 & x2 ) ;
+  assign y0 = ~n4 ;
+  assign y1 = 1'b1 ;
+endmodule
+";
+    assert_eq!(to_verilog(&network, 0), expected);
+    // unused inputs are declared nevertheless
+    assert!(to_verilog(&network, 4).contains("  input x0 , x1 , x2 , x3 ;\n"));
+}
+
+#[test]
+fn extracted_networks_keep_the_inputs_of_the_original() {
+    // rewriting reduces MAJ(a, a, b) to a, so b doesn't influence the output anymore
+    let mut network = MigNetwork::new();
+    let [a, b] = [(); 2].map(|_| network.add_input());
+    let maj = network.maj(a, a, b);
+    network.add_output(maj);
+
+    let path = std::env::temp_dir().join(format!("prada-extracted-{}.v", std::process::id()));
+    let c_path = CString::new(path.to_str().unwrap()).unwrap();
+    let settings = CompilerSettings { verilog_path: c_path.as_ptr(), ..CompilerSettings::default() };
+    compile(&ARCHITECTURE, &network, settings).unwrap();
+    let verilog = std::fs::read_to_string(&path).expect("extracted network should have been written");
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(verilog.starts_with("module top( x0 , x1 , y0 );\n"));
+    assert!(verilog.contains("  assign y0 = x0 ;\n"));
+    assert!(verilog.ends_with("endmodule\n"));
+}
+
+#[test]
+fn passthrough_networks_are_written_as_received() {
+    let mut network = MigNetwork::new();
+    let [a, b, c] = [(); 3].map(|_| network.add_input());
+    let maj = network.maj(a, b, c.invert());
+    network.add_output(maj);
+
+    let path = std::env::temp_dir().join(format!("prada-passthrough-{}.v", std::process::id()));
+    let c_path = CString::new(path.to_str().unwrap()).unwrap();
+    let settings = CompilerSettings { verilog_path: c_path.as_ptr(), passthrough: true, ..CompilerSettings::default() };
+    compile(&ARCHITECTURE, &network, settings).unwrap();
+    let verilog = std::fs::read_to_string(&path).expect("network should have been written");
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(verilog, to_verilog(&network, 3));
+    assert!(verilog.starts_with("module top( x0 , x1 , x2 , y0 );\n"));
+}
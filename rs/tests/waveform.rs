@@ -0,0 +1,42 @@
+//! Checks exporting the waveforms of simulated programs as VCD.
+use std::collections::HashMap;
+
+use lime_rs::prada::waveform::{vcd, VcdRecorder};
+use lime_rs::prada::{compile_annotated, NodeMetadata};
+use lime_rs::prelude::*;
+
+#[test]
+fn named_rows_become_signals() {
+    let mut network = MigNetwork::new();
+    let a = network.add_input();
+    let b = network.add_input();
+    let and = network.and(a, b);
+    network.add_output(and);
+    let metadata = HashMap::from([(a, NodeMetadata::named("a")), (and, NodeMetadata::bit("result", 0))]);
+    let program = compile_annotated(&ARCHITECTURE, &network, CompilerSettings::default(), &metadata).unwrap();
+
+    let waveform = vcd(&program, &[0b1100, 0b1010]).unwrap();
+    let declarations: Vec<&str> = waveform.lines().filter(|line| line.starts_with("$var")).collect();
+    assert_eq!(declarations.len(), program.symbols().rows().count());
+    assert!(declarations.iter().any(|line| line.ends_with(" a $end")));
+    let symbols = program.symbols();
+    let result = symbols.row(program.output_map[0]).unwrap().replace('[', "_").replace(']', "");
+    let declaration = declarations.iter().find(|line| line.ends_with(&format!(" {result} $end"))).unwrap();
+    let code = declaration.split_whitespace().nth(3).unwrap();
+
+    // the output changes to `a & b` at the latest with the last instruction
+    let final_value = waveform.lines().filter(|line| line.ends_with(&format!(" {code}"))).last().unwrap();
+    assert_eq!(final_value, format!("b1000 {code}"));
+    let end = waveform.lines().last().unwrap();
+    assert_eq!(end, format!("#{}", program.unrolled_instructions().len()));
+    assert!(waveform.contains("$enddefinitions $end\n#0\n$dumpvars\n"));
+}
+
+#[test]
+fn unwritten_rows_are_undefined() {
+    let simulator = Simulator::new();
+    let recorder = VcdRecorder::new(&simulator, [(RowAddress(0), "x"), (RowAddress(1), "x"), (RowAddress(0), "y")]);
+    let waveform = recorder.finish();
+    assert!(waveform.contains("$var wire 64 ! x $end\n$var wire 64 \" x@1 $end\n$upscope"));
+    assert!(waveform.ends_with("#0\n$dumpvars\nbx !\nbx \"\n$end\n"));
+}
@@ -0,0 +1,94 @@
+//! Checks bit-blasting word-level kernels into MIGs.
+use lime_rs::prada::simulation::evaluate_network;
+use lime_rs::prada::words::WordNetwork;
+use lime_rs::prelude::*;
+
+const WIDTH: usize = 4;
+
+/// Packs the values of the words (one per bitline) into the values of the inputs of the network,
+/// least significant bit first
+fn pack(words: &[Vec<u64>]) -> Vec<u64> {
+    words
+        .iter()
+        .flat_map(|word| {
+            (0..WIDTH).map(move |bit| {
+                word.iter().enumerate().fold(0, |packed, (bitline, value)| packed | ((value >> bit) & 1) << bitline)
+            })
+        })
+        .collect()
+}
+
+/// Values (one per bitline) of the consecutive output words of `WIDTH` bits (resp. 1 bit for
+/// comparisons) described by `widths`
+fn unpack(outputs: &[u64], widths: &[usize], bitlines: usize) -> Vec<Vec<u64>> {
+    let mut offset = 0;
+    widths
+        .iter()
+        .map(|width| {
+            let bit = |bitline: usize, bit: usize| ((outputs[offset + bit] >> bitline) & 1) << bit;
+            let word =
+                (0..bitlines).map(|bitline| (0..*width).fold(0, |value, idx| value | bit(bitline, idx))).collect();
+            offset += width;
+            word
+        })
+        .collect()
+}
+
+fn kernel() -> WordNetwork {
+    let mut words = WordNetwork::new();
+    let a = words.input("a", WIDTH);
+    let b = words.input("b", WIDTH);
+    let sum = words.add(a, b).unwrap();
+    let difference = words.sub(a, b).unwrap();
+    let product = words.mul(a, b).unwrap();
+    let less = words.lt(a, b).unwrap();
+    let equal = words.eq(a, b).unwrap();
+    let max = words.mux(less, b, a).unwrap();
+    for (name, word) in [("sum", sum), ("difference", difference), ("product", product), ("max", max)] {
+        words.output(name, word);
+    }
+    words.output("less", less);
+    words.output("equal", equal);
+    words
+}
+
+#[test]
+fn bit_blasted_operations_match_word_semantics() {
+    let words = kernel();
+    let mask = (1 << WIDTH) - 1;
+    let widths = [WIDTH, WIDTH, WIDTH, WIDTH, 1, 1];
+    // all 256 pairs of operands in 4 rounds of 64 bitlines
+    for round in 0..4u64 {
+        let a: Vec<u64> = (0..64).map(|bitline| (round * 64 + bitline) & mask).collect();
+        let b: Vec<u64> = (0..64).map(|bitline| (round * 64 + bitline) >> WIDTH).collect();
+        let outputs = evaluate_network(words.network(), &pack(&[a.clone(), b.clone()])).unwrap();
+        let results = unpack(&outputs, &widths, 64);
+        for bitline in 0..64 {
+            let (a, b) = (a[bitline], b[bitline]);
+            assert_eq!(results[0][bitline], (a + b) & mask);
+            assert_eq!(results[1][bitline], a.wrapping_sub(b) & mask);
+            assert_eq!(results[2][bitline], (a * b) & mask);
+            assert_eq!(results[3][bitline], a.max(b));
+            assert_eq!(results[4][bitline], (a < b) as u64);
+            assert_eq!(results[5][bitline], (a == b) as u64);
+        }
+    }
+}
+
+#[test]
+fn compiled_kernels_are_named_after_their_words() {
+    let words = kernel();
+    let program = words.compile(&ARCHITECTURE, CompilerSettings::default()).unwrap();
+    let inputs = pack(&[(0..64).map(|value| value % 16).collect(), (0..64).map(|value| value / 4).collect()]);
+    assert_eq!(simulate(&program, &inputs).unwrap(), evaluate_network(words.network(), &inputs).unwrap());
+    let symbols = program.symbols();
+    assert_eq!(symbols.input(WIDTH as u64), Some("b[0]"));
+    assert_eq!(symbols.output(0), Some("sum[0]"));
+    assert_eq!(symbols.output(4 * WIDTH + 1), Some("equal[0]"));
+
+    let mut mismatched = WordNetwork::new();
+    let (a, bit) = (mismatched.input("a", WIDTH), mismatched.input("bit", 1));
+    assert!(mismatched.add(a, bit).is_err());
+    assert!(mismatched.mux(a, a, a).is_err());
+    assert!(mismatched.mux(bit, a, a).is_ok());
+}
@@ -0,0 +1,69 @@
+//! Lowering of the three-MAJ XOR/XNOR constructions onto in-DRAM XOR.
+use std::collections::HashMap;
+
+use lime_rs::prada::architecture::PRADAArchitecture;
+use lime_rs::prada::compile_with_placement;
+use lime_rs::prada::simulation::evaluate_network;
+use lime_rs::prelude::*;
+
+const INPUTS: [u64; 3] = [0xf0f0_f0f0_f0f0_f0f0, 0xcccc_cccc_cccc_cccc, 0xaaaa_aaaa_aaaa_aaaa];
+
+fn xor_architecture() -> PRADAArchitecture {
+    PRADAArchitecture { capabilities: Capabilities::DEFAULT | Capabilities::XOR, ..ARCHITECTURE.clone() }
+}
+
+fn xnor(network: &mut MigNetwork, a: Signal, b: Signal) -> Signal {
+    let or = network.or(a, b);
+    let and = network.and(a, b);
+    network.or(or.invert(), and)
+}
+
+/// Compiles `network` as is (without rewriting it) and checks the program against the network,
+/// returning the nr of XOR instructions
+fn compile_xors(architecture: &PRADAArchitecture, network: &MigNetwork) -> usize {
+    let program = compile_with_placement(architecture, &network.with_backward_edges(), HashMap::new())
+        .expect("network should be compilable");
+    assert_eq!(simulate(&program, &INPUTS).unwrap(), evaluate_network(network, &INPUTS).unwrap());
+    program.instructions.iter().filter(|instruction| matches!(instruction, Instruction::Xor(..))).count()
+}
+
+#[test]
+fn xors_and_xnors_are_lowered() {
+    let mut network = MigNetwork::new();
+    let [a, b, c] = [(); 3].map(|_| network.add_input());
+    let xor = network.xor(a, b);
+    let xnor = xnor(&mut network, b, c.invert());
+    let maj = network.maj(xor, xnor, c);
+    network.add_output(xor);
+    network.add_output(xnor);
+    network.add_output(maj);
+
+    assert_eq!(compile_xors(&xor_architecture(), &network), 2);
+    assert_eq!(compile_xors(&ARCHITECTURE, &network), 0);
+}
+
+#[test]
+fn shared_inner_nodes_are_computed_explicitly() {
+    let mut network = MigNetwork::new();
+    let [a, b, c] = [(); 3].map(|_| network.add_input());
+    let or = network.or(a, b);
+    let and = network.and(a, b);
+    let xor = network.and(or, and.invert());
+    network.add_output(xor);
+    // the AND is needed by another node as well, hence the XOR can't absorb it
+    let carry = network.or(and, c);
+    network.add_output(carry);
+    assert_eq!(compile_xors(&xor_architecture(), &network), 0);
+
+    let mut network = MigNetwork::new();
+    let [a, b] = [(); 2].map(|_| network.add_input());
+    let xor = network.xor(a, b);
+    network.add_output(xor);
+    // same for outputs
+    let or = network.or(a, b);
+    let and = network.and(a, b);
+    let other = network.and(or, and.invert());
+    network.add_output(other);
+    network.add_output(and);
+    assert_eq!(compile_xors(&xor_architecture(), &network), 1);
+}
@@ -4,22 +4,139 @@
 #include <mockturtle/networks/mig.hpp>
 
 #include <chrono>
+#include <cstring>
+#include <filesystem>
 #include <iostream>
+#include <optional>
+#include <string>
+#include <thread>
 
 using namespace mockturtle;
 using namespace eggmock;
 using namespace std::chrono;
 
-// usage: exec [network]
+namespace
+{
+
+void print_usage( char const* exec )
+{
+  std::cerr << "usage: " << exec << " [--verify-rewrite] <network>" << std::endl;
+  std::cerr << "       " << exec << " watch [--verify-rewrite] <netlist>" << std::endl;
+}
+
+// prints the statistics which differ from the previous run (all of them on the first run),
+// returns whether any of them differ
+bool print_statistics_diff( std::optional<prada_compiler_statistics> const& previous,
+                            prada_compiler_statistics const& current )
+{
+  bool changed = !previous;
+  auto const print = [&]( char const* name, uint64_t prada_compiler_statistics::* field ) {
+    uint64_t const value = current.*field;
+    if ( !previous )
+    {
+      std::cout << "  " << name << ": " << value << std::endl;
+      return;
+    }
+    uint64_t const before = ( *previous ).*field;
+    if ( before == value )
+    {
+      return;
+    }
+    changed = true;
+    auto const delta = static_cast<int64_t>( value - before );
+    std::cout << "  " << name << ": " << before << " -> " << value << " (" << ( delta > 0 ? "+" : "" ) << delta;
+    if ( before != 0 )
+    {
+      auto const percent = 100.0 * static_cast<double>( delta ) / static_cast<double>( before );
+      std::cout << ", " << ( delta > 0 ? "+" : "" ) << percent << "%";
+    }
+    std::cout << ")" << std::endl;
+  };
+  print( "instructions", &prada_compiler_statistics::instruction_count );
+  print( "runtime estimate", &prada_compiler_statistics::runtime_estimate );
+  print( "energy estimate", &prada_compiler_statistics::energy_consumption_estimate );
+  print( "TRAs", &prada_compiler_statistics::tra_count );
+  print( "MAJs", &prada_compiler_statistics::maj_count );
+  print( "row copies", &prada_compiler_statistics::copy_count );
+  print( "row activations", &prada_compiler_statistics::row_activations );
+  print( "spills", &prada_compiler_statistics::spills );
+  print( "inverters", &prada_compiler_statistics::inverters_after );
+  print( "e-graph size", &prada_compiler_statistics::egraph_size );
+  return changed;
+}
+
+// recompiles the netlist at `path` whenever it is modified, until interrupted
+int watch( std::string const& path, bool const verify_rewrite )
+{
+  auto const settings = prada_compiler_settings{
+      .print_program = false,
+      .verbose = false,
+      .verify_rewrite = verify_rewrite,
+  };
+  std::optional<prada_compiler_statistics> previous;
+  std::optional<std::filesystem::file_time_type> last_write;
+  std::cout << "watching " << path << std::endl;
+  while ( true )
+  {
+    std::error_code error;
+    auto const write_time = std::filesystem::last_write_time( path, error );
+    if ( error || write_time == last_write )
+    {
+      std::this_thread::sleep_for( milliseconds( 500 ) );
+      continue;
+    }
+    last_write = write_time;
+
+    // the file may still be written, a failed read is retried on its next modification
+    std::optional<mig_network> mig = read_ntk<mig_network>( path );
+    if ( !mig )
+    {
+      continue;
+    }
+    auto const begin = system_clock::now();
+    auto const stats = prada_compile( settings, *mig );
+    auto const t_total = duration_cast<milliseconds>( system_clock::now() - begin ).count();
+    if ( stats.error != PRADA_ERROR_NONE )
+    {
+      // keep the statistics of the last successful run for the next diff
+      std::cout << "could not compile " << path << " (error " << stats.error << ")" << std::endl;
+      continue;
+    }
+
+    std::cout << "compiled " << path << " in " << t_total << "ms:" << std::endl;
+    if ( !print_statistics_diff( previous, stats ) )
+    {
+      std::cout << "  (no changes)" << std::endl;
+    }
+    previous = stats;
+  }
+}
+
+} // namespace
+
+// usage: exec [--verify-rewrite] [network]
+//        exec watch [--verify-rewrite] [netlist]
 int main( int const argc, char** argv )
 {
-  if ( argc != 2 )
+  if ( argc >= 2 && std::strcmp( argv[1], "watch" ) == 0 )
   {
-    std::cerr << "usage: " << argv[0] << std::endl;
+    bool const verify_rewrite = argc == 4 && std::strcmp( argv[2], "--verify-rewrite" ) == 0;
+    if ( argc != 3 && !verify_rewrite )
+    {
+      print_usage( argv[0] );
+      return 1;
+    }
+    return watch( argv[argc - 1], verify_rewrite );
+  }
+
+  bool const verify_rewrite = argc == 3 && std::strcmp( argv[1], "--verify-rewrite" ) == 0;
+  if ( argc != 2 && !verify_rewrite )
+  {
+    print_usage( argv[0] );
     return 1;
   }
 
-  std::optional<mig_network> mig = get_ntk<mig_network>( argv[1] );
+  std::optional<mig_network> mig = get_ntk<mig_network>( argv[argc - 1] );
   if ( !mig )
   {
     return 1;
@@ -31,18 +148,22 @@ int main( int const argc, char** argv )
   preoptimize_mig( *mig );
   auto const t_opt = duration_cast<milliseconds>( system_clock::now() - opt_begin ).count();
 
-  auto constexpr settings = prada_compiler_settings{
+  auto const settings = prada_compiler_settings{
       .print_program = false,
       .verbose = false,
+      .verify_rewrite = verify_rewrite,
   };
 
-  const auto [egraph_classes, egraph_nodes, egraph_size,
-              instruction_count,
-              t_runner, t_extractor, t_compiler] = prada_compile( settings, *mig );
+  auto const stats = prada_compile( settings, *mig );
+  if ( stats.error != PRADA_ERROR_NONE )
+  {
+    return 1;
+  }
 
-  std::cout << t_opt << "\t" << t_runner << "\t" << t_extractor << "\t" << t_compiler << "\t"
+  std::cout << t_opt << "\t" << stats.t_runner << "\t" << stats.t_extractor << "\t" << stats.t_compiler << "\t"
             << pre_opt_size << "\t" << mig->size() << "\t" << mig->num_cis() << "\t" << mig->num_cos() << "\t"
-            << instruction_count << "\t"
-            << egraph_classes << "\t" << egraph_nodes << "\t" << egraph_size;
+            << stats.instruction_count << "\t"
+            << stats.egraph_classes << "\t" << stats.egraph_nodes << "\t" << stats.egraph_size << "\t"
+            << stats.inverters_before << "\t" << stats.inverters_after;
   return 0;
 }
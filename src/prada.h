@@ -5,11 +5,58 @@
 
 #include <mockturtle/networks/mig.hpp>
 
+#include <cstddef>
 #include <cstdint>
+#include <tuple>
 #include <utility>
+#include <vector>
 
 extern "C"
 {
+  enum prada_runner_scheduler
+  {
+    PRADA_SCHEDULER_BACKOFF,
+    PRADA_SCHEDULER_SIMPLE,
+  };
+
+  enum prada_rewrite_backend
+  {
+    PRADA_BACKEND_EGG,
+    // requires building lime-rs with the `egglog` feature
+    PRADA_BACKEND_EGGLOG,
+  };
+
+  enum prada_scheduling_policy
+  {
+    PRADA_SCHEDULING_GREEDY,
+    PRADA_SCHEDULING_CRITICAL_PATH,
+    PRADA_SCHEDULING_SETHI_ULLMAN,
+  };
+
+  // diagnostics escalated into errors, combined by bitwise or
+  enum prada_strictness : uint32_t
+  {
+    PRADA_STRICTNESS_PERMISSIVE = 0,
+    PRADA_STRICTNESS_CONSTANT_OUTPUT = 1 << 0,
+    PRADA_STRICTNESS_ROW_BUDGET = 1 << 1,
+    PRADA_STRICTNESS_RETENTION_RISK = 1 << 2,
+    PRADA_STRICTNESS_CAPABILITY_EMULATION = 1 << 3,
+    PRADA_STRICTNESS_UNVERIFIED_TRA_GROUPING = 1 << 4,
+    PRADA_STRICTNESS_ENERGY_BUDGET = 1 << 5,
+    PRADA_STRICTNESS_PRODUCTION = PRADA_STRICTNESS_RETENTION_RISK | PRADA_STRICTNESS_CAPABILITY_EMULATION |
+                                  PRADA_STRICTNESS_UNVERIFIED_TRA_GROUPING,
+  };
+
+  // reason for which a network couldn't be compiled, the rewriting entry points send an empty
+  // network to their receiver in that case
+  enum prada_error : uint32_t
+  {
+    PRADA_ERROR_NONE = 0,
+    PRADA_ERROR_UNSUPPORTED_OPERATION = 1,
+    PRADA_ERROR_ESCALATED = 2,
+    PRADA_ERROR_OTHER = 3,
+  };
+
   struct prada_compiler_statistics
   {
     uint64_t egraph_classes;
@@ -20,9 +67,26 @@ extern "C"
     uint64_t runtime_estimate;
     uint64_t energy_consumption_estimate;
 
+    uint64_t inverters_before;
+    uint64_t inverters_after;
+    uint64_t emulated_inversions;
+    uint64_t emulation_majs;
+
+    uint64_t spills;
+    uint64_t reloads;
+    uint64_t rematerializations;
+
+    uint64_t tra_count;
+    uint64_t maj_count;
+    uint64_t copy_count;
+    uint64_t row_activations;
+
     uint64_t t_runner;
     uint64_t t_extractor;
     uint64_t t_compiler;
+
+    // a `prada_error`, all other statistics are 0 if the network couldn't be compiled
+    uint32_t error;
   };
 
   struct prada_compiler_settings
@@ -31,16 +95,100 @@ extern "C"
     bool verbose;
     bool preoptimize = true;
     bool rewrite = true;
+    bool explanations = false;
+    prada_runner_scheduler scheduler = PRADA_SCHEDULER_BACKOFF;
+    uint64_t backoff_match_limit = 1000;
+    uint64_t backoff_ban_length = 5;
+    bool sharing_guided_distributivity = false;
+    char const* telemetry_path = nullptr;
+    bool pin_inputs = false;
+    bool dual_rail = false;
+    bool pack_outputs = false;
+    uint64_t output_base = 0;
+    bool spill = false;
+    uint64_t spill_subarray = 1;
+    bool rematerialize = false;
+    prada_scheduling_policy scheduling = PRADA_SCHEDULING_GREEDY;
+    bool cpu_baseline = false;
+    char const* decision_log_path = nullptr;
+    uint64_t scratch_row_budget = 0;
+    char const* allocator_metrics_path = nullptr;
+    uint64_t output_subarray = 0;
+    bool passthrough = false;
+    char const* verilog_path = nullptr;
+    bool verify_rewrite = false;
+    char const* diagnostics_path = nullptr;
+    uint32_t strictness = PRADA_STRICTNESS_PERMISSIVE;
+    uint64_t energy_budget = 0;
+    uint64_t seed = 0;
+    prada_rewrite_backend backend = PRADA_BACKEND_EGG;
   };
 
+  // new fields are only ever appended, so that the `*_sized_ffi` functions can fill in the
+  // defaults of fields unknown to callers compiled against an older version of this header
   struct prada_compiler_settings_ffi
   {
     bool print_program;
     bool verbose;
     bool rewrite = true;
+    bool explanations = false;
+    prada_runner_scheduler scheduler = PRADA_SCHEDULER_BACKOFF;
+    uint64_t backoff_match_limit = 1000;
+    uint64_t backoff_ban_length = 5;
+    bool sharing_guided_distributivity = false;
+    char const* telemetry_path = nullptr;
+    bool pin_inputs = false;
+    bool dual_rail = false;
+    bool pack_outputs = false;
+    uint64_t output_base = 0;
+    bool spill = false;
+    uint64_t spill_subarray = 1;
+    bool rematerialize = false;
+    prada_scheduling_policy scheduling = PRADA_SCHEDULING_GREEDY;
+    bool cpu_baseline = false;
+    char const* decision_log_path = nullptr;
+    uint64_t scratch_row_budget = 0;
+    char const* allocator_metrics_path = nullptr;
+    uint64_t output_subarray = 0;
+    bool passthrough = false;
+    char const* verilog_path = nullptr;
+    bool verify_rewrite = false;
+    char const* diagnostics_path = nullptr;
+    uint32_t strictness = PRADA_STRICTNESS_PERMISSIVE;
+    uint64_t energy_budget = 0;
+    uint64_t seed = 0;
+    prada_rewrite_backend backend = PRADA_BACKEND_EGG;
 
     prada_compiler_settings_ffi( prada_compiler_settings s )
-        : print_program( s.print_program ), verbose( s.verbose ), rewrite( s.rewrite ) {}
+        : print_program( s.print_program ), verbose( s.verbose ), rewrite( s.rewrite ),
+          explanations( s.explanations ), scheduler( s.scheduler ),
+          backoff_match_limit( s.backoff_match_limit ), backoff_ban_length( s.backoff_ban_length ),
+          sharing_guided_distributivity( s.sharing_guided_distributivity ),
+          telemetry_path( s.telemetry_path ), pin_inputs( s.pin_inputs ),
+          dual_rail( s.dual_rail ), pack_outputs( s.pack_outputs ), output_base( s.output_base ),
+          spill( s.spill ), spill_subarray( s.spill_subarray ), rematerialize( s.rematerialize ),
+          scheduling( s.scheduling ), cpu_baseline( s.cpu_baseline ),
+          decision_log_path( s.decision_log_path ), scratch_row_budget( s.scratch_row_budget ),
+          allocator_metrics_path( s.allocator_metrics_path ), output_subarray( s.output_subarray ),
+          passthrough( s.passthrough ), verilog_path( s.verilog_path ),
+          verify_rewrite( s.verify_rewrite ), diagnostics_path( s.diagnostics_path ),
+          strictness( s.strictness ), energy_budget( s.energy_budget ), seed( s.seed ),
+          backend( s.backend ) {}
+  };
+
+  struct prada_node_annotation
+  {
+    uint64_t id;
+    uint64_t depth;
+    uint64_t runtime_estimate;
+    uint64_t energy_consumption_estimate;
+  };
+
+  struct prada_annotation_receiver
+  {
+    void* data;
+    void ( *annotate_node )( void* data, prada_node_annotation annotation );
+    void ( *annotate_output )( void* data, uint64_t output, uint64_t node_id );
   };
 
   eggmock::mig_receiver<prada_compiler_statistics> prada_compile_ffi(
@@ -48,8 +196,32 @@ extern "C"
   eggmock::mig_receiver<prada_compiler_statistics> prada_rewrite_ffi(
       prada_compiler_settings_ffi settings,
       eggmock::mig_receiver<void> receiver );
+  eggmock::mig_receiver<prada_compiler_statistics> prada_rewrite_annotated_ffi(
+      prada_compiler_settings_ffi settings,
+      eggmock::mig_receiver<void> receiver,
+      prada_annotation_receiver annotations );
+
+  eggmock::mig_receiver<prada_compiler_statistics> prada_compile_sized_ffi(
+      prada_compiler_settings_ffi const* settings,
+      size_t size );
+  eggmock::mig_receiver<prada_compiler_statistics> prada_rewrite_sized_ffi(
+      prada_compiler_settings_ffi const* settings,
+      size_t size,
+      eggmock::mig_receiver<void> receiver );
+  eggmock::mig_receiver<prada_compiler_statistics> prada_rewrite_annotated_sized_ffi(
+      prada_compiler_settings_ffi const* settings,
+      size_t size,
+      eggmock::mig_receiver<void> receiver,
+      prada_annotation_receiver annotations );
 }
 
+struct prada_annotations
+{
+  std::vector<prada_node_annotation> nodes;
+  /// id of the annotated node each output points to
+  std::vector<uint64_t> output_nodes;
+};
+
 inline std::pair<mockturtle::mig_network, prada_compiler_statistics> prada_rewrite(
     prada_compiler_settings settings,
     mockturtle::mig_network& ntk )
@@ -59,8 +231,9 @@ inline std::pair<mockturtle::mig_network, prada_compiler_statistics> prada_rewri
     preoptimize_mig( ntk );
   }
   mockturtle::mig_network out;
+  const prada_compiler_settings_ffi ffi_settings( settings );
   const auto stat = eggmock::send_mig(
-      ntk, prada_rewrite_ffi( settings, eggmock::receive_mig( out ) ) );
+      ntk, prada_rewrite_sized_ffi( &ffi_settings, sizeof( ffi_settings ), eggmock::receive_mig( out ) ) );
   return { out, stat };
 }
 
@@ -72,7 +245,40 @@ inline prada_compiler_statistics prada_compile(
   {
     preoptimize_mig( ntk );
   }
-  mockturtle::mig_network out;
-  const auto stat = eggmock::send_mig( ntk, prada_compile_ffi( settings ) );
+  const prada_compiler_settings_ffi ffi_settings( settings );
+  const auto stat = eggmock::send_mig( ntk, prada_compile_sized_ffi( &ffi_settings, sizeof( ffi_settings ) ) );
   return stat;
 }
+
+inline std::tuple<mockturtle::mig_network, prada_compiler_statistics, prada_annotations>
+prada_rewrite_annotated(
+    prada_compiler_settings settings,
+    mockturtle::mig_network& ntk )
+{
+  if ( settings.preoptimize )
+  {
+    preoptimize_mig( ntk );
+  }
+  mockturtle::mig_network out;
+  prada_annotations annotations;
+  const auto receiver = prada_annotation_receiver{
+      .data = &annotations,
+      .annotate_node = +[]( void* data, prada_node_annotation annotation ) {
+        static_cast<prada_annotations*>( data )->nodes.push_back( annotation );
+      },
+      .annotate_output = +[]( void* data, uint64_t output, uint64_t node_id ) {
+        auto& output_nodes = static_cast<prada_annotations*>( data )->output_nodes;
+        if ( output_nodes.size() <= output )
+        {
+          output_nodes.resize( output + 1 );
+        }
+        output_nodes[output] = node_id;
+      },
+  };
+  const prada_compiler_settings_ffi ffi_settings( settings );
+  const auto stat = eggmock::send_mig(
+      ntk,
+      prada_rewrite_annotated_sized_ffi(
+          &ffi_settings, sizeof( ffi_settings ), eggmock::receive_mig( out ), receiver ) );
+  return { out, stat, annotations };
+}